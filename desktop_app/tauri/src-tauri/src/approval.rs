@@ -0,0 +1,118 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use dg_controller::approval::APPROVAL_TIMEOUT;
+use dg_controller::{ApprovalChannel, DeviceApproval};
+use dg_core::pairing::SealedMessage;
+use dg_core::{PairedDevice, PairingOffer, PairingResponse};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::network::HttpClientFactory;
+
+const MAX_TIMEOUT_SECS: u64 = 600;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApprovalSettings {
+    pub relay_url: String,
+    pub timeout_secs: u64,
+}
+
+impl Default for ApprovalSettings {
+    fn default() -> Self {
+        Self {
+            relay_url: String::new(),
+            timeout_secs: APPROVAL_TIMEOUT.as_secs(),
+        }
+    }
+}
+
+impl ApprovalSettings {
+    pub fn validate(&self) -> Result<()> {
+        let url = reqwest::Url::parse(&self.relay_url)
+            .map_err(|err| anyhow!("invalid approval relay URL '{}': {err}", self.relay_url))?;
+        if !matches!(url.scheme(), "https" | "http") {
+            return Err(anyhow!("the approval relay must be reached over HTTP(S)"));
+        }
+        if !(1..=MAX_TIMEOUT_SECS).contains(&self.timeout_secs) {
+            return Err(anyhow!(
+                "the approval timeout must be between 1 and {MAX_TIMEOUT_SECS} seconds"
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PairingCode {
+    pub offer: PairingOffer,
+    // Rendered as a QR code for the phone to scan.
+    pub uri: String,
+}
+
+impl From<PairingOffer> for PairingCode {
+    fn from(offer: PairingOffer) -> Self {
+        Self {
+            uri: offer.to_uri(),
+            offer,
+        }
+    }
+}
+
+// A relay the phone app also talks to. It only stores sealed messages and signed pairing
+// responses, so it never sees what is being unlocked.
+pub struct HttpApprovalChannel {
+    base: String,
+    network: HttpClientFactory,
+}
+
+impl HttpApprovalChannel {
+    pub fn approval(settings: ApprovalSettings, network: HttpClientFactory) -> DeviceApproval {
+        DeviceApproval {
+            channel: Arc::new(Self {
+                base: settings.relay_url.trim_end_matches('/').to_owned(),
+                network,
+            }),
+            timeout: Duration::from_secs(settings.timeout_secs),
+        }
+    }
+
+    // 404 means nothing has been posted yet.
+    async fn fetch<T: serde::de::DeserializeOwned>(&self, url: String) -> Result<Option<T>> {
+        let response = self.network.client()?.get(url).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+}
+
+#[async_trait]
+impl ApprovalChannel for HttpApprovalChannel {
+    async fn publish(&self, device: &PairedDevice, message: SealedMessage) -> Result<()> {
+        self.network
+            .client()?
+            .post(format!("{}/devices/{}/challenges", self.base, device.id))
+            .json(&message)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn poll(&self, device: &PairedDevice, request_id: &str) -> Result<Option<SealedMessage>> {
+        self.fetch(format!(
+            "{}/devices/{}/answers/{request_id}",
+            self.base, device.id
+        ))
+        .await
+    }
+
+    async fn pairing_response(&self, offer: &PairingOffer) -> Result<Option<PairingResponse>> {
+        self.fetch(format!("{}/pairings/{}", self.base, offer.pairing_id()))
+            .await
+    }
+}
@@ -0,0 +1,365 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use dg_controller::{Controller, EncryptOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::task::JoinSet;
+
+use crate::bridge::Endpoint;
+
+pub const TOKEN_FILE: &str = "automation.token";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AutomationScope {
+    Status,
+    Encrypt,
+    Lock,
+}
+
+impl AutomationScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AutomationScope::Status => "status",
+            AutomationScope::Encrypt => "encrypt",
+            AutomationScope::Lock => "lock",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutomationSettings {
+    pub enabled: bool,
+    pub scopes: Vec<AutomationScope>,
+}
+
+impl AutomationSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.enabled && self.scopes.is_empty() {
+            return Err(anyhow!(
+                "automation needs at least one scope: status, encrypt or lock"
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn allows(&self, scope: AutomationScope) -> bool {
+        self.enabled && self.scopes.contains(&scope)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AutomationStatus {
+    pub settings: AutomationSettings,
+    pub endpoint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_file: Option<PathBuf>,
+}
+
+impl AutomationStatus {
+    pub fn new(runtime_dir: &Path, settings: AutomationSettings) -> Self {
+        let token_file = settings.enabled.then(|| token_path(runtime_dir));
+        Self {
+            settings,
+            endpoint: default_endpoint(runtime_dir).display(),
+            token_file,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutomationRequest {
+    #[serde(default)]
+    pub id: Value,
+    pub token: String,
+    pub command: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutomationResponse {
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct EncryptParams {
+    path: PathBuf,
+    recipients: Vec<String>,
+    labels: Vec<String>,
+    out_dir: Option<PathBuf>,
+}
+
+// Kept apart from the core socket so automation tools never see the engine's RPC surface.
+pub fn default_endpoint(runtime_dir: &Path) -> Endpoint {
+    #[cfg(target_os = "windows")]
+    {
+        let _ = runtime_dir;
+        Endpoint::NamedPipe(r"\\.\pipe\data_guardian_automation".to_string())
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Endpoint::Unix(runtime_dir.join("ipc").join("automation.sock"))
+    }
+}
+
+pub fn token_path(runtime_dir: &Path) -> PathBuf {
+    runtime_dir.join("ipc").join(TOKEN_FILE)
+}
+
+pub async fn load_or_create_token(runtime_dir: &Path) -> Result<String> {
+    let path = token_path(runtime_dir);
+    match tokio::fs::read_to_string(&path).await {
+        Ok(token) if !token.trim().is_empty() => return Ok(token.trim().to_owned()),
+        Ok(_) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("failed to read automation token {}", path.display()))
+        }
+    }
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let token = format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    );
+    // The token is written to an owner-only staging file and renamed into place, so it is never
+    // readable by others, even briefly.
+    let staging = path.with_extension("token.tmp");
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(target_family = "unix")]
+    options.mode(0o600);
+    let mut file = options
+        .open(&staging)
+        .await
+        .with_context(|| format!("failed to write automation token {}", path.display()))?;
+    file.write_all(token.as_bytes()).await?;
+    file.sync_all().await?;
+    drop(file);
+    tokio::fs::rename(&staging, &path)
+        .await
+        .with_context(|| format!("failed to write automation token {}", path.display()))?;
+    Ok(token)
+}
+
+// Disabling automation removes the token, so re-enabling it hands tools a fresh one.
+pub async fn revoke_token(runtime_dir: &Path) -> Result<()> {
+    let path = token_path(runtime_dir);
+    match tokio::fs::remove_file(&path).await {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err)
+            .with_context(|| format!("failed to remove automation token {}", path.display())),
+    }
+}
+
+#[derive(Clone)]
+pub struct AutomationServer {
+    controller: Controller,
+    settings: AutomationSettings,
+    token: [u8; 32],
+}
+
+impl AutomationServer {
+    pub fn new(controller: Controller, settings: AutomationSettings, token: &str) -> Self {
+        Self {
+            controller,
+            settings,
+            token: Sha256::digest(token.as_bytes()).into(),
+        }
+    }
+
+    pub async fn handle(&self, request: AutomationRequest) -> AutomationResponse {
+        let outcome = if !self.authenticated(&request.token) {
+            Err(anyhow!("invalid automation token"))
+        } else {
+            self.dispatch(&request.command, request.params).await
+        };
+        match outcome {
+            Ok(result) => AutomationResponse {
+                id: request.id,
+                result: Some(result),
+                error: None,
+            },
+            Err(err) => {
+                tracing::warn!(command = %request.command, error = %err, "automation request refused");
+                AutomationResponse {
+                    id: request.id,
+                    result: None,
+                    error: Some(format!("{err:#}")),
+                }
+            }
+        }
+    }
+
+    // Connections live in a `JoinSet`, so aborting the serving task also drops open connections.
+    pub async fn serve(self, endpoint: Endpoint) -> Result<()> {
+        let mut connections = JoinSet::new();
+        match endpoint {
+            #[cfg(unix)]
+            Endpoint::Unix(path) => {
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                match tokio::fs::remove_file(&path).await {
+                    Ok(()) => {}
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(err) => return Err(err.into()),
+                }
+                let listener = tokio::net::UnixListener::bind(&path).with_context(|| {
+                    format!("failed to bind automation socket {}", path.display())
+                })?;
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    tokio::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+                        .await?;
+                }
+                tracing::info!(socket = %path.display(), "automation endpoint listening");
+                loop {
+                    let (stream, _) = listener.accept().await?;
+                    while connections.try_join_next().is_some() {}
+                    let server = self.clone();
+                    connections.spawn(async move {
+                        if let Err(err) = server.serve_connection(stream).await {
+                            tracing::debug!(error = %err, "automation connection closed");
+                        }
+                    });
+                }
+            }
+            #[cfg(windows)]
+            Endpoint::NamedPipe(name) => {
+                use tokio::net::windows::named_pipe::ServerOptions;
+
+                let mut pipe = ServerOptions::new()
+                    .first_pipe_instance(true)
+                    .reject_remote_clients(true)
+                    .create(&name)
+                    .with_context(|| format!("failed to create automation pipe {name}"))?;
+                tracing::info!(pipe = %name, "automation endpoint listening");
+                loop {
+                    pipe.connect().await?;
+                    let connected = pipe;
+                    pipe = ServerOptions::new()
+                        .reject_remote_clients(true)
+                        .create(&name)?;
+                    while connections.try_join_next().is_some() {}
+                    let server = self.clone();
+                    connections.spawn(async move {
+                        if let Err(err) = server.serve_connection(connected).await {
+                            tracing::debug!(error = %err, "automation connection closed");
+                        }
+                    });
+                }
+            }
+            other => Err(anyhow!(
+                "automation is not available over {}",
+                other.display()
+            )),
+        }
+    }
+
+    // One JSON request per line, answered by one JSON response per line.
+    pub async fn serve_connection<S>(&self, stream: S) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str::<AutomationRequest>(&line) {
+                Ok(request) => self.handle(request).await,
+                Err(err) => AutomationResponse {
+                    id: Value::Null,
+                    result: None,
+                    error: Some(format!("invalid automation request: {err}")),
+                },
+            };
+            let mut encoded = serde_json::to_vec(&response)?;
+            encoded.push(b'\n');
+            writer.write_all(&encoded).await?;
+            writer.flush().await?;
+        }
+        Ok(())
+    }
+
+    fn authenticated(&self, token: &str) -> bool {
+        let presented: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+        presented
+            .iter()
+            .zip(self.token.iter())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+    }
+
+    async fn dispatch(&self, command: &str, params: Value) -> Result<Value> {
+        let scope = match command {
+            "commands" => {
+                return Ok(json!(self
+                    .settings
+                    .scopes
+                    .iter()
+                    .map(AutomationScope::as_str)
+                    .collect::<Vec<_>>()))
+            }
+            "status" => AutomationScope::Status,
+            "encrypt" => AutomationScope::Encrypt,
+            "lock" => AutomationScope::Lock,
+            other => return Err(anyhow!("unknown automation command '{other}'")),
+        };
+        if !self.settings.allows(scope) {
+            return Err(anyhow!(
+                "automation scope '{}' is not enabled",
+                scope.as_str()
+            ));
+        }
+        match scope {
+            AutomationScope::Status => Ok(json!(self.controller.status().await)),
+            AutomationScope::Encrypt => {
+                let params: EncryptParams = if params.is_null() {
+                    EncryptParams::default()
+                } else {
+                    serde_json::from_value(params).context("invalid encrypt parameters")?
+                };
+                if params.path.as_os_str().is_empty() {
+                    return Err(anyhow!("encrypt needs a path"));
+                }
+                let outcome = self
+                    .controller
+                    .encrypt_file_detailed(
+                        &params.path,
+                        EncryptOptions {
+                            recipients: params.recipients,
+                            labels: params.labels,
+                            out_dir: params.out_dir,
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+                Ok(json!(outcome))
+            }
+            // Locking forgets the master key; the engine stays up until the user enters the
+            // passphrase again.
+            AutomationScope::Lock => {
+                self.controller.lock_key().await?;
+                tracing::info!("master key locked by automation");
+                Ok(json!({ "locked": true }))
+            }
+        }
+    }
+}
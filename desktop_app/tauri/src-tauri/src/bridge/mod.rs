@@ -1,5 +0,0 @@
-pub mod client;
-pub mod transport;
-
-pub use client::{BridgeClient, BridgeConfig, RpcRequest, RpcResponse};
-pub use transport::{Endpoint, TransportKind};
@@ -2,21 +2,71 @@ use std::env;
 use std::path::PathBuf;
 
 use anyhow::{anyhow, Context, Result};
+use dg_controller::{BackendKind, ConflictPolicy};
 use directories::BaseDirs;
 use serde::Deserialize;
 
+use crate::oidc::OidcConfig;
+use crate::telemetry::TelemetrySettings;
+
 #[derive(Debug, Clone)]
 pub struct DesktopConfig {
     pub profile: String,
-    pub telemetry: bool,
+    pub telemetry: TelemetrySettings,
     pub data_dir: PathBuf,
+    pub self_test: bool,
+    pub repair_on_init: bool,
+    pub read_only: bool,
+    pub passphrase: bool,
+    pub headless: bool,
+    pub backend: BackendKind,
+    pub oidc: Option<OidcConfig>,
+    pub backup: Option<BackupSettings>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackupSettings {
+    pub target: PathBuf,
+    #[serde(default = "default_backup_interval")]
+    pub interval_minutes: u64,
+    #[serde(default)]
+    pub conflict: ConflictPolicy,
+}
+
+fn default_backup_interval() -> u64 {
+    60
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum FileTelemetry {
+    Enabled(bool),
+    Categories(TelemetrySettings),
+}
+
+impl From<FileTelemetry> for TelemetrySettings {
+    fn from(value: FileTelemetry) -> Self {
+        match value {
+            FileTelemetry::Enabled(true) => TelemetrySettings::all(),
+            FileTelemetry::Enabled(false) => TelemetrySettings::default(),
+            FileTelemetry::Categories(settings) => settings,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Default)]
 struct FileConfig {
     profile: Option<String>,
-    telemetry: Option<bool>,
+    telemetry: Option<FileTelemetry>,
     data_dir: Option<PathBuf>,
+    self_test: Option<bool>,
+    repair_on_init: Option<bool>,
+    read_only: Option<bool>,
+    passphrase: Option<bool>,
+    headless: Option<bool>,
+    backend: Option<String>,
+    oidc: Option<OidcConfig>,
+    backup: Option<BackupSettings>,
 }
 
 pub fn load() -> Result<DesktopConfig> {
@@ -37,10 +87,54 @@ pub fn load() -> Result<DesktopConfig> {
         .or(file_cfg.profile)
         .unwrap_or_else(|| "dev".into());
     let telemetry = env::var("DG_TELEMETRY")
+        .ok()
+        .and_then(|value| value.parse::<TelemetrySettings>().ok())
+        .or(file_cfg.telemetry.map(TelemetrySettings::from))
+        .unwrap_or_default();
+    let self_test = env::var("DG_SELF_TEST")
+        .ok()
+        .and_then(|value| value.parse::<bool>().ok())
+        .or(file_cfg.self_test)
+        .unwrap_or(true);
+    let repair_on_init = env::var("DG_REPAIR_ON_INIT")
+        .ok()
+        .and_then(|value| value.parse::<bool>().ok())
+        .or(file_cfg.repair_on_init)
+        .unwrap_or(false);
+    let read_only = env::var("DG_READ_ONLY")
+        .ok()
+        .and_then(|value| value.parse::<bool>().ok())
+        .or(file_cfg.read_only)
+        .unwrap_or(false);
+    let passphrase = env::var("DG_PASSPHRASE")
         .ok()
         .and_then(|value| value.parse::<bool>().ok())
-        .or(file_cfg.telemetry)
+        .or(file_cfg.passphrase)
         .unwrap_or(false);
+    let headless = crate::headless::requested(env::args())
+        || env::var("DG_HEADLESS")
+            .ok()
+            .and_then(|value| value.parse::<bool>().ok())
+            .or(file_cfg.headless)
+            .unwrap_or(false);
+    let backend = env::var("DG_BACKEND")
+        .ok()
+        .or(file_cfg.backend)
+        .map(|value| value.parse::<BackendKind>())
+        .transpose()?
+        .unwrap_or(BackendKind::InProcess);
+    let backup = match env::var_os("DG_BACKUP_TARGET") {
+        Some(target) => Some(BackupSettings {
+            target: PathBuf::from(target),
+            ..file_cfg.backup.unwrap_or(BackupSettings {
+                target: PathBuf::new(),
+                interval_minutes: default_backup_interval(),
+                conflict: ConflictPolicy::default(),
+            })
+        }),
+        None => file_cfg.backup,
+    }
+    .filter(|_| !read_only);
     let data_dir = if let Some(dir) = env::var_os("DG_DATA_DIR") {
         PathBuf::from(dir)
     } else if let Some(dir) = file_cfg.data_dir {
@@ -53,9 +147,22 @@ pub fn load() -> Result<DesktopConfig> {
         profile,
         telemetry,
         data_dir,
+        self_test,
+        repair_on_init,
+        read_only,
+        passphrase,
+        headless,
+        backend,
+        oidc: file_cfg.oidc,
+        backup,
     })
 }
 
+pub fn config_path() -> Result<PathBuf> {
+    let base = BaseDirs::new().ok_or_else(|| anyhow!("unable to determine base directories"))?;
+    config_file_path(&base)
+}
+
 fn config_file_path(base: &BaseDirs) -> Result<PathBuf> {
     let dir = if cfg!(windows) {
         PathBuf::from(base.config_dir()).join("DataGuardian")
@@ -0,0 +1,439 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use dg_controller::{ControllerEvent, RecordedEvent};
+use dg_core::policy::find_policy;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::fs;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::telemetry;
+
+pub const DIAGNOSTICS_DIR: &str = "diagnostics";
+pub const REDACTED: &str = "[REDACTED]";
+const LOG_LINES: usize = 2_000;
+const RECENT_ERRORS: usize = 50;
+const BEARER: &str = "bearer ";
+const SECRET_KEYS: [&str; 7] = [
+    "token",
+    "secret",
+    "password",
+    "passphrase",
+    "authorization",
+    "private",
+    "cookie",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct DiagnosticSources {
+    pub config_file: Option<PathBuf>,
+    pub settings: Option<Value>,
+    pub status: Option<Value>,
+    pub events: Vec<RecordedEvent>,
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleEntry {
+    pub name: String,
+    pub description: String,
+    pub bytes: u64,
+    pub redactions: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticBundle {
+    pub path: PathBuf,
+    pub created_at: u64,
+    pub entries: Vec<BundleEntry>,
+    pub summary: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    home: Option<String>,
+}
+
+impl Redactor {
+    pub fn new(home: Option<PathBuf>) -> Self {
+        Self {
+            home: home
+                .map(|home| home.display().to_string())
+                .filter(|home| home.len() > 1),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(directories::BaseDirs::new().map(|base| base.home_dir().to_path_buf()))
+    }
+
+    pub fn text(&self, text: &str) -> (String, usize) {
+        let mut count = 0;
+        let lines = text
+            .lines()
+            .map(|line| {
+                let (line, redactions) = self.line(line);
+                count += redactions;
+                line
+            })
+            .collect::<Vec<_>>();
+        let mut redacted = lines.join("\n");
+        if text.ends_with('\n') {
+            redacted.push('\n');
+        }
+        (redacted, count)
+    }
+
+    pub fn line(&self, line: &str) -> (String, usize) {
+        let mut count = 0;
+        let mut line = line.to_string();
+        if let Some(home) = &self.home {
+            count += line.matches(home.as_str()).count();
+            line = line.replace(home.as_str(), "~");
+        }
+        let (line, secrets) = redact_assignments(&line);
+        let (line, bearers) = redact_after(&line, BEARER);
+        let (line, emails) = redact_emails(&line);
+        (line, count + secrets + bearers + emails)
+    }
+
+    pub fn json(&self, value: &mut Value) -> usize {
+        match value {
+            Value::Object(map) => map
+                .iter_mut()
+                .map(|(key, value)| match value {
+                    Value::String(_) | Value::Number(_) if is_secret_key(key) => {
+                        *value = Value::String(REDACTED.into());
+                        1
+                    }
+                    value => self.json(value),
+                })
+                .sum(),
+            Value::Array(items) => items.iter_mut().map(|item| self.json(item)).sum(),
+            Value::String(text) => {
+                let (redacted, count) = self.line(text);
+                *text = redacted;
+                count
+            }
+            _ => 0,
+        }
+    }
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SECRET_KEYS.iter().any(|secret| key.contains(secret))
+}
+
+fn is_value_end(ch: char) -> bool {
+    ch.is_whitespace() || matches!(ch, '"' | '\'' | ',' | ';' | '}' | ']' | '&')
+}
+
+fn redact_assignments(line: &str) -> (String, usize) {
+    let lower = line.to_ascii_lowercase();
+    let mut out = String::with_capacity(line.len());
+    let mut count = 0;
+    let mut cursor = 0;
+    let mut search = 0;
+    while let Some((start, keyword)) = SECRET_KEYS
+        .iter()
+        .filter_map(|keyword| {
+            lower[search..]
+                .find(keyword)
+                .map(|at| (search + at, keyword))
+        })
+        .min_by_key(|(at, _)| *at)
+    {
+        let mut key_end = start + keyword.len();
+        while line[key_end..]
+            .chars()
+            .next()
+            .is_some_and(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+        {
+            key_end += 1;
+        }
+        let rest = &line[key_end..];
+        let after_quote = rest.trim_start_matches(['"', '\'', ' ']);
+        let Some(after_sep) = after_quote
+            .strip_prefix('=')
+            .or_else(|| after_quote.strip_prefix(':'))
+        else {
+            search = key_end;
+            continue;
+        };
+        let value = after_sep.trim_start_matches([' ', '"', '\'']);
+        let value = match value.get(..BEARER.len()) {
+            Some(scheme) if scheme.eq_ignore_ascii_case(BEARER) => &value[BEARER.len()..],
+            _ => value,
+        };
+        let value_start = line.len() - value.len();
+        let value_len = value.find(is_value_end).unwrap_or(value.len());
+        if value_len == 0 || value.starts_with(REDACTED) {
+            search = value_start.max(key_end);
+            continue;
+        }
+        out.push_str(&line[cursor..value_start]);
+        out.push_str(REDACTED);
+        cursor = value_start + value_len;
+        search = cursor;
+        count += 1;
+    }
+    out.push_str(&line[cursor..]);
+    (out, count)
+}
+
+fn redact_after(line: &str, marker: &str) -> (String, usize) {
+    let lower = line.to_ascii_lowercase();
+    let mut out = String::with_capacity(line.len());
+    let mut count = 0;
+    let mut cursor = 0;
+    while let Some(at) = lower[cursor..].find(marker) {
+        let value_start = cursor + at + marker.len();
+        let value = &line[value_start..];
+        let value_len = if value.starts_with(REDACTED) {
+            0
+        } else {
+            value.find(is_value_end).unwrap_or(value.len())
+        };
+        out.push_str(&line[cursor..value_start]);
+        if value_len > 0 {
+            out.push_str(REDACTED);
+            count += 1;
+        }
+        cursor = value_start + value_len;
+    }
+    out.push_str(&line[cursor..]);
+    (out, count)
+}
+
+fn redact_emails(line: &str) -> (String, usize) {
+    let local = |ch: char| ch.is_ascii_alphanumeric() || "._%+-".contains(ch);
+    let domain = |ch: char| ch.is_ascii_alphanumeric() || ".-".contains(ch);
+    let mut out = String::with_capacity(line.len());
+    let mut count = 0;
+    let mut cursor = 0;
+    while let Some(at) = line[cursor..].find('@').map(|at| cursor + at) {
+        let start = line[cursor..at]
+            .char_indices()
+            .rev()
+            .find(|(_, ch)| !local(*ch))
+            .map(|(index, ch)| cursor + index + ch.len_utf8())
+            .unwrap_or(cursor);
+        let end = line[at + 1..]
+            .find(|ch: char| !domain(ch))
+            .map(|index| at + 1 + index)
+            .unwrap_or(line.len());
+        let host = line[at + 1..end].trim_end_matches('.');
+        if start == at || !host.contains('.') {
+            out.push_str(&line[cursor..=at]);
+            cursor = at + 1;
+            continue;
+        }
+        out.push_str(&line[cursor..start]);
+        out.push_str("[EMAIL]");
+        cursor = at + 1 + host.len();
+        count += 1;
+    }
+    out.push_str(&line[cursor..]);
+    (out, count)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn environment(locale: Option<String>) -> Value {
+    let variables = std::env::vars()
+        .filter(|(name, _)| name.starts_with("DG_") || name == "RUST_LOG")
+        .map(|(name, value)| (name, Value::String(value)))
+        .collect::<serde_json::Map<_, _>>();
+    json!({
+        "os": std::env::consts::OS,
+        "family": std::env::consts::FAMILY,
+        "arch": std::env::consts::ARCH,
+        "cpus": std::thread::available_parallelism().map(|cpus| cpus.get()).unwrap_or(1),
+        "locale": locale,
+        "variables": variables,
+    })
+}
+
+struct Builder {
+    files: Vec<(BundleEntry, Vec<u8>)>,
+}
+
+impl Builder {
+    fn add(&mut self, name: &str, description: &str, content: Vec<u8>, redactions: usize) {
+        self.files.push((
+            BundleEntry {
+                name: name.into(),
+                description: description.into(),
+                bytes: content.len() as u64,
+                redactions,
+            },
+            content,
+        ));
+    }
+
+    fn add_json(&mut self, name: &str, description: &str, value: &Value, redactions: usize) {
+        let content = serde_json::to_vec_pretty(value).unwrap_or_default();
+        self.add(name, description, content, redactions);
+    }
+}
+
+pub fn consent_summary(entries: &[BundleEntry]) -> Vec<String> {
+    let mut summary = vec![
+        "This bundle stays on this device until you choose to share it.".to_string(),
+        "It contains only the files listed below; no envelopes, keys or decrypted data.".into(),
+    ];
+    summary.extend(entries.iter().map(|entry| {
+        format!(
+            "{}: {} ({} bytes, {} values redacted)",
+            entry.name, entry.description, entry.bytes, entry.redactions
+        )
+    }));
+    summary.push(format!(
+        "Home directory paths, email addresses, bearer tokens and values named like {} \
+         are replaced with {REDACTED}.",
+        SECRET_KEYS.join("/")
+    ));
+    summary
+}
+
+pub async fn create_bundle(
+    data_dir: &Path,
+    sources: DiagnosticSources,
+    redactor: &Redactor,
+) -> Result<DiagnosticBundle> {
+    let mut builder = Builder { files: Vec::new() };
+
+    let logs = telemetry::tail_logs(data_dir, LOG_LINES).await?;
+    let (logs, redactions) = redactor.text(&logs.join("\n"));
+    builder.add(
+        "logs/desktop.log",
+        &format!("last {LOG_LINES} lines of the desktop log"),
+        logs.into_bytes(),
+        redactions,
+    );
+
+    if let Some(path) = &sources.config_file {
+        if let Ok(content) = fs::read_to_string(path).await {
+            let (content, redactions) = redactor.text(&content);
+            builder.add(
+                "config/config.toml",
+                "desktop configuration file",
+                content.into_bytes(),
+                redactions,
+            );
+        }
+    }
+    if let Some(mut settings) = sources.settings {
+        let redactions = redactor.json(&mut settings);
+        builder.add_json(
+            "config/settings.json",
+            "user settings",
+            &settings,
+            redactions,
+        );
+    }
+
+    let policy = match find_policy(data_dir).await {
+        Some(path) => fs::read_to_string(&path)
+            .await
+            .ok()
+            .map(|content| (path, content)),
+        None => None,
+    };
+    if let Some((path, content)) = policy {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let (content, redactions) = redactor.text(&content);
+        builder.add(
+            &format!("policy/{name}"),
+            "access policy",
+            content.into_bytes(),
+            redactions,
+        );
+    }
+
+    let mut version = json!({
+        "desktop_app": env!("CARGO_PKG_VERSION"),
+        "status": sources.status,
+    });
+    let redactions = redactor.json(&mut version);
+    builder.add_json(
+        "version.json",
+        "application version and engine status",
+        &version,
+        redactions,
+    );
+
+    let mut errors = sources
+        .events
+        .into_iter()
+        .filter(|recorded| matches!(recorded.event, ControllerEvent::Error(_)))
+        .collect::<Vec<_>>();
+    errors.drain(..errors.len().saturating_sub(RECENT_ERRORS));
+    let mut errors = serde_json::to_value(errors)?;
+    let redactions = redactor.json(&mut errors);
+    builder.add_json(
+        "errors.json",
+        &format!("up to {RECENT_ERRORS} recent errors"),
+        &errors,
+        redactions,
+    );
+
+    let mut env = environment(sources.locale);
+    let redactions = redactor.json(&mut env);
+    builder.add_json(
+        "environment.json",
+        "operating system, architecture, locale and DG_* variables",
+        &env,
+        redactions,
+    );
+
+    let created_at = unix_now();
+    let entries = builder
+        .files
+        .iter()
+        .map(|(entry, _)| entry.clone())
+        .collect::<Vec<_>>();
+    let summary = consent_summary(&entries);
+    let mut consent = summary.join("\n");
+    consent.push('\n');
+    builder.add("SUMMARY.txt", "this summary", consent.into_bytes(), 0);
+
+    let dir = data_dir.join(DIAGNOSTICS_DIR);
+    fs::create_dir_all(&dir)
+        .await
+        .with_context(|| format!("unable to create {}", dir.display()))?;
+    let path = dir.join(format!("dg-diagnostics-{created_at}.zip"));
+    let target = path.clone();
+    let files = builder.files;
+    tokio::task::spawn_blocking(move || write_zip(&target, &files)).await??;
+
+    Ok(DiagnosticBundle {
+        path,
+        created_at,
+        entries,
+        summary,
+    })
+}
+
+fn write_zip(path: &Path, files: &[(BundleEntry, Vec<u8>)]) -> Result<()> {
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("unable to create {}", path.display()))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (entry, content) in files {
+        zip.start_file(entry.name.as_str(), options)?;
+        zip.write_all(content)?;
+    }
+    zip.finish()?.sync_all()?;
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use dg_controller::Controller;
+use dg_core::device::{enrollment_challenge, DeviceIdentity, Enrollment};
+use dg_core::SignedCommand;
+use serde::{Deserialize, Serialize};
+
+use crate::network::HttpClientFactory;
+
+#[derive(Debug, Deserialize)]
+struct Challenge {
+    nonce: String,
+}
+
+#[derive(Debug, Serialize)]
+struct EnrollRequest<'a> {
+    device_id: &'a str,
+    public_key: &'a str,
+    fingerprint: &'a str,
+    nonce: &'a str,
+    signature: String,
+    token: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct EnrollResponse {
+    organization: String,
+    #[serde(default)]
+    management_id: Option<String>,
+}
+
+// The management endpoint hands out a nonce; the device signs it to prove it holds the key it enrolls.
+pub async fn enroll(
+    controller: &Controller,
+    network: &HttpClientFactory,
+    endpoint: &str,
+    token: &str,
+) -> Result<DeviceIdentity> {
+    let base = reqwest::Url::parse(endpoint)
+        .map_err(|err| anyhow!("invalid management endpoint '{endpoint}': {err}"))?;
+    if base.scheme() != "https" {
+        return Err(anyhow!("device enrollment requires an HTTPS endpoint"));
+    }
+    if token.trim().is_empty() {
+        return Err(anyhow!("device enrollment needs an enrollment token"));
+    }
+    let endpoint = base.as_str().trim_end_matches('/').to_owned();
+    let identity = controller.device_identity().await?;
+    let client = network.client()?;
+
+    let challenge: Challenge = client
+        .post(format!("{endpoint}/devices/challenge"))
+        .json(&serde_json::json!({ "device_id": identity.device_id }))
+        .send()
+        .await
+        .context("management endpoint unreachable")?
+        .error_for_status()?
+        .json()
+        .await
+        .context("management endpoint returned an unreadable challenge")?;
+    let signature = controller
+        .sign_as_device(&enrollment_challenge(&identity.device_id, &challenge.nonce))
+        .await?;
+    let enrolled: EnrollResponse = client
+        .post(format!("{endpoint}/devices/enroll"))
+        .json(&EnrollRequest {
+            device_id: &identity.device_id,
+            public_key: &identity.public_key,
+            fingerprint: &identity.fingerprint.hex,
+            nonce: &challenge.nonce,
+            signature: general_purpose::STANDARD.encode(signature),
+            token: token.trim(),
+        })
+        .send()
+        .await
+        .context("management endpoint unreachable")?
+        .error_for_status()
+        .context("enrollment was rejected")?
+        .json()
+        .await
+        .context("management endpoint returned an unreadable enrollment")?;
+
+    controller
+        .record_enrollment(Enrollment {
+            endpoint,
+            organization: enrolled.organization,
+            enrolled_at: crate::oidc::unix_now(),
+            management_id: enrolled.management_id,
+        })
+        .await
+}
+
+// Pending commands are pulled from the management endpoint the device enrolled with.
+pub async fn fetch_commands(
+    network: &HttpClientFactory,
+    identity: &DeviceIdentity,
+) -> Result<Vec<SignedCommand>> {
+    let Some(enrollment) = &identity.enrollment else {
+        return Ok(Vec::new());
+    };
+    network
+        .client()?
+        .get(format!(
+            "{}/devices/{}/commands",
+            enrollment.endpoint, identity.device_id
+        ))
+        .send()
+        .await
+        .context("management endpoint unreachable")?
+        .error_for_status()?
+        .json()
+        .await
+        .context("management endpoint returned unreadable commands")
+}
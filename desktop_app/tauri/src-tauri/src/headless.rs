@@ -0,0 +1,75 @@
+use std::fmt;
+use std::future::Future;
+
+use anyhow::{Context, Result};
+
+use crate::process::{ProcessConfig, ProcessManager};
+
+pub const FLAG: &str = "--headless";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownSignal {
+    Interrupt,
+    Terminate,
+}
+
+impl fmt::Display for ShutdownSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShutdownSignal::Interrupt => f.write_str("interrupt"),
+            ShutdownSignal::Terminate => f.write_str("terminate"),
+        }
+    }
+}
+
+pub fn requested<I, S>(args: I) -> bool
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    args.into_iter().skip(1).any(|arg| arg.as_ref() == FLAG)
+}
+
+pub async fn start_core(config: ProcessConfig) -> Result<ProcessManager> {
+    let binary = config.binary.clone();
+    let manager = ProcessManager::new(config);
+    manager.ensure_running().await.with_context(|| {
+        format!(
+            "unable to start the core daemon at {}; launch the desktop app once to install its runtime",
+            binary.display()
+        )
+    })?;
+    Ok(manager)
+}
+
+pub async fn shutdown_signal() -> Result<ShutdownSignal> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.map(|()| ShutdownSignal::Interrupt).map_err(Into::into),
+            _ = terminate.recv() => Ok(ShutdownSignal::Terminate),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await?;
+        Ok(ShutdownSignal::Interrupt)
+    }
+}
+
+pub async fn serve<F>(core: &ProcessManager, shutdown: F) -> Result<ShutdownSignal>
+where
+    F: Future<Output = Result<ShutdownSignal>>,
+{
+    tracing::info!("running headless; waiting for a shutdown signal");
+    let signal = shutdown.await;
+    if let Err(err) = core.stop().await {
+        tracing::warn!(error = %err, "unable to stop the core daemon");
+    }
+    let signal = signal?;
+    tracing::info!(%signal, "headless shell shutting down");
+    Ok(signal)
+}
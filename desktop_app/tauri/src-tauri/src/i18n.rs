@@ -0,0 +1,162 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::RwLock;
+
+use dg_controller::UserError;
+use serde::Serialize;
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+const CATALOGS: [(&str, &str); 2] = [
+    ("en", include_str!("../locales/en.json")),
+    ("vi", include_str!("../locales/vi.json")),
+];
+
+static CATALOG: once_cell::sync::Lazy<MessageCatalog> =
+    once_cell::sync::Lazy::new(MessageCatalog::builtin);
+static LOCALE: RwLock<String> = RwLock::new(String::new());
+
+pub struct MessageCatalog {
+    locales: HashMap<String, HashMap<String, String>>,
+}
+
+impl MessageCatalog {
+    pub fn builtin() -> Self {
+        let locales = CATALOGS
+            .iter()
+            .map(|(locale, raw)| {
+                let messages = serde_json::from_str(raw)
+                    .unwrap_or_else(|err| panic!("invalid {locale} message catalog: {err}"));
+                (locale.to_string(), messages)
+            })
+            .collect();
+        Self { locales }
+    }
+
+    pub fn locales(&self) -> Vec<&str> {
+        let mut locales: Vec<&str> = self.locales.keys().map(String::as_str).collect();
+        locales.sort_unstable();
+        locales
+    }
+
+    pub fn resolve<'a>(&'a self, locale: &str) -> &'a str {
+        let normalized = normalize(locale);
+        let language = normalized.split('-').next().unwrap_or_default();
+        for candidate in [normalized.as_str(), language] {
+            if let Some((key, _)) = self.locales.get_key_value(candidate) {
+                return key;
+            }
+        }
+        DEFAULT_LOCALE
+    }
+
+    pub fn render(
+        &self,
+        locale: &str,
+        code: &str,
+        params: &BTreeMap<String, String>,
+    ) -> Option<String> {
+        let template = [self.resolve(locale), DEFAULT_LOCALE]
+            .into_iter()
+            .find_map(|locale| self.locales.get(locale)?.get(code))?;
+        Some(
+            params
+                .iter()
+                .fold(template.clone(), |message, (name, value)| {
+                    message.replace(&format!("{{{name}}}"), value)
+                }),
+        )
+    }
+}
+
+fn normalize(locale: &str) -> String {
+    locale
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .replace('_', "-")
+        .to_ascii_lowercase()
+}
+
+pub fn catalog() -> &'static MessageCatalog {
+    &CATALOG
+}
+
+pub fn set_locale(locale: &str) {
+    let resolved = CATALOG.resolve(locale).to_owned();
+    *LOCALE
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = resolved;
+}
+
+pub fn locale() -> String {
+    let locale = LOCALE
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone();
+    if locale.is_empty() {
+        DEFAULT_LOCALE.to_owned()
+    } else {
+        locale
+    }
+}
+
+pub fn system_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalizedError {
+    pub code: String,
+    pub params: BTreeMap<String, String>,
+    pub message: String,
+}
+
+impl LocalizedError {
+    pub fn localize(error: UserError, locale: &str) -> Self {
+        let message = CATALOG
+            .render(locale, &error.code, &error.params)
+            .unwrap_or_else(|| error.message().to_owned());
+        Self {
+            code: error.code,
+            params: error.params,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for LocalizedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<UserError> for LocalizedError {
+    fn from(error: UserError) -> Self {
+        Self::localize(error, &locale())
+    }
+}
+
+impl From<anyhow::Error> for LocalizedError {
+    fn from(error: anyhow::Error) -> Self {
+        UserError::from_anyhow(&error).into()
+    }
+}
+
+impl From<String> for LocalizedError {
+    fn from(detail: String) -> Self {
+        UserError::new(dg_controller::errors::INTERNAL, detail.clone())
+            .with_param("detail", detail)
+            .into()
+    }
+}
+
+impl From<&str> for LocalizedError {
+    fn from(detail: &str) -> Self {
+        detail.to_owned().into()
+    }
+}
@@ -1,7 +1,19 @@
-pub mod bridge;
-pub mod controller;
+pub use dg_bridge as bridge;
+pub use dg_controller as controller;
+
+pub mod approval;
+pub mod automation;
 pub mod desktop_config;
+pub mod diagnostics;
+pub mod enrollment;
+pub mod headless;
+pub mod i18n;
+pub mod network;
+pub mod oidc;
 pub mod process;
+pub mod receipts;
 pub mod runtime_paths;
+pub mod services;
 pub mod settings;
 pub mod telemetry;
+pub mod updates;
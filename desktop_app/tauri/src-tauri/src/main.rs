@@ -1,74 +1,2015 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
 use desktop_app::{
-    controller::{Controller, ControllerEvent},
-    desktop_config, telemetry,
+    approval::{ApprovalSettings, HttpApprovalChannel, PairingCode},
+    automation::{self, AutomationServer, AutomationSettings, AutomationStatus},
+    bridge::{
+        BridgeClient, BridgeConfig, Capability, CoreHealth, ProgressUpdate, RpcRequest,
+        SessionAuthority,
+    },
+    controller::{
+        doctor, inbox, ActiveOperation, AppendReport, BackendConfig, BackendKind, BackupOptions,
+        BackupReport, Controller, ControllerStatus, DirectoryDecryptReport, DirectoryEncryptReport,
+        DoctorOptions, EncryptOptions, EncryptOutcome, EncryptionDefaults, EnvelopeComparison,
+        EventVerbosity, ExportedReport, FingerprintCheck, InboxSettings, Job, JobSpec, JobThrottle,
+        LabelDefinition, LabelRegistry, LabelValidation, OutputRoutes, OutputTemplates,
+        QuarantineHandling, RecordedEvent, ReportFilter, ReportFormat, Retention, RetentionReport,
+        TransferSettings, UserError, ViewerOptions, ViewerSession, WalkSettings, WatchSettings,
+    },
+    desktop_config::{self, BackupSettings},
+    diagnostics::{self, DiagnosticBundle, DiagnosticSources, Redactor},
+    enrollment, headless,
+    i18n::{self, LocalizedError},
+    network::{HttpClientFactory, NetworkSettings},
+    oidc::{DeviceAuthorization, OidcClient},
+    process::{ProcessConfig, ProcessManager},
+    receipts::{HttpReceiptSink, ReceiptSettings},
+    services,
+    settings::{SettingsStore, UserSettings},
+    telemetry::{self, TelemetryConsent, TelemetrySettings},
+    updates::{AvailableUpdate, UpdateChannel, UpdateCheck, UpdateSettings},
 };
+use dg_core::api::DGConfig;
+use dg_core::audit::{AuditEvent, AuditFilter};
+use dg_core::index::{IndexEntry, Reconciliation};
+use dg_core::{
+    ChunkingParams, CustomMetadata, DecisionExplanation, DeviceIdentity, DoctorReport, GrantAction,
+    GrantRequest, Identity, IntegrityReport, KdfParams, KeyExpiryReport, KeyHierarchy, KeyMetadata,
+    KeyProtection, LicenseStatus, NewSigner, PairedDevice, PolicyCacheStats, RangeVerification,
+    SelfTestReport, TrustedSigner,
+};
+use futures::StreamExt;
 use tauri::Emitter;
+use zeroize::Zeroizing;
+
+const IDENTITY_RETRY: Duration = Duration::from_secs(60);
+const MANAGEMENT_POLL: Duration = Duration::from_secs(5 * 60);
+const INBOX_POLL: Duration = Duration::from_secs(5);
+const INDEX_RESCAN: Duration = Duration::from_secs(15 * 60);
+const READY_POLL: Duration = Duration::from_secs(1);
+const DISPOSITION_POLL: Duration = Duration::from_secs(24 * 60 * 60);
+const KEY_AGE_POLL: Duration = Duration::from_secs(24 * 60 * 60);
+const SHELL_CLIENT: &str = "desktop-shell";
+const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(60 * 60);
+const SHELL_SCOPES: [Capability; 6] = [
+    Capability::Scan,
+    Capability::Redact,
+    Capability::Policy,
+    Capability::Status,
+    Capability::ReadLogs,
+    Capability::Progress,
+];
+const ENGINE_SCOPES: [Capability; 6] = [
+    Capability::Encrypt,
+    Capability::Decrypt,
+    Capability::Policy,
+    Capability::Status,
+    Capability::Keys,
+    Capability::Admin,
+];
+
+#[derive(Clone)]
+struct AppState {
+    controller: Controller,
+    data_dir: PathBuf,
+    sessions: SessionAuthority,
+    bridge: BridgeClient,
+    sso: Option<OidcClient>,
+    local_identity: Identity,
+    backup: Option<BackupSettings>,
+    network: HttpClientFactory,
+    profile: String,
+    engine_config: DGConfig,
+    automation: Arc<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>>,
+    inboxes: Arc<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>>,
+    subscriptions: Arc<Mutex<HashMap<String, tauri::async_runtime::JoinHandle<()>>>>,
+}
+
+#[tauri::command]
+async fn encrypt_file(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    path: String,
+    recipients: Vec<String>,
+    labels: Option<Vec<String>>,
+    out_dir: Option<String>,
+    threshold: Option<u8>,
+    sign_with: Option<String>,
+    read_receipts: Option<bool>,
+    preview: Option<bool>,
+    metadata: Option<CustomMetadata>,
+    retention: Option<Retention>,
+    chunking: Option<ChunkingParams>,
+    previous: Option<String>,
+    shred_source: Option<bool>,
+) -> Result<EncryptOutcome, LocalizedError> {
+    let controller = state.controller.clone();
+    let path_buf = PathBuf::from(&path);
+    let pending = controller
+        .start_encrypt_file(
+            &path_buf,
+            EncryptOptions {
+                recipients,
+                labels: labels.unwrap_or_default(),
+                out_dir: out_dir.map(PathBuf::from),
+                threshold,
+                sign_with,
+                read_receipts: read_receipts.unwrap_or(false),
+                preview: preview.unwrap_or(false),
+                metadata: metadata.unwrap_or_default(),
+                retention,
+                chunking,
+                previous: previous.map(PathBuf::from),
+                shred_source: shred_source.unwrap_or(false),
+            },
+        )
+        .await
+        .map_err(LocalizedError::from)?;
+    announce_operation(&app, pending.id, "encrypt", &path);
+    pending
+        .wait()
+        .await
+        .inspect(|_| telemetry::record_usage("encrypt_file"))
+        .map_err(LocalizedError::from)
+}
+
+// Lets the UI offer a cancel button for an operation that is still running.
+fn announce_operation(app: &tauri::AppHandle, id: u64, kind: &str, path: &str) {
+    let _ = app.emit(
+        "dg://operation",
+        serde_json::json!({ "id": id, "kind": kind, "path": path }),
+    );
+}
+
+#[tauri::command]
+async fn cancel_operation(
+    state: tauri::State<'_, AppState>,
+    id: u64,
+) -> Result<ActiveOperation, LocalizedError> {
+    state
+        .controller
+        .cancel_operation(id)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn active_operations(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<ActiveOperation>, LocalizedError> {
+    Ok(state.controller.active_operations())
+}
+
+// Returned as a data URL so the UI can drop it straight into an `<img>`.
+#[tauri::command]
+async fn get_preview(
+    state: tauri::State<'_, AppState>,
+    path: String,
+) -> Result<Option<String>, LocalizedError> {
+    let preview = state
+        .controller
+        .preview(&PathBuf::from(path))
+        .await
+        .map_err(LocalizedError::from)?;
+    Ok(preview.map(|preview| {
+        format!(
+            "data:{};base64,{}",
+            preview.media_type,
+            general_purpose::STANDARD.encode(preview.data)
+        )
+    }))
+}
+
+#[tauri::command]
+async fn verify_range(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    offset: u64,
+    len: u64,
+) -> Result<RangeVerification, LocalizedError> {
+    state
+        .controller
+        .verify_range(&PathBuf::from(path), offset, len)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+// Returns the requested plaintext bytes base64-encoded.
+#[tauri::command]
+async fn decrypt_range(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    offset: u64,
+    len: u64,
+) -> Result<String, LocalizedError> {
+    let plaintext = state
+        .controller
+        .decrypt_range(&PathBuf::from(path), offset, len)
+        .await
+        .map_err(LocalizedError::from)?;
+    Ok(general_purpose::STANDARD.encode(plaintext.as_slice()))
+}
+
+// `data` is base64-encoded so binary log records survive the IPC boundary.
+#[tauri::command]
+async fn append_to_envelope(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    data: String,
+) -> Result<AppendReport, LocalizedError> {
+    let data = Zeroizing::new(
+        general_purpose::STANDARD
+            .decode(data)
+            .map_err(|err| LocalizedError::from(format!("invalid base64 data: {err}")))?,
+    );
+    state
+        .controller
+        .append(&PathBuf::from(path), &data)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn compare_envelopes(
+    state: tauri::State<'_, AppState>,
+    first: String,
+    second: String,
+    decrypt: Option<bool>,
+) -> Result<EnvelopeComparison, LocalizedError> {
+    state
+        .controller
+        .compare_envelopes(
+            &PathBuf::from(first),
+            &PathBuf::from(second),
+            decrypt.unwrap_or(false),
+        )
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn encrypt_text(
+    state: tauri::State<'_, AppState>,
+    content: String,
+    recipients: Vec<String>,
+    labels: Option<Vec<String>>,
+) -> Result<String, LocalizedError> {
+    state
+        .controller
+        .encrypt_text(
+            Zeroizing::new(content),
+            recipients,
+            labels.unwrap_or_default(),
+        )
+        .await
+        .map(|output| output.to_string_lossy().into_owned())
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn decrypt_to_text(
+    state: tauri::State<'_, AppState>,
+    path: String,
+) -> Result<String, LocalizedError> {
+    let mut text = state
+        .controller
+        .decrypt_to_text(&PathBuf::from(path))
+        .await
+        .map_err(LocalizedError::from)?;
+    Ok(std::mem::take(&mut *text))
+}
+
+#[tauri::command]
+async fn sign_file(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    signer: String,
+) -> Result<String, LocalizedError> {
+    state
+        .controller
+        .sign_file(&PathBuf::from(path), &signer)
+        .await
+        .map(|output| output.to_string_lossy().into_owned())
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn verify_signature(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    sig: Option<String>,
+    signer: String,
+) -> Result<bool, LocalizedError> {
+    let sig = sig.map(PathBuf::from);
+    state
+        .controller
+        .verify_signature(&PathBuf::from(path), sig.as_deref(), &signer)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn decrypt_file(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    path: String,
+    out_dir: Option<String>,
+    grant: Option<String>,
+) -> Result<String, LocalizedError> {
+    let controller = state.controller.clone();
+    let path_buf = PathBuf::from(&path);
+    let out_dir = out_dir.map(PathBuf::from);
+    let outcome = match grant {
+        Some(grant) => {
+            controller
+                .decrypt_with_grant(&path_buf, &PathBuf::from(grant), out_dir)
+                .await
+        }
+        None => match controller.start_decrypt_file(&path_buf, out_dir).await {
+            Ok(pending) => {
+                announce_operation(&app, pending.id, "decrypt", &path);
+                pending.wait().await
+            }
+            Err(err) => Err(err),
+        },
+    };
+    outcome
+        .map(|output| {
+            telemetry::record_usage("decrypt_file");
+            output.to_string_lossy().into_owned()
+        })
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn encrypt_directory(
+    state: tauri::State<'_, AppState>,
+    dir: String,
+    recipients: Vec<String>,
+    labels: Option<Vec<String>>,
+    out_dir: Option<String>,
+) -> Result<DirectoryEncryptReport, LocalizedError> {
+    state
+        .controller
+        .encrypt_directory(
+            &PathBuf::from(dir),
+            EncryptOptions {
+                recipients,
+                labels: labels.unwrap_or_default(),
+                out_dir: out_dir.map(PathBuf::from),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn decrypt_directory(
+    state: tauri::State<'_, AppState>,
+    dir: String,
+    out_dir: String,
+) -> Result<DirectoryDecryptReport, LocalizedError> {
+    state
+        .controller
+        .decrypt_directory(&PathBuf::from(dir), &PathBuf::from(out_dir))
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn open_in_viewer(
+    state: tauri::State<'_, AppState>,
+    path: String,
+) -> Result<ViewerSession, LocalizedError> {
+    state
+        .controller
+        .open_viewer(&PathBuf::from(path), ViewerOptions::default())
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn close_viewer(state: tauri::State<'_, AppState>, id: u64) -> Result<bool, LocalizedError> {
+    state
+        .controller
+        .close_viewer(id)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn collect_share(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    out_dir: Option<String>,
+) -> Result<Vec<String>, LocalizedError> {
+    state
+        .controller
+        .collect_share(&PathBuf::from(path), out_dir.map(PathBuf::from))
+        .await
+        .map(|outputs| {
+            outputs
+                .into_iter()
+                .map(|output| output.to_string_lossy().into_owned())
+                .collect()
+        })
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn decrypt_with_shares(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    share_files: Vec<String>,
+    out_dir: Option<String>,
+) -> Result<String, LocalizedError> {
+    state
+        .controller
+        .decrypt_with_shares(
+            &PathBuf::from(path),
+            share_files.into_iter().map(PathBuf::from).collect(),
+            out_dir.map(PathBuf::from),
+        )
+        .await
+        .map(|output| output.to_string_lossy().into_owned())
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn issue_grant(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    recipient: String,
+    signer: String,
+    actions: Vec<GrantAction>,
+    expires_at: u64,
+    out_dir: Option<String>,
+) -> Result<String, LocalizedError> {
+    state
+        .controller
+        .issue_grant(
+            &PathBuf::from(path),
+            GrantRequest {
+                recipient,
+                signer,
+                actions,
+                expires_at,
+            },
+            out_dir.map(PathBuf::from),
+        )
+        .await
+        .map(|output| output.to_string_lossy().into_owned())
+        .map_err(LocalizedError::from)
+}
+
+// The air-gapped machine answers requests with `dg offline answer`; the desktop only writes them
+// and completes them.
+#[tauri::command]
+async fn export_decrypt_request(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    out_dir: Option<String>,
+) -> Result<String, LocalizedError> {
+    state
+        .controller
+        .export_decrypt_request(&PathBuf::from(path), out_dir.map(PathBuf::from))
+        .await
+        .map(|output| output.to_string_lossy().into_owned())
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn complete_decrypt_request(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    response: String,
+    out_dir: Option<String>,
+) -> Result<String, LocalizedError> {
+    state
+        .controller
+        .complete_decrypt_request(
+            &PathBuf::from(path),
+            &PathBuf::from(response),
+            out_dir.map(PathBuf::from),
+        )
+        .await
+        .map(|output| {
+            telemetry::record_usage("decrypt_file");
+            output.to_string_lossy().into_owned()
+        })
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn revoke_recipient(
+    state: tauri::State<'_, AppState>,
+    key_id: String,
+) -> Result<usize, LocalizedError> {
+    state
+        .controller
+        .revoke_recipient(&key_id)
+        .await
+        .map(|job| job.affected.len())
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn key_expiry(state: tauri::State<'_, AppState>) -> Result<KeyExpiryReport, LocalizedError> {
+    state
+        .controller
+        .key_expiry()
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn rotate_key(
+    state: tauri::State<'_, AppState>,
+    key_id: String,
+) -> Result<usize, LocalizedError> {
+    state
+        .controller
+        .rotate_identity(&key_id)
+        .await
+        .map(|job| job.affected.len())
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn check_access(
+    state: tauri::State<'_, AppState>,
+    subject: String,
+    action: String,
+    resource: String,
+) -> Result<bool, LocalizedError> {
+    state
+        .controller
+        .check_access(&subject, &action, &resource)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn self_test(state: tauri::State<'_, AppState>) -> Result<SelfTestReport, LocalizedError> {
+    state
+        .controller
+        .self_test()
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn repair_data_dir(
+    state: tauri::State<'_, AppState>,
+) -> Result<IntegrityReport, LocalizedError> {
+    state
+        .controller
+        .repair_data_dir()
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn mint_session_token(
+    state: tauri::State<'_, AppState>,
+    client: String,
+    scopes: Vec<Capability>,
+    ttl_secs: Option<u64>,
+) -> Result<String, LocalizedError> {
+    let ttl = ttl_secs
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_SESSION_TTL);
+    state
+        .controller
+        .mint_session_token(&state.sessions, &client, &scopes, ttl)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn core_request(
+    state: tauri::State<'_, AppState>,
+    method: String,
+    params: Option<serde_json::Value>,
+) -> Result<serde_json::Value, LocalizedError> {
+    let response = state
+        .bridge
+        .send_request(RpcRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            method,
+            params,
+        })
+        .await
+        .map_err(LocalizedError::from)?;
+    match response.error {
+        Some(error) => Err(UserError::from_rpc(&error).into()),
+        None => Ok(response.result.unwrap_or(serde_json::Value::Null)),
+    }
+}
+
+// Forwards a daemon subscription to the UI as `dg://core-notification` events tagged with the
+// returned id, until `core_unsubscribe` or the daemon ends it.
+#[tauri::command]
+async fn core_subscribe(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    method: String,
+    params: Option<serde_json::Value>,
+) -> Result<String, LocalizedError> {
+    let mut subscription = state
+        .bridge
+        .subscribe(&method, params.unwrap_or_else(|| serde_json::json!({})))
+        .await
+        .map_err(LocalizedError::from)?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let mut subscriptions = state
+        .subscriptions
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let registry = state.subscriptions.clone();
+    let key = id.clone();
+    let forwarder = tauri::async_runtime::spawn(async move {
+        while let Some(notification) = subscription.next().await {
+            let _ = app.emit(
+                "dg://core-notification",
+                serde_json::json!({ "subscription": key, "notification": notification }),
+            );
+        }
+        registry
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&key);
+    });
+    subscriptions.insert(id.clone(), forwarder);
+    Ok(id)
+}
+
+#[tauri::command]
+async fn core_unsubscribe(
+    state: tauri::State<'_, AppState>,
+    id: String,
+) -> Result<bool, LocalizedError> {
+    let forwarder = state
+        .subscriptions
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .remove(&id);
+    Ok(forwarder.map(|forwarder| forwarder.abort()).is_some())
+}
+
+#[tauri::command]
+async fn current_identity(state: tauri::State<'_, AppState>) -> Result<Identity, LocalizedError> {
+    Ok(state.controller.identity())
+}
+
+#[tauri::command]
+async fn set_output_templates(
+    state: tauri::State<'_, AppState>,
+    templates: OutputTemplates,
+) -> Result<OutputTemplates, LocalizedError> {
+    state
+        .controller
+        .set_output_templates(templates.clone())
+        .map_err(LocalizedError::from)?;
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings.output_templates = templates.clone();
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    Ok(templates)
+}
+
+#[tauri::command]
+async fn set_output_routes(
+    state: tauri::State<'_, AppState>,
+    routes: OutputRoutes,
+) -> Result<OutputRoutes, LocalizedError> {
+    state
+        .controller
+        .set_output_routes(routes.clone())
+        .map_err(LocalizedError::from)?;
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings.output_routes = routes.clone();
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    Ok(routes)
+}
+
+#[tauri::command]
+async fn set_transfer_settings(
+    state: tauri::State<'_, AppState>,
+    transfer: TransferSettings,
+) -> Result<TransferSettings, LocalizedError> {
+    state
+        .controller
+        .set_transfer_settings(transfer)
+        .map_err(LocalizedError::from)?;
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings.transfer = transfer;
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    Ok(transfer)
+}
+
+#[tauri::command]
+async fn set_job_throttle(
+    state: tauri::State<'_, AppState>,
+    throttle: JobThrottle,
+) -> Result<JobThrottle, LocalizedError> {
+    state
+        .controller
+        .set_job_throttle(throttle)
+        .map_err(LocalizedError::from)?;
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings.job_throttle = throttle;
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    Ok(throttle)
+}
+
+#[tauri::command]
+async fn set_quarantine_handling(
+    state: tauri::State<'_, AppState>,
+    handling: QuarantineHandling,
+) -> Result<QuarantineHandling, LocalizedError> {
+    state.controller.set_quarantine_handling(handling);
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings.quarantine = handling;
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    Ok(handling)
+}
+
+#[tauri::command]
+async fn trusted_signers(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<TrustedSigner>, LocalizedError> {
+    state
+        .controller
+        .trust_store()
+        .await
+        .map(|store| store.signers)
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn import_trusted_signer(
+    state: tauri::State<'_, AppState>,
+    signer: NewSigner,
+    sign_with: String,
+) -> Result<TrustedSigner, LocalizedError> {
+    state
+        .controller
+        .import_trusted_signer(signer, &sign_with)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn remove_trusted_signer(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    sign_with: String,
+) -> Result<TrustedSigner, LocalizedError> {
+    state
+        .controller
+        .remove_trusted_signer(&id, &sign_with)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn pin_trusted_signer(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    pinned: bool,
+    sign_with: String,
+) -> Result<TrustedSigner, LocalizedError> {
+    state
+        .controller
+        .pin_trusted_signer(&id, pinned, &sign_with)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn device_identity(
+    state: tauri::State<'_, AppState>,
+) -> Result<DeviceIdentity, LocalizedError> {
+    state
+        .controller
+        .device_identity()
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn enroll_device(
+    state: tauri::State<'_, AppState>,
+    endpoint: String,
+    token: String,
+) -> Result<DeviceIdentity, LocalizedError> {
+    let token = Zeroizing::new(token);
+    enrollment::enroll(&state.controller, &state.network, &endpoint, &token)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn panic_wipe(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    confirm: String,
+    reason: Option<String>,
+) -> Result<Option<DeviceAuthorization>, LocalizedError> {
+    let reason = reason.unwrap_or_else(|| "panic wipe".into());
+    let Some(sso) = state.sso.clone() else {
+        // Without single sign-on there is no credential to re-check, so the user types the device id.
+        let identity = state
+            .controller
+            .device_identity()
+            .await
+            .map_err(LocalizedError::from)?;
+        if confirm.trim() != identity.device_id {
+            return Err("type this device's id to confirm the panic wipe".into());
+        }
+        state
+            .controller
+            .panic_wipe(&reason)
+            .await
+            .map_err(LocalizedError::from)?;
+        return Ok(None);
+    };
+    let authorization = sso
+        .start_device_login()
+        .await
+        .map_err(LocalizedError::from)?;
+    let controller = state.controller.clone();
+    let pending = authorization.clone();
+    tauri::async_runtime::spawn(async move {
+        let payload = match sso.complete_device_login(&pending).await {
+            Ok(cached) if cached.identity.subject() != controller.identity().subject() => {
+                serde_json::json!({ "status": "failed", "message": "signed in as a different user" })
+            }
+            Ok(_) => match controller.panic_wipe(&reason).await {
+                Ok(lock) => serde_json::json!({ "status": "wiped", "lock": lock }),
+                Err(err) => serde_json::json!({ "status": "failed", "message": err.to_string() }),
+            },
+            Err(err) => serde_json::json!({ "status": "failed", "message": err.to_string() }),
+        };
+        let _ = app.emit("dg://panic-wipe", payload);
+    });
+    Ok(Some(authorization))
+}
+
+#[tauri::command]
+async fn set_read_receipts(
+    state: tauri::State<'_, AppState>,
+    receipts: Option<ReceiptSettings>,
+) -> Result<Option<ReceiptSettings>, LocalizedError> {
+    if let Some(receipts) = &receipts {
+        receipts.validate().map_err(LocalizedError::from)?;
+    }
+    state.controller.set_receipt_sink(
+        receipts
+            .clone()
+            .map(|receipts| HttpReceiptSink::new(receipts, state.network.clone()) as _),
+    );
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings.read_receipts = receipts.clone();
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    Ok(receipts)
+}
+
+#[tauri::command]
+async fn set_device_approval(
+    state: tauri::State<'_, AppState>,
+    approval: Option<ApprovalSettings>,
+) -> Result<Option<ApprovalSettings>, LocalizedError> {
+    if let Some(approval) = &approval {
+        approval.validate().map_err(LocalizedError::from)?;
+    }
+    state.controller.set_device_approval(
+        approval
+            .clone()
+            .map(|approval| HttpApprovalChannel::approval(approval, state.network.clone())),
+    );
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings.device_approval = approval.clone();
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    Ok(approval)
+}
+
+#[tauri::command]
+async fn start_device_pairing(
+    state: tauri::State<'_, AppState>,
+) -> Result<PairingCode, LocalizedError> {
+    state
+        .controller
+        .start_pairing()
+        .await
+        .map(PairingCode::from)
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn await_device_pairing(
+    state: tauri::State<'_, AppState>,
+) -> Result<PairedDevice, LocalizedError> {
+    state
+        .controller
+        .await_pairing()
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn paired_devices(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<PairedDevice>, LocalizedError> {
+    state
+        .controller
+        .paired_devices()
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn unpair_device(
+    state: tauri::State<'_, AppState>,
+    id: String,
+) -> Result<PairedDevice, LocalizedError> {
+    state
+        .controller
+        .unpair_device(&id)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn automation_settings() -> Result<AutomationStatus, LocalizedError> {
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let settings = store.load().await.map_err(LocalizedError::from)?;
+    Ok(AutomationStatus::new(
+        &ProcessConfig::default().runtime_dir,
+        settings.automation,
+    ))
+}
+
+#[tauri::command]
+async fn set_automation(
+    state: tauri::State<'_, AppState>,
+    automation: AutomationSettings,
+) -> Result<AutomationStatus, LocalizedError> {
+    automation.validate().map_err(LocalizedError::from)?;
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings.automation = automation.clone();
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    let server = start_automation(state.controller.clone(), automation.clone())
+        .await
+        .map_err(LocalizedError::from)?;
+    let previous = std::mem::replace(
+        &mut *state
+            .automation
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        server,
+    );
+    if let Some(previous) = previous {
+        previous.abort();
+    }
+    Ok(AutomationStatus::new(
+        &ProcessConfig::default().runtime_dir,
+        automation,
+    ))
+}
+
+#[tauri::command]
+async fn shred_envelope(
+    state: tauri::State<'_, AppState>,
+    path: String,
+) -> Result<(), LocalizedError> {
+    state
+        .controller
+        .shred_envelope(&PathBuf::from(path))
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn place_legal_hold(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    reason: String,
+) -> Result<Retention, LocalizedError> {
+    state
+        .controller
+        .place_legal_hold(&PathBuf::from(path), &reason)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn release_legal_hold(
+    state: tauri::State<'_, AppState>,
+    path: String,
+) -> Result<Retention, LocalizedError> {
+    state
+        .controller
+        .release_legal_hold(&PathBuf::from(path))
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn retention_report(
+    state: tauri::State<'_, AppState>,
+) -> Result<RetentionReport, LocalizedError> {
+    state
+        .controller
+        .retention_report()
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn reload_policy(
+    state: tauri::State<'_, AppState>,
+) -> Result<PolicyCacheStats, LocalizedError> {
+    state
+        .controller
+        .reload_policy()
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn explain_decision(
+    state: tauri::State<'_, AppState>,
+    action: String,
+    resource: String,
+) -> Result<DecisionExplanation, LocalizedError> {
+    state
+        .controller
+        .explain_decision(&action, &resource)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn policy_cache_stats(
+    state: tauri::State<'_, AppState>,
+) -> Result<PolicyCacheStats, LocalizedError> {
+    state
+        .controller
+        .policy_cache_stats()
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn query_audit(
+    state: tauri::State<'_, AppState>,
+    filter: AuditFilter,
+) -> Result<Vec<AuditEvent>, LocalizedError> {
+    state
+        .controller
+        .query_audit(filter)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn schedule_disposition(
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<Job>, LocalizedError> {
+    state
+        .controller
+        .schedule_disposition()
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn set_auto_dispose(enabled: bool) -> Result<bool, LocalizedError> {
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings.auto_dispose = enabled;
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    Ok(enabled)
+}
+
+#[tauri::command]
+async fn set_index_locations(
+    state: tauri::State<'_, AppState>,
+    locations: Vec<String>,
+) -> Result<Vec<PathBuf>, LocalizedError> {
+    let locations: Vec<PathBuf> = locations.into_iter().map(PathBuf::from).collect();
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings.index_locations = locations.clone();
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    state.controller.set_index_locations(locations.clone());
+    Ok(locations)
+}
+
+#[tauri::command]
+async fn export_report(
+    state: tauri::State<'_, AppState>,
+    filter: ReportFilter,
+    format: ReportFormat,
+    path: String,
+) -> Result<ExportedReport, LocalizedError> {
+    state
+        .controller
+        .export_report(filter, format, &PathBuf::from(path))
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn search_envelopes(
+    state: tauri::State<'_, AppState>,
+    filters: Vec<String>,
+) -> Result<Vec<IndexEntry>, LocalizedError> {
+    state
+        .controller
+        .search_index(&filters)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn reconcile_index(
+    state: tauri::State<'_, AppState>,
+) -> Result<Reconciliation, LocalizedError> {
+    state
+        .controller
+        .reconcile_index()
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn inbox_settings() -> Result<Vec<InboxSettings>, LocalizedError> {
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let settings = store.load().await.map_err(LocalizedError::from)?;
+    Ok(settings.inboxes)
+}
+
+#[tauri::command]
+async fn set_inboxes(
+    state: tauri::State<'_, AppState>,
+    inboxes: Vec<InboxSettings>,
+) -> Result<Vec<InboxSettings>, LocalizedError> {
+    inbox::validate_inboxes(&inboxes).map_err(LocalizedError::from)?;
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings.inboxes = inboxes.clone();
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    restart_inbox_watcher(&state, &inboxes);
+    Ok(inboxes)
+}
+
+// Running inbox watchers pick up new debounce and ignore settings by being restarted.
+#[tauri::command]
+async fn set_watch_settings(
+    state: tauri::State<'_, AppState>,
+    watch: WatchSettings,
+) -> Result<WatchSettings, LocalizedError> {
+    state
+        .controller
+        .set_watch_settings(watch.clone())
+        .map_err(LocalizedError::from)?;
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings.watch = watch.clone();
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    restart_inbox_watcher(&state, &configured_inboxes(&settings));
+    Ok(watch)
+}
+
+#[tauri::command]
+async fn set_walk_settings(
+    state: tauri::State<'_, AppState>,
+    walk: WalkSettings,
+) -> Result<WalkSettings, LocalizedError> {
+    state
+        .controller
+        .set_walk_settings(walk.clone())
+        .map_err(LocalizedError::from)?;
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings.walk = walk.clone();
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    Ok(walk)
+}
+
+fn restart_inbox_watcher(state: &AppState, inboxes: &[InboxSettings]) {
+    let watcher = (!inboxes.is_empty()).then(|| {
+        tauri::async_runtime::spawn(watch_inboxes(state.controller.clone(), inboxes.to_vec()))
+    });
+    let previous = std::mem::replace(
+        &mut *state
+            .inboxes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        watcher,
+    );
+    if let Some(previous) = previous {
+        previous.abort();
+    }
+}
+
+#[tauri::command]
+async fn set_encryption_defaults(
+    state: tauri::State<'_, AppState>,
+    profile: Option<String>,
+    defaults: EncryptionDefaults,
+) -> Result<EncryptionDefaults, LocalizedError> {
+    let profile = profile.unwrap_or_else(|| state.profile.clone());
+    defaults.validate().map_err(LocalizedError::from)?;
+    if profile == state.profile {
+        state
+            .controller
+            .set_encryption_defaults(defaults.clone())
+            .map_err(LocalizedError::from)?;
+    }
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings
+        .encryption_defaults
+        .insert(profile, defaults.clone());
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    Ok(defaults)
+}
+
+#[tauri::command]
+async fn list_labels(state: tauri::State<'_, AppState>) -> Result<LabelRegistry, LocalizedError> {
+    Ok(state.controller.label_registry())
+}
+
+#[tauri::command]
+async fn save_label(
+    state: tauri::State<'_, AppState>,
+    label: LabelDefinition,
+) -> Result<LabelDefinition, LocalizedError> {
+    state
+        .controller
+        .save_label(label)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn delete_label(
+    state: tauri::State<'_, AppState>,
+    name: String,
+) -> Result<LabelRegistry, LocalizedError> {
+    state
+        .controller
+        .delete_label(&name)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn set_label_validation(
+    state: tauri::State<'_, AppState>,
+    validation: LabelValidation,
+) -> Result<LabelRegistry, LocalizedError> {
+    state
+        .controller
+        .set_label_validation(validation)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn list_contacts(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<KeyMetadata>, LocalizedError> {
+    state
+        .controller
+        .list_contacts()
+        .await
+        .map_err(LocalizedError::from)
+}
 
-#[derive(Clone)]
-struct AppState {
-    controller: Controller,
-    data_dir: PathBuf,
+#[tauri::command]
+async fn generate_identity(
+    state: tauri::State<'_, AppState>,
+    label: String,
+) -> Result<KeyMetadata, LocalizedError> {
+    state
+        .controller
+        .generate_identity(&label)
+        .await
+        .map_err(LocalizedError::from)
 }
 
 #[tauri::command]
-async fn encrypt_file(
+async fn generate_key_hierarchy(
     state: tauri::State<'_, AppState>,
-    path: String,
-    recipients: Vec<String>,
-    labels: Option<Vec<String>>,
-    out_dir: Option<String>,
-) -> Result<String, String> {
-    let controller = state.controller.clone();
-    let path_buf = PathBuf::from(path);
-    controller
-        .encrypt_file(
-            &path_buf,
-            recipients,
-            labels.unwrap_or_default(),
-            out_dir.map(PathBuf::from),
+    label: String,
+) -> Result<KeyHierarchy, LocalizedError> {
+    state
+        .controller
+        .generate_key_hierarchy(&label)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn list_identities(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<KeyMetadata>, LocalizedError> {
+    state
+        .controller
+        .list_identities()
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn verify_fingerprint(
+    state: tauri::State<'_, AppState>,
+    key: String,
+    fingerprint: String,
+) -> Result<FingerprintCheck, LocalizedError> {
+    state
+        .controller
+        .verify_fingerprint(&key, &fingerprint)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn network_settings(
+    state: tauri::State<'_, AppState>,
+) -> Result<NetworkSettings, LocalizedError> {
+    Ok(state.network.settings())
+}
+
+#[tauri::command]
+async fn set_network_settings(
+    state: tauri::State<'_, AppState>,
+    network: NetworkSettings,
+) -> Result<NetworkSettings, LocalizedError> {
+    let flushed = state
+        .network
+        .apply(network.clone())
+        .await
+        .map_err(LocalizedError::from)?;
+    if flushed > 0 {
+        tracing::info!(jobs = flushed, "ran deferred network work");
+    }
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings.network = network.clone();
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    Ok(network)
+}
+
+#[tauri::command]
+async fn telemetry_settings() -> Result<TelemetryConsent, LocalizedError> {
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let settings = store.load().await.map_err(LocalizedError::from)?;
+    Ok(TelemetryConsent {
+        categories: telemetry::gate().settings(),
+        decided: settings.telemetry.is_some(),
+    })
+}
+
+#[tauri::command]
+async fn set_telemetry_settings(
+    categories: TelemetrySettings,
+) -> Result<TelemetryConsent, LocalizedError> {
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings.telemetry = Some(categories);
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    telemetry::configure(categories);
+    Ok(TelemetryConsent {
+        categories,
+        decided: true,
+    })
+}
+
+#[tauri::command]
+async fn available_locales() -> Result<Vec<String>, LocalizedError> {
+    Ok(i18n::catalog()
+        .locales()
+        .into_iter()
+        .map(str::to_owned)
+        .collect())
+}
+
+#[tauri::command]
+async fn set_locale(locale: String) -> Result<String, LocalizedError> {
+    let store = SettingsStore::new()?;
+    let mut settings = store.load().await?;
+    i18n::set_locale(&locale);
+    settings.locale = Some(i18n::locale());
+    store.save(&settings).await?;
+    Ok(i18n::locale())
+}
+
+#[tauri::command]
+async fn set_update_channel(channel: UpdateChannel) -> Result<UpdateSettings, LocalizedError> {
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut settings = store.load().await.map_err(LocalizedError::from)?;
+    settings.updates.channel = channel;
+    settings.updates.ensure_install_id();
+    store.save(&settings).await.map_err(LocalizedError::from)?;
+    Ok(settings.updates)
+}
+
+#[tauri::command]
+async fn check_for_updates(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    channel: Option<UpdateChannel>,
+) -> Result<UpdateCheck, LocalizedError> {
+    if state.network.is_offline() {
+        return Err(UserError::new(
+            "network.offline",
+            "update checks are unavailable while offline mode is on",
         )
+        .into());
+    }
+    let store = SettingsStore::new().map_err(LocalizedError::from)?;
+    let mut updates = store.load().await.map_err(LocalizedError::from)?.updates;
+    updates.ensure_install_id();
+    let channel = channel.unwrap_or(updates.channel);
+    let available = fetch_update(&app, &state.network, channel, &updates.install_id).await?;
+    Ok(UpdateCheck {
+        channel,
+        current_version: env!("CARGO_PKG_VERSION").to_string(),
+        available,
+    })
+}
+
+#[tauri::command]
+async fn app_status(state: tauri::State<'_, AppState>) -> Result<ControllerStatus, LocalizedError> {
+    Ok(state.controller.status().await)
+}
+
+// Fails while no core answers on the bridge endpoints.
+#[tauri::command]
+async fn core_status(state: tauri::State<'_, AppState>) -> Result<CoreHealth, LocalizedError> {
+    state.bridge.health().await.map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn lock_master_key(state: tauri::State<'_, AppState>) -> Result<(), LocalizedError> {
+    state
+        .controller
+        .lock_key()
         .await
-        .map(|output| output.to_string_lossy().into_owned())
-        .map_err(|err| err.to_string())
+        .map_err(LocalizedError::from)
 }
 
 #[tauri::command]
-async fn decrypt_file(
+async fn unlock_master_key(
+    state: tauri::State<'_, AppState>,
+    passphrase: String,
+) -> Result<(), LocalizedError> {
+    let passphrase = Zeroizing::new(passphrase);
+    state
+        .controller
+        .unlock_key(&passphrase)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn license_info(state: tauri::State<'_, AppState>) -> Result<LicenseStatus, LocalizedError> {
+    Ok(state.controller.license_status().await)
+}
+
+#[tauri::command]
+async fn install_license(
     state: tauri::State<'_, AppState>,
     path: String,
-    out_dir: Option<String>,
-) -> Result<String, String> {
-    let controller = state.controller.clone();
-    let path_buf = PathBuf::from(path);
-    controller
-        .decrypt_file(&path_buf, out_dir.map(PathBuf::from))
+) -> Result<LicenseStatus, LocalizedError> {
+    let raw = tokio::fs::read(&path)
         .await
-        .map(|output| output.to_string_lossy().into_owned())
-        .map_err(|err| err.to_string())
+        .map_err(|err| format!("unable to read {path}: {err}"))?;
+    state
+        .controller
+        .install_license(&raw)
+        .await
+        .map_err(LocalizedError::from)
 }
 
 #[tauri::command]
-async fn check_access(
+async fn replay_events(
     state: tauri::State<'_, AppState>,
-    subject: String,
-    action: String,
-    resource: String,
-) -> Result<bool, String> {
+    since: Option<u64>,
+) -> Result<Vec<RecordedEvent>, LocalizedError> {
+    Ok(state.controller.replay_events(since).await)
+}
+
+#[tauri::command]
+async fn set_event_verbosity(
+    state: tauri::State<'_, AppState>,
+    verbosity: EventVerbosity,
+) -> Result<EventVerbosity, LocalizedError> {
+    state.controller.set_event_verbosity(verbosity);
+    let store = SettingsStore::new()?;
+    let mut settings = store.load().await?;
+    settings.event_verbosity = verbosity;
+    store.save(&settings).await?;
+    Ok(verbosity)
+}
+
+#[tauri::command]
+async fn backup_now(
+    state: tauri::State<'_, AppState>,
+    dry_run: Option<bool>,
+) -> Result<BackupReport, LocalizedError> {
+    let settings = state
+        .backup
+        .as_ref()
+        .ok_or_else(|| "no backup target is configured".to_string())?;
     state
         .controller
-        .check_access(&subject, &action, &resource)
+        .backup_envelopes(BackupOptions {
+            target: settings.target.clone(),
+            conflict: settings.conflict,
+            dry_run: dry_run.unwrap_or(false),
+        })
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn queue_job(
+    state: tauri::State<'_, AppState>,
+    spec: JobSpec,
+) -> Result<Job, LocalizedError> {
+    state
+        .controller
+        .submit_job(spec)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn list_jobs(state: tauri::State<'_, AppState>) -> Result<Vec<Job>, LocalizedError> {
+    state
+        .controller
+        .list_jobs()
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn pause_job(state: tauri::State<'_, AppState>, id: u64) -> Result<Job, LocalizedError> {
+    state
+        .controller
+        .pause_job(id)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn resume_job(state: tauri::State<'_, AppState>, id: u64) -> Result<Job, LocalizedError> {
+    state
+        .controller
+        .resume_job(id)
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn sso_login(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<DeviceAuthorization, LocalizedError> {
+    let sso = state
+        .sso
+        .clone()
+        .ok_or_else(|| "single sign-on is not configured".to_string())?;
+    let authorization = sso
+        .start_device_login()
         .await
-        .map_err(|err| err.to_string())
+        .map_err(LocalizedError::from)?;
+    let controller = state.controller.clone();
+    let pending = authorization.clone();
+    tauri::async_runtime::spawn(async move {
+        let payload = match sso.complete_device_login(&pending).await {
+            Ok(cached) => {
+                controller.set_identity(cached.identity.clone());
+                serde_json::json!({ "status": "signed-in", "identity": cached.identity })
+            }
+            Err(err) => serde_json::json!({ "status": "failed", "message": err.to_string() }),
+        };
+        let _ = app.emit("dg://identity", payload);
+    });
+    Ok(authorization)
+}
+
+#[tauri::command]
+async fn sso_logout(state: tauri::State<'_, AppState>) -> Result<Identity, LocalizedError> {
+    if let Some(sso) = &state.sso {
+        sso.logout().await.map_err(LocalizedError::from)?;
+    }
+    state.controller.set_identity(state.local_identity.clone());
+    Ok(state.local_identity.clone())
 }
 
 #[tauri::command]
-async fn tail_logs(state: tauri::State<'_, AppState>, limit: usize) -> Result<Vec<String>, String> {
+async fn tail_logs(
+    state: tauri::State<'_, AppState>,
+    limit: usize,
+) -> Result<Vec<String>, LocalizedError> {
     telemetry::tail_logs(&state.data_dir, limit)
         .await
-        .map_err(|err| err.to_string())
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn create_diagnostic_bundle(
+    state: tauri::State<'_, AppState>,
+) -> Result<DiagnosticBundle, LocalizedError> {
+    let settings = match SettingsStore::new() {
+        Ok(store) => store.load().await.ok(),
+        Err(_) => None,
+    };
+    let sources = DiagnosticSources {
+        config_file: desktop_config::config_path().ok(),
+        settings: settings.and_then(|settings| serde_json::to_value(settings).ok()),
+        status: serde_json::to_value(state.controller.status().await).ok(),
+        events: state.controller.replay_events(None).await,
+        locale: Some(i18n::locale()),
+    };
+    diagnostics::create_bundle(&state.data_dir, sources, &Redactor::from_env())
+        .await
+        .map_err(LocalizedError::from)
+}
+
+#[tauri::command]
+async fn run_doctor(state: tauri::State<'_, AppState>) -> Result<DoctorReport, LocalizedError> {
+    let process = ProcessConfig::default();
+    let mut endpoints = vec![process.socket_endpoint];
+    endpoints.extend(process.tcp_fallback);
+    let options = DoctorOptions {
+        endpoints,
+        core_binary: Some(process.binary),
+    };
+    Ok(doctor::run(&state.data_dir, &options).await)
+}
+
+fn core_bridge_config() -> BridgeConfig {
+    let config = ProcessConfig::default();
+    let mut endpoints = vec![config.socket_endpoint];
+    endpoints.extend(config.tcp_fallback);
+    let bridge = BridgeConfig::new(endpoints);
+    match config.tcp_token {
+        Some(token) => bridge.with_transport_token(token),
+        None => bridge,
+    }
+}
+
+fn engine_backend(kind: BackendKind, sessions: &SessionAuthority) -> BackendConfig {
+    match kind {
+        BackendKind::InProcess => BackendConfig::InProcess,
+        BackendKind::Remote => {
+            BackendConfig::Remote(Box::new(core_bridge_config().with_session_authority(
+                sessions.clone(),
+                SHELL_CLIENT,
+                &ENGINE_SCOPES,
+                DEFAULT_SESSION_TTL,
+            )))
+        }
+    }
+}
+
+async fn shared_bridge(
+    controller: &Controller,
+    sessions: SessionAuthority,
+) -> Result<BridgeClient> {
+    let mut bridge_config = core_bridge_config();
+    match controller
+        .authorize_session(SHELL_CLIENT, &SHELL_SCOPES)
+        .await
+    {
+        Ok(()) => {
+            bridge_config = bridge_config.with_session_authority(
+                sessions,
+                SHELL_CLIENT,
+                &SHELL_SCOPES,
+                DEFAULT_SESSION_TTL,
+            );
+        }
+        Err(err) => {
+            tracing::warn!(error = %err, "desktop shell session denied; core requests will be unauthenticated");
+        }
+    }
+    match controller.device_identity().await {
+        Ok(identity) => bridge_config = bridge_config.with_device_id(identity.device_id),
+        Err(err) => tracing::warn!(error = %err, "no device identity for the core handshake"),
+    }
+    BridgeClient::new(bridge_config)
+}
+
+async fn keep_sso_identity(sso: OidcClient, controller: Controller, local_identity: Identity) {
+    loop {
+        let wait = match sso.load_cached().await {
+            Ok(Some(cached)) => cached.refresh_in(desktop_app::oidc::unix_now()),
+            _ => IDENTITY_RETRY,
+        };
+        tokio::time::sleep(wait.max(Duration::from_secs(1))).await;
+        match sso.restore().await {
+            Ok(Some(identity)) => controller.set_identity(identity),
+            Ok(None) => controller.set_identity(local_identity.clone()),
+            Err(err) => tracing::warn!(error = %err, "unable to refresh SSO identity"),
+        }
+    }
+}
+
+async fn keep_management_commands(controller: Controller, network: HttpClientFactory) {
+    let mut ticker = tokio::time::interval(MANAGEMENT_POLL);
+    while !controller.locked() {
+        ticker.tick().await;
+        let Ok(identity) = controller.device_identity().await else {
+            continue;
+        };
+        let commands = match enrollment::fetch_commands(&network, &identity).await {
+            Ok(commands) => commands,
+            Err(err) => {
+                tracing::debug!(error = %err, "management commands unavailable");
+                continue;
+            }
+        };
+        for signed in commands {
+            match controller.apply_management_command(&signed).await {
+                Ok(outcome) => tracing::warn!(
+                    command = %outcome.command.id,
+                    action = outcome.command.action.as_str(),
+                    signer = %outcome.signer,
+                    "applied management command"
+                ),
+                Err(err) => tracing::warn!(error = %err, "rejected management command"),
+            }
+        }
+    }
+}
+
+async fn start_automation(
+    controller: Controller,
+    settings: AutomationSettings,
+) -> Result<Option<tauri::async_runtime::JoinHandle<()>>> {
+    let runtime_dir = ProcessConfig::default().runtime_dir;
+    if !settings.enabled {
+        automation::revoke_token(&runtime_dir).await?;
+        return Ok(None);
+    }
+    let token = automation::load_or_create_token(&runtime_dir).await?;
+    let server = AutomationServer::new(controller, settings, &token);
+    let endpoint = automation::default_endpoint(&runtime_dir);
+    Ok(Some(tauri::async_runtime::spawn(async move {
+        if let Err(err) = server.serve(endpoint).await {
+            tracing::warn!(error = %err, "automation endpoint stopped");
+        }
+    })))
+}
+
+fn configured_inboxes(settings: &UserSettings) -> Vec<InboxSettings> {
+    match inbox::validate_inboxes(&settings.inboxes) {
+        Ok(()) => settings.inboxes.clone(),
+        Err(err) => {
+            tracing::warn!(error = %err, "ignoring invalid inbox settings");
+            Vec::new()
+        }
+    }
+}
+
+// Folder events trigger a pass over the inbox that changed; the ticker still sweeps every inbox
+// for files that were too fresh to encrypt and for folders the watcher could not follow.
+async fn watch_inboxes(controller: Controller, inboxes: Vec<InboxSettings>) {
+    let mut watches = Vec::new();
+    for (position, inbox) in inboxes.iter().enumerate() {
+        let watch = match tokio::fs::create_dir_all(&inbox.dir).await {
+            Ok(()) => controller.watch_folder(&inbox.dir),
+            Err(err) => Err(err.into()),
+        };
+        match watch {
+            Ok(watch) => watches.push(
+                futures::stream::unfold(watch, move |mut watch| async move {
+                    watch.next().await.map(|_| (position, watch))
+                })
+                .boxed(),
+            ),
+            Err(err) => {
+                tracing::warn!(inbox = %inbox.name, error = %err, "inbox not watched; polling")
+            }
+        }
+    }
+    let mut changes = futures::stream::select_all(watches);
+    let mut ticker = tokio::time::interval(INBOX_POLL);
+    loop {
+        let due: Vec<&InboxSettings> = tokio::select! {
+            _ = ticker.tick() => inboxes.iter().collect(),
+            Some(position) = changes.next() => vec![&inboxes[position]],
+        };
+        // A locked session leaves files in the inbox until the engine is reopened.
+        if !controller.ready() {
+            continue;
+        }
+        for inbox in due {
+            match controller.process_inbox(inbox).await {
+                Ok(report) if !report.encrypted.is_empty() => tracing::info!(
+                    inbox = %inbox.name,
+                    encrypted = report.encrypted.len(),
+                    "inbox files encrypted"
+                ),
+                Ok(_) => {}
+                Err(err) => tracing::warn!(inbox = %inbox.name, error = %err, "inbox skipped"),
+            }
+        }
+    }
+}
+
+// The first pass runs once warm-up has finished, then the index is rescanned periodically.
+async fn keep_index_reconciled(controller: Controller) {
+    while !controller.ready() {
+        tokio::time::sleep(READY_POLL).await;
+    }
+    let mut ticker = tokio::time::interval(INDEX_RESCAN);
+    loop {
+        ticker.tick().await;
+        if !controller.ready() || controller.index_locations().is_empty() {
+            continue;
+        }
+        match controller.reconcile_index().await {
+            Ok(report) if report.changed() => {
+                tracing::info!(summary = %report.summary(), "envelope index reconciled")
+            }
+            Ok(_) => {}
+            Err(err) => tracing::warn!(error = %err, "envelope index reconciliation skipped"),
+        }
+    }
+}
+
+// Settings are read on every pass, so turning automatic disposition off takes effect without a
+// restart.
+async fn keep_disposition_scheduled(controller: Controller) {
+    while !controller.ready() {
+        tokio::time::sleep(READY_POLL).await;
+    }
+    let mut ticker = tokio::time::interval(DISPOSITION_POLL);
+    loop {
+        ticker.tick().await;
+        let enabled = match SettingsStore::new() {
+            Ok(store) => store
+                .load()
+                .await
+                .is_ok_and(|settings| settings.auto_dispose),
+            Err(_) => false,
+        };
+        if !enabled || !controller.ready() {
+            continue;
+        }
+        match controller.schedule_disposition().await {
+            Ok(Some(job)) => tracing::info!(
+                job = job.id,
+                envelopes = job.total,
+                "scheduled disposition of overdue envelopes"
+            ),
+            Ok(None) => {}
+            Err(err) => tracing::warn!(error = %err, "disposition scheduling skipped"),
+        }
+    }
+}
+
+async fn keep_keys_rotated(controller: Controller) {
+    while !controller.ready() {
+        tokio::time::sleep(READY_POLL).await;
+    }
+    let mut ticker = tokio::time::interval(KEY_AGE_POLL);
+    loop {
+        ticker.tick().await;
+        if !controller.ready() {
+            continue;
+        }
+        match controller.enforce_key_expiry().await {
+            Ok(jobs) => {
+                for job in jobs {
+                    tracing::info!(
+                        key = job.key.id,
+                        envelopes = job.affected.len(),
+                        "rotated an expired identity"
+                    );
+                }
+            }
+            Err(err) => tracing::warn!(error = %err, "key expiry check skipped"),
+        }
+    }
+}
+
+async fn warm_up(controller: Controller) {
+    match controller.warm_up().await {
+        Ok(report) => tracing::info!(elapsed_us = report.elapsed_us(), "controller warmed up"),
+        Err(err) => tracing::warn!(error = %err, "controller warm-up failed"),
+    }
+}
+
+async fn schedule_backups(controller: Controller, settings: BackupSettings) {
+    let mut ticker =
+        tokio::time::interval(Duration::from_secs(settings.interval_minutes.max(1) * 60));
+    loop {
+        ticker.tick().await;
+        let options = BackupOptions {
+            target: settings.target.clone(),
+            conflict: settings.conflict,
+            dry_run: false,
+        };
+        if let Err(err) = controller.backup_envelopes(options).await {
+            tracing::warn!(
+                target = %settings.target.display(),
+                error = %err,
+                "scheduled envelope backup skipped"
+            );
+        }
+    }
+}
+
+async fn forward_core_progress(handle: tauri::AppHandle, client: BridgeClient) {
+    let mut notifications = match client.subscribe_topic("progress").await {
+        Ok(notifications) => notifications,
+        Err(err) => {
+            tracing::debug!(error = %err, "core daemon unreachable; progress forwarding disabled");
+            return;
+        }
+    };
+    while let Some(notification) = notifications.recv().await {
+        if let Some(update) = ProgressUpdate::from_notification(&notification) {
+            let _ = handle.emit("dg://progress", update);
+        }
+    }
+}
+
+#[cfg(feature = "auto-update")]
+async fn fetch_update(
+    app: &tauri::AppHandle,
+    network: &HttpClientFactory,
+    channel: UpdateChannel,
+    install_id: &str,
+) -> Result<Option<AvailableUpdate>, String> {
+    use desktop_app::network::ProxyMode;
+    use desktop_app::updates::{in_rollout, rollout_percentage, UPDATE_FEED_BASE};
+    use tauri_plugin_updater::UpdaterExt;
+
+    let endpoint = channel
+        .endpoint(UPDATE_FEED_BASE)
+        .parse::<tauri::Url>()
+        .map_err(|err| err.to_string())?;
+    let mut builder = app
+        .updater_builder()
+        .endpoints(vec![endpoint])
+        .map_err(|err| err.to_string())?;
+    let proxy = network.settings();
+    if let (ProxyMode::Manual, Some(url)) = (proxy.proxy, proxy.proxy_url.as_deref()) {
+        builder = builder.proxy(url.parse::<tauri::Url>().map_err(|err| err.to_string())?);
+    }
+    let update = builder
+        .build()
+        .map_err(|err| err.to_string())?
+        .check()
+        .await
+        .map_err(|err| err.to_string())?;
+    Ok(update.map(|update| {
+        let rollout = rollout_percentage(&update.raw_json);
+        let eligible = in_rollout(install_id, &update.version, rollout);
+        AvailableUpdate {
+            version: update.version,
+            notes: update.body,
+            pub_date: update.date.map(|date| date.to_string()),
+            rollout_percentage: rollout,
+            eligible,
+        }
+    }))
+}
+
+#[cfg(not(feature = "auto-update"))]
+async fn fetch_update(
+    _app: &tauri::AppHandle,
+    _network: &HttpClientFactory,
+    _channel: UpdateChannel,
+    _install_id: &str,
+) -> Result<Option<AvailableUpdate>, String> {
+    Err("this build does not include the auto-update feature".into())
 }
 
 fn configure_updater(builder: tauri::Builder<tauri::Wry>) -> tauri::Builder<tauri::Wry> {
@@ -93,49 +2034,460 @@ fn run_app() -> Result<()> {
     let config = desktop_config::load()?;
     telemetry::init(config.telemetry, &config.data_dir)?;
 
-    let controller = Controller::new(dg_core::api::new_default());
-    tauri::async_runtime::block_on(controller.boot(
-        &config.profile,
-        config.data_dir.clone(),
-        config.telemetry,
-    ))?;
+    let session_key = ProcessConfig::default()
+        .runtime_dir
+        .join("ipc")
+        .join("session.key");
+    let sessions = tauri::async_runtime::block_on(SessionAuthority::load_or_create(&session_key))?;
+
+    let controller = Controller::from_config(engine_backend(config.backend, &sessions))?;
+    tracing::info!(backend = %controller.describe_backend(), "controller backend selected");
+    let mut settings =
+        match SettingsStore::new().and_then(|store| tauri::async_runtime::block_on(store.load())) {
+            Ok(settings) => settings,
+            Err(err) => {
+                tracing::warn!(error = %err, "settings unavailable; using defaults");
+                UserSettings::default()
+            }
+        };
+    if settings.updates.ensure_install_id() {
+        if let Err(err) = SettingsStore::new()
+            .and_then(|store| tauri::async_runtime::block_on(store.save(&settings)))
+        {
+            tracing::warn!(error = %err, "unable to persist update install id");
+        }
+    }
+    let telemetry_categories = settings.effective_telemetry(config.telemetry);
+    if settings.telemetry.is_some() {
+        telemetry::configure(telemetry_categories);
+    }
+    i18n::set_locale(
+        settings
+            .locale
+            .clone()
+            .or_else(i18n::system_locale)
+            .as_deref()
+            .unwrap_or(i18n::DEFAULT_LOCALE),
+    );
+    controller.set_event_verbosity(settings.event_verbosity);
+    controller.set_index_locations(settings.index_locations.clone());
+    let local_identity = settings.identity();
+    controller.set_identity(local_identity.clone());
+    if let Err(err) = controller.set_output_templates(settings.output_templates.clone()) {
+        tracing::warn!(error = %err, "ignoring invalid output name templates");
+    }
+    if let Err(err) = controller.set_output_routes(settings.output_routes.clone()) {
+        tracing::warn!(error = %err, "ignoring invalid output routes");
+    }
+    if let Err(err) = controller.set_transfer_settings(settings.transfer) {
+        tracing::warn!(error = %err, "ignoring invalid transfer settings");
+    }
+    if let Err(err) = controller.set_job_throttle(settings.job_throttle) {
+        tracing::warn!(error = %err, "ignoring invalid job throttle");
+    }
+    controller.set_quarantine_handling(settings.quarantine);
+    if let Err(err) = controller.set_watch_settings(settings.watch.clone()) {
+        tracing::warn!(error = %err, "ignoring invalid watch settings");
+    }
+    if let Err(err) = controller.set_walk_settings(settings.walk.clone()) {
+        tracing::warn!(error = %err, "ignoring invalid directory walk settings");
+    }
+    if let Some(defaults) = settings.encryption_defaults.get(&config.profile) {
+        if let Err(err) = controller.set_encryption_defaults(defaults.clone()) {
+            tracing::warn!(error = %err, profile = %config.profile, "ignoring invalid encryption defaults");
+        }
+    }
+    let network = match HttpClientFactory::new(settings.network.clone()) {
+        Ok(network) => network,
+        Err(err) => {
+            tracing::warn!(error = %err, "ignoring invalid network settings");
+            HttpClientFactory::default()
+        }
+    };
+    if let Some(receipts) = settings.read_receipts.clone() {
+        match receipts.validate() {
+            Ok(()) => {
+                controller.set_receipt_sink(Some(HttpReceiptSink::new(receipts, network.clone())))
+            }
+            Err(err) => tracing::warn!(error = %err, "ignoring invalid read receipt settings"),
+        }
+    }
+    if let Some(approval) = settings.device_approval.clone() {
+        match approval.validate() {
+            Ok(()) => controller.set_device_approval(Some(HttpApprovalChannel::approval(
+                approval,
+                network.clone(),
+            ))),
+            Err(err) => tracing::warn!(error = %err, "ignoring invalid device approval settings"),
+        }
+    }
+    let sso = match config.oidc.clone() {
+        Some(oidc) => {
+            let cache = ProcessConfig::default()
+                .runtime_dir
+                .join("identity")
+                .join("oidc.json");
+            let client = OidcClient::new(oidc, cache)?.with_network(network.clone());
+            match tauri::async_runtime::block_on(client.load_cached()) {
+                Ok(Some(cached)) if cached.usable_offline(desktop_app::oidc::unix_now()) => {
+                    controller.set_identity(cached.identity)
+                }
+                Ok(_) => {}
+                Err(err) => tracing::warn!(error = %err, "ignoring unreadable SSO identity cache"),
+            }
+            Some(client)
+        }
+        None => None,
+    };
+    let core = if config.headless {
+        Some(tauri::async_runtime::block_on(headless::start_core(
+            ProcessConfig::default(),
+        ))?)
+    } else {
+        None
+    };
+    let window_core = if config.headless {
+        None
+    } else {
+        tauri::async_runtime::block_on(start_window_core())
+    };
+    let engine_config = DGConfig {
+        profile: config.profile.clone(),
+        data_dir: config.data_dir.clone(),
+        telemetry: telemetry_categories.any(),
+        self_test_on_init: config.self_test,
+        repair_on_init: config.repair_on_init,
+        read_only: config.read_only,
+        key_protection: if config.passphrase {
+            KeyProtection::Passphrase(KdfParams::default())
+        } else {
+            KeyProtection::None
+        },
+        ..Default::default()
+    };
+    tauri::async_runtime::block_on(controller.boot_with_config(engine_config.clone()))?;
+    if let Some(invocation) = services::requested(std::env::args())? {
+        return run_service(controller, invocation);
+    }
+    if let Some(core) = core {
+        let inboxes = configured_inboxes(&settings);
+        return run_headless(
+            controller,
+            Arc::new(core),
+            config.backup,
+            sso,
+            local_identity,
+            inboxes,
+        );
+    }
+
+    let bridge = tauri::async_runtime::block_on(shared_bridge(&controller, sessions.clone()))?;
 
     let app_state = AppState {
         controller: controller.clone(),
         data_dir: config.data_dir.clone(),
+        sessions,
+        bridge,
+        sso,
+        local_identity,
+        backup: config.backup.clone(),
+        network,
+        profile: config.profile.clone(),
+        engine_config,
+        automation: Arc::new(Mutex::new(None)),
+        inboxes: Arc::new(Mutex::new(None)),
+        subscriptions: Arc::new(Mutex::new(HashMap::new())),
     };
+    let automation_settings = settings.automation.clone();
+    let inboxes = configured_inboxes(&settings);
+    let shutdown_core = window_core.clone();
 
     configure_updater(tauri::Builder::default())
         .plugin(tauri_plugin_shell::init())
         .manage(app_state.clone())
         .invoke_handler(tauri::generate_handler![
             encrypt_file,
+            encrypt_text,
+            get_preview,
+            verify_range,
+            decrypt_range,
+            append_to_envelope,
+            compare_envelopes,
+            decrypt_to_text,
+            sign_file,
+            verify_signature,
             decrypt_file,
+            encrypt_directory,
+            decrypt_directory,
+            open_in_viewer,
+            close_viewer,
+            collect_share,
+            decrypt_with_shares,
+            issue_grant,
+            export_decrypt_request,
+            complete_decrypt_request,
+            revoke_recipient,
+            key_expiry,
+            rotate_key,
             check_access,
-            tail_logs
+            self_test,
+            repair_data_dir,
+            mint_session_token,
+            current_identity,
+            set_output_templates,
+            set_output_routes,
+            set_transfer_settings,
+            set_job_throttle,
+            set_quarantine_handling,
+            set_watch_settings,
+            set_walk_settings,
+            cancel_operation,
+            active_operations,
+            set_read_receipts,
+            set_device_approval,
+            start_device_pairing,
+            await_device_pairing,
+            paired_devices,
+            unpair_device,
+            automation_settings,
+            set_automation,
+            inbox_settings,
+            set_inboxes,
+            set_index_locations,
+            reconcile_index,
+            search_envelopes,
+            export_report,
+            shred_envelope,
+            place_legal_hold,
+            release_legal_hold,
+            retention_report,
+            schedule_disposition,
+            set_auto_dispose,
+            reload_policy,
+            policy_cache_stats,
+            query_audit,
+            explain_decision,
+            lock_master_key,
+            unlock_master_key,
+            device_identity,
+            enroll_device,
+            panic_wipe,
+            trusted_signers,
+            import_trusted_signer,
+            remove_trusted_signer,
+            pin_trusted_signer,
+            set_encryption_defaults,
+            list_labels,
+            save_label,
+            delete_label,
+            set_label_validation,
+            list_contacts,
+            generate_identity,
+            generate_key_hierarchy,
+            list_identities,
+            verify_fingerprint,
+            network_settings,
+            set_network_settings,
+            telemetry_settings,
+            set_telemetry_settings,
+            available_locales,
+            set_locale,
+            set_update_channel,
+            check_for_updates,
+            app_status,
+            core_status,
+            license_info,
+            install_license,
+            backup_now,
+            queue_job,
+            list_jobs,
+            pause_job,
+            resume_job,
+            replay_events,
+            set_event_verbosity,
+            sso_login,
+            sso_logout,
+            core_request,
+            core_subscribe,
+            core_unsubscribe,
+            tail_logs,
+            create_diagnostic_bundle,
+            run_doctor
         ])
         .setup(move |app| {
             let handle = app.handle().clone();
+            tauri::async_runtime::spawn(forward_core_progress(
+                handle.clone(),
+                app_state.bridge.clone(),
+            ));
+            if let Some(settings) = app_state.backup.clone() {
+                tauri::async_runtime::spawn(schedule_backups(
+                    app_state.controller.clone(),
+                    settings,
+                ));
+            }
+            if let Some(sso) = app_state.sso.clone() {
+                tauri::async_runtime::spawn(keep_sso_identity(
+                    sso,
+                    app_state.controller.clone(),
+                    app_state.local_identity.clone(),
+                ));
+            }
+            tauri::async_runtime::spawn(keep_management_commands(
+                app_state.controller.clone(),
+                app_state.network.clone(),
+            ));
+            if let Some(core) = window_core.clone() {
+                let mut status = core.subscribe();
+                let events = handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    while let Ok(event) = status.recv().await {
+                        let _ = events.emit("dg://core-status", event);
+                    }
+                });
+                tauri::async_runtime::spawn(async move { core.supervise().await });
+            }
             let mut rx = app_state.controller.subscribe();
+            let events = handle.clone();
             tauri::async_runtime::spawn(async move {
                 while let Ok(event) = rx.recv().await {
-                    let payload = match event {
-                        ControllerEvent::Progress(msg) => serde_json::json!({
-                            "kind": "progress",
-                            "message": msg,
-                        }),
-                        ControllerEvent::Error(msg) => serde_json::json!({
-                            "kind": "error",
-                            "message": msg,
-                        }),
-                    };
-                    let _ = handle.emit("dg://controller", payload);
+                    let _ = events.emit("dg://controller", event);
+                }
+            });
+            let mut progress = app_state.controller.subscribe_progress();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    match progress.recv().await {
+                        Ok(update) => {
+                            let _ = handle.emit("dg://progress", update);
+                        }
+                        // A slow window only misses intermediate byte counts.
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    }
                 }
             });
+            if automation_settings.enabled {
+                let controller = app_state.controller.clone();
+                let slot = app_state.automation.clone();
+                let settings = automation_settings.clone();
+                tauri::async_runtime::spawn(async move {
+                    match start_automation(controller, settings).await {
+                        Ok(server) => {
+                            *slot.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = server
+                        }
+                        Err(err) => tracing::warn!(error = %err, "automation endpoint unavailable"),
+                    }
+                });
+            }
+            if !inboxes.is_empty() {
+                let watcher = tauri::async_runtime::spawn(watch_inboxes(
+                    app_state.controller.clone(),
+                    inboxes.clone(),
+                ));
+                *app_state
+                    .inboxes
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(watcher);
+            }
+            tauri::async_runtime::spawn(warm_up(app_state.controller.clone()));
+            tauri::async_runtime::spawn(keep_index_reconciled(app_state.controller.clone()));
+            tauri::async_runtime::spawn(keep_disposition_scheduled(app_state.controller.clone()));
+            tauri::async_runtime::spawn(keep_keys_rotated(app_state.controller.clone()));
             Ok(())
         })
         .run(tauri::generate_context!())?;
 
-    tauri::async_runtime::block_on(controller.shutdown())?;
+    let report = tauri::async_runtime::block_on(controller.shutdown())?;
+    tracing::info!(
+        drained = report.drained,
+        aborted = report.aborted,
+        "controller shut down"
+    );
+    if let Some(core) = shutdown_core {
+        if let Err(err) = tauri::async_runtime::block_on(core.stop()) {
+            tracing::warn!(error = %err, "unable to stop the core daemon");
+        }
+    }
     Ok(())
 }
+
+// The window talks to a core over the bridge. Start and supervise one unless another process,
+// such as a service manager or a headless shell, already serves the endpoint.
+async fn start_window_core() -> Option<Arc<ProcessManager>> {
+    let core = ProcessManager::new(ProcessConfig::default());
+    if core.is_serving().await {
+        return None;
+    }
+    match core.ensure_running().await {
+        Ok(()) => Some(Arc::new(core)),
+        Err(err) => {
+            tracing::warn!(error = %err, "unable to start the core daemon");
+            None
+        }
+    }
+}
+
+// Services launches are one-shot: handle the files, post a notification and exit without a window.
+fn run_service(controller: Controller, invocation: services::ServiceInvocation) -> Result<()> {
+    tauri::async_runtime::block_on(async move {
+        let report = services::run(&controller, invocation).await;
+        let (title, body) = report.notification();
+        if let Err(err) = services::notify(&title, &body).await {
+            tracing::warn!(error = %err, "unable to post the service notification");
+        }
+        controller.shutdown().await?;
+        if report.failed() > 0 {
+            return Err(anyhow::anyhow!(body));
+        }
+        Ok(())
+    })
+}
+
+fn run_headless(
+    controller: Controller,
+    core: Arc<ProcessManager>,
+    backup: Option<BackupSettings>,
+    sso: Option<OidcClient>,
+    local_identity: Identity,
+    inboxes: Vec<InboxSettings>,
+) -> Result<()> {
+    tauri::async_runtime::block_on(async move {
+        if !inboxes.is_empty() {
+            tauri::async_runtime::spawn(watch_inboxes(controller.clone(), inboxes));
+        }
+        if let Some(settings) = backup {
+            tauri::async_runtime::spawn(schedule_backups(controller.clone(), settings));
+        }
+        if let Some(sso) = sso {
+            tauri::async_runtime::spawn(keep_sso_identity(sso, controller.clone(), local_identity));
+        }
+        let mut rx = controller.subscribe();
+        tauri::async_runtime::spawn(async move {
+            while let Ok(event) = rx.recv().await {
+                tracing::info!(event = %event.summary, "controller event");
+            }
+        });
+        let supervisor = core.clone();
+        tauri::async_runtime::spawn(async move { supervisor.supervise().await });
+        tauri::async_runtime::spawn(warm_up(controller.clone()));
+        tauri::async_runtime::spawn(keep_index_reconciled(controller.clone()));
+        tauri::async_runtime::spawn(keep_disposition_scheduled(controller.clone()));
+        tauri::async_runtime::spawn(keep_keys_rotated(controller.clone()));
+        let mut drained = None;
+        headless::serve(&core, async {
+            let signal = headless::shutdown_signal().await;
+            drained = Some(controller.shutdown().await);
+            signal
+        })
+        .await?;
+        if let Some(report) = drained.transpose()? {
+            tracing::info!(
+                drained = report.drained,
+                aborted = report.aborted,
+                "controller shut down"
+            );
+        }
+        Ok(())
+    })
+}
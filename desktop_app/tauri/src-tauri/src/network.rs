@@ -0,0 +1,184 @@
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const USER_AGENT: &str = concat!("data-guardian-desktop/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyMode {
+    #[default]
+    System,
+    Direct,
+    Manual,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    pub offline: bool,
+    pub proxy: ProxyMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_url: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dispatch {
+    Completed,
+    Deferred,
+}
+
+type DeferredJob = Box<dyn FnOnce(reqwest::Client) -> BoxFuture<'static, Result<()>> + Send>;
+
+struct PendingJob {
+    label: String,
+    job: DeferredJob,
+}
+
+#[derive(Clone)]
+pub struct HttpClientFactory {
+    settings: Arc<RwLock<NetworkSettings>>,
+    client: Arc<RwLock<Option<reqwest::Client>>>,
+    deferred: Arc<Mutex<Vec<PendingJob>>>,
+}
+
+impl Default for HttpClientFactory {
+    fn default() -> Self {
+        Self {
+            settings: Arc::new(RwLock::new(NetworkSettings::default())),
+            client: Arc::new(RwLock::new(None)),
+            deferred: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+impl HttpClientFactory {
+    pub fn new(settings: NetworkSettings) -> Result<Self> {
+        build_client(&settings)?;
+        let factory = Self::default();
+        *factory
+            .settings
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = settings;
+        Ok(factory)
+    }
+
+    pub fn settings(&self) -> NetworkSettings {
+        self.settings
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.settings().offline
+    }
+
+    pub fn client(&self) -> Result<reqwest::Client> {
+        let settings = self.settings();
+        if settings.offline {
+            return Err(anyhow!(
+                "network access is disabled while offline mode is on"
+            ));
+        }
+        if let Some(client) = self
+            .client
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+        {
+            return Ok(client);
+        }
+        let client = build_client(&settings)?;
+        *self
+            .client
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(client.clone());
+        Ok(client)
+    }
+
+    pub async fn apply(&self, settings: NetworkSettings) -> Result<usize> {
+        build_client(&settings)?;
+        *self
+            .settings
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = settings;
+        *self
+            .client
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+        self.flush_deferred().await
+    }
+
+    pub async fn run_or_defer<F, Fut>(&self, label: &str, job: F) -> Result<Dispatch>
+    where
+        F: FnOnce(reqwest::Client) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        if self.is_offline() {
+            tracing::info!(job = label, "offline; deferring network work");
+            self.deferred.lock().await.push(PendingJob {
+                label: label.to_owned(),
+                job: Box::new(move |client| Box::pin(job(client))),
+            });
+            return Ok(Dispatch::Deferred);
+        }
+        job(self.client()?).await?;
+        Ok(Dispatch::Completed)
+    }
+
+    pub async fn pending(&self) -> Vec<String> {
+        self.deferred
+            .lock()
+            .await
+            .iter()
+            .map(|pending| pending.label.clone())
+            .collect()
+    }
+
+    pub async fn flush_deferred(&self) -> Result<usize> {
+        if self.is_offline() {
+            return Ok(0);
+        }
+        let client = self.client()?;
+        let pending = std::mem::take(&mut *self.deferred.lock().await);
+        let count = pending.len();
+        for PendingJob { label, job } in pending {
+            if let Err(err) = job(client.clone()).await {
+                tracing::warn!(job = %label, error = %err, "deferred network work failed");
+            }
+        }
+        Ok(count)
+    }
+}
+
+fn build_client(settings: &NetworkSettings) -> Result<reqwest::Client> {
+    let builder = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .user_agent(USER_AGENT);
+    let builder = match settings.proxy {
+        ProxyMode::System => builder,
+        ProxyMode::Direct => builder.no_proxy(),
+        ProxyMode::Manual => {
+            let url = settings
+                .proxy_url
+                .as_deref()
+                .filter(|url| !url.trim().is_empty())
+                .ok_or_else(|| anyhow!("manual proxy mode requires a proxy_url"))?;
+            let proxy = reqwest::Proxy::all(url)
+                .with_context(|| format!("invalid proxy url {url}"))?
+                .no_proxy(reqwest::NoProxy::from_string(&settings.no_proxy.join(",")));
+            builder.proxy(proxy)
+        }
+    };
+    builder.build().context("failed to build http client")
+}
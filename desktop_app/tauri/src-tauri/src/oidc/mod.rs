@@ -0,0 +1,482 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use dg_core::Identity;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::OnceCell;
+
+use crate::network::HttpClientFactory;
+
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+const DEFAULT_POLL_INTERVAL: u64 = 5;
+const SLOW_DOWN_STEP: u64 = 5;
+const REFRESH_MARGIN: u64 = 60;
+const HTTP_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcConfig {
+    pub issuer: String,
+    pub client_id: String,
+    #[serde(default = "default_scopes")]
+    pub scopes: Vec<String>,
+    #[serde(default = "default_subject_claim")]
+    pub subject_claim: String,
+    #[serde(default = "default_groups_claim")]
+    pub groups_claim: String,
+    #[serde(default)]
+    pub organization_claim: Option<String>,
+    #[serde(default)]
+    pub organization: Option<String>,
+    #[serde(default = "default_offline_grace_hours")]
+    pub offline_grace_hours: u64,
+}
+
+fn default_scopes() -> Vec<String> {
+    ["openid", "profile", "email", "offline_access"]
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+fn default_subject_claim() -> String {
+    "email".into()
+}
+
+fn default_groups_claim() -> String {
+    "groups".into()
+}
+
+fn default_offline_grace_hours() -> u64 {
+    72
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    token_endpoint: String,
+    device_authorization_endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceAuthorization {
+    #[serde(skip_serializing)]
+    device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification_uri_complete: Option<String>,
+    pub expires_in: u64,
+    #[serde(default = "default_poll_interval")]
+    pub interval: u64,
+}
+
+fn default_poll_interval() -> u64 {
+    DEFAULT_POLL_INTERVAL
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    #[serde(default)]
+    id_token: Option<String>,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenError {
+    error: String,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedIdentity {
+    pub identity: Identity,
+    pub expires_at: u64,
+    pub offline_until: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
+}
+
+impl CachedIdentity {
+    pub fn is_fresh(&self, now: u64) -> bool {
+        now < self.expires_at
+    }
+
+    pub fn usable_offline(&self, now: u64) -> bool {
+        now < self.offline_until
+    }
+
+    pub fn refresh_in(&self, now: u64) -> Duration {
+        Duration::from_secs(self.expires_at.saturating_sub(now + REFRESH_MARGIN))
+    }
+}
+
+#[derive(Clone)]
+pub struct OidcClient {
+    config: OidcConfig,
+    cache_path: PathBuf,
+    network: HttpClientFactory,
+    metadata: std::sync::Arc<OnceCell<ProviderMetadata>>,
+}
+
+impl OidcClient {
+    pub fn new(config: OidcConfig, cache_path: PathBuf) -> Result<Self> {
+        if !trusted_transport(&config.issuer) {
+            return Err(anyhow!("OIDC issuer {} must use https", config.issuer));
+        }
+        Ok(Self {
+            config,
+            cache_path,
+            network: HttpClientFactory::default(),
+            metadata: Default::default(),
+        })
+    }
+
+    pub fn with_network(mut self, network: HttpClientFactory) -> Self {
+        self.network = network;
+        self
+    }
+
+    pub fn cache_path(&self) -> &Path {
+        &self.cache_path
+    }
+
+    async fn metadata(&self) -> Result<&ProviderMetadata> {
+        self.metadata
+            .get_or_try_init(|| async {
+                let url = format!(
+                    "{}/.well-known/openid-configuration",
+                    self.config.issuer.trim_end_matches('/')
+                );
+                let metadata: ProviderMetadata = self
+                    .network
+                    .client()?
+                    .get(&url)
+                    .timeout(HTTP_TIMEOUT)
+                    .send()
+                    .await
+                    .with_context(|| format!("OIDC discovery failed for {url}"))?
+                    .error_for_status()?
+                    .json()
+                    .await
+                    .context("invalid OIDC discovery document")?;
+                if !same_issuer(&metadata.issuer, &self.config.issuer) {
+                    return Err(anyhow!(
+                        "discovery issuer {} does not match configured issuer {}",
+                        metadata.issuer,
+                        self.config.issuer
+                    ));
+                }
+                Ok(metadata)
+            })
+            .await
+    }
+
+    pub async fn start_device_login(&self) -> Result<DeviceAuthorization> {
+        let metadata = self.metadata().await?;
+        let endpoint = metadata
+            .device_authorization_endpoint
+            .as_deref()
+            .ok_or_else(|| anyhow!("identity provider does not support device login"))?;
+        let scope = self.config.scopes.join(" ");
+        let response = self
+            .network
+            .client()?
+            .post(endpoint)
+            .timeout(HTTP_TIMEOUT)
+            .form(&[
+                ("client_id", self.config.client_id.as_str()),
+                ("scope", scope.as_str()),
+            ])
+            .send()
+            .await
+            .context("device authorization request failed")?;
+        if !response.status().is_success() {
+            return Err(token_error(response).await);
+        }
+        response
+            .json()
+            .await
+            .context("invalid device authorization response")
+    }
+
+    pub async fn complete_device_login(
+        &self,
+        authorization: &DeviceAuthorization,
+    ) -> Result<CachedIdentity> {
+        let token_endpoint = self.metadata().await?.token_endpoint.clone();
+        let deadline = unix_now() + authorization.expires_in;
+        let mut interval = authorization.interval.max(1);
+        loop {
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            if unix_now() >= deadline {
+                return Err(anyhow!("device login expired before it was approved"));
+            }
+            let response = self
+                .network
+                .client()?
+                .post(&token_endpoint)
+                .timeout(HTTP_TIMEOUT)
+                .form(&[
+                    ("grant_type", DEVICE_CODE_GRANT),
+                    ("device_code", authorization.device_code.as_str()),
+                    ("client_id", self.config.client_id.as_str()),
+                ])
+                .send()
+                .await
+                .context("device token request failed")?;
+            if response.status().is_success() {
+                let tokens: TokenResponse =
+                    response.json().await.context("invalid token response")?;
+                let cached = self.cache_tokens(tokens, None)?;
+                self.persist(&cached).await?;
+                return Ok(cached);
+            }
+            let error: TokenError = response.json().await.context("invalid token error")?;
+            match error.error.as_str() {
+                "authorization_pending" => {}
+                "slow_down" => interval += SLOW_DOWN_STEP,
+                _ => return Err(describe_error(error)),
+            }
+        }
+    }
+
+    pub async fn refresh(&self, cached: &CachedIdentity) -> Result<CachedIdentity> {
+        let refresh_token = cached
+            .refresh_token
+            .as_deref()
+            .ok_or_else(|| anyhow!("cached identity has no refresh token"))?;
+        let token_endpoint = &self.metadata().await?.token_endpoint;
+        let response = self
+            .network
+            .client()?
+            .post(token_endpoint)
+            .timeout(HTTP_TIMEOUT)
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+                ("client_id", self.config.client_id.as_str()),
+            ])
+            .send()
+            .await
+            .context("token refresh request failed")?;
+        if !response.status().is_success() {
+            return Err(token_error(response).await);
+        }
+        let tokens: TokenResponse = response.json().await.context("invalid token response")?;
+        let refreshed = self.cache_tokens(tokens, Some(cached))?;
+        self.persist(&refreshed).await?;
+        Ok(refreshed)
+    }
+
+    pub async fn restore(&self) -> Result<Option<Identity>> {
+        let Some(cached) = self.load_cached().await? else {
+            return Ok(None);
+        };
+        let now = unix_now();
+        if cached.is_fresh(now) {
+            return Ok(Some(cached.identity));
+        }
+        match self.refresh(&cached).await {
+            Ok(refreshed) => Ok(Some(refreshed.identity)),
+            Err(err) if cached.usable_offline(now) => {
+                tracing::warn!(error = %err, "identity refresh failed; using cached SSO identity offline");
+                Ok(Some(cached.identity))
+            }
+            Err(err) => {
+                tracing::warn!(error = %err, "cached SSO identity expired");
+                Ok(None)
+            }
+        }
+    }
+
+    pub async fn load_cached(&self) -> Result<Option<CachedIdentity>> {
+        match tokio::fs::read(&self.cache_path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .with_context(|| format!("invalid identity cache {}", self.cache_path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err).with_context(|| {
+                format!(
+                    "failed to read identity cache {}",
+                    self.cache_path.display()
+                )
+            }),
+        }
+    }
+
+    pub async fn logout(&self) -> Result<()> {
+        match tokio::fs::remove_file(&self.cache_path).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err).with_context(|| {
+                format!(
+                    "failed to remove identity cache {}",
+                    self.cache_path.display()
+                )
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn map_claims(&self, claims: &Value) -> Result<Identity> {
+        let user = claims
+            .get(&self.config.subject_claim)
+            .or_else(|| claims.get("sub"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("ID token has no '{}' claim", self.config.subject_claim))?;
+        let organization = self
+            .config
+            .organization_claim
+            .as_ref()
+            .and_then(|claim| claims.get(claim))
+            .and_then(Value::as_str)
+            .map(String::from)
+            .or_else(|| self.config.organization.clone());
+        let groups = match claims.get(&self.config.groups_claim) {
+            Some(Value::Array(values)) => values
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect(),
+            Some(Value::String(group)) => vec![group.clone()],
+            _ => Vec::new(),
+        };
+        Ok(Identity::new(user)
+            .with_organization(organization)
+            .with_groups(groups))
+    }
+
+    pub fn validate_id_token(&self, id_token: &str) -> Result<Value> {
+        // ID tokens are only accepted straight from the token endpoint over TLS,
+        // which OIDC Core 3.1.3.7 allows in place of signature validation.
+        let claims = decode_jwt_claims(id_token)?;
+        let issuer = claims.get("iss").and_then(Value::as_str).unwrap_or("");
+        if !same_issuer(issuer, &self.config.issuer) {
+            return Err(anyhow!("ID token issuer {issuer} is not trusted"));
+        }
+        let audience_ok = match claims.get("aud") {
+            Some(Value::String(aud)) => aud == &self.config.client_id,
+            Some(Value::Array(values)) => values
+                .iter()
+                .any(|aud| aud.as_str() == Some(self.config.client_id.as_str())),
+            _ => false,
+        };
+        if !audience_ok {
+            return Err(anyhow!("ID token audience does not include this client"));
+        }
+        match claims.get("exp").and_then(Value::as_u64) {
+            Some(exp) if exp > unix_now() => Ok(claims),
+            Some(_) => Err(anyhow!("ID token has expired")),
+            None => Err(anyhow!("ID token has no expiry")),
+        }
+    }
+
+    fn cache_tokens(
+        &self,
+        tokens: TokenResponse,
+        previous: Option<&CachedIdentity>,
+    ) -> Result<CachedIdentity> {
+        let now = unix_now();
+        let (identity, expires_at) = match (&tokens.id_token, previous) {
+            (Some(id_token), _) => {
+                let claims = self.validate_id_token(id_token)?;
+                let exp = claims.get("exp").and_then(Value::as_u64).unwrap_or(now);
+                (self.map_claims(&claims)?, exp)
+            }
+            (None, Some(previous)) => (
+                previous.identity.clone(),
+                now + tokens.expires_in.unwrap_or_default(),
+            ),
+            (None, None) => return Err(anyhow!("token response did not include an ID token")),
+        };
+        Ok(CachedIdentity {
+            identity,
+            expires_at,
+            offline_until: expires_at + self.config.offline_grace_hours * 3600,
+            refresh_token: tokens
+                .refresh_token
+                .or_else(|| previous.and_then(|cached| cached.refresh_token.clone())),
+        })
+    }
+
+    async fn persist(&self, cached: &CachedIdentity) -> Result<()> {
+        if let Some(parent) = self.cache_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // The cache holds the refresh token, so it is written to an owner-only staging file and
+        // renamed into place rather than chmodded after the fact.
+        let mut staging = self.cache_path.as_os_str().to_owned();
+        staging.push(".tmp");
+        let staging = PathBuf::from(staging);
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(target_family = "unix")]
+        options.mode(0o600);
+        let mut file = options.open(&staging).await.with_context(|| {
+            format!(
+                "failed to write identity cache {}",
+                self.cache_path.display()
+            )
+        })?;
+        file.write_all(&serde_json::to_vec_pretty(cached)?).await?;
+        file.sync_all().await?;
+        drop(file);
+        tokio::fs::rename(&staging, &self.cache_path)
+            .await
+            .with_context(|| {
+                format!(
+                    "failed to write identity cache {}",
+                    self.cache_path.display()
+                )
+            })
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn trusted_transport(issuer: &str) -> bool {
+    issuer.starts_with("https://")
+        || issuer.starts_with("http://127.0.0.1")
+        || issuer.starts_with("http://localhost")
+}
+
+fn same_issuer(left: &str, right: &str) -> bool {
+    left.trim_end_matches('/') == right.trim_end_matches('/')
+}
+
+fn decode_jwt_claims(token: &str) -> Result<Value> {
+    let payload = token
+        .split('.')
+        .nth(1)
+        .ok_or_else(|| anyhow!("malformed ID token"))?;
+    let bytes = general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .context("ID token payload is not base64url")?;
+    serde_json::from_slice(&bytes).context("ID token payload is not JSON")
+}
+
+async fn token_error(response: reqwest::Response) -> anyhow::Error {
+    let status = response.status();
+    match response.json::<TokenError>().await {
+        Ok(error) => describe_error(error),
+        Err(_) => anyhow!("identity provider returned {status}"),
+    }
+}
+
+fn describe_error(error: TokenError) -> anyhow::Error {
+    match error.error_description {
+        Some(description) => anyhow!("{}: {description}", error.error),
+        None => anyhow!("{}", error.error),
+    }
+}
@@ -1,16 +1,31 @@
 use std::collections::VecDeque;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use std::sync::OnceLock;
 use std::time::{Duration, Instant};
 
 use crate::runtime_paths::runtime_config_dir;
 use anyhow::{anyhow, Context, Result};
+use dg_core::trust::{self, TrustRole, TrustStore};
+use serde::Serialize;
 use tauri::Manager;
 use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::Mutex;
-
-use crate::bridge::{BridgeClient, Endpoint};
+use tokio::sync::{broadcast, Mutex};
+
+use crate::bridge::{
+    BridgeClient, BridgeConfig, CoreHealth, Endpoint, TransportToken, TCP_TOKEN_ENV,
+};
+
+const SOCKET_ACTIVATION_ENV: &str = "DG_CORE_SOCKET_ACTIVATED";
+// Points the shell at another core binary, such as the native `dg-core` from `dg_server`.
+const CORE_BINARY_ENV: &str = "DG_CORE_BIN";
+const READY_TIMEOUT: Duration = Duration::from_secs(1);
+const ACTIVATION_READY_TIMEOUT: Duration = Duration::from_secs(10);
+// Overrides `RestartPolicy::max_failures`; `0` disables automatic restarts.
+const MAX_RESTARTS_ENV: &str = "DG_CORE_MAX_RESTARTS";
+const SUPERVISE_INTERVAL: Duration = Duration::from_millis(250);
+const PROBE_TIMEOUT: Duration = Duration::from_millis(200);
 
 #[derive(Debug, Clone)]
 pub struct ProcessConfig {
@@ -18,8 +33,71 @@ pub struct ProcessConfig {
     pub runtime_dir: PathBuf,
     pub socket_endpoint: Endpoint,
     pub tcp_fallback: Option<Endpoint>,
+    // Handed to the core in `DG_CORE_TCP_TOKEN`; clients must present it on the TCP fallback.
+    pub tcp_token: Option<TransportToken>,
     pub allow_network: bool,
     pub extra_args: Vec<String>,
+    pub session_key_file: Option<PathBuf>,
+    pub socket_activated: bool,
+    pub restart: RestartPolicy,
+}
+
+// How `ProcessManager::supervise` brings back a core that exits on its own.
+#[derive(Debug, Clone, Copy)]
+pub struct RestartPolicy {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    // Consecutive failures tolerated before the supervisor gives up.
+    pub max_failures: u32,
+    // A core that stays up this long resets the failure count.
+    pub stable_after: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_failures: 5,
+            stable_after: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RestartPolicy {
+    fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Some(max) = std::env::var(MAX_RESTARTS_ENV)
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+        {
+            policy.max_failures = max;
+        }
+        policy
+    }
+
+    // Doubles from `initial_backoff` for each consecutive failure, capped at `max_backoff`.
+    pub fn backoff(&self, failures: u32) -> Duration {
+        let doublings = failures.saturating_sub(1).min(16);
+        self.initial_backoff
+            .saturating_mul(1 << doublings)
+            .min(self.max_backoff)
+    }
+}
+
+// Emitted by the supervisor when the core exits and when it is back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SupervisorEvent {
+    CoreDown {
+        code: Option<i32>,
+        failures: u32,
+        // `None` once the supervisor has given up.
+        retry_in_ms: Option<u64>,
+    },
+    CoreRestarted {
+        attempt: u32,
+    },
 }
 
 impl Default for ProcessConfig {
@@ -56,43 +134,81 @@ impl Default for ProcessConfig {
         #[cfg(not(target_os = "windows"))]
         let launcher = "dg";
 
-        let binary = runtime_dir.join("bin").join(launcher);
+        let binary = std::env::var_os(CORE_BINARY_ENV)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| runtime_dir.join("bin").join(launcher));
+
+        let tcp_token = tcp_fallback.as_ref().map(|_| shell_tcp_token());
 
         Self {
             binary,
             runtime_dir,
             socket_endpoint,
             tcp_fallback,
+            tcp_token,
             allow_network: false,
             extra_args: Vec::new(),
+            session_key_file: None,
+            socket_activated: socket_activation_requested(),
+            restart: RestartPolicy::from_env(),
         }
     }
 }
 
 struct ProcessState {
     child: Option<Child>,
+    started_at: Option<Instant>,
+    // Set by `stop` so the supervisor does not mistake a requested exit for a crash.
+    stopped: bool,
+}
+
+enum ChildStatus {
+    Stopped,
+    Idle,
+    Running(Duration),
+    Exited(Option<i32>),
 }
 
 pub struct ProcessManager {
     config: Mutex<ProcessConfig>,
     state: Mutex<ProcessState>,
+    events: broadcast::Sender<SupervisorEvent>,
 }
 
 impl ProcessManager {
     pub fn new(config: ProcessConfig) -> Self {
+        let (events, _) = broadcast::channel(16);
         Self {
             config: Mutex::new(config),
-            state: Mutex::new(ProcessState { child: None }),
+            state: Mutex::new(ProcessState {
+                child: None,
+                started_at: None,
+                stopped: false,
+            }),
+            events,
         }
     }
 
+    pub fn subscribe(&self) -> broadcast::Receiver<SupervisorEvent> {
+        self.events.subscribe()
+    }
+
     pub async fn ensure_running(&self) -> Result<()> {
+        if self.config.lock().await.socket_activated {
+            return self
+                .wait_for_ready(ACTIVATION_READY_TIMEOUT)
+                .await
+                .map(|_| ());
+        }
+
         let mut state = self.state.lock().await;
+        state.stopped = false;
 
         if let Some(child) = state.child.as_mut() {
             if child.try_wait()?.is_none() {
                 drop(state);
-                self.wait_for_ready().await?;
+                self.wait_for_ready(READY_TIMEOUT).await?;
                 return Ok(());
             }
         }
@@ -103,9 +219,82 @@ impl ProcessManager {
         pipe_logs(child.stderr.take(), "dg-core stderr");
 
         state.child = Some(child);
+        state.started_at = Some(Instant::now());
         drop(state);
 
-        self.wait_for_ready().await
+        let health = self.wait_for_ready(READY_TIMEOUT).await?;
+        tracing::info!(version = %health.version, "DG Core is ready");
+        Ok(())
+    }
+
+    // Whether something already answers on the core endpoints, such as a core another shell owns.
+    pub async fn is_serving(&self) -> bool {
+        self.health().await.is_ok()
+    }
+
+    // Asks whichever endpoint answers first for its `core.health` report.
+    pub async fn health(&self) -> Result<CoreHealth> {
+        self.wait_for_ready(Duration::ZERO).await
+    }
+
+    // Watches the spawned core until `stop`, restarting it with backoff when it exits on its own.
+    // Returns early for socket-activated cores, which the service manager restarts.
+    pub async fn supervise(&self) {
+        let (policy, socket_activated) = {
+            let config = self.config.lock().await;
+            (config.restart, config.socket_activated)
+        };
+        if socket_activated {
+            return;
+        }
+
+        let mut failures = 0;
+        loop {
+            tokio::time::sleep(SUPERVISE_INTERVAL).await;
+            let code = match self.child_status().await {
+                ChildStatus::Stopped => return,
+                ChildStatus::Idle => continue,
+                ChildStatus::Running(uptime) => {
+                    if uptime >= policy.stable_after {
+                        failures = 0;
+                    }
+                    continue;
+                }
+                ChildStatus::Exited(code) => code,
+            };
+            tracing::warn!(?code, "DG Core exited unexpectedly");
+
+            loop {
+                failures += 1;
+                if failures > policy.max_failures {
+                    tracing::error!(failures, "DG Core keeps failing; giving up on restarts");
+                    self.emit(SupervisorEvent::CoreDown {
+                        code,
+                        failures,
+                        retry_in_ms: None,
+                    });
+                    return;
+                }
+                let delay = policy.backoff(failures);
+                self.emit(SupervisorEvent::CoreDown {
+                    code,
+                    failures,
+                    retry_in_ms: Some(delay.as_millis() as u64),
+                });
+                tokio::time::sleep(delay).await;
+                if self.state.lock().await.stopped {
+                    return;
+                }
+                match self.ensure_running().await {
+                    Ok(()) => {
+                        tracing::info!(attempt = failures, "DG Core restarted");
+                        self.emit(SupervisorEvent::CoreRestarted { attempt: failures });
+                        break;
+                    }
+                    Err(err) => tracing::warn!(error = %err, "unable to restart DG Core"),
+                }
+            }
+        }
     }
 
     pub async fn endpoints(&self) -> Vec<Endpoint> {
@@ -125,19 +314,22 @@ impl ProcessManager {
 
     pub async fn stop(&self) -> Result<()> {
         let mut state = self.state.lock().await;
-        if let Some(mut child) = state.child.take() {
-            child.start_kill().ok();
-            child.wait().await.ok();
-        }
+        state.stopped = true;
+        kill_child(&mut state).await;
         Ok(())
     }
 
     pub async fn restart(&self) -> Result<()> {
-        self.stop().await?;
+        // Leaves `stopped` alone so a running supervisor keeps watching the new core.
+        kill_child(&mut *self.state.lock().await).await;
         self.ensure_running().await
     }
 
-    pub async fn prepare_runtime(&self, app: &tauri::AppHandle) -> Result<()> {
+    pub async fn prepare_runtime(
+        &self,
+        app: &tauri::AppHandle,
+        trusted: &TrustStore,
+    ) -> Result<()> {
         let config = self.config.lock().await.clone();
         let resource_dir = app
             .path()
@@ -159,6 +351,13 @@ impl ProcessManager {
             return Ok(());
         }
 
+        if trusted.requires(TrustRole::Runtime) {
+            let signer = trust::verify_bundle(trusted, &resource_dir)
+                .await
+                .context("bundled DG Core runtime failed verification")?;
+            tracing::info!(signer = %signer.id, "verified bundled DG Core runtime");
+        }
+
         if tokio::fs::metadata(&config.runtime_dir).await.is_ok() {
             if let Err(err) = tokio::fs::remove_dir_all(&config.runtime_dir).await {
                 eprintln!(
@@ -188,22 +387,59 @@ impl ProcessManager {
         Ok(())
     }
 
-    async fn wait_for_ready(&self) -> Result<()> {
+    async fn child_status(&self) -> ChildStatus {
+        let mut state = self.state.lock().await;
+        if state.stopped {
+            return ChildStatus::Stopped;
+        }
+        let Some(child) = state.child.as_mut() else {
+            return ChildStatus::Idle;
+        };
+        match child.try_wait() {
+            Ok(None) => ChildStatus::Running(
+                state
+                    .started_at
+                    .map(|started| started.elapsed())
+                    .unwrap_or_default(),
+            ),
+            Ok(Some(status)) => {
+                state.child = None;
+                ChildStatus::Exited(status.code())
+            }
+            Err(err) => {
+                tracing::warn!(error = %err, "unable to poll DG Core");
+                ChildStatus::Idle
+            }
+        }
+    }
+
+    fn emit(&self, event: SupervisorEvent) {
+        // Nobody listening is fine; the supervisor logs every transition itself.
+        let _ = self.events.send(event);
+    }
+
+    // A core is ready once it answers `core.health`; accepting connections is not enough.
+    async fn wait_for_ready(&self, timeout: Duration) -> Result<CoreHealth> {
         let endpoints = self.endpoints().await;
-        let deadline = Instant::now() + Duration::from_secs(1);
+        let tcp_token = self.config.lock().await.tcp_token.clone();
+        let deadline = Instant::now() + timeout;
 
         loop {
+            let mut last_err = None;
             for endpoint in &endpoints {
-                if BridgeClient::probe_endpoint(endpoint, Duration::from_millis(200))
-                    .await
-                    .is_ok()
-                {
-                    return Ok(());
+                match probe_health(endpoint, tcp_token.as_ref()).await {
+                    Ok(health) => return Ok(health),
+                    Err(err) => last_err = Some(err),
                 }
             }
 
             if Instant::now() >= deadline {
-                return Err(anyhow!("DG Core did not become ready within timeout"));
+                return Err(match last_err {
+                    Some(cause) => {
+                        anyhow!("DG Core did not become ready within timeout: {cause:#}")
+                    }
+                    None => anyhow!("DG Core did not become ready within timeout"),
+                });
             }
 
             tokio::time::sleep(Duration::from_millis(50)).await;
@@ -221,6 +457,37 @@ impl Drop for ProcessManager {
     }
 }
 
+async fn probe_health(
+    endpoint: &Endpoint,
+    tcp_token: Option<&TransportToken>,
+) -> Result<CoreHealth> {
+    let mut config = BridgeConfig::new(vec![endpoint.clone()]).with_timeout(PROBE_TIMEOUT);
+    if let Some(token) = tcp_token {
+        config = config.with_transport_token(token.clone());
+    }
+    BridgeClient::new(config)?.health().await
+}
+
+async fn kill_child(state: &mut ProcessState) {
+    if let Some(mut child) = state.child.take() {
+        child.start_kill().ok();
+        child.wait().await.ok();
+    }
+    state.started_at = None;
+}
+
+// One token per shell process, so every default config agrees with the core it spawned.
+fn shell_tcp_token() -> TransportToken {
+    static TOKEN: OnceLock<TransportToken> = OnceLock::new();
+    TOKEN.get_or_init(TransportToken::generate).clone()
+}
+
+fn socket_activation_requested() -> bool {
+    std::env::var(SOCKET_ACTIVATION_ENV)
+        .map(|value| matches!(value.trim(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
 async fn spawn_core(config: &ProcessConfig) -> Result<Child> {
     ensure_dirs(&config.runtime_dir).await?;
 
@@ -258,6 +525,20 @@ async fn spawn_core(config: &ProcessConfig) -> Result<Child> {
         command.arg("--allow-network");
     }
 
+    if let Some(Endpoint::Tcp(addr)) = &config.tcp_fallback {
+        command.arg("--tcp").arg(addr.to_string());
+        match &config.tcp_token {
+            Some(token) => {
+                command.env(TCP_TOKEN_ENV, token.as_str());
+            }
+            None => tracing::warn!("TCP fallback enabled without a transport token"),
+        }
+    }
+
+    if let Some(key_file) = &config.session_key_file {
+        command.arg("--session-key-file").arg(key_file);
+    }
+
     for extra in &config.extra_args {
         command.arg(extra);
     }
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use dg_controller::{ReadReceipt, ReceiptSink};
+use serde::{Deserialize, Serialize};
+
+use crate::network::HttpClientFactory;
+
+const SIGNATURE_HEADER: &str = "X-DG-Signature";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReceiptSettings {
+    pub url: String,
+    pub secret: String,
+}
+
+impl ReceiptSettings {
+    pub fn validate(&self) -> Result<()> {
+        let url = reqwest::Url::parse(&self.url)
+            .map_err(|err| anyhow!("invalid read receipt URL '{}': {err}", self.url))?;
+        if !matches!(url.scheme(), "https" | "http") {
+            return Err(anyhow!("read receipts must be posted over HTTP(S)"));
+        }
+        if self.secret.trim().is_empty() {
+            return Err(anyhow!("read receipts need a signing secret"));
+        }
+        Ok(())
+    }
+}
+
+pub struct HttpReceiptSink {
+    settings: ReceiptSettings,
+    network: HttpClientFactory,
+}
+
+impl HttpReceiptSink {
+    pub fn new(settings: ReceiptSettings, network: HttpClientFactory) -> Arc<Self> {
+        Arc::new(Self { settings, network })
+    }
+}
+
+#[async_trait]
+impl ReceiptSink for HttpReceiptSink {
+    // Offline receipts wait in the deferred queue until the network is enabled again.
+    async fn deliver(&self, receipt: ReadReceipt) -> Result<()> {
+        let signed = receipt.sign(self.settings.secret.as_bytes())?;
+        let url = self.settings.url.clone();
+        self.network
+            .run_or_defer("read-receipt", move |client| async move {
+                client
+                    .post(url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .header(SIGNATURE_HEADER, format!("sha256={}", signed.signature))
+                    .body(signed.body)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                Ok(())
+            })
+            .await?;
+        Ok(())
+    }
+}
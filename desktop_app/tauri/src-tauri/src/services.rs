@@ -0,0 +1,190 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use dg_controller::Controller;
+use serde::Serialize;
+
+pub const FLAG: &str = "--service";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceAction {
+    Encrypt,
+    Decrypt,
+}
+
+impl ServiceAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ServiceAction::Encrypt => "encrypt",
+            ServiceAction::Decrypt => "decrypt",
+        }
+    }
+
+    // Accepts the NSMessage names from Info.plist as well as the plain verbs.
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "encrypt" | "encryptFiles" => Ok(ServiceAction::Encrypt),
+            "decrypt" | "decryptFiles" => Ok(ServiceAction::Decrypt),
+            other => Err(anyhow!("unknown service action '{other}'")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceInvocation {
+    pub action: ServiceAction,
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceOutcome {
+    pub path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceReport {
+    pub action: ServiceAction,
+    pub outcomes: Vec<ServiceOutcome>,
+}
+
+impl ServiceReport {
+    pub fn succeeded(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.error.is_none())
+            .count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.outcomes.len() - self.succeeded()
+    }
+
+    pub fn notification(&self) -> (String, String) {
+        let verb = match self.action {
+            ServiceAction::Encrypt => "Encrypted",
+            ServiceAction::Decrypt => "Decrypted",
+        };
+        let title = if self.failed() == 0 {
+            format!("{verb} with Data Guardian")
+        } else {
+            format!(
+                "Data Guardian could not {} every file",
+                self.action.as_str()
+            )
+        };
+        let body = match (
+            self.succeeded(),
+            self.outcomes.iter().find_map(|o| o.error.as_ref()),
+        ) {
+            (1, None) => self.outcomes[0]
+                .output
+                .as_ref()
+                .and_then(|output| output.file_name())
+                .map(|name| format!("Saved {}", name.to_string_lossy()))
+                .unwrap_or_else(|| "1 file processed".into()),
+            (done, None) => format!("{done} files processed"),
+            (done, Some(error)) => {
+                format!("{done} of {} files processed: {error}", self.outcomes.len())
+            }
+        };
+        (title, body)
+    }
+}
+
+// The Services wrapper launches the app as `<binary> --service <action> <paths>...`.
+pub fn requested<I, S>(args: I) -> Result<Option<ServiceInvocation>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut args = args
+        .into_iter()
+        .skip(1)
+        .skip_while(|arg| arg.as_ref() != FLAG);
+    if args.next().is_none() {
+        return Ok(None);
+    }
+    let action = args
+        .next()
+        .ok_or_else(|| anyhow!("{FLAG} needs an action: encrypt or decrypt"))?;
+    let action = ServiceAction::parse(action.as_ref())?;
+    let paths: Vec<PathBuf> = args
+        .map(|arg| arg.as_ref().to_owned())
+        .filter(|arg| arg != "--")
+        .map(PathBuf::from)
+        .collect();
+    if paths.is_empty() {
+        return Err(anyhow!(
+            "{FLAG} {} needs at least one file",
+            action.as_str()
+        ));
+    }
+    Ok(Some(ServiceInvocation { action, paths }))
+}
+
+// Files are handled one at a time so a single unreadable file does not stop the rest.
+pub async fn run(controller: &Controller, invocation: ServiceInvocation) -> ServiceReport {
+    let mut outcomes = Vec::with_capacity(invocation.paths.len());
+    for path in invocation.paths {
+        let result = match invocation.action {
+            ServiceAction::Encrypt => {
+                controller
+                    .encrypt_file(&path, Vec::new(), Vec::new(), None)
+                    .await
+            }
+            ServiceAction::Decrypt => controller.decrypt_file(&path, None).await,
+        };
+        let outcome = match result {
+            Ok(output) => ServiceOutcome {
+                path,
+                output: Some(output),
+                error: None,
+            },
+            Err(err) => {
+                tracing::warn!(path = %path.display(), error = %err, "service request failed");
+                ServiceOutcome {
+                    path,
+                    output: None,
+                    error: Some(format!("{err:#}")),
+                }
+            }
+        };
+        outcomes.push(outcome);
+    }
+    ServiceReport {
+        action: invocation.action,
+        outcomes,
+    }
+}
+
+pub async fn notify(title: &str, body: &str) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = format!(
+            "display notification \"{}\" with title \"{}\"",
+            applescript_escape(body),
+            applescript_escape(title)
+        );
+        let status = tokio::process::Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .status()
+            .await?;
+        if !status.success() {
+            return Err(anyhow!("osascript exited with {status}"));
+        }
+    }
+    #[cfg(not(target_os = "macos"))]
+    tracing::info!(%title, %body, "service finished");
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn applescript_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -1,25 +1,32 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use crate::runtime_paths::runtime_config_dir;
 use anyhow::{Context, Result};
+use dg_controller::{
+    EncryptionDefaults, EventVerbosity, InboxSettings, JobThrottle, OutputRoutes, OutputTemplates,
+    QuarantineHandling, TransferSettings, WalkSettings, WatchSettings,
+};
+use dg_core::Identity;
 use serde::{Deserialize, Serialize};
 
+use crate::approval::ApprovalSettings;
+use crate::automation::AutomationSettings;
 use crate::bridge::TransportKind;
+use crate::network::NetworkSettings;
+use crate::receipts::ReceiptSettings;
+use crate::telemetry::TelemetrySettings;
+use crate::updates::UpdateSettings;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ThemePreference {
+    #[default]
     System,
     Light,
     Dark,
 }
 
-impl Default for ThemePreference {
-    fn default() -> Self {
-        Self::System
-    }
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct UserSettings {
@@ -28,6 +35,37 @@ pub struct UserSettings {
     pub endpoint: Option<String>,
     pub theme: ThemePreference,
     pub allow_network: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+    pub output_templates: OutputTemplates,
+    #[serde(skip_serializing_if = "OutputRoutes::is_empty")]
+    pub output_routes: OutputRoutes,
+    pub network: NetworkSettings,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_receipts: Option<ReceiptSettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_approval: Option<ApprovalSettings>,
+    pub transfer: TransferSettings,
+    pub job_throttle: JobThrottle,
+    pub quarantine: QuarantineHandling,
+    pub watch: WatchSettings,
+    pub walk: WalkSettings,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub encryption_defaults: BTreeMap<String, EncryptionDefaults>,
+    pub updates: UpdateSettings,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub telemetry: Option<TelemetrySettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    pub event_verbosity: EventVerbosity,
+    pub automation: AutomationSettings,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub inboxes: Vec<InboxSettings>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub index_locations: Vec<PathBuf>,
+    pub auto_dispose: bool,
 }
 
 impl Default for UserSettings {
@@ -37,10 +75,43 @@ impl Default for UserSettings {
             endpoint: None,
             theme: ThemePreference::System,
             allow_network: false,
+            organization: None,
+            groups: Vec::new(),
+            output_templates: OutputTemplates::default(),
+            output_routes: OutputRoutes::default(),
+            network: NetworkSettings::default(),
+            read_receipts: None,
+            device_approval: None,
+            transfer: TransferSettings::default(),
+            job_throttle: JobThrottle::default(),
+            quarantine: QuarantineHandling::default(),
+            watch: WatchSettings::default(),
+            walk: WalkSettings::default(),
+            encryption_defaults: BTreeMap::new(),
+            updates: UpdateSettings::default(),
+            telemetry: None,
+            locale: None,
+            event_verbosity: EventVerbosity::default(),
+            automation: AutomationSettings::default(),
+            inboxes: Vec::new(),
+            index_locations: Vec::new(),
+            auto_dispose: false,
         }
     }
 }
 
+impl UserSettings {
+    pub fn identity(&self) -> Identity {
+        Identity::from_os()
+            .with_organization(self.organization.clone())
+            .with_groups(self.groups.clone())
+    }
+
+    pub fn effective_telemetry(&self, default: TelemetrySettings) -> TelemetrySettings {
+        self.telemetry.unwrap_or(default)
+    }
+}
+
 pub struct SettingsStore {
     path: PathBuf,
 }
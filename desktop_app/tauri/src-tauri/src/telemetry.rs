@@ -1,37 +1,209 @@
 use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
 use tokio::fs;
-use tracing_subscriber::{fmt, layer::SubscriberExt, EnvFilter, Registry};
+use tracing::Metadata;
+use tracing_subscriber::filter::{filter_fn, FilterFn};
+use tracing_subscriber::{fmt, layer::SubscriberExt, EnvFilter, Layer, Registry};
+
+pub const CRASH_TARGET: &str = "dg_telemetry::crash";
+pub const USAGE_TARGET: &str = "dg_telemetry::usage";
+pub const PERFORMANCE_TARGET: &str = "dg_telemetry::performance";
 
 static FILE_GUARD: once_cell::sync::OnceCell<tracing_appender::non_blocking::WorkerGuard> =
     once_cell::sync::OnceCell::new();
+static GATE: once_cell::sync::Lazy<TelemetryGate> =
+    once_cell::sync::Lazy::new(TelemetryGate::default);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TelemetryCategory {
+    Crash,
+    Usage,
+    Performance,
+}
+
+impl TelemetryCategory {
+    pub fn of(metadata: &Metadata<'_>) -> Option<Self> {
+        match metadata.target() {
+            CRASH_TARGET => Some(Self::Crash),
+            USAGE_TARGET => Some(Self::Usage),
+            PERFORMANCE_TARGET => Some(Self::Performance),
+            _ if metadata.is_span() => Some(Self::Performance),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for TelemetryCategory {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "crash" | "crash_reports" => Ok(Self::Crash),
+            "usage" | "usage_metrics" => Ok(Self::Usage),
+            "performance" | "performance_traces" => Ok(Self::Performance),
+            other => Err(anyhow!("unknown telemetry category {other}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    pub crash_reports: bool,
+    pub usage_metrics: bool,
+    pub performance_traces: bool,
+}
 
-pub fn init(telemetry: bool, data_dir: &Path) -> Result<()> {
-    if telemetry {
-        // Placeholder for OTLP exporter wiring.
-        let subscriber = Registry::default()
-            .with(EnvFilter::from_default_env())
-            .with(fmt::layer().with_target(true));
-        tracing::subscriber::set_global_default(subscriber)?;
-        tracing::info!("telemetry initialized with OTLP exporter");
-    } else {
-        let log_dir = data_dir.join("logs");
-        if !log_dir.exists() {
-            std::fs::create_dir_all(&log_dir)?;
+impl TelemetrySettings {
+    pub fn all() -> Self {
+        Self {
+            crash_reports: true,
+            usage_metrics: true,
+            performance_traces: true,
         }
-        let file_appender = tracing_appender::rolling::never(&log_dir, "desktop.log");
-        let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
-        FILE_GUARD.set(_guard).ok();
-        let subscriber = Registry::default()
-            .with(EnvFilter::from_default_env())
-            .with(fmt::layer().with_writer(non_blocking).with_target(false));
-        tracing::subscriber::set_global_default(subscriber)?;
-        tracing::info!("file logging initialized");
     }
+
+    pub fn allows(&self, category: TelemetryCategory) -> bool {
+        match category {
+            TelemetryCategory::Crash => self.crash_reports,
+            TelemetryCategory::Usage => self.usage_metrics,
+            TelemetryCategory::Performance => self.performance_traces,
+        }
+    }
+
+    pub fn any(&self) -> bool {
+        self.crash_reports || self.usage_metrics || self.performance_traces
+    }
+
+    pub fn with(mut self, category: TelemetryCategory) -> Self {
+        match category {
+            TelemetryCategory::Crash => self.crash_reports = true,
+            TelemetryCategory::Usage => self.usage_metrics = true,
+            TelemetryCategory::Performance => self.performance_traces = true,
+        }
+        self
+    }
+}
+
+impl FromStr for TelemetrySettings {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "true" | "all" => Ok(Self::all()),
+            "false" | "none" | "" => Ok(Self::default()),
+            list => list
+                .split(',')
+                .try_fold(Self::default(), |settings, category| {
+                    Ok(settings.with(category.parse()?))
+                }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetryConsent {
+    pub categories: TelemetrySettings,
+    pub decided: bool,
+}
+
+#[derive(Clone, Default)]
+pub struct TelemetryGate {
+    settings: Arc<RwLock<TelemetrySettings>>,
+}
+
+impl TelemetryGate {
+    pub fn new(settings: TelemetrySettings) -> Self {
+        let gate = Self::default();
+        gate.configure(settings);
+        gate
+    }
+
+    pub fn settings(&self) -> TelemetrySettings {
+        *self
+            .settings
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn configure(&self, settings: TelemetrySettings) {
+        *self
+            .settings
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = settings;
+    }
+
+    pub fn allows(&self, metadata: &Metadata<'_>) -> bool {
+        TelemetryCategory::of(metadata).is_some_and(|category| self.settings().allows(category))
+    }
+
+    pub fn filter(&self) -> FilterFn<impl Fn(&Metadata<'_>) -> bool> {
+        let gate = self.clone();
+        filter_fn(move |metadata| gate.allows(metadata))
+    }
+}
+
+pub fn gate() -> &'static TelemetryGate {
+    &GATE
+}
+
+pub fn configure(settings: TelemetrySettings) {
+    GATE.configure(settings);
+    tracing::info!(
+        crash_reports = settings.crash_reports,
+        usage_metrics = settings.usage_metrics,
+        performance_traces = settings.performance_traces,
+        "telemetry categories updated"
+    );
+}
+
+pub fn record_usage(action: &str) {
+    if GATE.settings().usage_metrics {
+        tracing::info!(target: USAGE_TARGET, action, "usage");
+    }
+}
+
+pub fn init(telemetry: TelemetrySettings, data_dir: &Path) -> Result<()> {
+    GATE.configure(telemetry);
+    let log_dir = data_dir.join("logs");
+    if !log_dir.exists() {
+        std::fs::create_dir_all(&log_dir)?;
+    }
+    let file_appender = tracing_appender::rolling::never(&log_dir, "desktop.log");
+    let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
+    FILE_GUARD.set(_guard).ok();
+    // Placeholder for OTLP exporter wiring; only categories the user opted into pass the gate.
+    let exporter = fmt::layer().with_target(true).with_filter(GATE.filter());
+    let subscriber = Registry::default()
+        .with(EnvFilter::from_default_env())
+        .with(fmt::layer().with_writer(non_blocking).with_target(false))
+        .with(exporter);
+    tracing::subscriber::set_global_default(subscriber)?;
+    install_crash_reporter();
+    tracing::info!(
+        crash_reports = telemetry.crash_reports,
+        usage_metrics = telemetry.usage_metrics,
+        performance_traces = telemetry.performance_traces,
+        "logging initialized"
+    );
     Ok(())
 }
 
+fn install_crash_reporter() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if GATE.settings().crash_reports {
+            tracing::error!(target: CRASH_TARGET, panic = %info, "desktop shell crashed");
+        }
+        previous(info);
+    }));
+}
+
 pub async fn tail_logs(data_dir: &Path, limit: usize) -> Result<Vec<String>> {
     let log_dir = data_dir.join("logs");
     let log_path = log_dir.join("desktop.log");
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+pub const UPDATE_FEED_BASE: &str =
+    "https://github.com/data-guardian/data-guardian/releases/latest/download";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+    Nightly,
+}
+
+impl UpdateChannel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+            UpdateChannel::Nightly => "nightly",
+        }
+    }
+
+    pub fn manifest_name(&self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "latest.json",
+            UpdateChannel::Beta => "beta.json",
+            UpdateChannel::Nightly => "nightly.json",
+        }
+    }
+
+    pub fn endpoint(&self, base: &str) -> String {
+        format!("{}/{}", base.trim_end_matches('/'), self.manifest_name())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateSettings {
+    pub channel: UpdateChannel,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub install_id: String,
+}
+
+impl UpdateSettings {
+    pub fn ensure_install_id(&mut self) -> bool {
+        if !self.install_id.is_empty() {
+            return false;
+        }
+        self.install_id = uuid::Uuid::new_v4().to_string();
+        true
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AvailableUpdate {
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pub_date: Option<String>,
+    pub rollout_percentage: u8,
+    pub eligible: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateCheck {
+    pub channel: UpdateChannel,
+    pub current_version: String,
+    pub available: Option<AvailableUpdate>,
+}
+
+pub fn rollout_percentage(manifest: &Value) -> u8 {
+    manifest
+        .pointer("/rollout/percentage")
+        .or_else(|| manifest.get("rollout_percentage"))
+        .and_then(Value::as_u64)
+        .map(|percentage| percentage.min(100) as u8)
+        .unwrap_or(100)
+}
+
+pub fn rollout_bucket(install_id: &str, version: &str) -> u8 {
+    let digest = Sha256::digest(format!("{install_id}:{version}").as_bytes());
+    (u16::from_be_bytes([digest[0], digest[1]]) % 100) as u8
+}
+
+pub fn in_rollout(install_id: &str, version: &str, percentage: u8) -> bool {
+    percentage >= 100 || rollout_bucket(install_id, version) < percentage
+}
@@ -0,0 +1,141 @@
+use anyhow::Result;
+use desktop_app::automation::{
+    self, AutomationRequest, AutomationScope, AutomationServer, AutomationSettings,
+};
+use desktop_app::controller::Controller;
+use dg_core::api::{new_default, DGConfig};
+use dg_core::{KdfParams, KeyProtection};
+use serde_json::{json, Value};
+use tempfile::tempdir;
+use tokio::fs;
+
+const FAST: KdfParams = KdfParams {
+    memory_kib: 1024,
+    iterations: 1,
+    parallelism: 1,
+};
+
+fn request(token: &str, command: &str, params: Value) -> AutomationRequest {
+    AutomationRequest {
+        id: json!(1),
+        token: token.into(),
+        command: command.into(),
+        params,
+    }
+}
+
+#[tokio::test]
+async fn automation_requests_need_the_token_and_an_enabled_scope() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = Controller::new(new_default());
+    controller
+        .boot("dev", temp.path().join("data"), false)
+        .await?;
+    controller.warm_up().await?;
+    let token = automation::load_or_create_token(temp.path()).await?;
+    assert_eq!(automation::load_or_create_token(temp.path()).await?, token);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let path = automation::token_path(temp.path());
+        let mode = fs::metadata(&path).await?.permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    let server = AutomationServer::new(
+        controller.clone(),
+        AutomationSettings {
+            enabled: true,
+            scopes: vec![AutomationScope::Status, AutomationScope::Encrypt],
+        },
+        &token,
+    );
+
+    let forged = server
+        .handle(request("not-the-token", "status", Value::Null))
+        .await;
+    assert!(forged.error.is_some() && forged.result.is_none());
+    let status = server.handle(request(&token, "status", Value::Null)).await;
+    assert_eq!(status.result.expect("status")["ready"], true);
+
+    let source = temp.path().join("notes.txt");
+    fs::write(&source, b"meeting notes").await?;
+    let encrypted = server
+        .handle(request(&token, "encrypt", json!({ "path": source })))
+        .await;
+    let output = encrypted.result.expect("encrypt")["output"]
+        .as_str()
+        .expect("output path")
+        .to_owned();
+    assert!(fs::metadata(&output).await.is_ok());
+
+    let lock = server.handle(request(&token, "lock", Value::Null)).await;
+    assert!(lock.error.expect("lock refused").contains("not enabled"));
+    assert!(controller.ready());
+    assert!(AutomationSettings {
+        enabled: true,
+        scopes: Vec::new(),
+    }
+    .validate()
+    .is_err());
+
+    automation::revoke_token(temp.path()).await?;
+    assert_ne!(automation::load_or_create_token(temp.path()).await?, token);
+    controller.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn the_automation_socket_answers_one_json_line_per_request() -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixStream;
+
+    let temp = tempdir()?;
+    let controller = Controller::new(new_default());
+    controller
+        .boot_with_config(DGConfig {
+            profile: "dev".into(),
+            data_dir: temp.path().join("data"),
+            key_protection: KeyProtection::Passphrase(FAST),
+            ..Default::default()
+        })
+        .await?;
+    controller.unlock_key("correct horse battery").await?;
+    controller.warm_up().await?;
+    let token = automation::load_or_create_token(temp.path()).await?;
+    let endpoint = automation::default_endpoint(temp.path());
+    let socket = temp.path().join("ipc").join("automation.sock");
+    let server = AutomationServer::new(
+        controller.clone(),
+        AutomationSettings {
+            enabled: true,
+            scopes: vec![AutomationScope::Lock],
+        },
+        &token,
+    );
+    let serving = tokio::spawn(server.serve(endpoint));
+    while !socket.exists() {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    let stream = UnixStream::connect(&socket).await?;
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    writer.write_all(b"{ not json\n").await?;
+    let invalid: Value = serde_json::from_str(&lines.next_line().await?.expect("response"))?;
+    assert!(invalid["error"]
+        .as_str()
+        .expect("error")
+        .contains("invalid automation request"));
+
+    let mut line = serde_json::to_vec(&request(&token, "lock", Value::Null))?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    let locked: Value = serde_json::from_str(&lines.next_line().await?.expect("response"))?;
+    assert_eq!(locked["result"]["locked"], true);
+    assert!(controller.ready() && controller.key_locked());
+    controller.unlock_key("correct horse battery").await?;
+    assert!(!controller.key_locked());
+    serving.abort();
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use desktop_app::controller::Controller;
-use dg_core::api::new_default;
+use dg_core::api::{new_default, DGConfig};
 use tempfile::tempdir;
 use tokio::fs;
 
@@ -15,18 +15,47 @@ async fn controller_round_trip_encrypt_decrypt() -> Result<()> {
         .await
         .expect("boot controller");
 
+    controller.generate_identity("alpha").await?;
+
     let source = temp.path().join("message.txt");
     fs::write(&source, b"classified payload").await?;
 
     let envelope_path = controller
-        .encrypt_file(&source, vec!["alpha".into()], vec!["confidential".into()])
+        .encrypt_file(
+            &source,
+            vec!["alpha".into()],
+            vec!["confidential".into()],
+            None,
+        )
         .await?;
     assert!(envelope_path.exists());
 
-    let recovered_path = controller.decrypt_file(&envelope_path).await?;
+    let recovered_path = controller.decrypt_file(&envelope_path, None).await?;
     let contents = fs::read(&recovered_path).await?;
     assert_eq!(contents, b"classified payload");
 
     controller.shutdown().await?;
     Ok(())
 }
+
+#[tokio::test]
+async fn controller_self_test_reports_checks() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = Controller::new(new_default());
+    controller
+        .boot_with_config(DGConfig {
+            profile: "dev".into(),
+            data_dir: temp.path().join("data"),
+            telemetry: false,
+            self_test_on_init: true,
+            ..Default::default()
+        })
+        .await?;
+
+    let report = controller.self_test().await?;
+    assert!(report.passed);
+    assert!(!report.checks.is_empty());
+
+    controller.shutdown().await?;
+    Ok(())
+}
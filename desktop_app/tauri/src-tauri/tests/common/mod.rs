@@ -0,0 +1,50 @@
+// Fixtures shared by the desktop integration tests; each test crate uses only some of them.
+#![allow(dead_code)]
+
+use std::path::Path;
+
+use desktop_app::bridge::Endpoint;
+use desktop_app::process::{ProcessConfig, RestartPolicy};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixListener;
+
+pub fn config(runtime_dir: &Path, socket_activated: bool) -> ProcessConfig {
+    ProcessConfig {
+        binary: runtime_dir.join("bin").join("missing-dg"),
+        runtime_dir: runtime_dir.to_path_buf(),
+        socket_endpoint: Endpoint::Unix(runtime_dir.join("ipc").join("dg-core.sock")),
+        tcp_fallback: None,
+        tcp_token: None,
+        allow_network: false,
+        extra_args: Vec::new(),
+        session_key_file: None,
+        socket_activated,
+        restart: RestartPolicy::default(),
+    }
+}
+
+// Stands in for the activated core, answering every request with a `core.health` report.
+pub fn serve_health(listener: UnixListener) {
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let (read, mut write) = stream.into_split();
+                let mut lines = BufReader::new(read).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let request: Value = serde_json::from_str(&line).unwrap_or_default();
+                    let response = json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "result": { "version": "test", "uptime": 1.5, "profile": null },
+                    });
+                    let mut frame = response.to_string().into_bytes();
+                    frame.push(b'\n');
+                    if write.write_all(&frame).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+}
@@ -0,0 +1,149 @@
+use std::io::Read;
+use std::path::PathBuf;
+
+use desktop_app::controller::{ControllerEvent, RecordedEvent};
+use desktop_app::diagnostics::{create_bundle, DiagnosticSources, Redactor, REDACTED};
+use serde_json::json;
+use tempfile::tempdir;
+
+fn event(seq: u64, event: ControllerEvent) -> RecordedEvent {
+    RecordedEvent {
+        seq,
+        timestamp: seq,
+        summary: event.summary(),
+        verbosity: event.verbosity(),
+        event,
+    }
+}
+
+#[test]
+fn redactor_masks_secrets_emails_and_home_paths() {
+    let redactor = Redactor::new(Some(PathBuf::from("/home/alice")));
+    let (line, count) = redactor.line(
+        "open /home/alice/notes.txt token=abc123 client_secret = \"s3cr3t\" for alice@example.com",
+    );
+    assert_eq!(
+        line,
+        format!("open ~/notes.txt token={REDACTED} client_secret = \"{REDACTED}\" for [EMAIL]")
+    );
+    assert_eq!(count, 4);
+
+    let (line, count) = redactor.line("Authorization: Bearer eyJhbGciOi.payload");
+    assert!(!line.contains("eyJ"), "{line}");
+    assert_eq!(count, 1);
+
+    let (line, count) = redactor.line("user@host has 2 tokens left, key_id=master");
+    assert_eq!(line, "user@host has 2 tokens left, key_id=master");
+    assert_eq!(count, 0);
+
+    let mut settings = json!({
+        "network": { "proxy": "http://proxy", "proxy_password": "hunter2" },
+        "organization": "Example",
+        "groups": ["ops@example.com"],
+        "session_token_ttl": 60,
+        "allow_network": false
+    });
+    assert_eq!(redactor.json(&mut settings), 3);
+    assert_eq!(settings["network"]["proxy_password"], REDACTED);
+    assert_eq!(settings["groups"][0], "[EMAIL]");
+    assert_eq!(settings["session_token_ttl"], REDACTED);
+    assert_eq!(settings["allow_network"], false);
+}
+
+#[tokio::test]
+async fn bundle_collects_redacted_diagnostics_into_a_zip() {
+    let temp = tempdir().expect("tempdir");
+    let data_dir = temp.path().join("data");
+    std::fs::create_dir_all(data_dir.join("logs")).expect("logs dir");
+    std::fs::write(
+        data_dir.join("logs").join("desktop.log"),
+        "INFO boot\nWARN sso refresh failed refresh_token=r-123 for bob@example.com\n",
+    )
+    .expect("log");
+    std::fs::write(data_dir.join("policy.json"), r#"{"default":"deny"}"#).expect("policy");
+    let config_file = temp.path().join("config.toml");
+    std::fs::write(
+        &config_file,
+        "profile = \"dev\"\n[oidc]\nclient_secret = \"abc\"\n",
+    )
+    .expect("config");
+
+    let mut events = vec![event(1, ControllerEvent::Completed("encrypt done".into()))];
+    for seq in 2..60 {
+        events.push(event(
+            seq,
+            ControllerEvent::Error(format!("failed {seq} password=p{seq}")),
+        ));
+    }
+    let bundle = create_bundle(
+        &data_dir,
+        DiagnosticSources {
+            config_file: Some(config_file),
+            settings: Some(json!({ "locale": "vi", "network": { "proxy_token": "t" } })),
+            status: Some(json!({ "ready": true, "backend": "in-process" })),
+            events,
+            locale: Some("vi".into()),
+        },
+        &Redactor::default(),
+    )
+    .await
+    .expect("bundle");
+
+    assert!(bundle.path.starts_with(data_dir.join("diagnostics")));
+    let names = bundle
+        .entries
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "logs/desktop.log",
+            "config/config.toml",
+            "config/settings.json",
+            "policy/policy.json",
+            "version.json",
+            "errors.json",
+            "environment.json",
+        ]
+    );
+    for name in &names {
+        assert!(
+            bundle.summary.iter().any(|line| line.starts_with(name)),
+            "{name} missing from {:?}",
+            bundle.summary
+        );
+    }
+    let log = &bundle.entries[0];
+    assert_eq!(log.redactions, 2);
+
+    let mut archive =
+        zip::ZipArchive::new(std::fs::File::open(&bundle.path).expect("open")).expect("zip");
+    let mut read = |name: &str| {
+        let mut content = String::new();
+        archive
+            .by_name(name)
+            .expect(name)
+            .read_to_string(&mut content)
+            .expect("read");
+        content
+    };
+    let log = read("logs/desktop.log");
+    assert!(log.contains("INFO boot"));
+    assert!(!log.contains("r-123") && !log.contains("bob@"), "{log}");
+    assert!(!read("config/config.toml").contains("abc"));
+    assert!(!read("config/settings.json").contains("\"t\""));
+    assert!(read("policy/policy.json").contains("deny"));
+    assert!(read("version.json").contains(env!("CARGO_PKG_VERSION")));
+    let errors: Vec<serde_json::Value> =
+        serde_json::from_str(&read("errors.json")).expect("errors json");
+    assert_eq!(errors.len(), 50);
+    assert_eq!(errors[0]["seq"], 10);
+    assert!(errors.iter().all(|error| error["kind"] == "error"));
+    assert!(!read("errors.json").contains("p59"));
+    let environment: serde_json::Value =
+        serde_json::from_str(&read("environment.json")).expect("environment json");
+    assert_eq!(environment["os"], std::env::consts::OS);
+    assert_eq!(environment["locale"], "vi");
+    assert!(read("SUMMARY.txt").contains("logs/desktop.log"));
+}
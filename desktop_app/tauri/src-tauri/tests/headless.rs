@@ -0,0 +1,44 @@
+#![cfg(unix)]
+
+mod common;
+
+use anyhow::{anyhow, Result};
+use common::{config, serve_health};
+use desktop_app::headless::{self, ShutdownSignal};
+use tempfile::tempdir;
+use tokio::net::UnixListener;
+
+#[test]
+fn headless_flag_is_read_from_arguments_after_the_binary() {
+    assert!(headless::requested(["desktop_app", "--headless"]));
+    assert!(!headless::requested(["desktop_app"]));
+    assert!(!headless::requested(["--headless"]));
+    assert!(!headless::requested(["desktop_app", "--headless=false"]));
+}
+
+#[tokio::test]
+async fn serve_returns_the_signal_that_stopped_it() -> Result<()> {
+    let temp = tempdir()?;
+    let socket = temp.path().join("ipc").join("dg-core.sock");
+    std::fs::create_dir_all(socket.parent().expect("ipc dir"))?;
+    serve_health(UnixListener::bind(&socket)?);
+
+    let core = headless::start_core(config(temp.path(), true)).await?;
+    let signal = headless::serve(&core, async { Ok(ShutdownSignal::Terminate) }).await?;
+    assert_eq!(signal, ShutdownSignal::Terminate);
+
+    let failed = headless::serve(&core, async { Err(anyhow!("signal handler unavailable")) }).await;
+    assert!(failed.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn start_core_reports_a_missing_runtime() -> Result<()> {
+    let temp = tempdir()?;
+    let err = match headless::start_core(config(temp.path(), false)).await {
+        Ok(_) => panic!("core started without a runtime"),
+        Err(err) => err,
+    };
+    assert!(format!("{err:#}").contains("missing-dg"));
+    Ok(())
+}
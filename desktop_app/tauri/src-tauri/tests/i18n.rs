@@ -0,0 +1,61 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use desktop_app::controller::UserError;
+use desktop_app::i18n::{catalog, LocalizedError, DEFAULT_LOCALE};
+
+#[test]
+fn every_locale_translates_every_code() {
+    let codes: BTreeSet<&str> = [
+        "controller.not_booted",
+        "policy.denied",
+        "file.unavailable",
+        "approval.declined",
+        "approval.timed_out",
+        "engine.policy_denied",
+        "engine.crypto",
+        "engine.config",
+        "engine.read_only",
+        "engine.locked",
+        "engine.internal",
+        "network.offline",
+        "internal",
+    ]
+    .into_iter()
+    .collect();
+    let params = BTreeMap::new();
+    for locale in catalog().locales() {
+        for code in &codes {
+            assert!(
+                catalog().render(locale, code, &params).is_some(),
+                "{locale} is missing {code}"
+            );
+        }
+    }
+}
+
+#[test]
+fn locales_resolve_and_fall_back() {
+    assert_eq!(catalog().resolve("vi_VN.UTF-8"), "vi");
+    assert_eq!(catalog().resolve("EN-gb"), "en");
+    assert_eq!(catalog().resolve("fr-FR"), DEFAULT_LOCALE);
+
+    let denied = UserError::policy_denied("decrypt", "report.dgenc");
+    let english = LocalizedError::localize(denied.clone(), "en-US");
+    assert_eq!(english.code, "policy.denied");
+    assert!(english.message.contains("decrypt on report.dgenc"));
+    let vietnamese = LocalizedError::localize(denied, "vi");
+    assert!(vietnamese.message.contains("report.dgenc"));
+    assert_ne!(vietnamese.message, english.message);
+
+    let unknown = LocalizedError::localize(UserError::new("custom.code", "raw text"), "vi");
+    assert_eq!(unknown.message, "raw text");
+}
+
+#[test]
+fn command_errors_serialize_with_code_and_params() {
+    let error = LocalizedError::from(anyhow::anyhow!("socket closed"));
+    let value = serde_json::to_value(&error).unwrap();
+    assert_eq!(value["code"], "internal");
+    assert_eq!(value["params"]["detail"], "socket closed");
+    assert!(value["message"].as_str().unwrap().contains("socket closed"));
+}
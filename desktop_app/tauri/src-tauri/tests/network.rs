@@ -0,0 +1,77 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use desktop_app::network::{Dispatch, HttpClientFactory, NetworkSettings, ProxyMode};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[tokio::test]
+async fn manual_proxy_receives_outbound_requests() -> Result<()> {
+    let proxy = TcpListener::bind("127.0.0.1:0").await?;
+    let proxy_url = format!("http://{}", proxy.local_addr()?);
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = proxy.accept().await?;
+        let mut buffer = vec![0u8; 4096];
+        let read = stream.read(&mut buffer).await?;
+        stream
+            .write_all(b"HTTP/1.1 204 No Content\r\ncontent-length: 0\r\n\r\n")
+            .await?;
+        Ok::<_, anyhow::Error>(String::from_utf8_lossy(&buffer[..read]).to_string())
+    });
+
+    let network = HttpClientFactory::new(NetworkSettings {
+        proxy: ProxyMode::Manual,
+        proxy_url: Some(proxy_url),
+        ..Default::default()
+    })?;
+    let response = network
+        .client()?
+        .get("http://policy.acme.test/sync")
+        .send()
+        .await?;
+    assert_eq!(response.status().as_u16(), 204);
+    let request = server.await??;
+    assert!(request.starts_with("GET http://policy.acme.test/sync HTTP/1.1"));
+
+    assert!(HttpClientFactory::new(NetworkSettings {
+        proxy: ProxyMode::Manual,
+        ..Default::default()
+    })
+    .is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn offline_mode_defers_work_until_reconnected() -> Result<()> {
+    let offline = NetworkSettings {
+        offline: true,
+        ..Default::default()
+    };
+    let network = HttpClientFactory::new(offline.clone())?;
+    assert!(network.client().is_err());
+
+    let ran = Arc::new(AtomicBool::new(false));
+    let flag = ran.clone();
+    let dispatch = network
+        .run_or_defer("webhook:delivery", move |_client| async move {
+            flag.store(true, Ordering::SeqCst);
+            Ok(())
+        })
+        .await?;
+    assert_eq!(dispatch, Dispatch::Deferred);
+    assert_eq!(
+        network.pending().await,
+        vec!["webhook:delivery".to_string()]
+    );
+    assert!(!ran.load(Ordering::SeqCst));
+
+    assert_eq!(network.apply(offline).await?, 0);
+    assert!(!ran.load(Ordering::SeqCst));
+
+    assert_eq!(network.apply(NetworkSettings::default()).await?, 1);
+    assert!(ran.load(Ordering::SeqCst));
+    assert!(network.pending().await.is_empty());
+    assert!(network.client().is_ok());
+    Ok(())
+}
@@ -0,0 +1,208 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use desktop_app::oidc::{unix_now, OidcClient, OidcConfig};
+use serde_json::{json, Value};
+use tempfile::tempdir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const CLIENT_ID: &str = "data-guardian-desktop";
+
+fn id_token(issuer: &str, groups: &[&str]) -> String {
+    let encode = |value: Value| general_purpose::URL_SAFE_NO_PAD.encode(value.to_string());
+    format!(
+        "{}.{}.signature",
+        encode(json!({ "alg": "RS256" })),
+        encode(json!({
+            "iss": issuer,
+            "aud": [CLIENT_ID],
+            "sub": "0001",
+            "email": "alice@acme.test",
+            "tenant": "acme",
+            "groups": groups,
+            "exp": unix_now() + 3600,
+        }))
+    )
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<(String, String)> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        let text = String::from_utf8_lossy(&buffer).to_string();
+        if let Some(split) = text.find("\r\n\r\n") {
+            let head = &text[..split];
+            let length = head
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().ok())?
+                })
+                .unwrap_or(0);
+            if buffer.len() >= split + 4 + length {
+                let path = head.split_whitespace().nth(1)?.to_string();
+                let body = text[split + 4..split + 4 + length].to_string();
+                return Some((path, body));
+            }
+        }
+    }
+}
+
+async fn serve_provider(listener: TcpListener, issuer: String, polls: Arc<AtomicUsize>) {
+    while let Ok((mut stream, _)) = listener.accept().await {
+        let Some((path, body)) = read_request(&mut stream).await else {
+            continue;
+        };
+        let (status, reply) = match path.as_str() {
+            "/.well-known/openid-configuration" => (
+                200,
+                json!({
+                    "issuer": issuer,
+                    "token_endpoint": format!("{issuer}/token"),
+                    "device_authorization_endpoint": format!("{issuer}/device"),
+                }),
+            ),
+            "/device" => (
+                200,
+                json!({
+                    "device_code": "device-1",
+                    "user_code": "WDJB-MJHT",
+                    "verification_uri": format!("{issuer}/activate"),
+                    "expires_in": 60,
+                    "interval": 1,
+                }),
+            ),
+            "/token" if body.contains("device_code=device-1") => {
+                if polls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    (400, json!({ "error": "authorization_pending" }))
+                } else {
+                    (
+                        200,
+                        json!({
+                            "id_token": id_token(&issuer, &["finance"]),
+                            "refresh_token": "refresh-1",
+                            "expires_in": 3600,
+                        }),
+                    )
+                }
+            }
+            "/token" if body.contains("refresh_token=refresh-1") => (
+                200,
+                json!({
+                    "id_token": id_token(&issuer, &["finance", "admins"]),
+                    "expires_in": 3600,
+                }),
+            ),
+            "/token" => (400, json!({ "error": "invalid_grant" })),
+            _ => (404, json!({ "error": "not_found" })),
+        };
+        let payload = reply.to_string();
+        let response = format!(
+            "HTTP/1.1 {status} OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{payload}",
+            payload.len()
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.shutdown().await;
+    }
+}
+
+fn config(issuer: &str) -> OidcConfig {
+    serde_json::from_value(json!({
+        "issuer": issuer,
+        "client_id": CLIENT_ID,
+        "organization_claim": "tenant",
+    }))
+    .expect("config")
+}
+
+#[tokio::test]
+async fn device_login_maps_claims_and_refreshes() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let issuer = format!("http://{}", listener.local_addr()?);
+    let polls = Arc::new(AtomicUsize::new(0));
+    tokio::spawn(serve_provider(listener, issuer.clone(), polls.clone()));
+
+    let temp = tempdir()?;
+    let client = OidcClient::new(config(&issuer), temp.path().join("oidc.json"))?;
+    let authorization = client.start_device_login().await?;
+    assert_eq!(authorization.user_code, "WDJB-MJHT");
+    assert!(!serde_json::to_string(&authorization)?.contains("device-1"));
+
+    let cached = client.complete_device_login(&authorization).await?;
+    assert_eq!(polls.load(Ordering::SeqCst), 2);
+    assert_eq!(cached.identity.subject(), "user:alice@acme.test");
+    assert_eq!(cached.identity.organization.as_deref(), Some("acme"));
+    assert_eq!(cached.identity.groups, vec!["finance"]);
+    assert_eq!(client.restore().await?, Some(cached.identity.clone()));
+
+    let refreshed = client.refresh(&cached).await?;
+    assert_eq!(refreshed.identity.groups, vec!["finance", "admins"]);
+    let stored = client.load_cached().await?.expect("cached identity");
+    assert_eq!(stored.identity.groups, vec!["finance", "admins"]);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(client.cache_path())?.permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    client.logout().await?;
+    assert!(client.restore().await?.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn expired_identity_is_used_offline_only_within_grace() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let issuer = format!("http://{}", listener.local_addr()?);
+    tokio::spawn(serve_provider(
+        listener,
+        issuer.clone(),
+        Arc::new(AtomicUsize::new(0)),
+    ));
+
+    let temp = tempdir()?;
+    let cache = temp.path().join("oidc.json");
+    let client = OidcClient::new(config(&issuer), cache.clone())?;
+    let now = unix_now();
+    let stale = |offline_until: u64| {
+        json!({
+            "identity": { "user": "alice@acme.test", "groups": ["finance"] },
+            "expires_at": now - 10,
+            "offline_until": offline_until,
+            "refresh_token": "revoked",
+        })
+    };
+
+    tokio::fs::write(&cache, stale(now + 3600).to_string()).await?;
+    let identity = client.restore().await?.expect("offline identity");
+    assert_eq!(identity.subject(), "user:alice@acme.test");
+
+    tokio::fs::write(&cache, stale(now - 1).to_string()).await?;
+    assert!(client.restore().await?.is_none());
+    Ok(())
+}
+
+#[test]
+fn id_tokens_for_other_clients_are_rejected() -> Result<()> {
+    let issuer = "https://login.acme.test";
+    let client = OidcClient::new(config(issuer), "unused.json".into())?;
+    assert!(client.validate_id_token(&id_token(issuer, &[])).is_ok());
+
+    let foreign = OidcClient::new(
+        serde_json::from_value(json!({ "issuer": issuer, "client_id": "other" }))?,
+        "unused.json".into(),
+    )?;
+    assert!(foreign.validate_id_token(&id_token(issuer, &[])).is_err());
+    assert!(OidcClient::new(config("http://login.acme.test"), "unused.json".into()).is_err());
+    Ok(())
+}
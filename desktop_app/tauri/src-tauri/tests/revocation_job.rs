@@ -0,0 +1,70 @@
+use anyhow::Result;
+use desktop_app::controller::{Controller, ControllerEvent, StoredEnvelope};
+use dg_core::api::new_default;
+use serde_json::json;
+use tempfile::tempdir;
+use tokio::fs;
+
+const RETIRED_ESCROW: &str = "3p7bfXt9wbTTW2HC7OQ1Nz+DQ8hbeGdNrfx+FG+IK08=";
+const RETIRED_ESCROW_PEM: &str = "-----BEGIN PUBLIC KEY-----
+MCowBQYDK2VuAyEA3p7bfXt9wbTTW2HC7OQ1Nz+DQ8hbeGdNrfx+FG+IK08=
+-----END PUBLIC KEY-----
+";
+const CURRENT_ESCROW: &str = "Ecq99UZ7Een7EkvwhYkEAH0PpvOC4g9P8HJ9gWOh2Hg=";
+
+#[tokio::test]
+async fn revocation_job_reencrypts_indexed_envelopes() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    fs::create_dir_all(&data_dir).await?;
+    let policy = data_dir.join("policy.json");
+    fs::write(
+        &policy,
+        serde_json::to_vec(&json!({ "escrow": { "public_key": RETIRED_ESCROW } }))?,
+    )
+    .await?;
+
+    let dg = new_default();
+    let controller = Controller::new(dg.clone());
+    controller.boot("dev", data_dir.clone(), false).await?;
+    let source = temp.path().join("report.txt");
+    fs::write(&source, b"annual report").await?;
+    let envelope_path = controller
+        .encrypt_file(&source, vec![], vec![], None)
+        .await?;
+    controller.shutdown().await?;
+
+    fs::write(
+        &policy,
+        serde_json::to_vec(&json!({ "escrow": { "public_key": CURRENT_ESCROW } }))?,
+    )
+    .await?;
+    controller.boot("dev", data_dir.clone(), false).await?;
+    dg.import_public_key(RETIRED_ESCROW_PEM, "retired-escrow")
+        .await?;
+
+    let mut events = controller.subscribe();
+    let job = controller.revoke_recipient("retired-escrow").await?;
+    let retired_id = job.key.id.clone();
+    assert_eq!(job.affected.len(), 1);
+    assert_eq!(job.wait().await?, 1);
+
+    let mut progress = Vec::new();
+    while let Ok(recorded) = events.try_recv() {
+        if let ControllerEvent::Step(message) = recorded.event {
+            progress.push(message);
+        }
+    }
+    assert!(progress
+        .iter()
+        .any(|message| message.starts_with("re-encrypting 1/1")));
+
+    let stored = StoredEnvelope::read(&envelope_path).await?;
+    assert!(!stored.meta["key_wraps"].to_string().contains(&retired_id));
+
+    let recovered = controller.decrypt_file(&envelope_path, None).await?;
+    assert_eq!(fs::read(recovered).await?, b"annual report");
+
+    controller.shutdown().await?;
+    Ok(())
+}
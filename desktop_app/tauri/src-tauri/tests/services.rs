@@ -0,0 +1,70 @@
+use anyhow::Result;
+use desktop_app::controller::Controller;
+use desktop_app::services::{self, ServiceAction, ServiceInvocation};
+use dg_core::api::new_default;
+use tempfile::tempdir;
+use tokio::fs;
+
+#[test]
+fn service_arguments_name_an_action_and_files() -> Result<()> {
+    assert_eq!(services::requested(["desktop_app"])?, None);
+    assert_eq!(
+        services::requested([
+            "desktop_app",
+            "--service",
+            "encryptFiles",
+            "--",
+            "/tmp/a.txt",
+            "/tmp/b.txt"
+        ])?,
+        Some(ServiceInvocation {
+            action: ServiceAction::Encrypt,
+            paths: vec!["/tmp/a.txt".into(), "/tmp/b.txt".into()],
+        })
+    );
+    assert!(services::requested(["desktop_app", "--service", "decrypt"]).is_err());
+    assert!(services::requested(["desktop_app", "--service", "shred", "/tmp/a.txt"]).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn services_encrypt_and_decrypt_each_file_and_report_failures() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = Controller::new(new_default());
+    controller
+        .boot("dev", temp.path().join("data"), false)
+        .await?;
+    let source = temp.path().join("notes.txt");
+    fs::write(&source, b"meeting notes").await?;
+    let missing = temp.path().join("missing.txt");
+
+    let report = services::run(
+        &controller,
+        ServiceInvocation {
+            action: ServiceAction::Encrypt,
+            paths: vec![source.clone(), missing],
+        },
+    )
+    .await;
+    assert_eq!((report.succeeded(), report.failed()), (1, 1));
+    let (title, body) = report.notification();
+    assert!(title.contains("could not encrypt"));
+    assert!(body.starts_with("1 of 2 files processed"));
+    let encrypted = report.outcomes[0].output.clone().expect("encrypted output");
+
+    fs::remove_file(&source).await?;
+    let report = services::run(
+        &controller,
+        ServiceInvocation {
+            action: ServiceAction::Decrypt,
+            paths: vec![encrypted],
+        },
+    )
+    .await;
+    assert_eq!(report.failed(), 0);
+    assert_eq!(report.notification().0, "Decrypted with Data Guardian");
+    let decrypted = report.outcomes[0].output.clone().expect("decrypted output");
+    assert_eq!(fs::read(&decrypted).await?, b"meeting notes");
+    controller.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use desktop_app::bridge::{Capability, SessionAuthority};
+use desktop_app::controller::Controller;
+use dg_core::api::new_default;
+use serde_json::json;
+use tempfile::tempdir;
+use tokio::fs;
+
+const TTL: Duration = Duration::from_secs(60);
+
+#[tokio::test]
+async fn minted_tokens_carry_scopes_and_reject_tampering() -> Result<()> {
+    let temp = tempdir()?;
+    let key_file = temp.path().join("ipc").join("session.key");
+    let authority = SessionAuthority::load_or_create(&key_file).await?;
+    let reloaded = SessionAuthority::load_or_create(&key_file).await?;
+
+    let token = authority.mint(
+        "tray",
+        &[
+            Capability::ReadLogs,
+            Capability::Encrypt,
+            Capability::Encrypt,
+        ],
+        TTL,
+    )?;
+    let claims = reloaded.verify(&token)?;
+    assert_eq!(claims.policy_subject(), "session:tray");
+    assert_eq!(
+        claims.scopes,
+        vec![Capability::Encrypt, Capability::ReadLogs]
+    );
+    assert!(claims.allows(Capability::Encrypt));
+    assert!(!claims.allows(Capability::Decrypt));
+
+    let claims_part = token.split('.').nth(1).expect("claims segment");
+    assert!(String::from_utf8(base64_url_decode(claims_part))?.contains("\"read-logs\""));
+
+    let forged = SessionAuthority::generate().mint("tray", &[Capability::Decrypt], TTL)?;
+    assert!(authority.verify(&forged).is_err());
+    let expired = authority.mint("tray", &[Capability::Encrypt], Duration::ZERO)?;
+    assert!(authority.verify(&expired).is_err());
+    assert!(authority.verify("v1.garbage").is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn minting_is_governed_by_session_policy_subjects() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    fs::create_dir_all(&data_dir).await?;
+    fs::write(
+        data_dir.join("policy.json"),
+        serde_json::to_vec(&json!({
+            "rules": [
+                { "subject": "session:*", "action": "decrypt", "resource": "ipc", "effect": "deny" }
+            ]
+        }))?,
+    )
+    .await?;
+
+    let controller = Controller::new(new_default());
+    controller.boot("dev", data_dir, false).await?;
+    let authority = SessionAuthority::generate();
+
+    let token = controller
+        .mint_session_token(&authority, "tray", &[Capability::Encrypt], TTL)
+        .await?;
+    assert_eq!(authority.verify(&token)?.scopes, vec![Capability::Encrypt]);
+
+    let denied = controller
+        .mint_session_token(
+            &authority,
+            "tray",
+            &[Capability::Encrypt, Capability::Decrypt],
+            TTL,
+        )
+        .await;
+    assert!(denied.is_err());
+
+    controller.shutdown().await?;
+    Ok(())
+}
+
+fn base64_url_decode(segment: &str) -> Vec<u8> {
+    use base64::{engine::general_purpose, Engine as _};
+    general_purpose::URL_SAFE_NO_PAD
+        .decode(segment)
+        .expect("base64url segment")
+}
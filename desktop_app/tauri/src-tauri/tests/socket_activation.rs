@@ -0,0 +1,40 @@
+#![cfg(unix)]
+
+mod common;
+
+use anyhow::Result;
+use common::{config, serve_health};
+use desktop_app::process::ProcessManager;
+use tempfile::tempdir;
+use tokio::net::UnixListener;
+
+#[tokio::test]
+async fn activated_socket_is_used_without_spawning_or_unlinking() -> Result<()> {
+    let temp = tempdir()?;
+    let socket = temp.path().join("ipc").join("dg-core.sock");
+    std::fs::create_dir_all(socket.parent().expect("ipc dir"))?;
+    serve_health(UnixListener::bind(&socket)?);
+
+    let manager = ProcessManager::new(config(temp.path(), true));
+    manager.ensure_running().await?;
+    assert!(socket.exists());
+    let health = manager.health().await?;
+    assert_eq!(health.version, "test");
+    assert!(health.profile.is_none());
+
+    let spawning = ProcessManager::new(config(temp.path(), false));
+    assert!(spawning.ensure_running().await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn a_socket_that_never_answers_is_not_ready() -> Result<()> {
+    let temp = tempdir()?;
+    let socket = temp.path().join("ipc").join("dg-core.sock");
+    std::fs::create_dir_all(socket.parent().expect("ipc dir"))?;
+    let _listener = UnixListener::bind(&socket)?;
+
+    let manager = ProcessManager::new(config(temp.path(), true));
+    assert!(!manager.is_serving().await);
+    Ok(())
+}
@@ -0,0 +1,81 @@
+#![cfg(unix)]
+
+use std::os::unix::fs::PermissionsExt;
+use std::time::Duration;
+
+use anyhow::Result;
+use desktop_app::bridge::Endpoint;
+use desktop_app::process::{ProcessConfig, ProcessManager, RestartPolicy, SupervisorEvent};
+use tempfile::tempdir;
+
+// A core that never binds its socket and exits shortly after starting.
+fn crashing_core(runtime_dir: &std::path::Path) -> Result<ProcessConfig> {
+    let binary = runtime_dir.join("crashing-dg");
+    std::fs::write(&binary, "#!/bin/sh\nsleep 0.2\nexit 3\n")?;
+    std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755))?;
+    Ok(ProcessConfig {
+        binary,
+        runtime_dir: runtime_dir.to_path_buf(),
+        socket_endpoint: Endpoint::Unix(runtime_dir.join("ipc").join("dg-core.sock")),
+        tcp_fallback: None,
+        tcp_token: None,
+        allow_network: false,
+        extra_args: Vec::new(),
+        session_key_file: None,
+        socket_activated: false,
+        restart: RestartPolicy {
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(20),
+            max_failures: 1,
+            stable_after: Duration::from_secs(60),
+        },
+    })
+}
+
+#[test]
+fn backoff_doubles_up_to_the_cap() {
+    let policy = RestartPolicy::default();
+    assert_eq!(policy.backoff(1), Duration::from_millis(500));
+    assert_eq!(policy.backoff(3), Duration::from_secs(2));
+    assert_eq!(policy.backoff(40), Duration::from_secs(30));
+}
+
+#[tokio::test]
+async fn crashes_are_reported_until_the_supervisor_gives_up() -> Result<()> {
+    let temp = tempdir()?;
+    let manager = ProcessManager::new(crashing_core(temp.path())?);
+    let mut events = manager.subscribe();
+    assert!(manager.ensure_running().await.is_err());
+
+    tokio::time::timeout(Duration::from_secs(10), manager.supervise()).await?;
+    assert_eq!(
+        events.recv().await?,
+        SupervisorEvent::CoreDown {
+            code: Some(3),
+            failures: 1,
+            retry_in_ms: Some(10),
+        }
+    );
+    assert_eq!(
+        events.recv().await?,
+        SupervisorEvent::CoreDown {
+            code: Some(3),
+            failures: 2,
+            retry_in_ms: None,
+        }
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn a_requested_stop_ends_supervision_quietly() -> Result<()> {
+    let temp = tempdir()?;
+    let manager = ProcessManager::new(crashing_core(temp.path())?);
+    let mut events = manager.subscribe();
+    manager.ensure_running().await.ok();
+    manager.stop().await?;
+
+    tokio::time::timeout(Duration::from_secs(5), manager.supervise()).await?;
+    assert!(events.try_recv().is_err());
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use desktop_app::settings::UserSettings;
+use desktop_app::telemetry::{
+    TelemetryCategory, TelemetryGate, TelemetrySettings, CRASH_TARGET, USAGE_TARGET,
+};
+use serde_json::json;
+use tracing_subscriber::{fmt, layer::SubscriberExt, Layer, Registry};
+
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Captured {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn categories_parse_from_env_and_settings() {
+    assert_eq!(
+        "true".parse::<TelemetrySettings>().unwrap(),
+        TelemetrySettings::all()
+    );
+    assert!(!"false".parse::<TelemetrySettings>().unwrap().any());
+    let settings: TelemetrySettings = "crash, performance".parse().unwrap();
+    assert!(settings.allows(TelemetryCategory::Crash));
+    assert!(!settings.allows(TelemetryCategory::Usage));
+    assert!(settings.allows(TelemetryCategory::Performance));
+    assert!("crash,location".parse::<TelemetrySettings>().is_err());
+
+    let user: UserSettings =
+        serde_json::from_value(json!({ "telemetry": { "usage_metrics": true } })).unwrap();
+    let effective = user.effective_telemetry(TelemetrySettings::all());
+    assert!(effective.usage_metrics);
+    assert!(!effective.crash_reports);
+    assert_eq!(
+        UserSettings::default().effective_telemetry(TelemetrySettings::all()),
+        TelemetrySettings::all()
+    );
+}
+
+#[test]
+fn disabled_categories_never_reach_the_exporter() {
+    let gate = TelemetryGate::new(TelemetrySettings {
+        crash_reports: true,
+        ..Default::default()
+    });
+    let exported = Captured::default();
+    let writer = exported.clone();
+    let subscriber = Registry::default().with(
+        fmt::layer()
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .with_filter(gate.filter()),
+    );
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::error!(target: CRASH_TARGET, "crash-report");
+        tracing::info!(target: USAGE_TARGET, action = "encrypt_file", "usage-metric");
+        tracing::info_span!("performance-trace").in_scope(|| {
+            tracing::info!("local-only");
+        });
+        gate.configure(TelemetrySettings {
+            usage_metrics: true,
+            ..Default::default()
+        });
+        tracing::info!(target: USAGE_TARGET, action = "decrypt_file", "usage-after-opt-in");
+        tracing::error!(target: CRASH_TARGET, "crash-after-opt-out");
+    });
+
+    let text = exported.text();
+    assert!(text.contains("crash-report"));
+    assert!(!text.contains("usage-metric"));
+    assert!(!text.contains("performance-trace"));
+    assert!(!text.contains("local-only"));
+    assert!(text.contains("usage-after-opt-in"));
+    assert!(!text.contains("crash-after-opt-out"));
+}
@@ -0,0 +1,50 @@
+use desktop_app::settings::UserSettings;
+use desktop_app::updates::{
+    in_rollout, rollout_bucket, rollout_percentage, UpdateChannel, UPDATE_FEED_BASE,
+};
+use serde_json::json;
+
+#[test]
+fn channels_select_their_own_manifest() {
+    assert_eq!(
+        UpdateChannel::Stable.endpoint(UPDATE_FEED_BASE),
+        format!("{UPDATE_FEED_BASE}/latest.json")
+    );
+    assert_eq!(
+        UpdateChannel::Nightly.endpoint("https://updates.acme.test/feed/"),
+        "https://updates.acme.test/feed/nightly.json"
+    );
+
+    let settings: UserSettings =
+        serde_json::from_value(json!({ "updates": { "channel": "beta" } })).unwrap();
+    assert_eq!(settings.updates.channel, UpdateChannel::Beta);
+    let mut updates = settings.updates;
+    assert!(updates.ensure_install_id());
+    assert!(!updates.ensure_install_id());
+}
+
+#[test]
+fn staged_rollouts_are_stable_per_install() {
+    assert_eq!(rollout_percentage(&json!({ "version": "1.2.0" })), 100);
+    assert_eq!(
+        rollout_percentage(&json!({ "rollout": { "percentage": 25 } })),
+        25
+    );
+    assert_eq!(
+        rollout_percentage(&json!({ "rollout_percentage": 400 })),
+        100
+    );
+
+    let install = "0b0e7a52-5f3c-4d8e-9a51-6f1d2c3b4a59";
+    assert_eq!(
+        rollout_bucket(install, "1.2.0"),
+        rollout_bucket(install, "1.2.0")
+    );
+    assert!(in_rollout(install, "1.2.0", 100));
+    assert!(!in_rollout(install, "1.2.0", 0));
+
+    let eligible = (0..1000)
+        .filter(|install| in_rollout(&format!("install-{install}"), "1.2.0", 25))
+        .count();
+    assert!((180..320).contains(&eligible), "{eligible}");
+}
@@ -30,7 +30,7 @@ async fn policy_denies_encryption_when_rule_matches() -> Result<()> {
     fs::write(&source, b"blocked").await?;
 
     let result = controller
-        .encrypt_file(&source, vec!["beta".into()], vec!["internal".into()])
+        .encrypt_file(&source, vec!["beta".into()], vec!["internal".into()], None)
         .await;
     assert!(result.is_err(), "policy should block encryption");
 
@@ -5,7 +5,15 @@ import type { UnlistenFn } from '@tauri-apps/api/event'
 import { Command } from '@tauri-apps/plugin-shell'
 import type { Child } from '@tauri-apps/plugin-shell'
 import './App.css'
-import { decryptFile, encryptFile } from './api/dg'
+import {
+  appStatus,
+  decryptFile,
+  encryptFile,
+  errorMessage,
+  replayEvents,
+  type CoreStatusEvent,
+  type RecordedEvent,
+} from './api/dg'
 
 type LogLevel = 'debug' | 'info' | 'warn' | 'error'
 
@@ -45,11 +53,31 @@ type FileJob = {
 
 type ControllerMessage = {
   id: string
-  kind: 'progress' | 'error'
+  kind: RecordedEvent['kind']
   message: string
+  summary: string
   timestamp: string
 }
 
+type CoreProgress = {
+  opId: string
+  method?: string
+  stage: string
+  bytes: number
+  totalBytes?: number
+  percent?: number
+  updatedAt: string
+}
+
+type CoreProgressPayload = {
+  op_id: string
+  method?: string | null
+  stage: string
+  bytes: number
+  total_bytes?: number | null
+  percent?: number | null
+}
+
 type CliLogEntry = {
   id: string
   stream: 'stdout' | 'stderr'
@@ -342,6 +370,8 @@ function App() {
   const [scanPath, setScanPath] = useState('/var/workspace')
   const [logFilter, setLogFilter] = useState<'all' | LogLevel>('all')
   const [controllerMessages, setControllerMessages] = useState<ControllerMessage[]>([])
+  const [engineReady, setEngineReady] = useState(false)
+  const [coreProgress, setCoreProgress] = useState<CoreProgress[]>([])
 
   const [isEncryptDialogOpen, setIsEncryptDialogOpen] = useState(false)
   const [encryptJobs, setEncryptJobs] = useState<FileJob[]>([])
@@ -479,29 +509,50 @@ function App() {
     const subscribe = async () => {
       try {
         unlisten = await listen('dg://controller', (event) => {
-          const payload = event.payload as { kind: 'progress' | 'error'; message: string }
+          const payload = event.payload as RecordedEvent
           setControllerMessages((previous) => {
             const next = [
               ...previous,
               {
-                id: createId(),
+                id: `event-${payload.seq}`,
                 kind: payload.kind,
                 message: payload.message,
-                timestamp: new Date().toISOString(),
+                summary: payload.summary,
+                timestamp: new Date(payload.timestamp * 1000).toISOString(),
               },
             ]
             return next.slice(-100)
           })
+          if (payload.kind === 'ready') {
+            setEngineReady(true)
+          }
           if (payload.kind === 'error') {
             addToast(payload.message, 'error')
             appendLog({ level: 'error', message: payload.message, context: 'ui' })
           }
         })
+        const status = await appStatus()
+        if (status.ready) {
+          setEngineReady(true)
+        }
+        const history = await replayEvents()
+        setControllerMessages((previous) =>
+          [
+            ...history.map((entry) => ({
+              id: `event-${entry.seq}`,
+              kind: entry.kind,
+              message: entry.message,
+              summary: entry.summary,
+              timestamp: new Date(entry.timestamp * 1000).toISOString(),
+            })),
+            ...previous.filter((message) => !history.some((entry) => `event-${entry.seq}` === message.id)),
+          ].slice(-100),
+        )
       } catch (error) {
         console.error(error)
         appendLog({
           level: 'warn',
-          message: `Failed to subscribe to controller events: ${String(error)}`,
+          message: `Failed to subscribe to controller events: ${errorMessage(error)}`,
           context: 'ui',
         })
       }
@@ -516,6 +567,78 @@ function App() {
     }
   }, [addToast, appendLog])
 
+  useEffect(() => {
+    let unlisten: UnlistenFn | undefined
+
+    const subscribe = async () => {
+      try {
+        unlisten = await listen('dg://core-status', (event) => {
+          const payload = event.payload as CoreStatusEvent
+          if (payload.kind === 'core_restarted') {
+            addToast('DG Core restarted', 'success')
+            appendLog({ level: 'info', message: `DG Core restarted (attempt ${payload.attempt})`, context: 'core' })
+            return
+          }
+          const message =
+            payload.retry_in_ms == null
+              ? `DG Core stopped after ${payload.failures} failures; restart the app to try again`
+              : `DG Core stopped; restarting in ${Math.ceil(payload.retry_in_ms / 1000)}s`
+          addToast(message, payload.retry_in_ms == null ? 'error' : 'warning')
+          appendLog({ level: 'error', message, context: 'core' })
+        })
+      } catch (error) {
+        console.error(error)
+      }
+    }
+
+    subscribe()
+
+    return () => {
+      if (unlisten) {
+        unlisten()
+      }
+    }
+  }, [addToast, appendLog])
+
+  useEffect(() => {
+    let unlisten: UnlistenFn | undefined
+
+    const subscribe = async () => {
+      try {
+        unlisten = await listen('dg://progress', (event) => {
+          const payload = event.payload as CoreProgressPayload
+          const update: CoreProgress = {
+            opId: payload.op_id,
+            method: payload.method ?? undefined,
+            stage: payload.stage,
+            bytes: payload.bytes,
+            totalBytes: payload.total_bytes ?? undefined,
+            percent: payload.percent ?? undefined,
+            updatedAt: new Date().toISOString(),
+          }
+          setCoreProgress((previous) =>
+            [update, ...previous.filter((entry) => entry.opId !== update.opId)].slice(0, 5),
+          )
+        })
+      } catch (error) {
+        console.error(error)
+        appendLog({
+          level: 'warn',
+          message: `Failed to subscribe to core progress: ${errorMessage(error)}`,
+          context: 'ui',
+        })
+      }
+    }
+
+    subscribe()
+
+    return () => {
+      if (unlisten) {
+        unlisten()
+      }
+    }
+  }, [appendLog])
+
   useEffect(() => {
     return () => {
       const child = cliChildRef.current
@@ -728,7 +851,7 @@ function App() {
       setEncryptJobs(normalized.map((path) => ({ id: createId(), path, status: 'idle' })))
     } catch (error) {
       addToast('Unable to select files for encryption', 'error')
-      appendLog({ level: 'error', message: `Failed to open encrypt selector: ${String(error)}`, context: 'ui' })
+      appendLog({ level: 'error', message: `Failed to open encrypt selector: ${errorMessage(error)}`, context: 'ui' })
     }
   }, [addToast, appendLog])
 
@@ -744,7 +867,7 @@ function App() {
       setDecryptJobs(normalized.map((path) => ({ id: createId(), path, status: 'idle' })))
     } catch (error) {
       addToast('Unable to select files for decryption', 'error')
-      appendLog({ level: 'error', message: `Failed to open decrypt selector: ${String(error)}`, context: 'ui' })
+      appendLog({ level: 'error', message: `Failed to open decrypt selector: ${errorMessage(error)}`, context: 'ui' })
     }
   }, [addToast, appendLog])
 
@@ -757,7 +880,7 @@ function App() {
       }
     } catch (error) {
       addToast('Unable to select an output directory', 'error')
-      appendLog({ level: 'error', message: `Failed to select encrypt output directory: ${String(error)}`, context: 'ui' })
+      appendLog({ level: 'error', message: `Failed to select encrypt output directory: ${errorMessage(error)}`, context: 'ui' })
     }
   }, [addToast, appendLog])
 
@@ -770,7 +893,7 @@ function App() {
       }
     } catch (error) {
       addToast('Unable to select an output directory', 'error')
-      appendLog({ level: 'error', message: `Failed to select decrypt output directory: ${String(error)}`, context: 'ui' })
+      appendLog({ level: 'error', message: `Failed to select decrypt output directory: ${errorMessage(error)}`, context: 'ui' })
     }
   }, [addToast, appendLog])
 
@@ -780,7 +903,7 @@ function App() {
         await openSystemPath(path)
       } catch (error) {
         addToast('Unable to open the requested path', 'error')
-        appendLog({ level: 'error', message: `Failed to open path ${path}: ${String(error)}`, context: 'ui' })
+        appendLog({ level: 'error', message: `Failed to open path ${path}: ${errorMessage(error)}`, context: 'ui' })
       }
     },
     [addToast, appendLog],
@@ -836,7 +959,7 @@ function App() {
         )
 
         try {
-          const output = await encryptFile({
+          const { output, defaults } = await encryptFile({
             path: job.path,
             recipients,
             labels: labels.length > 0 ? labels : undefined,
@@ -860,8 +983,19 @@ function App() {
             message: `Encrypted ${job.path} to ${output}`,
             context: 'ui',
           })
+          if (defaults) {
+            const applied = [
+              defaults.recipients?.length ? `recipients ${defaults.recipients.join(', ')}` : null,
+              defaults.labels?.length ? `labels ${defaults.labels.join(', ')}` : null,
+            ].filter(Boolean)
+            appendLog({
+              level: 'info',
+              message: `Applied profile defaults to ${job.path}: ${applied.join('; ')}`,
+              context: 'ui',
+            })
+          }
         } catch (error) {
-          const message = error instanceof Error ? error.message : String(error)
+          const message = errorMessage(error)
           setEncryptJobs((previous) =>
             previous.map((item) =>
               item.id === job.id
@@ -966,7 +1100,7 @@ function App() {
             context: 'ui',
           })
         } catch (error) {
-          const message = error instanceof Error ? error.message : String(error)
+          const message = errorMessage(error)
           setDecryptJobs((previous) =>
             previous.map((item) =>
               item.id === job.id
@@ -1106,7 +1240,7 @@ function App() {
         const child = await command.spawn()
         cliChildRef.current = child
       } catch (error) {
-        const message = error instanceof Error ? error.message : String(error)
+        const message = errorMessage(error)
         setCliError(message)
         setCliIsRunning(false)
         cliChildRef.current = null
@@ -1285,11 +1419,13 @@ function App() {
             </div>
             <div className="controller-card">
               <h3>Controller activity</h3>
-              <ul className="controller-feed">
+              <ul className="controller-feed" aria-live="polite">
                 {recentControllerMessages.map((message) => (
                   <li key={message.id} className={message.kind}>
                     <span className="timestamp">{formatTimestamp(message.timestamp)}</span>
-                    <span className="message">{message.message}</span>
+                    <span className="message" title={message.message}>
+                      {message.summary || message.message}
+                    </span>
                   </li>
                 ))}
                 {recentControllerMessages.length === 0 && (
@@ -1297,6 +1433,29 @@ function App() {
                 )}
               </ul>
             </div>
+            <div className="controller-card">
+              <h3>Core operations</h3>
+              <ul className="core-progress">
+                {coreProgress.map((entry) => (
+                  <li key={entry.opId} className={entry.stage === 'done' ? 'done' : ''}>
+                    <span className="message">
+                      {entry.method ?? 'operation'} · {entry.stage}
+                    </span>
+                    <progress
+                      max={100}
+                      value={entry.percent ?? undefined}
+                      aria-label={`${entry.method ?? 'operation'} ${entry.stage}`}
+                    />
+                    <span className="timestamp">
+                      {entry.percent !== undefined
+                        ? `${entry.percent.toFixed(0)}%`
+                        : `${entry.bytes} bytes`}
+                    </span>
+                  </li>
+                ))}
+                {coreProgress.length === 0 && <li className="empty">No core operations in progress.</li>}
+              </ul>
+            </div>
             <div className="helper-card">
               <h3>Need something fast?</h3>
               <p>
@@ -1571,11 +1730,13 @@ function App() {
 
               <div className="field-group">
                 <h4>Recent progress</h4>
-                <ul className="controller-feed">
+                <ul className="controller-feed" aria-live="polite">
                   {recentControllerMessages.map((message) => (
                     <li key={`${message.id}-encrypt`} className={message.kind}>
                       <span className="timestamp">{formatTimestamp(message.timestamp)}</span>
-                      <span className="message">{message.message}</span>
+                      <span className="message" title={message.message}>
+                        {message.summary || message.message}
+                      </span>
                     </li>
                   ))}
                   {recentControllerMessages.length === 0 && (
@@ -1592,7 +1753,8 @@ function App() {
                   type="submit"
                   className="primary"
                   disabled=
-                    {activeOperation !== null ||
+                    {!engineReady ||
+                      activeOperation !== null ||
                       encryptJobs.length === 0 ||
                       encryptRecipients.length === 0 ||
                       (encryptOutputMode === 'directory' && !encryptOutputDirectory)}
@@ -1670,11 +1832,13 @@ function App() {
 
               <div className="field-group">
                 <h4>Recent progress</h4>
-                <ul className="controller-feed">
+                <ul className="controller-feed" aria-live="polite">
                   {recentControllerMessages.map((message) => (
                     <li key={`${message.id}-decrypt`} className={message.kind}>
                       <span className="timestamp">{formatTimestamp(message.timestamp)}</span>
-                      <span className="message">{message.message}</span>
+                      <span className="message" title={message.message}>
+                        {message.summary || message.message}
+                      </span>
                     </li>
                   ))}
                   {recentControllerMessages.length === 0 && (
@@ -1691,7 +1855,8 @@ function App() {
                   type="submit"
                   className="primary"
                   disabled=
-                    {activeOperation !== null ||
+                    {!engineReady ||
+                      activeOperation !== null ||
                       decryptJobs.length === 0 ||
                       (decryptOutputMode === 'directory' && !decryptOutputDirectory)}
                 >
@@ -5,25 +5,597 @@ export type EncryptReq = {
   recipients: string[]
   labels?: string[]
   outDir?: string
+  readReceipts?: boolean
+  preview?: boolean
+  metadata?: Record<string, unknown>
+  retention?: Retention
+  chunking?: ChunkingParams
+  previous?: string
+  // Overwrite and delete the plaintext after a successful encryption.
+  shredSource?: boolean
+}
+
+export type ChunkingParams = {
+  min_size: number
+  avg_size: number
+  max_size: number
+}
+
+export type DedupStats = {
+  chunks: number
+  reused_chunks: number
+  bytes: number
+  reused_bytes: number
+}
+
+export type Retention = {
+  class?: string
+  keep_until?: number
+  dispose_after?: number
+  hold?: { reason: string; placed_by: string; placed_at: number }
+}
+
+export type AppliedDefaults = {
+  recipients?: string[]
+  labels?: string[]
+}
+
+export type EncryptResult = {
+  output: string
+  defaults?: AppliedDefaults
+  dedup?: DedupStats
+  // Present when `shredSource` was set and the plaintext was overwritten and removed.
+  shredded?: { path: string; bytes: number; passes: number }
 }
 
 export type DecryptReq = {
   path: string
   outDir?: string
+  grant?: string
+}
+
+export type CommandError = {
+  code: string
+  params: Record<string, string>
+  message: string
 }
 
-export async function encryptFile(req: EncryptReq): Promise<string> {
-  return invoke<string>('encrypt_file', {
+export function errorMessage(error: unknown): string {
+  if (error instanceof Error) {
+    return error.message
+  }
+  if (error && typeof error === 'object' && 'message' in error) {
+    return String((error as CommandError).message)
+  }
+  return String(error)
+}
+
+export async function setLocale(locale: string): Promise<string> {
+  return invoke<string>('set_locale', { locale })
+}
+
+export async function availableLocales(): Promise<string[]> {
+  return invoke<string[]>('available_locales')
+}
+
+export async function encryptFile(req: EncryptReq): Promise<EncryptResult> {
+  return invoke<EncryptResult>('encrypt_file', {
     path: req.path,
     recipients: req.recipients,
     labels: req.labels,
     out_dir: req.outDir,
+    read_receipts: req.readReceipts,
+    preview: req.preview,
+    metadata: req.metadata,
+    retention: req.retention,
+    chunking: req.chunking,
+    previous: req.previous,
+    shred_source: req.shredSource,
   })
 }
 
+export type DirectoryEncryptReport = {
+  root: string
+  scanned: number
+  encrypted: { source: string; output: string }[]
+  failures: { path: string; error: string }[]
+}
+
+export async function encryptDirectory(
+  dir: string,
+  recipients: string[],
+  labels?: string[],
+  outDir?: string,
+): Promise<DirectoryEncryptReport> {
+  return invoke<DirectoryEncryptReport>('encrypt_directory', {
+    dir,
+    recipients,
+    labels,
+    out_dir: outDir,
+  })
+}
+
+export async function getPreview(path: string): Promise<string | null> {
+  return invoke<string | null>('get_preview', { path })
+}
+
+export type RangeVerification = {
+  offset: number
+  len: number
+  first_chunk: number
+  chunks: number
+  root: string | null
+}
+
+export async function verifyRange(
+  path: string,
+  offset: number,
+  len: number,
+): Promise<RangeVerification> {
+  return invoke<RangeVerification>('verify_range', { path, offset, len })
+}
+
+// Resolves to the plaintext bytes `offset..offset + len`, base64-encoded.
+export async function decryptRange(path: string, offset: number, len: number): Promise<string> {
+  return invoke<string>('decrypt_range', { path, offset, len })
+}
+
+export type AppendReport = {
+  path: string
+  created: boolean
+  appended_bytes: number
+  records: number
+  plaintext_len: number
+  discarded_bytes: number
+}
+
+// `data` is base64-encoded; the envelope is created in append mode if it does not exist yet.
+export async function appendToEnvelope(path: string, data: string): Promise<AppendReport> {
+  return invoke<AppendReport>('append_to_envelope', { path, data })
+}
+
+export type Reconciliation = {
+  roots: string[]
+  scanned: number
+  unchanged: number
+  added: string[]
+  moved: { from: string; to: string }[]
+  removed: string[]
+}
+
+export async function setIndexLocations(locations: string[]): Promise<string[]> {
+  return invoke<string[]>('set_index_locations', { locations })
+}
+
+export async function reconcileIndex(): Promise<Reconciliation> {
+  return invoke<Reconciliation>('reconcile_index')
+}
+
+export type IndexedEnvelope = {
+  path: string
+  key_ids: string[]
+  labels: string[]
+  updated_at: number
+  metadata?: Record<string, unknown>
+}
+
+export async function searchEnvelopes(filters: string[]): Promise<IndexedEnvelope[]> {
+  return invoke<IndexedEnvelope[]>('search_envelopes', { filters })
+}
+
+// Times are unix seconds and match when an envelope was encrypted.
+export type ReportFilter = {
+  labels?: string[]
+  metadata?: string[]
+  encrypted_by?: string
+  since?: number
+  until?: number
+}
+
+export type ReportFormat = 'csv' | 'json'
+
+export type ExportedReport = {
+  path: string
+  format: ReportFormat
+  envelopes: number
+}
+
+export async function exportReport(
+  path: string,
+  format: ReportFormat,
+  filter: ReportFilter = {},
+): Promise<ExportedReport> {
+  return invoke<ExportedReport>('export_report', { filter, format, path })
+}
+
+export async function shredEnvelope(path: string): Promise<void> {
+  return invoke<void>('shred_envelope', { path })
+}
+
+export async function placeLegalHold(path: string, reason: string): Promise<Retention> {
+  return invoke<Retention>('place_legal_hold', { path, reason })
+}
+
+export async function releaseLegalHold(path: string): Promise<Retention> {
+  return invoke<Retention>('release_legal_hold', { path })
+}
+
+export type KeyMetadata = {
+  id: string
+  label: string
+  algorithm: 'x25519' | 'ed25519'
+  public_key: string
+  provenance: { source: 'generated' | 'imported'; format?: string; recorded_at: number }
+  revoked_at?: number
+  replaces?: string
+  purpose?: KeyPurpose
+  certificate?: { root: string; issued_at: number; signature: string }
+}
+
+export type KeyPurpose = 'root' | 'encryption' | 'signing' | 'escrow'
+
+export type KeyHierarchy = {
+  root: KeyMetadata
+  subkeys: KeyMetadata[]
+}
+
+export async function generateIdentity(label: string): Promise<KeyMetadata> {
+  return invoke<KeyMetadata>('generate_identity', { label })
+}
+
+export async function generateKeyHierarchy(label: string): Promise<KeyHierarchy> {
+  return invoke<KeyHierarchy>('generate_key_hierarchy', { label })
+}
+
+export async function listIdentities(): Promise<KeyMetadata[]> {
+  return invoke<KeyMetadata[]>('list_identities')
+}
+
+export type MaxKeyAge = {
+  age_secs: number
+  warn_before_secs: number
+  auto_rotate: boolean
+}
+
+export type KeyExpiry = {
+  key_id: string
+  label: string
+  algorithm: 'x25519' | 'ed25519'
+  created_at: number
+  expires_at: number
+  state: 'current' | 'expiring' | 'expired'
+  rotatable: boolean
+}
+
+// Empty unless `max_key_age` is configured.
+export type KeyExpiryReport = {
+  policy?: MaxKeyAge
+  keys: KeyExpiry[]
+}
+
+export async function keyExpiry(): Promise<KeyExpiryReport> {
+  return invoke<KeyExpiryReport>('key_expiry')
+}
+
+// Resolves to the number of envelopes being re-encrypted to the replacement key.
+export async function rotateKey(keyId: string): Promise<number> {
+  return invoke<number>('rotate_key', { keyId })
+}
+
+export type PolicyCacheStats = {
+  entries: number
+  hits: number
+  misses: number
+  stale_hits: number
+  evictions: number
+  invalidations: number
+}
+
+export type HookOutcome = {
+  rule: number
+  hook: string
+  result: 'approved' | 'declined' | 'timed_out' | 'failed'
+  reason?: string
+  elapsed_ms: number
+}
+
+export type DecisionExplanation = {
+  decision: 'allow' | 'deny'
+  source: 'policy' | 'cache' | 'stale_cache' | 'fallback'
+  rule: {
+    index: number
+    subject: string
+    action: string
+    resource: string
+    when?: { owner_sid?: string; zone?: string; drive_type?: string }
+    hook?: string
+  } | null
+  hooks?: HookOutcome[]
+  fail_mode?: 'open' | 'closed'
+}
+
+export async function explainDecision(action: string, resource: string): Promise<DecisionExplanation> {
+  return invoke<DecisionExplanation>('explain_decision', { action, resource })
+}
+
+export async function reloadPolicy(): Promise<PolicyCacheStats> {
+  return invoke<PolicyCacheStats>('reload_policy')
+}
+
+export async function policyCacheStats(): Promise<PolicyCacheStats> {
+  return invoke<PolicyCacheStats>('policy_cache_stats')
+}
+
+export type AuditFilter = {
+  subject?: string
+  action?: string
+  resource?: string
+  effect?: 'allow' | 'deny'
+  since?: number
+  until?: number
+  limit?: number
+}
+
+export type AuditEvent = {
+  timestamp: number
+  subject: string
+  action: string
+  resource: string
+  effect: string
+  details?: unknown
+  device?: string
+}
+
+export async function queryAudit(filter: AuditFilter = {}): Promise<AuditEvent[]> {
+  return invoke<AuditEvent[]>('query_audit', { filter })
+}
+
 export async function decryptFile(req: DecryptReq): Promise<string> {
   return invoke<string>('decrypt_file', {
     path: req.path,
     out_dir: req.outDir,
+    grant: req.grant,
   })
 }
+
+// Writes a `.dgreq` file to carry to an air-gapped machine holding one of the envelope's keys.
+export async function exportDecryptRequest(path: string, outDir?: string): Promise<string> {
+  return invoke<string>('export_decrypt_request', { path, out_dir: outDir })
+}
+
+// Decrypts with the `.dgresp` file the air-gapped machine wrote for this machine's request.
+export async function completeDecryptRequest(
+  path: string,
+  response: string,
+  outDir?: string,
+): Promise<string> {
+  return invoke<string>('complete_decrypt_request', { path, response, out_dir: outDir })
+}
+
+export type ActiveOperation = {
+  id: number
+  kind: string
+  target: string
+  started_at: number
+}
+
+// Emitted as `dg://operation` when an encryption or decryption starts.
+export type StartedOperation = {
+  id: number
+  kind: 'encrypt' | 'decrypt'
+  path: string
+}
+
+// Emitted as `dg://progress` while an operation reads, encrypts or decrypts, and writes.
+export type ProgressUpdate = {
+  op_id: string
+  method?: string
+  stage: string
+  bytes: number
+  total_bytes?: number
+  percent?: number
+}
+
+// What the core reports from `core.health`. `profile` is absent until its engine is initialised,
+// and always from the Python daemon, which runs no engine.
+export type CoreHealth = {
+  version: string
+  uptime: number
+  profile?: { profile: string; data_dir: string; read_only: boolean; locked: boolean } | null
+}
+
+export async function coreStatus(): Promise<CoreHealth> {
+  return invoke<CoreHealth>('core_status')
+}
+
+// Emitted as `dg://core-status` when the core the app started exits or is restarted.
+// `retry_in_ms` is absent once the app has given up restarting it.
+export type CoreStatusEvent =
+  | { kind: 'core_down'; code?: number | null; failures: number; retry_in_ms?: number | null }
+  | { kind: 'core_restarted'; attempt: number }
+
+// Emitted as `dg://core-notification` for each notification on a `coreSubscribe` stream.
+export type CoreNotification = {
+  subscription: string
+  notification: { jsonrpc?: string; method: string; params?: unknown }
+}
+
+export async function coreSubscribe(method: string, params?: Record<string, unknown>): Promise<string> {
+  return invoke<string>('core_subscribe', { method, params })
+}
+
+export async function coreUnsubscribe(id: string): Promise<boolean> {
+  return invoke<boolean>('core_unsubscribe', { id })
+}
+
+export async function activeOperations(): Promise<ActiveOperation[]> {
+  return invoke<ActiveOperation[]>('active_operations')
+}
+
+export async function cancelOperation(id: number): Promise<ActiveOperation> {
+  return invoke<ActiveOperation>('cancel_operation', { id })
+}
+
+export type EventVerbosity = 'terse' | 'normal' | 'verbose'
+
+export type RecordedEvent = {
+  seq: number
+  timestamp: number
+  kind: 'progress' | 'error' | 'step' | 'completed' | 'ready' | 'cancelled'
+  message: string
+  summary: string
+  verbosity: EventVerbosity
+}
+
+export type AppStatus = {
+  ready: boolean
+  read_only: boolean
+  key_locked: boolean
+}
+
+export async function appStatus(): Promise<AppStatus> {
+  return invoke<AppStatus>('app_status')
+}
+
+export async function lockMasterKey(): Promise<void> {
+  return invoke<void>('lock_master_key')
+}
+
+export async function unlockMasterKey(passphrase: string): Promise<void> {
+  return invoke<void>('unlock_master_key', { passphrase })
+}
+
+export async function replayEvents(since?: number): Promise<RecordedEvent[]> {
+  return invoke<RecordedEvent[]>('replay_events', { since })
+}
+
+export async function setEventVerbosity(verbosity: EventVerbosity): Promise<EventVerbosity> {
+  return invoke<EventVerbosity>('set_event_verbosity', { verbosity })
+}
+
+export type QuarantineHandling = 'preserve' | 'strip'
+
+export async function setQuarantineHandling(handling: QuarantineHandling): Promise<QuarantineHandling> {
+  return invoke<QuarantineHandling>('set_quarantine_handling', { handling })
+}
+
+export type WatchSettings = {
+  debounce_ms: number
+  ignore: string[]
+  poll: boolean
+}
+
+export async function setWatchSettings(watch: WatchSettings): Promise<WatchSettings> {
+  return invoke<WatchSettings>('set_watch_settings', { watch })
+}
+
+export type WalkSettings = {
+  exclude: string[]
+  include_hidden: boolean
+  follow_links: boolean
+  parallel_files?: number
+}
+
+export async function setWalkSettings(walk: WalkSettings): Promise<WalkSettings> {
+  return invoke<WalkSettings>('set_walk_settings', { walk })
+}
+
+export type ApprovalSettings = {
+  relay_url: string
+  timeout_secs: number
+}
+
+export async function setDeviceApproval(approval: ApprovalSettings | null): Promise<ApprovalSettings | null> {
+  return invoke<ApprovalSettings | null>('set_device_approval', { approval })
+}
+
+export type PairingOffer = {
+  device_id: string
+  channel_key: string
+  code: string
+  expires_at: number
+}
+
+export type PairingCode = {
+  offer: PairingOffer
+  // Shown as a QR code for the phone to scan.
+  uri: string
+}
+
+export type PairedDevice = {
+  id: string
+  name: string
+  signing_key: string
+  paired_at: number
+}
+
+export async function startDevicePairing(): Promise<PairingCode> {
+  return invoke<PairingCode>('start_device_pairing')
+}
+
+// Resolves once the phone that scanned the code has answered through the relay.
+export async function awaitDevicePairing(): Promise<PairedDevice> {
+  return invoke<PairedDevice>('await_device_pairing')
+}
+
+export async function pairedDevices(): Promise<PairedDevice[]> {
+  return invoke<PairedDevice[]>('paired_devices')
+}
+
+export async function unpairDevice(id: string): Promise<PairedDevice> {
+  return invoke<PairedDevice>('unpair_device', { id })
+}
+
+export type TelemetryCategories = {
+  crash_reports: boolean
+  usage_metrics: boolean
+  performance_traces: boolean
+}
+
+export type TelemetryConsent = {
+  categories: TelemetryCategories
+  decided: boolean
+}
+
+export async function telemetrySettings(): Promise<TelemetryConsent> {
+  return invoke<TelemetryConsent>('telemetry_settings')
+}
+
+export async function setTelemetrySettings(
+  categories: TelemetryCategories,
+): Promise<TelemetryConsent> {
+  return invoke<TelemetryConsent>('set_telemetry_settings', { categories })
+}
+
+export type BundleEntry = {
+  name: string
+  description: string
+  bytes: number
+  redactions: number
+}
+
+export type DiagnosticBundle = {
+  path: string
+  created_at: number
+  entries: BundleEntry[]
+  summary: string[]
+}
+
+export async function createDiagnosticBundle(): Promise<DiagnosticBundle> {
+  return invoke<DiagnosticBundle>('create_diagnostic_bundle')
+}
+
+export type Finding = {
+  check: string
+  severity: 'ok' | 'warning' | 'error'
+  message: string
+  fix?: string
+}
+
+export type DoctorReport = {
+  findings: Finding[]
+}
+
+export async function runDoctor(): Promise<DoctorReport> {
+  return invoke<DoctorReport>('run_doctor')
+}
@@ -0,0 +1,76 @@
+#[cfg(unix)]
+mod unix {
+    use criterion::{BenchmarkId, Criterion, Throughput};
+    use dg_bridge::{BridgeClient, BridgeConfig, Endpoint, RpcRequest};
+    use serde_json::{json, Value};
+    use tempfile::tempdir;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixListener;
+
+    const PAYLOAD_SIZES: [usize; 3] = [0, 4 << 10, 256 << 10];
+
+    async fn serve_echo(listener: UnixListener) {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let (read, mut write) = stream.into_split();
+                let mut lines = BufReader::new(read).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let request: Value = serde_json::from_str(&line).expect("request json");
+                    let reply = json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "result": request["params"],
+                    });
+                    let mut payload = serde_json::to_vec(&reply).expect("encode");
+                    payload.push(b'\n');
+                    if write.write_all(&payload).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    }
+
+    pub fn rpc(c: &mut Criterion) {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .expect("runtime");
+        let temp = tempdir().expect("tempdir");
+        let socket = temp.path().join("dg-core.sock");
+        let client = rt.block_on(async {
+            let listener = UnixListener::bind(&socket).expect("bind");
+            tokio::spawn(serve_echo(listener));
+            BridgeClient::new(BridgeConfig::new(vec![Endpoint::Unix(socket.clone())]))
+                .expect("client")
+        });
+
+        let mut group = c.benchmark_group("rpc_round_trip");
+        for size in PAYLOAD_SIZES {
+            let params = json!({ "data": "a".repeat(size) });
+            group.throughput(Throughput::Bytes(size as u64));
+            group.bench_with_input(BenchmarkId::from_parameter(size), &params, |b, params| {
+                b.iter(|| {
+                    let response = rt
+                        .block_on(client.send_request(RpcRequest {
+                            id: "bench".into(),
+                            method: "core.echo".into(),
+                            params: Some(params.clone()),
+                        }))
+                        .expect("round trip");
+                    assert!(response.error.is_none());
+                })
+            });
+        }
+        group.finish();
+    }
+}
+
+#[cfg(unix)]
+criterion::criterion_group!(benches, unix::rpc);
+#[cfg(unix)]
+criterion::criterion_main!(benches);
+
+#[cfg(not(unix))]
+fn main() {}
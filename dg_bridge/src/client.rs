@@ -0,0 +1,869 @@
+use std::collections::{HashMap, VecDeque};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use dg_core::api::LoadedProfile;
+use futures_core::Stream;
+use serde::{Deserialize, Serialize};
+use tokio::io::{
+    AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, ReadHalf, WriteHalf,
+};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, oneshot, Mutex, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+
+#[cfg(target_os = "windows")]
+use tokio::net::windows::named_pipe::ClientOptions;
+#[cfg(target_os = "windows")]
+use tokio::net::windows::named_pipe::NamedPipeClient;
+#[cfg(target_family = "unix")]
+use tokio::net::UnixStream;
+
+use crate::session::{Capability, SessionAuthority, TransportToken};
+use crate::transport::Endpoint;
+
+pub use dg_core::rpc_error::{RpcError, RATE_LIMITED, REQUEST_TOO_LARGE};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(5_000);
+const DEFAULT_RETRIES: usize = 1;
+const DEFAULT_MAX_IN_FLIGHT: usize = 8;
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_MAX_FRAME_BYTES: usize = 512 * 1024;
+const SUBSCRIPTION_BUFFER: usize = 64;
+const AUTHENTICATE_ID: &str = "authenticate";
+#[cfg(target_os = "windows")]
+const ERROR_PIPE_BUSY: i32 = 231;
+#[cfg(target_os = "windows")]
+const PIPE_BUSY_BACKOFF: Duration = Duration::from_millis(50);
+pub const PROGRESS_METHOD: &str = "core.progress";
+pub const LOG_METHOD: &str = "core.log";
+pub const DESCRIBE_METHOD: &str = "core.describe";
+pub const SUBSCRIBE_METHOD: &str = "core.subscribe";
+pub const TAIL_LOGS_METHOD: &str = "core.tail_logs";
+pub const HEALTH_METHOD: &str = "core.health";
+
+trait BridgeStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<S> BridgeStream for S where S: AsyncRead + AsyncWrite + Unpin + Send {}
+
+type Connection = BufReader<Box<dyn BridgeStream>>;
+
+type Waiters = Arc<std::sync::Mutex<HashMap<u64, oneshot::Sender<JsonRpcResponse>>>>;
+
+type Notifications = Option<mpsc::Sender<serde_json::Value>>;
+
+#[derive(Debug, Clone)]
+enum SessionCredentials {
+    Token(String),
+    Authority {
+        authority: SessionAuthority,
+        client: String,
+        scopes: Vec<Capability>,
+        ttl: Duration,
+    },
+}
+
+impl SessionCredentials {
+    fn token(&self) -> Result<String> {
+        match self {
+            SessionCredentials::Token(token) => Ok(token.clone()),
+            SessionCredentials::Authority {
+                authority,
+                client,
+                scopes,
+                ttl,
+            } => authority.mint(client, scopes, *ttl),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BridgeConfig {
+    pub endpoints: Vec<Endpoint>,
+    pub timeout: Duration,
+    pub retries: usize,
+    pub max_in_flight: usize,
+    pub idle_timeout: Duration,
+    pub max_frame_bytes: usize,
+    session: Option<SessionCredentials>,
+    transport_token: Option<TransportToken>,
+    device_id: Option<String>,
+}
+
+impl BridgeConfig {
+    pub fn new(endpoints: Vec<Endpoint>) -> Self {
+        Self {
+            endpoints,
+            timeout: DEFAULT_TIMEOUT,
+            retries: DEFAULT_RETRIES,
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            max_frame_bytes: DEFAULT_MAX_FRAME_BYTES,
+            session: None,
+            transport_token: None,
+            device_id: None,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight;
+        self
+    }
+
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    pub fn with_max_frame_bytes(mut self, max_frame_bytes: usize) -> Self {
+        self.max_frame_bytes = max_frame_bytes;
+        self
+    }
+
+    pub fn with_session_token(mut self, token: impl Into<String>) -> Self {
+        self.session = Some(SessionCredentials::Token(token.into()));
+        self
+    }
+
+    pub fn with_session_authority(
+        mut self,
+        authority: SessionAuthority,
+        client: impl Into<String>,
+        scopes: &[Capability],
+        ttl: Duration,
+    ) -> Self {
+        self.session = Some(SessionCredentials::Authority {
+            authority,
+            client: client.into(),
+            scopes: scopes.to_vec(),
+            ttl,
+        });
+        self
+    }
+
+    // Sent in `core.authenticate` on TCP connections only.
+    pub fn with_transport_token(mut self, token: TransportToken) -> Self {
+        self.transport_token = Some(token);
+        self
+    }
+
+    pub fn with_device_id(mut self, device_id: impl Into<String>) -> Self {
+        self.device_id = Some(device_id.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RpcRequest {
+    pub id: String,
+    pub method: String,
+    pub params: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcResponse {
+    pub id: String,
+    #[serde(default)]
+    pub result: Option<serde_json::Value>,
+    #[serde(default)]
+    pub error: Option<RpcError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcNotification {
+    pub method: String,
+    #[serde(default)]
+    pub params: serde_json::Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgressUpdate {
+    pub op_id: String,
+    #[serde(default)]
+    pub method: Option<String>,
+    pub stage: String,
+    #[serde(default)]
+    pub bytes: u64,
+    #[serde(default)]
+    pub total_bytes: Option<u64>,
+    #[serde(default)]
+    pub percent: Option<f64>,
+}
+
+impl ProgressUpdate {
+    pub fn from_notification(notification: &RpcNotification) -> Option<Self> {
+        if notification.method != PROGRESS_METHOD {
+            return None;
+        }
+        serde_json::from_value(notification.params.clone()).ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MethodDescriptor {
+    pub name: String,
+    #[serde(default)]
+    pub params: serde_json::Value,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+impl MethodDescriptor {
+    pub fn parameter_names(&self) -> Vec<&str> {
+        self.params
+            .get("properties")
+            .and_then(serde_json::Value::as_object)
+            .map(|properties| properties.keys().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn required_parameters(&self) -> Vec<&str> {
+        self.params
+            .get("required")
+            .and_then(serde_json::Value::as_array)
+            .map(|required| {
+                required
+                    .iter()
+                    .filter_map(serde_json::Value::as_str)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+// What `core.health` reports: the daemon's build, how long it has served and the profile its
+// engine has loaded, if any.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoreHealth {
+    pub version: String,
+    pub uptime: f64,
+    #[serde(default)]
+    pub profile: Option<LoadedProfile>,
+}
+
+// One authenticated stream to an endpoint, shared by every request sent there. Callers write
+// under `writer`; the reader task hands each response to the caller waiting on its wire id.
+struct Multiplexed {
+    endpoint: Endpoint,
+    writer: Mutex<WriteHalf<Connection>>,
+    waiters: Waiters,
+    in_flight: Semaphore,
+    closed: Arc<AtomicBool>,
+    last_used: std::sync::Mutex<Instant>,
+    reader: JoinHandle<()>,
+}
+
+impl Multiplexed {
+    fn start(
+        endpoint: Endpoint,
+        connection: Connection,
+        max_in_flight: usize,
+        notifications: Notifications,
+    ) -> Self {
+        let (read, write) = tokio::io::split(connection);
+        let waiters = Waiters::default();
+        let closed = Arc::new(AtomicBool::new(false));
+        let reader = tokio::spawn(Self::route(
+            read,
+            waiters.clone(),
+            closed.clone(),
+            notifications,
+        ));
+        Self {
+            endpoint,
+            writer: Mutex::new(write),
+            waiters,
+            in_flight: Semaphore::new(max_in_flight.max(1)),
+            closed,
+            last_used: std::sync::Mutex::new(Instant::now()),
+            reader,
+        }
+    }
+
+    async fn route(
+        read: ReadHalf<Connection>,
+        waiters: Waiters,
+        closed: Arc<AtomicBool>,
+        notifications: Notifications,
+    ) {
+        let mut reader = BufReader::new(read);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match reader.read_line(&mut line).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+            let Ok(message) = serde_json::from_str::<serde_json::Value>(line.trim()) else {
+                continue;
+            };
+            if message.get("id").is_none() && message.get("method").is_some() {
+                if let Some(subscriber) = &notifications {
+                    if subscriber.send(message).await.is_err() {
+                        break;
+                    }
+                }
+                continue;
+            }
+            let Ok(response) = serde_json::from_value::<JsonRpcResponse>(message) else {
+                continue;
+            };
+            // The daemon closes the stream after a limit violation, which carries a null id, so
+            // every request still waiting on it gets the violation as its answer.
+            if response.limit_violation() {
+                closed.store(true, Ordering::SeqCst);
+                let pending: Vec<_> = lock(&waiters).drain().map(|(_, waiter)| waiter).collect();
+                for waiter in pending {
+                    let _ = waiter.send(response.clone());
+                }
+                return;
+            }
+            let waiter = response
+                .id
+                .as_u64()
+                .and_then(|id| lock(&waiters).remove(&id));
+            if let Some(waiter) = waiter {
+                let _ = waiter.send(response);
+            }
+        }
+        closed.store(true, Ordering::SeqCst);
+        lock(&waiters).clear();
+    }
+
+    async fn call(
+        &self,
+        wire_id: u64,
+        message: &[u8],
+        timeout_duration: Duration,
+    ) -> Result<JsonRpcResponse> {
+        let exchange = async {
+            let _permit = self.in_flight.acquire().await?;
+            let (tx, rx) = oneshot::channel();
+            lock(&self.waiters).insert(wire_id, tx);
+            if self.closed.load(Ordering::SeqCst) {
+                return Err(anyhow!("connection closed before response"));
+            }
+            {
+                let mut writer = self.writer.lock().await;
+                writer.write_all(message).await?;
+                writer.flush().await?;
+            }
+            rx.await
+                .map_err(|_| anyhow!("connection closed before response"))
+        };
+        let result = timeout(timeout_duration, exchange)
+            .await
+            .context("io exchange timed out")
+            .and_then(|result| result);
+        lock(&self.waiters).remove(&wire_id);
+        *lock(&self.last_used) = Instant::now();
+        result
+    }
+
+    // Connections a caller is still using never expire; others are dropped once the daemon has
+    // closed them or they sat idle for `idle_timeout`.
+    fn reusable(self: &Arc<Self>, idle_timeout: Duration) -> bool {
+        !self.closed.load(Ordering::SeqCst)
+            && (Arc::strong_count(self) > 1 || lock(&self.last_used).elapsed() < idle_timeout)
+    }
+}
+
+impl Drop for Multiplexed {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+// Notifications from a `subscribe` call, in the order the daemon sent them. The subscription owns
+// its connection: dropping it closes the stream, and the stream ends when the daemon hangs up.
+pub struct Subscription {
+    notifications: mpsc::Receiver<serde_json::Value>,
+    _connection: Arc<Multiplexed>,
+}
+
+impl Subscription {
+    pub async fn recv(&mut self) -> Option<serde_json::Value> {
+        self.notifications.recv().await
+    }
+}
+
+impl Stream for Subscription {
+    type Item = serde_json::Value;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().notifications.poll_recv(cx)
+    }
+}
+
+fn lock<T>(mutex: &std::sync::Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[derive(Clone)]
+pub struct BridgeClient {
+    endpoints: Vec<Endpoint>,
+    timeout: Duration,
+    retries: usize,
+    max_in_flight: usize,
+    idle_timeout: Duration,
+    max_frame_bytes: usize,
+    session: Option<SessionCredentials>,
+    transport_token: Option<TransportToken>,
+    device_id: Option<String>,
+    active_endpoint: Arc<Mutex<Option<Endpoint>>>,
+    connections: Arc<Mutex<Vec<Arc<Multiplexed>>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl BridgeClient {
+    pub fn new(config: BridgeConfig) -> Result<Self> {
+        if config.endpoints.is_empty() {
+            return Err(anyhow!("bridge config requires at least one endpoint"));
+        }
+
+        let mut unique = Vec::new();
+        for endpoint in config.endpoints {
+            if !unique.contains(&endpoint) {
+                unique.push(endpoint);
+            }
+        }
+
+        Ok(Self {
+            endpoints: unique,
+            timeout: config.timeout,
+            retries: config.retries.max(1),
+            max_in_flight: config.max_in_flight,
+            idle_timeout: config.idle_timeout,
+            max_frame_bytes: config.max_frame_bytes,
+            session: config.session,
+            transport_token: config.transport_token,
+            device_id: config.device_id,
+            active_endpoint: Arc::new(Mutex::new(None)),
+            connections: Arc::new(Mutex::new(Vec::new())),
+            next_id: Arc::new(AtomicU64::new(1)),
+        })
+    }
+
+    pub async fn connect(config: BridgeConfig) -> Result<Self> {
+        let client = Self::new(config)?;
+
+        for endpoint in &client.endpoints {
+            if Self::probe_endpoint(endpoint, client.timeout).await.is_ok() {
+                *client.active_endpoint.lock().await = Some(endpoint.clone());
+                return Ok(client);
+            }
+        }
+
+        Err(anyhow!(
+            "failed to reach DG Core on any configured endpoint"
+        ))
+    }
+
+    pub fn endpoints(&self) -> &[Endpoint] {
+        &self.endpoints
+    }
+
+    pub async fn open_connections(&self) -> usize {
+        self.connections
+            .lock()
+            .await
+            .iter()
+            .filter(|connection| !connection.closed.load(Ordering::SeqCst))
+            .count()
+    }
+
+    pub async fn send_request(&self, request: RpcRequest) -> Result<RpcResponse> {
+        // Callers may reuse ids, so the wire carries a per-client id and the response is handed
+        // back under the caller's own.
+        let wire_id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let payload = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": wire_id,
+            "method": request.method,
+            "params": request.params.unwrap_or(serde_json::Value::Null),
+        });
+        let mut envelope = serde_json::to_vec(&payload)?;
+        if envelope.len() > self.max_frame_bytes {
+            let message = format!(
+                "{} request is {} bytes; the daemon accepts at most {}",
+                request.method,
+                envelope.len(),
+                self.max_frame_bytes
+            );
+            return Err(RpcError::new(REQUEST_TOO_LARGE, message)
+                .with_data(serde_json::json!({ "reason": "frame", "limit": self.max_frame_bytes }))
+                .into());
+        }
+        envelope.push(b'\n');
+
+        let mut candidates = VecDeque::new();
+        if let Some(active) = self.active_endpoint.lock().await.clone() {
+            candidates.push_back(active);
+        }
+        for endpoint in &self.endpoints {
+            if Some(endpoint) != candidates.front() {
+                candidates.push_back(endpoint.clone());
+            }
+        }
+
+        let mut last_err = None;
+
+        while let Some(endpoint) = candidates.pop_front() {
+            for attempt in 0..=self.retries {
+                let connection = match self.connection(&endpoint).await {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        last_err =
+                            Some(err.context(format!("attempt {attempt} via {} failed", endpoint)));
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        continue;
+                    }
+                };
+                match connection.call(wire_id, &envelope, self.timeout).await {
+                    Ok(response) => {
+                        let mut rpc = response.into_rpc()?;
+                        rpc.id = request.id;
+                        *self.active_endpoint.lock().await = Some(endpoint.clone());
+                        return Ok(rpc);
+                    }
+                    Err(err) => {
+                        self.evict(&connection).await;
+                        last_err =
+                            Some(err.context(format!("attempt {attempt} via {} failed", endpoint)));
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                    }
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow!("request dispatch failed")))
+    }
+
+    pub async fn describe(&self) -> Result<Vec<MethodDescriptor>> {
+        let response = self
+            .send_request(RpcRequest {
+                id: "describe".into(),
+                method: DESCRIBE_METHOD.into(),
+                params: Some(serde_json::json!({})),
+            })
+            .await?;
+        if let Some(error) = response.error {
+            return Err(anyhow::Error::new(error).context(format!("{DESCRIBE_METHOD} failed")));
+        }
+        let methods = response
+            .result
+            .and_then(|mut result| result.get_mut("methods").map(serde_json::Value::take))
+            .ok_or_else(|| anyhow!("{DESCRIBE_METHOD} returned no methods"))?;
+        serde_json::from_value(methods).context("invalid method registry")
+    }
+
+    // Unlike `probe_endpoint`, this needs the daemon to answer a request, so it also catches a
+    // core that accepts connections but is wedged or speaks another protocol.
+    pub async fn health(&self) -> Result<CoreHealth> {
+        let response = self
+            .send_request(RpcRequest {
+                id: "health".into(),
+                method: HEALTH_METHOD.into(),
+                params: Some(serde_json::json!({})),
+            })
+            .await?;
+        if let Some(error) = response.error {
+            return Err(anyhow::Error::new(error).context(format!("{HEALTH_METHOD} failed")));
+        }
+        let result = response
+            .result
+            .ok_or_else(|| anyhow!("{HEALTH_METHOD} returned no result"))?;
+        serde_json::from_value(result).context("invalid health report")
+    }
+
+    // Sends `method` on a connection of its own and keeps it open, yielding every notification the
+    // daemon pushes afterwards. Fails if the daemon rejects the call.
+    pub async fn subscribe(&self, method: &str, params: serde_json::Value) -> Result<Subscription> {
+        let endpoint = match self.active_endpoint.lock().await.clone() {
+            Some(endpoint) => endpoint,
+            None => self.endpoints[0].clone(),
+        };
+        let wire_id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let payload = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": wire_id,
+            "method": method,
+            "params": params,
+        });
+        let mut message = serde_json::to_vec(&payload)?;
+        message.push(b'\n');
+
+        let (tx, rx) = mpsc::channel(SUBSCRIPTION_BUFFER);
+        let connection = Arc::new(Multiplexed::start(
+            endpoint.clone(),
+            self.open_session(&endpoint).await?,
+            self.max_in_flight,
+            Some(tx),
+        ));
+        let response = connection
+            .call(wire_id, &message, self.timeout)
+            .await
+            .with_context(|| format!("{method} was not confirmed"))?;
+        if let Some(error) = response.into_rpc()?.error {
+            return Err(anyhow::Error::new(error).context(format!("{method} rejected")));
+        }
+        Ok(Subscription {
+            notifications: rx,
+            _connection: connection,
+        })
+    }
+
+    pub async fn subscribe_topic(&self, topic: &str) -> Result<mpsc::Receiver<RpcNotification>> {
+        self.notifications(SUBSCRIBE_METHOD, serde_json::json!({ "topic": topic }))
+            .await
+    }
+
+    pub async fn tail_logs(&self) -> Result<mpsc::Receiver<RpcNotification>> {
+        self.notifications(TAIL_LOGS_METHOD, serde_json::json!({}))
+            .await
+    }
+
+    async fn notifications(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<mpsc::Receiver<RpcNotification>> {
+        let mut subscription = self.subscribe(method, params).await?;
+        let (tx, rx) = mpsc::channel(SUBSCRIPTION_BUFFER);
+        tokio::spawn(async move {
+            while let Some(message) = subscription.recv().await {
+                let Ok(notification) = serde_json::from_value::<RpcNotification>(message) else {
+                    continue;
+                };
+                if tx.send(notification).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(rx)
+    }
+
+    pub async fn probe_endpoint(endpoint: &Endpoint, timeout_duration: Duration) -> Result<()> {
+        Self::connect_stream(endpoint, timeout_duration)
+            .await
+            .map(|_| ())
+    }
+
+    // The lock is held while connecting so concurrent first requests share one new stream.
+    async fn connection(&self, endpoint: &Endpoint) -> Result<Arc<Multiplexed>> {
+        let mut connections = self.connections.lock().await;
+        connections.retain(|connection| connection.reusable(self.idle_timeout));
+        if let Some(connection) = connections
+            .iter()
+            .find(|connection| &connection.endpoint == endpoint)
+        {
+            return Ok(connection.clone());
+        }
+        let connection = Arc::new(Multiplexed::start(
+            endpoint.clone(),
+            self.open_session(endpoint).await?,
+            self.max_in_flight,
+            None,
+        ));
+        connections.push(connection.clone());
+        Ok(connection)
+    }
+
+    async fn evict(&self, connection: &Arc<Multiplexed>) {
+        self.connections
+            .lock()
+            .await
+            .retain(|open| !Arc::ptr_eq(open, connection));
+    }
+
+    async fn open_session(&self, endpoint: &Endpoint) -> Result<Connection> {
+        let mut connection = BufReader::new(Self::connect_stream(endpoint, self.timeout).await?);
+        let transport_token = match endpoint {
+            Endpoint::Tcp(_) => self.transport_token.as_ref(),
+            _ => None,
+        };
+        if self.session.is_none() && transport_token.is_none() {
+            return Ok(connection);
+        }
+        let mut params = serde_json::json!({});
+        if let Some(credentials) = &self.session {
+            params["token"] = serde_json::Value::String(credentials.token()?);
+        }
+        if let Some(token) = transport_token {
+            params["transport_token"] = serde_json::Value::String(token.as_str().to_owned());
+        }
+        if let Some(device_id) = &self.device_id {
+            params["device"] = serde_json::Value::String(device_id.clone());
+        }
+        let payload = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": AUTHENTICATE_ID,
+            "method": "core.authenticate",
+            "params": params,
+        });
+        let mut message = serde_json::to_vec(&payload)?;
+        message.push(b'\n');
+        let response = Self::round_trip(
+            &mut connection,
+            &message,
+            &serde_json::Value::String(AUTHENTICATE_ID.into()),
+            self.timeout,
+        )
+        .await
+        .context("session handshake failed")?;
+        if let Some(error) = response.into_rpc()?.error {
+            return Err(
+                anyhow::Error::new(error).context(format!("session rejected by {endpoint}"))
+            );
+        }
+        Ok(connection)
+    }
+
+    async fn connect_stream(
+        endpoint: &Endpoint,
+        timeout_duration: Duration,
+    ) -> Result<Box<dyn BridgeStream>> {
+        match endpoint {
+            Endpoint::Tcp(addr) => {
+                let stream = timeout(timeout_duration, TcpStream::connect(addr))
+                    .await
+                    .context("tcp connect timed out")??;
+                Ok(Box::new(stream))
+            }
+            Endpoint::Unix(path) => {
+                #[cfg(target_family = "unix")]
+                {
+                    let stream = timeout(timeout_duration, UnixStream::connect(path))
+                        .await
+                        .with_context(|| {
+                            format!("unix connect to {} timed out", path.display())
+                        })??;
+                    Ok(Box::new(stream))
+                }
+                #[cfg(not(target_family = "unix"))]
+                {
+                    let _ = path;
+                    Err(anyhow!("unix sockets not supported on this platform"))
+                }
+            }
+            Endpoint::NamedPipe(name) => {
+                #[cfg(target_os = "windows")]
+                {
+                    let pipe_name = if name.starts_with(r"\\.\pipe\") {
+                        name.clone()
+                    } else {
+                        format!(r"\\.\pipe\{name}")
+                    };
+                    let deadline = Instant::now() + timeout_duration;
+                    let client: NamedPipeClient = loop {
+                        match ClientOptions::new().open(&pipe_name) {
+                            Ok(client) => break client,
+                            Err(err)
+                                if err.raw_os_error() == Some(ERROR_PIPE_BUSY)
+                                    && Instant::now() < deadline =>
+                            {
+                                tokio::time::sleep(PIPE_BUSY_BACKOFF).await;
+                            }
+                            Err(err) => {
+                                return Err(err).with_context(|| {
+                                    format!("failed to open named pipe {pipe_name}")
+                                })
+                            }
+                        }
+                    };
+                    Ok(Box::new(client))
+                }
+                #[cfg(not(target_os = "windows"))]
+                {
+                    let _ = name;
+                    Err(anyhow!("named pipes are only supported on windows"))
+                }
+            }
+        }
+    }
+
+    async fn round_trip(
+        connection: &mut Connection,
+        message: &[u8],
+        request_id: &serde_json::Value,
+        timeout_duration: Duration,
+    ) -> Result<JsonRpcResponse> {
+        timeout(timeout_duration, async {
+            connection.get_mut().write_all(message).await?;
+            connection.get_mut().flush().await?;
+
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if connection.read_line(&mut line).await? == 0 {
+                    return Err(anyhow!("connection closed before response"));
+                }
+                let Ok(response) = serde_json::from_str::<JsonRpcResponse>(line.trim()) else {
+                    continue;
+                };
+                if &response.id == request_id || response.limit_violation() {
+                    return Ok(response);
+                }
+            }
+        })
+        .await
+        .context("io exchange timed out")?
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Deserialize)]
+struct JsonRpcResponse {
+    #[serde(default)]
+    jsonrpc: Option<String>,
+    id: serde_json::Value,
+    #[serde(default)]
+    result: Option<serde_json::Value>,
+    #[serde(default)]
+    error: Option<RpcError>,
+}
+
+impl JsonRpcResponse {
+    fn limit_violation(&self) -> bool {
+        self.id.is_null()
+            && self
+                .error
+                .as_ref()
+                .is_some_and(RpcError::is_limit_violation)
+    }
+
+    fn into_rpc(self) -> Result<RpcResponse> {
+        let id = match self.id {
+            serde_json::Value::String(s) => s,
+            value => value.to_string(),
+        };
+
+        if self.result.is_none() && self.error.is_none() {
+            return Err(anyhow!("json-rpc response missing result and error"));
+        }
+
+        Ok(RpcResponse {
+            id,
+            result: self.result,
+            error: self.error,
+        })
+    }
+}
@@ -0,0 +1,239 @@
+use base64::{engine::general_purpose, Engine as _};
+use dg_core::api::{DGError, DGResult, EncryptRequest, Envelope};
+use dg_core::{ChunkingParams, CustomMetadata, JournalAppend, KeyShare, Preview};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use zeroize::Zeroize;
+
+pub const ENGINE_INIT: &str = "engine.init";
+pub const ENGINE_ENCRYPT: &str = "engine.encrypt";
+pub const ENGINE_DECRYPT: &str = "engine.decrypt";
+pub const ENGINE_CHECK_POLICY: &str = "engine.check_policy";
+pub const ENGINE_EXPLAIN: &str = "engine.explain";
+pub const ENGINE_IMPORT_PRIVATE_KEY: &str = "engine.import_private_key";
+pub const ENGINE_IMPORT_PUBLIC_KEY: &str = "engine.import_public_key";
+pub const ENGINE_REVOKE_KEY: &str = "engine.revoke_key";
+pub const ENGINE_GENERATE_IDENTITY: &str = "engine.generate_identity";
+pub const ENGINE_GENERATE_KEY_HIERARCHY: &str = "engine.generate_key_hierarchy";
+pub const ENGINE_LIST_IDENTITIES: &str = "engine.list_identities";
+pub const ENGINE_KEY_EXPIRY: &str = "engine.key_expiry";
+pub const ENGINE_ROTATE_KEY: &str = "engine.rotate_key";
+pub const ENGINE_REKEY: &str = "engine.rekey";
+pub const ENGINE_SIGN: &str = "engine.sign";
+pub const ENGINE_VERIFY_SIGNATURE: &str = "engine.verify_signature";
+pub const ENGINE_SELF_TEST: &str = "engine.self_test";
+pub const ENGINE_WARM_UP: &str = "engine.warm_up";
+pub const ENGINE_INTEGRITY: &str = "engine.integrity";
+pub const ENGINE_COLLECT_SHARE: &str = "engine.collect_share";
+pub const ENGINE_COMBINE_SHARES: &str = "engine.combine_shares";
+pub const ENGINE_ISSUE_GRANT: &str = "engine.issue_grant";
+pub const ENGINE_DECRYPT_WITH_GRANT: &str = "engine.decrypt_with_grant";
+pub const ENGINE_EXPORT_OFFLINE_REQUEST: &str = "engine.export_offline_request";
+pub const ENGINE_ANSWER_OFFLINE_REQUEST: &str = "engine.answer_offline_request";
+pub const ENGINE_COMPLETE_OFFLINE_REQUEST: &str = "engine.complete_offline_request";
+pub const ENGINE_PREVIEW: &str = "engine.preview";
+pub const ENGINE_VERIFY_RANGE: &str = "engine.verify_range";
+pub const ENGINE_DECRYPT_RANGE: &str = "engine.decrypt_range";
+pub const ENGINE_APPEND: &str = "engine.append";
+pub const ENGINE_INDEX_ENTRIES: &str = "engine.index_entries";
+pub const ENGINE_INDEX_RECORD: &str = "engine.index_record";
+pub const ENGINE_INDEX_REMOVE: &str = "engine.index_remove";
+pub const ENGINE_INDEX_RECONCILE: &str = "engine.index_reconcile";
+pub const ENGINE_RELOAD_POLICY: &str = "engine.reload_policy";
+pub const ENGINE_POLICY_CACHE: &str = "engine.policy_cache";
+pub const ENGINE_QUERY_AUDIT: &str = "engine.query_audit";
+pub const ENGINE_LOCK: &str = "engine.lock";
+pub const ENGINE_UNLOCK: &str = "engine.unlock";
+pub const ENGINE_SHUTDOWN: &str = "engine.shutdown";
+
+// Wire shapes of the `engine.*` methods, shared by the remote backend and the native server.
+// Binary fields travel as standard base64.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WireEnvelope {
+    pub payload: String,
+    pub meta: Value,
+}
+
+impl From<&Envelope> for WireEnvelope {
+    fn from(envelope: &Envelope) -> Self {
+        Self {
+            payload: encode_bytes(&envelope.bytes),
+            meta: envelope.meta.clone(),
+        }
+    }
+}
+
+impl TryFrom<WireEnvelope> for Envelope {
+    type Error = DGError;
+
+    fn try_from(wire: WireEnvelope) -> DGResult<Self> {
+        Ok(Envelope {
+            bytes: decode_bytes(&wire.payload)?,
+            meta: wire.meta,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WirePreview {
+    pub media_type: String,
+    pub data: String,
+}
+
+impl From<&Preview> for WirePreview {
+    fn from(preview: &Preview) -> Self {
+        Self {
+            media_type: preview.media_type.clone(),
+            data: encode_bytes(&preview.data),
+        }
+    }
+}
+
+impl TryFrom<WirePreview> for Preview {
+    type Error = DGError;
+
+    fn try_from(wire: WirePreview) -> DGResult<Self> {
+        Ok(Preview {
+            media_type: wire.media_type,
+            data: decode_bytes(&wire.data)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WireEncryptRequest {
+    pub plaintext: String,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub recipients: Vec<String>,
+    #[serde(default)]
+    pub threshold: Option<u8>,
+    #[serde(default)]
+    pub preview: Option<WirePreview>,
+    #[serde(default)]
+    pub metadata: CustomMetadata,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunking: Option<ChunkingParams>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<WireEnvelope>,
+    #[serde(default)]
+    pub journal: bool,
+}
+
+impl From<&EncryptRequest> for WireEncryptRequest {
+    fn from(req: &EncryptRequest) -> Self {
+        Self {
+            plaintext: encode_bytes(&req.plaintext),
+            labels: req.labels.clone(),
+            recipients: req.recipients.clone(),
+            threshold: req.threshold,
+            preview: req.preview.as_ref().map(WirePreview::from),
+            metadata: req.metadata.clone(),
+            chunking: req.chunking,
+            previous: req.previous.as_ref().map(WireEnvelope::from),
+            journal: req.journal,
+        }
+    }
+}
+
+impl TryFrom<WireEncryptRequest> for EncryptRequest {
+    type Error = DGError;
+
+    fn try_from(wire: WireEncryptRequest) -> DGResult<Self> {
+        Ok(EncryptRequest {
+            plaintext: decode_bytes(&wire.plaintext)?,
+            labels: wire.labels,
+            recipients: wire.recipients,
+            threshold: wire.threshold,
+            preview: wire.preview.map(Preview::try_from).transpose()?,
+            metadata: wire.metadata,
+            chunking: wire.chunking,
+            previous: wire.previous.map(Envelope::try_from).transpose()?,
+            journal: wire.journal,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WireJournalAppend {
+    pub committed_len: u64,
+    pub discarded_bytes: u64,
+    pub frames: String,
+    pub records: u64,
+    pub plaintext_len: u64,
+}
+
+impl From<&JournalAppend> for WireJournalAppend {
+    fn from(appended: &JournalAppend) -> Self {
+        Self {
+            committed_len: appended.committed_len,
+            discarded_bytes: appended.discarded_bytes,
+            frames: encode_bytes(&appended.frames),
+            records: appended.records,
+            plaintext_len: appended.plaintext_len,
+        }
+    }
+}
+
+impl TryFrom<WireJournalAppend> for JournalAppend {
+    type Error = DGError;
+
+    fn try_from(wire: WireJournalAppend) -> DGResult<Self> {
+        Ok(JournalAppend {
+            committed_len: wire.committed_len,
+            discarded_bytes: wire.discarded_bytes,
+            frames: decode_bytes(&wire.frames)?,
+            records: wire.records,
+            plaintext_len: wire.plaintext_len,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaintextResult {
+    pub plaintext: String,
+}
+
+// The encoded plaintext is as sensitive as the bytes it carries.
+impl Drop for PlaintextResult {
+    fn drop(&mut self) {
+        self.plaintext.zeroize();
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureResult {
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidResult {
+    pub valid: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllowedResult {
+    pub allowed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharesResult {
+    pub shares: Vec<KeyShare>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewResult {
+    pub preview: Option<WirePreview>,
+}
+
+pub fn encode_bytes(bytes: &[u8]) -> String {
+    general_purpose::STANDARD.encode(bytes)
+}
+
+pub fn decode_bytes(encoded: &str) -> DGResult<Vec<u8>> {
+    general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|err| DGError::Internal(format!("invalid base64 payload: {err}")))
+}
@@ -0,0 +1,15 @@
+pub mod client;
+pub mod engine;
+pub mod session;
+pub mod transport;
+
+pub use client::{
+    BridgeClient, BridgeConfig, CoreHealth, MethodDescriptor, ProgressUpdate, RpcError,
+    RpcNotification, RpcRequest, RpcResponse, Subscription, DESCRIBE_METHOD, HEALTH_METHOD,
+    LOG_METHOD, PROGRESS_METHOD, RATE_LIMITED, REQUEST_TOO_LARGE, SUBSCRIBE_METHOD,
+    TAIL_LOGS_METHOD,
+};
+pub use session::{
+    session_subject, Capability, SessionAuthority, SessionClaims, TransportToken, TCP_TOKEN_ENV,
+};
+pub use transport::{Endpoint, TransportKind};
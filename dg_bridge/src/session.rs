@@ -0,0 +1,301 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::io::AsyncWriteExt;
+use zeroize::Zeroizing;
+
+const TOKEN_VERSION: &str = "v1";
+const SUBJECT_PREFIX: &str = "session:";
+pub const SESSION_RESOURCE: &str = "ipc";
+// The desktop shell hands the TCP fallback's token to the core it spawns in this variable.
+pub const TCP_TOKEN_ENV: &str = "DG_CORE_TCP_TOKEN";
+const TRANSPORT_TOKEN_CONTEXT: &[u8] = b"data-guardian tcp transport token";
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Capability {
+    Encrypt,
+    Decrypt,
+    Scan,
+    Redact,
+    Policy,
+    ReadLogs,
+    Progress,
+    Status,
+    Keys,
+    Admin,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 10] = [
+        Capability::Encrypt,
+        Capability::Decrypt,
+        Capability::Scan,
+        Capability::Redact,
+        Capability::Policy,
+        Capability::ReadLogs,
+        Capability::Progress,
+        Capability::Status,
+        Capability::Keys,
+        Capability::Admin,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Capability::Encrypt => "encrypt",
+            Capability::Decrypt => "decrypt",
+            Capability::Scan => "scan",
+            Capability::Redact => "redact",
+            Capability::Policy => "policy",
+            Capability::ReadLogs => "read-logs",
+            Capability::Progress => "progress",
+            Capability::Status => "status",
+            Capability::Keys => "keys",
+            Capability::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Capability {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        Capability::ALL
+            .into_iter()
+            .find(|capability| capability.as_str() == value)
+            .ok_or_else(|| anyhow!("unknown capability '{value}'"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionClaims {
+    pub sub: String,
+    pub scopes: Vec<Capability>,
+    pub exp: u64,
+}
+
+impl SessionClaims {
+    pub fn policy_subject(&self) -> String {
+        session_subject(&self.sub)
+    }
+
+    pub fn allows(&self, capability: Capability) -> bool {
+        self.scopes.contains(&capability)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        unix_now() >= self.exp
+    }
+}
+
+pub fn session_subject(client: &str) -> String {
+    format!("{SUBJECT_PREFIX}{client}")
+}
+
+#[derive(Clone)]
+pub struct SessionAuthority {
+    key: [u8; 32],
+}
+
+impl fmt::Debug for SessionAuthority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionAuthority").finish_non_exhaustive()
+    }
+}
+
+impl SessionAuthority {
+    pub fn generate() -> Self {
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        Self { key }
+    }
+
+    pub fn from_key(key: [u8; 32]) -> Self {
+        Self { key }
+    }
+
+    pub async fn load_or_create(path: &Path) -> Result<Self> {
+        match tokio::fs::read_to_string(path).await {
+            Ok(raw) => {
+                let decoded = general_purpose::STANDARD
+                    .decode(raw.trim())
+                    .with_context(|| format!("invalid session key in {}", path.display()))?;
+                let key: [u8; 32] = decoded
+                    .try_into()
+                    .map_err(|_| anyhow!("session key in {} must be 32 bytes", path.display()))?;
+                Ok(Self { key })
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                let authority = Self::generate();
+                authority.persist(path).await?;
+                Ok(authority)
+            }
+            Err(err) => {
+                Err(err).with_context(|| format!("failed to read session key {}", path.display()))
+            }
+        }
+    }
+
+    // Written owner-only through a staging file, so the key is never readable by other users,
+    // not even briefly, and a crash mid-write leaves no truncated key behind.
+    async fn persist(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let encoded = Zeroizing::new(general_purpose::STANDARD.encode(self.key));
+        let mut staging = path.as_os_str().to_owned();
+        staging.push(".tmp");
+        let staging = PathBuf::from(staging);
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(target_family = "unix")]
+        options.mode(0o600);
+        let mut file = options
+            .open(&staging)
+            .await
+            .with_context(|| format!("failed to write session key {}", path.display()))?;
+        file.write_all(encoded.as_bytes()).await?;
+        file.sync_all().await?;
+        drop(file);
+        tokio::fs::rename(&staging, path)
+            .await
+            .with_context(|| format!("failed to write session key {}", path.display()))
+    }
+
+    pub fn mint(&self, client: &str, scopes: &[Capability], ttl: Duration) -> Result<String> {
+        if client.is_empty() {
+            return Err(anyhow!("session tokens require a client name"));
+        }
+        let mut scopes = scopes.to_vec();
+        scopes.sort();
+        scopes.dedup();
+        let claims = SessionClaims {
+            sub: client.to_string(),
+            scopes,
+            exp: unix_now() + ttl.as_secs(),
+        };
+        let claims = general_purpose::URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims)?);
+        let signed = format!("{TOKEN_VERSION}.{claims}");
+        let signature = general_purpose::URL_SAFE_NO_PAD.encode(self.sign(&signed));
+        Ok(format!("{signed}.{signature}"))
+    }
+
+    pub fn verify(&self, token: &str) -> Result<SessionClaims> {
+        let mut parts = token.split('.');
+        let (Some(version), Some(claims), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(anyhow!("malformed session token"));
+        };
+        if version != TOKEN_VERSION {
+            return Err(anyhow!("unsupported session token version '{version}'"));
+        }
+        let signature = general_purpose::URL_SAFE_NO_PAD
+            .decode(signature)
+            .context("malformed session token signature")?;
+        let mut mac = self.mac();
+        mac.update(format!("{version}.{claims}").as_bytes());
+        mac.verify_slice(&signature)
+            .map_err(|_| anyhow!("invalid session token signature"))?;
+        let claims: SessionClaims = serde_json::from_slice(
+            &general_purpose::URL_SAFE_NO_PAD
+                .decode(claims)
+                .context("malformed session token claims")?,
+        )
+        .context("malformed session token claims")?;
+        if claims.is_expired() {
+            return Err(anyhow!("session token for {} has expired", claims.sub));
+        }
+        Ok(claims)
+    }
+
+    fn sign(&self, message: &str) -> Vec<u8> {
+        let mut mac = self.mac();
+        mac.update(message.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        HmacSha256::new_from_slice(&self.key).expect("hmac accepts 32 byte keys")
+    }
+}
+
+// Shared secret a TCP client must present in `core.authenticate` before the core accepts any other
+// method. Unix sockets and named pipes are guarded by file permissions instead.
+#[derive(Clone)]
+pub struct TransportToken {
+    token: String,
+}
+
+impl fmt::Debug for TransportToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransportToken").finish_non_exhaustive()
+    }
+}
+
+impl TransportToken {
+    pub fn generate() -> Self {
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        Self {
+            token: general_purpose::URL_SAFE_NO_PAD.encode(secret),
+        }
+    }
+
+    pub fn new(token: impl Into<String>) -> Result<Self> {
+        let token = token.into();
+        if token.trim().is_empty() {
+            return Err(anyhow!("transport token must not be empty"));
+        }
+        Ok(Self { token })
+    }
+
+    // Reads `DG_CORE_TCP_TOKEN`, treating an unset or empty variable as no token.
+    pub fn from_env() -> Result<Option<Self>> {
+        match std::env::var(TCP_TOKEN_ENV) {
+            Ok(token) if !token.trim().is_empty() => Ok(Some(Self::new(token)?)),
+            Ok(_) | Err(std::env::VarError::NotPresent) => Ok(None),
+            Err(err) => Err(anyhow!("invalid {TCP_TOKEN_ENV}: {err}")),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.token
+    }
+
+    // Compares MACs of both tokens so the check takes the same time wherever they differ.
+    pub fn matches(&self, presented: &str) -> bool {
+        let mut mac = Self::mac();
+        mac.update(presented.as_bytes());
+        let mut expected = Self::mac();
+        expected.update(self.token.as_bytes());
+        mac.verify_slice(&expected.finalize().into_bytes()).is_ok()
+    }
+
+    fn mac() -> HmacSha256 {
+        HmacSha256::new_from_slice(TRANSPORT_TOKEN_CONTEXT).expect("hmac accepts any key length")
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,12 @@
+// Fixtures shared by the bridge integration tests; each test crate uses only some of them.
+#![allow(dead_code)]
+
+use dg_bridge::RpcRequest;
+
+pub fn request(id: &str) -> RpcRequest {
+    RpcRequest {
+        id: id.into(),
+        method: "core.get_status".into(),
+        params: None,
+    }
+}
@@ -0,0 +1,66 @@
+#![cfg(unix)]
+
+use anyhow::Result;
+use dg_bridge::{BridgeClient, BridgeConfig, Endpoint, DESCRIBE_METHOD};
+use serde_json::{json, Value};
+use tempfile::tempdir;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixListener;
+
+async fn serve_registry(listener: UnixListener) {
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(async move {
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let request: Value = serde_json::from_str(&line).expect("request json");
+                assert_eq!(request["method"], DESCRIBE_METHOD);
+                let response = json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": {
+                        "version": "0.1.0",
+                        "methods": [
+                            { "name": "core.ping", "params": { "type": "object", "properties": {} }, "capabilities": [] },
+                            {
+                                "name": "core.scan_path",
+                                "params": {
+                                    "type": "object",
+                                    "properties": { "path": { "type": "string" }, "op_id": { "type": "string" } },
+                                    "required": ["path"]
+                                },
+                                "capabilities": ["scan"]
+                            }
+                        ]
+                    }
+                });
+                let mut payload = serde_json::to_vec(&response).expect("encode");
+                payload.push(b'\n');
+                write.write_all(&payload).await.expect("write");
+            }
+        });
+    }
+}
+
+#[tokio::test]
+async fn describe_returns_method_descriptors() -> Result<()> {
+    let temp = tempdir()?;
+    let socket = temp.path().join("dg-core.sock");
+    let listener = UnixListener::bind(&socket)?;
+    tokio::spawn(serve_registry(listener));
+
+    let client = BridgeClient::new(BridgeConfig::new(vec![Endpoint::Unix(socket)]))?;
+    let methods = client.describe().await?;
+    assert_eq!(methods.len(), 2);
+    assert_eq!(methods[0].name, "core.ping");
+    assert!(methods[0].parameter_names().is_empty());
+    assert!(methods[0].capabilities.is_empty());
+
+    let scan = &methods[1];
+    assert_eq!(scan.capabilities, vec!["scan".to_string()]);
+    let mut params = scan.parameter_names();
+    params.sort_unstable();
+    assert_eq!(params, vec!["op_id", "path"]);
+    assert_eq!(scan.required_parameters(), vec!["path"]);
+    Ok(())
+}
@@ -0,0 +1,88 @@
+#![cfg(unix)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use dg_bridge::{
+    BridgeClient, BridgeConfig, Endpoint, RpcError, RpcRequest, RATE_LIMITED, REQUEST_TOO_LARGE,
+};
+use serde_json::{json, Value};
+use tempfile::tempdir;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixListener;
+
+async fn serve_rate_limited(listener: UnixListener, connections: Arc<AtomicUsize>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        connections.fetch_add(1, Ordering::SeqCst);
+        tokio::spawn(async move {
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+            let Ok(Some(_)) = lines.next_line().await else {
+                return;
+            };
+            let rejection = json!({
+                "jsonrpc": "2.0",
+                "id": Value::Null,
+                "error": { "code": RATE_LIMITED, "message": "Rate limit exceeded", "data": { "reason": "rate" } }
+            });
+            let mut payload = serde_json::to_vec(&rejection).expect("encode");
+            payload.push(b'\n');
+            write.write_all(&payload).await.expect("write");
+        });
+    }
+}
+
+fn ping() -> RpcRequest {
+    RpcRequest {
+        id: "ping".into(),
+        method: "core.ping".into(),
+        params: None,
+    }
+}
+
+#[tokio::test]
+async fn rate_limit_rejection_is_returned_without_retrying() -> Result<()> {
+    let temp = tempdir()?;
+    let socket = temp.path().join("dg-core.sock");
+    let listener = UnixListener::bind(&socket)?;
+    let connections = Arc::new(AtomicUsize::new(0));
+    tokio::spawn(serve_rate_limited(listener, connections.clone()));
+
+    let client =
+        BridgeClient::new(BridgeConfig::new(vec![Endpoint::Unix(socket)]).with_retries(3))?;
+    let response = client.send_request(ping()).await?;
+    let error = response.error.expect("rate limit error");
+    assert_eq!(error.code, RATE_LIMITED);
+    assert_eq!(error.kind(), "rpc.rate_limited");
+    assert!(error.is_limit_violation());
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+    assert_eq!(client.open_connections().await, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn oversized_requests_are_refused_before_sending() -> Result<()> {
+    let temp = tempdir()?;
+    let socket = temp.path().join("dg-core.sock");
+    let listener = UnixListener::bind(&socket)?;
+    let connections = Arc::new(AtomicUsize::new(0));
+    tokio::spawn(serve_rate_limited(listener, connections.clone()));
+
+    let client = BridgeClient::new(
+        BridgeConfig::new(vec![Endpoint::Unix(socket)]).with_max_frame_bytes(64),
+    )?;
+    let err = client
+        .send_request(RpcRequest {
+            params: Some(json!({ "text": "x".repeat(128) })),
+            ..ping()
+        })
+        .await
+        .expect_err("request over the frame limit");
+    assert!(err.to_string().contains("at most 64"));
+    let error = err.downcast_ref::<RpcError>().expect("typed frame error");
+    assert_eq!(error.code, REQUEST_TOO_LARGE);
+    assert_eq!(error.data.as_ref().expect("data")["limit"], 64);
+    assert_eq!(connections.load(Ordering::SeqCst), 0);
+    Ok(())
+}
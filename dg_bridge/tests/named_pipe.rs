@@ -0,0 +1,196 @@
+#![cfg(windows)]
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use common::request;
+use dg_bridge::{BridgeClient, BridgeConfig, Endpoint, LOG_METHOD};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+fn pipe_name(test: &str) -> String {
+    format!(r"\\.\pipe\dg_bridge_{test}_{}", std::process::id())
+}
+
+fn replies(request: &Value) -> Vec<u8> {
+    let mut messages = vec![json!({
+        "jsonrpc": "2.0",
+        "id": request["id"],
+        "result": { "method": request["method"] }
+    })];
+    if request["method"] == "core.subscribe" {
+        for index in 0..3 {
+            messages.push(json!({
+                "jsonrpc": "2.0",
+                "method": LOG_METHOD,
+                "params": { "msg": format!("event {index}") }
+            }));
+        }
+    }
+    let mut payload = Vec::new();
+    for message in messages {
+        payload.extend(serde_json::to_vec(&message).expect("encode"));
+        payload.push(b'\n');
+    }
+    payload
+}
+
+async fn serve_connection(server: NamedPipeServer, served: Arc<AtomicUsize>) {
+    let (read, mut write) = tokio::io::split(server);
+    let mut lines = BufReader::new(read).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let request: Value = serde_json::from_str(&line).expect("request json");
+        served.fetch_add(1, Ordering::SeqCst);
+        if write.write_all(&replies(&request)).await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn serve(name: String, mut server: NamedPipeServer, served: Arc<AtomicUsize>) {
+    let mut connections = JoinSet::new();
+    loop {
+        if server.connect().await.is_err() {
+            return;
+        }
+        let connected = server;
+        server = ServerOptions::new()
+            .create(&name)
+            .expect("next pipe instance");
+        connections.spawn(serve_connection(connected, served.clone()));
+    }
+}
+
+fn listen(name: &str, served: &Arc<AtomicUsize>) -> Result<tokio::task::JoinHandle<()>> {
+    let first = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(name)?;
+    Ok(tokio::spawn(serve(name.to_string(), first, served.clone())))
+}
+
+fn client(name: &str) -> Result<BridgeClient> {
+    BridgeClient::new(
+        BridgeConfig::new(vec![Endpoint::NamedPipe(name.to_string())])
+            .with_timeout(Duration::from_secs(5)),
+    )
+}
+
+#[tokio::test]
+async fn pipe_requests_share_one_connection() -> Result<()> {
+    let name = pipe_name("pool");
+    let served = Arc::new(AtomicUsize::new(0));
+    let _server = listen(&name, &served)?;
+    let client = client(&name)?;
+
+    for id in ["one", "two", "three"] {
+        let response = client.send_request(request(id)).await?;
+        assert_eq!(response.id, id);
+        assert_eq!(
+            response.result,
+            Some(json!({ "method": "core.get_status" }))
+        );
+    }
+    assert_eq!(client.open_connections().await, 1);
+
+    let (left, right) = tokio::join!(
+        client.send_request(request("left")),
+        client.send_request(request("right"))
+    );
+    assert_eq!(left?.id, "left");
+    assert_eq!(right?.id, "right");
+    assert_eq!(client.open_connections().await, 1);
+    assert_eq!(served.load(Ordering::SeqCst), 5);
+    Ok(())
+}
+
+#[tokio::test]
+async fn pipe_subscriptions_deliver_notifications() -> Result<()> {
+    let name = pipe_name("subscribe");
+    let _server = listen(&name, &Arc::new(AtomicUsize::new(0)))?;
+    let client = client(&name)?;
+
+    let mut notifications = client.subscribe_topic("logs").await?;
+    for index in 0..3 {
+        let notification = timeout(Duration::from_secs(2), notifications.recv())
+            .await?
+            .expect("notification stream open");
+        assert_eq!(notification.method, LOG_METHOD);
+        assert_eq!(notification.params["msg"], format!("event {index}"));
+    }
+    let response = client.send_request(request("after-subscribe")).await?;
+    assert_eq!(response.id, "after-subscribe");
+    Ok(())
+}
+
+#[tokio::test]
+async fn busy_pipes_are_retried_by_concurrent_clients() -> Result<()> {
+    let name = pipe_name("busy");
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .max_instances(1)
+        .create(&name)?;
+    let served = Arc::new(AtomicUsize::new(0));
+    let counter = served.clone();
+    let _server = tokio::spawn(async move {
+        while server.connect().await.is_ok() {
+            let mut reader = BufReader::new(&mut server);
+            let mut line = String::new();
+            if reader.read_line(&mut line).await.is_ok() && !line.is_empty() {
+                let request: Value = serde_json::from_str(&line).expect("request json");
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                server
+                    .write_all(&replies(&request))
+                    .await
+                    .expect("write reply");
+                server.flush().await.expect("flush");
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+            let _ = server.disconnect();
+        }
+    });
+
+    let mut clients = JoinSet::new();
+    for index in 0..4 {
+        let client = BridgeClient::new(
+            BridgeConfig::new(vec![Endpoint::NamedPipe(name.clone())])
+                .with_timeout(Duration::from_secs(5)),
+        )?;
+        clients.spawn(async move { client.send_request(request(&format!("busy-{index}"))).await });
+    }
+    while let Some(joined) = clients.join_next().await {
+        let response = joined??;
+        assert!(response.id.starts_with("busy-"), "{response:?}");
+    }
+    assert_eq!(served.load(Ordering::SeqCst), 4);
+    Ok(())
+}
+
+#[tokio::test]
+async fn clients_reconnect_after_the_pipe_server_restarts() -> Result<()> {
+    let name = pipe_name("restart");
+    let served = Arc::new(AtomicUsize::new(0));
+    let server = listen(&name, &served)?;
+    let client = client(&name)?;
+    client.send_request(request("before")).await?;
+    assert_eq!(client.open_connections().await, 1);
+
+    server.abort();
+    let _ = server.await;
+    client
+        .send_request(request("while-down"))
+        .await
+        .expect_err("no pipe instance while the server is down");
+
+    let _server = listen(&name, &served)?;
+    let response = client.send_request(request("after")).await?;
+    assert_eq!(response.id, "after");
+    assert_eq!(served.load(Ordering::SeqCst), 2);
+    Ok(())
+}
@@ -0,0 +1,214 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use common::request;
+use dg_bridge::{BridgeClient, BridgeConfig, Capability, Endpoint, RpcRequest, SessionAuthority};
+use serde_json::{json, Value};
+use tempfile::tempdir;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixListener;
+
+async fn serve_counting(
+    listener: UnixListener,
+    connections: Arc<AtomicUsize>,
+    authority: SessionAuthority,
+) {
+    while let Ok((stream, _)) = listener.accept().await {
+        connections.fetch_add(1, Ordering::SeqCst);
+        let authority = authority.clone();
+        tokio::spawn(async move {
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+            let mut authenticated = false;
+            while let Ok(Some(line)) = lines.next_line().await {
+                let request: Value = serde_json::from_str(&line).expect("request json");
+                let reply = if request["method"] == "core.authenticate" {
+                    let token = request["params"]["token"].as_str().expect("token");
+                    authenticated = authority.verify(token).is_ok();
+                    json!({ "jsonrpc": "2.0", "id": request["id"], "result": { "ok": authenticated } })
+                } else if !authenticated {
+                    json!({ "jsonrpc": "2.0", "id": request["id"], "error": { "code": -32011 } })
+                } else {
+                    json!({ "jsonrpc": "2.0", "id": request["id"], "result": { "method": request["method"] } })
+                };
+                let stale = json!({ "jsonrpc": "2.0", "id": null, "error": { "code": -32000, "message": "Request timed out" } });
+                let mut payload = Vec::new();
+                for message in [stale, reply] {
+                    payload.extend(serde_json::to_vec(&message).expect("encode"));
+                    payload.push(b'\n');
+                }
+                if write.write_all(&payload).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+#[tokio::test]
+async fn requests_share_one_authenticated_connection() -> Result<()> {
+    let temp = tempdir()?;
+    let socket = temp.path().join("dg-core.sock");
+    let listener = UnixListener::bind(&socket)?;
+    let connections = Arc::new(AtomicUsize::new(0));
+    let authority = SessionAuthority::generate();
+    tokio::spawn(serve_counting(
+        listener,
+        connections.clone(),
+        authority.clone(),
+    ));
+
+    let client = BridgeClient::new(
+        BridgeConfig::new(vec![Endpoint::Unix(socket)])
+            .with_timeout(Duration::from_secs(2))
+            .with_session_authority(
+                authority,
+                "desktop-shell",
+                &[Capability::Status],
+                Duration::from_secs(60),
+            ),
+    )?;
+    let shared = client.clone();
+
+    for id in ["one", "two", "three"] {
+        let response = shared.send_request(request(id)).await?;
+        assert_eq!(response.id, id);
+        assert_eq!(
+            response.result,
+            Some(json!({ "method": "core.get_status" }))
+        );
+    }
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+    assert_eq!(client.open_connections().await, 1);
+
+    let (left, right) = tokio::join!(
+        client.send_request(request("left")),
+        client.send_request(request("right"))
+    );
+    assert_eq!(left?.id, "left");
+    assert_eq!(right?.id, "right");
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+    assert_eq!(client.open_connections().await, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn expired_idle_connections_are_replaced() -> Result<()> {
+    let temp = tempdir()?;
+    let socket = temp.path().join("dg-core.sock");
+    let listener = UnixListener::bind(&socket)?;
+    let connections = Arc::new(AtomicUsize::new(0));
+    let authority = SessionAuthority::generate();
+    let token = authority.mint("cli", &[Capability::Status], Duration::from_secs(60))?;
+    tokio::spawn(serve_counting(listener, connections.clone(), authority));
+
+    let client = BridgeClient::new(
+        BridgeConfig::new(vec![Endpoint::Unix(socket)])
+            .with_idle_timeout(Duration::ZERO)
+            .with_session_token(token),
+    )?;
+    client.send_request(request("first")).await?;
+    client.send_request(request("second")).await?;
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn handshakes_carry_the_device_id() -> Result<()> {
+    let temp = tempdir()?;
+    let socket = temp.path().join("dg-core.sock");
+    let listener = UnixListener::bind(&socket)?;
+    let authority = SessionAuthority::generate();
+    let token = authority.mint("cli", &[Capability::Status], Duration::from_secs(60))?;
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.expect("accept");
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+        let mut handshake = Value::Null;
+        while let Ok(Some(line)) = lines.next_line().await {
+            let request: Value = serde_json::from_str(&line).expect("request json");
+            if request["method"] == "core.authenticate" {
+                handshake = request["params"].clone();
+            }
+            let reply = json!({ "jsonrpc": "2.0", "id": request["id"], "result": { "ok": true } });
+            let mut payload = serde_json::to_vec(&reply).expect("encode");
+            payload.push(b'\n');
+            write.write_all(&payload).await.expect("reply");
+            if request["method"] != "core.authenticate" {
+                break;
+            }
+        }
+        handshake
+    });
+
+    let client = BridgeClient::new(
+        BridgeConfig::new(vec![Endpoint::Unix(socket)])
+            .with_session_token(token.clone())
+            .with_device_id("4f1c2a9be07d4c55a1d2e3f4a5b6c7d8"),
+    )?;
+    client.send_request(request("first")).await?;
+    let handshake = server.await?;
+    assert_eq!(handshake["token"], json!(token));
+    assert_eq!(
+        handshake["device"],
+        json!("4f1c2a9be07d4c55a1d2e3f4a5b6c7d8")
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn concurrent_responses_are_matched_by_id_over_one_stream() -> Result<()> {
+    let temp = tempdir()?;
+    let socket = temp.path().join("dg-core.sock");
+    let listener = UnixListener::bind(&socket)?;
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.expect("accept");
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+        let mut requests = Vec::new();
+        while requests.len() < 3 {
+            let line = lines.next_line().await.expect("read").expect("request");
+            requests.push(serde_json::from_str::<Value>(&line).expect("request json"));
+        }
+        // Answer newest first, with a notification in between.
+        let mut payload = Vec::new();
+        let notification = json!({ "jsonrpc": "2.0", "method": "core.log", "params": {} });
+        payload.extend(serde_json::to_vec(&notification).expect("encode"));
+        payload.push(b'\n');
+        for request in requests.iter().rev() {
+            let reply =
+                json!({ "jsonrpc": "2.0", "id": request["id"], "result": request["params"] });
+            payload.extend(serde_json::to_vec(&reply).expect("encode"));
+            payload.push(b'\n');
+        }
+        write.write_all(&payload).await.expect("reply");
+        let _ = lines.next_line().await;
+    });
+
+    let client = BridgeClient::new(BridgeConfig::new(vec![Endpoint::Unix(socket)]))?;
+    let call = |id: &str, n: u64| {
+        client.send_request(RpcRequest {
+            params: Some(json!({ "n": n })),
+            ..request(id)
+        })
+    };
+    let (first, second, third) = tokio::join!(call("same", 1), call("same", 2), call("other", 3));
+    for (response, id, n) in [
+        (first?, "same", 1),
+        (second?, "same", 2),
+        (third?, "other", 3),
+    ] {
+        assert_eq!(response.id, id);
+        assert_eq!(response.result, Some(json!({ "n": n })));
+    }
+    assert_eq!(client.open_connections().await, 1);
+    drop(client);
+    server.await?;
+    Ok(())
+}
@@ -0,0 +1,281 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use dg_bridge::{BridgeClient, BridgeConfig, Endpoint, RpcRequest};
+use serde_json::json;
+use tempfile::{tempdir, TempDir};
+use tokio::process::{Child, Command};
+use tokio::time::timeout;
+
+const ENABLE_ENV: &str = "DG_BRIDGE_REAL_DAEMON";
+const BINARY_ENV: &str = "DG_CORE_BIN";
+const PYTHON_ENV: &str = "DG_CORE_PYTHON";
+const READY_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn enabled(test: &str) -> bool {
+    let enabled = std::env::var(ENABLE_ENV).is_ok_and(|value| value == "1");
+    if !enabled {
+        eprintln!("skipping {test}: set {ENABLE_ENV}=1 to run against a real DG Core daemon");
+    }
+    enabled
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transport {
+    #[cfg(unix)]
+    Unix,
+    #[cfg(windows)]
+    Pipe,
+    Tcp,
+}
+
+fn transports() -> Vec<Transport> {
+    vec![
+        #[cfg(unix)]
+        Transport::Unix,
+        #[cfg(windows)]
+        Transport::Pipe,
+        Transport::Tcp,
+    ]
+}
+
+fn native() -> Transport {
+    transports()[0]
+}
+
+struct Daemon {
+    child: Child,
+    args: Vec<String>,
+    endpoint: Endpoint,
+    _dir: TempDir,
+}
+
+impl Daemon {
+    async fn spawn(transport: Transport) -> Result<Self> {
+        let dir = tempdir()?;
+        let (args, endpoint) = match transport {
+            #[cfg(unix)]
+            Transport::Unix => {
+                let socket = dir.path().join("dg-core.sock");
+                (
+                    vec!["--socket".to_string(), socket.display().to_string()],
+                    Endpoint::Unix(socket),
+                )
+            }
+            #[cfg(windows)]
+            Transport::Pipe => {
+                let name = format!(
+                    "data_guardian_bridge_{}_{}",
+                    std::process::id(),
+                    dir.path()
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default()
+                );
+                (
+                    vec!["--pipe".to_string(), name.clone()],
+                    Endpoint::NamedPipe(name),
+                )
+            }
+            Transport::Tcp => {
+                let addr = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+                (
+                    vec!["--tcp".to_string(), addr.to_string()],
+                    Endpoint::Tcp(addr),
+                )
+            }
+        };
+        let child = launch(&args)?;
+        let mut daemon = Self {
+            child,
+            args,
+            endpoint,
+            _dir: dir,
+        };
+        daemon.wait_ready().await?;
+        Ok(daemon)
+    }
+
+    fn client(&self) -> Result<BridgeClient> {
+        BridgeClient::new(
+            BridgeConfig::new(vec![self.endpoint.clone()]).with_timeout(Duration::from_secs(5)),
+        )
+    }
+
+    async fn wait_ready(&mut self) -> Result<()> {
+        let client = self.client()?;
+        let deadline = Instant::now() + READY_TIMEOUT;
+        loop {
+            if let Some(status) = self.child.try_wait()? {
+                bail!("daemon exited during startup with {status}");
+            }
+            if ping(&client, "ready").await.is_ok() {
+                return Ok(());
+            }
+            if Instant::now() > deadline {
+                bail!(
+                    "daemon on {} did not answer within {READY_TIMEOUT:?}",
+                    self.endpoint
+                );
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    async fn kill(&mut self) -> Result<()> {
+        self.child.kill().await?;
+        self.child.wait().await?;
+        Ok(())
+    }
+
+    async fn restart(&mut self) -> Result<()> {
+        if self.child.try_wait()?.is_none() {
+            self.kill().await?;
+        }
+        self.child = launch(&self.args)?;
+        self.wait_ready().await
+    }
+}
+
+fn repo_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("workspace root")
+        .to_path_buf()
+}
+
+fn launch(args: &[String]) -> Result<Child> {
+    let mut command = match std::env::var_os(BINARY_ENV) {
+        Some(binary) => Command::new(binary),
+        None => {
+            let python = std::env::var_os(PYTHON_ENV)
+                .unwrap_or_else(|| if cfg!(windows) { "python" } else { "python3" }.into());
+            let mut command = Command::new(python);
+            command
+                .args(["-m", "dg_core.daemon.server"])
+                .env("PYTHONPATH", repo_root().join("dg_core").join("src"))
+                .env("PYTHONUNBUFFERED", "1");
+            command
+        }
+    };
+    command
+        .args(args)
+        .current_dir(repo_root())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .kill_on_drop(true);
+    command
+        .spawn()
+        .context("failed to start the DG Core daemon")
+}
+
+async fn ping(client: &BridgeClient, id: &str) -> Result<()> {
+    let response = client
+        .send_request(RpcRequest {
+            id: id.into(),
+            method: "core.ping".into(),
+            params: Some(json!({})),
+        })
+        .await?;
+    if response.result.is_none() {
+        bail!("core.ping failed: {:?}", response.error);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn every_transport_round_trips_against_the_daemon() -> Result<()> {
+    if !enabled("every_transport_round_trips_against_the_daemon") {
+        return Ok(());
+    }
+    for transport in transports() {
+        let mut daemon = Daemon::spawn(transport).await?;
+        let client = daemon.client()?;
+        ping(&client, "ping")
+            .await
+            .with_context(|| format!("{transport:?}"))?;
+
+        let methods = client.describe().await?;
+        assert!(
+            methods
+                .iter()
+                .any(|method| method.name == "core.get_status"),
+            "{transport:?}: {methods:?}"
+        );
+        let status = client
+            .send_request(RpcRequest {
+                id: "status".into(),
+                method: "core.get_status".into(),
+                params: None,
+            })
+            .await?;
+        assert!(status.result.is_some(), "{transport:?}: {status:?}");
+        daemon.kill().await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn clients_recover_after_the_daemon_is_killed_and_restarted() -> Result<()> {
+    if !enabled("clients_recover_after_the_daemon_is_killed_and_restarted") {
+        return Ok(());
+    }
+    for transport in transports() {
+        let mut daemon = Daemon::spawn(transport).await?;
+        let client = daemon.client()?;
+        ping(&client, "before").await?;
+        assert_eq!(client.open_connections().await, 1);
+
+        daemon.kill().await?;
+        ping(&client, "while-down")
+            .await
+            .expect_err("requests fail while the daemon is down");
+
+        daemon.restart().await?;
+        ping(&client, "after")
+            .await
+            .with_context(|| format!("{transport:?}: pooled client did not reconnect"))?;
+        daemon.kill().await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn slow_subscribers_do_not_stall_other_clients() -> Result<()> {
+    if !enabled("slow_subscribers_do_not_stall_other_clients") {
+        return Ok(());
+    }
+    let mut daemon = Daemon::spawn(native()).await?;
+    let subscriber = daemon.client()?;
+    let mut logs = subscriber.subscribe_topic("logs").await?;
+
+    let client = daemon.client()?;
+    let started = Instant::now();
+    for index in 0..80 {
+        let sent = Instant::now();
+        ping(&client, &format!("busy-{index}")).await?;
+        assert!(
+            sent.elapsed() < Duration::from_secs(2),
+            "ping {index} took {:?} while a subscriber was not reading",
+            sent.elapsed()
+        );
+    }
+    for index in 0..20 {
+        let fresh = daemon.client()?;
+        ping(&fresh, &format!("fresh-{index}")).await?;
+    }
+    eprintln!(
+        "100 requests beside a stalled subscriber in {:?}",
+        started.elapsed()
+    );
+
+    let notification = timeout(Duration::from_secs(5), logs.recv())
+        .await
+        .context("stalled subscriber received nothing")?
+        .expect("log stream open");
+    assert_eq!(notification.method, "core.log");
+    daemon.kill().await?;
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use dg_bridge::{Capability, SessionAuthority};
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn session_keys_are_created_owner_only_and_reloaded() -> Result<()> {
+    let temp = tempdir()?;
+    let path = temp.path().join("ipc").join("session.key");
+    let authority = SessionAuthority::load_or_create(&path).await?;
+    let token = authority.mint("cli", &[Capability::Status], Duration::from_secs(60))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path)?.permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    let leftovers: Vec<_> = std::fs::read_dir(path.parent().expect("parent"))?.collect();
+    assert_eq!(leftovers.len(), 1);
+
+    let reloaded = SessionAuthority::load_or_create(&path).await?;
+    assert_eq!(reloaded.verify(&token)?.sub, "cli");
+    Ok(())
+}
@@ -0,0 +1,186 @@
+#![cfg(unix)]
+
+use std::pin::Pin;
+use std::time::Duration;
+
+use anyhow::Result;
+use dg_bridge::{
+    BridgeClient, BridgeConfig, Capability, Endpoint, ProgressUpdate, SessionAuthority, LOG_METHOD,
+    TAIL_LOGS_METHOD,
+};
+use futures_core::Stream;
+use serde_json::{json, Value};
+use tempfile::tempdir;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixListener;
+use tokio::time::timeout;
+
+async fn serve_progress(listener: UnixListener, authority: Option<SessionAuthority>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        let authority = authority.clone();
+        tokio::spawn(async move {
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+            let Ok(Some(mut line)) = lines.next_line().await else {
+                return;
+            };
+            if let Some(authority) = authority {
+                let request: Value = serde_json::from_str(&line).expect("handshake json");
+                assert_eq!(request["method"], "core.authenticate");
+                let token = request["params"]["token"].as_str().expect("token");
+                let claims = authority.verify(token).expect("valid token");
+                assert!(claims.allows(Capability::Progress));
+                let mut ack = serde_json::to_vec(&json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": { "subject": claims.policy_subject() }
+                }))
+                .expect("encode");
+                ack.push(b'\n');
+                write.write_all(&ack).await.expect("write");
+                let Ok(Some(next)) = lines.next_line().await else {
+                    return;
+                };
+                line = next;
+            }
+            let request: Value = serde_json::from_str(&line).expect("request json");
+            assert_eq!(request["method"], "core.subscribe");
+            assert_eq!(request["params"]["topic"], "progress");
+            let messages = [
+                json!({ "jsonrpc": "2.0", "id": request["id"], "result": { "subscribed": true } }),
+                json!({ "jsonrpc": "2.0", "method": "core.log", "params": { "msg": "noise" } }),
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "core.progress",
+                    "params": {
+                        "op_id": "op-7",
+                        "method": "core.scan_path",
+                        "stage": "scan",
+                        "bytes": 512,
+                        "total_bytes": 1024,
+                        "percent": 50.0
+                    }
+                }),
+            ];
+            for message in messages {
+                let mut line = serde_json::to_vec(&message).expect("encode");
+                line.push(b'\n');
+                write.write_all(&line).await.expect("write");
+            }
+        });
+    }
+}
+
+async fn subscribe_with(config: BridgeConfig) -> Result<()> {
+    let client = BridgeClient::connect(config.with_timeout(Duration::from_secs(2))).await?;
+    let mut notifications = client.subscribe_topic("progress").await?;
+
+    let mut updates = Vec::new();
+    while updates.is_empty() {
+        let notification = timeout(Duration::from_secs(2), notifications.recv())
+            .await?
+            .expect("notification stream open");
+        updates.extend(ProgressUpdate::from_notification(&notification));
+    }
+
+    assert_eq!(
+        updates[0],
+        ProgressUpdate {
+            op_id: "op-7".into(),
+            method: Some("core.scan_path".into()),
+            stage: "scan".into(),
+            bytes: 512,
+            total_bytes: Some(1024),
+            percent: Some(50.0),
+        }
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn subscribe_delivers_progress_notifications() -> Result<()> {
+    let temp = tempdir()?;
+    let socket = temp.path().join("dg-core.sock");
+    let listener = UnixListener::bind(&socket)?;
+    tokio::spawn(serve_progress(listener, None));
+
+    subscribe_with(BridgeConfig::new(vec![Endpoint::Unix(socket)])).await
+}
+
+#[tokio::test]
+async fn subscribe_authenticates_with_session_token() -> Result<()> {
+    let temp = tempdir()?;
+    let socket = temp.path().join("dg-core.sock");
+    let listener = UnixListener::bind(&socket)?;
+    let authority = SessionAuthority::generate();
+    let token = authority.mint(
+        "desktop-shell",
+        &[Capability::Progress],
+        Duration::from_secs(60),
+    )?;
+    tokio::spawn(serve_progress(listener, Some(authority)));
+
+    subscribe_with(BridgeConfig::new(vec![Endpoint::Unix(socket)]).with_session_token(token)).await
+}
+
+#[tokio::test]
+async fn subscribe_streams_notifications_until_dropped() -> Result<()> {
+    let temp = tempdir()?;
+    let socket = temp.path().join("dg-core.sock");
+    let listener = UnixListener::bind(&socket)?;
+    let server = tokio::spawn(async move {
+        let mut closed = Vec::new();
+        for _ in 0..2 {
+            let (stream, _) = listener.accept().await.expect("accept");
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+            let line = lines.next_line().await.expect("read").expect("request");
+            let request: Value = serde_json::from_str(&line).expect("request json");
+            let mut messages = Vec::new();
+            if request["params"]["reject"] == true {
+                messages.push(json!({ "jsonrpc": "2.0", "id": request["id"], "error": { "code": -32012, "message": "denied" } }));
+            } else {
+                // The first entry races ahead of the confirmation.
+                messages.push(
+                    json!({ "jsonrpc": "2.0", "method": LOG_METHOD, "params": { "msg": "early" } }),
+                );
+                messages.push(json!({ "jsonrpc": "2.0", "id": request["id"], "result": { "subscribed": true } }));
+                messages.push(
+                    json!({ "jsonrpc": "2.0", "method": LOG_METHOD, "params": { "msg": "late" } }),
+                );
+            }
+            for message in messages {
+                let mut line = serde_json::to_vec(&message).expect("encode");
+                line.push(b'\n');
+                write.write_all(&line).await.expect("write");
+            }
+            closed.push(lines.next_line().await.expect("read").is_none());
+        }
+        closed
+    });
+
+    let client = BridgeClient::new(BridgeConfig::new(vec![Endpoint::Unix(socket)]))?;
+    let mut subscription = client.subscribe(TAIL_LOGS_METHOD, json!({})).await?;
+    let early = timeout(Duration::from_secs(2), subscription.recv())
+        .await?
+        .expect("early entry");
+    assert_eq!(early["method"], LOG_METHOD);
+    assert_eq!(early["params"]["msg"], "early");
+    let late = timeout(
+        Duration::from_secs(2),
+        std::future::poll_fn(|cx| Pin::new(&mut subscription).poll_next(cx)),
+    )
+    .await?
+    .expect("late entry");
+    assert_eq!(late["params"]["msg"], "late");
+    drop(subscription);
+
+    let err = client
+        .subscribe(TAIL_LOGS_METHOD, json!({ "reject": true }))
+        .await
+        .err()
+        .expect("rejected subscription");
+    assert!(err.to_string().contains("rejected"), "{err:#}");
+    assert_eq!(server.await?, vec![true, true]);
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use std::env;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+use dg_controller::inbox::PARTIAL_EXTENSION;
+use tokio::fs;
+
+/// Drop files into a Data Guardian inbox, where the desktop app encrypts them.
+///
+/// Point an application's "export to" or "print to file" target at this helper. Files are written
+/// under a hidden partial name and renamed once complete, so the inbox never picks up half a file.
+#[derive(Debug, Parser)]
+#[command(author, version, long_about = None)]
+struct Cli {
+    /// Inbox directory. Defaults to DG_INBOX
+    #[arg(long, value_name = "PATH")]
+    dir: Option<PathBuf>,
+
+    /// File name to use when reading from standard input
+    #[arg(long, value_name = "NAME")]
+    name: Option<String>,
+
+    /// Files to copy into the inbox. Reads standard input when none are given
+    #[arg(value_name = "FILE")]
+    files: Vec<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let dir = cli
+        .dir
+        .or_else(|| env::var_os("DG_INBOX").map(PathBuf::from))
+        .ok_or_else(|| anyhow!("no inbox directory; pass --dir or set DG_INBOX"))?;
+    fs::create_dir_all(&dir)
+        .await
+        .with_context(|| format!("unable to create inbox {}", dir.display()))?;
+
+    if cli.files.is_empty() {
+        let mut contents = Vec::new();
+        std::io::stdin().read_to_end(&mut contents)?;
+        let name = cli.name.unwrap_or_else(|| {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default();
+            format!("document-{now}")
+        });
+        let delivered = deliver(&dir, &name, &contents).await?;
+        println!("delivered {}", delivered.display());
+        return Ok(());
+    }
+    for file in &cli.files {
+        let name = file
+            .file_name()
+            .ok_or_else(|| anyhow!("{} is not a file", file.display()))?
+            .to_string_lossy()
+            .into_owned();
+        let contents = fs::read(file)
+            .await
+            .with_context(|| format!("unable to read {}", file.display()))?;
+        let delivered = deliver(&dir, &name, &contents).await?;
+        println!("delivered {}", delivered.display());
+    }
+    Ok(())
+}
+
+async fn deliver(dir: &Path, name: &str, contents: &[u8]) -> Result<PathBuf> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(anyhow!("'{name}' is not a usable inbox file name"));
+    }
+    let partial = dir.join(format!(
+        ".{name}.{}.{PARTIAL_EXTENSION}",
+        std::process::id()
+    ));
+    fs::write(&partial, contents)
+        .await
+        .with_context(|| format!("unable to write {}", partial.display()))?;
+    let target = available(dir, name).await;
+    if let Err(err) = fs::rename(&partial, &target).await {
+        let _ = fs::remove_file(&partial).await;
+        return Err(err).with_context(|| format!("unable to deliver {}", target.display()));
+    }
+    Ok(target)
+}
+
+// Never replace a file the inbox has not picked up yet.
+async fn available(dir: &Path, name: &str) -> PathBuf {
+    let candidate = dir.join(name);
+    if !fs::try_exists(&candidate).await.unwrap_or(false) {
+        return candidate;
+    }
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{extension}")),
+        _ => (name, String::new()),
+    };
+    let mut copy = 2;
+    loop {
+        let candidate = dir.join(format!("{stem} ({copy}){extension}"));
+        if !fs::try_exists(&candidate).await.unwrap_or(false) {
+            return candidate;
+        }
+        copy += 1;
+    }
+}
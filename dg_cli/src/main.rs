@@ -0,0 +1,1122 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use dg_bridge::{Endpoint, TransportKind};
+use dg_controller::{
+    doctor, Controller, DoctorOptions, EnvelopeComparison, MigrationOptions, MigrationReport,
+    NewerCopy, ReportFilter, ReportFormat,
+};
+use dg_core::api::{new_default, DGConfig};
+use dg_core::keyring::{KeyMetadata, Keyring};
+use dg_core::management;
+use dg_core::signature::{verify_with_pem, SIGNATURE_EXTENSION};
+use dg_core::{
+    AccessRequest, DoctorReport, Fingerprint, GrantAction, GrantRequest, KeyAgeState, KeyPurpose,
+    NewSigner, PolicyEngine, PolicyFormat, SelfTestReport, SignedCommand, SimulationReport,
+    TrustRole, TrustedSigner,
+};
+use directories::BaseDirs;
+
+#[derive(Debug, Parser)]
+#[command(author, version, about = "Data Guardian engine command line", long_about = None)]
+struct Cli {
+    /// Engine data directory. Defaults to DG_DATA_DIR or the desktop data directory
+    #[arg(long, value_name = "PATH", global = true)]
+    data_dir: Option<PathBuf>,
+
+    /// Profile recorded in envelope metadata
+    #[arg(long, default_value = "dev", global = true)]
+    profile: String,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Debug, Subcommand)]
+enum Commands {
+    /// Run crypto known-answer tests and envelope round-trip checks
+    Selftest {
+        /// Emit the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write a detached Ed25519 signature (`<FILE>.sig`) with a keyring signing key
+    Sign {
+        /// File to sign, usually a `.dgenc` envelope
+        #[arg(value_name = "FILE")]
+        path: PathBuf,
+
+        /// Key id or label of an imported Ed25519 private key
+        #[arg(long)]
+        signer: String,
+    },
+    /// Check a detached Ed25519 signature against a file
+    Verify {
+        /// Signed file
+        #[arg(value_name = "FILE")]
+        path: PathBuf,
+
+        /// Signature file. Defaults to `<FILE>.sig`
+        #[arg(long, value_name = "PATH")]
+        sig: Option<PathBuf>,
+
+        /// Key id or label from the keyring, or a path to an Ed25519 PEM public key
+        #[arg(long)]
+        signer: String,
+    },
+    /// Show a keyring key's fingerprint or check one read out by its owner
+    Fingerprint {
+        /// Key id or label from the keyring
+        #[arg(value_name = "KEY")]
+        key: String,
+
+        /// Fingerprint to compare: hex, word list or emoji rendering
+        #[arg(long, value_name = "FINGERPRINT")]
+        verify: Option<String>,
+    },
+    /// Convert envelopes in older container formats to the current format in place
+    Migrate {
+        /// Directory containing `.dgenc` envelopes
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+
+        /// Descend into subdirectories
+        #[arg(long, short)]
+        recursive: bool,
+
+        /// Report what would be converted without rewriting any file
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Emit the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Compare two envelope copies by header, checksum and provenance
+    Compare {
+        /// First envelope
+        #[arg(value_name = "FIRST")]
+        first: PathBuf,
+
+        /// Second envelope
+        #[arg(value_name = "SECOND")]
+        second: PathBuf,
+
+        /// Decrypt both copies and compare content hashes when the payloads differ
+        #[arg(long)]
+        decrypt: bool,
+
+        /// Emit the comparison as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Look for common misconfigurations and suggest fixes
+    Doctor {
+        /// DG Core Unix socket to probe. Defaults to the desktop runtime socket
+        #[arg(long, value_name = "PATH")]
+        socket: Option<PathBuf>,
+
+        /// DG Core named pipe to probe. Defaults to the desktop pipe on Windows
+        #[arg(long, value_name = "NAME")]
+        pipe: Option<String>,
+
+        /// DG Core loopback TCP endpoint to probe (host:port)
+        #[arg(long, value_name = "ADDR")]
+        tcp: Option<String>,
+
+        /// DG Core launcher to check. Defaults to the one the desktop app installs
+        #[arg(long, value_name = "PATH")]
+        core_binary: Option<PathBuf>,
+
+        /// Emit the findings as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Export what is encrypted, by whom and with which labels, from the index and audit log
+    Report {
+        /// Destination file
+        #[arg(value_name = "OUTPUT")]
+        output: PathBuf,
+
+        /// csv or json. Defaults to the output file's extension
+        #[arg(long)]
+        format: Option<ReportFormat>,
+
+        /// Only envelopes carrying this label; repeat to require several
+        #[arg(long = "label", value_name = "LABEL")]
+        labels: Vec<String>,
+
+        /// Metadata filter, `field=value` or a bare `field`; repeat to require several
+        #[arg(long = "metadata", value_name = "FILTER")]
+        metadata: Vec<String>,
+
+        /// Only envelopes encrypted by this subject
+        #[arg(long, value_name = "SUBJECT")]
+        encrypted_by: Option<String>,
+
+        /// Only envelopes encrypted at or after this unix time
+        #[arg(long, value_name = "SECONDS")]
+        since: Option<u64>,
+
+        /// Only envelopes encrypted at or before this unix time
+        #[arg(long, value_name = "SECONDS")]
+        until: Option<u64>,
+    },
+    /// Work with policy documents without touching the data directory
+    Policy {
+        #[command(subcommand)]
+        command: PolicyCommand,
+    },
+    /// Manage the signed list of signers trusted for policies, envelopes and runtime bundles
+    Trust {
+        #[command(subcommand)]
+        command: TrustCommand,
+    },
+    /// Apply signed management commands from the organization
+    Manage {
+        #[command(subcommand)]
+        command: ManageCommand,
+    },
+    /// Share decryption of one envelope through signed, expiring grant files
+    Grant {
+        #[command(subcommand)]
+        command: GrantCommand,
+    },
+    /// Decrypt through an air-gapped machine by carrying request and response files across
+    Offline {
+        #[command(subcommand)]
+        command: OfflineCommand,
+    },
+    /// Create and list the X25519 identities envelopes can be encrypted to
+    Identity {
+        #[command(subcommand)]
+        command: IdentityCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum IdentityCommand {
+    /// Generate an X25519 key pair and store it in the keyring
+    Generate {
+        /// Label recipients refer to the identity by
+        #[arg(value_name = "LABEL")]
+        label: String,
+
+        /// Generate an Ed25519 root identity with separate encryption, signing and escrow subkeys
+        #[arg(long)]
+        subkeys: bool,
+    },
+    /// List the keyring's own identities with their fingerprints
+    List {
+        /// Emit the identities as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show when each key reaches the configured maximum age
+    Expiry {
+        /// Emit the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Replace an identity with a new key and re-encrypt its envelopes
+    Rotate {
+        /// Label or key id of the identity to retire
+        #[arg(value_name = "KEY")]
+        key: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum GrantCommand {
+    /// Wrap an envelope's file key to a recipient in a signed `<FILE>.<KEY>.grant.json`
+    Issue {
+        /// Envelope to share
+        #[arg(value_name = "FILE")]
+        path: PathBuf,
+
+        /// Key id or label of the recipient's X25519 public key
+        #[arg(long)]
+        recipient: String,
+
+        /// Key id or label of an imported Ed25519 private key that signs the grant
+        #[arg(long)]
+        signer: String,
+
+        /// Hours until the grant expires
+        #[arg(long, value_name = "HOURS", default_value_t = 24)]
+        valid_for: u64,
+
+        /// Action the grant allows: decrypt or view. Repeat for several
+        #[arg(long = "allow", value_name = "ACTION", default_value = "decrypt", value_parser = parse_grant_action)]
+        actions: Vec<GrantAction>,
+
+        /// Directory for the grant file. Defaults to next to the envelope
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+    },
+    /// Decrypt an envelope with a grant addressed to one of your keys
+    Decrypt {
+        /// Envelope to decrypt
+        #[arg(value_name = "FILE")]
+        path: PathBuf,
+
+        /// Signed grant file
+        #[arg(long, value_name = "PATH")]
+        grant: PathBuf,
+
+        /// Directory for the decrypted file. Defaults to next to the envelope
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum OfflineCommand {
+    /// Write a `<FILE>.dgreq` decrypt request to carry to the air-gapped machine
+    Request {
+        /// Envelope to decrypt
+        #[arg(value_name = "FILE")]
+        path: PathBuf,
+
+        /// Directory for the request file. Defaults to next to the envelope
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+    },
+    /// On the air-gapped machine, answer a request with a `.dgresp` file
+    Answer {
+        /// Decrypt request file
+        #[arg(value_name = "REQUEST")]
+        request: PathBuf,
+
+        /// Directory for the response file. Defaults to next to the request
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+    },
+    /// Decrypt an envelope with the response to one of this machine's requests
+    Complete {
+        /// Envelope to decrypt
+        #[arg(value_name = "FILE")]
+        path: PathBuf,
+
+        /// Response file from the air-gapped machine
+        #[arg(long, value_name = "PATH")]
+        response: PathBuf,
+
+        /// Directory for the decrypted file. Defaults to next to the envelope
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ManageCommand {
+    /// Verify and execute a signed lock, wipe_keys or force_rotation command
+    Apply {
+        /// JSON file with the signed command (`payload` and `signature`)
+        #[arg(value_name = "FILE")]
+        command: PathBuf,
+    },
+    /// Reopen a locked data directory with a signed unlock command
+    Unlock {
+        /// JSON file with the signed command (`payload` and `signature`)
+        #[arg(value_name = "FILE")]
+        command: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum TrustCommand {
+    /// List trusted signers with their roles and fingerprints
+    List {
+        /// Emit the signers as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Trust an Ed25519 public key, or change the roles of a trusted signer
+    Import {
+        /// Name the signer is referred to by
+        #[arg(value_name = "ID")]
+        id: String,
+
+        /// Ed25519 PEM public key file
+        #[arg(long, value_name = "PATH")]
+        key: PathBuf,
+
+        /// Role to trust the key for: policy, envelope, runtime or management. Repeat for several
+        #[arg(long = "role", value_name = "ROLE", required = true, value_parser = parse_role)]
+        roles: Vec<TrustRole>,
+
+        /// Human-readable description
+        #[arg(long, default_value = "")]
+        label: String,
+
+        /// Keyring Ed25519 key that re-signs the trust store
+        #[arg(long, value_name = "KEY")]
+        sign_with: String,
+    },
+    /// Stop trusting a signer
+    Remove {
+        #[arg(value_name = "ID")]
+        id: String,
+
+        /// Keyring Ed25519 key that re-signs the trust store
+        #[arg(long, value_name = "KEY")]
+        sign_with: String,
+    },
+    /// Pin a signer to its current key so it cannot be replaced or removed
+    Pin {
+        #[arg(value_name = "ID")]
+        id: String,
+
+        /// Lift the pin instead
+        #[arg(long)]
+        unpin: bool,
+
+        /// Keyring Ed25519 key that re-signs the trust store
+        #[arg(long, value_name = "KEY")]
+        sign_with: String,
+    },
+    /// Check a runtime bundle's signed `SHA256SUMS` manifest against the runtime signers
+    VerifyBundle {
+        /// Bundle directory
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum PolicyCommand {
+    /// Evaluate a policy against a JSON array of example access requests
+    Simulate {
+        /// Policy document to test, e.g. a candidate `policy.json` or `policy.yaml`
+        #[arg(value_name = "POLICY")]
+        policy: PathBuf,
+
+        /// JSON array of `{subject, action, resource, expect?}` requests
+        #[arg(value_name = "REQUESTS")]
+        requests: PathBuf,
+
+        /// Emit the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Convert a policy between JSON and YAML, chosen by file extension
+    Convert {
+        /// Policy document to read
+        #[arg(value_name = "INPUT")]
+        input: PathBuf,
+
+        /// Destination; comments in an existing YAML file are carried over
+        #[arg(value_name = "OUTPUT")]
+        output: PathBuf,
+    },
+}
+
+#[tokio::main(flavor = "multi_thread")]
+async fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    let data_dir = resolve_data_dir(cli.data_dir)?;
+
+    match cli.command {
+        Commands::Selftest { json } => {
+            let engine = new_default();
+            if data_dir.exists() {
+                engine
+                    .init(DGConfig {
+                        profile: cli.profile,
+                        data_dir,
+                        ..Default::default()
+                    })
+                    .await?;
+            }
+            let report = engine.self_test().await?;
+            engine.shutdown().await?;
+            print_self_test(&report, json)?;
+            Ok(exit_code(report.passed))
+        }
+        Commands::Sign { path, signer } => {
+            let controller = Controller::new(new_default());
+            controller.boot(&cli.profile, data_dir, false).await?;
+            let signed = controller.sign_file(&path, &signer).await;
+            controller.shutdown().await?;
+            println!("wrote {}", signed?.display());
+            Ok(ExitCode::SUCCESS)
+        }
+        Commands::Verify { path, sig, signer } => {
+            let valid = if Path::new(&signer).is_file() {
+                let pem = tokio::fs::read_to_string(&signer).await?;
+                let sig = sig.unwrap_or_else(|| {
+                    let mut name = path.clone().into_os_string();
+                    name.push(".");
+                    name.push(SIGNATURE_EXTENSION);
+                    PathBuf::from(name)
+                });
+                verify_with_pem(
+                    &pem,
+                    &tokio::fs::read(&path).await?,
+                    &tokio::fs::read(&sig).await?,
+                )?
+            } else {
+                let controller = Controller::new(new_default());
+                controller.boot(&cli.profile, data_dir, false).await?;
+                let valid = controller
+                    .verify_signature(&path, sig.as_deref(), &signer)
+                    .await;
+                controller.shutdown().await?;
+                valid?
+            };
+            println!(
+                "{}  {}",
+                if valid { "VALID" } else { "INVALID" },
+                path.display()
+            );
+            Ok(exit_code(valid))
+        }
+        Commands::Fingerprint { key, verify } => match verify {
+            Some(presented) => {
+                let controller = Controller::new(new_default());
+                controller.boot(&cli.profile, data_dir, false).await?;
+                let check = controller.verify_fingerprint(&key, &presented).await;
+                controller.shutdown().await?;
+                let check = check?;
+                print_fingerprint(&check.label, &check.key_id, &check.fingerprint);
+                println!("{}", if check.matches { "MATCH" } else { "MISMATCH" });
+                Ok(exit_code(check.matches))
+            }
+            None => {
+                let keyring = Keyring::load(&data_dir).await?;
+                let meta = keyring
+                    .lookup(&key)
+                    .ok_or_else(|| anyhow!("no key named '{key}' in the keyring"))?;
+                print_fingerprint(&meta.label, &meta.id, &meta.compute_fingerprint()?);
+                Ok(ExitCode::SUCCESS)
+            }
+        },
+        Commands::Migrate {
+            dir,
+            recursive,
+            dry_run,
+            json,
+        } => {
+            let controller = Controller::new(new_default());
+            controller.boot(&cli.profile, data_dir, false).await?;
+            let report = controller
+                .migrate_envelopes(&dir, MigrationOptions { recursive, dry_run })
+                .await;
+            controller.shutdown().await?;
+            let report = report?;
+            print_migration(&report, json)?;
+            Ok(exit_code(report.succeeded()))
+        }
+        Commands::Compare {
+            first,
+            second,
+            decrypt,
+            json,
+        } => {
+            let controller = Controller::new(new_default());
+            if decrypt {
+                controller.boot(&cli.profile, data_dir, false).await?;
+            }
+            let comparison = controller.compare_envelopes(&first, &second, decrypt).await;
+            if decrypt {
+                controller.shutdown().await?;
+            }
+            let comparison = comparison?;
+            print_comparison(&comparison, json)?;
+            Ok(exit_code(comparison.same_content == Some(true)))
+        }
+        Commands::Doctor {
+            socket,
+            pipe,
+            tcp,
+            core_binary,
+            json,
+        } => {
+            let runtime_dir = runtime_dir()?;
+            let mut endpoints = Vec::new();
+            endpoints.extend(socket.map(Endpoint::Unix));
+            endpoints.extend(pipe.map(Endpoint::NamedPipe));
+            if let Some(addr) = tcp {
+                endpoints.push(Endpoint::from_user_input(TransportKind::Tcp, &addr)?);
+            }
+            if endpoints.is_empty() {
+                endpoints.push(default_endpoint(&runtime_dir));
+            }
+            let options = DoctorOptions {
+                endpoints,
+                core_binary: Some(core_binary.unwrap_or_else(|| {
+                    let launcher = if cfg!(windows) { "dg.cmd" } else { "dg" };
+                    runtime_dir.join("bin").join(launcher)
+                })),
+            };
+            let report = doctor::run(&data_dir, &options).await;
+            print_doctor(&report, json)?;
+            Ok(exit_code(report.healthy()))
+        }
+        Commands::Report {
+            output,
+            format,
+            labels,
+            metadata,
+            encrypted_by,
+            since,
+            until,
+        } => {
+            let format = format
+                .or_else(|| ReportFormat::from_path(&output))
+                .ok_or_else(|| anyhow!("pass --format csv or --format json"))?;
+            let filter = ReportFilter {
+                labels,
+                metadata,
+                encrypted_by,
+                since,
+                until,
+            };
+            let controller = Controller::new(new_default());
+            controller.boot(&cli.profile, data_dir, false).await?;
+            let exported = controller.export_report(filter, format, &output).await;
+            controller.shutdown().await?;
+            let exported = exported?;
+            println!(
+                "wrote {} ({} report, {} envelopes)",
+                exported.path.display(),
+                exported.format.as_str(),
+                exported.envelopes
+            );
+            Ok(ExitCode::SUCCESS)
+        }
+        Commands::Policy {
+            command:
+                PolicyCommand::Simulate {
+                    policy,
+                    requests,
+                    json,
+                },
+        } => {
+            let document = tokio::fs::read(&policy).await?;
+            let requests: Vec<AccessRequest> =
+                serde_json::from_slice(&tokio::fs::read(&requests).await?)
+                    .map_err(|err| anyhow!("invalid requests in {}: {err}", requests.display()))?;
+            let report =
+                PolicyEngine::simulate_as(&document, PolicyFormat::from_path(&policy), requests)
+                    .map_err(|err| anyhow!("{}: {err}", policy.display()))?;
+            print_simulation(&report, json)?;
+            Ok(exit_code(report.passed()))
+        }
+        Commands::Policy {
+            command: PolicyCommand::Convert { input, output },
+        } => {
+            let document = tokio::fs::read(&input).await?;
+            let from = PolicyFormat::from_path(&input);
+            let to = PolicyFormat::from_path(&output);
+            let comments = match tokio::fs::read_to_string(&output).await {
+                Ok(existing) if to == PolicyFormat::Yaml => Some(existing),
+                _ if from == PolicyFormat::Yaml => Some(String::from_utf8_lossy(&document).into()),
+                _ => None,
+            };
+            let converted = PolicyEngine::convert(&document, from, to, comments.as_deref())
+                .map_err(|err| anyhow!("{}: {err}", input.display()))?;
+            tokio::fs::write(&output, converted).await?;
+            println!("wrote {}", output.display());
+            Ok(ExitCode::SUCCESS)
+        }
+        Commands::Trust { command } => {
+            let controller = Controller::new(new_default());
+            controller.boot(&cli.profile, data_dir, false).await?;
+            let outcome = run_trust(&controller, command).await;
+            controller.shutdown().await?;
+            outcome
+        }
+        Commands::Manage {
+            command: ManageCommand::Apply { command },
+        } => {
+            let signed = read_signed_command(&command).await?;
+            let controller = Controller::new(new_default());
+            controller.boot(&cli.profile, data_dir, false).await?;
+            let outcome = controller.apply_management_command(&signed).await;
+            if !controller.locked() {
+                controller.shutdown().await?;
+            }
+            let outcome = outcome?;
+            println!(
+                "applied {} command {} from {}",
+                outcome.command.action.as_str(),
+                outcome.command.id,
+                outcome.signer
+            );
+            if let Some(job) = outcome.rotation_job {
+                println!("re-encryption job {job} queued");
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Commands::Manage {
+            command: ManageCommand::Unlock { command },
+        } => {
+            let signed = read_signed_command(&command).await?;
+            let (command, signer) = management::unlock(&data_dir, &signed, unix_now()).await?;
+            println!(
+                "unlocked {} with command {} from {}",
+                data_dir.display(),
+                command.id,
+                signer.id
+            );
+            Ok(ExitCode::SUCCESS)
+        }
+        Commands::Grant { command } => {
+            let controller = Controller::new(new_default());
+            controller.boot(&cli.profile, data_dir, false).await?;
+            let written = match command {
+                GrantCommand::Issue {
+                    path,
+                    recipient,
+                    signer,
+                    valid_for,
+                    actions,
+                    out_dir,
+                } => {
+                    let request = GrantRequest {
+                        recipient,
+                        signer,
+                        actions,
+                        expires_at: unix_now() + valid_for * 3600,
+                    };
+                    controller.issue_grant(&path, request, out_dir).await
+                }
+                GrantCommand::Decrypt {
+                    path,
+                    grant,
+                    out_dir,
+                } => controller.decrypt_with_grant(&path, &grant, out_dir).await,
+            };
+            controller.shutdown().await?;
+            println!("wrote {}", written?.display());
+            Ok(ExitCode::SUCCESS)
+        }
+        Commands::Offline { command } => {
+            let controller = Controller::new(new_default());
+            controller.boot(&cli.profile, data_dir, false).await?;
+            let written = match command {
+                OfflineCommand::Request { path, out_dir } => {
+                    controller.export_decrypt_request(&path, out_dir).await
+                }
+                OfflineCommand::Answer { request, out_dir } => {
+                    controller.answer_decrypt_request(&request, out_dir).await
+                }
+                OfflineCommand::Complete {
+                    path,
+                    response,
+                    out_dir,
+                } => {
+                    controller
+                        .complete_decrypt_request(&path, &response, out_dir)
+                        .await
+                }
+            };
+            controller.shutdown().await?;
+            println!("wrote {}", written?.display());
+            Ok(ExitCode::SUCCESS)
+        }
+        Commands::Identity { command } => {
+            let controller = Controller::new(new_default());
+            controller.boot(&cli.profile, data_dir, false).await?;
+            let outcome = run_identity(&controller, command).await;
+            controller.shutdown().await?;
+            outcome
+        }
+    }
+}
+
+async fn run_identity(controller: &Controller, command: IdentityCommand) -> Result<ExitCode> {
+    match command {
+        IdentityCommand::Generate {
+            label,
+            subkeys: false,
+        } => {
+            print_identity(&controller.generate_identity(&label).await?)?;
+        }
+        IdentityCommand::Generate {
+            label,
+            subkeys: true,
+        } => {
+            let hierarchy = controller.generate_key_hierarchy(&label).await?;
+            for meta in std::iter::once(&hierarchy.root).chain(&hierarchy.subkeys) {
+                print_identity(meta)?;
+            }
+        }
+        IdentityCommand::List { json } => {
+            let identities = controller.list_identities().await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&identities)?);
+                return Ok(ExitCode::SUCCESS);
+            }
+            if identities.is_empty() {
+                println!("no identities");
+            }
+            for identity in &identities {
+                print_identity(identity)?;
+            }
+        }
+        IdentityCommand::Expiry { json } => {
+            let report = controller.key_expiry().await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                return Ok(ExitCode::SUCCESS);
+            }
+            if report.policy.is_none() {
+                println!("no maximum key age configured");
+            }
+            for key in &report.keys {
+                println!(
+                    "{} ({})  {}  expires {}",
+                    key.label,
+                    key.key_id,
+                    format!("{:?}", key.state).to_lowercase(),
+                    key.expires_at
+                );
+            }
+            if report.in_state(KeyAgeState::Expired).next().is_some() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        IdentityCommand::Rotate { key } => {
+            let job = controller.rotate_identity(&key).await?;
+            let retired = job.key.id.clone();
+            let rewrapped = job.wait().await?;
+            println!("retired {retired}; re-encrypted {rewrapped} envelopes");
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn run_trust(controller: &Controller, command: TrustCommand) -> Result<ExitCode> {
+    let changed = match command {
+        TrustCommand::List { json } => {
+            let store = controller.trust_store().await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&store.signers)?);
+                return Ok(ExitCode::SUCCESS);
+            }
+            if store.signers.is_empty() {
+                println!("no trusted signers");
+            }
+            store.signers.iter().for_each(print_trusted_signer);
+            return Ok(ExitCode::SUCCESS);
+        }
+        TrustCommand::Import {
+            id,
+            key,
+            roles,
+            label,
+            sign_with,
+        } => {
+            let signer = NewSigner {
+                id,
+                label,
+                public_key: tokio::fs::read_to_string(&key).await?,
+                roles,
+            };
+            controller.import_trusted_signer(signer, &sign_with).await?
+        }
+        TrustCommand::Remove { id, sign_with } => {
+            let removed = controller.remove_trusted_signer(&id, &sign_with).await?;
+            println!("removed {}", removed.id);
+            return Ok(ExitCode::SUCCESS);
+        }
+        TrustCommand::Pin {
+            id,
+            unpin,
+            sign_with,
+        } => {
+            controller
+                .pin_trusted_signer(&id, !unpin, &sign_with)
+                .await?
+        }
+        TrustCommand::VerifyBundle { dir } => {
+            return match controller.verify_runtime_bundle(&dir).await {
+                Ok(Some(signer)) => {
+                    println!("VALID  {} (signed by {})", dir.display(), signer.id);
+                    Ok(ExitCode::SUCCESS)
+                }
+                Ok(None) => {
+                    println!(
+                        "no runtime signers are trusted; {} was not checked",
+                        dir.display()
+                    );
+                    Ok(ExitCode::SUCCESS)
+                }
+                Err(err) => {
+                    println!("INVALID  {}: {err}", dir.display());
+                    Ok(exit_code(false))
+                }
+            };
+        }
+    };
+    print_trusted_signer(&changed);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn parse_role(role: &str) -> Result<TrustRole, String> {
+    match role {
+        "policy" => Ok(TrustRole::Policy),
+        "envelope" => Ok(TrustRole::Envelope),
+        "runtime" => Ok(TrustRole::Runtime),
+        "management" => Ok(TrustRole::Management),
+        other => Err(format!(
+            "unknown role '{other}'; expected policy, envelope, runtime or management"
+        )),
+    }
+}
+
+fn parse_grant_action(action: &str) -> Result<GrantAction, String> {
+    match action {
+        "decrypt" => Ok(GrantAction::Decrypt),
+        "view" => Ok(GrantAction::View),
+        other => Err(format!(
+            "unknown action '{other}'; expected decrypt or view"
+        )),
+    }
+}
+
+async fn read_signed_command(path: &Path) -> Result<SignedCommand> {
+    serde_json::from_slice(&tokio::fs::read(path).await?)
+        .map_err(|err| anyhow!("invalid signed command in {}: {err}", path.display()))
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn print_trusted_signer(signer: &TrustedSigner) {
+    let roles: Vec<&str> = signer.roles.iter().map(|role| role.as_str()).collect();
+    let label = if signer.label.is_empty() {
+        &signer.id
+    } else {
+        &signer.label
+    };
+    print_fingerprint(label, &signer.id, &signer.fingerprint);
+    println!(
+        "  roles  {}{}",
+        roles.join(", "),
+        if signer.pinned { " (pinned)" } else { "" }
+    );
+}
+
+fn print_identity(meta: &KeyMetadata) -> Result<()> {
+    print_fingerprint(&meta.label, &meta.id, &meta.compute_fingerprint()?);
+    println!(
+        "  type   {}{}{}",
+        format!("{:?}", meta.algorithm).to_lowercase(),
+        match meta.purpose {
+            Some(KeyPurpose::Root) => ", root".to_owned(),
+            Some(purpose) => format!(", {purpose} subkey"),
+            None => String::new(),
+        },
+        if meta.revoked_at.is_some() {
+            " (revoked)"
+        } else {
+            ""
+        }
+    );
+    Ok(())
+}
+
+fn print_fingerprint(label: &str, key_id: &str, fingerprint: &Fingerprint) {
+    println!("{label} ({key_id})");
+    println!("  hex    {}", fingerprint.hex);
+    println!("  words  {}", fingerprint.words.join(" "));
+    println!("  emoji  {}", fingerprint.emoji);
+}
+
+fn print_migration(report: &MigrationReport, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(report)?);
+        return Ok(());
+    }
+    let verb = if report.dry_run {
+        "would migrate"
+    } else {
+        "migrated"
+    };
+    for entry in &report.migrated {
+        println!("{verb}  {} ({})", entry.path.display(), entry.from.as_str());
+    }
+    for failure in &report.failures {
+        println!("FAIL  {}: {}", failure.path.display(), failure.error);
+    }
+    println!(
+        "{} envelopes scanned: {} {verb}, {} already current, {} failed",
+        report.scanned,
+        report.migrated.len(),
+        report.current,
+        report.failures.len()
+    );
+    Ok(())
+}
+
+fn print_comparison(comparison: &EnvelopeComparison, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(comparison)?);
+        return Ok(());
+    }
+    for (name, summary) in [("first", &comparison.first), ("second", &comparison.second)] {
+        println!(
+            "{name:<8}{}  format {}, {} bytes, sha256 {}, {} re-encryptions",
+            summary.path.display(),
+            summary.format,
+            summary.size,
+            &summary.payload_sha256[..16],
+            summary.provenance.len()
+        );
+    }
+    if comparison.differences.is_empty() {
+        println!("differs none");
+    } else {
+        println!("differs {}", comparison.differences.join(", "));
+    }
+    let newer = match comparison.newer {
+        NewerCopy::First => "first",
+        NewerCopy::Second => "second",
+        NewerCopy::Same => "neither (identical)",
+        NewerCopy::Unknown => "unknown",
+    };
+    println!("newer   {newer} ({})", comparison.evidence.as_str());
+    let content = match comparison.same_content {
+        Some(true) => "same",
+        Some(false) => "different",
+        None => "unknown (pass --decrypt to compare content)",
+    };
+    println!("content {content}");
+    Ok(())
+}
+
+fn print_doctor(report: &DoctorReport, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(report)?);
+        return Ok(());
+    }
+    for finding in &report.findings {
+        let status = finding.severity.as_str().to_uppercase();
+        println!("{status:<7}  {}: {}", finding.check, finding.message);
+        if let Some(fix) = &finding.fix {
+            println!("         fix: {fix}");
+        }
+    }
+    let problems = report.problems().count();
+    if problems == 0 {
+        println!("no problems found ({} checks)", report.findings.len());
+    } else {
+        println!(
+            "{problems} of {} checks need attention",
+            report.findings.len()
+        );
+    }
+    Ok(())
+}
+
+fn print_simulation(report: &SimulationReport, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(report)?);
+        return Ok(());
+    }
+    for result in &report.results {
+        let request = &result.request;
+        let status = result.decision.as_str().to_uppercase();
+        let reason = match &result.rule {
+            Some(rule) => format!(
+                "rule #{} {} {} {}",
+                rule.index, rule.subject, rule.action, rule.resource
+            ),
+            None => format!("default_allow={}", report.default_allow),
+        };
+        let unexpected = match (result.as_expected, request.expect) {
+            (Some(false), Some(expected)) => {
+                format!("  UNEXPECTED, expected {}", expected.as_str())
+            }
+            _ => String::new(),
+        };
+        println!(
+            "{status:<5}  {} {} {}  ({reason}){unexpected}",
+            request.subject, request.action, request.resource
+        );
+    }
+    println!(
+        "{} requests: {} allowed, {} denied, {} unexpected",
+        report.results.len(),
+        report.allowed,
+        report.denied,
+        report.unexpected
+    );
+    Ok(())
+}
+
+fn print_self_test(report: &SelfTestReport, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(report)?);
+        return Ok(());
+    }
+    for check in &report.checks {
+        let status = if check.passed { "PASS" } else { "FAIL" };
+        match &check.detail {
+            Some(detail) => println!("{status}  {} ({detail})", check.name),
+            None => println!("{status}  {}", check.name),
+        }
+    }
+    let failed = report.failures().count();
+    if failed == 0 {
+        println!("self-test passed ({} checks)", report.checks.len());
+    } else {
+        println!(
+            "self-test FAILED ({failed} of {} checks)",
+            report.checks.len()
+        );
+    }
+    Ok(())
+}
+
+fn resolve_data_dir(flag: Option<PathBuf>) -> Result<PathBuf> {
+    if let Some(dir) = flag {
+        return Ok(dir);
+    }
+    if let Some(dir) = env::var_os("DG_DATA_DIR") {
+        return Ok(PathBuf::from(dir));
+    }
+    let base = BaseDirs::new().ok_or_else(|| anyhow!("unable to determine base directories"))?;
+    let name = if cfg!(windows) {
+        "DataGuardian"
+    } else {
+        "data_guardian"
+    };
+    Ok(base.data_dir().join(name))
+}
+
+fn runtime_dir() -> Result<PathBuf> {
+    let base = BaseDirs::new().ok_or_else(|| anyhow!("unable to determine base directories"))?;
+    Ok(match env::consts::OS {
+        "macos" => base.data_dir().join("Data Guardian"),
+        "windows" => base.config_dir().join("Data Guardian"),
+        _ => base.config_dir().join("data-guardian"),
+    })
+}
+
+fn default_endpoint(runtime_dir: &Path) -> Endpoint {
+    if cfg!(windows) {
+        Endpoint::NamedPipe(r"\\.\pipe\data_guardian_core".into())
+    } else {
+        Endpoint::Unix(runtime_dir.join("ipc").join("dg-core.sock"))
+    }
+}
+
+fn exit_code(success: bool) -> ExitCode {
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
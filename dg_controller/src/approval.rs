@@ -0,0 +1,160 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use dg_core::pairing::SealedMessage;
+use dg_core::{PairedDevice, PairingOffer, PairingResponse};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::fs;
+
+// The policy action checked before a device is paired or unpaired.
+pub const PAIR_ACTION: &str = "pair_device";
+pub const APPROVAL_ACTION: &str = "device_approval";
+pub const APPROVAL_TIMEOUT: Duration = Duration::from_secs(60);
+pub const PAIRING_TTL: Duration = Duration::from_secs(300);
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+const PAIRING_DIR: &str = "pairing";
+const CHALLENGE_SUFFIX: &str = ".challenge.json";
+const ANSWER_SUFFIX: &str = ".answer.json";
+
+// Carries sealed challenges to paired devices and their answers back. The channel only ever sees
+// sealed messages and signed pairing responses, so it does not need to be trusted.
+#[async_trait]
+pub trait ApprovalChannel: Send + Sync {
+    async fn publish(&self, device: &PairedDevice, message: SealedMessage) -> Result<()>;
+    async fn poll(&self, device: &PairedDevice, request_id: &str) -> Result<Option<SealedMessage>>;
+    async fn pairing_response(&self, offer: &PairingOffer) -> Result<Option<PairingResponse>>;
+}
+
+#[derive(Clone)]
+pub struct DeviceApproval {
+    pub channel: Arc<dyn ApprovalChannel>,
+    pub timeout: Duration,
+}
+
+impl DeviceApproval {
+    pub fn new(channel: Arc<dyn ApprovalChannel>) -> Self {
+        Self {
+            channel,
+            timeout: APPROVAL_TIMEOUT,
+        }
+    }
+}
+
+// A relay through a shared folder, such as one a phone app syncs: challenges are written under
+// `<root>/<device id>/`, answers next to them, and pairing responses under `<root>/pairing/`.
+#[derive(Debug, Clone)]
+pub struct DirectoryChannel {
+    root: PathBuf,
+}
+
+impl DirectoryChannel {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    // The paired device's side of the relay.
+    pub async fn pending_challenges(&self, device_id: &str) -> Result<Vec<SealedMessage>> {
+        let dir = self.root.join(device_id);
+        let mut challenges = Vec::new();
+        let mut entries = match fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(challenges),
+            Err(err) => {
+                return Err(err).with_context(|| format!("unable to read {}", dir.display()))
+            }
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Some(request_id) = name.strip_suffix(CHALLENGE_SUFFIX) else {
+                continue;
+            };
+            if !dir.join(format!("{request_id}{ANSWER_SUFFIX}")).exists() {
+                challenges.push(read_json(&entry.path()).await?);
+            }
+        }
+        Ok(challenges)
+    }
+
+    pub async fn answer(&self, device_id: &str, message: SealedMessage) -> Result<()> {
+        let path = self
+            .root
+            .join(device_id)
+            .join(format!("{}{ANSWER_SUFFIX}", message.request_id));
+        write_json(&path, &message).await
+    }
+
+    pub async fn post_pairing_response(
+        &self,
+        offer: &PairingOffer,
+        response: &PairingResponse,
+    ) -> Result<()> {
+        write_json(&self.pairing_path(offer), response).await
+    }
+
+    fn pairing_path(&self, offer: &PairingOffer) -> PathBuf {
+        self.root
+            .join(PAIRING_DIR)
+            .join(format!("{}.json", offer.pairing_id()))
+    }
+}
+
+#[async_trait]
+impl ApprovalChannel for DirectoryChannel {
+    async fn publish(&self, device: &PairedDevice, message: SealedMessage) -> Result<()> {
+        let path = self
+            .root
+            .join(&device.id)
+            .join(format!("{}{CHALLENGE_SUFFIX}", message.request_id));
+        write_json(&path, &message).await
+    }
+
+    async fn poll(&self, device: &PairedDevice, request_id: &str) -> Result<Option<SealedMessage>> {
+        let dir = self.root.join(&device.id);
+        let answer = dir.join(format!("{request_id}{ANSWER_SUFFIX}"));
+        if !answer.exists() {
+            return Ok(None);
+        }
+        let message = read_json(&answer).await?;
+        let _ = fs::remove_file(&answer).await;
+        let _ = fs::remove_file(dir.join(format!("{request_id}{CHALLENGE_SUFFIX}"))).await;
+        Ok(Some(message))
+    }
+
+    async fn pairing_response(&self, offer: &PairingOffer) -> Result<Option<PairingResponse>> {
+        let path = self.pairing_path(offer);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let response = read_json(&path).await?;
+        let _ = fs::remove_file(&path).await;
+        Ok(Some(response))
+    }
+}
+
+async fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let raw = fs::read(path)
+        .await
+        .with_context(|| format!("unable to read {}", path.display()))?;
+    serde_json::from_slice(&raw).with_context(|| format!("unreadable {}", path.display()))
+}
+
+// Written through a staging file so the other side never reads half a message.
+async fn write_json(path: &Path, value: &impl Serialize) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("unable to create {}", parent.display()))?;
+    }
+    let staging = path.with_extension("tmp");
+    fs::write(&staging, serde_json::to_vec(value)?)
+        .await
+        .with_context(|| format!("unable to write {}", staging.display()))?;
+    fs::rename(&staging, path)
+        .await
+        .with_context(|| format!("unable to write {}", path.display()))
+}
@@ -0,0 +1,317 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use dg_bridge::{BridgeClient, BridgeConfig};
+use dg_core::api::{DGConfig, DGResult, DataGuardian, EncryptRequest, Envelope, LoadedProfile};
+use dg_core::index::{IndexEntry, Reconciliation};
+use dg_core::{
+    AuditEvent, AuditFilter, DecisionExplanation, GrantAction, GrantRequest, Identity,
+    IntegrityReport, JournalAppend, KeyExpiryReport, KeyHierarchy, KeyMetadata, KeyRotation,
+    KeyShare, OfflineRequest, OfflineResponse, PolicyCacheStats, Preview, RangeVerification,
+    RekeyContext, SelfTestReport, SignedGrant, WarmupReport,
+};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::remote::RemoteDaemon;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackendKind {
+    InProcess,
+    Remote,
+}
+
+impl BackendKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackendKind::InProcess => "in-process",
+            BackendKind::Remote => "remote",
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for BackendKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "in-process" | "inprocess" | "embedded" => Ok(BackendKind::InProcess),
+            "remote" | "daemon" => Ok(BackendKind::Remote),
+            other => Err(anyhow::anyhow!(
+                "unknown backend '{other}'; expected in-process or remote"
+            )),
+        }
+    }
+}
+
+pub trait Backend: DataGuardian + Send + Sync {
+    fn kind(&self) -> BackendKind;
+
+    fn describe(&self) -> String {
+        self.kind().to_string()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum BackendConfig {
+    InProcess,
+    Remote(Box<BridgeConfig>),
+}
+
+impl BackendConfig {
+    pub fn kind(&self) -> BackendKind {
+        match self {
+            BackendConfig::InProcess => BackendKind::InProcess,
+            BackendConfig::Remote(_) => BackendKind::Remote,
+        }
+    }
+
+    pub fn build(self) -> Result<Arc<dyn Backend>> {
+        match self {
+            BackendConfig::InProcess => {
+                Ok(Arc::new(InProcessEngine::new(dg_core::api::new_default())))
+            }
+            BackendConfig::Remote(config) => {
+                Ok(Arc::new(RemoteDaemon::new(BridgeClient::new(*config)?)))
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct InProcessEngine {
+    engine: Arc<dyn DataGuardian + Send + Sync>,
+}
+
+impl InProcessEngine {
+    pub fn new(engine: Arc<dyn DataGuardian + Send + Sync>) -> Self {
+        Self { engine }
+    }
+}
+
+impl Backend for InProcessEngine {
+    fn kind(&self) -> BackendKind {
+        BackendKind::InProcess
+    }
+}
+
+#[async_trait]
+impl DataGuardian for InProcessEngine {
+    async fn init(&self, cfg: DGConfig) -> DGResult<()> {
+        self.engine.init(cfg).await
+    }
+
+    async fn encrypt(&self, req: EncryptRequest) -> DGResult<Envelope> {
+        self.engine.encrypt(req).await
+    }
+
+    async fn decrypt(&self, env: Envelope) -> DGResult<Zeroizing<Vec<u8>>> {
+        self.engine.decrypt(env).await
+    }
+
+    async fn check_policy(&self, subject: &str, action: &str, resource: &str) -> DGResult<bool> {
+        self.engine.check_policy(subject, action, resource).await
+    }
+
+    async fn check_identity(
+        &self,
+        identity: &Identity,
+        action: &str,
+        resource: &str,
+    ) -> DGResult<bool> {
+        self.engine.check_identity(identity, action, resource).await
+    }
+
+    async fn explain_decision(
+        &self,
+        identity: &Identity,
+        action: &str,
+        resource: &str,
+    ) -> DGResult<DecisionExplanation> {
+        self.engine
+            .explain_decision(identity, action, resource)
+            .await
+    }
+
+    async fn import_private_key(&self, pem: &str, label: &str) -> DGResult<KeyMetadata> {
+        self.engine.import_private_key(pem, label).await
+    }
+
+    async fn import_public_key(&self, pem: &str, contact: &str) -> DGResult<KeyMetadata> {
+        self.engine.import_public_key(pem, contact).await
+    }
+
+    async fn revoke_key(&self, id: &str) -> DGResult<KeyMetadata> {
+        self.engine.revoke_key(id).await
+    }
+
+    async fn generate_identity(&self, label: &str) -> DGResult<KeyMetadata> {
+        self.engine.generate_identity(label).await
+    }
+
+    async fn generate_key_hierarchy(&self, label: &str) -> DGResult<KeyHierarchy> {
+        self.engine.generate_key_hierarchy(label).await
+    }
+
+    async fn list_identities(&self) -> DGResult<Vec<KeyMetadata>> {
+        self.engine.list_identities().await
+    }
+
+    async fn key_expiry(&self) -> DGResult<KeyExpiryReport> {
+        self.engine.key_expiry().await
+    }
+
+    async fn rotate_key(&self, id: &str) -> DGResult<KeyRotation> {
+        self.engine.rotate_key(id).await
+    }
+
+    async fn rekey(&self, env: Envelope, context: RekeyContext) -> DGResult<Envelope> {
+        self.engine.rekey(env, context).await
+    }
+
+    async fn sign_detached(&self, signer: &str, data: &[u8]) -> DGResult<Vec<u8>> {
+        self.engine.sign_detached(signer, data).await
+    }
+
+    async fn verify_detached(&self, signer: &str, data: &[u8], signature: &[u8]) -> DGResult<bool> {
+        self.engine.verify_detached(signer, data, signature).await
+    }
+
+    async fn self_test(&self) -> DGResult<SelfTestReport> {
+        self.engine.self_test().await
+    }
+
+    async fn warm_up(&self) -> DGResult<WarmupReport> {
+        self.engine.warm_up().await
+    }
+
+    async fn integrity(&self, repair: bool) -> DGResult<IntegrityReport> {
+        self.engine.integrity(repair).await
+    }
+
+    async fn collect_share(&self, env: &Envelope) -> DGResult<Vec<KeyShare>> {
+        self.engine.collect_share(env).await
+    }
+
+    async fn combine_shares(
+        &self,
+        env: Envelope,
+        shares: Vec<KeyShare>,
+    ) -> DGResult<Zeroizing<Vec<u8>>> {
+        self.engine.combine_shares(env, shares).await
+    }
+
+    async fn issue_grant(&self, env: &Envelope, request: GrantRequest) -> DGResult<SignedGrant> {
+        self.engine.issue_grant(env, request).await
+    }
+
+    async fn decrypt_with_grant(
+        &self,
+        env: Envelope,
+        grant: SignedGrant,
+        action: GrantAction,
+    ) -> DGResult<Zeroizing<Vec<u8>>> {
+        self.engine.decrypt_with_grant(env, grant, action).await
+    }
+
+    async fn export_offline_request(&self, env: &Envelope) -> DGResult<OfflineRequest> {
+        self.engine.export_offline_request(env).await
+    }
+
+    async fn answer_offline_request(&self, request: OfflineRequest) -> DGResult<OfflineResponse> {
+        self.engine.answer_offline_request(request).await
+    }
+
+    async fn complete_offline_request(
+        &self,
+        env: Envelope,
+        response: OfflineResponse,
+    ) -> DGResult<Zeroizing<Vec<u8>>> {
+        self.engine.complete_offline_request(env, response).await
+    }
+
+    async fn preview(&self, env: &Envelope) -> DGResult<Option<Preview>> {
+        self.engine.preview(env).await
+    }
+
+    async fn verify_range(
+        &self,
+        env: &Envelope,
+        offset: u64,
+        len: u64,
+    ) -> DGResult<RangeVerification> {
+        self.engine.verify_range(env, offset, len).await
+    }
+
+    async fn decrypt_range(
+        &self,
+        env: &Envelope,
+        offset: u64,
+        len: u64,
+    ) -> DGResult<Zeroizing<Vec<u8>>> {
+        self.engine.decrypt_range(env, offset, len).await
+    }
+
+    async fn append(&self, env: &Envelope, data: Vec<u8>) -> DGResult<JournalAppend> {
+        self.engine.append(env, data).await
+    }
+
+    async fn index_entries(&self) -> DGResult<Vec<IndexEntry>> {
+        self.engine.index_entries().await
+    }
+
+    async fn index_record(&self, entry: IndexEntry) -> DGResult<()> {
+        self.engine.index_record(entry).await
+    }
+
+    async fn index_remove(&self, path: &Path) -> DGResult<()> {
+        self.engine.index_remove(path).await
+    }
+
+    async fn index_reconcile(
+        &self,
+        roots: Vec<PathBuf>,
+        observed: Vec<IndexEntry>,
+    ) -> DGResult<Reconciliation> {
+        self.engine.index_reconcile(roots, observed).await
+    }
+
+    async fn reload_policy(&self) -> DGResult<PolicyCacheStats> {
+        self.engine.reload_policy().await
+    }
+
+    async fn policy_cache_stats(&self) -> DGResult<PolicyCacheStats> {
+        self.engine.policy_cache_stats().await
+    }
+
+    async fn query_audit(&self, filter: AuditFilter) -> DGResult<Vec<AuditEvent>> {
+        self.engine.query_audit(filter).await
+    }
+
+    async fn loaded_profile(&self) -> DGResult<Option<LoadedProfile>> {
+        self.engine.loaded_profile().await
+    }
+
+    async fn lock(&self) -> DGResult<()> {
+        self.engine.lock().await
+    }
+
+    async fn unlock(&self, passphrase: &str) -> DGResult<()> {
+        self.engine.unlock(passphrase).await
+    }
+
+    async fn shutdown(&self) -> DGResult<()> {
+        self.engine.shutdown().await
+    }
+}
@@ -0,0 +1,459 @@
+use std::collections::BTreeMap;
+use std::io::SeekFrom;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use dg_core::index::IndexEntry;
+use dg_core::path_encoding;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tracing::warn;
+
+use crate::jobs::JobCheckpoints;
+
+const BACKUP_DIR: &str = "backup";
+const STATE_FILE: &str = "state.json";
+const CHUNK_SIZE: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    #[default]
+    Skip,
+    Overwrite,
+    KeepBoth,
+}
+
+impl ConflictPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictPolicy::Skip => "skip",
+            ConflictPolicy::Overwrite => "overwrite",
+            ConflictPolicy::KeepBoth => "keep-both",
+        }
+    }
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "skip" => Ok(ConflictPolicy::Skip),
+            "overwrite" => Ok(ConflictPolicy::Overwrite),
+            "keep-both" | "keep_both" => Ok(ConflictPolicy::KeepBoth),
+            other => Err(anyhow::anyhow!("unknown conflict policy '{other}'")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BackupOptions {
+    pub target: PathBuf,
+    pub conflict: ConflictPolicy,
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransferSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bandwidth_limit: Option<u64>,
+    pub retries: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for TransferSettings {
+    fn default() -> Self {
+        Self {
+            bandwidth_limit: None,
+            retries: 5,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+impl TransferSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.bandwidth_limit == Some(0) {
+            return Err(anyhow::anyhow!(
+                "bandwidth limit must be at least one byte per second"
+            ));
+        }
+        if self.initial_backoff_ms > self.max_backoff_ms {
+            return Err(anyhow::anyhow!(
+                "initial backoff {}ms exceeds the maximum of {}ms",
+                self.initial_backoff_ms,
+                self.max_backoff_ms
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .initial_backoff_ms
+            .saturating_mul(1u64 << attempt.min(32))
+            .min(self.max_backoff_ms);
+        Duration::from_millis(delay)
+    }
+
+    fn throttle(&self, sent: u64, started: Instant) -> Option<Duration> {
+        let limit = self.bandwidth_limit?;
+        Duration::from_secs_f64(sent as f64 / limit as f64).checked_sub(started.elapsed())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferCheckpoint {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub sha256: String,
+    pub offset: u64,
+}
+
+#[derive(Clone)]
+pub(crate) struct Transfer {
+    pub settings: TransferSettings,
+    pub checkpoints: Option<JobCheckpoints>,
+}
+
+impl Transfer {
+    async fn resume_offset(&self, source: &Path, target: &Path, expected: &str) -> u64 {
+        let Some(checkpoints) = &self.checkpoints else {
+            return 0;
+        };
+        checkpoints
+            .load()
+            .await
+            .filter(|checkpoint| {
+                checkpoint.source == source
+                    && checkpoint.target == target
+                    && checkpoint.sha256 == expected
+            })
+            .map(|checkpoint| checkpoint.offset)
+            .unwrap_or_default()
+    }
+
+    async fn checkpoint(&self, checkpoint: Option<TransferCheckpoint>) -> Result<()> {
+        match &self.checkpoints {
+            Some(checkpoints) => checkpoints.save(checkpoint).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupConflict {
+    pub path: PathBuf,
+    pub target: PathBuf,
+    pub resolution: ConflictPolicy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupFailure {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BackupReport {
+    pub target: PathBuf,
+    pub dry_run: bool,
+    pub copied: Vec<PathBuf>,
+    pub unchanged: usize,
+    pub missing: Vec<PathBuf>,
+    pub conflicts: Vec<BackupConflict>,
+    pub failures: Vec<BackupFailure>,
+}
+
+impl BackupReport {
+    pub fn succeeded(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SyncedEnvelope {
+    pub updated_at: u64,
+    #[serde(default)]
+    pub modified: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct BackupState {
+    #[serde(default)]
+    targets: BTreeMap<String, BTreeMap<String, SyncedEnvelope>>,
+}
+
+impl BackupState {
+    pub async fn load(data_dir: &Path) -> Result<Self> {
+        match fs::read(state_path(data_dir)).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).context("invalid backup state"),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(anyhow::anyhow!("unable to read backup state: {err}")),
+        }
+    }
+
+    pub async fn persist(&self, data_dir: &Path) -> Result<()> {
+        let path = state_path(data_dir);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&path, serde_json::to_vec_pretty(self)?).await?;
+        Ok(())
+    }
+
+    pub fn synced(&self, target: &Path, path: &Path) -> Option<&SyncedEnvelope> {
+        self.targets
+            .get(&path_encoding::encode(target))?
+            .get(&path_encoding::encode(path))
+    }
+
+    pub fn record(&mut self, target: &Path, path: &Path, synced: SyncedEnvelope) {
+        self.targets
+            .entry(path_encoding::encode(target))
+            .or_default()
+            .insert(path_encoding::encode(path), synced);
+    }
+}
+
+fn state_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(BACKUP_DIR).join(STATE_FILE)
+}
+
+pub(crate) fn mirror_path(target: &Path, source: &Path) -> PathBuf {
+    let mut mirrored = target.to_path_buf();
+    for component in source.components() {
+        match component {
+            Component::Prefix(prefix) => mirrored.push(
+                prefix
+                    .as_os_str()
+                    .to_string_lossy()
+                    .replace([':', '\\', '?'], ""),
+            ),
+            Component::Normal(part) => mirrored.push(part),
+            Component::RootDir | Component::CurDir | Component::ParentDir => {}
+        }
+    }
+    mirrored
+}
+
+pub(crate) async fn file_digest(path: &Path) -> Result<String> {
+    let bytes = fs::read(path)
+        .await
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(hex::encode(Sha256::digest(&bytes)))
+}
+
+// Copies in chunks into a ".partial" file. Each chunk is retried with exponential backoff,
+// and job-driven syncs record the offset so an interrupted copy resumes where it stopped.
+pub(crate) async fn copy_verified(
+    source: &Path,
+    target: &Path,
+    expected: &str,
+    transfer: &Transfer,
+) -> Result<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("unable to create {}", parent.display()))?;
+    }
+    let mut staging = target.as_os_str().to_owned();
+    staging.push(".partial");
+    let staging = PathBuf::from(staging);
+    let resume = transfer.resume_offset(source, target, expected).await;
+    let mut offset = match fs::metadata(&staging).await {
+        Ok(metadata) if metadata.len() >= resume => resume,
+        _ => 0,
+    };
+
+    let mut input = fs::File::open(source)
+        .await
+        .with_context(|| format!("failed to read {}", source.display()))?;
+    let mut output = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&staging)
+        .await
+        .with_context(|| format!("unable to create {}", staging.display()))?;
+    output.set_len(offset).await?;
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let started = Instant::now();
+    let mut sent = 0u64;
+    loop {
+        let mut attempt = 0;
+        let copied = loop {
+            match copy_chunk(&mut input, &mut output, offset, &mut buffer).await {
+                Ok(copied) => break copied,
+                Err(err) if attempt < transfer.settings.retries => {
+                    let delay = transfer.settings.backoff(attempt);
+                    warn!(path = %target.display(), offset, error = %err, ?delay, "retrying envelope copy");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(err) => {
+                    return Err(err.context(format!(
+                        "copy of {} failed at byte {offset}",
+                        source.display()
+                    )))
+                }
+            }
+        };
+        if copied == 0 {
+            break;
+        }
+        offset += copied as u64;
+        sent += copied as u64;
+        transfer
+            .checkpoint(Some(TransferCheckpoint {
+                source: source.to_path_buf(),
+                target: target.to_path_buf(),
+                sha256: expected.to_owned(),
+                offset,
+            }))
+            .await?;
+        if let Some(delay) = transfer.settings.throttle(sent, started) {
+            tokio::time::sleep(delay).await;
+        }
+    }
+    output.sync_all().await?;
+    drop(output);
+    if file_digest(&staging).await? != expected {
+        let _ = fs::remove_file(&staging).await;
+        transfer.checkpoint(None).await?;
+        return Err(anyhow::anyhow!(
+            "verification failed for {}: checksum mismatch",
+            target.display()
+        ));
+    }
+    fs::rename(&staging, target).await?;
+    transfer.checkpoint(None).await?;
+    Ok(())
+}
+
+async fn copy_chunk(
+    input: &mut fs::File,
+    output: &mut fs::File,
+    offset: u64,
+    buffer: &mut [u8],
+) -> Result<usize> {
+    input.seek(SeekFrom::Start(offset)).await?;
+    let read = input.read(buffer).await?;
+    output.seek(SeekFrom::Start(offset)).await?;
+    output.write_all(&buffer[..read]).await?;
+    output.flush().await?;
+    Ok(read)
+}
+
+pub(crate) fn conflict_copy_path(target: &Path, stamp: u64) -> PathBuf {
+    let mut name = target
+        .file_name()
+        .map(|name| name.to_os_string())
+        .unwrap_or_else(|| "envelope".into());
+    name.push(format!(".conflict-{stamp}"));
+    target.with_file_name(name)
+}
+
+pub(crate) enum SyncOutcome {
+    Unchanged,
+    Missing,
+    Copied,
+    Conflict { target: PathBuf, copied: bool },
+}
+
+pub(crate) async fn sync_entry(
+    state: &mut BackupState,
+    target: &Path,
+    entry: &IndexEntry,
+    options: &BackupOptions,
+    transfer: &Transfer,
+) -> Result<SyncOutcome> {
+    let source = &entry.path;
+    let Ok(metadata) = fs::metadata(source).await else {
+        return Ok(SyncOutcome::Missing);
+    };
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or_default();
+    let destination = mirror_path(target, source);
+    let previous = state.synced(target, source).cloned();
+    let exists = fs::metadata(&destination).await.is_ok();
+    if exists
+        && previous.as_ref().is_some_and(|synced| {
+            synced.updated_at == entry.updated_at && synced.modified == modified
+        })
+    {
+        return Ok(SyncOutcome::Unchanged);
+    }
+
+    let digest = file_digest(source).await?;
+    let mut conflict = false;
+    if exists {
+        let existing = file_digest(&destination).await?;
+        if existing == digest {
+            if !options.dry_run {
+                state.record(target, source, synced(entry, modified, digest));
+            }
+            return Ok(SyncOutcome::Unchanged);
+        }
+        if previous.map(|synced| synced.sha256) != Some(existing) {
+            conflict = true;
+            match options.conflict {
+                ConflictPolicy::Skip => {
+                    return Ok(SyncOutcome::Conflict {
+                        target: destination,
+                        copied: false,
+                    })
+                }
+                ConflictPolicy::Overwrite => {}
+                ConflictPolicy::KeepBoth if options.dry_run => {}
+                ConflictPolicy::KeepBoth => {
+                    let kept = conflict_copy_path(&destination, unix_now());
+                    fs::rename(&destination, &kept)
+                        .await
+                        .with_context(|| format!("unable to keep {}", destination.display()))?;
+                }
+            }
+        }
+    }
+
+    if !options.dry_run {
+        copy_verified(source, &destination, &digest, transfer).await?;
+        state.record(target, source, synced(entry, modified, digest));
+    }
+    Ok(if conflict {
+        SyncOutcome::Conflict {
+            target: destination,
+            copied: true,
+        }
+    } else {
+        SyncOutcome::Copied
+    })
+}
+
+fn synced(entry: &IndexEntry, modified: u64, sha256: String) -> SyncedEnvelope {
+    SyncedEnvelope {
+        updated_at: entry.updated_at,
+        modified,
+        sha256,
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,188 @@
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{Context, Result};
+use dg_core::provenance::{provenance, ProvenanceEntry};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::fs;
+
+use crate::stored::StoredEnvelope;
+
+const MASTER_KEY_ID: &str = "master";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NewerCopy {
+    First,
+    Second,
+    Same,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NewerEvidence {
+    Identical,
+    Provenance,
+    Modified,
+    None,
+}
+
+impl NewerEvidence {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NewerEvidence::Identical => "identical",
+            NewerEvidence::Provenance => "provenance",
+            NewerEvidence::Modified => "modified",
+            NewerEvidence::None => "none",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EnvelopeSummary {
+    pub path: PathBuf,
+    pub format: u32,
+    pub size: u64,
+    pub modified: u64,
+    pub payload_sha256: String,
+    pub labels: Vec<String>,
+    pub recipients: Vec<String>,
+    pub key_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_path: Option<String>,
+    pub provenance: Vec<ProvenanceEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EnvelopeComparison {
+    pub first: EnvelopeSummary,
+    pub second: EnvelopeSummary,
+    pub identical_payload: bool,
+    pub differences: Vec<String>,
+    pub newer: NewerCopy,
+    pub evidence: NewerEvidence,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub same_content: Option<bool>,
+}
+
+pub(crate) async fn summarize(path: &Path) -> Result<EnvelopeSummary> {
+    let metadata = fs::metadata(path)
+        .await
+        .with_context(|| format!("unable to read {}", path.display()))?;
+    let stored = StoredEnvelope::read(path)
+        .await
+        .with_context(|| format!("unable to load {}", path.display()))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let key_ids = stored
+        .meta
+        .get("key_wraps")
+        .and_then(Value::as_array)
+        .map(|wraps| {
+            wraps
+                .iter()
+                .map(|wrap| {
+                    wrap.get("key_id")
+                        .and_then(Value::as_str)
+                        .unwrap_or(MASTER_KEY_ID)
+                        .to_owned()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(EnvelopeSummary {
+        path: path.to_path_buf(),
+        format: stored.format,
+        size: metadata.len(),
+        modified,
+        payload_sha256: hex::encode(Sha256::digest(stored.payload_bytes()?)),
+        labels: strings(&stored.meta, "labels"),
+        recipients: strings(&stored.meta, "recipients"),
+        key_ids,
+        original_path: stored.original_path,
+        provenance: provenance(&stored.meta)
+            .map_err(|err| anyhow::anyhow!("{}: {err}", path.display()))?,
+    })
+}
+
+pub(crate) fn compare(first: EnvelopeSummary, second: EnvelopeSummary) -> EnvelopeComparison {
+    let mut differences = Vec::new();
+    let mut differs = |field: &str, same: bool| {
+        if !same {
+            differences.push(field.to_owned());
+        }
+    };
+    differs("format", first.format == second.format);
+    differs("payload", first.payload_sha256 == second.payload_sha256);
+    differs("labels", first.labels == second.labels);
+    differs("recipients", first.recipients == second.recipients);
+    differs("key_ids", first.key_ids == second.key_ids);
+    differs("original_path", first.original_path == second.original_path);
+    differs("provenance", first.provenance == second.provenance);
+
+    let identical_payload = first.payload_sha256 == second.payload_sha256;
+    let (newer, evidence) = if differences.is_empty() {
+        (NewerCopy::Same, NewerEvidence::Identical)
+    } else if let Some(newer) = newer_by_provenance(&first.provenance, &second.provenance) {
+        (newer, NewerEvidence::Provenance)
+    } else if first.modified != second.modified {
+        let newer = if first.modified > second.modified {
+            NewerCopy::First
+        } else {
+            NewerCopy::Second
+        };
+        (newer, NewerEvidence::Modified)
+    } else {
+        (NewerCopy::Unknown, NewerEvidence::None)
+    };
+    EnvelopeComparison {
+        first,
+        second,
+        identical_payload,
+        differences,
+        newer,
+        evidence,
+        same_content: identical_payload.then_some(true),
+    }
+}
+
+// A chain that extends the other one is a later re-encryption of the same envelope.
+fn newer_by_provenance(first: &[ProvenanceEntry], second: &[ProvenanceEntry]) -> Option<NewerCopy> {
+    if first.len() != second.len() {
+        let (shorter, longer, newer) = if first.len() < second.len() {
+            (first, second, NewerCopy::Second)
+        } else {
+            (second, first, NewerCopy::First)
+        };
+        if longer.starts_with(shorter) {
+            return Some(newer);
+        }
+    }
+    let first_at = first.last()?.timestamp;
+    let second_at = second.last()?.timestamp;
+    match first_at.cmp(&second_at) {
+        std::cmp::Ordering::Greater => Some(NewerCopy::First),
+        std::cmp::Ordering::Less => Some(NewerCopy::Second),
+        std::cmp::Ordering::Equal => None,
+    }
+}
+
+fn strings(meta: &Value, field: &str) -> Vec<String> {
+    meta.get(field)
+        .and_then(Value::as_array)
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_owned)
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,4002 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use dg_bridge::session::{session_subject, Capability, SessionAuthority, SESSION_RESOURCE};
+use dg_bridge::ProgressUpdate;
+use dg_core::api::{DGConfig, DGError, DataGuardian, EncryptRequest, Envelope};
+use dg_core::audit::{AuditEvent, AuditFilter, AuditLog};
+use dg_core::chunking::{self, ChunkingParams, DedupStats};
+use dg_core::device::{self, DeviceIdentity, DeviceKey, Enrollment};
+use dg_core::index::{IndexEntry, Reconciliation};
+use dg_core::journal;
+use dg_core::key_age::DAY;
+use dg_core::keyring::Keyring;
+use dg_core::license::{install_license, load_license_status};
+use dg_core::management::{self as management, LockState, ManagementAction, SignedCommand};
+use dg_core::offline::{REQUEST_EXTENSION, RESPONSE_EXTENSION};
+use dg_core::pairing::{self, PendingPairing, UnlockApproval, UnlockChallenge};
+use dg_core::passphrase;
+use dg_core::signature::SIGNATURE_EXTENSION;
+use dg_core::trust::{self as trust, SignedTrustStore};
+use dg_core::{
+    CustomMetadata, DecisionExplanation, Fingerprint, GrantAction, GrantRequest, Identity,
+    IntegrityReport, KeyAgeState, KeyExpiryReport, KeyHierarchy, KeyMetadata, KeyShare,
+    LicenseStatus, LicenseVerifier, MetadataFilter, NewSigner, OfflineRequest, OfflineResponse,
+    PairedDevice, PairingOffer, PairingResponse, PolicyCacheStats, Preview, RangeVerification,
+    RekeyContext, SelfTestReport, SignedGrant, TrustRole, TrustStore, TrustedSigner, WarmupReport,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{broadcast, Mutex};
+use tokio::task;
+use tracing::{info, instrument, warn};
+use zeroize::Zeroizing;
+
+use crate::approval::{DeviceApproval, APPROVAL_ACTION, PAIRING_TTL, PAIR_ACTION, POLL_INTERVAL};
+use crate::backend::{Backend, BackendConfig, BackendKind, InProcessEngine};
+use crate::backup::{
+    sync_entry, BackupConflict, BackupFailure, BackupOptions, BackupReport, BackupState,
+    SyncOutcome, Transfer, TransferSettings,
+};
+use crate::compare::{self, EnvelopeComparison};
+use crate::defaults::{EncryptOutcome, EncryptionDefaults};
+use crate::errors::UserError;
+use crate::events::{
+    ControllerEvent, EventJournal, EventVerbosity, RecordedEvent, DEFAULT_EVENT_CAPACITY,
+};
+use crate::inbox::{self, InboxFailure, InboxFile, InboxReport, InboxSettings};
+use crate::jobs::{self, Job, JobCheckpoints, JobFailure, JobQueue, JobSpec, JobStatus};
+use crate::labels::{self, LabelDefinition, LabelRegistry, LabelValidation};
+use crate::management::{wipe_keys, ManagementOutcome};
+use crate::migrate::{
+    find_envelopes, EnvelopeFormat, MigratedEnvelope, MigrationFailure, MigrationOptions,
+    MigrationReport, ENVELOPE_FORMAT,
+};
+use crate::naming::{output_target, OutputRoutes, OutputTemplates};
+use crate::operations::{
+    self, ActiveOperation, OperationGuard, OperationTracker, PendingOperation, ShutdownReport,
+    DEFAULT_DRAIN_TIMEOUT,
+};
+use crate::paths::{display_path, extended};
+use crate::progress::ProgressReporter;
+use crate::quarantine::{self, Quarantine, QuarantineHandling};
+use crate::receipts::{self, ReadReceipt, ReceiptSink};
+use crate::report::{self, EnvelopeReport, ExportedReport, ReportFilter, ReportFormat};
+use crate::restore::{
+    original_name, restore_target, DirectoryDecryptReport, RestoreFailure, RestoredFile,
+};
+use crate::retention::{
+    self, LegalHold, Retention, RetentionReport, RetentionState, RetentionStatus,
+};
+use crate::shred::{self, ShredReport, OVERWRITE_PASSES, SHRED_ACTION};
+use crate::stored::{self, StoredEnvelope};
+use crate::throttle::{self, JobThrottle, PowerProbe, Throttle};
+use crate::thumbnail;
+use crate::viewer::{
+    launch, scrub, shred, stage_plaintext, sweep, viewer_root, wait_for_close, ViewerOptions,
+    ViewerSession,
+};
+use crate::walk::{self, DirectoryEncryptReport, EncryptedFile, WalkFailure, WalkSettings};
+use crate::watch::{FolderWatch, WatchSettings};
+use crate::xattrs::{self, ExtendedAttributes};
+
+const ENCRYPTED_EXTENSION: &str = "dgenc";
+const SHARE_EXTENSION: &str = "share.json";
+const GRANT_EXTENSION: &str = "grant.json";
+const NOTES_DIR: &str = "notes";
+const ROUTE_ACTION: &str = "route_output";
+const TRUST_ACTION: &str = "manage_trust";
+const ENROLL_ACTION: &str = "enroll_device";
+const PANIC_WIPE_ACTION: &str = "panic_wipe";
+const BATTERY_POLL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Default)]
+pub struct EncryptOptions {
+    pub recipients: Vec<String>,
+    pub labels: Vec<String>,
+    pub out_dir: Option<PathBuf>,
+    pub threshold: Option<u8>,
+    pub sign_with: Option<String>,
+    pub read_receipts: bool,
+    pub preview: bool,
+    pub metadata: CustomMetadata,
+    pub retention: Option<Retention>,
+    // Content-defined chunking; set automatically when `previous` is.
+    pub chunking: Option<ChunkingParams>,
+    // An earlier envelope of the same file whose unchanged chunks are reused.
+    pub previous: Option<PathBuf>,
+    // Overwrite and remove the source once its envelope is written; see `encrypt_and_shred`.
+    pub shred_source: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ControllerStatus {
+    pub backend: BackendKind,
+    pub backend_detail: String,
+    pub identity: Identity,
+    pub license: LicenseStatus,
+    pub integrity: Option<IntegrityReport>,
+    pub keys: Vec<KeyMetadata>,
+    pub read_only: bool,
+    pub ready: bool,
+    pub locked: bool,
+    pub key_locked: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FingerprintCheck {
+    pub key_id: String,
+    pub label: String,
+    pub fingerprint: Fingerprint,
+    pub matches: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppendReport {
+    pub path: PathBuf,
+    pub created: bool,
+    pub appended_bytes: u64,
+    pub records: u64,
+    pub plaintext_len: u64,
+    // Bytes of an interrupted earlier append that were cut off before writing.
+    pub discarded_bytes: u64,
+}
+
+#[derive(Clone)]
+pub struct Controller {
+    dg: Arc<dyn Backend>,
+    events: broadcast::Sender<RecordedEvent>,
+    progress: broadcast::Sender<ProgressUpdate>,
+    journal: Arc<Mutex<EventJournal>>,
+    verbosity: Arc<RwLock<EventVerbosity>>,
+    index_locations: Arc<RwLock<Vec<PathBuf>>>,
+    audit: Arc<Mutex<Option<AuditLog>>>,
+    identity: Arc<RwLock<Identity>>,
+    templates: Arc<RwLock<OutputTemplates>>,
+    routes: Arc<RwLock<OutputRoutes>>,
+    transfer: Arc<RwLock<TransferSettings>>,
+    quarantine: Arc<RwLock<QuarantineHandling>>,
+    watch: Arc<RwLock<WatchSettings>>,
+    walk: Arc<RwLock<WalkSettings>>,
+    throttle: Arc<Throttle>,
+    defaults: Arc<RwLock<EncryptionDefaults>>,
+    receipts: Arc<RwLock<Option<Arc<dyn ReceiptSink>>>>,
+    approval: Arc<RwLock<Option<DeviceApproval>>>,
+    pairing: Arc<Mutex<Option<PendingPairing>>>,
+    trust: Arc<Mutex<()>>,
+    appends: Arc<Mutex<()>>,
+    data_dir: Arc<Mutex<Option<PathBuf>>>,
+    licenses: Arc<LicenseVerifier>,
+    viewers: Arc<Mutex<HashMap<u64, PathBuf>>>,
+    operations: OperationTracker,
+    drain_timeout: Duration,
+    jobs: Arc<Mutex<Option<JobQueue>>>,
+    labels: Arc<RwLock<LabelRegistry>>,
+    read_only: Arc<AtomicBool>,
+    ready: Arc<AtomicBool>,
+    locked: Arc<AtomicBool>,
+    key_locked: Arc<AtomicBool>,
+}
+
+pub struct RevocationJob {
+    pub key: KeyMetadata,
+    pub affected: Vec<PathBuf>,
+    pub job: u64,
+    handle: task::JoinHandle<Result<usize>>,
+}
+
+impl RevocationJob {
+    pub async fn wait(self) -> Result<usize> {
+        operations::join(self.handle).await
+    }
+}
+
+impl Controller {
+    pub fn new(dg: Arc<dyn DataGuardian + Send + Sync>) -> Self {
+        Self::with_backend(Arc::new(InProcessEngine::new(dg)))
+    }
+
+    pub fn from_config(config: BackendConfig) -> Result<Self> {
+        Ok(Self::with_backend(config.build()?))
+    }
+
+    pub fn with_backend(dg: Arc<dyn Backend>) -> Self {
+        let (tx, _rx) = broadcast::channel(64);
+        Self {
+            dg,
+            events: tx,
+            progress: broadcast::channel(256).0,
+            journal: Arc::new(Mutex::new(EventJournal::new(DEFAULT_EVENT_CAPACITY))),
+            verbosity: Arc::new(RwLock::new(EventVerbosity::Verbose)),
+            index_locations: Arc::new(RwLock::new(Vec::new())),
+            audit: Arc::new(Mutex::new(None)),
+            identity: Arc::new(RwLock::new(Identity::from_os())),
+            templates: Arc::new(RwLock::new(OutputTemplates::default())),
+            routes: Arc::new(RwLock::new(OutputRoutes::default())),
+            transfer: Arc::new(RwLock::new(TransferSettings::default())),
+            quarantine: Arc::new(RwLock::new(QuarantineHandling::default())),
+            watch: Arc::new(RwLock::new(WatchSettings::default())),
+            walk: Arc::new(RwLock::new(WalkSettings::default())),
+            throttle: Arc::new(Throttle::new(
+                throttle::system_power(),
+                JobThrottle::default(),
+            )),
+            defaults: Arc::new(RwLock::new(EncryptionDefaults::default())),
+            receipts: Arc::new(RwLock::new(None)),
+            approval: Arc::new(RwLock::new(None)),
+            pairing: Arc::new(Mutex::new(None)),
+            trust: Arc::new(Mutex::new(())),
+            appends: Arc::new(Mutex::new(())),
+            data_dir: Arc::new(Mutex::new(None)),
+            licenses: Arc::new(LicenseVerifier::vendor()),
+            viewers: Arc::new(Mutex::new(HashMap::new())),
+            operations: OperationTracker::default(),
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            jobs: Arc::new(Mutex::new(None)),
+            labels: Arc::new(RwLock::new(LabelRegistry::default())),
+            read_only: Arc::new(AtomicBool::new(false)),
+            ready: Arc::new(AtomicBool::new(false)),
+            locked: Arc::new(AtomicBool::new(false)),
+            key_locked: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn with_license_verifier(mut self, verifier: LicenseVerifier) -> Self {
+        self.licenses = Arc::new(verifier);
+        self
+    }
+
+    pub fn with_power_probe(mut self, power: PowerProbe) -> Self {
+        self.throttle = Arc::new(Throttle::new(power, self.throttle.settings()));
+        self
+    }
+
+    pub fn with_event_capacity(mut self, capacity: usize) -> Self {
+        self.journal = Arc::new(Mutex::new(EventJournal::new(capacity)));
+        self
+    }
+
+    pub fn with_drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = timeout;
+        self
+    }
+
+    pub fn active_operations(&self) -> Vec<ActiveOperation> {
+        self.operations.active()
+    }
+
+    pub fn with_event_verbosity(self, verbosity: EventVerbosity) -> Self {
+        self.set_event_verbosity(verbosity);
+        self
+    }
+
+    pub fn event_verbosity(&self) -> EventVerbosity {
+        *self
+            .verbosity
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn set_event_verbosity(&self, verbosity: EventVerbosity) {
+        *self
+            .verbosity
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = verbosity;
+    }
+
+    pub fn identity(&self) -> Identity {
+        self.identity
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn set_identity(&self, identity: Identity) {
+        *self
+            .identity
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = identity;
+    }
+
+    pub fn output_templates(&self) -> OutputTemplates {
+        self.templates
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn set_output_templates(&self, templates: OutputTemplates) -> Result<()> {
+        templates.validate()?;
+        *self
+            .templates
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = templates;
+        Ok(())
+    }
+
+    pub fn output_routes(&self) -> OutputRoutes {
+        self.routes
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn set_output_routes(&self, routes: OutputRoutes) -> Result<()> {
+        routes.validate()?;
+        *self
+            .routes
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = routes;
+        Ok(())
+    }
+
+    pub fn transfer_settings(&self) -> TransferSettings {
+        *self
+            .transfer
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn set_transfer_settings(&self, settings: TransferSettings) -> Result<()> {
+        settings.validate()?;
+        *self
+            .transfer
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = settings;
+        Ok(())
+    }
+
+    pub fn quarantine_handling(&self) -> QuarantineHandling {
+        *self
+            .quarantine
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn set_quarantine_handling(&self, handling: QuarantineHandling) {
+        *self
+            .quarantine
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = handling;
+    }
+
+    pub fn watch_settings(&self) -> WatchSettings {
+        self.watch
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn set_watch_settings(&self, settings: WatchSettings) -> Result<()> {
+        settings.validate()?;
+        *self
+            .watch
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = settings;
+        Ok(())
+    }
+
+    pub fn walk_settings(&self) -> WalkSettings {
+        self.walk
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn set_walk_settings(&self, settings: WalkSettings) -> Result<()> {
+        settings.validate()?;
+        *self
+            .walk
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = settings;
+        Ok(())
+    }
+
+    // Starts a debounced, recursive watch on `dir` with the current watch settings.
+    pub fn watch_folder(&self, dir: &Path) -> Result<FolderWatch> {
+        FolderWatch::start(dir, &self.watch_settings())
+    }
+
+    pub fn encryption_defaults(&self) -> EncryptionDefaults {
+        self.defaults
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn set_encryption_defaults(&self, defaults: EncryptionDefaults) -> Result<()> {
+        defaults.validate()?;
+        *self
+            .defaults
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = defaults;
+        Ok(())
+    }
+
+    pub fn set_receipt_sink(&self, sink: Option<Arc<dyn ReceiptSink>>) {
+        *self
+            .receipts
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = sink;
+    }
+
+    // With a channel set, every decryption waits for a paired device to approve it.
+    pub fn set_device_approval(&self, approval: Option<DeviceApproval>) {
+        *self
+            .approval
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = approval;
+    }
+
+    pub fn job_throttle(&self) -> JobThrottle {
+        self.throttle.settings()
+    }
+
+    pub fn set_job_throttle(&self, settings: JobThrottle) -> Result<()> {
+        self.throttle.set(settings)
+    }
+
+    pub fn index_locations(&self) -> Vec<PathBuf> {
+        self.index_locations
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn set_index_locations(&self, locations: Vec<PathBuf>) {
+        *self
+            .index_locations
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = locations;
+    }
+
+    pub fn label_registry(&self) -> LabelRegistry {
+        self.labels
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub async fn save_label(&self, label: LabelDefinition) -> Result<LabelDefinition> {
+        let name = labels::normalize(&label.name);
+        let registry = self
+            .update_labels(|registry| registry.upsert(label))
+            .await?;
+        registry
+            .get(&name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("label '{name}' was not saved"))
+    }
+
+    pub async fn delete_label(&self, name: &str) -> Result<LabelRegistry> {
+        self.update_labels(|registry| registry.remove(name).map(|_| ()))
+            .await
+    }
+
+    pub async fn set_label_validation(&self, validation: LabelValidation) -> Result<LabelRegistry> {
+        self.update_labels(|registry| {
+            registry.validation = validation;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn update_labels<F>(&self, change: F) -> Result<LabelRegistry>
+    where
+        F: FnOnce(&mut LabelRegistry) -> Result<()>,
+    {
+        self.ensure_writable("changing labels")?;
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+        let mut registry = self.label_registry();
+        change(&mut registry)?;
+        registry.persist(&data_dir).await?;
+        *self
+            .labels
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = registry.clone();
+        Ok(registry)
+    }
+
+    async fn check_labels(&self, labels: &[String]) -> Result<()> {
+        let registry = self.label_registry();
+        let unknown = registry.unknown(labels);
+        if unknown.is_empty() {
+            return Ok(());
+        }
+        match registry.validation {
+            LabelValidation::Warn => {
+                warn!(labels = ?unknown, "encrypting with unregistered labels");
+                self.emit(ControllerEvent::Progress(format!(
+                    "labels not in the registry: {}",
+                    unknown.join(", ")
+                )))
+                .await;
+                Ok(())
+            }
+            LabelValidation::Enforce => {
+                let err = UserError::unknown_labels(&unknown);
+                self.emit(ControllerEvent::Error(err.to_string())).await;
+                Err(err)
+            }
+        }
+    }
+
+    pub fn backend_kind(&self) -> BackendKind {
+        self.dg.kind()
+    }
+
+    pub fn describe_backend(&self) -> String {
+        self.dg.describe()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<RecordedEvent> {
+        self.events.subscribe()
+    }
+
+    // Byte counts for running encryptions and decryptions; these are not recorded in the journal.
+    pub fn subscribe_progress(&self) -> broadcast::Receiver<ProgressUpdate> {
+        self.progress.subscribe()
+    }
+
+    fn progress_reporter(&self, id: u64, method: &'static str) -> ProgressReporter {
+        ProgressReporter::new(self.progress.clone(), id, method)
+    }
+
+    pub async fn replay_events(&self, since: Option<u64>) -> Vec<RecordedEvent> {
+        self.journal
+            .lock()
+            .await
+            .since(since, self.event_verbosity())
+    }
+
+    async fn emit(&self, event: ControllerEvent) {
+        let recorded = {
+            let mut journal = self.journal.lock().await;
+            let recorded = journal.record(event);
+            if let Err(err) = journal.persist(&recorded).await {
+                warn!("failed to persist controller event: {err}");
+            }
+            recorded
+        };
+        if recorded.verbosity <= self.event_verbosity() {
+            let _ = self.events.send(recorded);
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn boot(&self, profile: &str, data_dir: PathBuf, telemetry: bool) -> Result<()> {
+        self.boot_with_config(DGConfig {
+            profile: profile.to_owned(),
+            data_dir,
+            telemetry,
+            ..Default::default()
+        })
+        .await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn boot_with_config(&self, cfg: DGConfig) -> Result<()> {
+        let data_dir = cfg.data_dir.clone();
+        let read_only = cfg.read_only;
+        let key_protection = cfg.key_protection;
+        self.ready.store(false, Ordering::SeqCst);
+        self.operations.reopen();
+        self.dg
+            .init(cfg)
+            .await
+            .map_err(|err| anyhow::anyhow!("dg init failed: {err}"))?;
+        let device_id = device::load_identity(&data_dir)
+            .await
+            .map_err(|err| anyhow::anyhow!("device identity unavailable: {err}"))?
+            .map(|identity| identity.device_id);
+        let audit = if read_only {
+            AuditLog::open_read_only(&data_dir)
+        } else {
+            AuditLog::open(&data_dir)
+                .await
+                .map_err(|err| anyhow::anyhow!("audit log unavailable: {err}"))?
+        }
+        .with_device(device_id);
+        let jobs = JobQueue::load(&data_dir)
+            .await
+            .map_err(|err| anyhow::anyhow!("job queue unavailable: {err}"))?;
+        let labels = LabelRegistry::load(&data_dir)
+            .await
+            .map_err(|err| anyhow::anyhow!("label registry unavailable: {err}"))?;
+        *self.audit.lock().await = Some(audit);
+        *self.jobs.lock().await = Some(jobs);
+        *self
+            .labels
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = labels;
+        self.read_only.store(read_only, Ordering::SeqCst);
+        self.locked.store(false, Ordering::SeqCst);
+        self.key_locked.store(
+            passphrase::requires_unlock(&data_dir, key_protection).await,
+            Ordering::SeqCst,
+        );
+        if read_only {
+            *self.data_dir.lock().await = Some(data_dir);
+            return Ok(());
+        }
+        if let Err(err) = self.journal.lock().await.attach(&data_dir).await {
+            warn!("event journal unavailable: {err}");
+        }
+        if let Err(err) = sweep(&viewer_root(&data_dir)).await {
+            warn!("unable to clear stale viewer files: {err}");
+        }
+        *self.data_dir.lock().await = Some(data_dir);
+        self.resume_pending_jobs().await;
+        Ok(())
+    }
+
+    pub async fn status(&self) -> ControllerStatus {
+        ControllerStatus {
+            backend: self.backend_kind(),
+            backend_detail: self.describe_backend(),
+            identity: self.identity(),
+            license: self.license_status().await,
+            integrity: self.dg.integrity(false).await.ok(),
+            keys: match self.keyring().await {
+                Ok(keyring) => keyring.identities(),
+                Err(_) => Vec::new(),
+            },
+            read_only: self.read_only(),
+            ready: self.ready(),
+            locked: self.locked(),
+            key_locked: self.key_locked(),
+        }
+    }
+
+    pub fn ready(&self) -> bool {
+        self.ready.load(Ordering::SeqCst)
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.read_only.load(Ordering::SeqCst)
+    }
+
+    pub fn locked(&self) -> bool {
+        self.locked.load(Ordering::SeqCst)
+    }
+
+    pub fn key_locked(&self) -> bool {
+        self.key_locked.load(Ordering::SeqCst)
+    }
+
+    pub async fn lock_key(&self) -> Result<()> {
+        self.dg.lock().await?;
+        self.key_locked.store(true, Ordering::SeqCst);
+        self.emit(ControllerEvent::Completed("master key locked".into()))
+            .await;
+        Ok(())
+    }
+
+    pub async fn unlock_key(&self, passphrase: &str) -> Result<()> {
+        self.dg.unlock(passphrase).await?;
+        self.key_locked.store(false, Ordering::SeqCst);
+        self.emit(ControllerEvent::Completed("master key unlocked".into()))
+            .await;
+        Ok(())
+    }
+
+    pub async fn license_status(&self) -> LicenseStatus {
+        match self.data_dir.lock().await.as_ref() {
+            Some(data_dir) => load_license_status(data_dir, &self.licenses).await,
+            None => LicenseStatus::unlicensed(),
+        }
+    }
+
+    pub async fn feature_enabled(&self, feature: &str) -> bool {
+        self.license_status().await.allows(feature)
+    }
+
+    #[instrument(skip(self, raw))]
+    pub async fn install_license(&self, raw: &[u8]) -> Result<LicenseStatus> {
+        self.ensure_writable("installing a license")?;
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+        let status = install_license(&data_dir, raw, &self.licenses)
+            .await
+            .map_err(|err| anyhow::anyhow!("license install failed: {err}"))?;
+        self.emit(ControllerEvent::Completed(format!(
+            "license installed ({})",
+            status.state.as_str()
+        )))
+        .await;
+        Ok(status)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn encrypt_file(
+        &self,
+        path: &Path,
+        recipients: Vec<String>,
+        labels: Vec<String>,
+        out_dir: Option<PathBuf>,
+    ) -> Result<PathBuf> {
+        self.encrypt_file_with(
+            path,
+            EncryptOptions {
+                recipients,
+                labels,
+                out_dir,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    pub async fn encrypt_file_with(&self, path: &Path, options: EncryptOptions) -> Result<PathBuf> {
+        self.encrypt_file_detailed(path, options)
+            .await
+            .map(|outcome| outcome.output)
+    }
+
+    // Encrypts `path` and then shreds it. The `shred` action on the source is checked with the
+    // policy before anything is encrypted, and the source is only touched once the envelope has
+    // been read back from disk.
+    pub async fn encrypt_and_shred(
+        &self,
+        path: &Path,
+        options: EncryptOptions,
+    ) -> Result<EncryptOutcome> {
+        self.encrypt_file_detailed(
+            path,
+            EncryptOptions {
+                shred_source: true,
+                ..options
+            },
+        )
+        .await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn encrypt_file_detailed(
+        &self,
+        path: &Path,
+        options: EncryptOptions,
+    ) -> Result<EncryptOutcome> {
+        self.start_encrypt_file(path, options).await?.wait().await
+    }
+
+    // Runs the checks up front and returns once the encryption is running on its own task.
+    #[instrument(skip(self))]
+    pub async fn start_encrypt_file(
+        &self,
+        path: &Path,
+        options: EncryptOptions,
+    ) -> Result<PendingOperation<EncryptOutcome>> {
+        let EncryptOptions {
+            mut recipients,
+            mut labels,
+            out_dir,
+            threshold,
+            sign_with,
+            read_receipts,
+            preview,
+            metadata,
+            retention,
+            chunking,
+            previous,
+            shred_source,
+        } = options;
+        let applied = self
+            .encryption_defaults()
+            .apply(&mut recipients, &mut labels);
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        let operation = self
+            .operations
+            .begin("encrypt", canonical.to_string_lossy().as_ref())?;
+        self.guard_identity("encrypt", canonical.to_string_lossy().as_ref())
+            .await?;
+        if shred_source {
+            self.guard_identity(SHRED_ACTION, canonical.to_string_lossy().as_ref())
+                .await?;
+        }
+        self.check_labels(&labels).await?;
+        if let Some(retention) = &retention {
+            retention.validate()?;
+        }
+        if let Some(params) = &chunking {
+            params.validate()?;
+        }
+        let previous = match previous {
+            Some(previous) => Some(
+                load_envelope(&previous)
+                    .await
+                    .with_context(|| format!("unable to load {}", previous.display()))?,
+            ),
+            None => None,
+        };
+        let previous_chunks = match &previous {
+            Some(previous) => chunking::read(&previous.meta)?,
+            None => None,
+        };
+        let operation_id = operation.id();
+        if !applied.is_empty() {
+            self.emit(ControllerEvent::Progress(format!(
+                "applied profile defaults to {}: {}",
+                canonical.display(),
+                applied.describe()
+            )))
+            .await;
+        }
+
+        let out_dir = match out_dir {
+            Some(dir) => Some(dir),
+            None => self.routed_directory(&labels).await?,
+        };
+        let output_directory = match out_dir {
+            Some(dir) => {
+                ensure_directory(&dir).await?;
+                Some(dir)
+            }
+            None => None,
+        };
+
+        let controller = self.clone();
+        let path_buf = canonical.clone();
+        let labels_clone = labels.clone();
+        let recipients_clone = recipients.clone();
+        let output_directory = output_directory.clone();
+        let handle = task::spawn(async move {
+            controller
+                .emit(ControllerEvent::Progress(format!(
+                    "encrypting {}",
+                    path_buf.display()
+                )))
+                .await;
+            let mut progress = controller.progress_reporter(operation_id, "encrypt");
+            let plaintext = progress.read(&path_buf, &operation).await?;
+            let size = plaintext.len() as u64;
+            let quarantine = Quarantine::detect(
+                quarantine::capture(&path_buf),
+                controller.quarantine_handling(),
+            );
+            let extended_attributes = xattrs::capture(&path_buf);
+            let (plaintext, preview) = if preview {
+                task::spawn_blocking(move || {
+                    let preview = thumbnail::generate(&plaintext);
+                    (plaintext, preview)
+                })
+                .await?
+            } else {
+                (plaintext, None)
+            };
+            progress.report("encrypting", 0, size);
+            let mut envelope = controller
+                .dg
+                .encrypt(EncryptRequest {
+                    plaintext,
+                    labels: labels_clone,
+                    recipients: recipients_clone,
+                    threshold,
+                    preview,
+                    metadata,
+                    chunking,
+                    previous,
+                    journal: false,
+                })
+                .await
+                .map_err(|err| anyhow::anyhow!("encryption failed: {err}"))?;
+            progress.report("encrypting", size, size);
+            let dedup = match (&previous_chunks, chunking::read(&envelope.meta)?) {
+                (Some(previous), Some(current)) => Some(DedupStats::between(previous, &current)),
+                _ => None,
+            };
+            if let Some(dedup) = &dedup {
+                controller
+                    .emit(ControllerEvent::Progress(format!(
+                        "reused {} of {} chunks ({} of {} bytes) from the previous version of {}",
+                        dedup.reused_chunks,
+                        dedup.chunks,
+                        dedup.reused_bytes,
+                        dedup.bytes,
+                        path_buf.display()
+                    )))
+                    .await;
+            }
+            if read_receipts {
+                receipts::mark(&mut envelope);
+            }
+            if let Some(retention) = &retention {
+                retention::write(&mut envelope.meta, retention)?;
+            }
+            if let Some(quarantine) = &quarantine {
+                quarantine::write(&mut envelope.meta, quarantine)?;
+            }
+            if !extended_attributes.is_empty() {
+                xattrs::write(&mut envelope.meta, &extended_attributes)?;
+            }
+            operation.check()?;
+            let target = encrypted_target(
+                &path_buf,
+                output_directory.as_deref(),
+                &controller.output_templates(),
+                &labels,
+            )?;
+            let staging = enriched_extension(&target, "tmp");
+            let written = progress
+                .write(
+                    &staging,
+                    &encode_envelope(&envelope, Some(&path_buf))?,
+                    &operation,
+                )
+                .await;
+            if let Err(err) = written {
+                let _ = fs::remove_file(&staging).await;
+                return Err(err);
+            }
+            fs::rename(&staging, &target)
+                .await
+                .with_context(|| format!("failed to write {}", target.display()))?;
+            controller.index_envelope(&target, &envelope).await?;
+            if let Some(signer) = sign_with.as_deref() {
+                controller.write_signature(&target, signer).await?;
+            }
+            controller
+                .emit(ControllerEvent::Completed(format!(
+                    "wrote encrypted envelope {}",
+                    target.display()
+                )))
+                .await;
+            let shredded = if shred_source {
+                Some(
+                    controller
+                        .shred_source(&path_buf, &target, &envelope)
+                        .await?,
+                )
+            } else {
+                None
+            };
+            Ok::<_, anyhow::Error>(EncryptOutcome {
+                output: target,
+                defaults: applied,
+                dedup,
+                shredded,
+            })
+        });
+        Ok(self.operations.spawned(operation_id, handle))
+    }
+
+    async fn shred_source(
+        &self,
+        source: &Path,
+        target: &Path,
+        envelope: &Envelope,
+    ) -> Result<ShredReport> {
+        let written = load_envelope(target)
+            .await
+            .with_context(|| format!("unable to read back {}", target.display()))?;
+        if written.bytes != envelope.bytes {
+            anyhow::bail!(
+                "{} does not match what was encrypted; {} was left in place",
+                target.display(),
+                source.display()
+            );
+        }
+        let report = shred::shred_file(source, OVERWRITE_PASSES)
+            .await
+            .with_context(|| {
+                format!(
+                    "encrypted {} to {} but could not shred it",
+                    source.display(),
+                    target.display()
+                )
+            })?;
+        self.audit_retention(
+            SHRED_ACTION,
+            source,
+            "allow",
+            serde_json::json!({
+                "envelope": target,
+                "bytes": report.bytes,
+                "passes": report.passes,
+            }),
+        )
+        .await;
+        self.emit(ControllerEvent::Completed(format!(
+            "shredded {} ({} passes)",
+            source.display(),
+            report.passes
+        )))
+        .await;
+        Ok(report)
+    }
+
+    pub async fn cancel_operation(&self, id: u64) -> Result<ActiveOperation> {
+        let operation = self.operations.cancel(id)?;
+        self.emit(ControllerEvent::Cancelled(format!(
+            "cancelled {} of {}",
+            operation.kind, operation.target
+        )))
+        .await;
+        Ok(operation)
+    }
+
+    fn read_receipt(&self, path: &Path, envelope: &Envelope) -> Option<ReadReceipt> {
+        if !receipts::requests_receipts(&envelope.meta) {
+            return None;
+        }
+        Some(receipts::receipt_for(
+            path,
+            envelope,
+            self.identity().subject(),
+        ))
+    }
+
+    // Delivery runs detached so an unreachable sink never holds up the decryption.
+    fn send_read_receipt(&self, receipt: Option<ReadReceipt>) {
+        let Some(receipt) = receipt else {
+            return;
+        };
+        let Some(sink) = self
+            .receipts
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+        else {
+            return;
+        };
+        task::spawn(async move {
+            let envelope = receipt.envelope.clone();
+            if let Err(err) = sink.deliver(receipt).await {
+                warn!(envelope = %envelope, error = %err, "read receipt delivery failed");
+            }
+        });
+    }
+
+    // Publishes a sealed unlock challenge to every paired device and waits for the first signed
+    // answer. Nothing is asked when no channel is set or no device has been paired.
+    async fn await_approval(&self, path: &Path, envelope: &Envelope) -> Result<()> {
+        let Some(approval) = self
+            .approval
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+        else {
+            return Ok(());
+        };
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+        let devices = pairing::paired_channels(&data_dir).await?;
+        if devices.is_empty() {
+            return Ok(());
+        }
+        let device_id = self.device_identity().await?.device_id;
+        let challenge = UnlockChallenge::new(
+            &device_id,
+            envelope,
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+            self.identity().subject(),
+            unix_seconds(),
+            approval.timeout.as_secs(),
+        );
+        for (device, channel) in &devices {
+            let sealed = pairing::seal(channel, &challenge.request_id, &challenge)?;
+            approval
+                .channel
+                .publish(device, sealed)
+                .await
+                .with_context(|| format!("unable to reach paired device {}", device.name))?;
+        }
+        self.emit(ControllerEvent::Progress(format!(
+            "waiting for a paired device to approve opening {}",
+            path.display()
+        )))
+        .await;
+
+        let deadline = Instant::now() + approval.timeout;
+        loop {
+            for (device, channel) in &devices {
+                let answer = match approval.channel.poll(device, &challenge.request_id).await {
+                    Ok(Some(answer)) => answer,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        warn!(device = %device.id, error = %err, "polling paired device failed");
+                        continue;
+                    }
+                };
+                // An answer that does not open or verify is ignored rather than trusted.
+                let Ok(answer) = pairing::open::<UnlockApproval>(channel, &answer) else {
+                    continue;
+                };
+                if !answer.verify(&challenge, device).unwrap_or(false) {
+                    continue;
+                }
+                let effect = if answer.approved { "allow" } else { "deny" };
+                self.audit_retention(
+                    APPROVAL_ACTION,
+                    path,
+                    effect,
+                    serde_json::json!({
+                        "device": device.id,
+                        "device_name": device.name,
+                        "request": challenge.request_id,
+                    }),
+                )
+                .await;
+                if !answer.approved {
+                    return Err(UserError::approval_declined(&device.name));
+                }
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                self.audit_retention(
+                    APPROVAL_ACTION,
+                    path,
+                    "deny",
+                    serde_json::json!({ "request": challenge.request_id, "timed_out": true }),
+                )
+                .await;
+                return Err(UserError::approval_timed_out(
+                    path,
+                    approval.timeout.as_secs(),
+                ));
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn compare_envelopes(
+        &self,
+        first: &Path,
+        second: &Path,
+        decrypt: bool,
+    ) -> Result<EnvelopeComparison> {
+        let mut comparison = compare::compare(
+            compare::summarize(first).await?,
+            compare::summarize(second).await?,
+        );
+        if decrypt && comparison.same_content.is_none() {
+            let first = self.content_digest(first).await?;
+            let second = self.content_digest(second).await?;
+            comparison.same_content = Some(first == second);
+        }
+        Ok(comparison)
+    }
+
+    async fn content_digest(&self, path: &Path) -> Result<String> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        let _operation = self
+            .operations
+            .begin("compare", canonical.to_string_lossy().as_ref())?;
+        self.guard_identity("decrypt", canonical.to_string_lossy().as_ref())
+            .await?;
+        let envelope = load_envelope(&canonical)
+            .await
+            .with_context(|| format!("unable to load {}", canonical.display()))?;
+        self.await_approval(&canonical, &envelope).await?;
+        let receipt = self.read_receipt(&canonical, &envelope);
+        let plaintext = self
+            .dg
+            .decrypt(envelope)
+            .await
+            .map_err(|err| anyhow::anyhow!("decryption failed: {err}"))?;
+        self.send_read_receipt(receipt);
+        Ok(hex::encode(Sha256::digest(plaintext.as_slice())))
+    }
+
+    #[instrument(skip(self, content))]
+    pub async fn encrypt_text(
+        &self,
+        mut content: Zeroizing<String>,
+        recipients: Vec<String>,
+        labels: Vec<String>,
+    ) -> Result<PathBuf> {
+        let notes = self
+            .data_dir
+            .lock()
+            .await
+            .as_ref()
+            .map(|data_dir| data_dir.join(NOTES_DIR))
+            .ok_or_else(UserError::not_booted)?;
+        fs::create_dir_all(&notes)
+            .await
+            .with_context(|| format!("unable to create {}", notes.display()))?;
+        let target = notes.join(format!("note-{}.{ENCRYPTED_EXTENSION}", unix_nanos()));
+        let operation = self
+            .operations
+            .begin("encrypt", target.to_string_lossy().as_ref())?;
+        self.guard_identity("encrypt", target.to_string_lossy().as_ref())
+            .await?;
+        self.check_labels(&labels).await?;
+
+        let mut envelope = self
+            .dg
+            .encrypt(EncryptRequest {
+                plaintext: std::mem::take(&mut *content).into_bytes(),
+                labels,
+                recipients,
+                ..Default::default()
+            })
+            .await
+            .map_err(|err| anyhow::anyhow!("encryption failed: {err}"))?;
+        if let Some(meta) = envelope.meta.as_object_mut() {
+            meta.insert("content_type".into(), "text/plain; charset=utf-8".into());
+        }
+        operation.check()?;
+        persist_envelope(&target, &envelope, None)
+            .await
+            .with_context(|| format!("failed to write {}", target.display()))?;
+        self.index_envelope(&target, &envelope).await?;
+        self.emit(ControllerEvent::Completed(format!(
+            "wrote encrypted note {}",
+            target.display()
+        )))
+        .await;
+        Ok(target)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn decrypt_to_text(&self, path: &Path) -> Result<Zeroizing<String>> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        self.guard_identity("decrypt", canonical.to_string_lossy().as_ref())
+            .await?;
+        let envelope = load_envelope(&canonical)
+            .await
+            .with_context(|| format!("unable to load {}", canonical.display()))?;
+        self.await_approval(&canonical, &envelope).await?;
+        let receipt = self.read_receipt(&canonical, &envelope);
+        let plaintext = self
+            .dg
+            .decrypt(envelope)
+            .await
+            .map_err(|err| anyhow::anyhow!("decryption failed: {err}"))?;
+        self.send_read_receipt(receipt);
+        let text = std::str::from_utf8(&plaintext)
+            .map_err(|_| anyhow::anyhow!("{} does not contain UTF-8 text", canonical.display()))?;
+        Ok(Zeroizing::new(text.to_owned()))
+    }
+
+    #[instrument(skip(self))]
+    pub async fn sign_file(&self, path: &Path, signer: &str) -> Result<PathBuf> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        let _operation = self
+            .operations
+            .begin("sign", canonical.to_string_lossy().as_ref())?;
+        self.guard_identity("sign", canonical.to_string_lossy().as_ref())
+            .await?;
+        self.write_signature(&canonical, signer).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn verify_signature(
+        &self,
+        path: &Path,
+        signature: Option<&Path>,
+        signer: &str,
+    ) -> Result<bool> {
+        let signature_path = signature
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| signature_path(path));
+        let data = fs::read(path)
+            .await
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let detached = fs::read(&signature_path)
+            .await
+            .with_context(|| format!("failed to read {}", signature_path.display()))?;
+        let signer = self.resolve_signer(signer).await?;
+        self.dg
+            .verify_detached(&signer, &data, &detached)
+            .await
+            .map_err(|err| anyhow::anyhow!("signature verification failed: {err}"))
+    }
+
+    // Trust store ids resolve to their recorded key, but only for signers trusted with envelopes.
+    async fn resolve_signer(&self, signer: &str) -> Result<String> {
+        if self.data_dir.lock().await.is_none() {
+            return Ok(signer.to_owned());
+        }
+        match self.trust_store().await?.get(signer) {
+            Some(trusted) if trusted.has_role(TrustRole::Envelope) => {
+                Ok(trusted.public_key.clone())
+            }
+            Some(_) => Err(anyhow::anyhow!("{signer} is not trusted to sign envelopes")),
+            None => Ok(signer.to_owned()),
+        }
+    }
+
+    pub async fn trust_store(&self) -> Result<TrustStore> {
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+        let Some(signed) = SignedTrustStore::read(&data_dir).await? else {
+            return Ok(TrustStore::default());
+        };
+        let verified = self
+            .dg
+            .verify_detached(&signed.signed_by, &signed.payload()?, &signed.signature()?)
+            .await
+            .map_err(|err| anyhow::anyhow!("trust store signature check failed: {err}"))?;
+        if !verified {
+            return Err(anyhow::anyhow!(
+                "trust store signature does not verify; it was changed outside Data Guardian"
+            ));
+        }
+        Ok(signed.store()?)
+    }
+
+    #[instrument(skip(self, signer))]
+    pub async fn import_trusted_signer(
+        &self,
+        signer: NewSigner,
+        sign_with: &str,
+    ) -> Result<TrustedSigner> {
+        let id = signer.id.clone();
+        self.update_trust_store(&id, sign_with, |store| store.import(signer, unix_seconds()))
+            .await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn remove_trusted_signer(&self, id: &str, sign_with: &str) -> Result<TrustedSigner> {
+        self.update_trust_store(id, sign_with, |store| store.remove(id))
+            .await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn pin_trusted_signer(
+        &self,
+        id: &str,
+        pinned: bool,
+        sign_with: &str,
+    ) -> Result<TrustedSigner> {
+        self.update_trust_store(id, sign_with, |store| store.pin(id, pinned))
+            .await
+    }
+
+    async fn update_trust_store(
+        &self,
+        id: &str,
+        sign_with: &str,
+        change: impl FnOnce(&mut TrustStore) -> Result<TrustedSigner, DGError>,
+    ) -> Result<TrustedSigner> {
+        self.ensure_writable("changing the trust store")?;
+        self.guard_identity(TRUST_ACTION, id).await?;
+        let _serialized = self.trust.lock().await;
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+        let mut store = self.trust_store().await?;
+        let changed = change(&mut store)?;
+        let payload = store.to_payload()?;
+        let signature = self
+            .dg
+            .sign_detached(sign_with, &payload)
+            .await
+            .map_err(|err| anyhow::anyhow!("signing the trust store failed: {err}"))?;
+        SignedTrustStore::new(&payload, sign_with, &signature)
+            .write(&data_dir)
+            .await?;
+        self.emit(ControllerEvent::Completed(format!(
+            "updated trusted signer {id}"
+        )))
+        .await;
+        Ok(changed)
+    }
+
+    pub async fn verify_runtime_bundle(&self, dir: &Path) -> Result<Option<TrustedSigner>> {
+        let store = self.trust_store().await?;
+        if !store.requires(TrustRole::Runtime) {
+            return Ok(None);
+        }
+        Ok(Some(trust::verify_bundle(&store, dir).await?))
+    }
+
+    pub async fn device_identity(&self) -> Result<DeviceIdentity> {
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+        device::load_identity(&data_dir).await?.ok_or_else(|| {
+            anyhow::anyhow!(
+                "{} has no device identity; open it read-write once to create one",
+                data_dir.display()
+            )
+        })
+    }
+
+    pub async fn sign_as_device(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+        let key = DeviceKey::load(&data_dir)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("this data directory has no device identity"))?;
+        Ok(key.sign(data).to_vec())
+    }
+
+    // Starts pairing a phone or second device. The offer is shown as a QR code; only the last
+    // offer started can be completed.
+    pub async fn start_pairing(&self) -> Result<PairingOffer> {
+        self.ensure_writable("pairing a device")?;
+        self.guard_identity(PAIR_ACTION, "device").await?;
+        let device_id = self.device_identity().await?.device_id;
+        let pending = pairing::offer(&device_id, PAIRING_TTL.as_secs(), unix_seconds());
+        let offer = pending.offer.clone();
+        *self.pairing.lock().await = Some(pending);
+        Ok(offer)
+    }
+
+    pub async fn complete_pairing(&self, response: &PairingResponse) -> Result<PairedDevice> {
+        self.ensure_writable("pairing a device")?;
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+        let mut pending = self.pairing.lock().await;
+        let (device, channel) = pending
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("no device pairing is in progress"))?
+            .accept(response, unix_seconds())?;
+        pairing::record_pairing(&data_dir, device.clone(), &channel).await?;
+        pending.take();
+        drop(pending);
+        self.audit_retention(
+            PAIR_ACTION,
+            &data_dir,
+            "allow",
+            serde_json::json!({ "device": device.id, "device_name": device.name }),
+        )
+        .await;
+        self.emit(ControllerEvent::Completed(format!(
+            "paired {}",
+            device.name
+        )))
+        .await;
+        Ok(device)
+    }
+
+    // Waits on the approval channel for the device that scanned the current offer.
+    pub async fn await_pairing(&self) -> Result<PairedDevice> {
+        let approval = self
+            .approval
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("no approval channel is configured"))?;
+        let offer = self
+            .pairing
+            .lock()
+            .await
+            .as_ref()
+            .map(|pending| pending.offer.clone())
+            .ok_or_else(|| anyhow::anyhow!("no device pairing is in progress"))?;
+        loop {
+            if let Some(response) = approval.channel.pairing_response(&offer).await? {
+                return self.complete_pairing(&response).await;
+            }
+            if unix_seconds() >= offer.expires_at {
+                self.pairing.lock().await.take();
+                anyhow::bail!("the pairing code expired before a device answered");
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    pub async fn paired_devices(&self) -> Result<Vec<PairedDevice>> {
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+        Ok(pairing::load_paired(&data_dir).await?)
+    }
+
+    pub async fn unpair_device(&self, id: &str) -> Result<PairedDevice> {
+        self.ensure_writable("unpairing a device")?;
+        self.guard_identity(PAIR_ACTION, "device").await?;
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+        let device = pairing::remove_pairing(&data_dir, id).await?;
+        self.audit_retention(
+            PAIR_ACTION,
+            &data_dir,
+            "allow",
+            serde_json::json!({ "device": device.id, "device_name": device.name, "unpaired": true }),
+        )
+        .await;
+        Ok(device)
+    }
+
+    #[instrument(skip(self, signed))]
+    pub async fn apply_management_command(
+        &self,
+        signed: &SignedCommand,
+    ) -> Result<ManagementOutcome> {
+        self.ensure_writable("applying management commands")?;
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+        // A locked engine is shut down, so unlocking verifies against the keyring on disk; boot again afterwards.
+        if signed.command()?.action == ManagementAction::Unlock {
+            let (command, signer) = management::unlock(&data_dir, signed, unix_seconds()).await?;
+            self.locked.store(false, Ordering::SeqCst);
+            return Ok(ManagementOutcome {
+                command,
+                signer: signer.id,
+                rotation_job: None,
+            });
+        }
+        let store = self.trust_store().await?;
+        let device_id = device::load_identity(&data_dir)
+            .await?
+            .map(|identity| identity.device_id);
+        let (command, signer) = signed.verify(&store, device_id.as_deref(), unix_seconds())?;
+        if !management::mark_applied(&data_dir, &command.id).await? {
+            return Err(anyhow::anyhow!(
+                "management command {} was already applied",
+                command.id
+            ));
+        }
+        let subject = format!("management:{}", signer.id);
+        let event = AuditEvent::new(
+            &subject,
+            &format!("management.{}", command.action.as_str()),
+            device_id.as_deref().unwrap_or("*"),
+            "allow",
+        )
+        .with_details(serde_json::json!({ "command": command.id, "reason": command.reason }));
+        if let Some(audit) = self.audit.lock().await.as_ref() {
+            audit.record(event).await?;
+        }
+
+        let mut rotation_job = None;
+        match &command.action {
+            ManagementAction::Lock | ManagementAction::WipeKeys => {
+                let wipe = command.action == ManagementAction::WipeKeys;
+                self.lock_down(
+                    &data_dir,
+                    LockState {
+                        command: Some(command.id.clone()),
+                        locked_by: subject,
+                        reason: command.reason.clone(),
+                        locked_at: unix_seconds(),
+                        keys_wiped: wipe,
+                    },
+                )
+                .await?;
+            }
+            ManagementAction::ForceRotation { key_id } => {
+                rotation_job = Some(self.revoke_recipient(key_id).await?.job);
+            }
+            ManagementAction::Unlock => {}
+        }
+        self.emit(ControllerEvent::Completed(format!(
+            "applied management command {} ({})",
+            command.id,
+            command.action.as_str()
+        )))
+        .await;
+        Ok(ManagementOutcome {
+            command,
+            signer: signer.id,
+            rotation_job,
+        })
+    }
+
+    // Local counterpart of a `wipe_keys` command; callers re-authenticate the user first.
+    #[instrument(skip(self))]
+    pub async fn panic_wipe(&self, reason: &str) -> Result<LockState> {
+        self.ensure_writable("wiping keys")?;
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+        self.guard_identity(PANIC_WIPE_ACTION, &data_dir.to_string_lossy())
+            .await?;
+        let state = LockState {
+            command: None,
+            locked_by: self.identity().subject(),
+            reason: reason.to_owned(),
+            locked_at: unix_seconds(),
+            keys_wiped: true,
+        };
+        self.lock_down(&data_dir, state.clone()).await?;
+        Ok(state)
+    }
+
+    async fn lock_down(&self, data_dir: &Path, state: LockState) -> Result<()> {
+        management::write_lock(data_dir, &state).await?;
+        self.locked.store(true, Ordering::SeqCst);
+        self.ready.store(false, Ordering::SeqCst);
+        self.dg
+            .shutdown()
+            .await
+            .map_err(|err| anyhow::anyhow!("engine shutdown failed: {err}"))?;
+        let wiped = if state.keys_wiped {
+            Some(wipe_keys(data_dir).await?)
+        } else {
+            None
+        };
+        warn!(locked_by = %state.locked_by, wiped_files = ?wiped, "data directory locked");
+        self.emit(ControllerEvent::Completed(match wiped {
+            Some(files) => format!(
+                "keys wiped ({files} files) and device locked by {}",
+                state.locked_by
+            ),
+            None => format!("device locked by {}", state.locked_by),
+        }))
+        .await;
+        Ok(())
+    }
+
+    #[instrument(skip(self, enrollment), fields(endpoint = %enrollment.endpoint))]
+    pub async fn record_enrollment(&self, enrollment: Enrollment) -> Result<DeviceIdentity> {
+        self.ensure_writable("enrolling this device")?;
+        self.guard_identity(ENROLL_ACTION, &enrollment.endpoint)
+            .await?;
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+        let organization = enrollment.organization.clone();
+        let identity = device::record_enrollment(&data_dir, enrollment).await?;
+        self.emit(ControllerEvent::Completed(format!(
+            "enrolled device {} with {organization}",
+            identity.device_id
+        )))
+        .await;
+        Ok(identity)
+    }
+
+    async fn write_signature(&self, path: &Path, signer: &str) -> Result<PathBuf> {
+        let data = fs::read(path)
+            .await
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let detached = self
+            .dg
+            .sign_detached(signer, &data)
+            .await
+            .map_err(|err| anyhow::anyhow!("signing failed: {err}"))?;
+        let target = signature_path(path);
+        fs::write(&target, detached)
+            .await
+            .with_context(|| format!("failed to write {}", target.display()))?;
+        self.emit(ControllerEvent::Completed(format!(
+            "wrote detached signature {}",
+            target.display()
+        )))
+        .await;
+        Ok(target)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn decrypt_file(&self, path: &Path, out_dir: Option<PathBuf>) -> Result<PathBuf> {
+        self.start_decrypt_file(path, out_dir).await?.wait().await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn start_decrypt_file(
+        &self,
+        path: &Path,
+        out_dir: Option<PathBuf>,
+    ) -> Result<PendingOperation<PathBuf>> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        let operation = self
+            .operations
+            .begin("decrypt", canonical.to_string_lossy().as_ref())?;
+        self.guard_identity("decrypt", canonical.to_string_lossy().as_ref())
+            .await?;
+        let operation_id = operation.id();
+
+        let output_directory = match out_dir {
+            Some(dir) => {
+                ensure_directory(&dir).await?;
+                Some(dir)
+            }
+            None => None,
+        };
+
+        let controller = self.clone();
+        let path_buf = canonical.clone();
+        let output_directory_clone = output_directory.clone();
+        let handle = task::spawn(async move {
+            controller
+                .emit(ControllerEvent::Progress(format!(
+                    "decrypting {}",
+                    path_buf.display()
+                )))
+                .await;
+            let mut progress = controller.progress_reporter(operation_id, "decrypt");
+            stored::check_size(
+                fs::metadata(&path_buf)
+                    .await
+                    .with_context(|| format!("unable to load {}", path_buf.display()))?
+                    .len(),
+            )?;
+            let data = progress.read(&path_buf, &operation).await?;
+            let (envelope, _) = StoredEnvelope::parse(&data)
+                .and_then(StoredEnvelope::into_envelope)
+                .with_context(|| format!("unable to load {}", path_buf.display()))?;
+            drop(data);
+            let labels = envelope_labels(&envelope);
+            let quarantine = quarantine::read(&envelope.meta);
+            let extended_attributes = xattrs::read(&envelope.meta);
+            controller.await_approval(&path_buf, &envelope).await?;
+            let receipt = controller.read_receipt(&path_buf, &envelope);
+            let size = envelope.bytes.len() as u64;
+            progress.report("decrypting", 0, size);
+            let plaintext = controller
+                .dg
+                .decrypt(envelope)
+                .await
+                .map_err(|err| anyhow::anyhow!("decryption failed: {err}"))?;
+            progress.report("decrypting", size, size);
+            controller.send_read_receipt(receipt);
+            operation.check()?;
+            let target = decrypted_target(
+                &path_buf,
+                output_directory_clone.as_deref(),
+                &controller.output_templates(),
+                &labels,
+            )?;
+            progress.write(&target, &plaintext, &operation).await?;
+            controller.reapply_quarantine(&target, quarantine).await;
+            controller
+                .reapply_xattrs(&target, extended_attributes)
+                .await;
+            controller
+                .emit(ControllerEvent::Completed(format!(
+                    "wrote decrypted file {}",
+                    target.display()
+                )))
+                .await;
+            Ok::<_, anyhow::Error>(target)
+        });
+        Ok(self.operations.spawned(operation_id, handle))
+    }
+
+    #[instrument(skip(self))]
+    pub async fn decrypt_directory(
+        &self,
+        dir: &Path,
+        out_dir: &Path,
+    ) -> Result<DirectoryDecryptReport> {
+        let root = dir
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(dir, err))?;
+        let operation = self
+            .operations
+            .begin("decrypt-directory", root.to_string_lossy().as_ref())?;
+        self.guard_identity("decrypt", root.to_string_lossy().as_ref())
+            .await?;
+        let out_dir = &extended(out_dir);
+        fs::create_dir_all(out_dir)
+            .await
+            .with_context(|| format!("unable to create {}", out_dir.display()))?;
+        let candidates = find_envelopes(&root, ENCRYPTED_EXTENSION, true).await?;
+        let mut report = DirectoryDecryptReport {
+            root: root.clone(),
+            out_dir: out_dir.to_path_buf(),
+            scanned: candidates.len(),
+            ..Default::default()
+        };
+        for (position, path) in candidates.into_iter().enumerate() {
+            operation.check()?;
+            self.emit(ControllerEvent::Step(format!(
+                "decrypting {}/{} {}",
+                position + 1,
+                report.scanned,
+                path.display()
+            )))
+            .await;
+            match self.restore_envelope(&root, out_dir, &path).await {
+                Ok(output) => report.restored.push(RestoredFile {
+                    envelope: path,
+                    output,
+                }),
+                Err(err) => {
+                    warn!(path = %path.display(), error = %err, "envelope restore failed");
+                    self.emit(ControllerEvent::Error(format!(
+                        "failed to decrypt {}: {err}",
+                        path.display()
+                    )))
+                    .await;
+                    report.failures.push(RestoreFailure {
+                        path,
+                        error: err.to_string(),
+                    });
+                }
+            }
+        }
+        self.emit(ControllerEvent::Completed(format!(
+            "restored {} of {} envelopes into {}",
+            report.restored.len(),
+            report.scanned,
+            out_dir.display()
+        )))
+        .await;
+        Ok(report)
+    }
+
+    // Encrypts every file below `dir` as the walker finds it, with at most `parallel_files` in
+    // flight. With an `out_dir` the envelopes mirror the folder structure below it.
+    #[instrument(skip(self, options))]
+    pub async fn encrypt_directory(
+        &self,
+        dir: &Path,
+        options: EncryptOptions,
+    ) -> Result<DirectoryEncryptReport> {
+        let root = dir
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(dir, err))?;
+        let operation = self
+            .operations
+            .begin("encrypt-directory", root.to_string_lossy().as_ref())?;
+        self.guard_identity("encrypt", root.to_string_lossy().as_ref())
+            .await?;
+        let settings = self.walk_settings();
+        let parallel = settings.parallelism();
+        let mut walked = walk::spawn(&root, &settings, &format!(".{ENCRYPTED_EXTENSION}"))?;
+        let mut running = task::JoinSet::new();
+        let mut report = DirectoryEncryptReport {
+            root: root.clone(),
+            ..Default::default()
+        };
+        loop {
+            while running.len() >= parallel {
+                if let Some(done) = running.join_next().await {
+                    self.record_encrypted(&mut report, done?).await;
+                }
+            }
+            operation.check()?;
+            let Some(next) = walked.recv().await else {
+                break;
+            };
+            let path = match next {
+                Ok(path) => path,
+                Err(failure) => {
+                    warn!(path = %failure.path.display(), error = %failure.error, "directory walk error");
+                    report.failures.push(failure);
+                    continue;
+                }
+            };
+            report.scanned += 1;
+            self.emit(ControllerEvent::Step(format!(
+                "encrypting {} {}",
+                report.scanned,
+                path.display()
+            )))
+            .await;
+            let mut file_options = options.clone();
+            if let Some(out_dir) = &options.out_dir {
+                let parent = path.parent().unwrap_or(&root);
+                let relative = parent.strip_prefix(&root).unwrap_or(Path::new(""));
+                let target = out_dir.join(relative);
+                fs::create_dir_all(extended(&target))
+                    .await
+                    .with_context(|| format!("unable to create {}", target.display()))?;
+                file_options.out_dir = Some(target);
+            }
+            let controller = self.clone();
+            running.spawn(async move {
+                let outcome = controller.encrypt_file_with(&path, file_options).await;
+                (path, outcome)
+            });
+        }
+        while let Some(done) = running.join_next().await {
+            self.record_encrypted(&mut report, done?).await;
+        }
+        self.emit(ControllerEvent::Completed(format!(
+            "encrypted {} of {} files in {}",
+            report.encrypted.len(),
+            report.scanned,
+            root.display()
+        )))
+        .await;
+        Ok(report)
+    }
+
+    async fn record_encrypted(
+        &self,
+        report: &mut DirectoryEncryptReport,
+        (source, outcome): (PathBuf, Result<PathBuf>),
+    ) {
+        match outcome {
+            Ok(output) => report.encrypted.push(EncryptedFile { source, output }),
+            Err(err) => {
+                warn!(path = %source.display(), error = %err, "directory encryption failed");
+                self.emit(ControllerEvent::Error(format!(
+                    "failed to encrypt {}: {err}",
+                    source.display()
+                )))
+                .await;
+                report.failures.push(WalkFailure {
+                    path: source,
+                    error: err.to_string(),
+                });
+            }
+        }
+    }
+
+    // Encrypts whatever has settled in the inbox and scrubs the plaintext once its envelope is written.
+    pub async fn process_inbox(&self, inbox: &InboxSettings) -> Result<InboxReport> {
+        inbox.validate()?;
+        let out_dir = inbox.output_dir();
+        fs::create_dir_all(extended(&out_dir))
+            .await
+            .with_context(|| format!("unable to create {}", out_dir.display()))?;
+        let (ready, pending) =
+            inbox::ready_files(&inbox.dir, ENCRYPTED_EXTENSION, SystemTime::now()).await?;
+        let mut report = InboxReport {
+            inbox: inbox.name.clone(),
+            pending,
+            ..Default::default()
+        };
+        for path in ready {
+            let outcome = self
+                .encrypt_file_detailed(
+                    &path,
+                    EncryptOptions {
+                        recipients: inbox.recipients.clone(),
+                        labels: inbox.labels.clone(),
+                        out_dir: Some(out_dir.clone()),
+                        ..Default::default()
+                    },
+                )
+                .await;
+            let result = match outcome {
+                Ok(outcome) => scrub(&path).await.map(|()| outcome.output),
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(output) => report.encrypted.push(InboxFile {
+                    source: path,
+                    output,
+                }),
+                Err(err) => {
+                    warn!(inbox = %inbox.name, path = %path.display(), error = %err, "inbox file not encrypted");
+                    self.emit(ControllerEvent::Error(format!(
+                        "inbox {} could not encrypt {}: {err}",
+                        inbox.name,
+                        path.display()
+                    )))
+                    .await;
+                    report.failures.push(InboxFailure {
+                        path,
+                        error: err.to_string(),
+                    });
+                }
+            }
+        }
+        if !report.is_empty() {
+            self.emit(ControllerEvent::Completed(format!(
+                "inbox {} encrypted {} file(s)",
+                inbox.name,
+                report.encrypted.len()
+            )))
+            .await;
+        }
+        Ok(report)
+    }
+
+    async fn restore_envelope(&self, root: &Path, out_dir: &Path, path: &Path) -> Result<PathBuf> {
+        self.guard_identity("decrypt", path.to_string_lossy().as_ref())
+            .await?;
+        let (envelope, original_path) = load_stored(path).await?;
+        let file_name = match original_path.as_deref().and_then(original_name) {
+            Some(name) => name.into(),
+            None => self
+                .output_templates()
+                .decrypted_name(path, &envelope_labels(&envelope))?,
+        };
+        let target = restore_target(root, out_dir, path, &file_name)?;
+        let quarantine = quarantine::read(&envelope.meta);
+        let extended_attributes = xattrs::read(&envelope.meta);
+        self.await_approval(path, &envelope).await?;
+        let receipt = self.read_receipt(path, &envelope);
+        let plaintext = self
+            .dg
+            .decrypt(envelope)
+            .await
+            .map_err(|err| anyhow::anyhow!("decryption failed: {err}"))?;
+        self.send_read_receipt(receipt);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("unable to create {}", parent.display()))?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&target)
+            .await
+            .with_context(|| format!("unable to create {}", target.display()))?;
+        file.write_all(&plaintext).await?;
+        file.flush().await?;
+        self.reapply_quarantine(&target, quarantine).await;
+        self.reapply_xattrs(&target, extended_attributes).await;
+        Ok(target)
+    }
+
+    // A decrypted download should still go through Gatekeeper, so the attributes sealed at
+    // encryption go back on unless the user chose to strip them.
+    async fn reapply_xattrs(&self, target: &Path, attributes: Option<ExtendedAttributes>) {
+        let Some(attributes) = attributes else {
+            return;
+        };
+        let report = xattrs::restore(target, &attributes);
+        if report.skipped.is_empty() {
+            return;
+        }
+        let skipped: Vec<String> = report
+            .skipped
+            .iter()
+            .map(|skipped| format!("{} ({})", skipped.name, skipped.reason))
+            .collect();
+        warn!(path = %target.display(), skipped = ?skipped, "extended attributes not restored");
+        self.emit(ControllerEvent::Error(format!(
+            "{} was decrypted with {} of {} extended attributes; skipped {}",
+            target.display(),
+            report.restored.len(),
+            report.restored.len() + report.skipped.len(),
+            skipped.join(", ")
+        )))
+        .await;
+    }
+
+    async fn reapply_quarantine(&self, target: &Path, quarantine: Option<Quarantine>) {
+        let Some(quarantine) = quarantine else {
+            return;
+        };
+        if self.quarantine_handling() == QuarantineHandling::Strip {
+            return;
+        }
+        match quarantine::restore(target, &quarantine) {
+            Ok(true) => {
+                self.emit(ControllerEvent::Progress(format!(
+                    "{} was downloaded{}; Gatekeeper will check it when it is opened",
+                    target.display(),
+                    quarantine
+                        .agent
+                        .as_deref()
+                        .map(|agent| format!(" by {agent}"))
+                        .unwrap_or_default()
+                )))
+                .await;
+            }
+            Ok(false) => {}
+            Err(err) => {
+                warn!(path = %target.display(), error = %err, "quarantine attributes not restored");
+                self.emit(ControllerEvent::Error(format!(
+                    "{} was decrypted without its quarantine attributes: {err}",
+                    target.display()
+                )))
+                .await;
+            }
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn open_viewer(&self, path: &Path, options: ViewerOptions) -> Result<ViewerSession> {
+        self.ensure_writable("staging viewer files")?;
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        self.guard_identity("decrypt", canonical.to_string_lossy().as_ref())
+            .await?;
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+
+        let (envelope, original_path) = load_stored(&canonical)
+            .await
+            .with_context(|| format!("unable to load {}", canonical.display()))?;
+        let file_name = match original_path.as_deref().and_then(original_name) {
+            Some(name) => name.into(),
+            None => canonical
+                .file_stem()
+                .map(|stem| stem.to_os_string())
+                .ok_or_else(|| anyhow::anyhow!("{} has no file name", canonical.display()))?,
+        };
+        self.await_approval(&canonical, &envelope).await?;
+        let receipt = self.read_receipt(&canonical, &envelope);
+        let plaintext = self
+            .dg
+            .decrypt(envelope)
+            .await
+            .map_err(|err| anyhow::anyhow!("decryption failed: {err}"))?;
+        self.send_read_receipt(receipt);
+        let id = unix_nanos() as u64;
+        let staged = stage_plaintext(&viewer_root(&data_dir), id, &file_name, &plaintext).await?;
+        let child = match launch(&staged, options.launcher.as_deref()) {
+            Ok(child) => child,
+            Err(err) => {
+                shred(&staged).await?;
+                return Err(err);
+            }
+        };
+        self.viewers.lock().await.insert(id, staged.clone());
+        self.emit(ControllerEvent::Completed(format!(
+            "opened {} in viewer",
+            canonical.display()
+        )))
+        .await;
+
+        let controller = self.clone();
+        let watched = staged.clone();
+        task::spawn(async move {
+            wait_for_close(child, &watched, options.grace).await;
+            if let Err(err) = controller.close_viewer(id).await {
+                warn!(path = %watched.display(), error = %err, "failed to remove viewer file");
+            }
+        });
+
+        Ok(ViewerSession {
+            id,
+            envelope: canonical,
+            path: staged,
+        })
+    }
+
+    pub async fn close_viewer(&self, id: u64) -> Result<bool> {
+        let Some(path) = self.viewers.lock().await.remove(&id) else {
+            return Ok(false);
+        };
+        shred(&path).await?;
+        self.emit(ControllerEvent::Progress(format!(
+            "removed viewer copy {}",
+            path.display()
+        )))
+        .await;
+        Ok(true)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn preview(&self, path: &Path) -> Result<Option<Preview>> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        self.guard_identity("preview", canonical.to_string_lossy().as_ref())
+            .await?;
+        let envelope = load_envelope(&canonical)
+            .await
+            .with_context(|| format!("unable to load {}", canonical.display()))?;
+        self.dg
+            .preview(&envelope)
+            .await
+            .map_err(|err| anyhow::anyhow!("preview unavailable: {err}"))
+    }
+
+    // Checks part of a chunked envelope without decrypting the rest of it.
+    #[instrument(skip(self))]
+    pub async fn verify_range(
+        &self,
+        path: &Path,
+        offset: u64,
+        len: u64,
+    ) -> Result<RangeVerification> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        self.guard_identity("decrypt", canonical.to_string_lossy().as_ref())
+            .await?;
+        let envelope = load_envelope(&canonical)
+            .await
+            .with_context(|| format!("unable to load {}", canonical.display()))?;
+        Ok(self.dg.verify_range(&envelope, offset, len).await?)
+    }
+
+    // Reads part of a chunked envelope's plaintext, decrypting only the chunks that hold it.
+    #[instrument(skip(self))]
+    pub async fn decrypt_range(
+        &self,
+        path: &Path,
+        offset: u64,
+        len: u64,
+    ) -> Result<Zeroizing<Vec<u8>>> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        self.guard_identity("decrypt", canonical.to_string_lossy().as_ref())
+            .await?;
+        let envelope = load_envelope(&canonical)
+            .await
+            .with_context(|| format!("unable to load {}", canonical.display()))?;
+        self.await_approval(&canonical, &envelope).await?;
+        Ok(self.dg.decrypt_range(&envelope, offset, len).await?)
+    }
+
+    // Adds `data` to an append-mode envelope, creating it on first use. Only the new frames are
+    // written; the tail of an append that was interrupted by a crash is cut off first.
+    #[instrument(skip(self, data))]
+    pub async fn append(&self, path: &Path, data: &[u8]) -> Result<AppendReport> {
+        if data.is_empty() {
+            return Err(DGError::Config("nothing to append".into()).into());
+        }
+        let _serialized = self.appends.lock().await;
+        if !fs::try_exists(path)
+            .await
+            .map_err(|err| UserError::path_unavailable(path, err))?
+        {
+            self.guard_identity("encrypt", path.to_string_lossy().as_ref())
+                .await?;
+            let envelope = self
+                .dg
+                .encrypt(EncryptRequest {
+                    plaintext: data.to_vec(),
+                    journal: true,
+                    ..Default::default()
+                })
+                .await
+                .map_err(|err| anyhow::anyhow!("encryption failed: {err}"))?;
+            persist_envelope(path, &envelope, None).await?;
+            self.emit(ControllerEvent::Completed(format!(
+                "started append-mode envelope {}",
+                path.display()
+            )))
+            .await;
+            return Ok(AppendReport {
+                path: path.to_path_buf(),
+                created: true,
+                appended_bytes: data.len() as u64,
+                records: data.len().div_ceil(journal::RECORD_LIMIT) as u64,
+                plaintext_len: data.len() as u64,
+                discarded_bytes: 0,
+            });
+        }
+
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        self.guard_identity("encrypt", canonical.to_string_lossy().as_ref())
+            .await?;
+        let bytes = fs::read(&canonical)
+            .await
+            .with_context(|| format!("unable to read {}", canonical.display()))?;
+        if !Envelope::is_binary(&bytes) {
+            return Err(anyhow::anyhow!(
+                "{} is not a binary envelope and cannot be appended to",
+                canonical.display()
+            ));
+        }
+        let header = Envelope::parse_header(&bytes)
+            .map_err(|err| anyhow::anyhow!("not a Data Guardian envelope: {err}"))?;
+        let (envelope, _) = StoredEnvelope::parse(&bytes)?.into_envelope()?;
+        let appended = self
+            .dg
+            .append(&envelope, data.to_vec())
+            .await
+            .map_err(|err| anyhow::anyhow!("append failed: {err}"))?;
+
+        // The payload after the nonce-sized journal id is the tail of the file.
+        let keep = (bytes.len() - header.ciphertext_len - header.nonce.len()) as u64
+            + appended.committed_len;
+        stored::check_size(keep + appended.frames.len() as u64)?;
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(&canonical)
+            .await
+            .with_context(|| format!("unable to open {}", canonical.display()))?;
+        file.set_len(keep).await?;
+        file.write_all(&appended.frames).await?;
+        file.sync_all().await?;
+        if appended.discarded_bytes > 0 {
+            self.emit(ControllerEvent::Progress(format!(
+                "dropped {} bytes of an interrupted append to {}",
+                appended.discarded_bytes,
+                canonical.display()
+            )))
+            .await;
+        }
+        self.emit(ControllerEvent::Completed(format!(
+            "appended {} bytes to {}",
+            data.len(),
+            canonical.display()
+        )))
+        .await;
+        Ok(AppendReport {
+            path: canonical,
+            created: false,
+            appended_bytes: data.len() as u64,
+            records: appended.records,
+            plaintext_len: appended.plaintext_len,
+            discarded_bytes: appended.discarded_bytes,
+        })
+    }
+
+    pub async fn collect_share(
+        &self,
+        path: &Path,
+        out_dir: Option<PathBuf>,
+    ) -> Result<Vec<PathBuf>> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        self.guard_identity("collect_share", canonical.to_string_lossy().as_ref())
+            .await?;
+        if let Some(dir) = &out_dir {
+            ensure_directory(dir).await?;
+        }
+
+        let envelope = load_envelope(&canonical)
+            .await
+            .with_context(|| format!("unable to load {}", canonical.display()))?;
+        let shares = self
+            .dg
+            .collect_share(&envelope)
+            .await
+            .map_err(|err| anyhow::anyhow!("share collection failed: {err}"))?;
+
+        let mut written = Vec::with_capacity(shares.len());
+        for share in shares {
+            let target = keyed_target(
+                &canonical,
+                &share.key_id,
+                SHARE_EXTENSION,
+                out_dir.as_deref(),
+            )?;
+            fs::write(&target, serde_json::to_vec_pretty(&share)?)
+                .await
+                .with_context(|| format!("failed to write {}", target.display()))?;
+            self.emit(ControllerEvent::Completed(format!(
+                "wrote key share {}",
+                target.display()
+            )))
+            .await;
+            written.push(target);
+        }
+        Ok(written)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn decrypt_with_shares(
+        &self,
+        path: &Path,
+        share_files: Vec<PathBuf>,
+        out_dir: Option<PathBuf>,
+    ) -> Result<PathBuf> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        self.guard_identity("decrypt", canonical.to_string_lossy().as_ref())
+            .await?;
+        if let Some(dir) = &out_dir {
+            ensure_directory(dir).await?;
+        }
+
+        let mut shares = Vec::with_capacity(share_files.len());
+        for file in &share_files {
+            let data = fs::read(file)
+                .await
+                .with_context(|| format!("unable to read key share {}", file.display()))?;
+            let share: KeyShare = serde_json::from_slice(&data)
+                .with_context(|| format!("invalid key share {}", file.display()))?;
+            shares.push(share);
+        }
+        self.emit(ControllerEvent::Progress(format!(
+            "assembling {} key shares for {}",
+            shares.len(),
+            canonical.display()
+        )))
+        .await;
+
+        let envelope = load_envelope(&canonical)
+            .await
+            .with_context(|| format!("unable to load {}", canonical.display()))?;
+        let labels = envelope_labels(&envelope);
+        self.await_approval(&canonical, &envelope).await?;
+        let receipt = self.read_receipt(&canonical, &envelope);
+        let plaintext = self
+            .dg
+            .combine_shares(envelope, shares)
+            .await
+            .map_err(|err| anyhow::anyhow!("threshold decryption failed: {err}"))?;
+        self.send_read_receipt(receipt);
+        let target = decrypted_target(
+            &canonical,
+            out_dir.as_deref(),
+            &self.output_templates(),
+            &labels,
+        )?;
+        fs::write(&target, &plaintext)
+            .await
+            .with_context(|| format!("failed to write {}", target.display()))?;
+        self.emit(ControllerEvent::Completed(format!(
+            "wrote decrypted file {}",
+            target.display()
+        )))
+        .await;
+        Ok(target)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn issue_grant(
+        &self,
+        path: &Path,
+        request: GrantRequest,
+        out_dir: Option<PathBuf>,
+    ) -> Result<PathBuf> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        self.guard_identity("grant", canonical.to_string_lossy().as_ref())
+            .await?;
+        if let Some(dir) = &out_dir {
+            ensure_directory(dir).await?;
+        }
+
+        let envelope = load_envelope(&canonical)
+            .await
+            .with_context(|| format!("unable to load {}", canonical.display()))?;
+        let signed = self
+            .dg
+            .issue_grant(&envelope, request)
+            .await
+            .map_err(|err| anyhow::anyhow!("grant issuance failed: {err}"))?;
+        let grant = signed.grant()?;
+        let target = keyed_target(
+            &canonical,
+            &grant.recipient,
+            GRANT_EXTENSION,
+            out_dir.as_deref(),
+        )?;
+        fs::write(&target, serde_json::to_vec_pretty(&signed)?)
+            .await
+            .with_context(|| format!("failed to write {}", target.display()))?;
+        self.emit(ControllerEvent::Completed(format!(
+            "wrote access grant {} for {}",
+            target.display(),
+            grant.recipient
+        )))
+        .await;
+        Ok(target)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn decrypt_with_grant(
+        &self,
+        path: &Path,
+        grant_file: &Path,
+        out_dir: Option<PathBuf>,
+    ) -> Result<PathBuf> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        self.guard_identity("decrypt", canonical.to_string_lossy().as_ref())
+            .await?;
+        if let Some(dir) = &out_dir {
+            ensure_directory(dir).await?;
+        }
+
+        let data = fs::read(grant_file)
+            .await
+            .with_context(|| format!("unable to read access grant {}", grant_file.display()))?;
+        let grant: SignedGrant = serde_json::from_slice(&data)
+            .with_context(|| format!("invalid access grant {}", grant_file.display()))?;
+        let envelope = load_envelope(&canonical)
+            .await
+            .with_context(|| format!("unable to load {}", canonical.display()))?;
+        let labels = envelope_labels(&envelope);
+        self.await_approval(&canonical, &envelope).await?;
+        let receipt = self.read_receipt(&canonical, &envelope);
+        let plaintext = self
+            .dg
+            .decrypt_with_grant(envelope, grant, GrantAction::Decrypt)
+            .await
+            .map_err(|err| anyhow::anyhow!("grant decryption failed: {err}"))?;
+        self.send_read_receipt(receipt);
+        let target = decrypted_target(
+            &canonical,
+            out_dir.as_deref(),
+            &self.output_templates(),
+            &labels,
+        )?;
+        fs::write(&target, &plaintext)
+            .await
+            .with_context(|| format!("failed to write {}", target.display()))?;
+        self.emit(ControllerEvent::Completed(format!(
+            "wrote decrypted file {}",
+            target.display()
+        )))
+        .await;
+        Ok(target)
+    }
+
+    // Writes the request an air-gapped machine answers for an envelope this one cannot open.
+    #[instrument(skip(self))]
+    pub async fn export_decrypt_request(
+        &self,
+        path: &Path,
+        out_dir: Option<PathBuf>,
+    ) -> Result<PathBuf> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        self.guard_identity("offline_request", canonical.to_string_lossy().as_ref())
+            .await?;
+        if let Some(dir) = &out_dir {
+            ensure_directory(dir).await?;
+        }
+
+        let envelope = load_envelope(&canonical)
+            .await
+            .with_context(|| format!("unable to load {}", canonical.display()))?;
+        let mut request = self
+            .dg
+            .export_offline_request(&envelope)
+            .await
+            .map_err(|err| anyhow::anyhow!("offline request failed: {err}"))?;
+        request.file_name = canonical
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+        let target = suffixed_target(&canonical, REQUEST_EXTENSION, out_dir.as_deref())?;
+        fs::write(&target, serde_json::to_vec_pretty(&request)?)
+            .await
+            .with_context(|| format!("failed to write {}", target.display()))?;
+        self.emit(ControllerEvent::Completed(format!(
+            "wrote decrypt request {}",
+            target.display()
+        )))
+        .await;
+        Ok(target)
+    }
+
+    // Runs on the air-gapped machine. Only the request file crosses over, never the envelope.
+    #[instrument(skip(self))]
+    pub async fn answer_decrypt_request(
+        &self,
+        request_file: &Path,
+        out_dir: Option<PathBuf>,
+    ) -> Result<PathBuf> {
+        self.guard_identity("offline_answer", request_file.to_string_lossy().as_ref())
+            .await?;
+        if let Some(dir) = &out_dir {
+            ensure_directory(dir).await?;
+        }
+
+        let data = fs::read(request_file).await.with_context(|| {
+            format!("unable to read decrypt request {}", request_file.display())
+        })?;
+        let request: OfflineRequest = serde_json::from_slice(&data)
+            .with_context(|| format!("invalid decrypt request {}", request_file.display()))?;
+        let id = request.id.clone();
+        let response = self
+            .dg
+            .answer_offline_request(request)
+            .await
+            .map_err(|err| anyhow::anyhow!("answering decrypt request {id} failed: {err}"))?;
+        let target = suffixed_target(
+            &request_file.with_extension(""),
+            RESPONSE_EXTENSION,
+            out_dir.as_deref(),
+        )?;
+        fs::write(&target, serde_json::to_vec_pretty(&response)?)
+            .await
+            .with_context(|| format!("failed to write {}", target.display()))?;
+        self.emit(ControllerEvent::Completed(format!(
+            "wrote decrypt response {} with key {}",
+            target.display(),
+            response.key_id
+        )))
+        .await;
+        Ok(target)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn complete_decrypt_request(
+        &self,
+        path: &Path,
+        response_file: &Path,
+        out_dir: Option<PathBuf>,
+    ) -> Result<PathBuf> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        self.guard_identity("decrypt", canonical.to_string_lossy().as_ref())
+            .await?;
+        if let Some(dir) = &out_dir {
+            ensure_directory(dir).await?;
+        }
+
+        let data = fs::read(response_file).await.with_context(|| {
+            format!(
+                "unable to read decrypt response {}",
+                response_file.display()
+            )
+        })?;
+        let response: OfflineResponse = serde_json::from_slice(&data)
+            .with_context(|| format!("invalid decrypt response {}", response_file.display()))?;
+        let envelope = load_envelope(&canonical)
+            .await
+            .with_context(|| format!("unable to load {}", canonical.display()))?;
+        let labels = envelope_labels(&envelope);
+        self.await_approval(&canonical, &envelope).await?;
+        let receipt = self.read_receipt(&canonical, &envelope);
+        let plaintext = self
+            .dg
+            .complete_offline_request(envelope, response)
+            .await
+            .map_err(|err| anyhow::anyhow!("offline decryption failed: {err}"))?;
+        self.send_read_receipt(receipt);
+        let target = decrypted_target(
+            &canonical,
+            out_dir.as_deref(),
+            &self.output_templates(),
+            &labels,
+        )?;
+        fs::write(&target, &plaintext)
+            .await
+            .with_context(|| format!("failed to write {}", target.display()))?;
+        self.emit(ControllerEvent::Completed(format!(
+            "wrote decrypted file {}",
+            target.display()
+        )))
+        .await;
+        Ok(target)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn revoke_recipient(&self, key_id: &str) -> Result<RevocationJob> {
+        let _operation = self.operations.begin("revoke", key_id)?;
+        self.guard_identity("revoke", key_id).await?;
+        let key = self
+            .dg
+            .revoke_key(key_id)
+            .await
+            .map_err(|err| anyhow::anyhow!("revocation failed: {err}"))?;
+        self.reencrypt_for(key, "revoked key").await
+    }
+
+    // Swaps an identity for a fresh key under the same label and re-encrypts its envelopes, which
+    // moves them over to the replacement.
+    #[instrument(skip(self))]
+    pub async fn rotate_identity(&self, key_id: &str) -> Result<RevocationJob> {
+        let _operation = self.operations.begin("rotate", key_id)?;
+        self.guard_identity("rotate", key_id).await?;
+        let rotation = self
+            .dg
+            .rotate_key(key_id)
+            .await
+            .map_err(|err| anyhow::anyhow!("rotation failed: {err}"))?;
+        let summary = format!("rotated key to {}:", rotation.replacement.id);
+        self.reencrypt_for(rotation.retired, &summary).await
+    }
+
+    pub async fn key_expiry(&self) -> Result<KeyExpiryReport> {
+        Ok(self.dg.key_expiry().await?)
+    }
+
+    // Warns about keys close to their maximum age and, when the policy asks for it, rotates
+    // expired identities. Returns the re-encryption jobs it started.
+    pub async fn enforce_key_expiry(&self) -> Result<Vec<RevocationJob>> {
+        let report = self.key_expiry().await?;
+        for key in report.in_state(KeyAgeState::Expiring) {
+            self.emit(ControllerEvent::Progress(format!(
+                "key {} ({}) reaches its maximum age in {} days; rotate it soon",
+                key.label,
+                key.key_id,
+                key.expires_at.saturating_sub(unix_seconds()).div_ceil(DAY)
+            )))
+            .await;
+        }
+        let mut jobs = Vec::new();
+        for key in report.in_state(KeyAgeState::Expired) {
+            if report.auto_rotate() && key.rotatable && !self.read_only() {
+                jobs.push(self.rotate_identity(&key.key_id).await?);
+                continue;
+            }
+            self.emit(ControllerEvent::Error(format!(
+                "key {} ({}) passed its maximum age; new envelopes cannot be encrypted to it",
+                key.label, key.key_id
+            )))
+            .await;
+        }
+        Ok(jobs)
+    }
+
+    async fn reencrypt_for(&self, key: KeyMetadata, summary: &str) -> Result<RevocationJob> {
+        let affected: Vec<PathBuf> = self
+            .index_entries()
+            .await?
+            .into_iter()
+            .filter(|entry| entry.key_ids.contains(&key.id))
+            .map(|entry| entry.path)
+            .collect();
+        self.emit(ControllerEvent::Progress(format!(
+            "{summary} {}; re-encrypting {} envelopes",
+            key.id,
+            affected.len()
+        )))
+        .await;
+
+        let job = self
+            .enqueue_job(JobSpec::Reencrypt {
+                key_id: key.id.clone(),
+                paths: affected.clone(),
+            })
+            .await?;
+        let controller = self.clone();
+        let handle = task::spawn(async move {
+            let job = controller.run_job(job.id).await?;
+            Ok::<_, anyhow::Error>(job.succeeded)
+        });
+
+        Ok(RevocationJob {
+            key,
+            affected,
+            job: job.id,
+            handle,
+        })
+    }
+
+    async fn rekey_file(&self, path: &Path) -> Result<()> {
+        if fs::metadata(path).await.is_err() {
+            self.unindex(path).await?;
+            return Err(anyhow::anyhow!("envelope no longer exists"));
+        }
+        let envelope = load_envelope(path)
+            .await
+            .with_context(|| format!("unable to load {}", path.display()))?;
+        let envelope = self
+            .dg
+            .rekey(envelope, RekeyContext::rekey(self.identity().subject()))
+            .await
+            .map_err(|err| anyhow::anyhow!("rekey failed: {err}"))?;
+        replace_envelope(path, &envelope)
+            .await
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        self.index_envelope(path, &envelope).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn submit_job(&self, spec: JobSpec) -> Result<Job> {
+        let job = self.enqueue_job(spec).await?;
+        self.spawn_job(job.id);
+        Ok(job)
+    }
+
+    pub async fn list_jobs(&self) -> Result<Vec<Job>> {
+        match self.jobs.lock().await.as_ref() {
+            Some(queue) => Ok(queue.jobs().to_vec()),
+            None => Err(UserError::not_booted()),
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn pause_job(&self, id: u64) -> Result<Job> {
+        let (job, _) = self
+            .transition_job(id, JobStatus::Running, JobStatus::Paused)
+            .await?;
+        Ok(job)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn resume_job(&self, id: u64) -> Result<Job> {
+        let (job, start) = self
+            .transition_job(id, JobStatus::Paused, JobStatus::Running)
+            .await?;
+        if start {
+            self.spawn_job(id);
+        }
+        Ok(job)
+    }
+
+    async fn enqueue_job(&self, spec: JobSpec) -> Result<Job> {
+        self.ensure_writable("background jobs")?;
+        let mut jobs = self.jobs.lock().await;
+        let queue = jobs.as_mut().ok_or_else(UserError::not_booted)?;
+        let job = queue.submit(spec);
+        queue.get_mut(job.id)?.active = true;
+        queue.persist().await?;
+        self.emit(ControllerEvent::Progress(format!(
+            "queued job {} ({} items)",
+            job.id, job.total
+        )))
+        .await;
+        Ok(job)
+    }
+
+    async fn transition_job(&self, id: u64, from: JobStatus, to: JobStatus) -> Result<(Job, bool)> {
+        let mut jobs = self.jobs.lock().await;
+        let queue = jobs.as_mut().ok_or_else(UserError::not_booted)?;
+        let job = queue.get_mut(id)?;
+        if job.status == from {
+            job.status = to;
+            job.updated_at = jobs::unix_now();
+        }
+        let start = job.status == JobStatus::Running && !job.active;
+        job.active |= start;
+        let job = job.clone();
+        queue.persist().await?;
+        Ok((job, start))
+    }
+
+    async fn resume_pending_jobs(&self) {
+        let pending = match self.jobs.lock().await.as_mut() {
+            Some(queue) => {
+                let pending = queue.pending();
+                for id in &pending {
+                    if let Ok(job) = queue.get_mut(*id) {
+                        job.active = true;
+                    }
+                }
+                pending
+            }
+            None => return,
+        };
+        for id in pending {
+            info!(job = id, "resuming background job");
+            self.spawn_job(id);
+        }
+    }
+
+    fn spawn_job(&self, id: u64) {
+        let controller = self.clone();
+        task::spawn(async move {
+            if let Err(err) = controller.run_job(id).await {
+                warn!(job = id, error = %err, "background job stopped");
+            }
+        });
+    }
+
+    async fn run_job(&self, id: u64) -> Result<Job> {
+        let result = self.drive_job(id).await;
+        if result.is_err() {
+            if let Some(queue) = self.jobs.lock().await.as_mut() {
+                if let Ok(job) = queue.get_mut(id) {
+                    job.active = false;
+                }
+            }
+        }
+        result
+    }
+
+    async fn drive_job(&self, id: u64) -> Result<Job> {
+        let operation = self.operations.begin("job", &id.to_string())?;
+        let spec = {
+            let mut jobs = self.jobs.lock().await;
+            let queue = jobs.as_mut().ok_or_else(UserError::not_booted)?;
+            queue.get_mut(id)?.spec.clone()
+        };
+        let (verb, action) = spec.verbs();
+        loop {
+            let position = {
+                let mut jobs = self.jobs.lock().await;
+                let queue = jobs.as_mut().ok_or_else(UserError::not_booted)?;
+                let job = queue.get_mut(id)?;
+                let completed = job.status == JobStatus::Running && job.position >= job.total;
+                if completed {
+                    job.status = JobStatus::Completed;
+                    job.updated_at = jobs::unix_now();
+                }
+                if job.status != JobStatus::Running || operation.draining() {
+                    job.active = false;
+                    let job = job.clone();
+                    queue.persist().await?;
+                    drop(jobs);
+                    if completed {
+                        self.emit(ControllerEvent::Completed(spec.completion(job.succeeded)))
+                            .await;
+                    }
+                    return Ok(job);
+                }
+                job.position
+            };
+            let Some(item) = spec.item(position) else {
+                return Err(anyhow::anyhow!("job {id} has no item {position}"));
+            };
+            self.emit(ControllerEvent::Step(format!(
+                "{verb} {}/{} {}",
+                position + 1,
+                spec.len(),
+                item.display()
+            )))
+            .await;
+            self.wait_for_mains_power(&operation).await;
+            if operation.draining() {
+                continue;
+            }
+            let permit = self.throttle.acquire().await;
+            let size = fs::metadata(item)
+                .await
+                .map(|metadata| {
+                    if metadata.is_file() {
+                        metadata.len()
+                    } else {
+                        0
+                    }
+                })
+                .unwrap_or_default();
+            self.throttle.pace(size).await;
+            let outcome = self.run_job_step(id, &spec, item).await;
+            drop(permit);
+            if outcome.is_err() && operation.draining() {
+                continue;
+            }
+            if let Err(err) = &outcome {
+                warn!(job = id, path = %item.display(), error = %err, "job step failed");
+                self.emit(ControllerEvent::Error(format!(
+                    "failed to {action} {}: {err:#}",
+                    item.display()
+                )))
+                .await;
+            }
+            let mut jobs = self.jobs.lock().await;
+            let queue = jobs.as_mut().ok_or_else(UserError::not_booted)?;
+            let job = queue.get_mut(id)?;
+            job.position = position + 1;
+            job.updated_at = jobs::unix_now();
+            match outcome {
+                Ok(()) => job.succeeded += 1,
+                Err(err) => job.failures.push(JobFailure {
+                    path: item.to_path_buf(),
+                    error: format!("{err:#}"),
+                }),
+            }
+            queue.persist().await?;
+        }
+    }
+
+    async fn wait_for_mains_power(&self, operation: &OperationGuard) {
+        let mut announced = false;
+        while !operation.draining() && self.throttle.paused_for_battery().await {
+            if !announced {
+                self.emit(ControllerEvent::Progress(
+                    "background jobs paused while on battery power".into(),
+                ))
+                .await;
+                announced = true;
+            }
+            let _ = tokio::time::timeout(BATTERY_POLL, self.throttle.changed()).await;
+        }
+    }
+
+    async fn run_job_step(&self, id: u64, spec: &JobSpec, item: &Path) -> Result<()> {
+        match spec {
+            JobSpec::Encrypt {
+                recipients,
+                labels,
+                out_dir,
+                ..
+            } => self
+                .encrypt_file_with(
+                    item,
+                    EncryptOptions {
+                        recipients: recipients.clone(),
+                        labels: labels.clone(),
+                        out_dir: out_dir.clone(),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map(|_| ()),
+            JobSpec::Reencrypt { .. } => self.rekey_file(item).await,
+            JobSpec::Dispose { .. } => self.dispose_envelope(item).await,
+            JobSpec::Sync { target, conflict } => {
+                let transfer = Transfer {
+                    settings: self.transfer_settings(),
+                    checkpoints: Some(JobCheckpoints::new(self.jobs.clone(), id)),
+                };
+                let report = self
+                    .backup_envelopes_with(
+                        BackupOptions {
+                            target: target.clone(),
+                            conflict: *conflict,
+                            dry_run: false,
+                        },
+                        transfer,
+                    )
+                    .await?;
+                if report.succeeded() {
+                    Ok(())
+                } else {
+                    Err(anyhow::anyhow!(
+                        "{} envelopes failed to sync",
+                        report.failures.len()
+                    ))
+                }
+            }
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn migrate_envelopes(
+        &self,
+        dir: &Path,
+        options: MigrationOptions,
+    ) -> Result<MigrationReport> {
+        self.ensure_writable("migration")?;
+        let canonical = dir
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(dir, err))?;
+        let operation = self
+            .operations
+            .begin("migrate", canonical.to_string_lossy().as_ref())?;
+        self.guard_identity("migrate", canonical.to_string_lossy().as_ref())
+            .await?;
+        let candidates = find_envelopes(&canonical, ENCRYPTED_EXTENSION, options.recursive).await?;
+        let mut report = MigrationReport {
+            scanned: candidates.len(),
+            dry_run: options.dry_run,
+            ..Default::default()
+        };
+        for (position, path) in candidates.into_iter().enumerate() {
+            operation.check()?;
+            self.emit(ControllerEvent::Step(format!(
+                "migrating {}/{} {}",
+                position + 1,
+                report.scanned,
+                path.display()
+            )))
+            .await;
+            match self.migrate_file(&path, options.dry_run).await {
+                Ok(EnvelopeFormat::Current) => report.current += 1,
+                Ok(from) => report.migrated.push(MigratedEnvelope { path, from }),
+                Err(err) => {
+                    warn!(path = %path.display(), error = %err, "envelope migration failed");
+                    report.failures.push(MigrationFailure {
+                        path,
+                        error: format!("{err:#}"),
+                    });
+                }
+            }
+        }
+        self.emit(ControllerEvent::Completed(format!(
+            "migrated {} of {} envelopes ({} already current, {} failed)",
+            report.migrated.len(),
+            report.scanned,
+            report.current,
+            report.failures.len()
+        )))
+        .await;
+        Ok(report)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn backup_envelopes(&self, options: BackupOptions) -> Result<BackupReport> {
+        let transfer = Transfer {
+            settings: self.transfer_settings(),
+            checkpoints: None,
+        };
+        self.backup_envelopes_with(options, transfer).await
+    }
+
+    async fn backup_envelopes_with(
+        &self,
+        options: BackupOptions,
+        transfer: Transfer,
+    ) -> Result<BackupReport> {
+        self.ensure_writable("backup")?;
+        ensure_directory(&options.target).await?;
+        let target = options
+            .target
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(&options.target, err))?;
+        let operation = self
+            .operations
+            .begin("backup", target.to_string_lossy().as_ref())?;
+        self.guard_identity("backup", target.to_string_lossy().as_ref())
+            .await?;
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+        let entries = self.index_entries().await?;
+
+        let mut state = BackupState::load(&data_dir).await?;
+        let mut report = BackupReport {
+            target: target.clone(),
+            dry_run: options.dry_run,
+            ..Default::default()
+        };
+        for entry in &entries {
+            operation.check()?;
+            if entry.path.starts_with(&target) {
+                continue;
+            }
+            let path = entry.path.clone();
+            match sync_entry(&mut state, &target, entry, &options, &transfer).await {
+                Ok(SyncOutcome::Unchanged) => report.unchanged += 1,
+                Ok(SyncOutcome::Missing) => report.missing.push(path),
+                Ok(SyncOutcome::Copied) => report.copied.push(path),
+                Ok(SyncOutcome::Conflict { target, copied }) => {
+                    if copied {
+                        report.copied.push(path.clone());
+                    }
+                    self.emit(ControllerEvent::Error(format!(
+                        "backup conflict for {} ({})",
+                        target.display(),
+                        options.conflict.as_str()
+                    )))
+                    .await;
+                    report.conflicts.push(BackupConflict {
+                        path,
+                        target,
+                        resolution: options.conflict,
+                    });
+                }
+                Err(err) => {
+                    warn!(path = %path.display(), error = %err, "envelope backup failed");
+                    report.failures.push(BackupFailure {
+                        path,
+                        error: format!("{err:#}"),
+                    });
+                }
+            }
+        }
+        if !options.dry_run {
+            state.persist(&data_dir).await?;
+        }
+        self.emit(ControllerEvent::Completed(format!(
+            "backed up {} envelopes to {} ({} unchanged, {} conflicts, {} failed)",
+            report.copied.len(),
+            target.display(),
+            report.unchanged,
+            report.conflicts.len(),
+            report.failures.len()
+        )))
+        .await;
+        Ok(report)
+    }
+
+    async fn migrate_file(&self, path: &Path, dry_run: bool) -> Result<EnvelopeFormat> {
+        let mut stored = StoredEnvelope::read(path).await?;
+        let format = stored.detect_format()?;
+        if dry_run || format == EnvelopeFormat::Current {
+            return Ok(format);
+        }
+        if format == EnvelopeFormat::MasterKey {
+            let bytes = stored.payload_bytes()?;
+            let envelope = self
+                .dg
+                .rekey(
+                    Envelope {
+                        bytes,
+                        meta: stored.meta,
+                    },
+                    RekeyContext::migrate(
+                        self.identity().subject(),
+                        stored.format,
+                        ENVELOPE_FORMAT,
+                    ),
+                )
+                .await
+                .map_err(|err| anyhow::anyhow!("rekey failed: {err}"))?;
+            stored.payload = general_purpose::STANDARD.encode(&envelope.bytes);
+            stored.meta = envelope.meta;
+        }
+        stored.format = ENVELOPE_FORMAT;
+        write_stored(path, &stored).await?;
+        let envelope = load_envelope(path).await?;
+        self.index_envelope(path, &envelope).await?;
+        Ok(format)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn shred_envelope(&self, path: &Path) -> Result<()> {
+        self.ensure_writable("shredding")?;
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        self.guard_identity("shred", canonical.to_string_lossy().as_ref())
+            .await?;
+        self.remove_envelope(&canonical, "shred", false).await
+    }
+
+    async fn dispose_envelope(&self, path: &Path) -> Result<()> {
+        self.ensure_writable("disposition")?;
+        self.guard_identity("dispose", path.to_string_lossy().as_ref())
+            .await?;
+        self.remove_envelope(path, "dispose", true).await
+    }
+
+    // Held envelopes and envelopes inside their retention period are refused, and every attempt
+    // lands in the audit log. Disposition additionally requires the envelope to be overdue.
+    async fn remove_envelope(&self, path: &Path, action: &str, overdue_only: bool) -> Result<()> {
+        let stored = StoredEnvelope::read(path)
+            .await
+            .with_context(|| format!("unable to load {}", path.display()))?;
+        let retention = retention::read(&stored.meta)?;
+        let now = unix_seconds();
+        let blocked = retention.deletion_blocked(now).or_else(|| {
+            (overdue_only && retention.state(now) != RetentionState::Overdue)
+                .then(|| "it is not due for disposition".to_owned())
+        });
+        if let Some(reason) = blocked {
+            self.audit_retention(
+                action,
+                path,
+                "deny",
+                serde_json::json!({ "reason": reason }),
+            )
+            .await;
+            return Err(DGError::PolicyDenied(format!(
+                "{} cannot be removed: {reason}",
+                path.display()
+            ))
+            .into());
+        }
+        scrub(path).await?;
+        scrub(&signature_path(path)).await?;
+        self.unindex(path).await?;
+        self.audit_retention(
+            action,
+            path,
+            "allow",
+            serde_json::json!({ "retention": retention }),
+        )
+        .await;
+        self.emit(ControllerEvent::Completed(format!(
+            "shredded envelope {}",
+            path.display()
+        )))
+        .await;
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn place_legal_hold(&self, path: &Path, reason: &str) -> Result<Retention> {
+        let hold = LegalHold {
+            reason: reason.trim().to_owned(),
+            placed_by: self.identity().subject(),
+            placed_at: unix_seconds(),
+        };
+        self.update_retention(path, "legal_hold", |retention| {
+            retention.hold = Some(hold);
+            Ok(())
+        })
+        .await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn release_legal_hold(&self, path: &Path) -> Result<Retention> {
+        self.update_retention(path, "release_hold", |retention| {
+            retention
+                .hold
+                .take()
+                .map(|_| ())
+                .ok_or_else(|| anyhow::anyhow!("{} is not under legal hold", path.display()))
+        })
+        .await
+    }
+
+    async fn update_retention(
+        &self,
+        path: &Path,
+        action: &str,
+        change: impl FnOnce(&mut Retention) -> Result<()>,
+    ) -> Result<Retention> {
+        self.ensure_writable("retention changes")?;
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| UserError::path_unavailable(path, err))?;
+        self.guard_identity(action, canonical.to_string_lossy().as_ref())
+            .await?;
+        let mut stored = StoredEnvelope::read(&canonical)
+            .await
+            .with_context(|| format!("unable to load {}", canonical.display()))?;
+        let mut retention = retention::read(&stored.meta)?;
+        change(&mut retention)?;
+        retention.validate()?;
+        retention::write(&mut stored.meta, &retention)?;
+        write_stored(&canonical, &stored)
+            .await
+            .with_context(|| format!("failed to write {}", canonical.display()))?;
+        self.audit_retention(
+            action,
+            &canonical,
+            "allow",
+            serde_json::json!({ "retention": retention }),
+        )
+        .await;
+        Ok(retention)
+    }
+
+    // Lists the indexed envelopes with who encrypted each one and when, joined from the audit log.
+    pub async fn export_report(
+        &self,
+        filter: ReportFilter,
+        format: ReportFormat,
+        path: &Path,
+    ) -> Result<ExportedReport> {
+        self.guard_identity("export_report", "audit").await?;
+        let entries = self.index_entries().await?;
+        let encryptions = self
+            .dg
+            .query_audit(AuditFilter {
+                action: Some(report::ENCRYPT_ACTION.into()),
+                effect: Some("allow".into()),
+                ..Default::default()
+            })
+            .await?;
+        let report = EnvelopeReport::build(entries, &encryptions, filter, unix_seconds()).await?;
+        let staging = enriched_extension(path, "tmp");
+        fs::write(&staging, report.render(format)?)
+            .await
+            .with_context(|| format!("unable to write report to {}", path.display()))?;
+        fs::rename(&staging, path).await?;
+        Ok(ExportedReport {
+            path: path.to_path_buf(),
+            format,
+            envelopes: report.envelopes.len(),
+        })
+    }
+
+    pub async fn retention_report(&self) -> Result<RetentionReport> {
+        let entries = self.index_entries().await?;
+        let now = unix_seconds();
+        let mut report = RetentionReport::default();
+        for entry in entries {
+            let retention = match StoredEnvelope::read(&entry.path).await {
+                Ok(stored) => retention::read(&stored.meta)?,
+                Err(_) => continue,
+            };
+            report.checked += 1;
+            let state = retention.state(now);
+            let status = RetentionStatus {
+                path: entry.path,
+                state,
+                retention,
+            };
+            match state {
+                RetentionState::Held => report.held.push(status),
+                RetentionState::Retained => report.retained.push(status),
+                RetentionState::Overdue => report.overdue.push(status),
+                RetentionState::Active => {}
+            }
+        }
+        Ok(report)
+    }
+
+    // Queues a disposition job for overdue envelopes unless one is still running.
+    #[instrument(skip(self))]
+    pub async fn schedule_disposition(&self) -> Result<Option<Job>> {
+        let running = self.list_jobs().await?.iter().any(|job| {
+            matches!(job.spec, JobSpec::Dispose { .. }) && job.status != JobStatus::Completed
+        });
+        if running {
+            return Ok(None);
+        }
+        let report = self.retention_report().await?;
+        if report.overdue.is_empty() {
+            return Ok(None);
+        }
+        let paths = report
+            .overdue
+            .into_iter()
+            .map(|status| status.path)
+            .collect();
+        self.submit_job(JobSpec::Dispose { paths }).await.map(Some)
+    }
+
+    async fn audit_retention(
+        &self,
+        action: &str,
+        path: &Path,
+        effect: &str,
+        details: serde_json::Value,
+    ) {
+        let event = AuditEvent::new(
+            &self.identity().subject(),
+            action,
+            path.to_string_lossy().as_ref(),
+            effect,
+        )
+        .with_details(details);
+        if let Some(audit) = self.audit.lock().await.as_ref() {
+            if let Err(err) = audit.record(event).await {
+                warn!(error = %err, "failed to record audit event");
+            }
+        }
+    }
+
+    // Brings the index in line with the envelopes under the configured locations, so files moved,
+    // copied or deleted outside the app are picked up.
+    pub async fn reconcile_index(&self) -> Result<Reconciliation> {
+        self.ensure_writable("index reconciliation")?;
+        self.guard_identity("reconcile_index", "index").await?;
+        let mut roots = Vec::new();
+        for location in self.index_locations() {
+            match location.canonicalize() {
+                Ok(root) => roots.push(root),
+                Err(err) => {
+                    warn!(location = %location.display(), error = %err, "index location unavailable")
+                }
+            }
+        }
+        let mut observed = Vec::new();
+        for root in &roots {
+            for path in find_envelopes(root, ENCRYPTED_EXTENSION, true).await? {
+                match load_envelope(&path).await {
+                    Ok(envelope) => observed.push(IndexEntry::observe(&path, &envelope).await),
+                    Err(err) => {
+                        warn!(path = %path.display(), error = %err, "skipping unreadable envelope")
+                    }
+                }
+            }
+        }
+        let report = self
+            .dg
+            .index_reconcile(roots, observed)
+            .await
+            .map_err(|err| anyhow::anyhow!("index update failed: {err}"))?;
+        self.emit(ControllerEvent::Completed(report.summary()))
+            .await;
+        Ok(report)
+    }
+
+    // Filters are `field=value` or a bare `field`; every filter has to match.
+    pub async fn search_index(&self, filters: &[String]) -> Result<Vec<IndexEntry>> {
+        let filters = filters
+            .iter()
+            .map(|filter| MetadataFilter::parse(filter))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| anyhow::anyhow!("{err}"))?;
+        Ok(self
+            .index_entries()
+            .await?
+            .into_iter()
+            .filter(|entry| filters.iter().all(|filter| filter.matches(&entry.metadata)))
+            .collect())
+    }
+
+    async fn index_entries(&self) -> Result<Vec<IndexEntry>> {
+        if self.data_dir.lock().await.is_none() {
+            return Err(UserError::not_booted());
+        }
+        self.dg
+            .index_entries()
+            .await
+            .map_err(|err| anyhow::anyhow!("envelope index unavailable: {err}"))
+    }
+
+    async fn index_envelope(&self, path: &Path, envelope: &Envelope) -> Result<()> {
+        if self.data_dir.lock().await.is_none() {
+            return Ok(());
+        }
+        self.dg
+            .index_record(IndexEntry::observe(path, envelope).await)
+            .await
+            .map_err(|err| anyhow::anyhow!("index update failed: {err}"))
+    }
+
+    async fn unindex(&self, path: &Path) -> Result<()> {
+        if self.data_dir.lock().await.is_none() {
+            return Ok(());
+        }
+        self.dg
+            .index_remove(path)
+            .await
+            .map_err(|err| anyhow::anyhow!("index update failed: {err}"))
+    }
+
+    pub async fn list_contacts(&self) -> Result<Vec<KeyMetadata>> {
+        Ok(self.keyring().await?.contacts().to_vec())
+    }
+
+    // Generates an X25519 key pair that envelopes can name as a recipient.
+    #[instrument(skip(self))]
+    pub async fn generate_identity(&self, label: &str) -> Result<KeyMetadata> {
+        self.ensure_writable("generating keys")?;
+        self.guard_identity("generate_key", label).await?;
+        let meta = self
+            .dg
+            .generate_identity(label)
+            .await
+            .map_err(|err| anyhow::anyhow!("unable to generate identity: {err}"))?;
+        self.emit(ControllerEvent::Completed(format!(
+            "generated identity {} ({})",
+            meta.label, meta.id
+        )))
+        .await;
+        Ok(meta)
+    }
+
+    // Generates a root identity with certified encryption, signing and escrow subkeys; its label
+    // works wherever a recipient or signer is named.
+    #[instrument(skip(self))]
+    pub async fn generate_key_hierarchy(&self, label: &str) -> Result<KeyHierarchy> {
+        self.ensure_writable("generating keys")?;
+        self.guard_identity("generate_key", label).await?;
+        let hierarchy = self
+            .dg
+            .generate_key_hierarchy(label)
+            .await
+            .map_err(|err| anyhow::anyhow!("unable to generate identity: {err}"))?;
+        self.emit(ControllerEvent::Completed(format!(
+            "generated root identity {} ({}) with {} subkeys",
+            hierarchy.root.label,
+            hierarchy.root.id,
+            hierarchy.subkeys.len()
+        )))
+        .await;
+        Ok(hierarchy)
+    }
+
+    pub async fn list_identities(&self) -> Result<Vec<KeyMetadata>> {
+        Ok(self.dg.list_identities().await?)
+    }
+
+    #[instrument(skip(self, presented))]
+    pub async fn verify_fingerprint(&self, key: &str, presented: &str) -> Result<FingerprintCheck> {
+        let keyring = self.keyring().await?;
+        let meta = keyring
+            .lookup(key)
+            .ok_or_else(|| UserError::key_not_found(key))?;
+        let fingerprint = meta
+            .compute_fingerprint()
+            .map_err(|err| anyhow::anyhow!("fingerprint unavailable: {err}"))?;
+        let check = FingerprintCheck {
+            key_id: meta.id.clone(),
+            label: meta.label.clone(),
+            matches: fingerprint.matches(presented),
+            fingerprint,
+        };
+        let identity = self.identity();
+        self.audit_decision(
+            &identity,
+            "verify_fingerprint",
+            &check.key_id,
+            check.matches,
+        )
+        .await;
+        if !check.matches {
+            self.emit(ControllerEvent::Error(format!(
+                "fingerprint mismatch for key {} ({})",
+                check.label, check.key_id
+            )))
+            .await;
+        }
+        Ok(check)
+    }
+
+    async fn keyring(&self) -> Result<Keyring> {
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+        Keyring::load(&data_dir)
+            .await
+            .map_err(|err| anyhow::anyhow!("keyring unavailable: {err}"))
+    }
+
+    #[instrument(skip(self))]
+    pub async fn check_access(&self, subject: &str, action: &str, resource: &str) -> Result<bool> {
+        self.dg
+            .check_policy(subject, action, resource)
+            .await
+            .map_err(|err| anyhow::anyhow!("policy check failed: {err}"))
+    }
+
+    #[instrument(skip(self, authority))]
+    pub async fn mint_session_token(
+        &self,
+        authority: &SessionAuthority,
+        client: &str,
+        scopes: &[Capability],
+        ttl: Duration,
+    ) -> Result<String> {
+        self.authorize_session(client, scopes).await?;
+        authority.mint(client, scopes, ttl)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn authorize_session(&self, client: &str, scopes: &[Capability]) -> Result<()> {
+        let subject = session_subject(client);
+        for scope in scopes {
+            self.guard_policy(&subject, scope.as_str(), SESSION_RESOURCE)
+                .await?;
+        }
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn repair_data_dir(&self) -> Result<IntegrityReport> {
+        let data_dir = self
+            .data_dir
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(UserError::not_booted)?;
+        self.guard_identity("repair", data_dir.to_string_lossy().as_ref())
+            .await?;
+        let report = self
+            .dg
+            .integrity(true)
+            .await
+            .map_err(|err| anyhow::anyhow!("data directory repair failed: {err}"))?;
+        for issue in report.outstanding() {
+            self.emit(ControllerEvent::Error(format!(
+                "integrity: {}",
+                issue.problem
+            )))
+            .await;
+        }
+        let repaired = report.issues.iter().filter(|issue| issue.repaired).count();
+        self.emit(ControllerEvent::Completed(format!(
+            "data directory repair complete ({repaired} repaired)"
+        )))
+        .await;
+        Ok(report)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn warm_up(&self) -> Result<WarmupReport> {
+        let started = Instant::now();
+        let indexed = self.index_entries().await?.len();
+        let mut report = match self.dg.warm_up().await {
+            Ok(report) => report,
+            Err(err) => {
+                warn!("engine warm-up skipped: {err}");
+                WarmupReport::default()
+            }
+        };
+        report.push("index", started, Some(format!("{indexed} envelopes")));
+        self.ready.store(true, Ordering::SeqCst);
+        self.emit(ControllerEvent::Ready(format!(
+            "engine ready after {} ms warm-up",
+            report.elapsed_us() / 1000
+        )))
+        .await;
+        Ok(report)
+    }
+
+    pub async fn self_test(&self) -> Result<SelfTestReport> {
+        let report = self
+            .dg
+            .self_test()
+            .await
+            .map_err(|err| anyhow::anyhow!("self-test failed to run: {err}"))?;
+        for check in report.failures() {
+            self.emit(ControllerEvent::Error(format!(
+                "self-test check {} failed",
+                check.name
+            )))
+            .await;
+        }
+        Ok(report)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn shutdown(&self) -> Result<ShutdownReport> {
+        self.ready.store(false, Ordering::SeqCst);
+        let report = self.operations.drain(self.drain_timeout).await;
+        if report.aborted > 0 {
+            warn!(
+                drained = report.drained,
+                aborted = report.aborted,
+                "shutdown aborted in-flight operations"
+            );
+        } else {
+            info!(drained = report.drained, "in-flight operations drained");
+        }
+        for (_, path) in self.viewers.lock().await.drain() {
+            if let Err(err) = shred(&path).await {
+                warn!(path = %path.display(), error = %err, "failed to remove viewer file");
+            }
+        }
+        *self.audit.lock().await = None;
+        *self.jobs.lock().await = None;
+        *self.data_dir.lock().await = None;
+        self.journal.lock().await.detach();
+        self.dg
+            .shutdown()
+            .await
+            .map_err(|err| anyhow::anyhow!("shutdown failed: {err}"))?;
+        Ok(report)
+    }
+
+    // Re-reads the policy file and drops every decision cached under the old one.
+    pub async fn reload_policy(&self) -> Result<PolicyCacheStats> {
+        self.guard_identity("reload_policy", "policy").await?;
+        let stats = self
+            .dg
+            .reload_policy()
+            .await
+            .map_err(|err| anyhow::anyhow!("unable to reload the policy: {err}"))?;
+        self.emit(ControllerEvent::Completed("policy reloaded".into()))
+            .await;
+        Ok(stats)
+    }
+
+    pub async fn policy_cache_stats(&self) -> Result<PolicyCacheStats> {
+        Ok(self.dg.policy_cache_stats().await?)
+    }
+
+    // Audited decisions and operations, newest first, for the security history.
+    pub async fn query_audit(&self, filter: AuditFilter) -> Result<Vec<AuditEvent>> {
+        self.guard_identity("query_audit", "audit").await?;
+        Ok(self.dg.query_audit(filter).await?)
+    }
+
+    fn ensure_writable(&self, operation: &str) -> Result<()> {
+        if self.read_only() {
+            return Err(
+                DGError::ReadOnly(format!("{operation} is not allowed in read-only mode")).into(),
+            );
+        }
+        Ok(())
+    }
+
+    pub async fn explain_decision(
+        &self,
+        action: &str,
+        resource: &str,
+    ) -> Result<DecisionExplanation> {
+        Ok(self
+            .dg
+            .explain_decision(&self.identity(), action, resource)
+            .await?)
+    }
+
+    async fn guard_identity(&self, action: &str, resource: &str) -> Result<()> {
+        if self.locked() {
+            return Err(DGError::PolicyDenied(
+                "this device was locked; apply a signed unlock command to reopen it".into(),
+            )
+            .into());
+        }
+        let identity = self.identity();
+        let allowed = self
+            .dg
+            .check_identity(&identity, action, resource)
+            .await
+            .map_err(|err| anyhow::anyhow!("policy check failed: {err}"))?;
+        self.audit_decision(&identity, action, resource, allowed)
+            .await;
+        self.enforce(allowed, action, resource).await
+    }
+
+    async fn routed_directory(&self, labels: &[String]) -> Result<Option<PathBuf>> {
+        let Some(directory) = self
+            .output_routes()
+            .directory_for(labels)
+            .map(Path::to_path_buf)
+        else {
+            return Ok(None);
+        };
+        self.guard_identity(ROUTE_ACTION, directory.to_string_lossy().as_ref())
+            .await?;
+        Ok(Some(directory))
+    }
+
+    async fn guard_policy(&self, subject: &str, action: &str, resource: &str) -> Result<()> {
+        let allowed = self
+            .dg
+            .check_policy(subject, action, resource)
+            .await
+            .map_err(|err| anyhow::anyhow!("policy check failed: {err}"))?;
+        self.enforce(allowed, action, resource).await
+    }
+
+    async fn enforce(&self, allowed: bool, action: &str, resource: &str) -> Result<()> {
+        if !allowed {
+            let denied = UserError::policy_denied(action, resource);
+            self.emit(ControllerEvent::Error(denied.message().to_owned()))
+                .await;
+            return Err(denied.into());
+        }
+        Ok(())
+    }
+
+    async fn audit_decision(
+        &self,
+        identity: &Identity,
+        action: &str,
+        resource: &str,
+        allowed: bool,
+    ) {
+        let effect = if allowed { "allow" } else { "deny" };
+        let event = AuditEvent::new(&identity.subject(), action, resource, effect)
+            .with_details(serde_json::json!({ "identity": identity }));
+        if let Some(audit) = self.audit.lock().await.as_ref() {
+            if let Err(err) = audit.record(event).await {
+                warn!(error = %err, "failed to record audit event");
+            }
+        }
+    }
+}
+
+async fn persist_envelope(target: &Path, envelope: &Envelope, source: Option<&Path>) -> Result<()> {
+    let serialized = encode_envelope(envelope, source)?;
+    let staging = enriched_extension(target, "tmp");
+    fs::write(&staging, serialized).await?;
+    fs::rename(&staging, target).await?;
+    Ok(())
+}
+
+fn encode_envelope(envelope: &Envelope, source: Option<&Path>) -> Result<Vec<u8>> {
+    let meta = match source {
+        Some(source) => enrich_meta(envelope, source),
+        None => envelope.meta.clone(),
+    };
+    StoredEnvelope::new(
+        envelope,
+        meta,
+        source.map(|source| display_path(source).to_string_lossy().into_owned()),
+    )
+    .to_bytes()
+}
+
+async fn load_envelope(path: &Path) -> Result<Envelope> {
+    Ok(load_stored(path).await?.0)
+}
+
+async fn load_stored(path: &Path) -> Result<(Envelope, Option<String>)> {
+    StoredEnvelope::read(path).await?.into_envelope()
+}
+
+async fn replace_envelope(path: &Path, envelope: &Envelope) -> Result<()> {
+    let mut stored = StoredEnvelope::read(path).await?;
+    stored.payload = general_purpose::STANDARD.encode(&envelope.bytes);
+    stored.meta = envelope.meta.clone();
+    stored.format = ENVELOPE_FORMAT;
+    write_stored(path, &stored).await
+}
+
+async fn write_stored(path: &Path, stored: &StoredEnvelope) -> Result<()> {
+    let permissions = fs::metadata(path).await?.permissions();
+    let staging = enriched_extension(path, "tmp");
+    fs::write(&staging, stored.to_bytes()?).await?;
+    fs::set_permissions(&staging, permissions).await?;
+    fs::rename(&staging, path).await?;
+    Ok(())
+}
+
+fn enriched_extension(path: &Path, suffix: &str) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_else(|| "data".into());
+    let mut new_name = file_name;
+    new_name.push(".");
+    new_name.push(suffix);
+    path.with_file_name(new_name)
+}
+
+fn unix_seconds() -> u64 {
+    (unix_nanos() / 1_000_000_000) as u64
+}
+
+fn unix_nanos() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default()
+}
+
+fn signature_path(path: &Path) -> PathBuf {
+    enriched_extension(path, SIGNATURE_EXTENSION)
+}
+
+fn envelope_labels(envelope: &Envelope) -> Vec<String> {
+    envelope
+        .meta
+        .get("labels")
+        .and_then(|labels| serde_json::from_value(labels.clone()).ok())
+        .unwrap_or_default()
+}
+
+fn enrich_meta(envelope: &Envelope, source: &Path) -> serde_json::Value {
+    let mut meta = envelope.meta.clone();
+    if let Some(obj) = meta.as_object_mut() {
+        obj.insert(
+            "source".into(),
+            serde_json::Value::String(display_path(source).to_string_lossy().into_owned()),
+        );
+    }
+    meta
+}
+
+async fn ensure_directory(path: &Path) -> Result<()> {
+    let metadata = fs::metadata(extended(path))
+        .await
+        .with_context(|| format!("output directory does not exist: {}", path.display()))?;
+    if !metadata.is_dir() {
+        return Err(anyhow::anyhow!(
+            "output directory is not a directory: {}",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+fn encrypted_target(
+    path: &Path,
+    out_dir: Option<&Path>,
+    templates: &OutputTemplates,
+    labels: &[String],
+) -> Result<PathBuf> {
+    output_target(path, &templates.encrypted_name(path, labels)?, out_dir)
+}
+
+fn decrypted_target(
+    path: &Path,
+    out_dir: Option<&Path>,
+    templates: &OutputTemplates,
+    labels: &[String],
+) -> Result<PathBuf> {
+    output_target(path, &templates.decrypted_name(path, labels)?, out_dir)
+}
+
+fn keyed_target(
+    path: &Path,
+    key_id: &str,
+    extension: &str,
+    out_dir: Option<&Path>,
+) -> Result<PathBuf> {
+    suffixed_target(path, &format!("{key_id}.{extension}"), out_dir)
+}
+
+fn suffixed_target(path: &Path, extension: &str, out_dir: Option<&Path>) -> Result<PathBuf> {
+    let candidate = enriched_extension(path, extension);
+    match out_dir {
+        Some(dir) => {
+            let file_name = candidate.file_name().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "unable to determine {extension} name for {}",
+                    path.display()
+                )
+            })?;
+            Ok(extended(&dir.join(file_name)))
+        }
+        None => Ok(extended(&candidate)),
+    }
+}
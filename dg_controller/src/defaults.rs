@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use dg_core::chunking::DedupStats;
+use serde::{Deserialize, Serialize};
+
+use crate::shred::ShredReport;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EncryptionDefaults {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+}
+
+impl EncryptionDefaults {
+    pub fn validate(&self) -> Result<()> {
+        if self
+            .recipients
+            .iter()
+            .any(|recipient| recipient.trim().is_empty())
+        {
+            return Err(anyhow::anyhow!("default recipients must not be blank"));
+        }
+        Ok(())
+    }
+
+    // Fills in whatever the request left empty and records what was filled in.
+    pub(crate) fn apply(
+        &self,
+        recipients: &mut Vec<String>,
+        labels: &mut Vec<String>,
+    ) -> AppliedDefaults {
+        let mut applied = AppliedDefaults::default();
+        if recipients.is_empty() && !self.recipients.is_empty() {
+            recipients.clone_from(&self.recipients);
+            applied.recipients.clone_from(&self.recipients);
+        }
+        if labels.is_empty() && !self.labels.is_empty() {
+            labels.clone_from(&self.labels);
+            applied.labels.clone_from(&self.labels);
+        }
+        applied
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AppliedDefaults {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+}
+
+impl AppliedDefaults {
+    pub fn is_empty(&self) -> bool {
+        self.recipients.is_empty() && self.labels.is_empty()
+    }
+
+    pub(crate) fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.recipients.is_empty() {
+            parts.push(format!("recipients {}", self.recipients.join(", ")));
+        }
+        if !self.labels.is_empty() {
+            parts.push(format!("labels {}", self.labels.join(", ")));
+        }
+        parts.join("; ")
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EncryptOutcome {
+    pub output: PathBuf,
+    #[serde(skip_serializing_if = "AppliedDefaults::is_empty")]
+    pub defaults: AppliedDefaults,
+    // Set when the file was re-encrypted against an earlier version.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dedup: Option<DedupStats>,
+    // Set when the source was shredded after encryption.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shredded: Option<ShredReport>,
+}
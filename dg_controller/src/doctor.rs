@@ -0,0 +1,159 @@
+use std::path::Path;
+use std::time::Duration;
+
+use dg_bridge::{BridgeClient, BridgeConfig, Endpoint, RpcRequest};
+use dg_core::doctor::{self, DoctorReport, Finding};
+use serde_json::json;
+
+pub const EXPECTED_CORE_VERSION: &str = env!("CARGO_PKG_VERSION");
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Default)]
+pub struct DoctorOptions {
+    pub endpoints: Vec<Endpoint>,
+    pub core_binary: Option<std::path::PathBuf>,
+}
+
+pub async fn run(data_dir: &Path, options: &DoctorOptions) -> DoctorReport {
+    let mut report = doctor::examine(data_dir).await;
+    if let Some(binary) = &options.core_binary {
+        report.push(check_binary(binary));
+    }
+    for endpoint in &options.endpoints {
+        report.extend(check_endpoint(endpoint).await);
+    }
+    report
+}
+
+pub fn check_binary(path: &Path) -> Finding {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => metadata,
+        Ok(_) | Err(_) => {
+            return Finding::error(
+                "core-binary",
+                format!("{} is missing", path.display()),
+                "Reinstall Data Guardian to restore the bundled DG Core runtime",
+            )
+        }
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Finding::error(
+                "core-binary",
+                format!("{} is not executable", path.display()),
+                format!(
+                    "Run `chmod +x {}` or reinstall Data Guardian",
+                    path.display()
+                ),
+            );
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+    Finding::ok("core-binary", format!("{} is present", path.display()))
+}
+
+pub async fn check_endpoint(endpoint: &Endpoint) -> Vec<Finding> {
+    if let Err(err) = BridgeClient::probe_endpoint(endpoint, PROBE_TIMEOUT).await {
+        return vec![unreachable(endpoint, &err)];
+    }
+    let mut findings = vec![Finding::ok(
+        "core-endpoint",
+        format!("DG Core answers on {endpoint}"),
+    )];
+    let client = match BridgeClient::new(
+        BridgeConfig::new(vec![endpoint.clone()])
+            .with_timeout(PROBE_TIMEOUT)
+            .with_retries(0),
+    ) {
+        Ok(client) => client,
+        Err(err) => {
+            findings.push(Finding::warning(
+                "core-version",
+                format!("unable to query {endpoint}: {err}"),
+                "Restart Data Guardian",
+            ));
+            return findings;
+        }
+    };
+    let reported = client
+        .send_request(RpcRequest {
+            id: "doctor".into(),
+            method: "core.ping".into(),
+            params: Some(json!({})),
+        })
+        .await
+        .ok()
+        .and_then(|response| response.result)
+        .and_then(|result| result.get("version")?.as_str().map(str::to_owned));
+    findings.push(match reported {
+        Some(version) => compare_versions(&version, EXPECTED_CORE_VERSION),
+        None => Finding::warning(
+            "core-version",
+            format!("DG Core on {endpoint} did not report its version"),
+            "Update DG Core so it matches this release",
+        ),
+    });
+    findings
+}
+
+fn unreachable(endpoint: &Endpoint, err: &anyhow::Error) -> Finding {
+    match endpoint {
+        Endpoint::Unix(path) if path.exists() => Finding::error(
+            "core-endpoint",
+            format!(
+                "{} exists but nothing is listening: {err:#}",
+                path.display()
+            ),
+            format!(
+                "The socket is stale; remove {} and restart Data Guardian",
+                path.display()
+            ),
+        ),
+        Endpoint::Unix(path) => Finding::warning(
+            "core-endpoint",
+            format!("DG Core is not running ({} is missing)", path.display()),
+            "Start Data Guardian or run the daemon with --socket",
+        ),
+        Endpoint::NamedPipe(name) => Finding::warning(
+            "core-endpoint",
+            format!("DG Core is not listening on {name}: {err:#}"),
+            "Start Data Guardian or run the daemon with --pipe",
+        ),
+        Endpoint::Tcp(addr) => Finding::warning(
+            "core-endpoint",
+            format!("DG Core is not listening on {addr}: {err:#}"),
+            "Start the daemon with --tcp or remove the TCP fallback",
+        ),
+    }
+}
+
+pub fn compare_versions(reported: &str, expected: &str) -> Finding {
+    let major_minor = |version: &str| {
+        let mut parts = version.trim_start_matches('v').split('.');
+        (
+            parts.next().unwrap_or_default().to_string(),
+            parts.next().unwrap_or_default().to_string(),
+        )
+    };
+    let (reported_major, reported_minor) = major_minor(reported);
+    let (expected_major, expected_minor) = major_minor(expected);
+    if reported_major != expected_major {
+        Finding::error(
+            "core-version",
+            format!("DG Core {reported} is incompatible with this client ({expected})"),
+            "Install matching versions of the desktop app and DG Core",
+        )
+    } else if reported_minor != expected_minor {
+        Finding::warning(
+            "core-version",
+            format!("DG Core {reported} differs from this client ({expected})"),
+            "Update so both report the same version; newer methods may be missing",
+        )
+    } else {
+        Finding::ok("core-version", format!("DG Core {reported}"))
+    }
+}
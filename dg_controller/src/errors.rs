@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use dg_core::rpc_error::{self, RpcError, APPLICATION_ERROR};
+use dg_core::DGError;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::paths::display_path;
+
+pub const NOT_BOOTED: &str = "controller.not_booted";
+pub const SHUTTING_DOWN: &str = "controller.shutting_down";
+pub const OPERATION_CANCELLED: &str = "operation.cancelled";
+pub const OPERATION_CANCELLED_BY_USER: &str = "operation.cancelled_by_user";
+pub const OPERATION_NOT_FOUND: &str = "operation.not_found";
+pub const JOB_NOT_FOUND: &str = "job.not_found";
+pub const KEY_NOT_FOUND: &str = "key.not_found";
+pub const LABEL_INVALID: &str = "label.invalid";
+pub const LABEL_UNKNOWN: &str = "label.unknown";
+pub const POLICY_DENIED: &str = "policy.denied";
+pub const PATH_UNAVAILABLE: &str = "file.unavailable";
+pub const APPROVAL_DECLINED: &str = "approval.declined";
+pub const APPROVAL_TIMED_OUT: &str = "approval.timed_out";
+pub const INTERNAL: &str = "internal";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UserError {
+    pub code: String,
+    pub params: BTreeMap<String, String>,
+    message: String,
+}
+
+impl UserError {
+    pub fn new(code: &str, message: impl Into<String>) -> Self {
+        Self {
+            code: code.to_owned(),
+            params: BTreeMap::new(),
+            message: message.into(),
+        }
+    }
+
+    pub fn with_param(mut self, name: &str, value: impl ToString) -> Self {
+        self.params.insert(name.to_owned(), value.to_string());
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn not_booted() -> anyhow::Error {
+        Self::new(NOT_BOOTED, "controller is not booted").into()
+    }
+
+    pub fn shutting_down() -> anyhow::Error {
+        Self::new(SHUTTING_DOWN, "controller is shutting down").into()
+    }
+
+    pub fn operation_cancelled() -> anyhow::Error {
+        Self::new(OPERATION_CANCELLED, "operation cancelled by shutdown").into()
+    }
+
+    pub fn cancelled_by_user(id: u64) -> anyhow::Error {
+        Self::new(
+            OPERATION_CANCELLED_BY_USER,
+            format!("operation {id} was cancelled"),
+        )
+        .with_param("id", id)
+        .into()
+    }
+
+    pub fn operation_not_found(id: u64) -> anyhow::Error {
+        Self::new(
+            OPERATION_NOT_FOUND,
+            format!("no running operation with id {id}"),
+        )
+        .with_param("id", id)
+        .into()
+    }
+
+    pub fn job_not_found(id: u64) -> anyhow::Error {
+        Self::new(JOB_NOT_FOUND, format!("no background job with id {id}"))
+            .with_param("id", id)
+            .into()
+    }
+
+    pub fn key_not_found(key: &str) -> anyhow::Error {
+        Self::new(
+            KEY_NOT_FOUND,
+            format!("no key named '{key}' in the keyring"),
+        )
+        .with_param("key", key)
+        .into()
+    }
+
+    pub fn invalid_label(label: &str, reason: &str) -> anyhow::Error {
+        Self::new(
+            LABEL_INVALID,
+            format!("label '{label}' is invalid: {reason}"),
+        )
+        .with_param("label", label)
+        .with_param("reason", reason)
+        .into()
+    }
+
+    pub fn unknown_labels(labels: &[String]) -> anyhow::Error {
+        let labels = labels.join(", ");
+        Self::new(
+            LABEL_UNKNOWN,
+            format!("labels are not in the label registry: {labels}"),
+        )
+        .with_param("labels", labels)
+        .into()
+    }
+
+    pub fn policy_denied(action: &str, resource: &str) -> Self {
+        Self::new(
+            POLICY_DENIED,
+            format!("operation denied by policy for {action} on {resource}"),
+        )
+        .with_param("action", action)
+        .with_param("resource", resource)
+    }
+
+    pub fn path_unavailable(path: &Path, err: std::io::Error) -> anyhow::Error {
+        let path = display_path(path);
+        Self::new(
+            PATH_UNAVAILABLE,
+            format!("unable to canonicalize {}: {err}", path.display()),
+        )
+        .with_param("path", path.display())
+        .with_param("reason", err)
+        .into()
+    }
+
+    pub fn approval_declined(device: &str) -> anyhow::Error {
+        Self::new(
+            APPROVAL_DECLINED,
+            format!("the unlock was declined on {device}"),
+        )
+        .with_param("device", device)
+        .into()
+    }
+
+    pub fn approval_timed_out(path: &Path, seconds: u64) -> anyhow::Error {
+        let path = display_path(path);
+        Self::new(
+            APPROVAL_TIMED_OUT,
+            format!(
+                "no paired device approved unlocking {} within {seconds}s",
+                path.display()
+            ),
+        )
+        .with_param("path", path.display())
+        .with_param("seconds", seconds)
+        .into()
+    }
+
+    pub fn from_anyhow(err: &anyhow::Error) -> Self {
+        if let Some(user) = err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<UserError>())
+        {
+            return user.clone();
+        }
+        if let Some(rpc) = err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<RpcError>())
+        {
+            return Self::from_rpc(rpc);
+        }
+        let detail = format!("{err:#}");
+        match err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<DGError>())
+        {
+            Some(engine) => Self::new(engine.code(), detail.clone()).with_param("detail", detail),
+            None => Self::new(INTERNAL, detail.clone()).with_param("detail", detail),
+        }
+    }
+
+    pub fn from_rpc(error: &RpcError) -> Self {
+        let mut user = Self::new(error.kind(), error.message.clone());
+        match error.data.as_ref().and_then(|data| data.get("params")) {
+            Some(serde_json::Value::Object(params)) => {
+                for (name, value) in params {
+                    let value = match value {
+                        serde_json::Value::String(value) => value.clone(),
+                        other => other.to_string(),
+                    };
+                    user = user.with_param(name, value);
+                }
+            }
+            _ => user = user.with_param("detail", error.detail()),
+        }
+        user
+    }
+
+    pub fn to_rpc(&self) -> RpcError {
+        RpcError::new(
+            match self.code.as_str() {
+                POLICY_DENIED => rpc_error::POLICY_DENIED,
+                code => rpc_error::code_for(code).unwrap_or(APPLICATION_ERROR),
+            },
+            self.message.clone(),
+        )
+        .with_data(json!({ "kind": self.code, "params": self.params }))
+    }
+}
+
+impl fmt::Display for UserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for UserError {}
@@ -0,0 +1,244 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+pub const DEFAULT_EVENT_CAPACITY: usize = 500;
+const EVENTS_DIR: &str = "events";
+const JOURNAL_FILE: &str = "journal.jsonl";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventVerbosity {
+    Terse,
+    #[default]
+    Normal,
+    Verbose,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "message", rename_all = "lowercase")]
+pub enum ControllerEvent {
+    Progress(String),
+    Error(String),
+    Step(String),
+    Completed(String),
+    Ready(String),
+    Cancelled(String),
+}
+
+impl ControllerEvent {
+    pub fn message(&self) -> &str {
+        match self {
+            ControllerEvent::Progress(message)
+            | ControllerEvent::Error(message)
+            | ControllerEvent::Step(message)
+            | ControllerEvent::Completed(message)
+            | ControllerEvent::Ready(message)
+            | ControllerEvent::Cancelled(message) => message,
+        }
+    }
+
+    pub fn verbosity(&self) -> EventVerbosity {
+        match self {
+            ControllerEvent::Error(_)
+            | ControllerEvent::Completed(_)
+            | ControllerEvent::Ready(_)
+            | ControllerEvent::Cancelled(_) => EventVerbosity::Terse,
+            ControllerEvent::Progress(_) => EventVerbosity::Normal,
+            ControllerEvent::Step(_) => EventVerbosity::Verbose,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let text = self
+            .message()
+            .split(' ')
+            .map(shorten_path)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut chars = text.chars();
+        let sentence = match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        };
+        match self {
+            ControllerEvent::Error(_) => format!("Error: {sentence}"),
+            _ => sentence,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub seq: u64,
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub event: ControllerEvent,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub verbosity: EventVerbosity,
+}
+
+impl RecordedEvent {
+    fn new(seq: u64, timestamp: u64, event: ControllerEvent) -> Self {
+        Self {
+            seq,
+            timestamp,
+            summary: event.summary(),
+            verbosity: event.verbosity(),
+            event,
+        }
+    }
+}
+
+fn shorten_path(token: &str) -> String {
+    let path = token.trim_end_matches([':', ',', ';', ')']);
+    let separators = ['/', '\\'];
+    let drive = path.len() > 2 && path.as_bytes()[1] == b':' && path[2..].starts_with(separators);
+    if !(path.starts_with(separators) || drive) {
+        return token.to_owned();
+    }
+    match path.rsplit(separators).find(|part| !part.is_empty()) {
+        Some(name) => format!("{name}{}", &token[path.len()..]),
+        None => token.to_owned(),
+    }
+}
+
+pub(crate) struct EventJournal {
+    capacity: usize,
+    events: VecDeque<RecordedEvent>,
+    next_seq: u64,
+    path: Option<PathBuf>,
+    lines_on_disk: usize,
+}
+
+impl EventJournal {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            events: VecDeque::new(),
+            next_seq: 1,
+            path: None,
+            lines_on_disk: 0,
+        }
+    }
+
+    pub async fn attach(&mut self, data_dir: &Path) -> Result<()> {
+        let path = data_dir.join(EVENTS_DIR).join(JOURNAL_FILE);
+        let stored = match fs::read_to_string(&path).await {
+            Ok(content) => content
+                .lines()
+                .filter_map(|line| serde_json::from_str::<RecordedEvent>(line).ok())
+                .map(|recorded| {
+                    RecordedEvent::new(recorded.seq, recorded.timestamp, recorded.event)
+                })
+                .collect::<Vec<_>>(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                return Err(anyhow::anyhow!(
+                    "unable to read event journal {}: {err}",
+                    path.display()
+                ))
+            }
+        };
+
+        let pending = std::mem::take(&mut self.events);
+        let last_seq = stored.last().map(|event| event.seq).unwrap_or(0);
+        self.events = stored.into();
+        self.next_seq = last_seq + 1;
+        for recorded in pending {
+            self.push(recorded.timestamp, recorded.event);
+        }
+        self.trim();
+        self.path = Some(path);
+        self.compact().await
+    }
+
+    pub fn detach(&mut self) {
+        self.path = None;
+    }
+
+    pub fn record(&mut self, event: ControllerEvent) -> RecordedEvent {
+        let recorded = self.push(unix_now(), event);
+        self.trim();
+        recorded
+    }
+
+    pub async fn persist(&mut self, recorded: &RecordedEvent) -> Result<()> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+        if self.lines_on_disk >= self.capacity * 2 {
+            return self.compact().await;
+        }
+        let mut line = serde_json::to_vec(recorded)?;
+        line.push(b'\n');
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .with_context(|| format!("unable to open {}", path.display()))?;
+        file.write_all(&line).await?;
+        self.lines_on_disk += 1;
+        Ok(())
+    }
+
+    pub fn since(&self, since: Option<u64>, verbosity: EventVerbosity) -> Vec<RecordedEvent> {
+        let since = since.unwrap_or(0);
+        self.events
+            .iter()
+            .filter(|recorded| recorded.seq > since && recorded.verbosity <= verbosity)
+            .cloned()
+            .collect()
+    }
+
+    fn push(&mut self, timestamp: u64, event: ControllerEvent) -> RecordedEvent {
+        let recorded = RecordedEvent::new(self.next_seq, timestamp, event);
+        self.next_seq += 1;
+        self.events.push_back(recorded.clone());
+        recorded
+    }
+
+    fn trim(&mut self) {
+        while self.events.len() > self.capacity {
+            self.events.pop_front();
+        }
+    }
+
+    async fn compact(&mut self) -> Result<()> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("unable to create {}", parent.display()))?;
+        }
+        let mut content = Vec::new();
+        for recorded in &self.events {
+            content.extend(serde_json::to_vec(recorded)?);
+            content.push(b'\n');
+        }
+        let staging = path.with_extension("jsonl.tmp");
+        fs::write(&staging, content)
+            .await
+            .with_context(|| format!("unable to write {}", staging.display()))?;
+        fs::rename(&staging, path).await?;
+        self.lines_on_disk = self.events.len();
+        Ok(())
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+pub const ENCRYPTED_DIR: &str = "encrypted";
+pub const PARTIAL_EXTENSION: &str = "partial";
+// Files written in place by other apps are left alone until they stop changing.
+pub const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InboxSettings {
+    pub name: String,
+    pub dir: PathBuf,
+    pub recipients: Vec<String>,
+    pub labels: Vec<String>,
+    pub out_dir: Option<PathBuf>,
+}
+
+impl InboxSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(anyhow!("inbox needs a name"));
+        }
+        if self.dir.as_os_str().is_empty() {
+            return Err(anyhow!("inbox '{}' needs a directory", self.name));
+        }
+        if self.out_dir.as_deref() == Some(self.dir.as_path()) {
+            return Err(anyhow!(
+                "inbox '{}' cannot write envelopes into the inbox itself",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn output_dir(&self) -> PathBuf {
+        self.out_dir
+            .clone()
+            .unwrap_or_else(|| self.dir.join(ENCRYPTED_DIR))
+    }
+}
+
+pub fn validate_inboxes(inboxes: &[InboxSettings]) -> Result<()> {
+    for (position, inbox) in inboxes.iter().enumerate() {
+        inbox.validate()?;
+        if inboxes[..position]
+            .iter()
+            .any(|other| other.name == inbox.name || other.dir == inbox.dir)
+        {
+            return Err(anyhow!(
+                "inbox '{}' shares its name or directory with another inbox",
+                inbox.name
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InboxFile {
+    pub source: PathBuf,
+    pub output: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InboxFailure {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct InboxReport {
+    pub inbox: String,
+    pub encrypted: Vec<InboxFile>,
+    pub failures: Vec<InboxFailure>,
+    pub pending: usize,
+}
+
+impl InboxReport {
+    pub fn is_empty(&self) -> bool {
+        self.encrypted.is_empty() && self.failures.is_empty()
+    }
+}
+
+// Top-level regular files only: hidden files, helper partials and existing envelopes are skipped.
+pub(crate) async fn ready_files(
+    dir: &Path,
+    encrypted_extension: &str,
+    now: SystemTime,
+) -> Result<(Vec<PathBuf>, usize)> {
+    fs::create_dir_all(dir)
+        .await
+        .with_context(|| format!("unable to create inbox {}", dir.display()))?;
+    let mut entries = fs::read_dir(dir)
+        .await
+        .with_context(|| format!("unable to list inbox {}", dir.display()))?;
+    let mut ready = Vec::new();
+    let mut pending = 0;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let skipped = name.starts_with('.')
+            || path
+                .extension()
+                .is_some_and(|ext| ext == encrypted_extension || ext == PARTIAL_EXTENSION);
+        if skipped {
+            continue;
+        }
+        let metadata = entry.metadata().await?;
+        if !metadata.is_file() {
+            continue;
+        }
+        let settled = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age >= SETTLE_TIME);
+        if settled {
+            ready.push(path);
+        } else {
+            pending += 1;
+        }
+    }
+    ready.sort();
+    Ok((ready, pending))
+}
@@ -0,0 +1,236 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::sync::Mutex;
+
+use crate::backup::{ConflictPolicy, TransferCheckpoint};
+use crate::errors::UserError;
+
+const JOBS_DIR: &str = "jobs";
+const QUEUE_FILE: &str = "queue.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum JobSpec {
+    Encrypt {
+        paths: Vec<PathBuf>,
+        #[serde(default)]
+        recipients: Vec<String>,
+        #[serde(default)]
+        labels: Vec<String>,
+        #[serde(default)]
+        out_dir: Option<PathBuf>,
+    },
+    Reencrypt {
+        key_id: String,
+        paths: Vec<PathBuf>,
+    },
+    Sync {
+        target: PathBuf,
+        #[serde(default)]
+        conflict: ConflictPolicy,
+    },
+    Dispose {
+        paths: Vec<PathBuf>,
+    },
+}
+
+impl JobSpec {
+    pub fn len(&self) -> usize {
+        match self {
+            JobSpec::Encrypt { paths, .. }
+            | JobSpec::Reencrypt { paths, .. }
+            | JobSpec::Dispose { paths } => paths.len(),
+            JobSpec::Sync { .. } => 1,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn item(&self, position: usize) -> Option<&Path> {
+        match self {
+            JobSpec::Encrypt { paths, .. }
+            | JobSpec::Reencrypt { paths, .. }
+            | JobSpec::Dispose { paths } => paths.get(position).map(PathBuf::as_path),
+            JobSpec::Sync { target, .. } => (position == 0).then_some(target.as_path()),
+        }
+    }
+
+    pub(crate) fn verbs(&self) -> (&'static str, &'static str) {
+        match self {
+            JobSpec::Encrypt { .. } => ("encrypting", "encrypt"),
+            JobSpec::Reencrypt { .. } => ("re-encrypting", "re-encrypt"),
+            JobSpec::Sync { .. } => ("syncing", "sync"),
+            JobSpec::Dispose { .. } => ("disposing of", "dispose of"),
+        }
+    }
+
+    pub(crate) fn completion(&self, succeeded: usize) -> String {
+        match self {
+            JobSpec::Encrypt { paths, .. } => {
+                format!("encrypted {succeeded} of {} files", paths.len())
+            }
+            JobSpec::Reencrypt { paths, .. } => {
+                format!("re-encrypted {succeeded} of {} envelopes", paths.len())
+            }
+            JobSpec::Sync { target, .. } => format!("synced envelopes to {}", target.display()),
+            JobSpec::Dispose { paths } => {
+                format!(
+                    "disposed of {succeeded} of {} overdue envelopes",
+                    paths.len()
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Running,
+    Paused,
+    Completed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobFailure {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: u64,
+    pub spec: JobSpec,
+    pub status: JobStatus,
+    pub position: usize,
+    pub total: usize,
+    pub succeeded: usize,
+    #[serde(default)]
+    pub failures: Vec<JobFailure>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checkpoint: Option<TransferCheckpoint>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    #[serde(skip)]
+    pub(crate) active: bool,
+}
+
+impl Job {
+    pub fn finished(&self) -> bool {
+        self.status == JobStatus::Completed
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct JobQueue {
+    #[serde(default)]
+    next_id: u64,
+    #[serde(default)]
+    jobs: Vec<Job>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl JobQueue {
+    pub async fn load(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join(JOBS_DIR).join(QUEUE_FILE);
+        let mut queue = match fs::read(&path).await {
+            Ok(bytes) => serde_json::from_slice::<Self>(&bytes).context("invalid job queue")?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(err) => return Err(anyhow::anyhow!("unable to read job queue: {err}")),
+        };
+        queue.path = path;
+        Ok(queue)
+    }
+
+    pub async fn persist(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let mut staging = self.path.as_os_str().to_owned();
+        staging.push(".tmp");
+        let staging = PathBuf::from(staging);
+        fs::write(&staging, serde_json::to_vec_pretty(self)?).await?;
+        fs::rename(&staging, &self.path).await?;
+        Ok(())
+    }
+
+    pub fn submit(&mut self, spec: JobSpec) -> Job {
+        self.next_id += 1;
+        let now = unix_now();
+        let job = Job {
+            id: self.next_id,
+            total: spec.len(),
+            spec,
+            status: JobStatus::Running,
+            position: 0,
+            succeeded: 0,
+            failures: Vec::new(),
+            checkpoint: None,
+            created_at: now,
+            updated_at: now,
+            active: false,
+        };
+        self.jobs.push(job.clone());
+        job
+    }
+
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Result<&mut Job> {
+        self.jobs
+            .iter_mut()
+            .find(|job| job.id == id)
+            .ok_or_else(|| UserError::job_not_found(id))
+    }
+
+    pub fn pending(&self) -> Vec<u64> {
+        self.jobs
+            .iter()
+            .filter(|job| job.status == JobStatus::Running)
+            .map(|job| job.id)
+            .collect()
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct JobCheckpoints {
+    queue: Arc<Mutex<Option<JobQueue>>>,
+    job: u64,
+}
+
+impl JobCheckpoints {
+    pub fn new(queue: Arc<Mutex<Option<JobQueue>>>, job: u64) -> Self {
+        Self { queue, job }
+    }
+
+    pub async fn load(&self) -> Option<TransferCheckpoint> {
+        let mut jobs = self.queue.lock().await;
+        jobs.as_mut()?.get_mut(self.job).ok()?.checkpoint.clone()
+    }
+
+    pub async fn save(&self, checkpoint: Option<TransferCheckpoint>) -> Result<()> {
+        let mut jobs = self.queue.lock().await;
+        let queue = jobs.as_mut().ok_or_else(UserError::not_booted)?;
+        let job = queue.get_mut(self.job)?;
+        job.checkpoint = checkpoint;
+        job.updated_at = unix_now();
+        queue.persist().await
+    }
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::errors::UserError;
+
+const LABELS_FILE: &str = "labels.json";
+pub(crate) const SEPARATOR: char = '/';
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelValidation {
+    #[default]
+    Warn,
+    Enforce,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+impl LabelDefinition {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            description: String::new(),
+            color: None,
+        }
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_owned();
+        self
+    }
+
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.color = Some(color.to_owned());
+        self
+    }
+
+    pub fn parent(&self) -> Option<&str> {
+        self.name.rsplit_once(SEPARATOR).map(|(parent, _)| parent)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelRegistry {
+    #[serde(default)]
+    pub validation: LabelValidation,
+    #[serde(default)]
+    labels: BTreeMap<String, LabelDefinition>,
+}
+
+impl LabelRegistry {
+    pub async fn load(data_dir: &Path) -> Result<Self> {
+        match fs::read(registry_path(data_dir)).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).context("invalid label registry"),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(anyhow::anyhow!("unable to read label registry: {err}")),
+        }
+    }
+
+    pub async fn persist(&self, data_dir: &Path) -> Result<()> {
+        let path = registry_path(data_dir);
+        let mut staging = path.as_os_str().to_owned();
+        staging.push(".tmp");
+        let staging = PathBuf::from(staging);
+        fs::write(&staging, serde_json::to_vec_pretty(self)?).await?;
+        fs::rename(&staging, &path).await?;
+        Ok(())
+    }
+
+    pub fn labels(&self) -> impl Iterator<Item = &LabelDefinition> {
+        self.labels.values()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LabelDefinition> {
+        self.labels.get(name)
+    }
+
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a LabelDefinition> {
+        self.labels
+            .values()
+            .filter(move |label| label.parent() == Some(name))
+    }
+
+    pub fn upsert(&mut self, mut label: LabelDefinition) -> Result<()> {
+        label.name = normalize(&label.name);
+        validate_name(&label.name)?;
+        if let Some(color) = label.color.as_deref() {
+            if !is_hex_color(color) {
+                return Err(invalid(&label.name, "colors must look like #1a2b3c"));
+            }
+        }
+        if let Some(parent) = label.parent() {
+            if !self.labels.contains_key(parent) {
+                return Err(invalid(
+                    &label.name,
+                    &format!("parent label '{parent}' is not registered"),
+                ));
+            }
+        }
+        self.labels.insert(label.name.clone(), label);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<LabelDefinition> {
+        let name = normalize(name);
+        if let Some(child) = self.children(&name).next() {
+            return Err(invalid(
+                &name,
+                &format!("remove its child label '{}' first", child.name),
+            ));
+        }
+        self.labels
+            .remove(&name)
+            .ok_or_else(|| invalid(&name, "label is not registered"))
+    }
+
+    pub fn unknown(&self, labels: &[String]) -> Vec<String> {
+        if self.labels.is_empty() {
+            return Vec::new();
+        }
+        labels
+            .iter()
+            .filter(|label| !self.labels.contains_key(&normalize(label)))
+            .cloned()
+            .collect()
+    }
+}
+
+pub fn normalize(name: &str) -> String {
+    name.trim().trim_matches(SEPARATOR).to_ascii_lowercase()
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.split(SEPARATOR).all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid(
+            name,
+            "use letters, digits, '-' or '_' in '/'-separated segments",
+        ))
+    }
+}
+
+fn is_hex_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn invalid(name: &str, reason: &str) -> anyhow::Error {
+    UserError::invalid_label(name, reason)
+}
+
+fn registry_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(LABELS_FILE)
+}
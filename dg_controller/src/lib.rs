@@ -0,0 +1,66 @@
+pub mod approval;
+pub mod backend;
+pub mod backup;
+pub mod compare;
+pub mod controller;
+pub mod defaults;
+pub mod doctor;
+pub mod errors;
+pub mod events;
+pub mod inbox;
+pub mod jobs;
+pub mod labels;
+pub mod management;
+pub mod migrate;
+pub mod naming;
+pub mod operations;
+pub mod paths;
+pub mod progress;
+pub mod quarantine;
+pub mod receipts;
+pub mod remote;
+pub mod report;
+pub mod restore;
+pub mod retention;
+pub mod shred;
+pub mod stored;
+pub mod throttle;
+pub mod thumbnail;
+pub mod viewer;
+pub mod walk;
+pub mod watch;
+pub mod xattrs;
+
+pub use approval::{ApprovalChannel, DeviceApproval, DirectoryChannel};
+pub use backend::{Backend, BackendConfig, BackendKind, InProcessEngine};
+pub use backup::{
+    BackupOptions, BackupReport, ConflictPolicy, TransferCheckpoint, TransferSettings,
+};
+pub use compare::{EnvelopeComparison, EnvelopeSummary, NewerCopy, NewerEvidence};
+pub use controller::{
+    AppendReport, Controller, ControllerStatus, EncryptOptions, FingerprintCheck, RevocationJob,
+};
+pub use defaults::{AppliedDefaults, EncryptOutcome, EncryptionDefaults};
+pub use doctor::DoctorOptions;
+pub use errors::UserError;
+pub use events::{ControllerEvent, EventVerbosity, RecordedEvent};
+pub use inbox::{InboxFailure, InboxFile, InboxReport, InboxSettings};
+pub use jobs::{Job, JobFailure, JobSpec, JobStatus};
+pub use labels::{LabelDefinition, LabelRegistry, LabelValidation};
+pub use management::ManagementOutcome;
+pub use migrate::{EnvelopeFormat, MigrationOptions, MigrationReport};
+pub use naming::{OutputRoutes, OutputRule, OutputTemplates};
+pub use operations::{ActiveOperation, PendingOperation, ShutdownReport};
+pub use quarantine::{Quarantine, QuarantineHandling};
+pub use receipts::{ReadReceipt, ReceiptSink, SignedReceipt};
+pub use remote::RemoteDaemon;
+pub use report::{EnvelopeReport, ExportedReport, ReportFilter, ReportFormat, ReportRow};
+pub use restore::DirectoryDecryptReport;
+pub use retention::{LegalHold, Retention, RetentionReport, RetentionState, RetentionStatus};
+pub use shred::ShredReport;
+pub use stored::StoredEnvelope;
+pub use throttle::{JobThrottle, PowerProbe};
+pub use viewer::{ViewerOptions, ViewerSession};
+pub use walk::{DirectoryEncryptReport, EncryptedFile, WalkFailure, WalkSettings};
+pub use watch::{FolderWatch, IgnoreRules, WatchBackend, WatchEvent, WatchSettings};
+pub use xattrs::{ExtendedAttributes, SkippedXattr, XattrRestore};
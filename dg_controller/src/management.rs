@@ -0,0 +1,38 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use dg_core::ManagementCommand;
+use serde::Serialize;
+use tokio::fs;
+
+use crate::viewer::shred;
+
+const KEY_DIR: &str = "keys";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ManagementOutcome {
+    pub command: ManagementCommand,
+    pub signer: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotation_job: Option<u64>,
+}
+
+// Overwrites and removes every file under `keys/`: the master key and the keyring.
+pub(crate) async fn wipe_keys(data_dir: &Path) -> Result<usize> {
+    let dir = data_dir.join(KEY_DIR);
+    let mut entries = match fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err).with_context(|| format!("unable to list {}", dir.display())),
+    };
+    let mut files = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_file() {
+            files.push(entry.path());
+        }
+    }
+    for file in &files {
+        shred(file).await?;
+    }
+    Ok(files.len())
+}
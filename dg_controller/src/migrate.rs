@@ -0,0 +1,100 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use dg_core::envelope::ENVELOPE_VERSION;
+use serde::Serialize;
+use tokio::fs;
+
+pub const ENVELOPE_FORMAT: u32 = ENVELOPE_VERSION as u32;
+const JSON_FORMAT: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EnvelopeFormat {
+    MasterKey,
+    Unversioned,
+    Json,
+    Current,
+}
+
+impl EnvelopeFormat {
+    pub fn detect(format: u32, meta: &serde_json::Value) -> Result<Self> {
+        match format {
+            0 if meta.get("key_wraps").is_some() => Ok(EnvelopeFormat::Unversioned),
+            0 => Ok(EnvelopeFormat::MasterKey),
+            JSON_FORMAT => Ok(EnvelopeFormat::Json),
+            ENVELOPE_FORMAT => Ok(EnvelopeFormat::Current),
+            other => Err(anyhow::anyhow!("unsupported envelope format {other}")),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EnvelopeFormat::MasterKey => "master-key",
+            EnvelopeFormat::Unversioned => "unversioned",
+            EnvelopeFormat::Json => "json",
+            EnvelopeFormat::Current => "current",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MigrationOptions {
+    pub recursive: bool,
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigratedEnvelope {
+    pub path: PathBuf,
+    pub from: EnvelopeFormat,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationFailure {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MigrationReport {
+    pub scanned: usize,
+    pub dry_run: bool,
+    pub migrated: Vec<MigratedEnvelope>,
+    pub current: usize,
+    pub failures: Vec<MigrationFailure>,
+}
+
+impl MigrationReport {
+    pub fn succeeded(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+pub(crate) async fn find_envelopes(
+    dir: &Path,
+    extension: &str,
+    recursive: bool,
+) -> Result<Vec<PathBuf>> {
+    let suffix = format!(".{extension}");
+    let mut pending = vec![dir.to_path_buf()];
+    let mut found = Vec::new();
+    while let Some(current) = pending.pop() {
+        let mut entries = fs::read_dir(&current)
+            .await
+            .with_context(|| format!("unable to read directory {}", current.display()))?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_type = entry.file_type().await?;
+            let path = entry.path();
+            if file_type.is_dir() {
+                if recursive {
+                    pending.push(path);
+                }
+            } else if file_type.is_file() && path.to_string_lossy().ends_with(&suffix) {
+                found.push(path);
+            }
+        }
+    }
+    found.sort();
+    Ok(found)
+}
@@ -0,0 +1,218 @@
+use std::cmp::Reverse;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::labels;
+use crate::paths::extended;
+
+pub const DEFAULT_ENCRYPTED_TEMPLATE: &str = "{name}.dgenc";
+pub const DEFAULT_DECRYPTED_TEMPLATE: &str = "{name}.dg";
+const TOKENS: &[&str] = &["name", "stem", "ext", "timestamp", "label"];
+const UNLABELED: &str = "unlabeled";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputTemplates {
+    pub encrypted: String,
+    pub decrypted: String,
+}
+
+impl Default for OutputTemplates {
+    fn default() -> Self {
+        Self {
+            encrypted: DEFAULT_ENCRYPTED_TEMPLATE.into(),
+            decrypted: DEFAULT_DECRYPTED_TEMPLATE.into(),
+        }
+    }
+}
+
+impl OutputTemplates {
+    pub fn validate(&self) -> Result<()> {
+        for template in [&self.encrypted, &self.decrypted] {
+            let sample = render(template, Path::new("sample.txt"), &[], 0)?;
+            if sample == "sample.txt" {
+                return Err(anyhow::anyhow!(
+                    "output template '{template}' would overwrite its input"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn encrypted_name(&self, source: &Path, labels: &[String]) -> Result<OsString> {
+        render(&self.encrypted, source, labels, unix_now())
+    }
+
+    pub fn decrypted_name(&self, envelope: &Path, labels: &[String]) -> Result<OsString> {
+        render(&self.decrypted, envelope, labels, unix_now())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputRule {
+    pub label: String,
+    pub directory: PathBuf,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputRoutes {
+    pub rules: Vec<OutputRule>,
+}
+
+impl OutputRoutes {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for rule in &self.rules {
+            if labels::normalize(&rule.label).is_empty() {
+                return Err(anyhow::anyhow!("output route needs a label"));
+            }
+            if !rule.directory.is_absolute() {
+                return Err(anyhow::anyhow!(
+                    "output route for '{}' must use an absolute directory, got {}",
+                    rule.label,
+                    rule.directory.display()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // The most specific rule wins, so "finance/payroll" beats "finance" for a payroll file.
+    pub fn directory_for(&self, labels: &[String]) -> Option<&Path> {
+        labels
+            .iter()
+            .map(|label| labels::normalize(label))
+            .flat_map(|label| {
+                self.rules.iter().filter_map(move |rule| {
+                    let prefix = labels::normalize(&rule.label);
+                    let matches = label == prefix
+                        || label
+                            .strip_prefix(prefix.as_str())
+                            .is_some_and(|rest| rest.starts_with(labels::SEPARATOR));
+                    matches.then_some((prefix.len(), rule))
+                })
+            })
+            .min_by_key(|(specificity, _)| Reverse(*specificity))
+            .map(|(_, rule)| rule.directory.as_path())
+    }
+}
+
+pub fn render(template: &str, source: &Path, labels: &[String], now: u64) -> Result<OsString> {
+    let name = source
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("{} has no file name", source.display()))?;
+    let stem = source.file_stem().unwrap_or(name);
+    let ext = source.extension().unwrap_or_default();
+    let label = labels
+        .first()
+        .map(String::as_str)
+        .unwrap_or(UNLABELED)
+        .replace(['/', '\\', ':', '\0'], "-");
+
+    let mut rendered = OsString::new();
+    let mut rest = template;
+    while let Some(open) = rest.find(['{', '}']) {
+        if rest[open..].starts_with('}') {
+            return Err(anyhow::anyhow!("unbalanced '}}' in template '{template}'"));
+        }
+        rendered.push(&rest[..open]);
+        let close = rest[open..]
+            .find('}')
+            .map(|offset| open + offset)
+            .ok_or_else(|| anyhow::anyhow!("unterminated token in template '{template}'"))?;
+        let token = &rest[open + 1..close];
+        match token {
+            "name" => rendered.push(name),
+            "stem" => rendered.push(stem),
+            "ext" => rendered.push(ext),
+            "timestamp" => rendered.push(utc_timestamp(now)),
+            "label" => rendered.push(&label),
+            other => {
+                return Err(anyhow::anyhow!(
+                    "unknown token '{{{other}}}' in template '{template}'; expected one of {}",
+                    TOKENS
+                        .iter()
+                        .map(|token| format!("{{{token}}}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            }
+        }
+        rest = &rest[close + 1..];
+    }
+    rendered.push(rest);
+    ensure_file_name(&rendered)?;
+    Ok(rendered)
+}
+
+pub(crate) fn output_target(
+    source: &Path,
+    file_name: &OsStr,
+    out_dir: Option<&Path>,
+) -> Result<PathBuf> {
+    ensure_file_name(file_name)?;
+    let target = match out_dir {
+        Some(dir) => dir.join(file_name),
+        None => source.with_file_name(file_name),
+    };
+    if target == source {
+        return Err(anyhow::anyhow!(
+            "output name {} would overwrite {}",
+            file_name.to_string_lossy(),
+            source.display()
+        ));
+    }
+    Ok(extended(&target))
+}
+
+fn ensure_file_name(name: &OsStr) -> Result<()> {
+    let name = name.to_string_lossy();
+    let escapes =
+        name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\', ':', '\0']);
+    if escapes {
+        return Err(anyhow::anyhow!(
+            "output name '{name}' must be a plain file name without path separators"
+        ));
+    }
+    Ok(())
+}
+
+fn utc_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let remainder = secs % 86_400;
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        remainder / 3_600,
+        remainder % 3_600 / 60,
+        remainder % 60
+    )
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tokio::sync::Notify;
+use tokio::task::{AbortHandle, JoinHandle};
+
+use crate::errors::UserError;
+
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+const CANCEL_GRACE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveOperation {
+    pub id: u64,
+    pub kind: String,
+    pub target: String,
+    pub started_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ShutdownReport {
+    pub drained: usize,
+    pub aborted: usize,
+}
+
+struct Entry {
+    info: ActiveOperation,
+    abort: Option<AbortHandle>,
+    cancelled: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct TrackerState {
+    next_id: u64,
+    closing: bool,
+    active: HashMap<u64, Entry>,
+}
+
+#[derive(Clone, Default)]
+pub struct OperationTracker {
+    state: Arc<Mutex<TrackerState>>,
+    idle: Arc<Notify>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl OperationTracker {
+    fn lock(&self) -> std::sync::MutexGuard<'_, TrackerState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn begin(&self, kind: &str, target: &str) -> anyhow::Result<OperationGuard> {
+        let mut state = self.lock();
+        if state.closing {
+            return Err(UserError::shutting_down());
+        }
+        state.next_id += 1;
+        let id = state.next_id;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        state.active.insert(
+            id,
+            Entry {
+                info: ActiveOperation {
+                    id,
+                    kind: kind.to_owned(),
+                    target: target.to_owned(),
+                    started_at: unix_now(),
+                },
+                abort: None,
+                cancelled: cancelled.clone(),
+            },
+        );
+        Ok(OperationGuard {
+            id,
+            tracker: self.clone(),
+            cancelled,
+        })
+    }
+
+    pub fn active(&self) -> Vec<ActiveOperation> {
+        let mut active: Vec<ActiveOperation> = self
+            .lock()
+            .active
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect();
+        active.sort_by_key(|operation| operation.id);
+        active
+    }
+
+    pub fn reopen(&self) {
+        self.lock().closing = false;
+        self.cancelled.store(false, Ordering::SeqCst);
+    }
+
+    pub async fn drain(&self, timeout: Duration) -> ShutdownReport {
+        let pending = {
+            let mut state = self.lock();
+            state.closing = true;
+            state.active.len()
+        };
+        if self.wait_idle(timeout).await {
+            return ShutdownReport {
+                drained: pending,
+                aborted: 0,
+            };
+        }
+
+        self.cancelled.store(true, Ordering::SeqCst);
+        let aborted = {
+            let state = self.lock();
+            for entry in state.active.values() {
+                if let Some(abort) = &entry.abort {
+                    abort.abort();
+                }
+            }
+            state.active.len()
+        };
+        self.wait_idle(CANCEL_GRACE).await;
+        ShutdownReport {
+            drained: pending.saturating_sub(aborted),
+            aborted,
+        }
+    }
+
+    async fn wait_idle(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let notified = self.idle.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.lock().active.is_empty() {
+                return true;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || tokio::time::timeout(remaining, notified).await.is_err() {
+                return self.lock().active.is_empty();
+            }
+        }
+    }
+
+    // Flags the operation for its next `check` and aborts its task, if one is attached.
+    pub fn cancel(&self, id: u64) -> anyhow::Result<ActiveOperation> {
+        let state = self.lock();
+        let entry = state
+            .active
+            .get(&id)
+            .ok_or_else(|| UserError::operation_not_found(id))?;
+        entry.cancelled.store(true, Ordering::SeqCst);
+        if let Some(abort) = &entry.abort {
+            abort.abort();
+        }
+        Ok(entry.info.clone())
+    }
+
+    // Attaches the task so `cancel` and `drain` can abort it.
+    pub fn spawned<T>(
+        &self,
+        id: u64,
+        handle: JoinHandle<anyhow::Result<T>>,
+    ) -> PendingOperation<T> {
+        self.attach(id, handle.abort_handle());
+        PendingOperation {
+            id,
+            handle,
+            tracker: self.clone(),
+        }
+    }
+
+    pub fn attach(&self, id: u64, abort: AbortHandle) {
+        if let Some(entry) = self.lock().active.get_mut(&id) {
+            entry.abort = Some(abort);
+        }
+    }
+
+    fn finish(&self, id: u64) {
+        let mut state = self.lock();
+        state.active.remove(&id);
+        if state.active.is_empty() {
+            self.idle.notify_waiters();
+        }
+    }
+}
+
+pub struct OperationGuard {
+    id: u64,
+    tracker: OperationTracker,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl OperationGuard {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn draining(&self) -> bool {
+        self.tracker.lock().closing
+    }
+
+    pub fn cancelled(&self) -> bool {
+        self.tracker.cancelled.load(Ordering::SeqCst) || self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.cancelled.load(Ordering::SeqCst) {
+            return Err(UserError::cancelled_by_user(self.id));
+        }
+        if self.tracker.cancelled.load(Ordering::SeqCst) {
+            return Err(UserError::operation_cancelled());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        self.tracker.finish(self.id);
+    }
+}
+
+pub async fn join<T>(handle: JoinHandle<anyhow::Result<T>>) -> anyhow::Result<T> {
+    match handle.await {
+        Ok(result) => result,
+        Err(err) if err.is_cancelled() => Err(UserError::operation_cancelled()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// An operation running on its own task; its id can be passed to `Controller::cancel_operation`.
+pub struct PendingOperation<T> {
+    pub id: u64,
+    handle: JoinHandle<anyhow::Result<T>>,
+    tracker: OperationTracker,
+}
+
+impl<T> PendingOperation<T> {
+    pub async fn wait(self) -> anyhow::Result<T> {
+        match self.handle.await {
+            Ok(result) => result,
+            Err(err) if err.is_cancelled() && !self.tracker.cancelled.load(Ordering::SeqCst) => {
+                Err(UserError::cancelled_by_user(self.id))
+            }
+            Err(err) if err.is_cancelled() => Err(UserError::operation_cancelled()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
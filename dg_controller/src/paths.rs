@@ -0,0 +1,64 @@
+use std::path::{Path, PathBuf};
+
+pub const LONG_PATH_THRESHOLD: usize = 248;
+
+#[cfg(windows)]
+pub fn extended(path: &Path) -> PathBuf {
+    use std::ffi::OsString;
+    use std::path::{Component, Prefix};
+
+    if path.as_os_str().len() < LONG_PATH_THRESHOLD {
+        return path.to_path_buf();
+    }
+    let Ok(absolute) = std::path::absolute(path) else {
+        return path.to_path_buf();
+    };
+    let mut components = absolute.components();
+    let mut extended = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(letter) => PathBuf::from(format!(r"\\?\{}:\", letter as char)),
+            Prefix::UNC(server, share) => {
+                let mut root = OsString::from(r"\\?\UNC\");
+                root.push(server);
+                root.push(r"\");
+                root.push(share);
+                root.push(r"\");
+                PathBuf::from(root)
+            }
+            _ => return absolute,
+        },
+        _ => return absolute,
+    };
+    for component in components {
+        match component {
+            Component::Normal(part) => extended.push(part),
+            Component::ParentDir => {
+                extended.pop();
+            }
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+        }
+    }
+    extended
+}
+
+#[cfg(not(windows))]
+pub fn extended(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+#[cfg(windows)]
+pub fn display_path(path: &Path) -> PathBuf {
+    let raw = path.as_os_str().to_string_lossy();
+    if let Some(unc) = raw.strip_prefix(r"\\?\UNC\") {
+        return PathBuf::from(format!(r"\\{unc}"));
+    }
+    match raw.strip_prefix(r"\\?\") {
+        Some(local) if local.as_bytes().get(1) == Some(&b':') => PathBuf::from(local),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(not(windows))]
+pub fn display_path(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
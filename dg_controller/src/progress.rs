@@ -0,0 +1,113 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use dg_bridge::ProgressUpdate;
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::broadcast;
+use zeroize::Zeroizing;
+
+use crate::operations::OperationGuard;
+
+pub const PROGRESS_CHUNK: usize = 1 << 20;
+// Updates within a stage are spaced at least this far apart; the last one of a stage always goes out.
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+// Byte-level progress for one operation, sent with the same shape as the core daemon's
+// `core.progress` notifications.
+pub(crate) struct ProgressReporter {
+    sender: broadcast::Sender<ProgressUpdate>,
+    op_id: String,
+    method: &'static str,
+    last_sent: Option<Instant>,
+}
+
+impl ProgressReporter {
+    pub fn new(sender: broadcast::Sender<ProgressUpdate>, id: u64, method: &'static str) -> Self {
+        Self {
+            sender,
+            op_id: id.to_string(),
+            method,
+            last_sent: None,
+        }
+    }
+
+    pub fn report(&mut self, stage: &str, bytes: u64, total: u64) {
+        let finished = bytes >= total;
+        if !finished
+            && self
+                .last_sent
+                .is_some_and(|sent| sent.elapsed() < MIN_INTERVAL)
+        {
+            return;
+        }
+        self.last_sent = (!finished).then(Instant::now);
+        let percent = if total == 0 {
+            100.0
+        } else {
+            bytes as f64 * 100.0 / total as f64
+        };
+        let _ = self.sender.send(ProgressUpdate {
+            op_id: self.op_id.clone(),
+            method: Some(self.method.to_owned()),
+            stage: stage.to_owned(),
+            bytes,
+            total_bytes: Some(total),
+            percent: Some(percent),
+        });
+    }
+
+    // Reads `path` one chunk at a time, checking for cancellation between chunks.
+    pub async fn read(&mut self, path: &Path, operation: &OperationGuard) -> Result<Vec<u8>> {
+        let mut file = fs::File::open(path)
+            .await
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let total = file.metadata().await?.len();
+        // Both buffers hold plaintext; they are wiped if the read is cancelled or fails.
+        let mut data = Zeroizing::new(Vec::with_capacity(total as usize));
+        let mut chunk = Zeroizing::new(vec![0u8; PROGRESS_CHUNK]);
+        self.report("reading", 0, total);
+        loop {
+            operation.check()?;
+            let read = file
+                .read(&mut chunk)
+                .await
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            if read == 0 {
+                break;
+            }
+            data.extend_from_slice(&chunk[..read]);
+            self.report("reading", data.len() as u64, total.max(data.len() as u64));
+        }
+        if data.len() as u64 != total {
+            // The file shrank while it was read.
+            self.report("reading", data.len() as u64, data.len() as u64);
+        }
+        Ok(std::mem::take(&mut *data))
+    }
+
+    pub async fn write(
+        &mut self,
+        path: &Path,
+        data: &[u8],
+        operation: &OperationGuard,
+    ) -> Result<()> {
+        let mut file = fs::File::create(path)
+            .await
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        let total = data.len() as u64;
+        let mut written = 0u64;
+        self.report("writing", 0, total);
+        for chunk in data.chunks(PROGRESS_CHUNK) {
+            operation.check()?;
+            file.write_all(chunk)
+                .await
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            written += chunk.len() as u64;
+            self.report("writing", written, total);
+        }
+        file.flush().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const QUARANTINE_FIELD: &str = "quarantine";
+pub const QUARANTINE_ATTRIBUTE: &str = "com.apple.quarantine";
+pub const PROVENANCE_ATTRIBUTE: &str = "com.apple.provenance";
+const ATTRIBUTES: [&str; 2] = [QUARANTINE_ATTRIBUTE, PROVENANCE_ATTRIBUTE];
+
+// What happens to the Gatekeeper attributes macOS puts on downloaded files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuarantineHandling {
+    // Seal the attributes into the envelope and put them back on decrypted files.
+    #[default]
+    Preserve,
+    // Only note the download in the envelope; decrypted files come out without the attributes.
+    Strip,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Quarantine {
+    // The application that downloaded the file, e.g. `Safari`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quarantined_at: Option<u64>,
+    pub stripped: bool,
+    // Attribute name to base64 value; empty once stripped.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl Quarantine {
+    // `None` when the file carries neither attribute.
+    pub fn detect(
+        attributes: BTreeMap<String, Vec<u8>>,
+        handling: QuarantineHandling,
+    ) -> Option<Self> {
+        if attributes.is_empty() {
+            return None;
+        }
+        let (agent, quarantined_at) = attributes
+            .get(QUARANTINE_ATTRIBUTE)
+            .map(|value| parse_quarantine(&String::from_utf8_lossy(value)))
+            .unwrap_or_default();
+        let stripped = handling == QuarantineHandling::Strip;
+        Some(Self {
+            agent,
+            quarantined_at,
+            stripped,
+            attributes: if stripped {
+                BTreeMap::new()
+            } else {
+                attributes
+                    .into_iter()
+                    .map(|(name, value)| (name, general_purpose::STANDARD.encode(value)))
+                    .collect()
+            },
+        })
+    }
+
+    fn decoded(&self) -> Result<Vec<(&str, Vec<u8>)>> {
+        self.attributes
+            .iter()
+            .filter(|(name, _)| ATTRIBUTES.contains(&name.as_str()))
+            .map(|(name, value)| {
+                general_purpose::STANDARD
+                    .decode(value)
+                    .map(|bytes| (name.as_str(), bytes))
+                    .map_err(|err| anyhow::anyhow!("envelope has a malformed {name} value: {err}"))
+            })
+            .collect()
+    }
+}
+
+// `flags;hex timestamp;agent;event id`, as written by LaunchServices.
+pub fn parse_quarantine(value: &str) -> (Option<String>, Option<u64>) {
+    let mut fields = value.trim_end_matches('\0').split(';');
+    let _flags = fields.next();
+    let quarantined_at = fields
+        .next()
+        .and_then(|stamp| u64::from_str_radix(stamp, 16).ok());
+    let agent = fields
+        .next()
+        .filter(|agent| !agent.is_empty())
+        .map(str::to_owned);
+    (agent, quarantined_at)
+}
+
+pub fn read(meta: &Value) -> Option<Quarantine> {
+    meta.get(QUARANTINE_FIELD)
+        .and_then(|field| serde_json::from_value(field.clone()).ok())
+}
+
+pub(crate) fn write(meta: &mut Value, quarantine: &Quarantine) -> Result<()> {
+    if let Some(meta) = meta.as_object_mut() {
+        meta.insert(QUARANTINE_FIELD.into(), serde_json::to_value(quarantine)?);
+    }
+    Ok(())
+}
+
+// Off macOS there are no Gatekeeper attributes to read.
+pub(crate) fn capture(path: &Path) -> BTreeMap<String, Vec<u8>> {
+    ATTRIBUTES
+        .iter()
+        .filter_map(|name| platform::get(path, name).map(|value| (name.to_string(), value)))
+        .collect()
+}
+
+// Returns whether anything was written back.
+pub(crate) fn restore(path: &Path, quarantine: &Quarantine) -> Result<bool> {
+    let attributes = quarantine.decoded()?;
+    if attributes.is_empty() || !platform::SUPPORTED {
+        return Ok(false);
+    }
+    for (name, value) in attributes {
+        platform::set(path, name, &value)
+            .map_err(|err| anyhow::anyhow!("unable to set {name} on {}: {err}", path.display()))?;
+    }
+    Ok(true)
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::CString;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    pub(super) const SUPPORTED: bool = true;
+
+    fn c_path(path: &Path) -> io::Result<CString> {
+        CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))
+    }
+
+    pub(super) fn get(path: &Path, name: &str) -> Option<Vec<u8>> {
+        let path = c_path(path).ok()?;
+        let name = CString::new(name).ok()?;
+        // SAFETY: both strings are NUL terminated; a null buffer asks for the value size only.
+        let size =
+            unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), std::ptr::null_mut(), 0, 0, 0) };
+        if size < 0 {
+            return None;
+        }
+        let mut value = vec![0u8; size as usize];
+        // SAFETY: `value` is writable for `value.len()` bytes.
+        let read = unsafe {
+            libc::getxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_mut_ptr().cast(),
+                value.len(),
+                0,
+                0,
+            )
+        };
+        if read < 0 {
+            return None;
+        }
+        value.truncate(read as usize);
+        Some(value)
+    }
+
+    pub(super) fn set(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+        let path = c_path(path)?;
+        let name = CString::new(name).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+        // SAFETY: both strings are NUL terminated and `value` is readable for its length.
+        let status = unsafe {
+            libc::setxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_ptr().cast(),
+                value.len(),
+                0,
+                0,
+            )
+        };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use std::io;
+    use std::path::Path;
+
+    pub(super) const SUPPORTED: bool = false;
+
+    pub(super) fn get(_path: &Path, _name: &str) -> Option<Vec<u8>> {
+        None
+    }
+
+    pub(super) fn set(_path: &Path, _name: &str, _value: &[u8]) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+}
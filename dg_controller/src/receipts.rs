@@ -0,0 +1,94 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use dg_core::api::Envelope;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+pub const READ_RECEIPTS_FIELD: &str = "read_receipts";
+pub const RECEIPT_EVENT: &str = "envelope.decrypted";
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadReceipt {
+    pub event: String,
+    pub envelope: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    pub labels: Vec<String>,
+    pub subject: String,
+    pub opened_at: u64,
+}
+
+impl ReadReceipt {
+    // The signature covers the exact body bytes, so sinks must post them unchanged.
+    pub fn sign(&self, secret: &[u8]) -> Result<SignedReceipt> {
+        let body = serde_json::to_vec(self)?;
+        let mut mac = HmacSha256::new_from_slice(secret)
+            .map_err(|_| anyhow::anyhow!("read receipt secret is unusable"))?;
+        mac.update(&body);
+        Ok(SignedReceipt {
+            body,
+            signature: hex::encode(mac.finalize().into_bytes()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedReceipt {
+    pub body: Vec<u8>,
+    pub signature: String,
+}
+
+impl SignedReceipt {
+    pub fn verify(&self, secret: &[u8]) -> bool {
+        let Ok(mut mac) = HmacSha256::new_from_slice(secret) else {
+            return false;
+        };
+        mac.update(&self.body);
+        hex::decode(&self.signature).is_ok_and(|expected| mac.verify_slice(&expected).is_ok())
+    }
+}
+
+#[async_trait]
+pub trait ReceiptSink: Send + Sync {
+    async fn deliver(&self, receipt: ReadReceipt) -> Result<()>;
+}
+
+pub fn requests_receipts(meta: &Value) -> bool {
+    meta.get(READ_RECEIPTS_FIELD)
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+pub(crate) fn mark(envelope: &mut Envelope) {
+    if let Some(meta) = envelope.meta.as_object_mut() {
+        meta.insert(READ_RECEIPTS_FIELD.into(), true.into());
+    }
+}
+
+// Identifies the envelope by its ciphertext digest so the receipt never carries local paths.
+pub(crate) fn receipt_for(path: &Path, envelope: &Envelope, subject: String) -> ReadReceipt {
+    ReadReceipt {
+        event: RECEIPT_EVENT.into(),
+        envelope: hex::encode(Sha256::digest(&envelope.bytes)),
+        file_name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned()),
+        labels: envelope
+            .meta
+            .get("labels")
+            .and_then(|labels| serde_json::from_value(labels.clone()).ok())
+            .unwrap_or_default(),
+        subject,
+        opened_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default(),
+    }
+}
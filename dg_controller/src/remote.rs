@@ -0,0 +1,430 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use dg_bridge::{BridgeClient, CoreHealth, RpcRequest, HEALTH_METHOD};
+use dg_core::api::{
+    DGConfig, DGError, DGResult, DataGuardian, EncryptRequest, Envelope, LoadedProfile,
+};
+use dg_core::index::{IndexEntry, Reconciliation};
+use dg_core::path_encoding;
+use dg_core::{
+    AuditEvent, AuditFilter, DecisionExplanation, GrantAction, GrantRequest, Identity,
+    IntegrityReport, JournalAppend, KeyExpiryReport, KeyHierarchy, KeyMetadata, KeyRotation,
+    KeyShare, OfflineRequest, OfflineResponse, PolicyCacheStats, Preview, RangeVerification,
+    RekeyContext, SelfTestReport, SignedGrant, WarmupReport,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use zeroize::Zeroizing;
+
+use crate::backend::{Backend, BackendKind};
+
+pub use dg_bridge::engine::*;
+
+#[derive(Clone)]
+pub struct RemoteDaemon {
+    bridge: BridgeClient,
+    next_id: Arc<AtomicU64>,
+}
+
+impl RemoteDaemon {
+    pub fn new(bridge: BridgeClient) -> Self {
+        Self {
+            bridge,
+            next_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    pub fn bridge(&self) -> &BridgeClient {
+        &self.bridge
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> DGResult<T> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let response = self
+            .bridge
+            .send_request(RpcRequest {
+                id: format!("engine-{id}"),
+                method: method.to_owned(),
+                params: Some(params),
+            })
+            .await
+            .map_err(|err| DGError::Internal(format!("{method} unavailable: {err:#}")))?;
+        if let Some(error) = response.error {
+            return Err(error.to_dg_error(format!("{method}: {}", error.detail())));
+        }
+        serde_json::from_value(response.result.unwrap_or(Value::Null))
+            .map_err(|err| DGError::Internal(format!("invalid {method} result: {err}")))
+    }
+}
+
+impl Backend for RemoteDaemon {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Remote
+    }
+
+    fn describe(&self) -> String {
+        let endpoints: Vec<String> = self
+            .bridge
+            .endpoints()
+            .iter()
+            .map(|endpoint| endpoint.display())
+            .collect();
+        format!("remote ({})", endpoints.join(", "))
+    }
+}
+
+#[async_trait]
+impl DataGuardian for RemoteDaemon {
+    async fn init(&self, cfg: DGConfig) -> DGResult<()> {
+        self.call::<Value>(ENGINE_INIT, json!({ "config": cfg }))
+            .await
+            .map(|_| ())
+    }
+
+    async fn encrypt(&self, req: EncryptRequest) -> DGResult<Envelope> {
+        self.call::<WireEnvelope>(ENGINE_ENCRYPT, json!(WireEncryptRequest::from(&req)))
+            .await?
+            .try_into()
+    }
+
+    async fn decrypt(&self, env: Envelope) -> DGResult<Zeroizing<Vec<u8>>> {
+        let result: PlaintextResult = self
+            .call(
+                ENGINE_DECRYPT,
+                json!({ "envelope": WireEnvelope::from(&env) }),
+            )
+            .await?;
+        decode_bytes(&result.plaintext).map(Zeroizing::new)
+    }
+
+    async fn check_policy(&self, subject: &str, action: &str, resource: &str) -> DGResult<bool> {
+        let result: AllowedResult = self
+            .call(
+                ENGINE_CHECK_POLICY,
+                json!({ "subject": subject, "action": action, "resource": resource }),
+            )
+            .await?;
+        Ok(result.allowed)
+    }
+
+    async fn check_identity(
+        &self,
+        identity: &Identity,
+        action: &str,
+        resource: &str,
+    ) -> DGResult<bool> {
+        let result: AllowedResult = self
+            .call(
+                ENGINE_CHECK_POLICY,
+                json!({
+                    "subject": identity.subject(),
+                    "subjects": identity.subjects(),
+                    "action": action,
+                    "resource": resource,
+                }),
+            )
+            .await?;
+        Ok(result.allowed)
+    }
+
+    async fn explain_decision(
+        &self,
+        identity: &Identity,
+        action: &str,
+        resource: &str,
+    ) -> DGResult<DecisionExplanation> {
+        self.call(
+            ENGINE_EXPLAIN,
+            json!({
+                "subject": identity.subject(),
+                "subjects": identity.subjects(),
+                "action": action,
+                "resource": resource,
+            }),
+        )
+        .await
+    }
+
+    async fn import_private_key(&self, pem: &str, label: &str) -> DGResult<KeyMetadata> {
+        self.call(
+            ENGINE_IMPORT_PRIVATE_KEY,
+            json!({ "pem": pem, "label": label }),
+        )
+        .await
+    }
+
+    async fn import_public_key(&self, pem: &str, contact: &str) -> DGResult<KeyMetadata> {
+        self.call(
+            ENGINE_IMPORT_PUBLIC_KEY,
+            json!({ "pem": pem, "contact": contact }),
+        )
+        .await
+    }
+
+    async fn revoke_key(&self, id: &str) -> DGResult<KeyMetadata> {
+        self.call(ENGINE_REVOKE_KEY, json!({ "id": id })).await
+    }
+
+    async fn generate_identity(&self, label: &str) -> DGResult<KeyMetadata> {
+        self.call(ENGINE_GENERATE_IDENTITY, json!({ "label": label }))
+            .await
+    }
+
+    async fn generate_key_hierarchy(&self, label: &str) -> DGResult<KeyHierarchy> {
+        self.call(ENGINE_GENERATE_KEY_HIERARCHY, json!({ "label": label }))
+            .await
+    }
+
+    async fn list_identities(&self) -> DGResult<Vec<KeyMetadata>> {
+        self.call(ENGINE_LIST_IDENTITIES, json!({})).await
+    }
+
+    async fn key_expiry(&self) -> DGResult<KeyExpiryReport> {
+        self.call(ENGINE_KEY_EXPIRY, json!({})).await
+    }
+
+    async fn rotate_key(&self, id: &str) -> DGResult<KeyRotation> {
+        self.call(ENGINE_ROTATE_KEY, json!({ "id": id })).await
+    }
+
+    async fn rekey(&self, env: Envelope, context: RekeyContext) -> DGResult<Envelope> {
+        self.call::<WireEnvelope>(
+            ENGINE_REKEY,
+            json!({ "envelope": WireEnvelope::from(&env), "context": context }),
+        )
+        .await?
+        .try_into()
+    }
+
+    async fn sign_detached(&self, signer: &str, data: &[u8]) -> DGResult<Vec<u8>> {
+        let result: SignatureResult = self
+            .call(
+                ENGINE_SIGN,
+                json!({ "signer": signer, "data": encode_bytes(data) }),
+            )
+            .await?;
+        decode_bytes(&result.signature)
+    }
+
+    async fn verify_detached(&self, signer: &str, data: &[u8], signature: &[u8]) -> DGResult<bool> {
+        let result: ValidResult = self
+            .call(
+                ENGINE_VERIFY_SIGNATURE,
+                json!({
+                    "signer": signer,
+                    "data": encode_bytes(data),
+                    "signature": encode_bytes(signature),
+                }),
+            )
+            .await?;
+        Ok(result.valid)
+    }
+
+    async fn self_test(&self) -> DGResult<SelfTestReport> {
+        self.call(ENGINE_SELF_TEST, json!({})).await
+    }
+
+    async fn warm_up(&self) -> DGResult<WarmupReport> {
+        self.call(ENGINE_WARM_UP, json!({})).await
+    }
+
+    async fn integrity(&self, repair: bool) -> DGResult<IntegrityReport> {
+        self.call(ENGINE_INTEGRITY, json!({ "repair": repair }))
+            .await
+    }
+
+    async fn collect_share(&self, env: &Envelope) -> DGResult<Vec<KeyShare>> {
+        let result: SharesResult = self
+            .call(
+                ENGINE_COLLECT_SHARE,
+                json!({ "envelope": WireEnvelope::from(env) }),
+            )
+            .await?;
+        Ok(result.shares)
+    }
+
+    async fn combine_shares(
+        &self,
+        env: Envelope,
+        shares: Vec<KeyShare>,
+    ) -> DGResult<Zeroizing<Vec<u8>>> {
+        let result: PlaintextResult = self
+            .call(
+                ENGINE_COMBINE_SHARES,
+                json!({ "envelope": WireEnvelope::from(&env), "shares": shares }),
+            )
+            .await?;
+        decode_bytes(&result.plaintext).map(Zeroizing::new)
+    }
+
+    async fn issue_grant(&self, env: &Envelope, request: GrantRequest) -> DGResult<SignedGrant> {
+        self.call(
+            ENGINE_ISSUE_GRANT,
+            json!({ "envelope": WireEnvelope::from(env), "request": request }),
+        )
+        .await
+    }
+
+    async fn decrypt_with_grant(
+        &self,
+        env: Envelope,
+        grant: SignedGrant,
+        action: GrantAction,
+    ) -> DGResult<Zeroizing<Vec<u8>>> {
+        let result: PlaintextResult = self
+            .call(
+                ENGINE_DECRYPT_WITH_GRANT,
+                json!({ "envelope": WireEnvelope::from(&env), "grant": grant, "action": action }),
+            )
+            .await?;
+        decode_bytes(&result.plaintext).map(Zeroizing::new)
+    }
+
+    async fn export_offline_request(&self, env: &Envelope) -> DGResult<OfflineRequest> {
+        self.call(
+            ENGINE_EXPORT_OFFLINE_REQUEST,
+            json!({ "envelope": WireEnvelope::from(env) }),
+        )
+        .await
+    }
+
+    async fn answer_offline_request(&self, request: OfflineRequest) -> DGResult<OfflineResponse> {
+        self.call(ENGINE_ANSWER_OFFLINE_REQUEST, json!({ "request": request }))
+            .await
+    }
+
+    async fn complete_offline_request(
+        &self,
+        env: Envelope,
+        response: OfflineResponse,
+    ) -> DGResult<Zeroizing<Vec<u8>>> {
+        let result: PlaintextResult = self
+            .call(
+                ENGINE_COMPLETE_OFFLINE_REQUEST,
+                json!({ "envelope": WireEnvelope::from(&env), "response": response }),
+            )
+            .await?;
+        decode_bytes(&result.plaintext).map(Zeroizing::new)
+    }
+
+    async fn preview(&self, env: &Envelope) -> DGResult<Option<Preview>> {
+        let result: PreviewResult = self
+            .call(
+                ENGINE_PREVIEW,
+                json!({ "envelope": WireEnvelope::from(env) }),
+            )
+            .await?;
+        result.preview.map(Preview::try_from).transpose()
+    }
+
+    async fn verify_range(
+        &self,
+        env: &Envelope,
+        offset: u64,
+        len: u64,
+    ) -> DGResult<RangeVerification> {
+        self.call(
+            ENGINE_VERIFY_RANGE,
+            json!({ "envelope": WireEnvelope::from(env), "offset": offset, "len": len }),
+        )
+        .await
+    }
+
+    async fn decrypt_range(
+        &self,
+        env: &Envelope,
+        offset: u64,
+        len: u64,
+    ) -> DGResult<Zeroizing<Vec<u8>>> {
+        let result: PlaintextResult = self
+            .call(
+                ENGINE_DECRYPT_RANGE,
+                json!({ "envelope": WireEnvelope::from(env), "offset": offset, "len": len }),
+            )
+            .await?;
+        decode_bytes(&result.plaintext).map(Zeroizing::new)
+    }
+
+    async fn append(&self, env: &Envelope, data: Vec<u8>) -> DGResult<JournalAppend> {
+        let result: WireJournalAppend = self
+            .call(
+                ENGINE_APPEND,
+                json!({ "envelope": WireEnvelope::from(env), "data": encode_bytes(&data) }),
+            )
+            .await?;
+        JournalAppend::try_from(result)
+    }
+
+    async fn index_entries(&self) -> DGResult<Vec<IndexEntry>> {
+        self.call(ENGINE_INDEX_ENTRIES, json!({})).await
+    }
+
+    async fn index_record(&self, entry: IndexEntry) -> DGResult<()> {
+        self.call::<Value>(ENGINE_INDEX_RECORD, json!({ "entry": entry }))
+            .await
+            .map(|_| ())
+    }
+
+    async fn index_remove(&self, path: &Path) -> DGResult<()> {
+        self.call::<Value>(
+            ENGINE_INDEX_REMOVE,
+            json!({ "path": path_encoding::encode(path) }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn index_reconcile(
+        &self,
+        roots: Vec<PathBuf>,
+        observed: Vec<IndexEntry>,
+    ) -> DGResult<Reconciliation> {
+        let roots: Vec<String> = roots
+            .iter()
+            .map(|root| path_encoding::encode(root))
+            .collect();
+        self.call(
+            ENGINE_INDEX_RECONCILE,
+            json!({ "roots": roots, "observed": observed }),
+        )
+        .await
+    }
+
+    async fn reload_policy(&self) -> DGResult<PolicyCacheStats> {
+        self.call(ENGINE_RELOAD_POLICY, json!({})).await
+    }
+
+    async fn policy_cache_stats(&self) -> DGResult<PolicyCacheStats> {
+        self.call(ENGINE_POLICY_CACHE, json!({})).await
+    }
+
+    async fn query_audit(&self, filter: AuditFilter) -> DGResult<Vec<AuditEvent>> {
+        self.call(ENGINE_QUERY_AUDIT, json!({ "filter": filter }))
+            .await
+    }
+
+    async fn loaded_profile(&self) -> DGResult<Option<LoadedProfile>> {
+        self.call::<CoreHealth>(HEALTH_METHOD, json!({}))
+            .await
+            .map(|health| health.profile)
+    }
+
+    async fn lock(&self) -> DGResult<()> {
+        self.call::<Value>(ENGINE_LOCK, json!({})).await.map(|_| ())
+    }
+
+    async fn unlock(&self, passphrase: &str) -> DGResult<()> {
+        self.call::<Value>(ENGINE_UNLOCK, json!({ "passphrase": passphrase }))
+            .await
+            .map(|_| ())
+    }
+
+    async fn shutdown(&self) -> DGResult<()> {
+        self.call::<Value>(ENGINE_SHUTDOWN, json!({}))
+            .await
+            .map(|_| ())
+    }
+}
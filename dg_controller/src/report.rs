@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use dg_core::audit::AuditEvent;
+use dg_core::index::IndexEntry;
+use dg_core::{CustomMetadata, MetadataFilter};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::paths::display_path;
+use crate::stored::StoredEnvelope;
+
+// The audit action recorded when a file is encrypted; its resource is the plaintext path.
+pub const ENCRYPT_ACTION: &str = "encrypt";
+const CSV_COLUMNS: [&str; 9] = [
+    "path",
+    "original_path",
+    "labels",
+    "key_ids",
+    "recipients",
+    "encrypted_by",
+    "encrypted_at",
+    "indexed_at",
+    "metadata",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+impl ReportFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| ext.to_ascii_lowercase().parse().ok())
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "csv",
+            ReportFormat::Json => "json",
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            other => Err(format!(
+                "unknown report format '{other}'; expected csv or json"
+            )),
+        }
+    }
+}
+
+// Every set field has to match. Times are unix seconds and apply to when the envelope was
+// encrypted, or indexed when the audit log has no record of it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReportFilter {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    // `field=value` or a bare `field`, as for index searches.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub metadata: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encrypted_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportRow {
+    pub path: PathBuf,
+    pub original_path: Option<String>,
+    pub labels: Vec<String>,
+    pub key_ids: Vec<String>,
+    pub recipients: Vec<String>,
+    pub encrypted_by: Option<String>,
+    pub encrypted_at: Option<u64>,
+    pub indexed_at: u64,
+    pub metadata: CustomMetadata,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EnvelopeReport {
+    pub generated_at: u64,
+    pub filter: ReportFilter,
+    pub envelopes: Vec<ReportRow>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedReport {
+    pub path: PathBuf,
+    pub format: ReportFormat,
+    pub envelopes: usize,
+}
+
+impl EnvelopeReport {
+    // `encryptions` are the allowed encrypt events from the audit log, in any order.
+    pub async fn build(
+        entries: Vec<IndexEntry>,
+        encryptions: &[AuditEvent],
+        filter: ReportFilter,
+        generated_at: u64,
+    ) -> Result<Self> {
+        let metadata_filters = filter
+            .metadata
+            .iter()
+            .map(|filter| MetadataFilter::parse(filter))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| anyhow!("{err}"))?;
+        // Audit resources are canonical paths; envelopes record the display form.
+        let mut by_source: HashMap<String, Vec<&AuditEvent>> = HashMap::new();
+        for event in encryptions {
+            let source = display_path(Path::new(&event.resource));
+            by_source
+                .entry(source.to_string_lossy().into_owned())
+                .or_default()
+                .push(event);
+        }
+
+        let mut envelopes = Vec::new();
+        for entry in entries {
+            if !filter
+                .labels
+                .iter()
+                .all(|label| entry.labels.contains(label))
+                || !metadata_filters
+                    .iter()
+                    .all(|filter| filter.matches(&entry.metadata))
+            {
+                continue;
+            }
+            // An envelope that is gone or unreadable is still reported from its index entry.
+            let stored = StoredEnvelope::read(&entry.path).await.ok();
+            let original_path = stored
+                .as_ref()
+                .and_then(|stored| stored.original_path.clone());
+            let recipients = stored
+                .as_ref()
+                .map(|stored| strings(&stored.meta, "recipients"))
+                .unwrap_or_default();
+            // The latest encryption of the source before the envelope was indexed produced it.
+            let encryption = original_path
+                .as_deref()
+                .and_then(|source| by_source.get(source))
+                .and_then(|events| {
+                    events
+                        .iter()
+                        .filter(|event| event.timestamp <= entry.updated_at)
+                        .max_by_key(|event| event.timestamp)
+                });
+            let row = ReportRow {
+                path: entry.path,
+                original_path,
+                labels: entry.labels,
+                key_ids: entry.key_ids,
+                recipients,
+                encrypted_by: encryption.map(|event| event.subject.clone()),
+                encrypted_at: encryption.map(|event| event.timestamp),
+                indexed_at: entry.updated_at,
+                metadata: entry.metadata,
+            };
+            if filter.admits(&row) {
+                envelopes.push(row);
+            }
+        }
+        envelopes.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(Self {
+            generated_at,
+            filter,
+            envelopes,
+        })
+    }
+
+    pub fn render(&self, format: ReportFormat) -> Result<Vec<u8>> {
+        match format {
+            ReportFormat::Json => Ok(serde_json::to_vec_pretty(self)?),
+            ReportFormat::Csv => {
+                let mut out = CSV_COLUMNS.join(",");
+                out.push_str("\r\n");
+                for row in &self.envelopes {
+                    let fields = [
+                        csv_text(&row.path.to_string_lossy()),
+                        csv_text(row.original_path.as_deref().unwrap_or_default()),
+                        csv_text(&row.labels.join(";")),
+                        csv_text(&row.key_ids.join(";")),
+                        csv_text(&row.recipients.join(";")),
+                        csv_text(row.encrypted_by.as_deref().unwrap_or_default()),
+                        row.encrypted_at
+                            .map(|at| at.to_string())
+                            .unwrap_or_default(),
+                        row.indexed_at.to_string(),
+                        if row.metadata.is_empty() {
+                            String::new()
+                        } else {
+                            csv_text(&serde_json::to_string(&row.metadata)?)
+                        },
+                    ];
+                    out.push_str(&fields.join(","));
+                    out.push_str("\r\n");
+                }
+                Ok(out.into_bytes())
+            }
+        }
+    }
+}
+
+impl ReportFilter {
+    fn admits(&self, row: &ReportRow) -> bool {
+        let at = row.encrypted_at.unwrap_or(row.indexed_at);
+        self.encrypted_by
+            .as_deref()
+            .is_none_or(|subject| row.encrypted_by.as_deref() == Some(subject))
+            && self.since.is_none_or(|since| at >= since)
+            && self.until.is_none_or(|until| at <= until)
+    }
+}
+
+// Quotes fields that need it, and defuses values a spreadsheet would run as a formula.
+fn csv_text(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{value}")
+    } else {
+        value.to_owned()
+    };
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+fn strings(meta: &Value, field: &str) -> Vec<String> {
+    meta.get(field)
+        .and_then(Value::as_array)
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_owned)
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,68 @@
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Result;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoredFile {
+    pub envelope: PathBuf,
+    pub output: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreFailure {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DirectoryDecryptReport {
+    pub root: PathBuf,
+    pub out_dir: PathBuf,
+    pub scanned: usize,
+    pub restored: Vec<RestoredFile>,
+    pub failures: Vec<RestoreFailure>,
+}
+
+impl DirectoryDecryptReport {
+    pub fn succeeded(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+pub(crate) fn original_name(original_path: &str) -> Option<&str> {
+    let name = original_path.rsplit(['/', '\\']).next()?;
+    let unsafe_name = name.is_empty()
+        || name == "."
+        || name == ".."
+        || name.contains([':', '\0', char::REPLACEMENT_CHARACTER]);
+    (!unsafe_name).then_some(name)
+}
+
+pub(crate) fn restore_target(
+    root: &Path,
+    out_dir: &Path,
+    envelope: &Path,
+    file_name: &OsStr,
+) -> Result<PathBuf> {
+    let parent = envelope.parent().unwrap_or(root);
+    let relative = parent
+        .strip_prefix(root)
+        .map_err(|_| anyhow::anyhow!("{} is outside {}", envelope.display(), root.display()))?;
+    let mut target = out_dir.to_path_buf();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => target.push(part),
+            Component::CurDir => {}
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "refusing to restore {} outside {}",
+                    envelope.display(),
+                    out_dir.display()
+                ))
+            }
+        }
+    }
+    Ok(target.join(file_name))
+}
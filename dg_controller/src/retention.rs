@@ -0,0 +1,132 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const RETENTION_FIELD: &str = "retention";
+pub const YEAR: u64 = 365 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegalHold {
+    pub reason: String,
+    pub placed_by: String,
+    pub placed_at: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Retention {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+    // Deletion is refused before this time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_until: Option<u64>,
+    // The envelope is due for disposition from this time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dispose_after: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hold: Option<LegalHold>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RetentionState {
+    Held,
+    Retained,
+    Overdue,
+    Active,
+}
+
+impl Retention {
+    pub fn for_years(keep: Option<u32>, dispose: Option<u32>, now: u64) -> Self {
+        let after = |years: u32| now + u64::from(years) * YEAR;
+        Self {
+            keep_until: keep.map(after),
+            dispose_after: dispose.map(after),
+            ..Default::default()
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let (Some(keep_until), Some(dispose_after)) = (self.keep_until, self.dispose_after) {
+            if dispose_after < keep_until {
+                return Err(anyhow!(
+                    "retention cannot require disposition before the retention period ends"
+                ));
+            }
+        }
+        if self
+            .hold
+            .as_ref()
+            .is_some_and(|hold| hold.reason.trim().is_empty())
+        {
+            return Err(anyhow!("a legal hold needs a reason"));
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // A hold outranks every schedule: held envelopes are never overdue.
+    pub fn state(&self, now: u64) -> RetentionState {
+        if self.hold.is_some() {
+            RetentionState::Held
+        } else if self.keep_until.is_some_and(|until| until > now) {
+            RetentionState::Retained
+        } else if self.dispose_after.is_some_and(|after| after <= now) {
+            RetentionState::Overdue
+        } else {
+            RetentionState::Active
+        }
+    }
+
+    pub fn deletion_blocked(&self, now: u64) -> Option<String> {
+        match (self.state(now), &self.hold, self.keep_until) {
+            (RetentionState::Held, Some(hold), _) => {
+                Some(format!("it is under legal hold: {}", hold.reason))
+            }
+            (RetentionState::Retained, _, Some(until)) => {
+                Some(format!("it is retained until {until}"))
+            }
+            _ => None,
+        }
+    }
+}
+
+pub fn read(meta: &Value) -> Result<Retention> {
+    match meta.get(RETENTION_FIELD) {
+        None => Ok(Retention::default()),
+        Some(field) => serde_json::from_value(field.clone())
+            .map_err(|err| anyhow!("invalid retention attributes: {err}")),
+    }
+}
+
+pub fn write(meta: &mut Value, retention: &Retention) -> Result<()> {
+    let fields = meta
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("envelope metadata must be an object"))?;
+    if retention.is_empty() {
+        fields.remove(RETENTION_FIELD);
+    } else {
+        fields.insert(RETENTION_FIELD.into(), serde_json::to_value(retention)?);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetentionStatus {
+    pub path: PathBuf,
+    pub state: RetentionState,
+    pub retention: Retention,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RetentionReport {
+    pub checked: usize,
+    pub held: Vec<RetentionStatus>,
+    pub retained: Vec<RetentionStatus>,
+    pub overdue: Vec<RetentionStatus>,
+}
@@ -0,0 +1,86 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use rand::RngCore;
+use serde::Serialize;
+use tokio::fs;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+// The policy action checked before a plaintext source is shredded.
+pub const SHRED_ACTION: &str = "shred";
+const BLOCK: usize = 1024 * 1024;
+
+// APFS is copy-on-write, so further passes land in fresh blocks and leave the original data where
+// it was; one pass is all that overwriting can do there. Elsewhere passes alternate random data
+// and 0xff, and the last one writes zeros.
+#[cfg(target_os = "macos")]
+pub const OVERWRITE_PASSES: usize = 1;
+#[cfg(not(target_os = "macos"))]
+pub const OVERWRITE_PASSES: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ShredReport {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub passes: usize,
+}
+
+// Overwrites `path` in place `passes` times, syncing after each pass, then renames it to a random
+// name in the same directory and removes it so the original name is not left in the directory
+// either. Wear-levelled flash and copy-on-write or journaling filesystems can still keep older
+// copies of the blocks; only the envelope's encryption protects against that.
+pub async fn shred_file(path: &Path, passes: usize) -> Result<ShredReport> {
+    let metadata = fs::symlink_metadata(path)
+        .await
+        .with_context(|| format!("unable to read {}", path.display()))?;
+    if !metadata.is_file() {
+        anyhow::bail!("{} is not a regular file", path.display());
+    }
+    let bytes = metadata.len();
+    let passes = passes.max(1);
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .await
+        .with_context(|| format!("unable to overwrite {}", path.display()))?;
+    let mut block = vec![0u8; BLOCK.min(bytes as usize).max(1)];
+    for pass in 0..passes {
+        let last = pass + 1 == passes;
+        file.rewind().await?;
+        let mut remaining = bytes;
+        while remaining > 0 {
+            let len = remaining.min(block.len() as u64) as usize;
+            fill(&mut block[..len], pass, last);
+            file.write_all(&block[..len]).await?;
+            remaining -= len as u64;
+        }
+        file.sync_all()
+            .await
+            .with_context(|| format!("unable to flush {}", path.display()))?;
+    }
+    drop(file);
+
+    let hidden = path.with_file_name(format!(".dg-shred-{:016x}", rand::random::<u64>()));
+    let removed = match fs::rename(path, &hidden).await {
+        Ok(()) => hidden,
+        Err(_) => path.to_path_buf(),
+    };
+    fs::remove_file(&removed)
+        .await
+        .with_context(|| format!("unable to remove {}", path.display()))?;
+    Ok(ShredReport {
+        path: path.to_path_buf(),
+        bytes,
+        passes,
+    })
+}
+
+fn fill(block: &mut [u8], pass: usize, last: bool) {
+    if last {
+        block.fill(0);
+    } else if pass.is_multiple_of(2) {
+        rand::thread_rng().fill_bytes(block);
+    } else {
+        block.fill(0xff);
+    }
+}
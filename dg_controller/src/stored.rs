@@ -0,0 +1,109 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use dg_core::api::Envelope;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::migrate::{EnvelopeFormat, ENVELOPE_FORMAT};
+
+pub const MAX_ENVELOPE_BYTES: u64 = 1 << 30;
+// Binary envelopes carry the original path in their metadata block under this key.
+const ORIGINAL_PATH_FIELD: &str = "original_path";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredEnvelope {
+    #[serde(default)]
+    pub format: u32,
+    pub payload: String,
+    pub meta: serde_json::Value,
+    pub original_path: Option<String>,
+}
+
+impl StoredEnvelope {
+    pub fn new(
+        envelope: &Envelope,
+        meta: serde_json::Value,
+        original_path: Option<String>,
+    ) -> Self {
+        Self {
+            format: ENVELOPE_FORMAT,
+            payload: general_purpose::STANDARD.encode(&envelope.bytes),
+            meta,
+            original_path,
+        }
+    }
+
+    // Binary envelopes and the older JSON documents are both accepted.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        check_size(data.len() as u64)?;
+        if Envelope::is_binary(data) {
+            let Envelope { bytes, mut meta } = Envelope::parse(data)
+                .map_err(|err| anyhow::anyhow!("not a Data Guardian envelope: {err}"))?;
+            let original_path = meta
+                .as_object_mut()
+                .and_then(|fields| fields.remove(ORIGINAL_PATH_FIELD))
+                .and_then(|path| path.as_str().map(str::to_owned));
+            return Ok(Self {
+                format: ENVELOPE_FORMAT,
+                payload: general_purpose::STANDARD.encode(bytes),
+                meta,
+                original_path,
+            });
+        }
+        let stored: Self = serde_json::from_slice(data).context("not a Data Guardian envelope")?;
+        if !stored.meta.is_object() {
+            return Err(anyhow::anyhow!("envelope metadata must be a JSON object"));
+        }
+        Ok(stored)
+    }
+
+    pub async fn read(path: &Path) -> Result<Self> {
+        check_size(fs::metadata(path).await?.len())?;
+        Self::parse(&fs::read(path).await?)
+    }
+
+    pub fn detect_format(&self) -> Result<EnvelopeFormat> {
+        EnvelopeFormat::detect(self.format, &self.meta)
+    }
+
+    pub fn payload_bytes(&self) -> Result<Vec<u8>> {
+        general_purpose::STANDARD
+            .decode(&self.payload)
+            .map_err(|err| anyhow::anyhow!("invalid envelope payload: {err}"))
+    }
+
+    pub fn into_envelope(self) -> Result<(Envelope, Option<String>)> {
+        self.detect_format()?;
+        let envelope = Envelope {
+            bytes: self.payload_bytes()?,
+            meta: self.meta,
+        };
+        Ok((envelope, self.original_path))
+    }
+
+    // Always writes the current binary layout, whatever format the envelope was read from.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut meta = self.meta.clone();
+        if let (Some(fields), Some(path)) = (meta.as_object_mut(), &self.original_path) {
+            fields.insert(ORIGINAL_PATH_FIELD.into(), path.clone().into());
+        }
+        let envelope = Envelope {
+            bytes: self.payload_bytes()?,
+            meta,
+        };
+        envelope
+            .serialize()
+            .map_err(|err| anyhow::anyhow!("unable to encode envelope: {err}"))
+    }
+}
+
+pub(crate) fn check_size(len: u64) -> Result<()> {
+    if len > MAX_ENVELOPE_BYTES {
+        return Err(anyhow::anyhow!(
+            "envelope is {len} bytes; the limit is {MAX_ENVELOPE_BYTES}"
+        ));
+    }
+    Ok(())
+}
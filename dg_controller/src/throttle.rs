@@ -0,0 +1,192 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+pub type PowerProbe = Arc<dyn Fn() -> Pin<Box<dyn Future<Output = bool> + Send>> + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobThrottle {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes_per_second: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_parallel_files: Option<usize>,
+    pub pause_on_battery: bool,
+}
+
+impl JobThrottle {
+    pub fn validate(&self) -> Result<()> {
+        if self.bytes_per_second == Some(0) {
+            return Err(anyhow::anyhow!(
+                "job IO limit must be at least one byte per second"
+            ));
+        }
+        if self.max_parallel_files == Some(0) {
+            return Err(anyhow::anyhow!(
+                "background jobs need at least one parallel file"
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) struct Throttle {
+    settings: RwLock<JobThrottle>,
+    active: Mutex<usize>,
+    changed: Notify,
+    next_slot: tokio::sync::Mutex<Instant>,
+    power: PowerProbe,
+}
+
+impl Throttle {
+    pub fn new(power: PowerProbe, settings: JobThrottle) -> Self {
+        Self {
+            settings: RwLock::new(settings),
+            active: Mutex::new(0),
+            changed: Notify::new(),
+            next_slot: tokio::sync::Mutex::new(Instant::now()),
+            power,
+        }
+    }
+
+    pub fn settings(&self) -> JobThrottle {
+        *self
+            .settings
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn set(&self, settings: JobThrottle) -> Result<()> {
+        settings.validate()?;
+        *self
+            .settings
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = settings;
+        self.changed.notify_waiters();
+        Ok(())
+    }
+
+    pub async fn changed(&self) {
+        self.changed.notified().await
+    }
+
+    pub async fn paused_for_battery(&self) -> bool {
+        self.settings().pause_on_battery && (self.power)().await
+    }
+
+    pub async fn acquire(self: &Arc<Self>) -> ThrottlePermit {
+        loop {
+            let changed = self.changed.notified();
+            {
+                let mut active = self
+                    .active
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                if self
+                    .settings()
+                    .max_parallel_files
+                    .is_none_or(|limit| *active < limit)
+                {
+                    *active += 1;
+                    return ThrottlePermit {
+                        throttle: self.clone(),
+                    };
+                }
+            }
+            changed.await;
+        }
+    }
+
+    // Reserves the next slot of the shared byte budget, so concurrent jobs together stay under the limit.
+    pub async fn pace(&self, bytes: u64) {
+        let Some(limit) = self.settings().bytes_per_second else {
+            return;
+        };
+        let start = {
+            let mut next_slot = self.next_slot.lock().await;
+            let start = (*next_slot).max(Instant::now());
+            *next_slot = start + Duration::from_secs_f64(bytes as f64 / limit as f64);
+            start
+        };
+        tokio::time::sleep_until(start).await;
+    }
+}
+
+pub(crate) struct ThrottlePermit {
+    throttle: Arc<Throttle>,
+}
+
+impl Drop for ThrottlePermit {
+    fn drop(&mut self) {
+        let mut active = self
+            .throttle
+            .active
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        *active = active.saturating_sub(1);
+        self.throttle.changed.notify_waiters();
+    }
+}
+
+pub fn system_power() -> PowerProbe {
+    Arc::new(|| Box::pin(on_battery()))
+}
+
+#[cfg(target_os = "linux")]
+pub async fn on_battery() -> bool {
+    let Ok(mut supplies) = tokio::fs::read_dir("/sys/class/power_supply").await else {
+        return false;
+    };
+    while let Ok(Some(supply)) = supplies.next_entry().await {
+        let read = |name: &str| tokio::fs::read_to_string(supply.path().join(name));
+        let is_battery = read("type")
+            .await
+            .is_ok_and(|kind| kind.trim() == "Battery");
+        if is_battery
+            && read("status")
+                .await
+                .is_ok_and(|status| status.trim() == "Discharging")
+        {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(target_os = "macos")]
+pub async fn on_battery() -> bool {
+    tokio::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .await
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("'Battery Power'"))
+}
+
+#[cfg(windows)]
+pub async fn on_battery() -> bool {
+    // Win32_Battery.BatteryStatus 1 means the battery is discharging.
+    tokio::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "(Get-CimInstance Win32_Battery).BatteryStatus",
+        ])
+        .output()
+        .await
+        .is_ok_and(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .any(|line| line.trim() == "1")
+        })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub async fn on_battery() -> bool {
+    false
+}
@@ -0,0 +1,58 @@
+use std::io::Cursor;
+
+use dg_core::preview::MAX_PREVIEW_BYTES;
+use dg_core::Preview;
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, ImageFormat};
+
+pub const THUMBNAIL_SIZE: u32 = 256;
+const JPEG_QUALITY: u8 = 70;
+const PDF_MAGIC: &[u8] = b"%PDF-";
+
+// Images are decoded directly. PDFs use their first embedded JPEG, which is what scanners
+// produce; PDFs without one get no preview rather than a rendering dependency.
+pub fn generate(plaintext: &[u8]) -> Option<Preview> {
+    let decoded = if plaintext.starts_with(PDF_MAGIC) {
+        first_pdf_jpeg(plaintext)
+            .and_then(|jpeg| image::load_from_memory_with_format(jpeg, ImageFormat::Jpeg).ok())
+    } else {
+        match image::guess_format(plaintext) {
+            Ok(ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::WebP) => {
+                image::load_from_memory(plaintext).ok()
+            }
+            _ => None,
+        }
+    }?;
+    encode(&decoded)
+}
+
+fn encode(image: &DynamicImage) -> Option<Preview> {
+    let thumbnail = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgb8();
+    let mut data = Vec::new();
+    JpegEncoder::new_with_quality(Cursor::new(&mut data), JPEG_QUALITY)
+        .encode_image(&thumbnail)
+        .ok()?;
+    (data.len() <= MAX_PREVIEW_BYTES).then(|| Preview {
+        media_type: "image/jpeg".into(),
+        data,
+    })
+}
+
+fn first_pdf_jpeg(pdf: &[u8]) -> Option<&[u8]> {
+    let filter = find(pdf, b"/DCTDecode")?;
+    let after = &pdf[filter..];
+    let start = filter + find(after, b"stream")? + b"stream".len();
+    let body = &pdf[start..];
+    let body = body
+        .strip_prefix(b"\r\n")
+        .or_else(|| body.strip_prefix(b"\n"))
+        .unwrap_or(body);
+    let end = find(body, b"endstream")?;
+    Some(&body[..end])
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
@@ -0,0 +1,203 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, Command};
+
+const VIEWER_DIR: &str = "viewer";
+const HANDLE_POLL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+pub struct ViewerOptions {
+    pub launcher: Option<Vec<String>>,
+    pub grace: Duration,
+}
+
+impl Default for ViewerOptions {
+    fn default() -> Self {
+        Self {
+            launcher: None,
+            grace: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ViewerSession {
+    pub id: u64,
+    pub envelope: PathBuf,
+    pub path: PathBuf,
+}
+
+pub(crate) fn viewer_root(data_dir: &Path) -> PathBuf {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let shm = Path::new("/dev/shm");
+        if let (true, Ok(metadata)) = (shm.is_dir(), std::fs::metadata(data_dir)) {
+            return shm.join(format!("dg-viewer-{}", metadata.uid()));
+        }
+    }
+    data_dir.join(VIEWER_DIR)
+}
+
+pub(crate) async fn stage_plaintext(
+    root: &Path,
+    id: u64,
+    file_name: &OsStr,
+    plaintext: &[u8],
+) -> Result<PathBuf> {
+    let session_dir = root.join(id.to_string());
+    fs::create_dir_all(&session_dir)
+        .await
+        .with_context(|| format!("unable to create {}", session_dir.display()))?;
+    restrict(root, 0o700).await?;
+    restrict(&session_dir, 0o700).await?;
+
+    let path = session_dir.join(file_name);
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .await
+        .with_context(|| format!("unable to create {}", path.display()))?;
+    file.write_all(plaintext).await?;
+    file.sync_all().await?;
+    drop(file);
+    restrict(&path, 0o400).await?;
+    Ok(path)
+}
+
+pub(crate) async fn shred(path: &Path) -> Result<()> {
+    scrub(path).await?;
+    if let Some(parent) = path.parent() {
+        let _ = fs::remove_dir(parent).await;
+    }
+    Ok(())
+}
+
+// Overwrites and removes one file, leaving its directory in place.
+pub(crate) async fn scrub(path: &Path) -> Result<()> {
+    let metadata = match fs::metadata(path).await {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+    restrict(path, 0o600).await?;
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .await
+        .with_context(|| format!("unable to overwrite {}", path.display()))?;
+    file.write_all(&vec![0u8; metadata.len() as usize]).await?;
+    file.sync_all().await?;
+    drop(file);
+    fs::remove_file(path).await?;
+    Ok(())
+}
+
+pub(crate) async fn sweep(root: &Path) -> Result<usize> {
+    let mut entries = match fs::read_dir(root).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err.into()),
+    };
+    let mut removed = 0;
+    while let Some(session) = entries.next_entry().await? {
+        if !session.file_type().await?.is_dir() {
+            continue;
+        }
+        let mut files = fs::read_dir(session.path()).await?;
+        while let Some(file) = files.next_entry().await? {
+            shred(&file.path()).await?;
+            removed += 1;
+        }
+        let _ = fs::remove_dir(session.path()).await;
+    }
+    Ok(removed)
+}
+
+pub(crate) fn launch(path: &Path, launcher: Option<&[String]>) -> Result<Child> {
+    let mut command = match launcher {
+        Some([program, args @ ..]) => {
+            let mut command = Command::new(program);
+            command.args(args);
+            command
+        }
+        Some([]) => return Err(anyhow::anyhow!("viewer launcher command is empty")),
+        None => default_launcher(),
+    };
+    command
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| anyhow::anyhow!("unable to open viewer for {}: {err}", path.display()))
+}
+
+fn default_launcher() -> Command {
+    if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg("-W");
+        command
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", "", "/WAIT"]);
+        command
+    } else {
+        Command::new("xdg-open")
+    }
+}
+
+pub(crate) async fn wait_for_close(mut child: Child, path: &Path, grace: Duration) {
+    let _ = child.wait().await;
+    tokio::time::sleep(grace).await;
+    while held_open(path) {
+        tokio::time::sleep(HANDLE_POLL).await;
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn held_open(path: &Path) -> bool {
+    let Ok(processes) = std::fs::read_dir("/proc") else {
+        return false;
+    };
+    processes.flatten().any(|process| {
+        std::fs::read_dir(process.path().join("fd")).is_ok_and(|fds| {
+            fds.flatten()
+                .any(|fd| std::fs::read_link(fd.path()).is_ok_and(|target| target == path))
+        })
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn held_open(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(unix)]
+async fn restrict(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+        .await
+        .with_context(|| format!("unable to restrict {}", path.display()))
+}
+
+#[cfg(not(unix))]
+async fn restrict(path: &Path, mode: u32) -> Result<()> {
+    if path.is_file() {
+        let mut permissions = fs::metadata(path).await?.permissions();
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(mode & 0o200 == 0);
+        fs::set_permissions(path, permissions).await?;
+    }
+    Ok(())
+}
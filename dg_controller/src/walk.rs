@@ -0,0 +1,162 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{WalkBuilder, WalkState};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::task;
+
+// Per-folder exclusion file, in `.gitignore` syntax.
+pub const IGNORE_FILE: &str = ".dgignore";
+// Paths the walker may run ahead of the encryption pool before its threads block.
+const WALK_QUEUE: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WalkSettings {
+    // `.gitignore`-style patterns excluded below every folder that is walked.
+    pub exclude: Vec<String>,
+    pub include_hidden: bool,
+    pub follow_links: bool,
+    // Defaults to the number of CPUs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_files: Option<usize>,
+}
+
+impl Default for WalkSettings {
+    fn default() -> Self {
+        Self {
+            exclude: vec![
+                "node_modules/".into(),
+                "target/".into(),
+                "__pycache__/".into(),
+                "*.o".into(),
+                "*.pyc".into(),
+                "*.partial".into(),
+                "*.tmp".into(),
+            ],
+            include_hidden: false,
+            follow_links: false,
+            parallel_files: None,
+        }
+    }
+}
+
+impl WalkSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.parallel_files == Some(0) {
+            return Err(anyhow!(
+                "directory encryption needs at least one parallel file"
+            ));
+        }
+        excludes(Path::new("/"), &self.exclude).map(|_| ())
+    }
+
+    pub fn parallelism(&self) -> usize {
+        self.parallel_files.unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|count| count.get())
+                .unwrap_or(4)
+        })
+    }
+}
+
+fn excludes(root: &Path, patterns: &[String]) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .map_err(|err| anyhow!("invalid exclusion pattern '{pattern}': {err}"))?;
+    }
+    Ok(builder.build()?)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EncryptedFile {
+    pub source: PathBuf,
+    pub output: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WalkFailure {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DirectoryEncryptReport {
+    pub root: PathBuf,
+    pub scanned: usize,
+    pub encrypted: Vec<EncryptedFile>,
+    pub failures: Vec<WalkFailure>,
+}
+
+impl DirectoryEncryptReport {
+    pub fn succeeded(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+// Walks `root` on a pool of threads and streams the files to encrypt. Folders excluded by the
+// settings, `.dgignore`, `.gitignore` inside a repository or `.ignore` are never descended into, and
+// files already ending in `skip_suffix` are left out. Dropping the receiver stops the walk.
+pub fn spawn(
+    root: &Path,
+    settings: &WalkSettings,
+    skip_suffix: &str,
+) -> Result<mpsc::Receiver<std::result::Result<PathBuf, WalkFailure>>> {
+    let excludes = Arc::new(excludes(root, &settings.exclude)?);
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(!settings.include_hidden)
+        .follow_links(settings.follow_links)
+        .add_custom_ignore_filename(IGNORE_FILE)
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|kind| kind.is_dir());
+            !excludes.matched(entry.path(), is_dir).is_ignore()
+        });
+    let walker = builder.build_parallel();
+    let (sender, receiver) = mpsc::channel(WALK_QUEUE);
+    let root = root.to_path_buf();
+    let skip_suffix = skip_suffix.to_owned();
+    task::spawn_blocking(move || {
+        walker.run(|| {
+            let sender = sender.clone();
+            let root = root.clone();
+            let skip_suffix = skip_suffix.clone();
+            Box::new(move |entry| {
+                let item = match entry {
+                    Ok(entry) => {
+                        let is_file = entry.file_type().is_some_and(|kind| kind.is_file());
+                        if !is_file || entry.path().to_string_lossy().ends_with(&skip_suffix) {
+                            return WalkState::Continue;
+                        }
+                        Ok(entry.into_path())
+                    }
+                    Err(err) => Err(WalkFailure {
+                        path: failed_path(&err).unwrap_or_else(|| root.clone()),
+                        error: err.to_string(),
+                    }),
+                };
+                match sender.blocking_send(item) {
+                    Ok(()) => WalkState::Continue,
+                    Err(_) => WalkState::Quit,
+                }
+            })
+        });
+    });
+    Ok(receiver)
+}
+
+fn failed_path(err: &ignore::Error) -> Option<PathBuf> {
+    match err {
+        ignore::Error::WithPath { path, .. } => Some(path.clone()),
+        ignore::Error::WithLineNumber { err, .. } | ignore::Error::WithDepth { err, .. } => {
+            failed_path(err)
+        }
+        ignore::Error::Loop { child, .. } => Some(child.clone()),
+        _ => None,
+    }
+}
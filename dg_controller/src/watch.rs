@@ -0,0 +1,397 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use notify::event::{EventKind, ModifyKind, RenameMode};
+use notify::{Event, PollWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+pub const DEFAULT_DEBOUNCE_MS: u64 = 500;
+const MAX_DEBOUNCE_MS: u64 = 60_000;
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchSettings {
+    // A path is reported once it has been quiet for this long.
+    pub debounce_ms: u64,
+    // Globs matched against the path below the watched folder and against each name in it.
+    pub ignore: Vec<String>,
+    // Scan the folder instead of using inotify, FSEvents or ReadDirectoryChangesW; network
+    // shares often deliver no native events.
+    pub poll: bool,
+}
+
+impl Default for WatchSettings {
+    fn default() -> Self {
+        Self {
+            debounce_ms: DEFAULT_DEBOUNCE_MS,
+            ignore: vec![
+                ".*".into(),
+                "*.partial".into(),
+                "*.tmp".into(),
+                "*.swp".into(),
+                "~$*".into(),
+            ],
+            poll: false,
+        }
+    }
+}
+
+impl WatchSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.debounce_ms == 0 || self.debounce_ms > MAX_DEBOUNCE_MS {
+            return Err(anyhow!(
+                "debounce window must be between 1 and {MAX_DEBOUNCE_MS} milliseconds"
+            ));
+        }
+        compile(&self.ignore).map(|_| ())
+    }
+
+    pub fn window(&self) -> Duration {
+        Duration::from_millis(self.debounce_ms)
+    }
+}
+
+fn compile(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(
+            Glob::new(pattern)
+                .map_err(|err| anyhow!("invalid ignore pattern '{pattern}': {err}"))?,
+        );
+    }
+    Ok(builder.build()?)
+}
+
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+    root: PathBuf,
+    globs: GlobSet,
+}
+
+impl IgnoreRules {
+    pub fn new(root: &Path, patterns: &[String]) -> Result<Self> {
+        Ok(Self {
+            root: root.to_path_buf(),
+            globs: compile(patterns)?,
+        })
+    }
+
+    // Anything below an ignored folder is ignored too.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        relative
+            .ancestors()
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .any(|ancestor| {
+                self.globs.is_match(ancestor)
+                    || ancestor
+                        .file_name()
+                        .is_some_and(|name| self.globs.is_match(name))
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum WatchEvent {
+    Changed { path: PathBuf },
+    Removed { path: PathBuf },
+    Renamed { from: PathBuf, to: PathBuf },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    Changed,
+    Removed,
+    Renamed { from: PathBuf },
+}
+
+// Collapses the raw notifications for each path into one event per quiet period and pairs the
+// two halves of a rename, which inotify reports separately and links with a cookie.
+pub struct Debouncer {
+    window: Duration,
+    ignore: IgnoreRules,
+    pending: BTreeMap<PathBuf, (Change, Instant)>,
+    // Unpaired rename sources by cookie; backends without cookies use `None` for the last one.
+    renames: HashMap<Option<usize>, (PathBuf, Instant)>,
+}
+
+impl Debouncer {
+    pub fn new(window: Duration, ignore: IgnoreRules) -> Self {
+        Self {
+            window,
+            ignore,
+            pending: BTreeMap::new(),
+            renames: HashMap::new(),
+        }
+    }
+
+    pub fn push(&mut self, event: &Event, now: Instant) {
+        let cookie = event.attrs.tracker();
+        match event.kind {
+            EventKind::Access(_) | EventKind::Modify(ModifyKind::Metadata(_)) => {}
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                self.renamed(&event.paths[0], &event.paths[1], now);
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                for path in &event.paths {
+                    self.expire_rename(cookie);
+                    self.renames.insert(cookie, (path.clone(), now));
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                for path in &event.paths {
+                    match self.renames.remove(&cookie) {
+                        Some((from, _)) => self.renamed(&from, path, now),
+                        None => self.changed(path, now),
+                    }
+                }
+            }
+            EventKind::Create(_) | EventKind::Modify(_) => {
+                for path in &event.paths {
+                    self.changed(path, now);
+                }
+            }
+            EventKind::Remove(_) => {
+                for path in &event.paths {
+                    self.removed(path, now);
+                }
+            }
+            // Renames without a direction and unknown kinds are settled by looking at the disk.
+            EventKind::Any | EventKind::Other => {
+                for path in &event.paths {
+                    if path.exists() {
+                        self.changed(path, now);
+                    } else {
+                        self.removed(path, now);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending
+            .values()
+            .map(|(_, at)| *at)
+            .chain(self.renames.values().map(|(_, at)| *at))
+            .min()
+            .map(|at| at + self.window)
+    }
+
+    // Events for paths that have been quiet for the whole window, in path order.
+    pub fn ready(&mut self, now: Instant) -> Vec<WatchEvent> {
+        let expired: Vec<Option<usize>> = self
+            .renames
+            .iter()
+            .filter(|(_, (_, at))| now.duration_since(*at) >= self.window)
+            .map(|(cookie, _)| *cookie)
+            .collect();
+        for cookie in expired {
+            self.expire_rename(cookie);
+        }
+        let settled: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, (_, at))| now.duration_since(*at) >= self.window)
+            .map(|(path, _)| path.clone())
+            .collect();
+        settled
+            .into_iter()
+            .filter_map(|path| {
+                let (change, _) = self.pending.remove(&path)?;
+                Some(match change {
+                    Change::Changed => WatchEvent::Changed { path },
+                    Change::Removed => WatchEvent::Removed { path },
+                    Change::Renamed { from } => WatchEvent::Renamed { from, to: path },
+                })
+            })
+            .collect()
+    }
+
+    // The source of a rename that never found its target left the watched folder.
+    fn expire_rename(&mut self, cookie: Option<usize>) {
+        if let Some((from, at)) = self.renames.remove(&cookie) {
+            self.removed(&from, at);
+        }
+    }
+
+    fn changed(&mut self, path: &Path, now: Instant) {
+        if self.ignore.is_ignored(path) {
+            return;
+        }
+        let change = match self.pending.remove(path) {
+            Some((Change::Renamed { from }, _)) => Change::Renamed { from },
+            _ => Change::Changed,
+        };
+        self.pending.insert(path.to_path_buf(), (change, now));
+    }
+
+    fn removed(&mut self, path: &Path, now: Instant) {
+        if self.ignore.is_ignored(path) {
+            return;
+        }
+        let gone = match self.pending.remove(path) {
+            Some((Change::Renamed { from }, _)) => from,
+            _ => path.to_path_buf(),
+        };
+        self.pending.insert(gone, (Change::Removed, now));
+    }
+
+    // Saving through an ignored temporary file shows up as a change to the target.
+    fn renamed(&mut self, from: &Path, to: &Path, now: Instant) {
+        match (self.ignore.is_ignored(from), self.ignore.is_ignored(to)) {
+            (true, true) => return,
+            (true, false) => return self.changed(to, now),
+            (false, true) => return self.removed(from, now),
+            (false, false) => {}
+        }
+        let origin = match self.pending.remove(from) {
+            Some((Change::Renamed { from: origin }, _)) => origin,
+            _ => from.to_path_buf(),
+        };
+        let change = if origin == to {
+            Change::Changed
+        } else {
+            Change::Renamed { from: origin }
+        };
+        self.pending.insert(to.to_path_buf(), (change, now));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchBackend {
+    Native,
+    Poll,
+}
+
+// Watches a folder recursively until dropped. Falls back to polling when the native backend
+// cannot be set up, for example once the inotify watch limit is reached.
+pub struct FolderWatch {
+    root: PathBuf,
+    backend: WatchBackend,
+    events: mpsc::UnboundedReceiver<Vec<WatchEvent>>,
+    task: JoinHandle<()>,
+    _watcher: Box<dyn Watcher + Send>,
+}
+
+impl FolderWatch {
+    pub fn start(root: &Path, settings: &WatchSettings) -> Result<Self> {
+        settings.validate()?;
+        let root = root
+            .canonicalize()
+            .with_context(|| format!("unable to watch {}", root.display()))?;
+        let (raw_tx, raw_rx) = mpsc::unbounded_channel();
+        let handler = move |event: notify::Result<Event>| {
+            let _ = raw_tx.send(event);
+        };
+        let native = if settings.poll {
+            None
+        } else {
+            match native(&root, handler.clone()) {
+                Ok(watcher) => Some(watcher),
+                Err(err) => {
+                    warn!(root = %root.display(), error = %err, "native folder watch unavailable; polling");
+                    None
+                }
+            }
+        };
+        let (watcher, backend) = match native {
+            Some(watcher) => (watcher, WatchBackend::Native),
+            None => (
+                polling(&root, handler)
+                    .with_context(|| format!("unable to watch {}", root.display()))?,
+                WatchBackend::Poll,
+            ),
+        };
+        let debouncer = Debouncer::new(
+            settings.window(),
+            IgnoreRules::new(&root, &settings.ignore)?,
+        );
+        let (events_tx, events) = mpsc::unbounded_channel();
+        let task = tokio::spawn(debounce(debouncer, raw_rx, events_tx));
+        Ok(Self {
+            root,
+            backend,
+            events,
+            task,
+            _watcher: watcher,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn backend(&self) -> WatchBackend {
+        self.backend
+    }
+
+    // The next batch of settled changes; `None` once the watch has stopped.
+    pub async fn next(&mut self) -> Option<Vec<WatchEvent>> {
+        self.events.recv().await
+    }
+}
+
+impl Drop for FolderWatch {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn native<F>(root: &Path, handler: F) -> notify::Result<Box<dyn Watcher + Send>>
+where
+    F: notify::EventHandler,
+{
+    let mut watcher = notify::recommended_watcher(handler)?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+    Ok(Box::new(watcher))
+}
+
+fn polling<F>(root: &Path, handler: F) -> notify::Result<Box<dyn Watcher + Send>>
+where
+    F: notify::EventHandler,
+{
+    let mut watcher = PollWatcher::new(
+        handler,
+        notify::Config::default().with_poll_interval(POLL_INTERVAL),
+    )?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+    Ok(Box::new(watcher))
+}
+
+async fn debounce(
+    mut debouncer: Debouncer,
+    mut raw: mpsc::UnboundedReceiver<notify::Result<Event>>,
+    events: mpsc::UnboundedSender<Vec<WatchEvent>>,
+) {
+    loop {
+        let deadline = debouncer.next_deadline();
+        let quiet = async {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            received = raw.recv() => match received {
+                Some(Ok(event)) => debouncer.push(&event, Instant::now()),
+                Some(Err(err)) => warn!(error = %err, "folder watch error"),
+                None => break,
+            },
+            () = quiet => {}
+        }
+        let ready = debouncer.ready(Instant::now());
+        if !ready.is_empty() && events.send(ready).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const XATTRS_FIELD: &str = "xattrs";
+pub const SELINUX_ATTRIBUTE: &str = "security.selinux";
+// `security.capability` and the other `security.*` attributes are left out on purpose: envelope
+// metadata is not authenticated, so restoring them could grant privileges the source never had.
+const NAMESPACES: [&str; 2] = ["user.", "trusted."];
+
+// Extended attributes captured from a Linux source file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExtendedAttributes {
+    // Attribute name to base64 value.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selinux: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct XattrRestore {
+    pub restored: Vec<String>,
+    pub skipped: Vec<SkippedXattr>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedXattr {
+    pub name: String,
+    pub reason: String,
+}
+
+impl ExtendedAttributes {
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty() && self.selinux.is_none()
+    }
+
+    pub fn from_raw(raw: BTreeMap<String, Vec<u8>>) -> Self {
+        let mut captured = Self::default();
+        for (name, value) in raw {
+            if name == SELINUX_ATTRIBUTE {
+                let context = String::from_utf8_lossy(&value);
+                captured.selinux = Some(context.trim_end_matches('\0').to_owned());
+            } else if is_captured(&name) {
+                captured
+                    .attributes
+                    .insert(name, general_purpose::STANDARD.encode(value));
+            }
+        }
+        captured
+    }
+
+    fn decoded(&self) -> Vec<(String, Result<Vec<u8>, String>)> {
+        let attributes = self
+            .attributes
+            .iter()
+            .filter(|(name, _)| is_captured(name))
+            .map(|(name, value)| {
+                let bytes = general_purpose::STANDARD
+                    .decode(value)
+                    .map_err(|err| format!("malformed value in the envelope: {err}"));
+                (name.clone(), bytes)
+            });
+        let selinux = self.selinux.iter().map(|context| {
+            let mut value = context.clone().into_bytes();
+            value.push(0);
+            (SELINUX_ATTRIBUTE.to_owned(), Ok(value))
+        });
+        attributes.chain(selinux).collect()
+    }
+}
+
+fn is_captured(name: &str) -> bool {
+    NAMESPACES
+        .iter()
+        .any(|namespace| name.starts_with(namespace))
+}
+
+pub fn read(meta: &Value) -> Option<ExtendedAttributes> {
+    meta.get(XATTRS_FIELD)
+        .and_then(|field| serde_json::from_value(field.clone()).ok())
+}
+
+pub(crate) fn write(meta: &mut Value, attributes: &ExtendedAttributes) -> Result<()> {
+    if let Some(meta) = meta.as_object_mut() {
+        meta.insert(XATTRS_FIELD.into(), serde_json::to_value(attributes)?);
+    }
+    Ok(())
+}
+
+// Attributes the process may not read, such as `trusted.*` without CAP_SYS_ADMIN, are left out.
+pub fn capture(path: &Path) -> ExtendedAttributes {
+    ExtendedAttributes::from_raw(platform::list(path))
+}
+
+// Every attribute is attempted; the ones the kernel refuses are reported instead of failing the
+// decryption, since the plaintext has already been written.
+pub fn restore(path: &Path, attributes: &ExtendedAttributes) -> XattrRestore {
+    let mut report = XattrRestore::default();
+    for (name, value) in attributes.decoded() {
+        let outcome = value
+            .and_then(|value| platform::set(path, &name, &value).map_err(|err| describe(&err)));
+        match outcome {
+            Ok(()) => report.restored.push(name),
+            Err(reason) => report.skipped.push(SkippedXattr { name, reason }),
+        }
+    }
+    report
+}
+
+fn describe(err: &std::io::Error) -> String {
+    match err.kind() {
+        std::io::ErrorKind::PermissionDenied => {
+            "the process lacks the privilege to set it".to_owned()
+        }
+        std::io::ErrorKind::Unsupported => "the destination does not support it".to_owned(),
+        _ => err.to_string(),
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::collections::BTreeMap;
+    use std::ffi::CString;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    fn c_string(bytes: &[u8]) -> io::Result<CString> {
+        CString::new(bytes).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))
+    }
+
+    // Retries once when the attribute grew between the size query and the read.
+    fn sized(mut call: impl FnMut(&mut [u8]) -> isize) -> io::Result<Vec<u8>> {
+        for _ in 0..2 {
+            let size = call(&mut []);
+            if size < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut buffer = vec![0u8; size as usize];
+            let read = call(&mut buffer);
+            if read >= 0 {
+                buffer.truncate(read as usize);
+                return Ok(buffer);
+            }
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::ERANGE) {
+                return Err(err);
+            }
+        }
+        Err(io::Error::from_raw_os_error(libc::ERANGE))
+    }
+
+    pub(super) fn list(path: &Path) -> BTreeMap<String, Vec<u8>> {
+        let Ok(path) = c_string(path.as_os_str().as_bytes()) else {
+            return BTreeMap::new();
+        };
+        // SAFETY: `path` is NUL terminated and the buffer is writable for its length.
+        let Ok(names) = sized(|buffer| unsafe {
+            libc::listxattr(path.as_ptr(), buffer.as_mut_ptr().cast(), buffer.len())
+        }) else {
+            return BTreeMap::new();
+        };
+        names
+            .split(|byte| *byte == 0)
+            .filter(|name| !name.is_empty())
+            .filter_map(|name| {
+                let key = c_string(name).ok()?;
+                // SAFETY: both strings are NUL terminated and the buffer is writable for its length.
+                let value = sized(|buffer| unsafe {
+                    libc::getxattr(
+                        path.as_ptr(),
+                        key.as_ptr(),
+                        buffer.as_mut_ptr().cast(),
+                        buffer.len(),
+                    )
+                })
+                .ok()?;
+                Some((String::from_utf8(name.to_vec()).ok()?, value))
+            })
+            .collect()
+    }
+
+    pub(super) fn set(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+        let path = c_string(path.as_os_str().as_bytes())?;
+        let name = c_string(name.as_bytes())?;
+        // SAFETY: both strings are NUL terminated and `value` is readable for its length.
+        let status = unsafe {
+            libc::setxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_ptr().cast(),
+                value.len(),
+                0,
+            )
+        };
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use std::collections::BTreeMap;
+    use std::io;
+    use std::path::Path;
+
+    pub(super) fn list(_path: &Path) -> BTreeMap<String, Vec<u8>> {
+        BTreeMap::new()
+    }
+
+    pub(super) fn set(_path: &Path, _name: &str, _value: &[u8]) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+}
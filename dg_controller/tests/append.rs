@@ -0,0 +1,44 @@
+mod common;
+
+use anyhow::Result;
+use common::booted;
+use tempfile::tempdir;
+use tokio::fs;
+
+#[tokio::test]
+async fn appends_survive_a_torn_tail_on_disk() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let log = temp.path().join("audit.log.dg");
+    fs::create_dir(temp.path().join("out")).await?;
+
+    let created = controller.append(&log, b"start\n").await?;
+    assert!(created.created);
+    let appended = controller.append(&log, b"entry one\n").await?;
+    assert!(!appended.created);
+    assert_eq!(appended.records, 2);
+    assert_eq!(appended.plaintext_len, 16);
+    let committed = fs::read(&log).await?;
+
+    // A crash part-way through an append leaves unterminated frames behind.
+    let mut torn = committed.clone();
+    torn.extend_from_slice(&[1, 0, 0, 0, 64, 0xaa, 0xbb]);
+    fs::write(&log, &torn).await?;
+    let out = controller
+        .decrypt_file(&log, Some(temp.path().join("out")))
+        .await?;
+    assert_eq!(fs::read(&out).await?, b"start\nentry one\n");
+
+    let recovered = controller.append(&log, b"entry two\n").await?;
+    assert_eq!(recovered.discarded_bytes, 7);
+    assert_eq!(recovered.records, 3);
+    let grown = fs::read(&log).await?;
+    assert_eq!(grown[..committed.len()], committed[..]);
+    fs::remove_file(&out).await?;
+    let out = controller
+        .decrypt_file(&log, Some(temp.path().join("out")))
+        .await?;
+    assert_eq!(fs::read(&out).await?, b"start\nentry one\nentry two\n");
+    controller.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::approval::APPROVAL_ACTION;
+use dg_controller::errors::{APPROVAL_DECLINED, APPROVAL_TIMED_OUT};
+use dg_controller::{Controller, DeviceApproval, DirectoryChannel, EncryptOptions, UserError};
+use dg_core::audit::AuditFilter;
+use dg_core::pairing;
+use dg_core::{PairingOffer, UnlockApproval, UnlockChallenge};
+use tempfile::tempdir;
+use tokio::fs;
+use tokio::task::JoinHandle;
+use zeroize::Zeroizing;
+
+// Stands in for the phone app: scans the offer, then answers the next challenge it is sent.
+#[derive(Clone)]
+struct Phone {
+    relay: DirectoryChannel,
+    signing: [u8; 32],
+    channel: Zeroizing<[u8; 32]>,
+    id: String,
+}
+
+impl Phone {
+    async fn pair(relay: &DirectoryChannel, offer: &PairingOffer) -> Result<Self> {
+        let signing = rand::random::<[u8; 32]>();
+        let scanned = PairingOffer::parse_uri(&offer.to_uri())?;
+        let (response, channel) =
+            pairing::respond(&scanned, "Pixel", &signing, &rand::random::<[u8; 32]>())?;
+        relay.post_pairing_response(&scanned, &response).await?;
+        Ok(Self {
+            relay: relay.clone(),
+            signing,
+            channel,
+            id: String::new(),
+        })
+    }
+
+    fn answer_next(self, approve: bool) -> JoinHandle<Result<UnlockChallenge>> {
+        tokio::spawn(async move {
+            loop {
+                if let Some(sealed) = self.relay.pending_challenges(&self.id).await?.pop() {
+                    let challenge: UnlockChallenge = pairing::open(&self.channel, &sealed)?;
+                    let answer = UnlockApproval::sign(&challenge, approve, &self.signing)?;
+                    let reply = pairing::seal(&self.channel, &challenge.request_id, &answer)?;
+                    self.relay.answer(&self.id, reply).await?;
+                    return Ok(challenge);
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+    }
+}
+
+async fn setup(root: &Path) -> Result<(Controller, DirectoryChannel, std::path::PathBuf)> {
+    let controller = booted(root.join("data")).await?;
+    let relay = DirectoryChannel::new(root.join("relay"));
+    controller.set_device_approval(Some(DeviceApproval {
+        channel: Arc::new(relay.clone()),
+        timeout: Duration::from_secs(5),
+    }));
+    let source = root.join("payroll.csv");
+    fs::write(&source, b"alice,120000").await?;
+    let envelope = controller
+        .encrypt_file_with(&source, EncryptOptions::default())
+        .await?;
+    fs::remove_file(&source).await?;
+    Ok((controller, relay, envelope))
+}
+
+async fn paired_phone(controller: &Controller, relay: &DirectoryChannel) -> Result<Phone> {
+    let offer = controller.start_pairing().await?;
+    let mut phone = Phone::pair(relay, &offer).await?;
+    phone.id = controller.await_pairing().await?.id;
+    Ok(phone)
+}
+
+#[tokio::test]
+async fn decryption_waits_for_the_paired_phone() -> Result<()> {
+    let temp = tempdir()?;
+    let (controller, relay, envelope) = setup(temp.path()).await?;
+    let phone = paired_phone(&controller, &relay).await?;
+    assert_eq!(controller.paired_devices().await?.len(), 1);
+
+    let declining = phone.clone().answer_next(false);
+    let err = controller.decrypt_file(&envelope, None).await.unwrap_err();
+    assert_eq!(UserError::from_anyhow(&err).code, APPROVAL_DECLINED);
+    declining.await??;
+    assert!(!temp.path().join("payroll.csv").exists());
+
+    let approving = phone.answer_next(true);
+    let restored = controller.decrypt_file(&envelope, None).await?;
+    let challenge = approving.await??;
+    assert_eq!(fs::read(&restored).await?, b"alice,120000");
+    assert_eq!(challenge.file_name.as_deref(), Some("payroll.csv.dgenc"));
+
+    let audited = controller
+        .query_audit(AuditFilter {
+            action: Some(APPROVAL_ACTION.into()),
+            ..Default::default()
+        })
+        .await?;
+    let mut effects = audited
+        .iter()
+        .map(|event| event.effect.as_str())
+        .collect::<Vec<_>>();
+    effects.sort_unstable();
+    assert_eq!(effects, ["allow", "deny"]);
+    controller.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn forged_answers_are_ignored_until_the_timeout() -> Result<()> {
+    let temp = tempdir()?;
+    let (controller, relay, envelope) = setup(temp.path()).await?;
+    let phone = paired_phone(&controller, &relay).await?;
+    controller.set_device_approval(Some(DeviceApproval {
+        channel: Arc::new(relay.clone()),
+        timeout: Duration::from_secs(1),
+    }));
+
+    // Someone with the relay but without the phone's signing key answers in its place.
+    let impostor = Phone {
+        signing: rand::random::<[u8; 32]>(),
+        ..phone.clone()
+    };
+    let forged = impostor.answer_next(true);
+    let err = controller.decrypt_file(&envelope, None).await.unwrap_err();
+    assert_eq!(UserError::from_anyhow(&err).code, APPROVAL_TIMED_OUT);
+    forged.await??;
+
+    controller.unpair_device(&phone.id).await?;
+    assert!(controller.paired_devices().await?.is_empty());
+    let restored = controller.decrypt_file(&envelope, None).await?;
+    assert_eq!(fs::read(&restored).await?, b"alice,120000");
+    controller.shutdown().await?;
+    Ok(())
+}
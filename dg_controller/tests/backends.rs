@@ -0,0 +1,136 @@
+#![cfg(unix)]
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use dg_bridge::{BridgeConfig, Endpoint};
+use dg_controller::remote::WireEnvelope;
+use dg_controller::{BackendConfig, BackendKind, Controller};
+use dg_core::api::{new_default, DGConfig, DataGuardian, EncryptRequest, Envelope};
+use dg_core::RpcError;
+use serde_json::{json, Value};
+use tempfile::tempdir;
+use tokio::fs;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixListener;
+
+async fn serve_engine(listener: UnixListener, engine: Arc<dyn DataGuardian + Send + Sync>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        let engine = engine.clone();
+        tokio::spawn(async move {
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let request: Value = serde_json::from_str(&line).expect("request json");
+                let params = &request["params"];
+                let result = match request["method"].as_str().expect("method") {
+                    "engine.init" => {
+                        let cfg: DGConfig =
+                            serde_json::from_value(params["config"].clone()).expect("config");
+                        engine.init(cfg).await.map(|_| json!({ "ok": true }))
+                    }
+                    "engine.encrypt" => engine
+                        .encrypt(EncryptRequest {
+                            plaintext: general_purpose::STANDARD
+                                .decode(params["plaintext"].as_str().expect("plaintext"))
+                                .expect("base64"),
+                            labels: serde_json::from_value(params["labels"].clone())
+                                .expect("labels"),
+                            recipients: serde_json::from_value(params["recipients"].clone())
+                                .expect("recipients"),
+                            ..Default::default()
+                        })
+                        .await
+                        .map(|envelope| json!(WireEnvelope::from(&envelope))),
+                    "engine.decrypt" => {
+                        let wire: WireEnvelope =
+                            serde_json::from_value(params["envelope"].clone()).expect("envelope");
+                        let envelope = Envelope::try_from(wire).expect("payload");
+                        engine.decrypt(envelope).await.map(|plaintext| {
+                            json!({ "plaintext": general_purpose::STANDARD.encode(plaintext) })
+                        })
+                    }
+                    "engine.check_policy" => engine
+                        .check_policy(
+                            params["subject"].as_str().expect("subject"),
+                            params["action"].as_str().expect("action"),
+                            params["resource"].as_str().expect("resource"),
+                        )
+                        .await
+                        .map(|allowed| json!({ "allowed": allowed })),
+                    "engine.index_record" => engine
+                        .index_record(
+                            serde_json::from_value(params["entry"].clone()).expect("entry"),
+                        )
+                        .await
+                        .map(|_| json!({ "ok": true })),
+                    "engine.shutdown" => engine.shutdown().await.map(|_| json!({ "ok": true })),
+                    other => panic!("unexpected method {other}"),
+                };
+                let reply = match result {
+                    Ok(result) => {
+                        json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })
+                    }
+                    Err(err) => json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "error": RpcError::from(err)
+                    }),
+                };
+                let mut payload = serde_json::to_vec(&reply).expect("encode");
+                payload.push(b'\n');
+                if write.write_all(&payload).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+#[tokio::test]
+async fn remote_and_in_process_backends_share_envelopes() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    fs::create_dir_all(&data_dir).await?;
+    let socket = temp.path().join("dg-core.sock");
+    tokio::spawn(serve_engine(UnixListener::bind(&socket)?, new_default()));
+
+    let remote =
+        Controller::from_config(BackendConfig::Remote(Box::new(BridgeConfig::new(vec![
+            Endpoint::Unix(socket.clone()),
+        ]))))?;
+    assert_eq!(remote.backend_kind(), BackendKind::Remote);
+    assert!(remote
+        .describe_backend()
+        .contains(&socket.display().to_string()));
+    remote.boot("dev", data_dir.clone(), false).await?;
+
+    let source = temp.path().join("notes.txt");
+    fs::write(&source, b"shared across shells").await?;
+    let envelope = remote
+        .encrypt_file(&source, vec![], vec!["internal".into()], None)
+        .await?;
+    remote.shutdown().await?;
+
+    let local = Controller::from_config(BackendConfig::InProcess)?;
+    assert_eq!(local.backend_kind(), BackendKind::InProcess);
+    local.boot("dev", data_dir, false).await?;
+    let decrypted = local.decrypt_file(&envelope, None).await?;
+    assert_eq!(fs::read(decrypted).await?, b"shared across shells");
+    local.shutdown().await?;
+    Ok(())
+}
+
+#[test]
+fn backend_kind_parses_configuration_values() {
+    assert_eq!(
+        "in-process".parse::<BackendKind>().unwrap(),
+        BackendKind::InProcess
+    );
+    assert_eq!(
+        " Remote ".parse::<BackendKind>().unwrap(),
+        BackendKind::Remote
+    );
+    assert!("mock".parse::<BackendKind>().is_err());
+}
@@ -0,0 +1,264 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::{BackupOptions, ConflictPolicy, Controller, Job, JobSpec, TransferSettings};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tempfile::tempdir;
+use tokio::fs;
+
+fn mirrored(target: &Path, source: &Path) -> PathBuf {
+    target.join(source.strip_prefix("/").expect("absolute path"))
+}
+
+fn options(target: &Path, conflict: ConflictPolicy, dry_run: bool) -> BackupOptions {
+    BackupOptions {
+        target: target.to_path_buf(),
+        conflict,
+        dry_run,
+    }
+}
+
+async fn finished(controller: &Controller, id: u64) -> Result<Job> {
+    for _ in 0..500 {
+        let jobs = controller.list_jobs().await?;
+        if let Some(job) = jobs.into_iter().find(|job| job.id == id && job.finished()) {
+            return Ok(job);
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    anyhow::bail!("job {id} never finished")
+}
+
+#[tokio::test]
+async fn mirrors_changed_envelopes_and_reports_conflicts() -> Result<()> {
+    let temp = tempdir()?;
+    let vault = temp.path().join("vault");
+    let drive = temp.path().join("drive");
+    fs::create_dir_all(&vault).await?;
+    fs::create_dir_all(&drive).await?;
+    let drive = drive.canonicalize()?;
+
+    let controller = booted(temp.path().join("data")).await?;
+    let ledger = vault.join("ledger.csv");
+    let notes = vault.join("notes.txt");
+    fs::write(&ledger, b"q3,42").await?;
+    fs::write(&notes, b"notes").await?;
+    let ledger_env = controller
+        .encrypt_file(&ledger, vec![], vec![], None)
+        .await?;
+    let notes_env = controller
+        .encrypt_file(&notes, vec![], vec![], None)
+        .await?;
+
+    let preview = controller
+        .backup_envelopes(options(&drive, ConflictPolicy::Skip, true))
+        .await?;
+    assert_eq!(preview.copied.len(), 2);
+    assert!(!mirrored(&drive, &ledger_env).exists());
+
+    let first = controller
+        .backup_envelopes(options(&drive, ConflictPolicy::Skip, false))
+        .await?;
+    assert_eq!(first.copied.len(), 2);
+    assert!(first.succeeded());
+    assert_eq!(
+        fs::read(mirrored(&drive, &ledger_env)).await?,
+        fs::read(&ledger_env).await?
+    );
+
+    let second = controller
+        .backup_envelopes(options(&drive, ConflictPolicy::Skip, false))
+        .await?;
+    assert!(second.copied.is_empty());
+    assert_eq!(second.unchanged, 2);
+
+    fs::write(mirrored(&drive, &ledger_env), b"edited on the NAS").await?;
+    fs::write(&ledger, b"q4,7").await?;
+    controller
+        .encrypt_file(&ledger, vec![], vec![], None)
+        .await?;
+    fs::remove_file(&notes_env).await?;
+
+    let skipped = controller
+        .backup_envelopes(options(&drive, ConflictPolicy::Skip, false))
+        .await?;
+    assert!(skipped.copied.is_empty());
+    assert_eq!(skipped.conflicts.len(), 1);
+    assert_eq!(skipped.missing, vec![notes_env.clone()]);
+    assert_eq!(
+        fs::read(mirrored(&drive, &ledger_env)).await?,
+        b"edited on the NAS"
+    );
+
+    let kept = controller
+        .backup_envelopes(options(&drive, ConflictPolicy::KeepBoth, false))
+        .await?;
+    assert_eq!(kept.copied, vec![ledger_env.clone()]);
+    assert_eq!(
+        fs::read(mirrored(&drive, &ledger_env)).await?,
+        fs::read(&ledger_env).await?
+    );
+    let mut siblings = fs::read_dir(mirrored(&drive, &vault)).await?;
+    let mut conflict_copies = 0;
+    while let Some(entry) = siblings.next_entry().await? {
+        if entry.file_name().to_string_lossy().contains(".conflict-") {
+            conflict_copies += 1;
+        }
+    }
+    assert_eq!(conflict_copies, 1);
+    controller.shutdown().await?;
+    Ok(())
+}
+
+#[test]
+fn conflict_policies_parse() {
+    assert_eq!(
+        "keep-both".parse::<ConflictPolicy>().ok(),
+        Some(ConflictPolicy::KeepBoth)
+    );
+    assert!("merge".parse::<ConflictPolicy>().is_err());
+}
+
+#[test]
+fn transfer_backoff_doubles_up_to_the_cap() {
+    let settings = TransferSettings {
+        initial_backoff_ms: 100,
+        max_backoff_ms: 1_000,
+        ..Default::default()
+    };
+    let delays: Vec<_> = (0..6).map(|attempt| settings.backoff(attempt)).collect();
+    assert_eq!(
+        delays,
+        [100, 200, 400, 800, 1_000, 1_000].map(Duration::from_millis)
+    );
+    assert_eq!(settings.backoff(u32::MAX), Duration::from_millis(1_000));
+    assert!(settings.validate().is_ok());
+    assert!(TransferSettings {
+        bandwidth_limit: Some(0),
+        ..Default::default()
+    }
+    .validate()
+    .is_err());
+    assert!(TransferSettings {
+        initial_backoff_ms: 5_000,
+        max_backoff_ms: 1_000,
+        ..Default::default()
+    }
+    .validate()
+    .is_err());
+}
+
+#[tokio::test]
+async fn bandwidth_limit_paces_the_copy() -> Result<()> {
+    let temp = tempdir()?;
+    let drive = temp.path().join("drive");
+    fs::create_dir_all(&drive).await?;
+    let drive = drive.canonicalize()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let ledger = temp.path().join("ledger.csv");
+    fs::write(&ledger, vec![b'x'; 16 * 1024]).await?;
+    let envelope = controller
+        .encrypt_file(&ledger, vec![], vec![], None)
+        .await?;
+    let size = fs::metadata(&envelope).await?.len();
+
+    controller.set_transfer_settings(TransferSettings {
+        bandwidth_limit: Some(size * 4),
+        ..Default::default()
+    })?;
+    let started = Instant::now();
+    let report = controller
+        .backup_envelopes(options(&drive, ConflictPolicy::Skip, false))
+        .await?;
+    assert_eq!(report.copied, vec![envelope.clone()]);
+    assert!(started.elapsed() >= Duration::from_millis(240));
+    assert_eq!(
+        fs::read(mirrored(&drive, &envelope)).await?,
+        fs::read(&envelope).await?
+    );
+    controller.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn interrupted_sync_jobs_resume_from_their_checkpoint() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    let drive = temp.path().join("drive");
+    fs::create_dir_all(&drive).await?;
+    let drive = drive.canonicalize()?;
+    let controller = booted(data_dir.clone()).await?;
+    let ledger = temp.path().join("ledger.csv");
+    fs::write(&ledger, b"q3,42").await?;
+    let envelope = controller
+        .encrypt_file(&ledger, vec![], vec![], None)
+        .await?;
+    controller.shutdown().await?;
+
+    let bytes = fs::read(&envelope).await?;
+    let target = mirrored(&drive, &envelope);
+    let mut partial = target.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    fs::create_dir_all(target.parent().expect("parent")).await?;
+    let spec = JobSpec::Sync {
+        target: drive.clone(),
+        conflict: ConflictPolicy::Skip,
+    };
+    let checkpoint = json!({ "source": envelope, "target": target,
+        "sha256": hex::encode(Sha256::digest(&bytes)), "offset": 10 });
+    let interrupted = json!({ "id": 1, "spec": spec, "status": "running", "position": 0,
+        "total": 1, "succeeded": 0, "checkpoint": checkpoint, "created_at": 0, "updated_at": 0 });
+    fs::create_dir_all(data_dir.join("jobs")).await?;
+    fs::write(
+        data_dir.join("jobs").join("queue.json"),
+        serde_json::to_vec(&json!({ "next_id": 1, "jobs": [interrupted] }))?,
+    )
+    .await?;
+
+    // The resumed copy keeps the first ten bytes already on the drive, so a damaged
+    // prefix is caught by verification and thrown away rather than published.
+    fs::write(&partial, b"corrupted!").await?;
+    controller.boot("dev", data_dir.clone(), false).await?;
+    let damaged = finished(&controller, 1).await?;
+    assert_eq!(damaged.failures.len(), 1);
+    assert!(
+        damaged.failures[0]
+            .error
+            .contains("envelopes failed to sync"),
+        "{:?}",
+        damaged.failures
+    );
+    assert_eq!(damaged.checkpoint, None);
+    assert!(!partial.exists());
+    assert!(!target.exists());
+    controller.shutdown().await?;
+
+    let queue = fs::read(data_dir.join("jobs").join("queue.json")).await?;
+    let mut queue: serde_json::Value = serde_json::from_slice(&queue)?;
+    queue["jobs"][0]["status"] = json!("running");
+    queue["jobs"][0]["position"] = json!(0);
+    queue["jobs"][0]["failures"] = json!([]);
+    queue["jobs"][0]["checkpoint"] = checkpoint;
+    fs::write(
+        data_dir.join("jobs").join("queue.json"),
+        serde_json::to_vec(&queue)?,
+    )
+    .await?;
+    fs::write(&partial, &bytes[..10]).await?;
+    controller.boot("dev", data_dir, false).await?;
+    let resumed = finished(&controller, 1).await?;
+    assert_eq!(resumed.succeeded, 1, "{:?}", resumed.failures);
+    assert_eq!(resumed.checkpoint, None);
+    assert_eq!(fs::read(&target).await?, bytes);
+    assert!(!partial.exists());
+    controller.shutdown().await?;
+    Ok(())
+}
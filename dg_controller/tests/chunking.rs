@@ -0,0 +1,70 @@
+mod common;
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::EncryptOptions;
+use dg_core::ChunkingParams;
+use tempfile::tempdir;
+use tokio::fs;
+
+#[tokio::test]
+async fn re_encrypting_a_new_version_reports_reused_chunks() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let source = temp.path().join("ledger.bin");
+    let mut contents: Vec<u8> = (0..256 * 1024u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+        .collect();
+    fs::write(&source, &contents).await?;
+    for version in ["v1", "v2"] {
+        fs::create_dir(temp.path().join(version)).await?;
+    }
+
+    let first = controller
+        .encrypt_file_detailed(
+            &source,
+            EncryptOptions {
+                chunking: Some(ChunkingParams {
+                    min_size: 1024,
+                    avg_size: 4096,
+                    max_size: 16384,
+                }),
+                out_dir: Some(temp.path().join("v1")),
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert!(first.dedup.is_none());
+
+    contents[100_000] ^= 0xff;
+    fs::write(&source, &contents).await?;
+    let second = controller
+        .encrypt_file_detailed(
+            &source,
+            EncryptOptions {
+                previous: Some(first.output.clone()),
+                out_dir: Some(temp.path().join("v2")),
+                ..Default::default()
+            },
+        )
+        .await?;
+    let dedup = second.dedup.expect("dedup stats");
+    assert_eq!(dedup.bytes, contents.len() as u64);
+    assert!(dedup.reused_chunks > 0 && dedup.reused_chunks < dedup.chunks);
+    let checked = controller
+        .verify_range(&second.output, 100_000, 4096)
+        .await?;
+    assert!(checked.chunks >= 1 && checked.root.is_some());
+
+    fs::remove_file(&source).await?;
+    let restored = fs::read(controller.decrypt_file(&second.output, None).await?).await?;
+    assert_eq!(restored, contents);
+    for (offset, len) in [(0, 1), (99_990, 20), (250_000, 6144)] {
+        let range = controller
+            .decrypt_range(&second.output, offset, len)
+            .await?;
+        assert_eq!(*range, restored[offset as usize..(offset + len) as usize]);
+    }
+    controller.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,17 @@
+// Fixtures shared by the controller integration tests; each test crate uses only some of them.
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use dg_controller::Controller;
+use dg_core::api::new_default;
+
+pub async fn booted(data_dir: PathBuf) -> Result<Controller> {
+    boot(Controller::new(new_default()), data_dir).await
+}
+
+pub async fn boot(controller: Controller, data_dir: PathBuf) -> Result<Controller> {
+    controller.boot("dev", data_dir, false).await?;
+    Ok(controller)
+}
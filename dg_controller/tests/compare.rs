@@ -0,0 +1,82 @@
+mod common;
+
+use std::time::Duration;
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::{JobSpec, NewerCopy, NewerEvidence};
+use tempfile::tempdir;
+use tokio::fs;
+
+#[tokio::test]
+async fn copies_are_compared_by_header_provenance_and_content() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let ledger = temp.path().join("ledger.csv");
+    fs::write(&ledger, b"q3,42").await?;
+    let original = controller
+        .encrypt_file(&ledger, vec![], vec!["finance".into()], None)
+        .await?;
+    let copy = temp.path().join("copy.dgenc");
+    fs::copy(&original, &copy).await?;
+
+    let same = controller
+        .compare_envelopes(&original, &copy, false)
+        .await?;
+    assert!(same.identical_payload);
+    assert!(same.differences.is_empty());
+    assert_eq!(same.newer, NewerCopy::Same);
+    assert_eq!(same.same_content, Some(true));
+
+    let job = controller
+        .submit_job(JobSpec::Reencrypt {
+            key_id: "master".into(),
+            paths: vec![copy.canonicalize()?],
+        })
+        .await?;
+    for _ in 0..500 {
+        let jobs = controller.list_jobs().await?;
+        if jobs
+            .iter()
+            .any(|candidate| candidate.id == job.id && candidate.finished())
+        {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let rekeyed = controller
+        .compare_envelopes(&original, &copy, false)
+        .await?;
+    assert!(!rekeyed.identical_payload);
+    assert_eq!(rekeyed.differences, vec!["payload", "provenance"]);
+    assert_eq!(rekeyed.newer, NewerCopy::Second);
+    assert_eq!(rekeyed.evidence, NewerEvidence::Provenance);
+    assert_eq!(rekeyed.second.provenance.len(), 1);
+    assert_eq!(rekeyed.same_content, None);
+    let unlocked = controller.compare_envelopes(&copy, &original, true).await?;
+    assert_eq!(unlocked.newer, NewerCopy::First);
+    assert_eq!(unlocked.same_content, Some(true));
+
+    fs::write(&ledger, b"q4,7").await?;
+    fs::create_dir_all(temp.path().join("out")).await?;
+    let edited = controller
+        .encrypt_file(
+            &ledger,
+            vec![],
+            vec!["finance".into()],
+            Some(temp.path().join("out")),
+        )
+        .await?;
+    let changed = controller
+        .compare_envelopes(&original, &edited, true)
+        .await?;
+    assert_eq!(changed.same_content, Some(false));
+
+    let missing = controller
+        .compare_envelopes(&original, &temp.path().join("absent.dgenc"), false)
+        .await;
+    assert!(missing.is_err());
+    controller.shutdown().await?;
+    Ok(())
+}
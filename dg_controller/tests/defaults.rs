@@ -0,0 +1,73 @@
+mod common;
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::{AppliedDefaults, EncryptOptions, EncryptionDefaults, StoredEnvelope};
+use serde_json::json;
+use tempfile::tempdir;
+use tokio::fs;
+
+fn finance_defaults() -> EncryptionDefaults {
+    EncryptionDefaults {
+        recipients: vec!["finance-team".into()],
+        labels: vec!["finance".into()],
+    }
+}
+
+#[tokio::test]
+async fn omitted_fields_fall_back_to_profile_defaults() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let source = temp.path().join("ledger.csv");
+    fs::write(&source, b"q3,42").await?;
+
+    assert!(controller
+        .set_encryption_defaults(EncryptionDefaults {
+            recipients: vec![" ".into()],
+            ..Default::default()
+        })
+        .is_err());
+    controller.generate_identity("finance-team").await?;
+    controller.set_encryption_defaults(finance_defaults())?;
+
+    let outcome = controller
+        .encrypt_file_detailed(&source, EncryptOptions::default())
+        .await?;
+    assert_eq!(
+        outcome.defaults,
+        AppliedDefaults {
+            recipients: vec!["finance-team".into()],
+            labels: vec!["finance".into()],
+        }
+    );
+    let stored = StoredEnvelope::read(&outcome.output).await?;
+    assert_eq!(stored.meta["labels"], json!(["finance"]));
+    assert_eq!(stored.meta["recipients"], json!(["finance-team"]));
+
+    let explicit = controller
+        .encrypt_file_detailed(
+            &source,
+            EncryptOptions {
+                labels: vec!["legal".into()],
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(explicit.defaults.labels, Vec::<String>::new());
+    assert_eq!(
+        explicit.defaults.recipients,
+        vec!["finance-team".to_string()]
+    );
+    let report = serde_json::to_value(&explicit)?;
+    assert_eq!(report["defaults"]["recipients"], json!(["finance-team"]));
+    assert!(report["defaults"].get("labels").is_none());
+
+    controller.set_encryption_defaults(EncryptionDefaults::default())?;
+    let plain = controller
+        .encrypt_file_detailed(&source, EncryptOptions::default())
+        .await?;
+    assert!(plain.defaults.is_empty());
+    assert!(serde_json::to_value(&plain)?.get("defaults").is_none());
+    controller.shutdown().await?;
+    Ok(())
+}
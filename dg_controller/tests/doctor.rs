@@ -0,0 +1,91 @@
+use dg_bridge::Endpoint;
+use dg_controller::doctor::{check_binary, check_endpoint, compare_versions};
+use dg_core::Severity;
+use tempfile::tempdir;
+
+#[test]
+fn version_skew_is_graded_by_component() {
+    assert_eq!(compare_versions("0.1.3", "0.1.0").severity, Severity::Ok);
+    assert_eq!(
+        compare_versions("0.2.0", "0.1.0").severity,
+        Severity::Warning
+    );
+    assert_eq!(
+        compare_versions("v1.0.0", "0.1.0").severity,
+        Severity::Error
+    );
+}
+
+#[test]
+fn missing_core_binary_is_an_error() {
+    let temp = tempdir().expect("tempdir");
+    let binary = temp.path().join("dg");
+    assert_eq!(check_binary(&binary).severity, Severity::Error);
+
+    std::fs::write(&binary, b"#!/bin/sh\n").expect("write");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        assert_eq!(check_binary(&binary).severity, Severity::Error);
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).expect("chmod");
+    }
+    assert_eq!(check_binary(&binary).severity, Severity::Ok);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn endpoints_report_stale_sockets_and_versions() {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixListener;
+
+    let temp = tempdir().expect("tempdir");
+    let socket = temp.path().join("dg-core.sock");
+    let findings = check_endpoint(&Endpoint::Unix(socket.clone())).await;
+    assert_eq!(findings[0].severity, Severity::Warning);
+
+    drop(UnixListener::bind(&socket).expect("bind"));
+    let findings = check_endpoint(&Endpoint::Unix(socket.clone())).await;
+    assert_eq!(findings[0].severity, Severity::Error);
+    assert!(findings[0]
+        .fix
+        .as_deref()
+        .unwrap_or_default()
+        .contains("stale"));
+
+    std::fs::remove_file(&socket).expect("remove stale socket");
+    let listener = UnixListener::bind(&socket).expect("bind");
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let (read, mut write) = stream.into_split();
+                let mut lines = BufReader::new(read).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let request: serde_json::Value = serde_json::from_str(&line).expect("json");
+                    let reply = serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "result": { "ok": true, "version": "9.0.0" }
+                    });
+                    let mut payload = serde_json::to_vec(&reply).expect("encode");
+                    payload.push(b'\n');
+                    if write.write_all(&payload).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    let findings = check_endpoint(&Endpoint::Unix(socket)).await;
+    let checks = findings
+        .iter()
+        .map(|finding| (finding.check.as_str(), finding.severity))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        checks,
+        [
+            ("core-endpoint", Severity::Ok),
+            ("core-version", Severity::Error)
+        ]
+    );
+}
@@ -0,0 +1,105 @@
+use anyhow::Result;
+use dg_controller::errors::{INTERNAL, NOT_BOOTED, PATH_UNAVAILABLE, POLICY_DENIED};
+use dg_controller::{Controller, UserError};
+use dg_core::api::new_default;
+use dg_core::rpc_error::{self, RpcError, APPLICATION_ERROR, CRYPTO_FAILED, RATE_LIMITED};
+use dg_core::DGError;
+use tempfile::tempdir;
+use zeroize::Zeroizing;
+
+#[tokio::test]
+async fn controller_errors_carry_stable_codes() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = Controller::new(new_default());
+
+    let err = controller
+        .encrypt_text(Zeroizing::new("note".into()), Vec::new(), Vec::new())
+        .await
+        .expect_err("not booted");
+    let user = UserError::from_anyhow(&err);
+    assert_eq!(user.code, NOT_BOOTED);
+    assert!(user.params.is_empty());
+
+    controller
+        .boot("dev", temp.path().join("data"), false)
+        .await?;
+    let missing = temp.path().join("missing.txt");
+    let err = controller
+        .encrypt_file(&missing, Vec::new(), Vec::new(), None)
+        .await
+        .expect_err("missing file");
+    let user = UserError::from_anyhow(&err);
+    assert_eq!(user.code, PATH_UNAVAILABLE);
+    assert_eq!(user.params["path"], missing.display().to_string());
+    assert!(user.params.contains_key("reason"));
+    assert_eq!(err.to_string(), user.message());
+    Ok(())
+}
+
+#[test]
+fn engine_and_unknown_errors_are_classified() {
+    let err = anyhow::Error::from(DGError::Crypto("bad tag".into())).context("decrypt failed");
+    let user = UserError::from_anyhow(&err);
+    assert_eq!(user.code, "engine.crypto");
+    assert_eq!(
+        user.params["detail"],
+        "decrypt failed: crypto error: bad tag"
+    );
+
+    let user = UserError::from_anyhow(&anyhow::anyhow!("disk on fire"));
+    assert_eq!(user.code, INTERNAL);
+    assert_eq!(user.params["detail"], "disk on fire");
+
+    let denied = anyhow::Error::from(UserError::policy_denied("decrypt", "/tmp/a.dgenc"))
+        .context("while restoring");
+    let user = UserError::from_anyhow(&denied);
+    assert_eq!(user.code, POLICY_DENIED);
+    assert_eq!(user.params["action"], "decrypt");
+    assert_eq!(user.params["resource"], "/tmp/a.dgenc");
+}
+
+#[test]
+fn rpc_errors_share_one_code_table() {
+    let mut codes = rpc_error::ERROR_CODES.to_vec();
+    codes.sort();
+    codes.dedup_by_key(|(code, _)| *code);
+    assert_eq!(codes.len(), rpc_error::ERROR_CODES.len());
+
+    for engine in [
+        DGError::PolicyDenied("no".into()),
+        DGError::Crypto("bad tag".into()),
+        DGError::Config("missing".into()),
+        DGError::ReadOnly("archive".into()),
+        DGError::Locked("passphrase required".into()),
+        DGError::Internal("boom".into()),
+    ] {
+        let rpc = RpcError::from(&engine);
+        assert_eq!(rpc_error::kind_for(rpc.code), Some(engine.code()));
+        let wire: RpcError =
+            serde_json::from_value(serde_json::to_value(&rpc).expect("encode")).expect("decode");
+        assert_eq!(wire, rpc);
+        let restored = DGError::from(wire);
+        assert_eq!(restored.code(), engine.code());
+        assert_eq!(restored.detail(), engine.detail());
+    }
+
+    let rpc = RpcError::from(DGError::Crypto("bad tag".into()));
+    assert_eq!(rpc.code, CRYPTO_FAILED);
+    let err = anyhow::Error::from(rpc).context("decrypt rejected");
+    let user = UserError::from_anyhow(&err);
+    assert_eq!(user.code, "engine.crypto");
+    assert_eq!(user.params["detail"], "bad tag");
+
+    let denied = UserError::policy_denied("decrypt", "/tmp/a.dgenc");
+    let rpc = denied.to_rpc();
+    assert_eq!(rpc.code, rpc_error::POLICY_DENIED);
+    assert_eq!(UserError::from_rpc(&rpc), denied);
+
+    let label = UserError::new("label.invalid", "label 'x' is invalid").with_param("label", "x");
+    assert_eq!(label.to_rpc().code, APPLICATION_ERROR);
+    assert_eq!(UserError::from_rpc(&label.to_rpc()), label);
+
+    let limited = RpcError::new(RATE_LIMITED, "Rate limit exceeded");
+    assert_eq!(UserError::from_rpc(&limited).code, "rpc.rate_limited");
+    assert_eq!(DGError::from(limited).code(), "engine.internal");
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use anyhow::Result;
+use common::boot;
+use dg_controller::{Controller, ControllerEvent, EventVerbosity};
+use dg_core::api::new_default;
+use tempfile::tempdir;
+use tokio::fs;
+
+#[tokio::test]
+async fn events_survive_restart_in_a_bounded_ring() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    let controller = boot(
+        Controller::new(new_default()).with_event_capacity(3),
+        data_dir.clone(),
+    )
+    .await?;
+    for name in ["a.txt", "b.txt", "c.txt", "d.txt"] {
+        let path = temp.path().join(name);
+        fs::write(&path, name).await?;
+        controller.encrypt_file(&path, vec![], vec![], None).await?;
+    }
+    let live = controller.replay_events(None).await;
+    assert_eq!(live.len(), 3);
+    controller.shutdown().await?;
+
+    let restarted = boot(
+        Controller::new(new_default()).with_event_capacity(3),
+        data_dir.clone(),
+    )
+    .await?;
+    let replayed = restarted.replay_events(None).await;
+    assert_eq!(replayed, live);
+    assert!(replayed.windows(2).all(|pair| pair[0].seq < pair[1].seq));
+    assert!(matches!(
+        &replayed[2].event,
+        ControllerEvent::Completed(message) if message.contains("d.txt")
+    ));
+    assert_eq!(replayed[2].verbosity, EventVerbosity::Terse);
+    assert!(replayed[2]
+        .summary
+        .starts_with("Wrote encrypted envelope d.txt"));
+
+    let newer = restarted.replay_events(Some(replayed[1].seq)).await;
+    assert_eq!(newer, vec![replayed[2].clone()]);
+
+    let raw = fs::read_to_string(data_dir.join("events").join("journal.jsonl")).await?;
+    let first: serde_json::Value = serde_json::from_str(raw.lines().next().unwrap())?;
+    assert_eq!(first["kind"], "completed");
+    assert_eq!(first["summary"], replayed[0].summary);
+    assert_eq!(first["seq"], replayed[0].seq);
+    restarted.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn verbosity_filters_live_and_replayed_events() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = boot(
+        Controller::new(new_default()).with_event_verbosity(EventVerbosity::Terse),
+        temp.path().join("data"),
+    )
+    .await?;
+    let mut live = controller.subscribe();
+
+    let source = temp.path().join("quarterly report.txt");
+    fs::write(&source, b"numbers").await?;
+    let envelope = controller
+        .encrypt_file(&source, vec![], vec![], None)
+        .await?;
+    let missing = temp.path().join("missing.dgenc");
+    assert!(controller.decrypt_file(&missing, None).await.is_err());
+
+    let received = live.try_recv()?;
+    assert_eq!(
+        received.event,
+        ControllerEvent::Completed(format!("wrote encrypted envelope {}", envelope.display()))
+    );
+    assert!(live.try_recv().is_err());
+    let terse = controller.replay_events(None).await;
+    assert!(terse
+        .iter()
+        .all(|recorded| recorded.verbosity == EventVerbosity::Terse));
+
+    controller.set_event_verbosity(EventVerbosity::Verbose);
+    let verbose = controller.replay_events(None).await;
+    assert!(verbose.len() > terse.len());
+    assert!(verbose
+        .iter()
+        .any(|recorded| matches!(recorded.event, ControllerEvent::Progress(_))));
+    controller.shutdown().await?;
+    Ok(())
+}
+
+#[test]
+fn summaries_shorten_paths_and_mark_errors() {
+    let event = ControllerEvent::Step("decrypting 2/5 /home/ana/vault/q3.dgenc".into());
+    assert_eq!(event.summary(), "Decrypting 2/5 q3.dgenc");
+    assert_eq!(event.verbosity(), EventVerbosity::Verbose);
+
+    let event = ControllerEvent::Error(r"failed to decrypt C:\Users\ana\q3.dgenc: bad tag".into());
+    assert_eq!(
+        event.summary(),
+        "Error: Failed to decrypt q3.dgenc: bad tag"
+    );
+    assert_eq!(event.verbosity(), EventVerbosity::Terse);
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use anyhow::Result;
+use common::booted;
+use dg_core::audit::AuditEvent;
+use dg_core::Identity;
+use serde_json::json;
+use tempfile::tempdir;
+use tokio::fs;
+
+#[test]
+fn identity_subjects_cover_user_org_groups_and_legacy_alias() {
+    let identity = Identity::new("alice")
+        .with_organization(Some("acme".into()))
+        .with_groups(["finance".to_string(), "finance".to_string()]);
+    assert_eq!(identity.subject(), "user:alice");
+    assert_eq!(
+        identity.subjects(),
+        vec!["user:alice", "org:acme", "group:finance", "local-user"]
+    );
+}
+
+#[tokio::test]
+async fn policy_checks_use_the_controller_identity() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    fs::create_dir_all(&data_dir).await?;
+    fs::write(
+        data_dir.join("policy.json"),
+        serde_json::to_vec(&json!({
+            "default_allow": false,
+            "rules": [
+                { "subject": "user:mallory", "action": "*", "resource": "*", "effect": "deny" },
+                { "subject": "group:finance", "action": "encrypt", "resource": "*", "effect": "allow" },
+                { "subject": "system", "action": "*", "resource": "data", "effect": "allow" }
+            ]
+        }))?,
+    )
+    .await?;
+
+    let controller = booted(data_dir.clone()).await?;
+    let source = temp.path().join("ledger.csv");
+    fs::write(&source, b"q3,42").await?;
+
+    controller.set_identity(Identity::new("bob").with_groups(["finance".to_string()]));
+    controller
+        .encrypt_file(&source, vec![], vec![], None)
+        .await?;
+
+    controller.set_identity(Identity::new("mallory").with_groups(["finance".to_string()]));
+    assert!(controller
+        .encrypt_file(&source, vec![], vec![], None)
+        .await
+        .is_err());
+    controller.shutdown().await?;
+
+    let log = fs::read_to_string(data_dir.join("audit").join("audit.log")).await?;
+    let decisions: Vec<(String, String)> = log
+        .lines()
+        .map(serde_json::from_str::<AuditEvent>)
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter(|event| event.action == "encrypt" && event.subject.starts_with("user:"))
+        .map(|event| (event.subject, event.effect))
+        .collect();
+    assert_eq!(
+        decisions,
+        vec![
+            ("user:bob".to_string(), "allow".to_string()),
+            ("user:mallory".to_string(), "deny".to_string()),
+        ]
+    );
+    Ok(())
+}
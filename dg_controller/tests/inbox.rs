@@ -0,0 +1,76 @@
+mod common;
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::inbox::validate_inboxes;
+use dg_controller::InboxSettings;
+use tempfile::tempdir;
+use tokio::fs;
+
+async fn drop_settled(path: &Path, contents: &[u8]) -> Result<()> {
+    fs::write(path, contents).await?;
+    std::fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(SystemTime::now() - Duration::from_secs(60))?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn inbox_encrypts_settled_files_and_removes_the_plaintext() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let inbox = InboxSettings {
+        name: "scans".into(),
+        dir: temp.path().join("inbox"),
+        labels: vec!["internal".into()],
+        ..Default::default()
+    };
+    fs::create_dir_all(&inbox.dir).await?;
+    drop_settled(&inbox.dir.join("invoice.pdf"), b"%PDF-1.7 invoice").await?;
+    drop_settled(&inbox.dir.join(".invoice.pdf.partial"), b"%PDF").await?;
+    fs::write(inbox.dir.join("still-printing.pdf"), b"%PDF-1.7").await?;
+
+    let report = controller.process_inbox(&inbox).await?;
+    assert_eq!(report.encrypted.len(), 1);
+    assert_eq!(report.pending, 1);
+    assert!(report.failures.is_empty());
+    let output = &report.encrypted[0].output;
+    assert!(output.starts_with(inbox.dir.join("encrypted")));
+    assert!(!inbox.dir.join("invoice.pdf").exists());
+    assert!(inbox.dir.join(".invoice.pdf.partial").exists());
+    assert!(inbox.dir.join("still-printing.pdf").exists());
+
+    let restored = controller
+        .decrypt_file(output, Some(temp.path().into()))
+        .await?;
+    assert_eq!(fs::read(restored).await?, b"%PDF-1.7 invoice");
+    assert!(controller.process_inbox(&inbox).await?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn inbox_settings_need_a_name_and_a_separate_output() {
+    let inbox = InboxSettings {
+        name: "exports".into(),
+        dir: "/tmp/exports".into(),
+        ..Default::default()
+    };
+    assert!(inbox.validate().is_ok());
+    assert!(InboxSettings {
+        name: String::new(),
+        ..inbox.clone()
+    }
+    .validate()
+    .is_err());
+    assert!(validate_inboxes(&[inbox.clone(), inbox.clone()]).is_err());
+    assert!(InboxSettings {
+        out_dir: Some("/tmp/exports".into()),
+        ..inbox
+    }
+    .validate()
+    .is_err());
+}
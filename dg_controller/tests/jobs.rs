@@ -0,0 +1,233 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use common::{boot, booted};
+use dg_controller::errors::JOB_NOT_FOUND;
+use dg_controller::{Controller, Job, JobSpec, JobStatus, JobThrottle, PowerProbe, UserError};
+use dg_core::api::new_default;
+use serde_json::json;
+use tempfile::tempdir;
+use tokio::fs;
+
+async fn sources(dir: &Path, count: usize) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for position in 0..count {
+        let path = dir.join(format!("batch-{position}.txt"));
+        fs::write(&path, format!("record {position}")).await?;
+        paths.push(path.canonicalize()?);
+    }
+    Ok(paths)
+}
+
+async fn wait_for(controller: &Controller, id: u64, status: JobStatus) -> Result<Job> {
+    for _ in 0..500 {
+        let jobs = controller.list_jobs().await?;
+        if let Some(job) = jobs.into_iter().find(|job| job.id == id) {
+            if job.status == status {
+                return Ok(job);
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    anyhow::bail!("job {id} never reached {status:?}")
+}
+
+#[tokio::test]
+async fn batch_encrypt_job_runs_to_completion() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    let out_dir = temp.path().join("out");
+    fs::create_dir_all(&out_dir).await?;
+    let paths = sources(temp.path(), 3).await?;
+    let controller = booted(data_dir.clone()).await?;
+
+    let job = controller
+        .submit_job(JobSpec::Encrypt {
+            paths,
+            recipients: vec![],
+            labels: vec![],
+            out_dir: Some(out_dir.clone()),
+        })
+        .await?;
+    assert_eq!(job.total, 3);
+    let finished = wait_for(&controller, job.id, JobStatus::Completed).await?;
+    assert_eq!(finished.succeeded, 3);
+    assert!(finished.failures.is_empty());
+
+    let mut written = fs::read_dir(&out_dir).await?;
+    let mut count = 0;
+    while written.next_entry().await?.is_some() {
+        count += 1;
+    }
+    assert_eq!(count, 3);
+
+    let missing = controller.pause_job(job.id + 1).await.expect_err("unknown");
+    assert_eq!(UserError::from_anyhow(&missing).code, JOB_NOT_FOUND);
+    controller.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn incomplete_jobs_resume_on_next_boot() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    let out_dir = temp.path().join("out");
+    fs::create_dir_all(data_dir.join("jobs")).await?;
+    fs::create_dir_all(&out_dir).await?;
+    let paths = sources(temp.path(), 2).await?;
+    let spec = JobSpec::Encrypt {
+        paths,
+        recipients: vec![],
+        labels: vec![],
+        out_dir: Some(out_dir.clone()),
+    };
+    let interrupted = json!({ "id": 1, "spec": spec, "status": "running", "position": 1,
+        "total": 2, "succeeded": 1, "created_at": 0, "updated_at": 0 });
+    let paused = json!({ "id": 2, "spec": spec, "status": "paused", "position": 0,
+        "total": 2, "succeeded": 0, "created_at": 0, "updated_at": 0 });
+    fs::write(
+        data_dir.join("jobs").join("queue.json"),
+        serde_json::to_vec(&json!({ "next_id": 2, "jobs": [interrupted, paused] }))?,
+    )
+    .await?;
+
+    let controller = booted(data_dir.clone()).await?;
+    let resumed = wait_for(&controller, 1, JobStatus::Completed).await?;
+    assert_eq!(resumed.succeeded, 2);
+    assert_eq!(resumed.position, 2);
+    let jobs = controller.list_jobs().await?;
+    assert_eq!(jobs[1].status, JobStatus::Paused);
+
+    let restarted = controller.resume_job(2).await?;
+    assert_eq!(restarted.status, JobStatus::Running);
+    let finished = wait_for(&controller, 2, JobStatus::Completed).await?;
+    assert_eq!(finished.succeeded, 2);
+    controller.shutdown().await?;
+
+    controller.boot("dev", data_dir, false).await?;
+    let persisted = controller.list_jobs().await?;
+    assert!(persisted.iter().all(Job::finished));
+    controller.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn paused_jobs_stay_paused_across_restarts() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    let out_dir = temp.path().join("out");
+    fs::create_dir_all(&out_dir).await?;
+    let paths = sources(temp.path(), 20).await?;
+    let controller = booted(data_dir.clone()).await?;
+    let job = controller
+        .submit_job(JobSpec::Encrypt {
+            paths,
+            recipients: vec![],
+            labels: vec![],
+            out_dir: Some(out_dir),
+        })
+        .await?;
+    let paused = controller.pause_job(job.id).await?;
+    controller.shutdown().await?;
+
+    controller.boot("dev", data_dir, false).await?;
+    let stored = controller.list_jobs().await?.remove(0);
+    assert_eq!(stored.status, paused.status);
+    if stored.status == JobStatus::Paused {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(controller.list_jobs().await?[0].position, stored.position);
+        controller.resume_job(job.id).await?;
+    }
+    let finished = wait_for(&controller, job.id, JobStatus::Completed).await?;
+    assert_eq!(finished.succeeded, 20);
+    controller.shutdown().await?;
+    Ok(())
+}
+
+fn encrypt_into(paths: Vec<PathBuf>, out_dir: &Path) -> JobSpec {
+    JobSpec::Encrypt {
+        paths,
+        recipients: vec![],
+        labels: vec![],
+        out_dir: Some(out_dir.to_path_buf()),
+    }
+}
+
+#[tokio::test]
+async fn throttled_jobs_share_one_byte_budget() -> Result<()> {
+    let temp = tempdir()?;
+    let out_dir = temp.path().join("out");
+    fs::create_dir_all(&out_dir).await?;
+    let paths = sources(temp.path(), 4).await?;
+    let controller = booted(temp.path().join("data")).await?;
+
+    assert!(controller
+        .set_job_throttle(JobThrottle {
+            max_parallel_files: Some(0),
+            ..Default::default()
+        })
+        .is_err());
+    // Each source is 8 bytes, so at 40 bytes per second every file after the first waits 200ms.
+    controller.set_job_throttle(JobThrottle {
+        bytes_per_second: Some(40),
+        max_parallel_files: Some(1),
+        ..Default::default()
+    })?;
+    let started = Instant::now();
+    let first = controller
+        .submit_job(encrypt_into(paths[..2].to_vec(), &out_dir))
+        .await?;
+    let second = controller
+        .submit_job(encrypt_into(paths[2..].to_vec(), &out_dir))
+        .await?;
+    wait_for(&controller, first.id, JobStatus::Completed).await?;
+    wait_for(&controller, second.id, JobStatus::Completed).await?;
+    assert!(started.elapsed() >= Duration::from_millis(550));
+    assert_eq!(controller.job_throttle().bytes_per_second, Some(40));
+    controller.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn jobs_wait_for_mains_power_when_asked() -> Result<()> {
+    let temp = tempdir()?;
+    let out_dir = temp.path().join("out");
+    fs::create_dir_all(&out_dir).await?;
+    let paths = sources(temp.path(), 2).await?;
+    let battery = Arc::new(AtomicBool::new(true));
+    let probe = battery.clone();
+    let power: PowerProbe = Arc::new(move || {
+        let on_battery = probe.load(Ordering::SeqCst);
+        Box::pin(async move { on_battery })
+    });
+    let controller = boot(
+        Controller::new(new_default()).with_power_probe(power),
+        temp.path().join("data"),
+    )
+    .await?;
+    controller.set_job_throttle(JobThrottle {
+        pause_on_battery: true,
+        ..Default::default()
+    })?;
+
+    let job = controller.submit_job(encrypt_into(paths, &out_dir)).await?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let waiting = controller.list_jobs().await?.remove(0);
+    assert_eq!(waiting.status, JobStatus::Running);
+    assert_eq!(waiting.position, 0);
+
+    battery.store(false, Ordering::SeqCst);
+    controller.set_job_throttle(JobThrottle {
+        pause_on_battery: true,
+        ..Default::default()
+    })?;
+    let finished = wait_for(&controller, job.id, JobStatus::Completed).await?;
+    assert_eq!(finished.succeeded, 2);
+    controller.shutdown().await?;
+    Ok(())
+}
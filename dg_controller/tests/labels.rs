@@ -0,0 +1,101 @@
+mod common;
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::errors::{LABEL_INVALID, LABEL_UNKNOWN};
+use dg_controller::{ControllerEvent, LabelDefinition, LabelRegistry, LabelValidation, UserError};
+use tempfile::tempdir;
+use tokio::fs;
+
+fn code(err: &anyhow::Error) -> String {
+    UserError::from_anyhow(err).code
+}
+
+#[test]
+fn registry_enforces_hierarchy_and_naming() {
+    let mut registry = LabelRegistry::default();
+    assert!(registry.unknown(&["anything".into()]).is_empty());
+
+    let orphan = registry
+        .upsert(LabelDefinition::new("pii/ssn"))
+        .expect_err("orphan");
+    assert_eq!(code(&orphan), LABEL_INVALID);
+    registry
+        .upsert(LabelDefinition::new(" PII ").with_color("#d9534f"))
+        .expect("parent");
+    registry
+        .upsert(LabelDefinition::new("pii/ssn").with_description("Social security numbers"))
+        .expect("child");
+    assert_eq!(
+        registry.get("pii").and_then(|label| label.color.as_deref()),
+        Some("#d9534f")
+    );
+    assert_eq!(
+        registry
+            .children("pii")
+            .map(|label| label.name.as_str())
+            .collect::<Vec<_>>(),
+        ["pii/ssn"]
+    );
+    assert!(registry
+        .upsert(LabelDefinition::new("pii/ssn").with_color("red"))
+        .is_err());
+    assert!(registry.upsert(LabelDefinition::new("pii//ssn")).is_err());
+    assert!(registry.upsert(LabelDefinition::new("top secret")).is_err());
+
+    assert_eq!(
+        registry.unknown(&["PII/SSN".into(), "pii/snn".into()]),
+        ["pii/snn"]
+    );
+    assert!(registry.remove("pii").is_err());
+    registry.remove("pii/ssn").expect("leaf");
+    registry.remove("pii").expect("parent");
+}
+
+#[tokio::test]
+async fn encryption_checks_labels_against_the_registry() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    let source = temp.path().join("payroll.csv");
+    fs::write(&source, b"name,ssn").await?;
+    let controller = booted(data_dir.clone()).await?;
+    controller.save_label(LabelDefinition::new("pii")).await?;
+    let saved = controller
+        .save_label(LabelDefinition::new("PII/SSN").with_color("#aa0000"))
+        .await?;
+    assert_eq!(saved.name, "pii/ssn");
+
+    let mut events = controller.subscribe();
+    controller
+        .encrypt_file(&source, vec![], vec!["pii/snn".into()], None)
+        .await?;
+    let mut warned = false;
+    while let Ok(recorded) = events.try_recv() {
+        if let ControllerEvent::Progress(message) = recorded.event {
+            warned |= message.contains("pii/snn");
+        }
+    }
+    assert!(warned);
+
+    controller
+        .set_label_validation(LabelValidation::Enforce)
+        .await?;
+    let refused = controller
+        .encrypt_file(&source, vec![], vec!["pii/snn".into()], None)
+        .await
+        .expect_err("unknown label");
+    assert_eq!(code(&refused), LABEL_UNKNOWN);
+    controller
+        .encrypt_file(&source, vec![], vec!["pii/ssn".into()], None)
+        .await?;
+    controller.shutdown().await?;
+
+    controller.boot("dev", data_dir, false).await?;
+    let registry = controller.label_registry();
+    assert_eq!(registry.validation, LabelValidation::Enforce);
+    assert_eq!(registry.labels().count(), 2);
+    let registry = controller.delete_label("pii/ssn").await?;
+    assert!(registry.get("pii/ssn").is_none());
+    controller.shutdown().await?;
+    Ok(())
+}
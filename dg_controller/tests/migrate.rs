@@ -0,0 +1,115 @@
+mod common;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use common::booted;
+use dg_controller::{EnvelopeFormat, MigrationOptions, StoredEnvelope};
+use serde_json::{json, Value};
+use tempfile::tempdir;
+use tokio::fs;
+
+const MASTER_KEY: [u8; 32] = [7; 32];
+
+fn master_key_envelope(plaintext: &[u8], source: &str) -> Vec<u8> {
+    let nonce = [1u8; 12];
+    let mut payload = nonce.to_vec();
+    payload.extend(
+        Aes256Gcm::new(&MASTER_KEY.into())
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .expect("seal"),
+    );
+    serde_json::to_vec_pretty(&json!({
+        "payload": general_purpose::STANDARD.encode(payload),
+        "meta": { "labels": ["finance"], "recipients": [], "profile": "dev", "source": source },
+        "original_path": source,
+    }))
+    .expect("envelope")
+}
+
+async fn stored(path: &std::path::Path) -> Result<Value> {
+    Ok(serde_json::to_value(StoredEnvelope::read(path).await?)?)
+}
+
+#[tokio::test]
+async fn migrates_old_envelopes_in_place_and_reports_failures() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    fs::create_dir_all(data_dir.join("keys")).await?;
+    fs::write(data_dir.join("keys").join("master.key"), MASTER_KEY).await?;
+    let vault = temp.path().join("vault");
+    let nested = vault.join("2019");
+    fs::create_dir_all(&nested).await?;
+
+    let controller = booted(data_dir).await?;
+
+    let legacy = nested.join("ledger.csv.dgenc");
+    fs::write(&legacy, master_key_envelope(b"q3,42", "/old/ledger.csv")).await?;
+    let source = vault.join("notes.txt");
+    fs::write(&source, b"notes").await?;
+    let current = controller
+        .encrypt_file(&source, vec![], vec![], None)
+        .await?;
+    let mut unversioned = stored(&current).await?;
+    unversioned
+        .as_object_mut()
+        .expect("object")
+        .remove("format");
+    let unversioned_path = vault.join("unversioned.txt.dgenc");
+    fs::write(&unversioned_path, serde_json::to_vec(&unversioned)?).await?;
+    let broken = vault.join("broken.dgenc");
+    fs::write(&broken, b"not json").await?;
+
+    let preview = controller
+        .migrate_envelopes(
+            &vault,
+            MigrationOptions {
+                recursive: true,
+                dry_run: true,
+            },
+        )
+        .await?;
+    assert_eq!(preview.migrated.len(), 2);
+    assert_eq!(stored(&legacy).await?["format"], 0);
+
+    let report = controller
+        .migrate_envelopes(
+            &vault,
+            MigrationOptions {
+                recursive: true,
+                dry_run: false,
+            },
+        )
+        .await?;
+    assert_eq!(report.scanned, 4);
+    assert_eq!(report.current, 1);
+    let mut formats: Vec<_> = report.migrated.iter().map(|entry| entry.from).collect();
+    formats.sort_by_key(|format| format.as_str());
+    assert_eq!(
+        formats,
+        vec![EnvelopeFormat::MasterKey, EnvelopeFormat::Unversioned]
+    );
+    assert_eq!(report.failures.len(), 1);
+    assert!(report.failures[0].path.ends_with("broken.dgenc"));
+    assert!(!report.succeeded());
+
+    assert!(dg_core::Envelope::is_binary(&fs::read(&legacy).await?));
+    let migrated = stored(&legacy).await?;
+    assert_eq!(migrated["format"], 3);
+    assert_eq!(migrated["original_path"], "/old/ledger.csv");
+    assert_eq!(migrated["meta"]["labels"], json!(["finance"]));
+    assert!(migrated["meta"]["key_wraps"].is_array());
+    let provenance = &migrated["meta"]["provenance"];
+    assert_eq!(provenance.as_array().map(Vec::len), Some(1));
+    assert_eq!(provenance[0]["action"], "migrate");
+    assert_eq!(provenance[0]["previous_key_ids"], json!(["master"]));
+    assert_eq!(provenance[0]["to_format"], 3);
+    assert!(!nested.join("ledger.csv.dgenc.tmp").exists());
+
+    let restored = controller.decrypt_file(&legacy, None).await?;
+    assert_eq!(fs::read(restored).await?, b"q3,42");
+    assert_eq!(stored(&unversioned_path).await?["format"], 3);
+    controller.shutdown().await?;
+    Ok(())
+}
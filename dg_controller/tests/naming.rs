@@ -0,0 +1,190 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::naming::render;
+use dg_controller::{EncryptOptions, OutputRoutes, OutputRule, OutputTemplates};
+use serde_json::json;
+use tempfile::tempdir;
+use tokio::fs;
+
+const OCT_16_2026_NOON: u64 = 1_792_152_000;
+
+fn templates(encrypted: &str, decrypted: &str) -> OutputTemplates {
+    OutputTemplates {
+        encrypted: encrypted.into(),
+        decrypted: decrypted.into(),
+    }
+}
+
+#[test]
+fn tokens_render_into_a_single_file_name() -> Result<()> {
+    let source = Path::new("/vault/ledger.csv");
+    let labels = vec!["fin/q3".to_string()];
+    assert_eq!(
+        render(
+            "{stem}-{label}-{timestamp}.{ext}.dgenc",
+            source,
+            &labels,
+            OCT_16_2026_NOON
+        )?,
+        "ledger-fin-q3-20261016T120000Z.csv.dgenc"
+    );
+    assert_eq!(
+        render("{name}.dgenc", Path::new("README"), &[], 0)?,
+        "README.dgenc"
+    );
+    assert_eq!(
+        render("{label}_{stem}", source, &[], 0)?,
+        "unlabeled_ledger"
+    );
+
+    for escaping in ["../{name}", "out/{name}", "{name}\\x", "..", "C:{name}"] {
+        assert!(render(escaping, source, &[], 0).is_err(), "{escaping}");
+    }
+    assert!(render("{owner}.dgenc", source, &[], 0).is_err());
+    assert!(render("{name.dgenc", source, &[], 0).is_err());
+    assert!(templates("{name}", "{name}.out").validate().is_err());
+    assert!(OutputTemplates::default().validate().is_ok());
+    Ok(())
+}
+
+#[tokio::test]
+async fn controller_names_outputs_from_templates() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let source = temp.path().join("ledger.csv");
+    fs::write(&source, b"q3,42").await?;
+
+    let default = controller
+        .encrypt_file(&source, vec![], vec![], None)
+        .await?;
+    assert_eq!(default, temp.path().join("ledger.csv.dgenc"));
+
+    assert!(controller
+        .set_output_templates(templates("../{name}", "{name}.dg"))
+        .is_err());
+    controller.set_output_templates(templates("{label}-{stem}.enc", "{stem}.plain"))?;
+    let out_dir = temp.path().join("out");
+    fs::create_dir_all(&out_dir).await?;
+    let envelope = controller
+        .encrypt_file_with(
+            &source,
+            EncryptOptions {
+                labels: vec!["finance".into()],
+                out_dir: Some(out_dir.clone()),
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(envelope, out_dir.join("finance-ledger.enc"));
+
+    let restored = controller.decrypt_file(&envelope, None).await?;
+    assert_eq!(restored, out_dir.join("finance-ledger.plain"));
+    assert_eq!(fs::read(&restored).await?, b"q3,42");
+    controller.shutdown().await?;
+    Ok(())
+}
+
+fn routes(rules: &[(&str, &Path)]) -> OutputRoutes {
+    OutputRoutes {
+        rules: rules
+            .iter()
+            .map(|(label, directory)| OutputRule {
+                label: label.to_string(),
+                directory: directory.to_path_buf(),
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn the_most_specific_route_wins() {
+    let finance = PathBuf::from("/vaults/finance");
+    let payroll = PathBuf::from("/vaults/payroll");
+    let table = routes(&[("finance", &finance), ("Finance/Payroll", &payroll)]);
+    let labels = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        table.directory_for(&labels(&["finance"])),
+        Some(finance.as_path())
+    );
+    assert_eq!(
+        table.directory_for(&labels(&["finance/q3"])),
+        Some(finance.as_path())
+    );
+    assert_eq!(
+        table.directory_for(&labels(&["finance", "finance/payroll/2026"])),
+        Some(payroll.as_path())
+    );
+    assert_eq!(table.directory_for(&labels(&["financial"])), None);
+    assert_eq!(table.directory_for(&[]), None);
+
+    assert!(table.validate().is_ok());
+    assert!(routes(&[("finance", Path::new("vaults/finance"))])
+        .validate()
+        .is_err());
+    assert!(routes(&[("/", &finance)]).validate().is_err());
+}
+
+#[tokio::test]
+async fn labeled_files_land_in_their_routed_directory() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    fs::create_dir_all(&data_dir).await?;
+    fs::write(
+        data_dir.join("policy.json"),
+        serde_json::to_vec(&json!({
+            "default_allow": true,
+            "rules": [
+                { "subject": "*", "action": "route_output", "resource": "*restricted*", "effect": "deny" }
+            ]
+        }))?,
+    )
+    .await?;
+    let controller = booted(data_dir).await?;
+    let source = temp.path().join("ledger.csv");
+    fs::write(&source, b"q3,42").await?;
+
+    let vault = temp.path().join("vaults").join("finance");
+    let restricted = temp.path().join("restricted");
+    fs::create_dir_all(&vault).await?;
+    controller.set_output_routes(routes(&[("finance", &vault), ("legal", &restricted)]))?;
+
+    let finance = |out_dir: Option<PathBuf>, label: &str| EncryptOptions {
+        labels: vec![label.into()],
+        out_dir,
+        ..Default::default()
+    };
+    let routed = controller
+        .encrypt_file_with(&source, finance(None, "finance/q3"))
+        .await?;
+    assert_eq!(routed, vault.join("ledger.csv.dgenc"));
+
+    let explicit = temp.path().join("out");
+    fs::create_dir_all(&explicit).await?;
+    let chosen = controller
+        .encrypt_file_with(&source, finance(Some(explicit.clone()), "finance"))
+        .await?;
+    assert_eq!(chosen, explicit.join("ledger.csv.dgenc"));
+
+    assert!(controller
+        .encrypt_file_with(&source, finance(None, "legal"))
+        .await
+        .is_err());
+    assert!(!fs::try_exists(restricted.join("ledger.csv.dgenc")).await?);
+
+    let unrouted = controller
+        .encrypt_file_with(&source, finance(None, "hr"))
+        .await?;
+    assert_eq!(unrouted, temp.path().join("ledger.csv.dgenc"));
+    controller.shutdown().await?;
+    Ok(())
+}
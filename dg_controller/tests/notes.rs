@@ -0,0 +1,46 @@
+mod common;
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::StoredEnvelope;
+use tempfile::tempdir;
+use tokio::fs;
+use zeroize::Zeroizing;
+
+#[tokio::test]
+async fn text_round_trips_without_a_source_file() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    let controller = booted(data_dir.clone()).await?;
+
+    let secret = "wifi: correct horse battery staple";
+    let first = controller
+        .encrypt_text(
+            Zeroizing::new(secret.to_string()),
+            vec![],
+            vec!["secret".into()],
+        )
+        .await?;
+    let second = controller
+        .encrypt_text(Zeroizing::new("second".to_string()), vec![], vec![])
+        .await?;
+    assert_ne!(first, second);
+    assert!(first.starts_with(data_dir.join("notes")));
+
+    let stored = StoredEnvelope::read(&first).await?;
+    assert!(stored.original_path.is_none());
+    assert_eq!(stored.meta["content_type"], "text/plain; charset=utf-8");
+    assert!(!String::from_utf8_lossy(&fs::read(&first).await?).contains("correct horse"));
+
+    let text = controller.decrypt_to_text(&first).await?;
+    assert_eq!(text.as_str(), secret);
+
+    let binary = temp.path().join("image.bin");
+    fs::write(&binary, [0xff, 0xfe, 0x00]).await?;
+    let envelope = controller
+        .encrypt_file(&binary, vec![], vec![], None)
+        .await?;
+    assert!(controller.decrypt_to_text(&envelope).await.is_err());
+    controller.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,194 @@
+mod common;
+
+use std::time::Duration;
+
+use anyhow::Result;
+use common::{boot, booted};
+use dg_controller::errors::{
+    OPERATION_CANCELLED, OPERATION_CANCELLED_BY_USER, OPERATION_NOT_FOUND, SHUTTING_DOWN,
+};
+use dg_controller::operations::{join, OperationTracker};
+use dg_controller::{Controller, ControllerEvent, ShutdownReport, UserError};
+use dg_core::api::new_default;
+use tempfile::tempdir;
+use tokio::fs;
+
+#[tokio::test]
+async fn drain_waits_for_operations_that_finish_in_time() -> Result<()> {
+    let tracker = OperationTracker::default();
+    let guard = tracker.begin("encrypt", "/tmp/report.pdf")?;
+    assert_eq!(tracker.active()[0].kind, "encrypt");
+
+    let finishing = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        drop(guard);
+    });
+    let report = tracker.drain(Duration::from_secs(5)).await;
+    finishing.await?;
+    assert_eq!(
+        report,
+        ShutdownReport {
+            drained: 1,
+            aborted: 0
+        }
+    );
+
+    let Err(refused) = tracker.begin("encrypt", "/tmp/late.pdf") else {
+        panic!("tracker accepted work while draining");
+    };
+    assert_eq!(UserError::from_anyhow(&refused).code, SHUTTING_DOWN);
+    tracker.reopen();
+    assert!(tracker.begin("encrypt", "/tmp/late.pdf").is_ok());
+    Ok(())
+}
+
+#[tokio::test]
+async fn drain_cancels_operations_that_outlive_the_timeout() -> Result<()> {
+    let tracker = OperationTracker::default();
+    let cooperative = tracker.begin("migrate", "/tmp/archive")?;
+    let stuck = tracker.begin("encrypt", "/tmp/huge.iso")?;
+    let stuck_id = stuck.id();
+    let handle = tokio::spawn(async move {
+        let _stuck = stuck;
+        tokio::time::sleep(Duration::from_secs(3600)).await;
+        Ok::<_, anyhow::Error>(())
+    });
+    tracker.attach(stuck_id, handle.abort_handle());
+    let looping = tokio::spawn(async move {
+        while cooperative.check().is_ok() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        cooperative.check()
+    });
+
+    let report = tracker.drain(Duration::from_millis(50)).await;
+    assert_eq!(
+        report,
+        ShutdownReport {
+            drained: 0,
+            aborted: 2
+        }
+    );
+    assert!(tracker.active().is_empty());
+
+    let aborted = join(handle).await.expect_err("aborted");
+    assert_eq!(UserError::from_anyhow(&aborted).code, OPERATION_CANCELLED);
+    let stopped = looping.await?.expect_err("cancelled");
+    assert_eq!(UserError::from_anyhow(&stopped).code, OPERATION_CANCELLED);
+    Ok(())
+}
+
+#[tokio::test]
+async fn controller_refuses_new_work_while_shut_down() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    let source = temp.path().join("notes.txt");
+    fs::write(&source, b"quarterly numbers").await?;
+    let controller = boot(
+        Controller::new(new_default()).with_drain_timeout(Duration::from_secs(1)),
+        data_dir.clone(),
+    )
+    .await?;
+    controller
+        .encrypt_file(&source, vec![], vec![], None)
+        .await?;
+    assert!(controller.active_operations().is_empty());
+
+    let report = controller.shutdown().await?;
+    assert_eq!(report, ShutdownReport::default());
+    let refused = controller
+        .encrypt_file(&source, vec![], vec![], None)
+        .await
+        .expect_err("shut down");
+    assert_eq!(UserError::from_anyhow(&refused).code, SHUTTING_DOWN);
+
+    controller.boot("dev", data_dir, false).await?;
+    controller
+        .encrypt_file(&source, vec![], vec![], None)
+        .await?;
+    controller.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancelling_one_operation_leaves_the_others_running() -> Result<()> {
+    let tracker = OperationTracker::default();
+    let cooperative = tracker.begin("decrypt", "/tmp/ledger.dgenc")?;
+    let cooperative_id = cooperative.id();
+    let other = tracker.begin("encrypt", "/tmp/other.pdf")?;
+    let stuck = tracker.begin("encrypt", "/tmp/huge.iso")?;
+    let stuck_id = stuck.id();
+    let pending = tracker.spawned(
+        stuck_id,
+        tokio::spawn(async move {
+            let _stuck = stuck;
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+            Ok::<_, anyhow::Error>(())
+        }),
+    );
+
+    let cancelled = tracker.cancel(stuck_id)?;
+    assert_eq!(cancelled.target, "/tmp/huge.iso");
+    let aborted = pending.wait().await.expect_err("aborted");
+    assert_eq!(
+        UserError::from_anyhow(&aborted).code,
+        OPERATION_CANCELLED_BY_USER
+    );
+
+    tracker.cancel(cooperative_id)?;
+    let stopped = cooperative.check().expect_err("cancelled");
+    assert_eq!(
+        UserError::from_anyhow(&stopped).code,
+        OPERATION_CANCELLED_BY_USER
+    );
+    assert!(other.check().is_ok());
+    drop(cooperative);
+    assert_eq!(tracker.active().len(), 1);
+
+    let missing = tracker.cancel(stuck_id).expect_err("finished");
+    assert_eq!(UserError::from_anyhow(&missing).code, OPERATION_NOT_FOUND);
+    Ok(())
+}
+
+#[tokio::test]
+async fn controller_reports_cancellations() -> Result<()> {
+    let temp = tempdir()?;
+    let source = temp.path().join("notes.txt");
+    fs::write(&source, b"quarterly numbers").await?;
+    let controller = booted(temp.path().join("data")).await?;
+    let mut events = controller.subscribe();
+
+    let pending = controller
+        .start_encrypt_file(&source, Default::default())
+        .await?;
+    let id = pending.id;
+    match controller.cancel_operation(id).await {
+        Ok(operation) => {
+            assert_eq!(operation.kind, "encrypt");
+            let err = pending.wait().await.expect_err("cancelled");
+            assert_eq!(
+                UserError::from_anyhow(&err).code,
+                OPERATION_CANCELLED_BY_USER
+            );
+            loop {
+                let recorded = events.recv().await?;
+                if let ControllerEvent::Cancelled(message) = recorded.event {
+                    assert!(message.starts_with("cancelled encrypt of "));
+                    break;
+                }
+            }
+        }
+        // The file is small enough that the task may already be done.
+        Err(err) => {
+            assert_eq!(UserError::from_anyhow(&err).code, OPERATION_NOT_FOUND);
+            pending.wait().await?;
+        }
+    }
+
+    let missing = controller
+        .cancel_operation(id + 100)
+        .await
+        .expect_err("unknown");
+    assert_eq!(UserError::from_anyhow(&missing).code, OPERATION_NOT_FOUND);
+    Ok(())
+}
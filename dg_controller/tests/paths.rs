@@ -0,0 +1,121 @@
+mod common;
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::OutputTemplates;
+use tempfile::tempdir;
+use tokio::fs;
+
+#[tokio::test]
+async fn unicode_names_keep_their_extensions() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let received = temp.path().join("получено");
+    fs::create_dir_all(&received).await?;
+    let source = temp.path().join("résumé 履歴書.tar.gz");
+    fs::write(&source, "cv").await?;
+
+    let envelope = controller
+        .encrypt_file(&source, vec![], vec![], Some(received.clone()))
+        .await?;
+    assert_eq!(envelope, received.join("résumé 履歴書.tar.gz.dgenc"));
+
+    let restored = temp.path().join("restored");
+    let report = controller.decrypt_directory(&received, &restored).await?;
+    assert!(report.succeeded());
+    assert_eq!(
+        fs::read_to_string(restored.join("résumé 履歴書.tar.gz")).await?,
+        "cv"
+    );
+    controller.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn non_utf8_names_round_trip_byte_for_byte() -> Result<()> {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    controller.set_output_templates(OutputTemplates {
+        encrypted: "{name}.dgenc".into(),
+        decrypted: "{stem}".into(),
+    })?;
+    let received = temp.path().join("received");
+    fs::create_dir_all(&received).await?;
+    let name = OsStr::from_bytes(b"report-\xff\xfe.txt");
+    let source = temp.path().join(name);
+    fs::write(&source, "latin-1 name").await?;
+
+    let envelope = controller
+        .encrypt_file(&source, vec![], vec![], Some(received.clone()))
+        .await?;
+    assert_eq!(
+        envelope.file_name().unwrap().as_bytes(),
+        b"report-\xff\xfe.txt.dgenc"
+    );
+
+    let restored = temp.path().join("restored");
+    let report = controller.decrypt_directory(&received, &restored).await?;
+    assert!(report.succeeded());
+    assert_eq!(
+        fs::read_to_string(restored.join(name)).await?,
+        "latin-1 name"
+    );
+    controller.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(windows)]
+#[test]
+fn long_paths_use_the_extended_prefix() {
+    use std::path::{Path, PathBuf};
+
+    use dg_controller::paths::{display_path, extended};
+
+    let short = Path::new(r"C:\vault\ledger.csv");
+    assert_eq!(extended(short), short);
+
+    let deep = format!(r"C:\vault\{}\..\ledger.csv", "nested\\".repeat(40));
+    let long = extended(Path::new(&deep));
+    assert!(long.to_string_lossy().starts_with(r"\\?\C:\vault\nested"));
+    assert!(!long.to_string_lossy().contains(".."));
+    assert_eq!(
+        display_path(&long),
+        PathBuf::from(long.to_string_lossy().trim_start_matches(r"\\?\"))
+    );
+
+    let share = format!(r"\\server\share\{}", "x".repeat(300));
+    assert!(extended(Path::new(&share))
+        .to_string_lossy()
+        .starts_with(r"\\?\UNC\server\share\"));
+    assert_eq!(
+        display_path(Path::new(r"\\?\UNC\server\share\a.txt")),
+        Path::new(r"\\server\share\a.txt")
+    );
+}
+
+#[cfg(windows)]
+#[tokio::test]
+async fn envelopes_round_trip_beyond_max_path() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let mut deep = temp.path().to_path_buf();
+    for level in 0..24 {
+        deep.push(format!("deeply-nested-folder-{level:02}"));
+    }
+    assert!(deep.as_os_str().len() > 260);
+    fs::create_dir_all(dg_controller::paths::extended(&deep)).await?;
+    let source = dg_controller::paths::extended(&deep.join("ledger.csv"));
+    fs::write(&source, "q3,42").await?;
+
+    let envelope = controller
+        .encrypt_file(&source, vec![], vec![], None)
+        .await?;
+    let restored = controller.decrypt_file(&envelope, None).await?;
+    assert_eq!(fs::read_to_string(&restored).await?, "q3,42");
+    controller.shutdown().await?;
+    Ok(())
+}
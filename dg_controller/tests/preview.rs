@@ -0,0 +1,65 @@
+mod common;
+
+use std::io::Cursor;
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::thumbnail::{self, THUMBNAIL_SIZE};
+use dg_controller::EncryptOptions;
+use image::{GenericImageView, ImageFormat, RgbImage};
+use tempfile::tempdir;
+use tokio::fs;
+
+fn encoded(width: u32, height: u32, format: ImageFormat) -> Result<Vec<u8>> {
+    let image = RgbImage::from_fn(width, height, |x, y| {
+        image::Rgb([(x % 256) as u8, (y % 256) as u8, 128])
+    });
+    let mut bytes = Vec::new();
+    image.write_to(&mut Cursor::new(&mut bytes), format)?;
+    Ok(bytes)
+}
+
+#[tokio::test]
+async fn encrypting_with_a_preview_stores_a_small_thumbnail() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let photo = temp.path().join("photo.png");
+    fs::write(&photo, encoded(1200, 800, ImageFormat::Png)?).await?;
+    let notes = temp.path().join("notes.txt");
+    fs::write(&notes, b"not an image").await?;
+
+    let with_preview = |path| {
+        controller.encrypt_file_detailed(
+            path,
+            EncryptOptions {
+                preview: true,
+                ..Default::default()
+            },
+        )
+    };
+    let envelope = with_preview(&photo).await?.output;
+    let preview = controller.preview(&envelope).await?.expect("preview");
+    assert_eq!(preview.media_type, "image/jpeg");
+    let thumbnail = image::load_from_memory(&preview.data)?;
+    assert_eq!(thumbnail.dimensions(), (THUMBNAIL_SIZE, 171));
+
+    let text = with_preview(&notes).await?.output;
+    assert!(controller.preview(&text).await?.is_none());
+    Ok(())
+}
+
+#[test]
+fn pdf_previews_come_from_the_first_embedded_jpeg() -> Result<()> {
+    let jpeg = encoded(600, 900, ImageFormat::Jpeg)?;
+    let mut pdf =
+        b"%PDF-1.4\n1 0 obj\n<< /Type /XObject /Subtype /Image /Filter /DCTDecode >>\nstream\n"
+            .to_vec();
+    pdf.extend_from_slice(&jpeg);
+    pdf.extend_from_slice(b"\nendstream\nendobj\n%%EOF\n");
+
+    let preview = thumbnail::generate(&pdf).expect("pdf preview");
+    let decoded = image::load_from_memory(&preview.data)?;
+    assert_eq!(decoded.dimensions(), (171, THUMBNAIL_SIZE));
+    assert!(thumbnail::generate(b"%PDF-1.4\nno images here\n%%EOF").is_none());
+    Ok(())
+}
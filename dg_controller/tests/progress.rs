@@ -0,0 +1,76 @@
+mod common;
+
+use anyhow::Result;
+use common::booted;
+use dg_bridge::ProgressUpdate;
+use dg_controller::progress::PROGRESS_CHUNK;
+use dg_controller::EncryptOptions;
+use tempfile::tempdir;
+use tokio::fs;
+use tokio::sync::broadcast;
+
+fn drain(updates: &mut broadcast::Receiver<ProgressUpdate>) -> Vec<ProgressUpdate> {
+    std::iter::from_fn(|| updates.try_recv().ok()).collect()
+}
+
+fn finished(updates: &[ProgressUpdate], stage: &str) -> Option<(u64, Option<u64>)> {
+    updates
+        .iter()
+        .rev()
+        .find(|update| update.stage == stage)
+        .map(|update| (update.bytes, update.total_bytes))
+}
+
+#[tokio::test]
+async fn large_files_report_byte_progress_per_operation() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let source = temp.path().join("disk.img");
+    let size = (PROGRESS_CHUNK * 3 + 17) as u64;
+    fs::write(&source, vec![7u8; size as usize]).await?;
+
+    let mut updates = controller.subscribe_progress();
+    let pending = controller
+        .start_encrypt_file(&source, EncryptOptions::default())
+        .await?;
+    let id = pending.id.to_string();
+    let envelope = pending.wait().await?.output;
+    let encrypted = drain(&mut updates);
+    assert!(encrypted
+        .iter()
+        .all(|update| update.op_id == id && update.method.as_deref() == Some("encrypt")));
+    let stages: Vec<&str> = encrypted
+        .iter()
+        .map(|update| update.stage.as_str())
+        .collect();
+    assert_eq!(stages.first(), Some(&"reading"));
+    assert!(stages.contains(&"encrypting"));
+    assert_eq!(stages.last(), Some(&"writing"));
+    assert_eq!(finished(&encrypted, "reading"), Some((size, Some(size))));
+    let written = fs::metadata(&envelope).await?.len();
+    assert_eq!(
+        finished(&encrypted, "writing"),
+        Some((written, Some(written)))
+    );
+    assert_eq!(
+        encrypted.last().and_then(|update| update.percent),
+        Some(100.0)
+    );
+
+    fs::remove_file(&source).await?;
+    let pending = controller.start_decrypt_file(&envelope, None).await?;
+    let id = pending.id.to_string();
+    let restored = pending.wait().await?;
+    let decrypted = drain(&mut updates);
+    assert!(decrypted
+        .iter()
+        .all(|update| update.op_id == id && update.method.as_deref() == Some("decrypt")));
+    assert_eq!(
+        finished(&decrypted, "reading"),
+        Some((written, Some(written)))
+    );
+    assert_eq!(finished(&decrypted, "writing"), Some((size, Some(size))));
+    assert_eq!(fs::metadata(&restored).await?.len(), size);
+    controller.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::quarantine::{
+    self, parse_quarantine, PROVENANCE_ATTRIBUTE, QUARANTINE_ATTRIBUTE,
+};
+use dg_controller::{Quarantine, QuarantineHandling, StoredEnvelope};
+use serde_json::json;
+use tempfile::tempdir;
+use tokio::fs;
+
+fn downloaded() -> BTreeMap<String, Vec<u8>> {
+    BTreeMap::from([
+        (
+            QUARANTINE_ATTRIBUTE.to_string(),
+            b"0083;5f5e1000;Safari;8E1E2F0C-1B5A-4C5D-9E4F-2A3B4C5D6E7F".to_vec(),
+        ),
+        (
+            PROVENANCE_ATTRIBUTE.to_string(),
+            vec![0x01, 0x02, 0x00, 0xff],
+        ),
+    ])
+}
+
+#[test]
+fn quarantine_values_name_the_downloading_agent() {
+    assert_eq!(
+        parse_quarantine("0083;5f5e1000;Safari;8E1E2F0C"),
+        (Some("Safari".to_string()), Some(0x5f5e1000))
+    );
+    assert_eq!(parse_quarantine("0081;zz;;"), (None, None));
+    assert!(Quarantine::detect(BTreeMap::new(), QuarantineHandling::Preserve).is_none());
+
+    let preserved = Quarantine::detect(downloaded(), QuarantineHandling::Preserve).expect("found");
+    assert_eq!(preserved.agent.as_deref(), Some("Safari"));
+    assert_eq!(preserved.quarantined_at, Some(0x5f5e1000));
+    assert!(!preserved.stripped);
+    assert_eq!(preserved.attributes[PROVENANCE_ATTRIBUTE], "AQIA/w==");
+
+    let stripped = Quarantine::detect(downloaded(), QuarantineHandling::Strip).expect("found");
+    assert!(stripped.stripped);
+    assert!(stripped.attributes.is_empty());
+    assert_eq!(stripped.agent.as_deref(), Some("Safari"));
+
+    let meta = json!({ "quarantine": stripped });
+    assert_eq!(quarantine::read(&meta), Some(stripped));
+    assert_eq!(quarantine::read(&json!({})), None);
+}
+
+#[tokio::test]
+async fn handling_defaults_to_preserve_and_files_without_attributes_are_untouched() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    assert_eq!(
+        controller.quarantine_handling(),
+        QuarantineHandling::Preserve
+    );
+    controller.set_quarantine_handling(QuarantineHandling::Strip);
+    assert_eq!(controller.quarantine_handling(), QuarantineHandling::Strip);
+
+    let source = temp.path().join("local.txt");
+    fs::write(&source, b"made here").await?;
+    let envelope = controller
+        .encrypt_file(&source, vec![], vec![], None)
+        .await?;
+    let stored = StoredEnvelope::read(&envelope).await?;
+    assert!(stored.meta.get("quarantine").is_none());
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+#[tokio::test]
+async fn preserved_attributes_return_on_decrypted_files() -> Result<()> {
+    use std::process::Command;
+
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let source = temp.path().join("invoice.pdf");
+    fs::write(&source, b"%PDF-1.7").await?;
+    let value = "0083;5f5e1000;Safari;8E1E2F0C-1B5A-4C5D-9E4F-2A3B4C5D6E7F";
+    let status = Command::new("xattr")
+        .args(["-w", QUARANTINE_ATTRIBUTE, value])
+        .arg(&source)
+        .status()?;
+    assert!(status.success());
+
+    let envelope = controller
+        .encrypt_file(&source, vec![], vec![], None)
+        .await?;
+    let recorded =
+        quarantine::read(&StoredEnvelope::read(&envelope).await?.meta).expect("recorded");
+    assert_eq!(recorded.agent.as_deref(), Some("Safari"));
+
+    let out = temp.path().join("out");
+    fs::create_dir_all(&out).await?;
+    let decrypted = controller
+        .decrypt_file(&envelope, Some(out.clone()))
+        .await?;
+    let restored = Command::new("xattr")
+        .args(["-p", QUARANTINE_ATTRIBUTE])
+        .arg(&decrypted)
+        .output()?;
+    assert_eq!(String::from_utf8_lossy(&restored.stdout).trim(), value);
+
+    controller.set_quarantine_handling(QuarantineHandling::Strip);
+    fs::remove_file(&decrypted).await?;
+    let decrypted = controller.decrypt_file(&envelope, Some(out)).await?;
+    let stripped = Command::new("xattr")
+        .args(["-p", QUARANTINE_ATTRIBUTE])
+        .arg(&decrypted)
+        .output()?;
+    assert!(!stripped.status.success());
+    Ok(())
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use common::booted;
+use dg_controller::{EncryptOptions, ReadReceipt, ReceiptSink, StoredEnvelope};
+use serde_json::json;
+use tempfile::tempdir;
+use tokio::fs;
+use tokio::sync::mpsc;
+
+struct RecordingSink(mpsc::UnboundedSender<ReadReceipt>);
+
+#[async_trait]
+impl ReceiptSink for RecordingSink {
+    async fn deliver(&self, receipt: ReadReceipt) -> Result<()> {
+        self.0.send(receipt)?;
+        Ok(())
+    }
+}
+
+struct OfflineSink;
+
+#[async_trait]
+impl ReceiptSink for OfflineSink {
+    async fn deliver(&self, _receipt: ReadReceipt) -> Result<()> {
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        Err(anyhow::anyhow!("receipt endpoint unreachable"))
+    }
+}
+
+#[tokio::test]
+async fn opted_in_envelopes_post_a_receipt_when_decrypted() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let (sender, mut receipts) = mpsc::unbounded_channel();
+    controller.set_receipt_sink(Some(Arc::new(RecordingSink(sender))));
+    let source = temp.path().join("plan.txt");
+    fs::write(&source, b"launch on friday").await?;
+
+    let tracked = controller
+        .encrypt_file_with(
+            &source,
+            EncryptOptions {
+                labels: vec!["internal".into()],
+                read_receipts: true,
+                ..Default::default()
+            },
+        )
+        .await?;
+    let stored = StoredEnvelope::read(&tracked).await?;
+    assert_eq!(stored.meta["read_receipts"], json!(true));
+
+    let text = controller.decrypt_to_text(&tracked).await?;
+    assert_eq!(text.as_str(), "launch on friday");
+    let receipt = tokio::time::timeout(Duration::from_secs(5), receipts.recv())
+        .await?
+        .expect("receipt delivered");
+    assert_eq!(receipt.event, "envelope.decrypted");
+    assert_eq!(receipt.labels, vec!["internal".to_string()]);
+    assert_eq!(
+        receipt.file_name.as_deref(),
+        tracked.file_name().and_then(|name| name.to_str())
+    );
+    assert_eq!(receipt.subject, controller.identity().subject());
+    assert!(!serde_json::to_string(&receipt)?.contains(&*temp.path().to_string_lossy()));
+
+    let signed = receipt.sign(b"team-secret")?;
+    assert!(signed.verify(b"team-secret"));
+    assert!(!signed.verify(b"other-secret"));
+
+    fs::create_dir_all(temp.path().join("plain")).await?;
+    let untracked = controller
+        .encrypt_file(&source, vec![], vec![], Some(temp.path().join("plain")))
+        .await?;
+    controller.decrypt_to_text(&untracked).await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(receipts.try_recv().is_err());
+    controller.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn unreachable_sinks_never_hold_up_decryption() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    controller.set_receipt_sink(Some(Arc::new(OfflineSink)));
+    let source = temp.path().join("plan.txt");
+    fs::write(&source, b"launch on friday").await?;
+    let tracked = controller
+        .encrypt_file_with(
+            &source,
+            EncryptOptions {
+                read_receipts: true,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    let decrypted = tokio::time::timeout(
+        Duration::from_secs(5),
+        controller.decrypt_file(&tracked, Some(temp.path().join("data"))),
+    )
+    .await??;
+    assert_eq!(fs::read(&decrypted).await?, b"launch on friday");
+    controller.shutdown().await?;
+    Ok(())
+}
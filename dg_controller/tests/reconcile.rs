@@ -0,0 +1,69 @@
+mod common;
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::EncryptOptions;
+use dg_core::index::EnvelopeIndex;
+use tempfile::tempdir;
+use tokio::fs;
+
+#[tokio::test]
+async fn reconciliation_tracks_envelopes_moved_or_deleted_outside_the_app() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    let vault = temp.path().join("vault");
+    let elsewhere = temp.path().join("elsewhere");
+    fs::create_dir_all(&vault).await?;
+    fs::create_dir_all(&elsewhere).await?;
+    let controller = booted(data_dir.clone()).await?;
+    controller.set_index_locations(vec![vault.clone(), temp.path().join("missing")]);
+
+    let mut outputs = Vec::new();
+    for (name, out_dir) in [
+        ("plan.txt", &vault),
+        ("budget.txt", &vault),
+        ("draft.txt", &elsewhere),
+    ] {
+        let source = temp.path().join(name);
+        fs::write(&source, name).await?;
+        let outcome = controller
+            .encrypt_file_detailed(
+                &source,
+                EncryptOptions {
+                    out_dir: Some(out_dir.clone()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        outputs.push(outcome.output);
+    }
+    let unchanged = controller.reconcile_index().await?;
+    assert_eq!((unchanged.scanned, unchanged.unchanged), (2, 2));
+    assert!(!unchanged.changed());
+
+    let archive = vault.canonicalize()?.join("archive");
+    fs::create_dir_all(&archive).await?;
+    let moved_to = archive.join("plan-2024.dgenc");
+    fs::rename(&outputs[0], &moved_to).await?;
+    fs::remove_file(&outputs[1]).await?;
+    let copied = vault.canonicalize()?.join("draft-copy.dgenc");
+    fs::copy(&outputs[2], &copied).await?;
+
+    let mut events = controller.subscribe();
+    let report = controller.reconcile_index().await?;
+    assert_eq!(report.moved.len(), 1);
+    assert_eq!(report.moved[0].to, moved_to);
+    assert_eq!(report.removed, vec![outputs[1].clone()]);
+    assert_eq!(report.added, vec![copied.clone()]);
+    assert!(events.recv().await?.summary.contains("1 moved"));
+
+    let paths: Vec<_> = EnvelopeIndex::load(&data_dir)
+        .await?
+        .entries()
+        .iter()
+        .map(|entry| entry.path.clone())
+        .collect();
+    assert_eq!(paths.len(), 3);
+    assert!(paths.contains(&moved_to) && paths.contains(&copied) && paths.contains(&outputs[2]));
+    Ok(())
+}
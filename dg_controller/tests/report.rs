@@ -0,0 +1,73 @@
+mod common;
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::{ReportFilter, ReportFormat};
+use tempfile::tempdir;
+use tokio::fs;
+
+#[tokio::test]
+async fn reports_join_the_index_with_the_audit_log() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let board = temp.path().join("board, minutes.txt");
+    let draft = temp.path().join("draft.txt");
+    fs::write(&board, b"minutes").await?;
+    fs::write(&draft, b"draft").await?;
+    let board = controller
+        .encrypt_file(&board, Vec::new(), vec!["confidential".into()], None)
+        .await?;
+    controller
+        .encrypt_file(&draft, Vec::new(), vec!["=cmd".into()], None)
+        .await?;
+
+    let json = temp.path().join("report.json");
+    let exported = controller
+        .export_report(
+            ReportFilter {
+                labels: vec!["confidential".into()],
+                ..Default::default()
+            },
+            ReportFormat::Json,
+            &json,
+        )
+        .await?;
+    assert_eq!(exported.envelopes, 1);
+    let report: serde_json::Value = serde_json::from_slice(&fs::read(&json).await?)?;
+    let row = &report["envelopes"][0];
+    assert_eq!(row["path"], board.to_string_lossy().as_ref());
+    assert_eq!(row["labels"], serde_json::json!(["confidential"]));
+    assert_eq!(row["encrypted_by"], controller.identity().subject());
+    assert!(row["encrypted_at"].as_u64().is_some());
+    assert_eq!(
+        report["filter"]["labels"],
+        serde_json::json!(["confidential"])
+    );
+
+    let csv = temp.path().join("report.csv");
+    let exported = controller
+        .export_report(ReportFilter::default(), ReportFormat::Csv, &csv)
+        .await?;
+    assert_eq!(exported.envelopes, 2);
+    let csv = fs::read_to_string(&csv).await?;
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("path,original_path,labels,"));
+    // Sorted by path; the comma forces quoting and the formula is defused.
+    assert!(lines[1].starts_with(&format!("\"{}\",", board.display())));
+    assert!(lines[2].contains(",'=cmd,"));
+
+    let later = controller
+        .export_report(
+            ReportFilter {
+                since: Some(u64::MAX),
+                ..Default::default()
+            },
+            ReportFormat::Csv,
+            &temp.path().join("empty.csv"),
+        )
+        .await?;
+    assert_eq!(later.envelopes, 0);
+    controller.shutdown().await?;
+    Ok(())
+}
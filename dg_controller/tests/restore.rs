@@ -0,0 +1,55 @@
+mod common;
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::EncryptOptions;
+use tempfile::tempdir;
+use tokio::fs;
+
+#[tokio::test]
+async fn directory_restore_keeps_relative_structure() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+
+    let received = temp.path().join("received");
+    fs::create_dir_all(received.join("nested")).await?;
+    for (name, out_dir) in [
+        ("report.txt", received.clone()),
+        ("notes.md", received.join("nested")),
+    ] {
+        let source = temp.path().join(name);
+        fs::write(&source, name).await?;
+        controller
+            .encrypt_file_with(
+                &source,
+                EncryptOptions {
+                    out_dir: Some(out_dir),
+                    ..Default::default()
+                },
+            )
+            .await?;
+    }
+    fs::write(received.join("nested").join("broken.dgenc"), b"not json").await?;
+
+    let restored = temp.path().join("restored");
+    let report = controller.decrypt_directory(&received, &restored).await?;
+    assert_eq!(report.scanned, 3);
+    assert_eq!(report.restored.len(), 2);
+    assert_eq!(report.failures.len(), 1);
+    assert!(report.failures[0].path.ends_with("broken.dgenc"));
+    assert!(!report.succeeded());
+    assert_eq!(
+        fs::read_to_string(restored.join("report.txt")).await?,
+        "report.txt"
+    );
+    assert_eq!(
+        fs::read_to_string(restored.join("nested").join("notes.md")).await?,
+        "notes.md"
+    );
+
+    let again = controller.decrypt_directory(&received, &restored).await?;
+    assert!(again.restored.is_empty());
+    assert_eq!(again.failures.len(), 3);
+    controller.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::{Controller, EncryptOptions, JobStatus, Retention, RetentionState};
+use tempfile::tempdir;
+use tokio::fs;
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock")
+        .as_secs()
+}
+
+async fn encrypt(controller: &Controller, path: &Path, retention: Retention) -> Result<PathBuf> {
+    fs::write(path, b"board minutes").await?;
+    controller
+        .encrypt_file_with(
+            path,
+            EncryptOptions {
+                retention: Some(retention),
+                ..Default::default()
+            },
+        )
+        .await
+}
+
+#[tokio::test]
+async fn held_and_retained_envelopes_cannot_be_shredded() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    let controller = booted(data_dir.clone()).await?;
+
+    let retained = encrypt(
+        &controller,
+        &temp.path().join("minutes.txt"),
+        Retention::for_years(Some(7), None, now()),
+    )
+    .await?;
+    let refused = controller
+        .shred_envelope(&retained)
+        .await
+        .expect_err("retained");
+    assert!(format!("{refused:#}").contains("retained until"));
+    assert!(retained.exists());
+
+    let plain = encrypt(
+        &controller,
+        &temp.path().join("draft.txt"),
+        Retention::default(),
+    )
+    .await?;
+    let hold = controller
+        .place_legal_hold(&plain, "Smith v. Acme discovery")
+        .await?;
+    assert!(hold.hold.is_some());
+    let refused = controller.shred_envelope(&plain).await.expect_err("held");
+    assert!(format!("{refused:#}").contains("Smith v. Acme"));
+    assert!(controller.place_legal_hold(&plain, "  ").await.is_err());
+
+    let report = controller.retention_report().await?;
+    assert_eq!(report.held.len(), 1);
+    assert_eq!(report.retained.len(), 1);
+    assert_eq!(report.retained[0].state, RetentionState::Retained);
+
+    controller.release_legal_hold(&plain).await?;
+    assert!(controller.release_legal_hold(&plain).await.is_err());
+    controller.shred_envelope(&plain).await?;
+    assert!(!plain.exists());
+
+    let log = fs::read_to_string(data_dir.join("audit").join("audit.log")).await?;
+    assert!(log.contains("\"legal_hold\""));
+    assert!(log.contains("\"release_hold\""));
+    assert!(log.contains("\"shred\""));
+    Ok(())
+}
+
+#[tokio::test]
+async fn overdue_envelopes_are_disposed_of_by_a_job() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let expired = Retention {
+        class: Some("correspondence".into()),
+        dispose_after: Some(now() - 60),
+        ..Default::default()
+    };
+    let overdue = encrypt(
+        &controller,
+        &temp.path().join("letter.txt"),
+        expired.clone(),
+    )
+    .await?;
+    let held = encrypt(&controller, &temp.path().join("memo.txt"), expired).await?;
+    controller.place_legal_hold(&held, "audit 2024").await?;
+    let kept = encrypt(
+        &controller,
+        &temp.path().join("policy.txt"),
+        Retention::for_years(None, Some(3), now()),
+    )
+    .await?;
+    assert!(encrypt(
+        &controller,
+        &temp.path().join("invalid.txt"),
+        Retention::for_years(Some(5), Some(1), now()),
+    )
+    .await
+    .is_err());
+
+    let report = controller.retention_report().await?;
+    assert_eq!(report.overdue.len(), 1);
+    assert_eq!(report.held.len(), 1);
+
+    let job = controller
+        .schedule_disposition()
+        .await?
+        .expect("disposition job");
+    let mut finished = None;
+    for _ in 0..500 {
+        let jobs = controller.list_jobs().await?;
+        if let Some(done) = jobs
+            .into_iter()
+            .find(|candidate| candidate.id == job.id && candidate.status == JobStatus::Completed)
+        {
+            finished = Some(done);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(finished.expect("job finished").succeeded, 1);
+    assert!(!overdue.exists());
+    assert!(held.exists() && kept.exists());
+    assert!(controller.schedule_disposition().await?.is_none());
+    Ok(())
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::shred::{shred_file, OVERWRITE_PASSES, SHRED_ACTION};
+use dg_controller::EncryptOptions;
+use dg_core::audit::AuditFilter;
+use serde_json::json;
+use tempfile::tempdir;
+use tokio::fs;
+
+#[tokio::test]
+async fn the_source_is_shredded_once_its_envelope_is_written() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let source = temp.path().join("salaries.csv");
+    let plaintext = vec![b'7'; 3 * 1024 * 1024 + 17];
+    fs::write(&source, &plaintext).await?;
+
+    let outcome = controller
+        .encrypt_and_shred(&source, EncryptOptions::default())
+        .await?;
+    let report = outcome.shredded.expect("shred report");
+    assert_eq!(report.bytes, plaintext.len() as u64);
+    assert_eq!(report.passes, OVERWRITE_PASSES);
+    assert!(!source.exists());
+    let mut leftovers = fs::read_dir(temp.path()).await?;
+    while let Some(entry) = leftovers.next_entry().await? {
+        assert!(!entry.file_name().to_string_lossy().starts_with(".dg-shred"));
+    }
+
+    let restored = controller.decrypt_file(&outcome.output, None).await?;
+    assert_eq!(fs::read(&restored).await?, plaintext);
+    let audited = controller
+        .query_audit(AuditFilter {
+            action: Some(SHRED_ACTION.into()),
+            ..Default::default()
+        })
+        .await?;
+    assert!(audited
+        .iter()
+        .any(|event| event.details.as_ref().is_some_and(|details| {
+            details["passes"] == OVERWRITE_PASSES && details["bytes"] == plaintext.len()
+        })));
+    controller.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn a_denied_shred_leaves_the_source_unencrypted() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    fs::create_dir_all(&data_dir).await?;
+    fs::write(
+        data_dir.join("policy.json"),
+        serde_json::to_vec(&json!({
+            "default_allow": true,
+            "rules": [
+                { "subject": "*", "action": "shred", "resource": "*", "effect": "deny" }
+            ]
+        }))?,
+    )
+    .await?;
+    let controller = booted(data_dir).await?;
+    let source = temp.path().join("minutes.txt");
+    fs::write(&source, b"board minutes").await?;
+
+    assert!(controller
+        .encrypt_and_shred(&source, EncryptOptions::default())
+        .await
+        .is_err());
+    assert_eq!(fs::read(&source).await?, b"board minutes");
+    assert!(!temp.path().join("minutes.txt.dgenc").exists());
+
+    let output = controller
+        .encrypt_file_with(&source, EncryptOptions::default())
+        .await?;
+    assert!(output.exists() && source.exists());
+    controller.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn shredding_overwrites_the_data_in_place() -> Result<()> {
+    let temp = tempdir()?;
+    let path = temp.path().join("notes.txt");
+    let other_link = temp.path().join("notes-link.txt");
+    fs::write(&path, b"wire the funds on friday").await?;
+    fs::hard_link(&path, &other_link).await?;
+
+    let report = shred_file(&path, 3).await?;
+    assert_eq!(report.passes, 3);
+    assert!(!path.exists());
+    assert_eq!(
+        fs::read(&other_link).await?,
+        vec![0u8; report.bytes as usize]
+    );
+    assert!(shred_file(temp.path(), 1).await.is_err());
+    Ok(())
+}
@@ -0,0 +1,300 @@
+#![cfg(target_os = "linux")]
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use dg_bridge::{BridgeConfig, Endpoint};
+use dg_controller::remote::WireEnvelope;
+use dg_controller::{BackendConfig, Controller};
+use dg_core::api::{new_default, DGConfig, DataGuardian, EncryptRequest, Envelope};
+use dg_core::RpcError;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use serde_json::{json, Value};
+use tempfile::tempdir;
+use tokio::fs;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixListener;
+use zeroize::Zeroizing;
+
+const OPERATIONS_PER_DAY: usize = 400;
+const WARM_UP_DAYS: usize = 1;
+const MIN_DAYS: usize = 7;
+
+struct SoakConfig {
+    duration: Duration,
+    seed: u64,
+    max_rss_growth_kib: u64,
+    max_fd_growth: usize,
+}
+
+impl SoakConfig {
+    fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+                .unwrap_or(default)
+        }
+        Self {
+            duration: Duration::from_secs(var("DG_SOAK_SECS", 60)),
+            seed: var("DG_SOAK_SEED", rand::random()),
+            max_rss_growth_kib: var("DG_SOAK_MAX_RSS_GROWTH_KIB", 64 * 1024),
+            max_fd_growth: var("DG_SOAK_MAX_FD_GROWTH", 8),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    day: usize,
+    rss_kib: u64,
+    fds: usize,
+}
+
+fn sample(day: usize) -> Result<Sample> {
+    let status = std::fs::read_to_string("/proc/self/status")?;
+    let rss_kib = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+        .context("VmRSS missing from /proc/self/status")?;
+    let fds = std::fs::read_dir("/proc/self/fd")?.count();
+    Ok(Sample { day, rss_kib, fds })
+}
+
+async fn serve_engine(listener: UnixListener, engine: Arc<dyn DataGuardian + Send + Sync>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        let engine = engine.clone();
+        tokio::spawn(async move {
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let request: Value = serde_json::from_str(&line).expect("request json");
+                let params = &request["params"];
+                let result = match request["method"].as_str().expect("method") {
+                    "engine.init" => {
+                        let cfg: DGConfig =
+                            serde_json::from_value(params["config"].clone()).expect("config");
+                        engine.init(cfg).await.map(|_| json!({ "ok": true }))
+                    }
+                    "engine.encrypt" => engine
+                        .encrypt(EncryptRequest {
+                            plaintext: general_purpose::STANDARD
+                                .decode(params["plaintext"].as_str().expect("plaintext"))
+                                .expect("base64"),
+                            labels: serde_json::from_value(params["labels"].clone())
+                                .expect("labels"),
+                            recipients: serde_json::from_value(params["recipients"].clone())
+                                .expect("recipients"),
+                            ..Default::default()
+                        })
+                        .await
+                        .map(|envelope| json!(WireEnvelope::from(&envelope))),
+                    "engine.decrypt" => {
+                        let wire: WireEnvelope =
+                            serde_json::from_value(params["envelope"].clone()).expect("envelope");
+                        let envelope = Envelope::try_from(wire).expect("payload");
+                        engine.decrypt(envelope).await.map(|plaintext| {
+                            json!({ "plaintext": general_purpose::STANDARD.encode(plaintext) })
+                        })
+                    }
+                    "engine.check_policy" => engine
+                        .check_policy(
+                            params["subject"].as_str().expect("subject"),
+                            params["action"].as_str().expect("action"),
+                            params["resource"].as_str().expect("resource"),
+                        )
+                        .await
+                        .map(|allowed| json!({ "allowed": allowed })),
+                    "engine.shutdown" => engine.shutdown().await.map(|_| json!({ "ok": true })),
+                    other => panic!("unexpected method {other}"),
+                };
+                let reply = match result {
+                    Ok(result) => {
+                        json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })
+                    }
+                    Err(err) => json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "error": RpcError::from(err)
+                    }),
+                };
+                let mut payload = serde_json::to_vec(&reply).expect("encode");
+                payload.push(b'\n');
+                if write.write_all(&payload).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+struct Desktop {
+    local: Controller,
+    remote: Controller,
+    local_data: PathBuf,
+    remote_data: PathBuf,
+    workspace: PathBuf,
+}
+
+impl Desktop {
+    async fn boot(&self) -> Result<()> {
+        self.local
+            .boot("dev", self.local_data.clone(), false)
+            .await?;
+        self.remote
+            .boot("dev", self.remote_data.clone(), false)
+            .await?;
+        self.local.warm_up().await?;
+        Ok(())
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        self.local.shutdown().await?;
+        self.remote.shutdown().await?;
+        Ok(())
+    }
+
+    async fn operate(&self, rng: &mut StdRng, step: usize) -> Result<()> {
+        let controller = if rng.gen_bool(0.5) {
+            &self.local
+        } else {
+            &self.remote
+        };
+        match rng.gen_range(0..10) {
+            0..=3 => {
+                let mut content = vec![0u8; rng.gen_range(0..256 * 1024)];
+                rng.fill_bytes(&mut content);
+                let source = self.workspace.join(format!("file-{step}.bin"));
+                fs::write(&source, &content).await?;
+                let envelope = controller
+                    .encrypt_file(&source, vec![], vec!["internal".into()], None)
+                    .await?;
+                let out_dir = self.workspace.join(format!("out-{step}"));
+                fs::create_dir_all(&out_dir).await?;
+                let decrypted = controller
+                    .decrypt_file(&envelope, Some(out_dir.clone()))
+                    .await?;
+                assert_eq!(fs::read(&decrypted).await?, content, "round trip {step}");
+                fs::remove_file(&source).await?;
+                fs::remove_file(&envelope).await?;
+                fs::remove_dir_all(&out_dir).await?;
+            }
+            4..=5 => {
+                let text = format!("note {step}: {}", rng.gen::<u64>());
+                let note = controller
+                    .encrypt_text(Zeroizing::new(text.clone()), vec![], vec![])
+                    .await?;
+                assert_eq!(*controller.decrypt_to_text(&note).await?, text);
+                fs::remove_file(&note).await?;
+            }
+            6..=7 => {
+                let resource = format!("{}/doc-{step}", self.workspace.display());
+                controller
+                    .check_access("system", "decrypt", &resource)
+                    .await?;
+            }
+            8 => {
+                self.local.status().await;
+            }
+            _ => {
+                let events = self.local.replay_events(None).await;
+                let since = events.last().map(|recorded| recorded.seq.saturating_sub(5));
+                self.local.replay_events(since).await;
+                self.local.list_jobs().await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn assert_within_bounds(config: &SoakConfig, baseline: Sample, samples: &[Sample]) {
+    let peak = samples
+        .iter()
+        .max_by_key(|sample| sample.rss_kib)
+        .copied()
+        .unwrap_or(baseline);
+    let last = samples.last().copied().unwrap_or(baseline);
+    let rss_growth = peak.rss_kib.saturating_sub(baseline.rss_kib);
+    let fd_growth = last.fds.saturating_sub(baseline.fds);
+    println!(
+        "soak: seed {} over {} simulated days, rss {} KiB -> peak {} KiB (day {}), fds {} -> {}",
+        config.seed,
+        last.day + 1,
+        baseline.rss_kib,
+        peak.rss_kib,
+        peak.day,
+        baseline.fds,
+        last.fds
+    );
+    assert!(
+        rss_growth <= config.max_rss_growth_kib,
+        "RSS grew by {rss_growth} KiB (budget {} KiB, seed {}): {samples:?}",
+        config.max_rss_growth_kib,
+        config.seed
+    );
+    assert!(
+        fd_growth <= config.max_fd_growth,
+        "open descriptors grew by {fd_growth} (budget {}, seed {}): {samples:?}",
+        config.max_fd_growth,
+        config.seed
+    );
+}
+
+async fn open_desktop(root: &Path) -> Result<Desktop> {
+    let local_data = root.join("local");
+    let remote_data = root.join("remote");
+    let workspace = root.join("workspace");
+    for dir in [&local_data, &remote_data, &workspace] {
+        fs::create_dir_all(dir).await?;
+    }
+    let socket = root.join("dg-core.sock");
+    tokio::spawn(serve_engine(UnixListener::bind(&socket)?, new_default()));
+    Ok(Desktop {
+        local: Controller::from_config(BackendConfig::InProcess)?.with_event_capacity(256),
+        remote: Controller::from_config(BackendConfig::Remote(Box::new(BridgeConfig::new(vec![
+            Endpoint::Unix(socket),
+        ]))))?
+        .with_event_capacity(256),
+        local_data,
+        remote_data,
+        workspace: workspace.canonicalize()?,
+    })
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn a_week_of_desktop_usage_stays_within_resource_bounds() -> Result<()> {
+    let config = SoakConfig::from_env();
+    let temp = tempdir()?;
+    let desktop = open_desktop(temp.path()).await?;
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let started = Instant::now();
+    let mut baseline = None;
+    let mut samples = Vec::new();
+    let mut day = 0;
+
+    while day < MIN_DAYS || started.elapsed() < config.duration {
+        desktop.boot().await?;
+        for operation in 0..OPERATIONS_PER_DAY {
+            let step = day * OPERATIONS_PER_DAY + operation;
+            desktop.operate(&mut rng, step).await.with_context(|| {
+                format!("day {day}, operation {operation} (seed {})", config.seed)
+            })?;
+        }
+        desktop.shutdown().await?;
+        let current = sample(day)?;
+        match baseline {
+            None if day == WARM_UP_DAYS => baseline = Some(current),
+            Some(_) => samples.push(current),
+            None => {}
+        }
+        day += 1;
+    }
+
+    assert_within_bounds(&config, baseline.expect("warm-up sample"), &samples);
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use dg_controller::migrate::ENVELOPE_FORMAT;
+use dg_controller::StoredEnvelope;
+use dg_core::api::{new_default, DGConfig, DGError, Envelope};
+use dg_core::RekeyContext;
+use proptest::prelude::*;
+use serde_json::{json, Value};
+use tempfile::tempdir;
+
+fn meta() -> impl Strategy<Value = Value> {
+    (
+        prop::collection::vec("[a-z/]{1,12}", 0..4),
+        prop::collection::vec(any::<String>(), 0..3),
+    )
+        .prop_map(|(labels, recipients)| {
+            json!({ "labels": labels, "recipients": recipients, "profile": "dev" })
+        })
+}
+
+fn any_meta() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        meta(),
+        Just(json!([])),
+        Just(json!(null)),
+        any::<i64>().prop_map(Value::from),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_bytes_never_panic(bytes in prop::collection::vec(any::<u8>(), 0..4096)) {
+        if let Ok(stored) = StoredEnvelope::parse(&bytes) {
+            let _ = stored.into_envelope();
+        }
+    }
+
+    #[test]
+    fn arbitrary_documents_never_panic(
+        format in any::<u32>(),
+        payload in any::<String>(),
+        meta in any_meta(),
+        original_path in prop::option::of(any::<String>()),
+    ) {
+        let doc = json!({
+            "format": format,
+            "payload": payload,
+            "meta": meta,
+            "original_path": original_path,
+        });
+        if let Ok(stored) = StoredEnvelope::parse(&serde_json::to_vec(&doc).expect("json")) {
+            prop_assert!(stored.meta.is_object());
+            let _ = stored.into_envelope();
+        }
+    }
+
+    #[test]
+    fn stored_envelopes_round_trip(
+        bytes in prop::collection::vec(any::<u8>(), 12..512),
+        meta in meta(),
+        original_path in prop::option::of("[a-zA-Z0-9/._ -]{1,40}"),
+    ) {
+        let envelope = Envelope { bytes, meta: meta.clone() };
+        let stored = StoredEnvelope::new(&envelope, meta, original_path.clone());
+        prop_assert_eq!(stored.format, ENVELOPE_FORMAT);
+        let parsed = StoredEnvelope::parse(&stored.to_bytes().expect("encode")).expect("parse");
+        prop_assert_eq!(&parsed, &stored);
+        let (decoded, path) = parsed.into_envelope().expect("decode");
+        prop_assert_eq!(decoded.bytes, envelope.bytes);
+        prop_assert_eq!(decoded.meta, envelope.meta);
+        prop_assert_eq!(path, original_path);
+    }
+}
+
+#[test]
+fn non_object_metadata_is_rejected() {
+    for meta in [json!([]), json!(null), json!("labels")] {
+        let doc = json!({ "format": ENVELOPE_FORMAT, "payload": "", "meta": meta });
+        let err = StoredEnvelope::parse(&serde_json::to_vec(&doc).expect("json"))
+            .expect_err("metadata must be an object");
+        assert!(err.to_string().contains("JSON object"), "{err}");
+    }
+}
+
+#[tokio::test]
+async fn rekey_rejects_non_object_metadata() {
+    let temp = tempdir().expect("tempdir");
+    let key = [9u8; 32];
+    std::fs::create_dir_all(temp.path().join("keys")).expect("key dir");
+    std::fs::write(temp.path().join("keys/master.key"), key).expect("key");
+    let engine = new_default();
+    engine
+        .init(DGConfig {
+            profile: "dev".into(),
+            data_dir: temp.path().to_path_buf(),
+            ..Default::default()
+        })
+        .await
+        .expect("init");
+
+    let nonce = [3u8; 12];
+    let mut bytes = nonce.to_vec();
+    bytes.extend(
+        Aes256Gcm::new(&key.into())
+            .encrypt(Nonce::from_slice(&nonce), b"legacy".as_slice())
+            .expect("seal"),
+    );
+    let err = engine
+        .rekey(
+            Envelope {
+                bytes,
+                meta: json!(["not", "an", "object"]),
+            },
+            RekeyContext::default(),
+        )
+        .await
+        .expect_err("array metadata");
+    assert!(matches!(err, DGError::Crypto(_)), "{err}");
+}
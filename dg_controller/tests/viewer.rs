@@ -0,0 +1,58 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+use std::time::Duration;
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::ViewerOptions;
+use tempfile::tempdir;
+use tokio::fs;
+
+fn launcher(seconds: &str) -> ViewerOptions {
+    ViewerOptions {
+        launcher: Some(vec![
+            "sh".into(),
+            "-c".into(),
+            format!("sleep {seconds}"),
+            "viewer".into(),
+        ]),
+        grace: Duration::ZERO,
+    }
+}
+
+#[tokio::test]
+async fn viewer_copy_is_removed_after_the_viewer_exits() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let source = temp.path().join("plan.txt");
+    fs::write(&source, "quarterly plan").await?;
+    let envelope = controller
+        .encrypt_file(&source, vec![], vec![], None)
+        .await?;
+
+    let session = controller.open_viewer(&envelope, launcher("0.2")).await?;
+    assert!(session.path.ends_with("plan.txt"));
+    assert_eq!(fs::read_to_string(&session.path).await?, "quarterly plan");
+    let mode = fs::metadata(&session.path).await?.permissions().mode();
+    assert_eq!(mode & 0o777, 0o400);
+
+    let mut removed = false;
+    for _ in 0..50 {
+        if fs::metadata(&session.path).await.is_err() {
+            removed = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(removed);
+    assert!(!controller.close_viewer(session.id).await?);
+
+    let early = controller.open_viewer(&envelope, launcher("5")).await?;
+    assert!(controller.close_viewer(early.id).await?);
+    assert!(fs::metadata(&early.path).await.is_err());
+    controller.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::{Controller, EncryptOptions, WalkSettings};
+use dg_core::api::new_default;
+use tempfile::tempdir;
+use tokio::fs;
+
+#[tokio::test]
+async fn directory_encryption_skips_excluded_paths_and_mirrors_folders() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    controller.set_walk_settings(WalkSettings {
+        parallel_files: Some(2),
+        ..WalkSettings::default()
+    })?;
+
+    let project = temp.path().join("project");
+    for dir in ["docs/drafts", "node_modules/left-pad", "scratch", ".cache"] {
+        fs::create_dir_all(project.join(dir)).await?;
+    }
+    for file in [
+        "readme.md",
+        "docs/plan.md",
+        "docs/drafts/v1.md",
+        "docs/drafts/v1.md.tmp",
+        "node_modules/left-pad/index.js",
+        "scratch/notes.txt",
+        ".cache/state",
+        "sealed.dgenc",
+        "debug.log",
+    ] {
+        fs::write(project.join(file), file).await?;
+    }
+    fs::write(project.join(".dgignore"), "scratch/\n*.log\n").await?;
+
+    let sealed = temp.path().join("sealed");
+    let report = controller
+        .encrypt_directory(
+            &project,
+            EncryptOptions {
+                out_dir: Some(sealed.clone()),
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert!(report.succeeded(), "{:?}", report.failures);
+    assert_eq!(report.scanned, 3);
+    let sources: BTreeSet<PathBuf> = report
+        .encrypted
+        .iter()
+        .map(|file| {
+            file.source
+                .strip_prefix(&report.root)
+                .unwrap()
+                .to_path_buf()
+        })
+        .collect();
+    assert_eq!(
+        sources,
+        BTreeSet::from([
+            PathBuf::from("docs/drafts/v1.md"),
+            PathBuf::from("docs/plan.md"),
+            PathBuf::from("readme.md"),
+        ])
+    );
+    for file in &report.encrypted {
+        let relative = file.source.strip_prefix(&report.root)?;
+        assert_eq!(file.output.parent(), sealed.join(relative).parent());
+    }
+
+    let restored = temp.path().join("restored");
+    let restore = controller.decrypt_directory(&sealed, &restored).await?;
+    assert_eq!(restore.restored.len(), 3);
+    assert_eq!(
+        fs::read_to_string(restored.join("docs/drafts/v1.md")).await?,
+        "docs/drafts/v1.md"
+    );
+    controller.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_walk_settings_are_rejected() -> Result<()> {
+    let controller = Controller::new(new_default());
+    assert!(controller
+        .set_walk_settings(WalkSettings {
+            parallel_files: Some(0),
+            ..WalkSettings::default()
+        })
+        .is_err());
+    assert!(controller
+        .set_walk_settings(WalkSettings {
+            exclude: vec!["src/{a,b".into()],
+            ..WalkSettings::default()
+        })
+        .is_err());
+    assert_eq!(controller.walk_settings(), WalkSettings::default());
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use anyhow::Result;
+use dg_controller::{Controller, ControllerEvent, UserError};
+use dg_core::api::new_default;
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn warm_up_primes_the_engine_and_announces_readiness() -> Result<()> {
+    let temp = tempdir()?;
+    let controller = Controller::new(new_default());
+
+    let err = controller.warm_up().await.expect_err("not booted");
+    assert_eq!(
+        UserError::from_anyhow(&err).code,
+        dg_controller::errors::NOT_BOOTED
+    );
+
+    controller
+        .boot("dev", temp.path().join("data"), false)
+        .await?;
+    assert!(!controller.status().await.ready);
+
+    let report = controller.warm_up().await?;
+    let steps: Vec<&str> = report.steps.iter().map(|step| step.name.as_str()).collect();
+    assert_eq!(steps, ["policy", "cipher", "state", "index"]);
+    assert!(controller.status().await.ready);
+
+    let events = controller.replay_events(None).await;
+    assert!(events
+        .iter()
+        .any(|recorded| matches!(&recorded.event, ControllerEvent::Ready(message) if message.starts_with("engine ready"))));
+
+    controller
+        .boot("dev", temp.path().join("data"), false)
+        .await?;
+    assert!(!controller.ready(), "a reboot needs a fresh warm-up");
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use dg_controller::watch::{Debouncer, IgnoreRules};
+use dg_controller::{Controller, WatchEvent, WatchSettings};
+use dg_core::api::new_default;
+use notify::event::{CreateKind, DataChange, EventKind, ModifyKind, RemoveKind, RenameMode};
+use notify::Event;
+use tempfile::tempdir;
+
+const WINDOW: Duration = Duration::from_millis(500);
+
+fn debouncer() -> Debouncer {
+    let ignore = IgnoreRules::new(Path::new("/vault"), &WatchSettings::default().ignore)
+        .expect("default ignore rules");
+    Debouncer::new(WINDOW, ignore)
+}
+
+fn path(name: &str) -> PathBuf {
+    Path::new("/vault").join(name)
+}
+
+fn event(kind: EventKind, names: &[&str]) -> Event {
+    names
+        .iter()
+        .fold(Event::new(kind), |event, name| event.add_path(path(name)))
+}
+
+fn rename(mode: RenameMode, name: &str, cookie: usize) -> Event {
+    event(EventKind::Modify(ModifyKind::Name(mode)), &[name]).set_tracker(cookie)
+}
+
+#[test]
+fn bursts_settle_into_one_event_per_path() {
+    let mut debouncer = debouncer();
+    let start = Instant::now();
+    debouncer.push(
+        &event(EventKind::Create(CreateKind::File), &["report.pdf"]),
+        start,
+    );
+    for step in 1..20 {
+        let write = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+        debouncer.push(
+            &event(write, &["report.pdf"]),
+            start + Duration::from_millis(step * 100),
+        );
+    }
+    let last = start + Duration::from_millis(1900);
+    assert!(debouncer.ready(last + WINDOW / 2).is_empty());
+    assert_eq!(debouncer.next_deadline(), Some(last + WINDOW));
+    assert_eq!(
+        debouncer.ready(last + WINDOW),
+        vec![WatchEvent::Changed {
+            path: path("report.pdf")
+        }]
+    );
+    assert!(debouncer.next_deadline().is_none());
+}
+
+#[test]
+fn rename_halves_are_paired_by_cookie() {
+    let mut debouncer = debouncer();
+    let now = Instant::now();
+    debouncer.push(&rename(RenameMode::From, "a.txt", 7), now);
+    debouncer.push(&rename(RenameMode::From, "c.txt", 8), now);
+    debouncer.push(&rename(RenameMode::To, "d.txt", 8), now);
+    debouncer.push(&rename(RenameMode::To, "b.txt", 7), now);
+    assert_eq!(
+        debouncer.ready(now + WINDOW),
+        vec![
+            WatchEvent::Renamed {
+                from: path("a.txt"),
+                to: path("b.txt")
+            },
+            WatchEvent::Renamed {
+                from: path("c.txt"),
+                to: path("d.txt")
+            },
+        ]
+    );
+
+    // A source whose target landed outside the folder was moved away.
+    debouncer.push(&rename(RenameMode::From, "gone.txt", 9), now);
+    debouncer.push(&rename(RenameMode::To, "arrived.txt", 10), now);
+    assert_eq!(
+        debouncer.ready(now + WINDOW),
+        vec![
+            WatchEvent::Changed {
+                path: path("arrived.txt")
+            },
+            WatchEvent::Removed {
+                path: path("gone.txt")
+            },
+        ]
+    );
+}
+
+#[test]
+fn ignored_names_are_dropped_and_atomic_saves_become_changes() {
+    let mut debouncer = debouncer();
+    let now = Instant::now();
+    debouncer.push(
+        &event(
+            EventKind::Create(CreateKind::File),
+            &[".git/objects/ab", "draft.docx.tmp", "~$draft.docx"],
+        ),
+        now,
+    );
+    debouncer.push(
+        &event(
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+            &["draft.docx.tmp", "draft.docx"],
+        ),
+        now,
+    );
+    debouncer.push(
+        &event(
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+            &["notes.md", "notes.md.partial"],
+        ),
+        now,
+    );
+    assert_eq!(
+        debouncer.ready(now + WINDOW),
+        vec![
+            WatchEvent::Changed {
+                path: path("draft.docx")
+            },
+            WatchEvent::Removed {
+                path: path("notes.md")
+            },
+        ]
+    );
+}
+
+#[test]
+fn chained_renames_collapse_and_deletes_report_the_original_name() {
+    let mut debouncer = debouncer();
+    let now = Instant::now();
+    let rename = |from, to| {
+        event(
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+            &[from, to],
+        )
+    };
+    debouncer.push(&rename("a.txt", "b.txt"), now);
+    debouncer.push(&rename("b.txt", "c.txt"), now);
+    debouncer.push(&rename("x.txt", "y.txt"), now);
+    debouncer.push(&event(EventKind::Remove(RemoveKind::File), &["y.txt"]), now);
+    assert_eq!(
+        debouncer.ready(now + WINDOW),
+        vec![
+            WatchEvent::Renamed {
+                from: path("a.txt"),
+                to: path("c.txt")
+            },
+            WatchEvent::Removed {
+                path: path("x.txt")
+            },
+        ]
+    );
+}
+
+#[test]
+fn invalid_watch_settings_are_rejected() -> Result<()> {
+    let controller = Controller::new(new_default());
+    let mut settings = WatchSettings {
+        ignore: vec!["[".into()],
+        ..WatchSettings::default()
+    };
+    assert!(controller.set_watch_settings(settings.clone()).is_err());
+    settings.ignore = vec!["*.bak".into()];
+    settings.debounce_ms = 0;
+    assert!(controller.set_watch_settings(settings.clone()).is_err());
+    settings.debounce_ms = 200;
+    controller.set_watch_settings(settings.clone())?;
+    assert_eq!(controller.watch_settings(), settings);
+    Ok(())
+}
+
+#[tokio::test]
+async fn folder_watch_reports_settled_changes() -> Result<()> {
+    let dir = tempdir()?;
+    let controller = Controller::new(new_default());
+    controller.set_watch_settings(WatchSettings {
+        debounce_ms: 100,
+        ..WatchSettings::default()
+    })?;
+    let mut watch = controller.watch_folder(dir.path())?;
+    let root = watch.root().to_path_buf();
+
+    tokio::fs::write(root.join(".hidden"), b"skip").await?;
+    for chunk in 0..5 {
+        tokio::fs::write(root.join("ledger.csv"), format!("row {chunk}\n")).await?;
+    }
+    let mut seen = Vec::new();
+    while !seen.contains(&WatchEvent::Changed {
+        path: root.join("ledger.csv"),
+    }) {
+        let batch = tokio::time::timeout(Duration::from_secs(10), watch.next())
+            .await?
+            .expect("watch running");
+        seen.extend(batch);
+    }
+    assert!(seen.iter().all(|event| !matches!(
+        event,
+        WatchEvent::Changed { path } if path.ends_with(".hidden")
+    )));
+    Ok(())
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use common::booted;
+use dg_controller::xattrs::{self, SELINUX_ATTRIBUTE};
+use dg_controller::ExtendedAttributes;
+use tempfile::tempdir;
+
+#[test]
+fn only_user_trusted_and_selinux_attributes_are_captured() {
+    let captured = ExtendedAttributes::from_raw(BTreeMap::from([
+        ("user.origin".to_string(), b"ledger".to_vec()),
+        ("trusted.md5".to_string(), vec![0xde, 0xad]),
+        (
+            SELINUX_ATTRIBUTE.to_string(),
+            b"system_u:object_r:httpd_sys_content_t:s0\0".to_vec(),
+        ),
+        ("security.capability".to_string(), vec![1, 0, 0, 2]),
+        ("system.posix_acl_access".to_string(), vec![2, 0, 0, 0]),
+    ]));
+    assert_eq!(
+        captured.attributes.keys().collect::<Vec<_>>(),
+        ["trusted.md5", "user.origin"]
+    );
+    assert_eq!(captured.attributes["user.origin"], "bGVkZ2Vy");
+    assert_eq!(
+        captured.selinux.as_deref(),
+        Some("system_u:object_r:httpd_sys_content_t:s0")
+    );
+    assert!(ExtendedAttributes::from_raw(BTreeMap::new()).is_empty());
+}
+
+#[test]
+fn malformed_values_are_reported_as_skipped() -> Result<()> {
+    let temp = tempdir()?;
+    let target = temp.path().join("report.csv");
+    std::fs::write(&target, b"a,b")?;
+    let attributes = ExtendedAttributes {
+        attributes: BTreeMap::from([
+            ("user.origin".to_string(), "not base64!".to_string()),
+            ("security.capability".to_string(), "AQAAAg==".to_string()),
+        ]),
+        selinux: None,
+    };
+    let report = xattrs::restore(&target, &attributes);
+    assert!(report.restored.is_empty());
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0].name, "user.origin");
+    assert!(report.skipped[0].reason.contains("malformed"));
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn user_attributes_survive_the_round_trip() -> Result<()> {
+    use dg_controller::StoredEnvelope;
+
+    use tokio::fs;
+
+    let temp = tempdir()?;
+    let controller = booted(temp.path().join("data")).await?;
+    let source = temp.path().join("ledger.csv");
+    fs::write(&source, b"account,balance").await?;
+    let seeded = xattrs::restore(
+        &source,
+        &ExtendedAttributes {
+            attributes: BTreeMap::from([("user.origin".to_string(), "bGVkZ2Vy".to_string())]),
+            selinux: None,
+        },
+    );
+    if !seeded.skipped.is_empty() {
+        // The temporary directory's file system has no user attributes.
+        return Ok(());
+    }
+
+    let envelope = controller
+        .encrypt_file(&source, vec![], vec![], None)
+        .await?;
+    let recorded = xattrs::read(&StoredEnvelope::read(&envelope).await?.meta).expect("recorded");
+    assert_eq!(recorded.attributes["user.origin"], "bGVkZ2Vy");
+
+    let out = temp.path().join("out");
+    fs::create_dir_all(&out).await?;
+    let decrypted = controller.decrypt_file(&envelope, Some(out)).await?;
+    assert_eq!(
+        xattrs::capture(&decrypted).attributes.get("user.origin"),
+        Some(&"bGVkZ2Vy".to_string())
+    );
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use dg_core::api::{new_default, DGConfig, EncryptRequest};
+use tempfile::tempdir;
+
+const SIZES: [usize; 3] = [1 << 10, 64 << 10, 1 << 20];
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("runtime")
+}
+
+fn request(size: usize) -> EncryptRequest {
+    EncryptRequest {
+        plaintext: vec![0x5a; size],
+        ..Default::default()
+    }
+}
+
+fn crypto(c: &mut Criterion) {
+    let rt = runtime();
+    let temp = tempdir().expect("tempdir");
+    let engine = new_default();
+    rt.block_on(engine.init(DGConfig {
+        profile: "dev".into(),
+        data_dir: temp.path().to_path_buf(),
+        telemetry: false,
+        ..Default::default()
+    }))
+    .expect("init");
+
+    let mut encrypt = c.benchmark_group("encrypt");
+    for size in SIZES {
+        encrypt.throughput(Throughput::Bytes(size as u64));
+        encrypt.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter(|| rt.block_on(engine.encrypt(request(size))).expect("encrypt"))
+        });
+    }
+    encrypt.finish();
+
+    let mut decrypt = c.benchmark_group("decrypt");
+    for size in SIZES {
+        let envelope = rt.block_on(engine.encrypt(request(size))).expect("encrypt");
+        decrypt.throughput(Throughput::Bytes(size as u64));
+        decrypt.bench_with_input(
+            BenchmarkId::from_parameter(size),
+            &envelope,
+            |b, envelope| {
+                b.iter(|| {
+                    rt.block_on(engine.decrypt(envelope.clone()))
+                        .expect("decrypt")
+                })
+            },
+        );
+    }
+    decrypt.finish();
+
+    rt.block_on(engine.shutdown()).expect("shutdown");
+}
+
+criterion_group!(benches, crypto);
+criterion_main!(benches);
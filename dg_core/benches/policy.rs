@@ -0,0 +1,51 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use dg_core::PolicyEngine;
+use serde_json::json;
+
+const RULE_COUNTS: [usize; 3] = [1, 64, 1024];
+
+fn document(rules: usize) -> Vec<u8> {
+    let rules: Vec<_> = (0..rules)
+        .map(|index| {
+            json!({
+                "subject": format!("team-{index}/*"),
+                "action": "decrypt",
+                "resource": format!("vault/{index}/**"),
+                "effect": "allow",
+            })
+        })
+        .collect();
+    serde_json::to_vec(&json!({ "default_allow": false, "rules": rules })).expect("policy json")
+}
+
+fn policy(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("runtime");
+
+    let mut parse = c.benchmark_group("policy_parse");
+    for rules in RULE_COUNTS {
+        let bytes = document(rules);
+        parse.bench_with_input(BenchmarkId::from_parameter(rules), &bytes, |b, bytes| {
+            b.iter(|| PolicyEngine::parse(bytes).expect("parse"))
+        });
+    }
+    parse.finish();
+
+    let mut evaluate = c.benchmark_group("policy_evaluate");
+    for rules in RULE_COUNTS {
+        let engine = PolicyEngine::parse(&document(rules)).expect("parse");
+        let subject = format!("team-{}/alice", rules - 1);
+        let resource = format!("vault/{}/reports/q3.pdf", rules - 1);
+        evaluate.bench_function(BenchmarkId::from_parameter(rules), |b| {
+            b.iter(|| {
+                rt.block_on(engine.evaluate(&subject, "decrypt", &resource))
+                    .expect("evaluate")
+            })
+        });
+    }
+    evaluate.finish();
+}
+
+criterion_group!(benches, policy);
+criterion_main!(benches);
@@ -1,20 +1,84 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+use crate::audit::{AuditEvent, AuditFilter};
+use crate::chunking::{ChunkingParams, RangeVerification};
+use crate::grant::{GrantAction, GrantRequest, SignedGrant};
+use crate::identity::Identity;
+use crate::index::{IndexEntry, Reconciliation};
+use crate::integrity::IntegrityReport;
+use crate::journal::JournalAppend;
+use crate::key_age::{KeyExpiryReport, KeyRotation, MaxKeyAge};
+use crate::keyring::KeyMetadata;
+use crate::metadata::CustomMetadata;
+use crate::offline::{OfflineRequest, OfflineResponse};
+use crate::passphrase::KeyProtection;
+use crate::policy::DecisionExplanation;
+use crate::policy_cache::PolicyCacheStats;
+use crate::preview::Preview;
+use crate::provenance::RekeyContext;
+use crate::selftest::SelfTestReport;
+use crate::state::StateBackend;
+use crate::subkey::KeyHierarchy;
+use crate::threshold::KeyShare;
+use crate::warmup::WarmupReport;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct DGConfig {
     pub profile: String,
     pub data_dir: PathBuf,
     pub telemetry: bool,
+    #[serde(default)]
+    pub self_test_on_init: bool,
+    #[serde(default)]
+    pub repair_on_init: bool,
+    #[serde(default)]
+    pub state_backend: StateBackend,
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deterministic_seed: Option<u64>,
+    #[serde(default)]
+    pub key_protection: KeyProtection,
+    // Overridden by `max_key_age` in the policy document.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_key_age: Option<MaxKeyAge>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+// What an initialised engine is serving, as reported by `core.health`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadedProfile {
+    pub profile: String,
+    pub data_dir: PathBuf,
+    pub read_only: bool,
+    // The master key is protected by a passphrase and has not been unlocked.
+    pub locked: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct EncryptRequest {
     pub plaintext: Vec<u8>,
     pub labels: Vec<String>,
     pub recipients: Vec<String>,
+    #[serde(default)]
+    pub threshold: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<Preview>,
+    #[serde(default, skip_serializing_if = "CustomMetadata::is_empty")]
+    pub metadata: CustomMetadata,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunking: Option<ChunkingParams>,
+    // An earlier version of the same file. Its file key is reused so unchanged chunks can be
+    // copied over instead of encrypted again; implies chunking.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<Envelope>,
+    // Seals the plaintext as the first records of an append-mode envelope that `append` can
+    // extend later. Cannot be combined with chunking, `previous` or a threshold.
+    #[serde(default)]
+    pub journal: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -31,18 +95,138 @@ pub enum DGError {
     Crypto(String),
     #[error("config error: {0}")]
     Config(String),
+    #[error("read-only data directory: {0}")]
+    ReadOnly(String),
+    #[error("master key locked: {0}")]
+    Locked(String),
     #[error("internal: {0}")]
     Internal(String),
 }
 
+impl DGError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            DGError::PolicyDenied(_) => "engine.policy_denied",
+            DGError::Crypto(_) => "engine.crypto",
+            DGError::Config(_) => "engine.config",
+            DGError::ReadOnly(_) => "engine.read_only",
+            DGError::Locked(_) => "engine.locked",
+            DGError::Internal(_) => "engine.internal",
+        }
+    }
+
+    pub fn detail(&self) -> &str {
+        match self {
+            DGError::PolicyDenied(detail)
+            | DGError::Crypto(detail)
+            | DGError::Config(detail)
+            | DGError::ReadOnly(detail)
+            | DGError::Locked(detail)
+            | DGError::Internal(detail) => detail,
+        }
+    }
+}
+
 pub type DGResult<T> = Result<T, DGError>;
 
 #[async_trait::async_trait]
 pub trait DataGuardian {
     async fn init(&self, cfg: DGConfig) -> DGResult<()>;
     async fn encrypt(&self, req: EncryptRequest) -> DGResult<Envelope>;
-    async fn decrypt(&self, env: Envelope) -> DGResult<Vec<u8>>;
+    async fn decrypt(&self, env: Envelope) -> DGResult<Zeroizing<Vec<u8>>>;
     async fn check_policy(&self, subject: &str, action: &str, resource: &str) -> DGResult<bool>;
+    async fn check_identity(
+        &self,
+        identity: &Identity,
+        action: &str,
+        resource: &str,
+    ) -> DGResult<bool>;
+    async fn explain_decision(
+        &self,
+        identity: &Identity,
+        action: &str,
+        resource: &str,
+    ) -> DGResult<DecisionExplanation>;
+    async fn import_private_key(&self, pem: &str, label: &str) -> DGResult<KeyMetadata>;
+    async fn import_public_key(&self, pem: &str, contact: &str) -> DGResult<KeyMetadata>;
+    async fn revoke_key(&self, id: &str) -> DGResult<KeyMetadata>;
+    async fn generate_identity(&self, label: &str) -> DGResult<KeyMetadata>;
+    // Generates a root identity with certified encryption, signing and escrow subkeys. The root's
+    // label names the hierarchy: recipients resolve to its encryption subkey and signers to its
+    // signing subkey.
+    async fn generate_key_hierarchy(&self, label: &str) -> DGResult<KeyHierarchy>;
+    async fn list_identities(&self) -> DGResult<Vec<KeyMetadata>>;
+    // Every active key with its age under the effective `max_key_age`; empty when none is set.
+    async fn key_expiry(&self) -> DGResult<KeyExpiryReport>;
+    // Replaces an X25519 identity or a subkey with a new key under the same label; `rekey` moves
+    // envelopes wrapped to the retired key over to its replacement.
+    async fn rotate_key(&self, id: &str) -> DGResult<KeyRotation>;
+    async fn rekey(&self, env: Envelope, context: RekeyContext) -> DGResult<Envelope>;
+    async fn sign_detached(&self, signer: &str, data: &[u8]) -> DGResult<Vec<u8>>;
+    async fn verify_detached(&self, signer: &str, data: &[u8], signature: &[u8]) -> DGResult<bool>;
+    async fn self_test(&self) -> DGResult<SelfTestReport>;
+    async fn warm_up(&self) -> DGResult<WarmupReport>;
+    async fn integrity(&self, repair: bool) -> DGResult<IntegrityReport>;
+    async fn collect_share(&self, env: &Envelope) -> DGResult<Vec<KeyShare>>;
+    async fn combine_shares(
+        &self,
+        env: Envelope,
+        shares: Vec<KeyShare>,
+    ) -> DGResult<Zeroizing<Vec<u8>>>;
+    async fn issue_grant(&self, env: &Envelope, request: GrantRequest) -> DGResult<SignedGrant>;
+    async fn decrypt_with_grant(
+        &self,
+        env: Envelope,
+        grant: SignedGrant,
+        action: GrantAction,
+    ) -> DGResult<Zeroizing<Vec<u8>>>;
+    // The air-gapped exchange: the online machine exports a request for an envelope it cannot
+    // open, a machine holding one of its recipient keys answers it, and the online machine
+    // completes the decryption with the answer.
+    async fn export_offline_request(&self, env: &Envelope) -> DGResult<OfflineRequest>;
+    async fn answer_offline_request(&self, request: OfflineRequest) -> DGResult<OfflineResponse>;
+    async fn complete_offline_request(
+        &self,
+        env: Envelope,
+        response: OfflineResponse,
+    ) -> DGResult<Zeroizing<Vec<u8>>>;
+    async fn preview(&self, env: &Envelope) -> DGResult<Option<Preview>>;
+    // Authenticates plaintext bytes `offset..offset + len` of a chunked envelope by opening only
+    // the chunks that hold them.
+    async fn verify_range(
+        &self,
+        env: &Envelope,
+        offset: u64,
+        len: u64,
+    ) -> DGResult<RangeVerification>;
+    // Decrypts plaintext bytes `offset..offset + len` of a chunked envelope from the chunks that
+    // hold them. The range must be non-empty and lie within the plaintext.
+    async fn decrypt_range(
+        &self,
+        env: &Envelope,
+        offset: u64,
+        len: u64,
+    ) -> DGResult<Zeroizing<Vec<u8>>>;
+    // Seals `data` as new records of an append-mode envelope and returns the frames to write after
+    // its last commit marker, dropping any torn append after it.
+    async fn append(&self, env: &Envelope, data: Vec<u8>) -> DGResult<JournalAppend>;
+    // The envelope index has one writer, the engine that owns the data directory, so shells reach
+    // it over RPC while a daemon runs. Entries are re-read on every call to pick up other writers.
+    async fn index_entries(&self) -> DGResult<Vec<IndexEntry>>;
+    async fn index_record(&self, entry: IndexEntry) -> DGResult<()>;
+    async fn index_remove(&self, path: &Path) -> DGResult<()>;
+    async fn index_reconcile(
+        &self,
+        roots: Vec<PathBuf>,
+        observed: Vec<IndexEntry>,
+    ) -> DGResult<Reconciliation>;
+    async fn reload_policy(&self) -> DGResult<PolicyCacheStats>;
+    async fn policy_cache_stats(&self) -> DGResult<PolicyCacheStats>;
+    async fn query_audit(&self, filter: AuditFilter) -> DGResult<Vec<AuditEvent>>;
+    // None until `init` succeeds and again after `shutdown`.
+    async fn loaded_profile(&self) -> DGResult<Option<LoadedProfile>>;
+    async fn lock(&self) -> DGResult<()>;
+    async fn unlock(&self, passphrase: &str) -> DGResult<()>;
     async fn shutdown(&self) -> DGResult<()>;
 }
 
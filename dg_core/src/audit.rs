@@ -0,0 +1,187 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::api::{DGError, DGResult};
+
+const AUDIT_DIR: &str = "audit";
+const AUDIT_FILE: &str = "audit.log";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub timestamp: u64,
+    pub subject: String,
+    pub action: String,
+    pub resource: String,
+    pub effect: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(subject: &str, action: &str, resource: &str, effect: &str) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            subject: subject.to_owned(),
+            action: action.to_owned(),
+            resource: resource.to_owned(),
+            effect: effect.to_owned(),
+            details: None,
+            device: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+// Every set field has to match: subject, action and effect exactly, resource as a substring.
+// `since` is inclusive and `until` exclusive, both in Unix seconds.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditFilter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effect: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, event: &AuditEvent) -> bool {
+        let exact = |wanted: &Option<String>, actual: &str| {
+            wanted.as_deref().is_none_or(|wanted| wanted == actual)
+        };
+        exact(&self.subject, &event.subject)
+            && exact(&self.action, &event.action)
+            && exact(&self.effect, &event.effect)
+            && self
+                .resource
+                .as_deref()
+                .is_none_or(|part| event.resource.contains(part))
+            && self.since.is_none_or(|since| event.timestamp >= since)
+            && self.until.is_none_or(|until| event.timestamp < until)
+    }
+}
+
+pub struct AuditLog {
+    path: PathBuf,
+    write_lock: Mutex<()>,
+    read_only: bool,
+    device: Option<String>,
+}
+
+impl AuditLog {
+    pub async fn open(data_dir: &Path) -> DGResult<Self> {
+        let dir = data_dir.join(AUDIT_DIR);
+        fs::create_dir_all(&dir)
+            .await
+            .map_err(|err| DGError::Config(format!("unable to create audit directory: {err}")))?;
+        Ok(Self {
+            path: dir.join(AUDIT_FILE),
+            write_lock: Mutex::new(()),
+            read_only: false,
+            device: None,
+        })
+    }
+
+    pub fn open_read_only(data_dir: &Path) -> Self {
+        Self {
+            path: data_dir.join(AUDIT_DIR).join(AUDIT_FILE),
+            write_lock: Mutex::new(()),
+            read_only: true,
+            device: None,
+        }
+    }
+
+    pub fn with_device(mut self, device_id: Option<String>) -> Self {
+        self.device = device_id;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub async fn record(&self, mut event: AuditEvent) -> DGResult<()> {
+        if self.read_only {
+            debug!(action = %event.action, effect = %event.effect, "audit event not recorded in read-only mode");
+            return Ok(());
+        }
+        if event.device.is_none() {
+            event.device = self.device.clone();
+        }
+        let mut line = serde_json::to_vec(&event)
+            .map_err(|err| DGError::Internal(format!("unable to encode audit event: {err}")))?;
+        line.push(b'\n');
+
+        let _guard = self.write_lock.lock().await;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(|err| DGError::Internal(format!("unable to open audit log: {err}")))?;
+        file.write_all(&line)
+            .await
+            .map_err(|err| DGError::Internal(format!("unable to append audit event: {err}")))?;
+        file.sync_data()
+            .await
+            .map_err(|err| DGError::Internal(format!("unable to flush audit log: {err}")))
+    }
+
+    // Newest first; `limit` keeps the most recent matches.
+    pub async fn query(&self, filter: &AuditFilter) -> DGResult<Vec<AuditEvent>> {
+        let mut events: Vec<AuditEvent> = self
+            .read_all()
+            .await?
+            .into_iter()
+            .rev()
+            .filter(|event| filter.matches(event))
+            .collect();
+        if let Some(limit) = filter.limit {
+            events.truncate(limit);
+        }
+        Ok(events)
+    }
+
+    pub async fn read_all(&self) -> DGResult<Vec<AuditEvent>> {
+        let content = match fs::read_to_string(&self.path).await {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(DGError::Internal(format!(
+                    "unable to read audit log: {err}"
+                )))
+            }
+        };
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .map_err(|err| DGError::Internal(format!("corrupt audit entry: {err}")))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,516 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use fastcdc::v2020::{
+    FastCDC, AVERAGE_MAX, AVERAGE_MIN, MAXIMUM_MAX, MAXIMUM_MIN, MINIMUM_MAX, MINIMUM_MIN,
+};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::api::{DGError, DGResult};
+use crate::engine::{open_payload_with, seal_payload_with};
+use crate::entropy::Entropy;
+
+pub const CHUNKS_FIELD: &str = "chunks";
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const ID_CONTEXT: &str = "data-guardian chunking 2024 chunk id";
+const TABLE_CONTEXT: &str = "data-guardian chunking 2024 chunk table";
+const ROOT_TABLE_CONTEXT: &str = "data-guardian chunking 2024 chunk table root";
+const LEAF_CONTEXT: &str = "data-guardian chunking 2024 merkle leaf";
+const NODE_CONTEXT: &str = "data-guardian chunking 2024 merkle node";
+
+// FastCDC bounds in bytes. The defaults suit files from a few megabytes to many gigabytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkingParams {
+    pub min_size: u32,
+    pub avg_size: u32,
+    pub max_size: u32,
+}
+
+impl Default for ChunkingParams {
+    fn default() -> Self {
+        Self {
+            min_size: 16 * 1024,
+            avg_size: 64 * 1024,
+            max_size: 256 * 1024,
+        }
+    }
+}
+
+impl ChunkingParams {
+    pub fn validate(&self) -> DGResult<()> {
+        let in_range = (MINIMUM_MIN..=MINIMUM_MAX).contains(&self.min_size)
+            && (AVERAGE_MIN..=AVERAGE_MAX).contains(&self.avg_size)
+            && (MAXIMUM_MIN..=MAXIMUM_MAX).contains(&self.max_size);
+        if !in_range || self.min_size > self.avg_size || self.avg_size > self.max_size {
+            return Err(DGError::Config(format!(
+                "chunk sizes must satisfy {MINIMUM_MIN} <= min <= avg <= max <= {MAXIMUM_MAX} \
+                 with avg between {AVERAGE_MIN} and {AVERAGE_MAX}; got {}/{}/{}",
+                self.min_size, self.avg_size, self.max_size
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn boundaries(&self, data: &[u8]) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> =
+            FastCDC::new(data, self.min_size, self.avg_size, self.max_size)
+                .map(|chunk| chunk.offset..chunk.offset + chunk.length)
+                .collect();
+        // An empty file is one empty chunk, so every payload starts with a nonce.
+        if ranges.is_empty() {
+            ranges.push(0..0);
+        }
+        ranges
+    }
+}
+
+// A chunk is identified by a hash keyed with the file key, so ids only match between versions
+// sealed under the same key and reveal nothing about the content to anyone else.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkRef {
+    pub id: String,
+    pub len: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkTable {
+    pub params: ChunkingParams,
+    pub chunks: Vec<ChunkRef>,
+    // Merkle root over the chunks and their plaintext offsets. Tables written before it existed
+    // have none and can only be verified as a whole.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    // Keyed hash over the bound metadata, the parameters and the root, or the ordered chunk list
+    // when there is no root.
+    pub tag: String,
+}
+
+impl ChunkTable {
+    pub fn plaintext_len(&self) -> u64 {
+        self.chunks.iter().map(|chunk| chunk.len).sum()
+    }
+
+    pub fn merkle_root(&self) -> DGResult<blake3::Hash> {
+        let mut level = self.leaves()?;
+        while level.len() > 1 {
+            level = parents(&level);
+        }
+        Ok(level
+            .pop()
+            .unwrap_or_else(|| node_hasher(LEAF_CONTEXT).finalize()))
+    }
+
+    // Sibling hashes from chunk `index` up to the root. An unpaired node moves up a level as is,
+    // so those levels contribute no sibling.
+    pub fn proof(&self, index: usize) -> DGResult<MerkleProof> {
+        if index >= self.chunks.len() {
+            return Err(DGError::Config(format!(
+                "chunk {index} is out of range; the table has {} chunks",
+                self.chunks.len()
+            )));
+        }
+        let mut level = self.leaves()?;
+        let mut position = index;
+        let mut siblings = Vec::new();
+        while level.len() > 1 {
+            let sibling = position ^ 1;
+            if sibling < level.len() {
+                siblings.push(level[sibling].to_hex().to_string());
+            }
+            level = parents(&level);
+            position /= 2;
+        }
+        Ok(MerkleProof {
+            index,
+            leaves: self.chunks.len(),
+            offset: self.chunks[..index].iter().map(|chunk| chunk.len).sum(),
+            siblings,
+        })
+    }
+
+    // The chunks holding plaintext bytes `offset..offset + len`.
+    pub fn span(&self, offset: u64, len: u64) -> DGResult<ChunkSpan> {
+        let total = self.plaintext_len();
+        let end = offset
+            .checked_add(len)
+            .filter(|end| len > 0 && *end <= total)
+            .ok_or_else(|| {
+                DGError::Config(format!(
+                    "range {offset}+{len} is empty or outside the {total} byte plaintext"
+                ))
+            })?;
+        let mut chunk_start = 0u64;
+        let mut first = None;
+        for (index, chunk) in self.chunks.iter().enumerate() {
+            let chunk_end = chunk_start + chunk.len;
+            if first.is_none() && chunk_end > offset {
+                first = Some((index, chunk_start));
+            }
+            if let Some((first, start)) = first.filter(|_| chunk_end >= end) {
+                return Ok(ChunkSpan {
+                    chunks: first..index + 1,
+                    start,
+                });
+            }
+            chunk_start = chunk_end;
+        }
+        unreachable!("a non-empty range inside the plaintext ends in some chunk")
+    }
+
+    fn leaves(&self) -> DGResult<Vec<blake3::Hash>> {
+        let mut offset = 0u64;
+        self.chunks
+            .iter()
+            .map(|chunk| {
+                let hash = leaf(&decode_id(&chunk.id)?, offset, chunk.len);
+                offset += chunk.len;
+                Ok(hash)
+            })
+            .collect()
+    }
+
+    fn segments(&self) -> impl Iterator<Item = (&ChunkRef, usize)> {
+        self.chunks
+            .iter()
+            .map(|chunk| (chunk, NONCE_LEN + chunk.len as usize + TAG_LEN))
+    }
+}
+
+// Places chunk `index` of `leaves` under a Merkle root without the rest of the chunk table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub index: usize,
+    pub leaves: usize,
+    // Plaintext offset of the chunk, which the leaf hash binds.
+    pub offset: u64,
+    pub siblings: Vec<String>,
+}
+
+impl MerkleProof {
+    pub fn verify(&self, root: &str, chunk: &ChunkRef) -> bool {
+        let (Ok(root), Ok(id)) = (blake3::Hash::from_hex(root), decode_id(&chunk.id)) else {
+            return false;
+        };
+        if self.index >= self.leaves {
+            return false;
+        }
+        let mut hash = leaf(&id, self.offset, chunk.len);
+        let mut position = self.index;
+        let mut width = self.leaves;
+        let mut siblings = self.siblings.iter();
+        while width > 1 {
+            if position % 2 == 1 || position + 1 < width {
+                let Some(sibling) = siblings
+                    .next()
+                    .and_then(|hex| blake3::Hash::from_hex(hex).ok())
+                else {
+                    return false;
+                };
+                hash = if position % 2 == 1 {
+                    node(&sibling, &hash)
+                } else {
+                    node(&hash, &sibling)
+                };
+            }
+            position /= 2;
+            width = width.div_ceil(2);
+        }
+        siblings.next().is_none() && hash == root
+    }
+}
+
+// Chunk indices covering a plaintext range, and the plaintext offset of the first of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkSpan {
+    pub chunks: Range<usize>,
+    pub start: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RangeVerification {
+    pub offset: u64,
+    pub len: u64,
+    pub first_chunk: usize,
+    pub chunks: usize,
+    // The chunk table's Merkle root, for checking `ChunkTable::proof`s handed out later.
+    pub root: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DedupStats {
+    pub chunks: usize,
+    pub reused_chunks: usize,
+    pub bytes: u64,
+    pub reused_bytes: u64,
+}
+
+impl DedupStats {
+    // How much of `current` was carried over from `previous` instead of encrypted again.
+    pub fn between(previous: &ChunkTable, current: &ChunkTable) -> Self {
+        let known: HashSet<&str> = previous
+            .chunks
+            .iter()
+            .map(|chunk| chunk.id.as_str())
+            .collect();
+        current
+            .chunks
+            .iter()
+            .fold(Self::default(), |mut stats, chunk| {
+                stats.chunks += 1;
+                stats.bytes += chunk.len;
+                if known.contains(chunk.id.as_str()) {
+                    stats.reused_chunks += 1;
+                    stats.reused_bytes += chunk.len;
+                }
+                stats
+            })
+    }
+}
+
+pub fn read(meta: &serde_json::Value) -> DGResult<Option<ChunkTable>> {
+    meta.get(CHUNKS_FIELD)
+        .map(|field| {
+            serde_json::from_value(field.clone())
+                .map_err(|err| DGError::Crypto(format!("invalid chunk table: {err}")))
+        })
+        .transpose()
+}
+
+// Seals each chunk on its own under the file key. Chunks whose id already appears in `previous`
+// are copied from its payload; they are checked when the new envelope is opened, not here.
+pub(crate) fn seal(
+    entropy: &Entropy,
+    file_key: &[u8; 32],
+    plaintext: &[u8],
+    aad: &[u8],
+    params: ChunkingParams,
+    previous: Option<(&ChunkTable, &[u8], &[u8])>,
+) -> DGResult<(Vec<u8>, ChunkTable)> {
+    params.validate()?;
+    let id_key = Zeroizing::new(blake3::derive_key(ID_CONTEXT, file_key));
+    let reusable = match previous {
+        Some((table, payload, previous_aad)) => {
+            verify(file_key, table, previous_aad)?;
+            segment_ranges(table, payload)?
+                .into_iter()
+                .map(|(chunk, range)| (chunk.id.clone(), &payload[range]))
+                .collect()
+        }
+        None => HashMap::new(),
+    };
+    let mut payload = Vec::with_capacity(plaintext.len() + plaintext.len() / 1024 + 64);
+    let mut chunks = Vec::new();
+    for range in params.boundaries(plaintext) {
+        let chunk = &plaintext[range];
+        let id = blake3::keyed_hash(&id_key, chunk);
+        let id_hex = id.to_hex().to_string();
+        match reusable.get(&id_hex) {
+            Some(segment) => payload.extend_from_slice(segment),
+            None => payload.extend(seal_payload_with(entropy, file_key, chunk, id.as_bytes())?),
+        }
+        chunks.push(ChunkRef {
+            id: id_hex,
+            len: chunk.len() as u64,
+        });
+    }
+    let mut table = ChunkTable {
+        params,
+        chunks,
+        root: None,
+        tag: String::new(),
+    };
+    table.root = Some(table.merkle_root()?.to_hex().to_string());
+    table.tag = tag(file_key, &table, aad)?.to_hex().to_string();
+    Ok((payload, table))
+}
+
+pub(crate) fn open(
+    file_key: &[u8; 32],
+    payload: &[u8],
+    aad: &[u8],
+    table: &ChunkTable,
+) -> DGResult<Vec<u8>> {
+    verify(file_key, table, aad)?;
+    let mut plaintext = Vec::with_capacity(table.plaintext_len() as usize);
+    for (chunk, range) in segment_ranges(table, payload)? {
+        let id = decode_id(&chunk.id)?;
+        let opened = open_payload_with(file_key, &payload[range], &id)?;
+        if opened.len() as u64 != chunk.len {
+            return Err(DGError::Crypto(format!(
+                "chunk {} does not match its recorded length",
+                chunk.id
+            )));
+        }
+        plaintext.extend(opened);
+    }
+    Ok(plaintext)
+}
+
+// Opens only the chunks holding `offset..offset + len`; the rest of the payload is never read.
+pub(crate) fn open_range(
+    file_key: &[u8; 32],
+    payload: &[u8],
+    aad: &[u8],
+    table: &ChunkTable,
+    offset: u64,
+    len: u64,
+) -> DGResult<(Vec<u8>, RangeVerification)> {
+    verify(file_key, table, aad)?;
+    let span = table.span(offset, len)?;
+    let mut segment_start = table
+        .segments()
+        .take(span.chunks.start)
+        .map(|(_, len)| len)
+        .sum::<usize>();
+    let mut opened = Zeroizing::new(Vec::new());
+    for index in span.chunks.clone() {
+        let chunk = &table.chunks[index];
+        let segment_len = NONCE_LEN + chunk.len as usize + TAG_LEN;
+        let segment = segment_start
+            .checked_add(segment_len)
+            .filter(|end| *end <= payload.len())
+            .map(|end| &payload[segment_start..end])
+            .ok_or_else(|| DGError::Crypto("payload is shorter than its chunk table".into()))?;
+        let plaintext = Zeroizing::new(open_payload_with(
+            file_key,
+            segment,
+            &decode_id(&chunk.id)?,
+        )?);
+        if plaintext.len() as u64 != chunk.len {
+            return Err(DGError::Crypto(format!(
+                "chunk {} does not match its recorded length",
+                chunk.id
+            )));
+        }
+        opened.extend_from_slice(&plaintext);
+        segment_start += segment_len;
+    }
+    let skip = (offset - span.start) as usize;
+    let range = opened[skip..skip + len as usize].to_vec();
+    Ok((
+        range,
+        RangeVerification {
+            offset,
+            len,
+            first_chunk: span.chunks.start,
+            chunks: span.chunks.len(),
+            root: table.root.clone(),
+        },
+    ))
+}
+
+fn verify(file_key: &[u8; 32], table: &ChunkTable, aad: &[u8]) -> DGResult<()> {
+    let recorded = blake3::Hash::from_hex(&table.tag)
+        .map_err(|_| DGError::Crypto("invalid chunk table tag".into()))?;
+    // `blake3::Hash` compares in constant time.
+    if tag(file_key, table, aad)? != recorded {
+        return Err(DGError::Crypto(
+            "chunk table does not match this envelope".into(),
+        ));
+    }
+    // The tag only covers the root, so the chunk list has to hash to it.
+    if let Some(root) = &table.root {
+        let root = blake3::Hash::from_hex(root)
+            .map_err(|_| DGError::Crypto("invalid chunk table root".into()))?;
+        if table.merkle_root()? != root {
+            return Err(DGError::Crypto(
+                "chunk list does not match the chunk table root".into(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn tag(file_key: &[u8; 32], table: &ChunkTable, aad: &[u8]) -> DGResult<blake3::Hash> {
+    let context = match table.root {
+        Some(_) => ROOT_TABLE_CONTEXT,
+        None => TABLE_CONTEXT,
+    };
+    let table_key = Zeroizing::new(blake3::derive_key(context, file_key));
+    let mut hasher = blake3::Hasher::new_keyed(&table_key);
+    hasher.update(&(aad.len() as u64).to_be_bytes());
+    hasher.update(aad);
+    for size in [
+        table.params.min_size,
+        table.params.avg_size,
+        table.params.max_size,
+    ] {
+        hasher.update(&size.to_be_bytes());
+    }
+    match &table.root {
+        Some(root) => {
+            let root = blake3::Hash::from_hex(root)
+                .map_err(|_| DGError::Crypto("invalid chunk table root".into()))?;
+            hasher.update(root.as_bytes());
+            hasher.update(&(table.chunks.len() as u64).to_be_bytes());
+        }
+        None => {
+            for chunk in &table.chunks {
+                hasher.update(&decode_id(&chunk.id)?);
+                hasher.update(&chunk.len.to_be_bytes());
+            }
+        }
+    }
+    Ok(hasher.finalize())
+}
+
+fn node_hasher(context: &str) -> blake3::Hasher {
+    blake3::Hasher::new_derive_key(context)
+}
+
+fn leaf(id: &[u8; 32], offset: u64, len: u64) -> blake3::Hash {
+    let mut hasher = node_hasher(LEAF_CONTEXT);
+    hasher.update(id);
+    hasher.update(&offset.to_be_bytes());
+    hasher.update(&len.to_be_bytes());
+    hasher.finalize()
+}
+
+fn parents(level: &[blake3::Hash]) -> Vec<blake3::Hash> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node(left, right),
+            [single] => *single,
+            _ => unreachable!("chunks(2) yields one or two nodes"),
+        })
+        .collect()
+}
+
+fn node(left: &blake3::Hash, right: &blake3::Hash) -> blake3::Hash {
+    let mut hasher = node_hasher(NODE_CONTEXT);
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    hasher.finalize()
+}
+
+fn decode_id(id: &str) -> DGResult<[u8; 32]> {
+    hex::decode(id)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| DGError::Crypto(format!("invalid chunk id {id}")))
+}
+
+fn segment_ranges<'a>(
+    table: &'a ChunkTable,
+    payload: &[u8],
+) -> DGResult<Vec<(&'a ChunkRef, Range<usize>)>> {
+    let mut offset = 0usize;
+    let mut ranges = Vec::with_capacity(table.chunks.len());
+    for (chunk, len) in table.segments() {
+        let end = offset
+            .checked_add(len)
+            .filter(|end| *end <= payload.len())
+            .ok_or_else(|| DGError::Crypto("payload is shorter than its chunk table".into()))?;
+        ranges.push((chunk, offset..end));
+        offset = end;
+    }
+    if offset != payload.len() {
+        return Err(DGError::Crypto(
+            "payload is longer than its chunk table".into(),
+        ));
+    }
+    Ok(ranges)
+}
@@ -0,0 +1,209 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::{engine::general_purpose, Engine as _};
+use ed25519_dalek::SigningKey;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+use zeroize::Zeroizing;
+
+use crate::api::{DGError, DGResult};
+use crate::entropy::Entropy;
+use crate::fingerprint::Fingerprint;
+use crate::keyring::KeyAlgorithm;
+use crate::signature::{self, SIGNATURE_LEN};
+
+pub const DEVICE_DIR: &str = "device";
+const DEVICE_FILE: &str = "device.json";
+const DEVICE_KEY_FILE: &str = "device.key";
+const DEVICE_ID_BYTES: usize = 16;
+const ENROLLMENT_CONTEXT: &str = "data-guardian device enrollment v1";
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Enrollment {
+    pub endpoint: String,
+    pub organization: String,
+    pub enrolled_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub management_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceIdentity {
+    pub device_id: String,
+    pub public_key: String,
+    pub fingerprint: Fingerprint,
+    pub created_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enrollment: Option<Enrollment>,
+}
+
+pub struct DeviceKey {
+    identity: DeviceIdentity,
+    secret: Zeroizing<[u8; 32]>,
+}
+
+impl DeviceKey {
+    pub async fn load(data_dir: &Path) -> DGResult<Option<Self>> {
+        let Some(identity) = load_identity(data_dir).await? else {
+            return Ok(None);
+        };
+        let key_path = device_dir(data_dir).join(DEVICE_KEY_FILE);
+        let bytes = Zeroizing::new(fs::read(&key_path).await.map_err(|err| {
+            DGError::Config(format!("unable to read {}: {err}", key_path.display()))
+        })?);
+        let secret: [u8; 32] = bytes.as_slice().try_into().map_err(|_| {
+            DGError::Config(format!(
+                "{} has unexpected length {}",
+                key_path.display(),
+                bytes.len()
+            ))
+        })?;
+        let secret = Zeroizing::new(secret);
+        if device_id(&SigningKey::from_bytes(&secret).verifying_key().to_bytes())
+            != identity.device_id
+        {
+            return Err(DGError::Config(format!(
+                "{} does not belong to device {}",
+                key_path.display(),
+                identity.device_id
+            )));
+        }
+        Ok(Some(Self { identity, secret }))
+    }
+
+    // The keypair is created once per data directory and never rotated implicitly.
+    pub(crate) async fn load_or_create(
+        data_dir: &Path,
+        create: bool,
+        entropy: &Entropy,
+    ) -> DGResult<Option<Self>> {
+        if let Some(existing) = Self::load(data_dir).await? {
+            return Ok(Some(existing));
+        }
+        if !create {
+            return Ok(None);
+        }
+        let dir = device_dir(data_dir);
+        fs::create_dir_all(&dir)
+            .await
+            .map_err(|err| DGError::Config(format!("unable to create device directory: {err}")))?;
+        let secret = entropy.key();
+        let public = SigningKey::from_bytes(&secret).verifying_key().to_bytes();
+        let identity = DeviceIdentity {
+            device_id: device_id(&public),
+            public_key: public_pem(&public),
+            fingerprint: Fingerprint::compute(KeyAlgorithm::Ed25519, &public),
+            created_at: unix_now(),
+            enrollment: None,
+        };
+
+        let key_path = dir.join(DEVICE_KEY_FILE);
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options
+            .open(&key_path)
+            .await
+            .map_err(|err| DGError::Config(format!("unable to create device key: {err}")))?;
+        file.write_all(secret.as_slice())
+            .await
+            .map_err(|err| DGError::Config(format!("unable to write device key: {err}")))?;
+        file.sync_all()
+            .await
+            .map_err(|err| DGError::Config(format!("unable to flush device key: {err}")))?;
+        write_identity(data_dir, &identity).await?;
+        info!(device_id = %identity.device_id, "generated device identity");
+        Ok(Some(Self { identity, secret }))
+    }
+
+    pub fn identity(&self) -> &DeviceIdentity {
+        &self.identity
+    }
+
+    pub fn device_id(&self) -> &str {
+        &self.identity.device_id
+    }
+
+    pub fn sign(&self, data: &[u8]) -> [u8; SIGNATURE_LEN] {
+        signature::sign(&self.secret, data)
+    }
+}
+
+// Bytes the device signs to prove it holds the key for the identity it enrolls.
+pub fn enrollment_challenge(device_id: &str, nonce: &str) -> Vec<u8> {
+    format!("{ENROLLMENT_CONTEXT}\n{device_id}\n{nonce}").into_bytes()
+}
+
+pub async fn load_identity(data_dir: &Path) -> DGResult<Option<DeviceIdentity>> {
+    let path = device_dir(data_dir).join(DEVICE_FILE);
+    let raw = match fs::read(&path).await {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(DGError::Config(format!(
+                "unable to read {}: {err}",
+                path.display()
+            )))
+        }
+    };
+    serde_json::from_slice(&raw)
+        .map(Some)
+        .map_err(|err| DGError::Config(format!("unreadable {}: {err}", path.display())))
+}
+
+pub async fn record_enrollment(
+    data_dir: &Path,
+    enrollment: Enrollment,
+) -> DGResult<DeviceIdentity> {
+    let mut identity = load_identity(data_dir)
+        .await?
+        .ok_or_else(|| DGError::Config("this data directory has no device identity".into()))?;
+    identity.enrollment = Some(enrollment);
+    write_identity(data_dir, &identity).await?;
+    Ok(identity)
+}
+
+async fn write_identity(data_dir: &Path, identity: &DeviceIdentity) -> DGResult<()> {
+    let path = device_dir(data_dir).join(DEVICE_FILE);
+    let staging = path.with_extension("json.tmp");
+    let encoded = serde_json::to_vec_pretty(identity)
+        .map_err(|err| DGError::Internal(format!("unable to encode device identity: {err}")))?;
+    fs::write(&staging, encoded)
+        .await
+        .map_err(|err| DGError::Config(format!("unable to write {}: {err}", staging.display())))?;
+    fs::rename(&staging, &path)
+        .await
+        .map_err(|err| DGError::Config(format!("unable to write {}: {err}", path.display())))
+}
+
+fn device_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(DEVICE_DIR)
+}
+
+fn device_id(public: &[u8; 32]) -> String {
+    hex::encode(&Sha256::digest(public)[..DEVICE_ID_BYTES])
+}
+
+fn public_pem(public: &[u8; 32]) -> String {
+    let mut der = ED25519_SPKI_PREFIX.to_vec();
+    der.extend_from_slice(public);
+    format!(
+        "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+        general_purpose::STANDARD.encode(der)
+    )
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
@@ -4,8 +4,9 @@ from __future__ import annotations
 from typing import TYPE_CHECKING, Any
 
 from .log_stream import get_log_stream
+from .progress import get_progress_stream
 
-__all__ = ["DaemonServer", "get_log_stream", "main"]
+__all__ = ["DaemonServer", "get_log_stream", "get_progress_stream", "main"]
 
 
 def __getattr__(name: str) -> Any:  # pragma: no cover - thin wrapper
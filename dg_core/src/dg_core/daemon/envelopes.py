@@ -0,0 +1,293 @@
+"""Read-only access to the envelope metadata index maintained by the engine."""
+from __future__ import annotations
+
+import hashlib
+import json
+import struct
+import time
+from dataclasses import dataclass
+from pathlib import Path
+from typing import Any, Dict, List, Mapping
+
+__all__ = [
+    "DEFAULT_PAGE_SIZE",
+    "MAX_PAGE_SIZE",
+    "EnvelopeIndex",
+    "IndexEntry",
+    "IndexUnavailable",
+    "SearchFilters",
+]
+
+DEFAULT_PAGE_SIZE = 50
+MAX_PAGE_SIZE = 500
+_INDEX_PATH = ("index", "envelopes.json")
+_LOG_PATH = ("index", "envelopes.wal")
+_CHECK_LEN = 16
+_READ_ATTEMPTS = 20
+_READ_DELAY = 0.02
+_KEYRING_PATH = ("keys", "keyring.json")
+_RAW_MARKER = "\0"
+_LABEL_SEPARATOR = "/"
+_ENVELOPE_MAGIC = b"DGEV"
+
+
+class IndexUnavailable(RuntimeError):
+    """Raised when the envelope index cannot be read."""
+
+
+@dataclass(frozen=True)
+class IndexEntry:
+    """One envelope recorded in the index."""
+
+    path: str
+    key_ids: tuple[str, ...]
+    labels: tuple[str, ...]
+    updated_at: int
+
+    @classmethod
+    def from_json(cls, raw: Mapping[str, Any]) -> "IndexEntry":
+        return cls(
+            path=_decode_path(str(raw["path"])),
+            key_ids=tuple(str(key_id) for key_id in raw.get("key_ids", ())),
+            labels=tuple(str(label) for label in raw.get("labels", ())),
+            updated_at=int(raw.get("updated_at", 0)),
+        )
+
+    def to_dict(self) -> Dict[str, Any]:
+        return {
+            "path": self.path,
+            "key_ids": list(self.key_ids),
+            "labels": list(self.labels),
+            "updated_at": self.updated_at,
+        }
+
+
+@dataclass(frozen=True)
+class SearchFilters:
+    """Field filters applied to index entries; unset fields match everything."""
+
+    query: str | None = None
+    label: str | None = None
+    key_id: str | None = None
+    updated_after: int | None = None
+    updated_before: int | None = None
+
+    @classmethod
+    def from_params(cls, params: Mapping[str, Any]) -> "SearchFilters":
+        values: Dict[str, Any] = {}
+        for name in ("query", "label", "key_id"):
+            value = params.get(name)
+            if value is not None and not isinstance(value, str):
+                raise ValueError(f"'{name}' must be a string")
+            values[name] = value
+        for name in ("updated_after", "updated_before"):
+            value = params.get(name)
+            if value is not None and (isinstance(value, bool) or not isinstance(value, int)):
+                raise ValueError(f"'{name}' must be an integer timestamp")
+            values[name] = value
+        return cls(**values)
+
+    def matches(self, entry: IndexEntry) -> bool:
+        if self.query and self.query.casefold() not in entry.path.casefold():
+            return False
+        if self.label and not any(_label_matches(self.label, label) for label in entry.labels):
+            return False
+        if self.key_id and self.key_id not in entry.key_ids:
+            return False
+        if self.updated_after is not None and entry.updated_at < self.updated_after:
+            return False
+        if self.updated_before is not None and entry.updated_at > self.updated_before:
+            return False
+        return True
+
+
+class EnvelopeIndex:
+    """Searches the envelope index under a data directory."""
+
+    def __init__(self, data_dir: Path) -> None:
+        self._data_dir = data_dir
+        self._path = data_dir.joinpath(*_INDEX_PATH)
+
+    @property
+    def path(self) -> Path:
+        return self._path
+
+    def entries(self) -> List[IndexEntry]:
+        # The engine is the only writer. It compacts by replacing the snapshot and then the log;
+        # a log newer than the snapshot means that happened between the two reads.
+        for _ in range(_READ_ATTEMPTS):
+            snapshot = self._read(self._path)
+            log = self._read(self._data_dir.joinpath(*_LOG_PATH))
+            raw = self._replay(snapshot, log)
+            if raw is not None:
+                break
+            time.sleep(_READ_DELAY)
+        else:
+            raise IndexUnavailable(f"{self._path} kept changing while it was read")
+        try:
+            return [IndexEntry.from_json(entry) for entry in raw]
+        except (AttributeError, KeyError, TypeError, ValueError) as exc:
+            raise IndexUnavailable(f"{self._path} is not a valid envelope index") from exc
+
+    def _read(self, path: Path) -> bytes | None:
+        try:
+            return path.read_bytes()
+        except FileNotFoundError:
+            return None
+        except OSError as exc:
+            raise IndexUnavailable(f"unable to read {path}: {exc}") from exc
+
+    def _replay(self, snapshot: bytes | None, log: bytes | None) -> List[Any] | None:
+        try:
+            state = json.loads(snapshot) if snapshot is not None else {}
+            generation = int(state.get("generation", 0))
+            envelopes = {str(raw["path"]): raw for raw in state.get("envelopes", [])}
+        except (AttributeError, KeyError, TypeError, ValueError) as exc:
+            raise IndexUnavailable(f"{self._path} is not a valid envelope index") from exc
+        if log is None:
+            return list(envelopes.values())
+        header, _, changes = log.partition(b"\n")
+        try:
+            log_generation = int(json.loads(header)["generation"])
+        except (KeyError, TypeError, ValueError) as exc:
+            raise IndexUnavailable(f"{self._path} has an unreadable change log") from exc
+        if log_generation > generation:
+            return None
+        if log_generation < generation:
+            return list(envelopes.values())
+        for line in changes.split(b"\n"):
+            change = _decode_change(line)
+            if change is None:
+                continue
+            if change.get("op") == "record" and isinstance(change.get("entry"), dict):
+                envelopes[str(change["entry"].get("path"))] = change["entry"]
+            elif change.get("op") == "remove":
+                envelopes.pop(str(change.get("path")), None)
+        return list(envelopes.values())
+
+    def search(
+        self, filters: SearchFilters, *, offset: int = 0, limit: int = DEFAULT_PAGE_SIZE
+    ) -> Dict[str, Any]:
+        if offset < 0:
+            raise ValueError("'offset' must not be negative")
+        if not 1 <= limit <= MAX_PAGE_SIZE:
+            raise ValueError(f"'limit' must be between 1 and {MAX_PAGE_SIZE}")
+        matches = sorted(
+            (entry for entry in self.entries() if filters.matches(entry)),
+            key=lambda entry: (-entry.updated_at, entry.path),
+        )
+        page = matches[offset : offset + limit]
+        next_offset = offset + len(page)
+        return {
+            "total": len(matches),
+            "offset": offset,
+            "limit": limit,
+            "next_offset": next_offset if next_offset < len(matches) else None,
+            "envelopes": [entry.to_dict() for entry in page],
+        }
+
+    def inspect(self, path: str) -> Dict[str, Any] | None:
+        entry = next((entry for entry in self.entries() if entry.path == path), None)
+        if entry is None:
+            return None
+        details = entry.to_dict()
+        try:
+            stored = _read_envelope(Path(entry.path).read_bytes())
+        except FileNotFoundError:
+            details["present"] = False
+            return details
+        except (OSError, ValueError) as exc:
+            raise IndexUnavailable(f"unable to read {entry.path}: {exc}") from exc
+        meta = dict(stored.get("meta") or {})
+        wraps = meta.pop("key_wraps", None) or []
+        provenance = meta.pop("provenance", None) or []
+        read_receipts = meta.pop("read_receipts", False) is True
+        recipients = [wrap.get("key_id") for wrap in wraps if isinstance(wrap, dict)]
+        known = self.fingerprints()
+        details.update(
+            {
+                "present": True,
+                "format": stored.get("format", 0),
+                "original_path": stored.get("original_path"),
+                "payload_bytes": stored.get("payload_bytes", _payload_size(stored.get("payload", ""))),
+                "recipients": recipients,
+                "fingerprints": {key: known[key] for key in recipients if key in known},
+                "provenance": [step for step in provenance if isinstance(step, dict)],
+                "read_receipts": read_receipts,
+                "meta": meta,
+            }
+        )
+        return details
+
+    def fingerprints(self) -> Dict[str, Any]:
+        try:
+            raw = json.loads(self._data_dir.joinpath(*_KEYRING_PATH).read_text(encoding="utf-8"))
+        except (OSError, ValueError):
+            return {}
+        keys = list(raw.get("identities") or []) + list(raw.get("contacts") or [])
+        return {
+            str(key["id"]): key["fingerprint"]
+            for key in keys
+            if isinstance(key, dict) and "id" in key and key.get("fingerprint")
+        }
+
+
+def _label_matches(wanted: str, label: str) -> bool:
+    wanted = wanted.strip(_LABEL_SEPARATOR).casefold()
+    label = label.casefold()
+    return label == wanted or label.startswith(wanted + _LABEL_SEPARATOR)
+
+
+def _decode_change(line: bytes) -> Dict[str, Any] | None:
+    """Parse one log line; torn or damaged lines fail their checksum and are skipped."""
+    check, body = line[:_CHECK_LEN], line[_CHECK_LEN + 1 :]
+    if line[_CHECK_LEN : _CHECK_LEN + 1] != b" ":
+        return None
+    if hashlib.sha256(body).hexdigest()[:_CHECK_LEN].encode("ascii") != check:
+        return None
+    try:
+        change = json.loads(body)
+    except ValueError:
+        return None
+    return change if isinstance(change, dict) else None
+
+
+def _decode_path(encoded: str) -> str:
+    if not encoded.startswith(_RAW_MARKER):
+        return encoded
+    try:
+        return bytes.fromhex(encoded[1:]).decode("utf-8", errors="surrogateescape")
+    except ValueError:
+        return encoded
+
+
+def _read_envelope(data: bytes) -> Dict[str, Any]:
+    """Decode a binary envelope header, or an older JSON envelope document."""
+    if not data.startswith(_ENVELOPE_MAGIC):
+        return json.loads(data.decode("utf-8"))
+    try:
+        offset = len(_ENVELOPE_MAGIC)
+        version, _algorithm, key_len = struct.unpack_from(">HBB", data, offset)
+        offset += 4 + key_len
+        (nonce_len,) = struct.unpack_from(">B", data, offset)
+        offset += 1 + nonce_len
+        (aad_len,) = struct.unpack_from(">I", data, offset)
+        offset += 4
+        if offset + aad_len > len(data):
+            raise ValueError("envelope header is truncated")
+        meta = json.loads(data[offset : offset + aad_len].decode("utf-8"))
+    except struct.error as exc:
+        raise ValueError(f"envelope header is truncated: {exc}") from exc
+    if not isinstance(meta, dict):
+        raise ValueError("envelope metadata must be a JSON object")
+    return {
+        "format": version,
+        "original_path": meta.pop("original_path", None),
+        "payload_bytes": nonce_len + len(data) - offset - aad_len,
+        "meta": meta,
+    }
+
+
+def _payload_size(payload: str) -> int:
+    padding = payload.count("=", max(len(payload) - 2, 0))
+    return len(payload) * 3 // 4 - padding
@@ -0,0 +1,69 @@
+"""JSON-RPC error codes shared with ``dg_core::rpc_error`` on the Rust side.
+
+Keep this table in step with ``ERROR_CODES`` in ``dg_core/src/rpc_error.rs``.
+"""
+from __future__ import annotations
+
+REQUEST_TIMED_OUT = -32000
+APPLICATION_ERROR = -32001
+POLICY_ONLY = -32010
+UNAUTHENTICATED = -32011
+CAPABILITY_DENIED = -32012
+POLICY_DENIED = -32013
+RATE_LIMITED = -32014
+CRYPTO_FAILED = -32015
+CONFIG_INVALID = -32016
+READ_ONLY = -32017
+LOCKED = -32018
+INDEX_UNAVAILABLE = -32020
+ENVELOPE_NOT_FOUND = -32021
+INVALID_REQUEST = -32600
+REQUEST_TOO_LARGE = INVALID_REQUEST
+METHOD_NOT_FOUND = -32601
+INVALID_PARAMS = -32602
+INTERNAL_ERROR = -32603
+PARSE_ERROR = -32700
+
+ERROR_KINDS = {
+    PARSE_ERROR: "rpc.parse_error",
+    INVALID_REQUEST: "rpc.invalid_request",
+    METHOD_NOT_FOUND: "rpc.method_not_found",
+    INVALID_PARAMS: "rpc.invalid_params",
+    INTERNAL_ERROR: "engine.internal",
+    REQUEST_TIMED_OUT: "rpc.timed_out",
+    APPLICATION_ERROR: "application.error",
+    POLICY_ONLY: "daemon.policy_only",
+    UNAUTHENTICATED: "session.unauthenticated",
+    CAPABILITY_DENIED: "session.capability_denied",
+    POLICY_DENIED: "engine.policy_denied",
+    RATE_LIMITED: "rpc.rate_limited",
+    CRYPTO_FAILED: "engine.crypto",
+    CONFIG_INVALID: "engine.config",
+    READ_ONLY: "engine.read_only",
+    LOCKED: "engine.locked",
+    INDEX_UNAVAILABLE: "index.unavailable",
+    ENVELOPE_NOT_FOUND: "index.envelope_not_found",
+}
+
+__all__ = [
+    "APPLICATION_ERROR",
+    "CAPABILITY_DENIED",
+    "CONFIG_INVALID",
+    "CRYPTO_FAILED",
+    "ENVELOPE_NOT_FOUND",
+    "ERROR_KINDS",
+    "INDEX_UNAVAILABLE",
+    "INTERNAL_ERROR",
+    "INVALID_PARAMS",
+    "INVALID_REQUEST",
+    "LOCKED",
+    "METHOD_NOT_FOUND",
+    "PARSE_ERROR",
+    "POLICY_DENIED",
+    "POLICY_ONLY",
+    "RATE_LIMITED",
+    "READ_ONLY",
+    "REQUEST_TIMED_OUT",
+    "REQUEST_TOO_LARGE",
+    "UNAUTHENTICATED",
+]
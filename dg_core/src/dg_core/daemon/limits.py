@@ -0,0 +1,120 @@
+"""Per-connection flood protection for the DG Core daemon."""
+from __future__ import annotations
+
+import time
+from dataclasses import dataclass, field
+from typing import Any, Callable
+
+from ..ipc.transport import DEFAULT_MAX_FRAME_BYTES
+from .errors import RATE_LIMITED, REQUEST_TOO_LARGE
+from .protocol import JSONRPCError
+
+DEFAULT_REQUESTS_PER_SECOND = 50.0
+DEFAULT_BURST = 100
+DEFAULT_MAX_IN_FLIGHT = 8
+
+
+@dataclass(frozen=True, slots=True)
+class ConnectionLimits:
+    """Limits applied to every client connection."""
+
+    max_frame_bytes: int = DEFAULT_MAX_FRAME_BYTES
+    requests_per_second: float = DEFAULT_REQUESTS_PER_SECOND
+    burst: int = DEFAULT_BURST
+    max_in_flight: int = DEFAULT_MAX_IN_FLIGHT
+
+    def __post_init__(self) -> None:
+        if self.max_frame_bytes <= 0:
+            raise ValueError("max_frame_bytes must be positive")
+        if self.requests_per_second <= 0 or self.burst <= 0:
+            raise ValueError("requests_per_second and burst must be positive")
+        if self.max_in_flight <= 0:
+            raise ValueError("max_in_flight must be positive")
+
+
+class LimitExceeded(Exception):
+    """Raised when a client breaks a connection limit; the connection is closed."""
+
+    def __init__(self, code: int, message: str, *, reason: str, **data: Any) -> None:
+        super().__init__(message)
+        self.reason = reason
+        self.error = JSONRPCError(code=code, message=message, data={"reason": reason, **data})
+
+
+@dataclass(slots=True)
+class RateLimiter:
+    """Token bucket refilled at ``rate`` tokens per second up to ``burst``."""
+
+    rate: float
+    burst: int
+    clock: Callable[[], float] = time.monotonic
+    _tokens: float = field(init=False)
+    _updated: float = field(init=False)
+
+    def __post_init__(self) -> None:
+        self._tokens = float(self.burst)
+        self._updated = self.clock()
+
+    def allow(self) -> bool:
+        now = self.clock()
+        self._tokens = min(float(self.burst), self._tokens + (now - self._updated) * self.rate)
+        self._updated = now
+        if self._tokens < 1.0:
+            return False
+        self._tokens -= 1.0
+        return True
+
+
+class ConnectionGuard:
+    """Tracks one connection's request rate and in-flight requests."""
+
+    def __init__(
+        self, limits: ConnectionLimits, clock: Callable[[], float] = time.monotonic
+    ) -> None:
+        self.limits = limits
+        self._limiter = RateLimiter(limits.requests_per_second, limits.burst, clock)
+        self.in_flight = 0
+
+    def admit(self, frame_bytes: int) -> None:
+        if frame_bytes > self.limits.max_frame_bytes:
+            raise frame_too_large(frame_bytes, self.limits.max_frame_bytes)
+        if not self._limiter.allow():
+            raise LimitExceeded(
+                RATE_LIMITED,
+                "Rate limit exceeded",
+                reason="rate",
+                limit=self.limits.requests_per_second,
+                burst=self.limits.burst,
+            )
+        if self.in_flight >= self.limits.max_in_flight:
+            raise LimitExceeded(
+                RATE_LIMITED,
+                "Too many requests in flight",
+                reason="in_flight",
+                limit=self.limits.max_in_flight,
+            )
+        self.in_flight += 1
+
+    def release(self) -> None:
+        self.in_flight = max(0, self.in_flight - 1)
+
+
+def frame_too_large(size: int | None, limit: int) -> LimitExceeded:
+    return LimitExceeded(
+        REQUEST_TOO_LARGE, "Request too large", reason="frame", size=size, limit=limit
+    )
+
+
+__all__ = [
+    "ConnectionGuard",
+    "ConnectionLimits",
+    "DEFAULT_BURST",
+    "DEFAULT_MAX_FRAME_BYTES",
+    "DEFAULT_MAX_IN_FLIGHT",
+    "DEFAULT_REQUESTS_PER_SECOND",
+    "LimitExceeded",
+    "RATE_LIMITED",
+    "REQUEST_TOO_LARGE",
+    "RateLimiter",
+    "frame_too_large",
+]
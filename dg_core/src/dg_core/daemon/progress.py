@@ -0,0 +1,72 @@
+"""Progress reporting for long-running daemon operations."""
+from __future__ import annotations
+
+import uuid
+from dataclasses import asdict, dataclass
+from typing import Any, Dict
+
+from .log_stream import LogStream
+
+
+@dataclass(slots=True)
+class ProgressUpdate:
+    """Payload of a ``core.progress`` notification."""
+
+    op_id: str
+    method: str
+    stage: str
+    bytes: int
+    total_bytes: int | None = None
+    percent: float | None = None
+
+    def to_params(self) -> Dict[str, Any]:
+        return asdict(self)
+
+
+class ProgressReporter:
+    """Publish stage transitions for a single operation."""
+
+    def __init__(
+        self,
+        stream: LogStream,
+        method: str,
+        *,
+        op_id: str | None = None,
+        total_bytes: int | None = None,
+    ) -> None:
+        self._stream = stream
+        self.method = method
+        self.op_id = op_id or uuid.uuid4().hex
+        self.total_bytes = total_bytes
+
+    def stage(self, stage: str, processed: int = 0) -> ProgressUpdate:
+        percent: float | None = None
+        if self.total_bytes:
+            percent = round(min(processed, self.total_bytes) * 100.0 / self.total_bytes, 1)
+        elif self.total_bytes == 0:
+            percent = 100.0
+        update = ProgressUpdate(
+            op_id=self.op_id,
+            method=self.method,
+            stage=stage,
+            bytes=processed,
+            total_bytes=self.total_bytes,
+            percent=percent,
+        )
+        self._stream.publish(update.to_params())
+        return update
+
+    def done(self) -> ProgressUpdate:
+        return self.stage("done", self.total_bytes or 0)
+
+
+_GLOBAL_PROGRESS_STREAM = LogStream(backlog=32)
+
+
+def get_progress_stream() -> LogStream:
+    """Return the singleton progress stream."""
+
+    return _GLOBAL_PROGRESS_STREAM
+
+
+__all__ = ["ProgressReporter", "ProgressUpdate", "get_progress_stream"]
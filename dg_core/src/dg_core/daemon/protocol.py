@@ -3,11 +3,14 @@ from __future__ import annotations
 
 import inspect
 from dataclasses import dataclass
-from typing import Any, Callable, Dict, Mapping, Protocol
+from typing import Any, Callable, Dict, Iterable, Mapping, Protocol
 
 from pydantic import BaseModel, ConfigDict, Field, ValidationError
 
+from .errors import INTERNAL_ERROR, INVALID_PARAMS, METHOD_NOT_FOUND, UNAUTHENTICATED
+
 IDType = int | str | None
+AUTHENTICATE_METHOD = "core.authenticate"
 
 
 class JSONRPCError(BaseModel):
@@ -66,12 +69,12 @@ class RPCError(Exception):
 
 class MethodNotFound(RPCError):
     def __init__(self, method: str) -> None:
-        super().__init__(-32601, "Method not found", data=method)
+        super().__init__(METHOD_NOT_FOUND, "Method not found", data=method)
 
 
 class InvalidParams(RPCError):
     def __init__(self, message: str, data: Any | None = None) -> None:
-        super().__init__(-32602, message, data=data)
+        super().__init__(INVALID_PARAMS, message, data=data)
 
 
 @dataclass(slots=True)
@@ -80,6 +83,10 @@ class MethodContext:
 
     server: Any
     connection: Any
+    session: Any | None = None
+    enforce_scopes: bool = False
+    # False on a token-guarded TCP connection until ``core.authenticate`` presents the token.
+    transport_verified: bool = True
 
 
 @dataclass(slots=True)
@@ -95,29 +102,93 @@ class MethodHandler(Protocol):
         ...
 
 
+Scope = str | Callable[[Dict[str, Any]], str] | None
+Schema = Mapping[str, Any]
+
+EMPTY_PARAMS: Schema = {"type": "object", "properties": {}}
+
+
+@dataclass(slots=True)
+class MethodSpec:
+    """Introspection data for a registered method."""
+
+    name: str
+    params: Schema
+    capabilities: tuple[str, ...]
+
+    def describe(self) -> Dict[str, Any]:
+        return {
+            "name": self.name,
+            "params": dict(self.params),
+            "capabilities": list(self.capabilities),
+        }
+
+
 class MethodRegistry:
     """Registry for mapping JSON-RPC methods to callables."""
 
     def __init__(self) -> None:
         self._handlers: Dict[str, MethodHandler] = {}
-
-    def register(self, name: str, handler: MethodHandler) -> None:
+        self._scopes: Dict[str, Scope] = {}
+        self._specs: Dict[str, MethodSpec] = {}
+
+    def register(
+        self,
+        name: str,
+        handler: MethodHandler,
+        *,
+        scope: Scope = None,
+        params: Schema | None = None,
+        capabilities: Iterable[str] | None = None,
+    ) -> None:
         if name in self._handlers:
             raise ValueError(f"Handler already registered for {name}")
+        if capabilities is None:
+            capabilities = (scope,) if isinstance(scope, str) else ()
         self._handlers[name] = handler
-
-    def method(self, name: str) -> Callable[[MethodHandler], MethodHandler]:
+        self._scopes[name] = scope
+        self._specs[name] = MethodSpec(
+            name=name,
+            params=params if params is not None else EMPTY_PARAMS,
+            capabilities=tuple(capabilities),
+        )
+
+    def method(
+        self,
+        name: str,
+        *,
+        scope: Scope = None,
+        params: Schema | None = None,
+        capabilities: Iterable[str] | None = None,
+    ) -> Callable[[MethodHandler], MethodHandler]:
         def decorator(func: MethodHandler) -> MethodHandler:
-            self.register(name, func)
+            self.register(name, func, scope=scope, params=params, capabilities=capabilities)
             return func
 
         return decorator
 
+    def describe(self) -> list[Dict[str, Any]]:
+        return [self._specs[name].describe() for name in sorted(self._specs)]
+
+    def required_scope(self, name: str, params: Dict[str, Any]) -> str | None:
+        scope = self._scopes.get(name)
+        if callable(scope):
+            return scope(params)
+        return scope
+
     async def dispatch(self, context: MethodContext, request: JSONRPCRequest) -> MethodResult:
         handler = self._handlers.get(request.method)
         if not handler:
             raise MethodNotFound(request.method)
+        if not context.transport_verified and request.method != AUTHENTICATE_METHOD:
+            raise RPCError(UNAUTHENTICATED, "Transport token required", data=request.method)
         params = _coerce_params(request)
+        if context.enforce_scopes:
+            scope = self.required_scope(request.method, params)
+            if scope is not None:
+                if context.session is None:
+                    raise RPCError(UNAUTHENTICATED, "Authentication required", data=request.method)
+                context.session.require(scope, request.method)
         try:
             result = handler(context, params)
             if isinstance(result, MethodResult):
@@ -133,7 +204,7 @@ class MethodRegistry:
         except ValidationError as exc:  # pragma: no cover - defensive
             raise InvalidParams("Invalid parameters", data=exc.errors()) from exc
         except Exception as exc:  # pragma: no cover - defensive
-            raise RPCError(-32603, "Internal error", data=str(exc)) from exc
+            raise RPCError(INTERNAL_ERROR, "Internal error", data=str(exc)) from exc
 
 
 def parse_request(payload: str) -> JSONRPCRequest:
@@ -171,16 +242,21 @@ def _coerce_params(request: JSONRPCRequest) -> Dict[str, Any]:
 
 
 __all__ = [
+    "AUTHENTICATE_METHOD",
     "IDType",
     "JSONRPCError",
     "JSONRPCRequest",
     "JSONRPCResponse",
     "JSONRPCNotification",
+    "EMPTY_PARAMS",
     "MethodContext",
     "MethodRegistry",
     "MethodResult",
+    "MethodSpec",
     "ProtocolError",
     "RPCError",
+    "Schema",
+    "Scope",
     "MethodNotFound",
     "InvalidParams",
     "parse_request",
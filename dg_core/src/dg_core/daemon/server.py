@@ -3,9 +3,10 @@ from __future__ import annotations
 
 import argparse
 import asyncio
+import os
 import sys
 import time
-from dataclasses import asdict
+from dataclasses import asdict, replace
 from pathlib import Path
 from typing import Any, Dict, Iterable
 
@@ -19,10 +20,47 @@ from ..scanner import Scanner, ScannerConfig, scan_text
 from ..utils.text import to_text
 from ..utils.validation import resolve_and_check_path
 from ..version import __version__
-from ..ipc.transport import BaseConnection, ConnectionClosed, NamedPipeTransport, UnixSocketTransport
+from ..ipc.activation import inherited_listener
+from ..ipc.transport import (
+    BaseConnection,
+    ConnectionClosed,
+    FrameTooLarge,
+    InheritedSocketTransport,
+    NamedPipeTransport,
+    TCPTransport,
+    UnixSocketTransport,
+)
 from ..logging import configure_logging
 from ..paths import default_named_pipe, default_unix_socket_path, runtime_config_dir
+from .limits import (
+    DEFAULT_BURST,
+    DEFAULT_MAX_FRAME_BYTES,
+    DEFAULT_MAX_IN_FLIGHT,
+    DEFAULT_REQUESTS_PER_SECOND,
+    ConnectionGuard,
+    ConnectionLimits,
+    LimitExceeded,
+    frame_too_large,
+)
+from .errors import (
+    APPLICATION_ERROR,
+    ENVELOPE_NOT_FOUND,
+    INDEX_UNAVAILABLE,
+    INTERNAL_ERROR,
+    PARSE_ERROR,
+    REQUEST_TIMED_OUT,
+    UNAUTHENTICATED,
+)
+from .envelopes import (
+    DEFAULT_PAGE_SIZE,
+    MAX_PAGE_SIZE,
+    EnvelopeIndex,
+    IndexUnavailable,
+    SearchFilters,
+)
 from .log_stream import get_log_stream
+from .progress import ProgressReporter, get_progress_stream
+from .session import TCP_TOKEN_ENV, Session, SessionVerifier, transport_token_matches
 from .protocol import (
     JSONRPCError,
     JSONRPCNotification,
@@ -39,11 +77,15 @@ from .protocol import (
     parse_request,
 )
 
-_MAX_REQUEST_BYTES = 512 * 1024
 _REQUEST_TIMEOUT = 15.0
 _LOG_STREAM_NAME = "logs"
+_PROGRESS_STREAM_NAME = "progress"
 _DEFAULT_PIPE = default_named_pipe()
 _DEFAULT_SOCKET = default_unix_socket_path()
+_STRING: Dict[str, Any] = {"type": "string"}
+_OBJECT: Dict[str, Any] = {"type": "object"}
+_TIMESTAMP: Dict[str, Any] = {"type": "integer", "minimum": 0}
+_DATA_DIR_ENV = "DG_DATA_DIR"
 
 logger = structlog.get_logger(__name__)
 
@@ -56,13 +98,18 @@ class DaemonServer:
         *,
         socket_path: Path | None = None,
         pipe_name: str | None = None,
-        max_request_bytes: int = _MAX_REQUEST_BYTES,
+        tcp_address: tuple[str, int] | None = None,
+        limits: ConnectionLimits | None = None,
         request_timeout: float = _REQUEST_TIMEOUT,
+        session_verifier: SessionVerifier | None = None,
+        data_dir: Path | None = None,
+        tcp_token: str | None = None,
     ) -> None:
-        self._max_request_bytes = max_request_bytes
+        self._limits = limits or ConnectionLimits()
         self._request_timeout = request_timeout
         self._shutdown = asyncio.Event()
         self._log_stream = get_log_stream()
+        self._progress_stream = get_progress_stream()
         self._scanner = Scanner()
         self._default_policy_path = (
             Path(__file__).resolve().parents[2] / "policies" / "default.yaml"
@@ -74,7 +121,15 @@ class DaemonServer:
         self._start_time = time.monotonic()
         self._request_count = 0
         self._connections: set[int] = set()
-        self._transport = self._create_transport(socket_path=socket_path, pipe_name=pipe_name)
+        self._session_verifier = session_verifier
+        self._envelopes = EnvelopeIndex(data_dir) if data_dir is not None else None
+        self._sessions: dict[int, Session] = {}
+        self._transport = self._create_transport(
+            socket_path=socket_path, pipe_name=pipe_name, tcp_address=tcp_address
+        )
+        # Sockets and pipes rely on file permissions; TCP clients must present the shared token.
+        self._tcp_token = tcp_token if isinstance(self._transport, TCPTransport) else None
+        self._transport_verified: set[int] = set()
         self._policy_roots = [
             self._default_policy_path.parent,
             runtime_config_dir(),
@@ -85,8 +140,11 @@ class DaemonServer:
     async def serve_forever(self) -> None:
         loop = asyncio.get_running_loop()
         self._log_stream.attach_loop(loop)
+        self._progress_stream.attach_loop(loop)
         endpoint = self.endpoint
-        logger.info("daemon.start", endpoint=str(endpoint))
+        logger.info(
+            "daemon.start", endpoint=str(endpoint), socket_activated=self.socket_activated
+        )
         await self._transport.start(self._handle_connection)
         await self._shutdown.wait()
         await self._transport.close()
@@ -101,22 +159,43 @@ class DaemonServer:
             return self._transport.path
         if isinstance(self._transport, NamedPipeTransport):
             return self._transport.pipe_name
+        if isinstance(self._transport, InheritedSocketTransport):
+            return self._transport.address
+        if isinstance(self._transport, TCPTransport):
+            return f"{self._transport.host}:{self._transport.port}"
         return "unknown"
 
+    @property
+    def socket_activated(self) -> bool:
+        return isinstance(self._transport, InheritedSocketTransport)
+
     def _create_transport(
-        self, *, socket_path: Path | None, pipe_name: str | None
-    ) -> NamedPipeTransport | UnixSocketTransport:
+        self,
+        *,
+        socket_path: Path | None,
+        pipe_name: str | None,
+        tcp_address: tuple[str, int] | None,
+    ) -> InheritedSocketTransport | NamedPipeTransport | TCPTransport | UnixSocketTransport:
+        max_frame_bytes = self._limits.max_frame_bytes
+        activated = inherited_listener()
+        if activated is not None:
+            return InheritedSocketTransport(activated, max_frame_bytes=max_frame_bytes)
+        if tcp_address is not None:
+            host, port = tcp_address
+            return TCPTransport(host, port, max_frame_bytes=max_frame_bytes)
         if sys.platform == "win32":
             name = pipe_name or _DEFAULT_PIPE
-            return NamedPipeTransport(name)
+            return NamedPipeTransport(name, max_frame_bytes=max_frame_bytes)
         path = Path(socket_path or _DEFAULT_SOCKET)
         path.parent.mkdir(parents=True, exist_ok=True)
-        return UnixSocketTransport(path)
+        return UnixSocketTransport(path, max_frame_bytes=max_frame_bytes)
 
     async def _handle_connection(self, connection: BaseConnection) -> None:
         conn_id = id(connection)
         self._connections.add(conn_id)
         logger.info("daemon.connection.opened", connection=conn_id)
+        guard = ConnectionGuard(self._limits)
+        requests: set[asyncio.Task[Any]] = set()
         tasks: set[asyncio.Task[Any]] = set()
         subscriptions: list[Any] = []
         try:
@@ -125,37 +204,32 @@ class DaemonServer:
                     payload = await asyncio.wait_for(
                         connection.receive(), timeout=self._request_timeout
                     )
+                    if not payload:
+                        continue
+                    guard.admit(len(payload.encode("utf-8")))
                 except asyncio.TimeoutError:
                     timeout = JSONRPCResponse(
-                        error=JSONRPCError(code=-32000, message="Request timed out"),
+                        error=JSONRPCError(code=REQUEST_TIMED_OUT, message="Request timed out"),
                         id=None,
                     )
                     await connection.send(timeout.model_dump_json())
                     continue
+                except FrameTooLarge as exc:
+                    await self._reject(connection, frame_too_large(None, exc.limit))
+                    break
+                except LimitExceeded as exc:
+                    await self._reject(connection, exc)
+                    break
                 except ConnectionClosed:
                     break
 
-                if not payload:
-                    continue
-                if len(payload.encode("utf-8")) > self._max_request_bytes:
-                    error = JSONRPCResponse(
-                        error=JSONRPCError(
-                            code=-32600,
-                            message="Request too large",
-                            data=len(payload),
-                        ),
-                        id=None,
-                    )
-                    await connection.send(error.model_dump_json())
-                    continue
-
-                response_payload = await self._dispatch_request(
-                    connection, payload, tasks, subscriptions
+                request = asyncio.create_task(
+                    self._serve_request(connection, payload, guard, tasks, subscriptions)
                 )
-                if response_payload is not None:
-                    await connection.send(response_payload)
+                requests.add(request)
+                request.add_done_callback(requests.discard)
         finally:
-            for task in tasks:
+            for task in (*requests, *tasks):
                 task.cancel()
             for subscription in subscriptions:
                 try:
@@ -164,8 +238,42 @@ class DaemonServer:
                     pass
             await connection.close()
             self._connections.discard(conn_id)
+            self._sessions.pop(conn_id, None)
+            self._transport_verified.discard(conn_id)
             logger.info("daemon.connection.closed", connection=conn_id)
 
+    async def _serve_request(
+        self,
+        connection: BaseConnection,
+        payload: str,
+        guard: ConnectionGuard,
+        tasks: set[asyncio.Task[Any]],
+        subscriptions: list[Any],
+    ) -> None:
+        try:
+            response_payload = await self._dispatch_request(
+                connection, payload, tasks, subscriptions
+            )
+            if response_payload is not None:
+                await connection.send(response_payload)
+        except (ConnectionClosed, ConnectionError):
+            pass
+        finally:
+            guard.release()
+
+    async def _reject(self, connection: BaseConnection, violation: LimitExceeded) -> None:
+        logger.warning(
+            "daemon.connection.limit_exceeded",
+            connection=id(connection),
+            reason=violation.reason,
+            detail=violation.error.data,
+        )
+        response = JSONRPCResponse(error=violation.error, id=None)
+        try:
+            await connection.send(response.model_dump_json())
+        except (ConnectionClosed, ConnectionError):
+            pass
+
     async def _dispatch_request(
         self,
         connection: BaseConnection,
@@ -177,10 +285,17 @@ class DaemonServer:
         try:
             request = parse_request(payload)
         except ProtocolError as exc:
-            error = JSONRPCError(code=-32700, message="Parse error", data=str(exc))
+            error = JSONRPCError(code=PARSE_ERROR, message="Parse error", data=str(exc))
             return JSONRPCResponse(id=None, error=error).model_dump_json()
 
-        context = MethodContext(server=self, connection=connection)
+        context = MethodContext(
+            server=self,
+            connection=connection,
+            session=self._sessions.get(id(connection)),
+            enforce_scopes=self._session_verifier is not None,
+            transport_verified=self._tcp_token is None
+            or id(connection) in self._transport_verified,
+        )
         try:
             result = await self._registry.dispatch(context, request)
         except RPCError as exc:
@@ -203,17 +318,24 @@ class DaemonServer:
         tasks: set[asyncio.Task[Any]],
         subscriptions: list[Any],
     ) -> None:
-        if stream_name != _LOG_STREAM_NAME:
-            raise RPCError(-32603, f"Unknown stream: {stream_name}")
-        subscription = self._log_stream.subscribe()
+        streams = {
+            _LOG_STREAM_NAME: (self._log_stream, "core.log"),
+            _PROGRESS_STREAM_NAME: (self._progress_stream, "core.progress"),
+        }
+        if stream_name not in streams:
+            raise RPCError(INTERNAL_ERROR, f"Unknown stream: {stream_name}")
+        stream, method = streams[stream_name]
+        subscription = stream.subscribe()
         subscriptions.append(subscription)
-        task = asyncio.create_task(self._pump_logs(connection, subscription))
+        task = asyncio.create_task(self._pump_stream(connection, subscription, method))
         tasks.add(task)
 
-    async def _pump_logs(self, connection: BaseConnection, subscription: Any) -> None:
+    async def _pump_stream(
+        self, connection: BaseConnection, subscription: Any, method: str
+    ) -> None:
         try:
             async for record in subscription:
-                notification = JSONRPCNotification(method="core.log", params=record)
+                notification = JSONRPCNotification(method=method, params=record)
                 try:
                     await connection.send(notification.model_dump_json())
                 except ConnectionClosed:
@@ -231,16 +353,66 @@ class DaemonServer:
     def _register_methods(self) -> None:
         registry = self._registry
 
+        @registry.method("core.describe")
+        async def _describe(_ctx: MethodContext, _params: Dict[str, Any]) -> Dict[str, Any]:
+            return {"version": __version__, "methods": registry.describe()}
+
         @registry.method("core.ping")
         async def _ping(_ctx: MethodContext, _params: Dict[str, Any]) -> Dict[str, Any]:
             return {"ok": True, "version": __version__}
 
-        @registry.method("core.scan_path")
+        @registry.method(
+            "core.authenticate",
+            params=_schema({"token": _STRING, "transport_token": _STRING, "device": _STRING}),
+        )
+        async def _authenticate(ctx: MethodContext, params: Dict[str, Any]) -> Dict[str, Any]:
+            if not ctx.transport_verified:
+                presented = params.get("transport_token")
+                if not transport_token_matches(self._tcp_token or "", presented):
+                    logger.warning("daemon.transport.rejected", connection=id(ctx.connection))
+                    raise RPCError(UNAUTHENTICATED, "Invalid transport token")
+                self._transport_verified.add(id(ctx.connection))
+            token = params.get("token")
+            if token is None and "transport_token" in params:
+                return {"required": self._session_verifier is not None}
+            if not isinstance(token, str):
+                raise InvalidParams("'token' must be a string")
+            if self._session_verifier is None:
+                raise RPCError(UNAUTHENTICATED, "Session tokens are not enabled on this daemon")
+            session = self._session_verifier.verify(token)
+            device = params.get("device")
+            if device is not None:
+                session = replace(session, device=device)
+            self._sessions[id(ctx.connection)] = session
+            logger.info(
+                "daemon.session.authenticated",
+                subject=session.subject,
+                scopes=sorted(session.scopes),
+                device=session.device,
+            )
+            return session.describe()
+
+        @registry.method(
+            "core.scan_path",
+            scope="scan",
+            params=_schema(
+                {
+                    "path": _STRING,
+                    "detectors": {"type": "array", "items": _STRING},
+                    "max_results": {"type": "integer", "minimum": 0},
+                    "op_id": _STRING,
+                },
+                "path",
+            ),
+        )
         async def _scan_path(_ctx: MethodContext, params: Dict[str, Any]) -> Dict[str, Any]:
             path = self._require_path(params, "path")
             detectors = params.get("detectors")
             max_results = params.get("max_results")
+            progress = self._progress_reporter("core.scan_path", params, path)
+            progress.stage("read")
             data = await asyncio.to_thread(path.read_bytes)
+            progress.stage("scan", len(data))
             config = ScannerConfig(
                 enabled=detectors,
                 max_detections=max_results,
@@ -248,12 +420,27 @@ class DaemonServer:
             detections = await asyncio.to_thread(
                 scan_text, data, scanner=self._scanner, config=config
             )
+            progress.done()
             return {
                 "path": str(path),
+                "op_id": progress.op_id,
                 "detections": [asdict(det) for det in detections],
             }
 
-        @registry.method("core.redact_file")
+        @registry.method(
+            "core.redact_file",
+            scope="redact",
+            params=_schema(
+                {
+                    "path": _STRING,
+                    "output_path": _STRING,
+                    "policy_path": _STRING,
+                    "policy": _OBJECT,
+                    "op_id": _STRING,
+                },
+                "path",
+            ),
+        )
         async def _redact_file(_ctx: MethodContext, params: Dict[str, Any]) -> Dict[str, Any]:
             path = self._require_path(params, "path")
             output_path = params.get("output_path")
@@ -268,8 +455,12 @@ class DaemonServer:
             )
             engine = self._policy_engine if document is self._default_policy else PolicyEngine(document)
             redactor = self._redactor if document is self._default_policy else RedactionEngine(engine)
+            progress = self._progress_reporter("core.redact_file", params, path)
+            progress.stage("read")
             content = await asyncio.to_thread(path.read_bytes)
+            progress.stage("scan", len(content))
             detections = await asyncio.to_thread(scan_text, content, scanner=self._scanner)
+            progress.stage("redact", len(content))
             redacted, segments = await asyncio.to_thread(
                 redactor.redact, content, detections
             )
@@ -277,23 +468,33 @@ class DaemonServer:
             written_to: str | None = None
             if output_path:
                 target = self._require_output_path(output_path)
+                progress.stage("write", len(content))
                 await asyncio.to_thread(self._write_output, target, redacted)
                 written_to = str(target)
+            progress.done()
 
             return {
                 "path": str(path),
+                "op_id": progress.op_id,
                 "output": rendered,
                 "segments": [asdict(segment) for segment in segments],
                 "written_to": written_to,
             }
 
-        @registry.method("core.load_policy")
+        @registry.method("core.load_policy", scope="policy", params=_schema({"path": _STRING}, "path"))
         async def _load_policy(_ctx: MethodContext, params: Dict[str, Any]) -> Dict[str, Any]:
             path = self._require_path(params, "path")
             document = await asyncio.to_thread(policy_from_path, path)
             return {"path": str(path), "policy": document.model_dump(mode="json")}
 
-        @registry.method("core.test_policy")
+        @registry.method(
+            "core.test_policy",
+            scope="policy",
+            params=_schema(
+                {"text": _STRING, "policy_path": _STRING, "policy": _OBJECT},
+                "text",
+            ),
+        )
         async def _test_policy(_ctx: MethodContext, params: Dict[str, Any]) -> Dict[str, Any]:
             sample = params.get("text")
             if not isinstance(sample, (str, bytes)):
@@ -323,7 +524,16 @@ class DaemonServer:
                 "output": to_text(redacted),
             }
 
-        @registry.method("core.get_status")
+        # Readiness probe; needs no session. This daemon runs no engine, so no profile is loaded.
+        @registry.method("core.health")
+        async def _health(_ctx: MethodContext, _params: Dict[str, Any]) -> Dict[str, Any]:
+            return {
+                "version": __version__,
+                "uptime": time.monotonic() - self._start_time,
+                "profile": None,
+            }
+
+        @registry.method("core.get_status", scope="status")
         async def _get_status(_ctx: MethodContext, _params: Dict[str, Any]) -> Dict[str, Any]:
             uptime = time.monotonic() - self._start_time
             return {
@@ -332,12 +542,79 @@ class DaemonServer:
                 "requests": self._request_count,
                 "connections": len(self._connections),
                 "log_subscribers": self._log_stream.subscriber_count,
+                "progress_subscribers": self._progress_stream.subscriber_count,
+                "sessions": len(self._sessions),
             }
 
-        @registry.method("core.tail_logs")
+        @registry.method(
+            "core.search_envelopes",
+            scope="status",
+            params=_schema(
+                {
+                    "query": _STRING,
+                    "label": _STRING,
+                    "key_id": _STRING,
+                    "updated_after": _TIMESTAMP,
+                    "updated_before": _TIMESTAMP,
+                    "offset": {"type": "integer", "minimum": 0},
+                    "limit": {"type": "integer", "minimum": 1, "maximum": MAX_PAGE_SIZE},
+                }
+            ),
+        )
+        async def _search_envelopes(_ctx: MethodContext, params: Dict[str, Any]) -> Dict[str, Any]:
+            index = self._require_index()
+            offset = params.get("offset", 0)
+            limit = params.get("limit", DEFAULT_PAGE_SIZE)
+            if isinstance(offset, bool) or not isinstance(offset, int):
+                raise InvalidParams("'offset' must be an integer")
+            if isinstance(limit, bool) or not isinstance(limit, int):
+                raise InvalidParams("'limit' must be an integer")
+            try:
+                filters = SearchFilters.from_params(params)
+                return await asyncio.to_thread(
+                    index.search, filters, offset=offset, limit=limit
+                )
+            except ValueError as exc:
+                raise InvalidParams(str(exc)) from exc
+            except IndexUnavailable as exc:
+                raise RPCError(INDEX_UNAVAILABLE, str(exc)) from exc
+
+        @registry.method(
+            "core.inspect_envelope",
+            scope="status",
+            params=_schema({"path": _STRING}, "path"),
+        )
+        async def _inspect_envelope(_ctx: MethodContext, params: Dict[str, Any]) -> Dict[str, Any]:
+            index = self._require_index()
+            path = params.get("path")
+            if not isinstance(path, str):
+                raise InvalidParams("'path' must be a string")
+            try:
+                details = await asyncio.to_thread(index.inspect, path)
+            except IndexUnavailable as exc:
+                raise RPCError(INDEX_UNAVAILABLE, str(exc)) from exc
+            if details is None:
+                raise RPCError(ENVELOPE_NOT_FOUND, "Envelope is not in the index", data=path)
+            return details
+
+        @registry.method("core.tail_logs", scope="read-logs")
         async def _tail_logs(_ctx: MethodContext, _params: Dict[str, Any]) -> MethodResult:
             return MethodResult(result={"subscribed": True}, stream=_LOG_STREAM_NAME)
 
+        @registry.method(
+            "core.subscribe",
+            scope=_subscribe_scope,
+            params=_schema(
+                {"topic": {"type": "string", "enum": [_PROGRESS_STREAM_NAME, _LOG_STREAM_NAME]}}
+            ),
+            capabilities=("progress", "read-logs"),
+        )
+        async def _subscribe(_ctx: MethodContext, params: Dict[str, Any]) -> MethodResult:
+            topic = params.get("topic", _PROGRESS_STREAM_NAME)
+            if topic not in (_PROGRESS_STREAM_NAME, _LOG_STREAM_NAME):
+                raise InvalidParams("topic must be 'progress' or 'logs'", data=topic)
+            return MethodResult(result={"subscribed": True, "topic": topic}, stream=topic)
+
     # -- Helpers ---------------------------------------------------------
 
     def _require_path(self, params: Dict[str, Any], key: str) -> Path:
@@ -347,7 +624,29 @@ class DaemonServer:
         try:
             return resolve_and_check_path(raw, must_exist=True, require_file=True)
         except ValueError as exc:
-            raise RPCError(-32001, str(exc)) from exc
+            raise RPCError(APPLICATION_ERROR, str(exc)) from exc
+
+    def _require_index(self) -> EnvelopeIndex:
+        if self._envelopes is None:
+            raise RPCError(
+                INDEX_UNAVAILABLE,
+                "No data directory configured; start the daemon with --data-dir "
+                f"or {_DATA_DIR_ENV}",
+            )
+        return self._envelopes
+
+    def _progress_reporter(
+        self, method: str, params: Dict[str, Any], path: Path
+    ) -> ProgressReporter:
+        op_id = params.get("op_id")
+        if op_id is not None and not isinstance(op_id, str):
+            raise InvalidParams("'op_id' must be a string")
+        return ProgressReporter(
+            self._progress_stream,
+            method,
+            op_id=op_id,
+            total_bytes=path.stat().st_size,
+        )
 
     def _require_output_path(self, raw: str) -> Path:
         resolved = resolve_and_check_path(raw, must_exist=False)
@@ -371,7 +670,7 @@ class DaemonServer:
                     require_file=True,
                 )
             except ValueError as exc:
-                raise RPCError(-32001, str(exc)) from exc
+                raise RPCError(APPLICATION_ERROR, str(exc)) from exc
             try:
                 return policy_from_path(candidate)
             except ValidationError as exc:
@@ -385,9 +684,42 @@ class DaemonServer:
             path.write_text(content, encoding="utf-8")
 
 
+def _schema(properties: Dict[str, Any], *required: str) -> Dict[str, Any]:
+    schema: Dict[str, Any] = {"type": "object", "properties": properties}
+    if required:
+        schema["required"] = list(required)
+    return schema
+
+
+def _tcp_address(value: str) -> tuple[str, int]:
+    host, separator, port = value.rpartition(":")
+    if not separator or not host or not port.isdigit() or not 0 < int(port) < 65536:
+        raise argparse.ArgumentTypeError(f"expected HOST:PORT, got '{value}'")
+    return host.strip("[]"), int(port)
+
+
+def _subscribe_scope(params: Dict[str, Any]) -> str:
+    return "read-logs" if params.get("topic") == _LOG_STREAM_NAME else "progress"
+
+
 async def _async_main(args: argparse.Namespace) -> None:
     configure_logging()
-    server = DaemonServer(socket_path=args.socket, pipe_name=args.pipe)
+    verifier = SessionVerifier.from_file(args.session_key_file) if args.session_key_file else None
+    limits = ConnectionLimits(
+        max_frame_bytes=args.max_frame_bytes,
+        requests_per_second=args.max_requests_per_second,
+        burst=args.burst,
+        max_in_flight=args.max_in_flight,
+    )
+    server = DaemonServer(
+        socket_path=args.socket,
+        pipe_name=args.pipe,
+        tcp_address=args.tcp,
+        limits=limits,
+        session_verifier=verifier,
+        data_dir=args.data_dir,
+        tcp_token=os.environ.get(TCP_TOKEN_ENV) or None,
+    )
     try:
         await server.serve_forever()
     except asyncio.CancelledError:  # pragma: no cover - cancellation path
@@ -398,6 +730,52 @@ def main(argv: Iterable[str] | None = None) -> None:
     parser = argparse.ArgumentParser(description="Run the DG Core daemon")
     parser.add_argument("--socket", type=Path, default=None, help="Override the Unix socket path")
     parser.add_argument("--pipe", type=str, default=None, help="Override the Windows named pipe")
+    parser.add_argument(
+        "--tcp",
+        type=_tcp_address,
+        default=None,
+        metavar="HOST:PORT",
+        help=(
+            "Listen on a loopback TCP address instead of the socket or pipe; set "
+            f"{TCP_TOKEN_ENV} to require a shared token in core.authenticate"
+        ),
+    )
+    parser.add_argument(
+        "--session-key-file",
+        type=Path,
+        default=None,
+        help="Require capability-scoped session tokens signed with this key",
+    )
+    parser.add_argument(
+        "--data-dir",
+        type=Path,
+        default=os.environ.get(_DATA_DIR_ENV) or None,
+        help="Engine data directory whose envelope index is served to clients",
+    )
+    parser.add_argument(
+        "--max-frame-bytes",
+        type=int,
+        default=DEFAULT_MAX_FRAME_BYTES,
+        help="Close connections that send a request line larger than this",
+    )
+    parser.add_argument(
+        "--max-requests-per-second",
+        type=float,
+        default=DEFAULT_REQUESTS_PER_SECOND,
+        help="Sustained request rate allowed per connection",
+    )
+    parser.add_argument(
+        "--burst",
+        type=int,
+        default=DEFAULT_BURST,
+        help="Requests a connection may send at once before the rate limit applies",
+    )
+    parser.add_argument(
+        "--max-in-flight",
+        type=int,
+        default=DEFAULT_MAX_IN_FLIGHT,
+        help="Concurrent unanswered requests allowed per connection",
+    )
     args = parser.parse_args(list(argv) if argv is not None else None)
     try:
         asyncio.run(_async_main(args))
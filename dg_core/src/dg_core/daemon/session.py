@@ -0,0 +1,156 @@
+"""Capability-scoped session tokens for IPC clients."""
+from __future__ import annotations
+
+import base64
+import hashlib
+import hmac
+import json
+import time
+from dataclasses import dataclass
+from pathlib import Path
+from typing import Any, Dict, Iterable
+
+from .errors import CAPABILITY_DENIED, UNAUTHENTICATED
+from .protocol import RPCError
+
+TOKEN_VERSION = "v1"
+# The desktop shell hands the TCP transport's shared token to the daemon in this variable.
+TCP_TOKEN_ENV = "DG_CORE_TCP_TOKEN"
+SUBJECT_PREFIX = "session:"
+CAPABILITIES = frozenset(
+    {
+        "encrypt",
+        "decrypt",
+        "scan",
+        "redact",
+        "policy",
+        "read-logs",
+        "progress",
+        "status",
+        "keys",
+        "admin",
+    }
+)
+
+
+@dataclass(frozen=True, slots=True)
+class Session:
+    """Authenticated client session bound to a connection."""
+
+    client: str
+    scopes: frozenset[str]
+    expires_at: int
+    device: str | None = None
+
+    @property
+    def subject(self) -> str:
+        return f"{SUBJECT_PREFIX}{self.client}"
+
+    def expired(self, now: float | None = None) -> bool:
+        return (time.time() if now is None else now) >= self.expires_at
+
+    def require(self, scope: str, method: str) -> None:
+        if self.expired():
+            raise RPCError(UNAUTHENTICATED, "Session expired", data={"method": method})
+        if scope not in self.scopes:
+            raise RPCError(
+                CAPABILITY_DENIED,
+                "Capability denied",
+                data={"method": method, "scope": scope, "subject": self.subject},
+            )
+
+    def describe(self) -> Dict[str, Any]:
+        described: Dict[str, Any] = {
+            "subject": self.subject,
+            "scopes": sorted(self.scopes),
+            "expires_at": self.expires_at,
+        }
+        if self.device is not None:
+            described["device"] = self.device
+        return described
+
+
+class SessionVerifier:
+    """Verify tokens minted by the desktop shell with the shared session key."""
+
+    def __init__(self, key: bytes) -> None:
+        if len(key) < 32:
+            raise ValueError("session key must be at least 32 bytes")
+        self._key = key
+
+    @classmethod
+    def from_file(cls, path: Path) -> "SessionVerifier":
+        raw = path.read_text(encoding="utf-8").strip()
+        return cls(base64.b64decode(raw))
+
+    def verify(self, token: str) -> Session:
+        try:
+            version, claims_b64, signature_b64 = token.split(".")
+        except (AttributeError, ValueError):
+            raise RPCError(UNAUTHENTICATED, "Malformed session token") from None
+        if version != TOKEN_VERSION:
+            raise RPCError(UNAUTHENTICATED, "Unsupported session token", data=version)
+        expected = _sign(self._key, f"{version}.{claims_b64}")
+        try:
+            signature = _b64decode(signature_b64)
+        except ValueError:
+            raise RPCError(UNAUTHENTICATED, "Malformed session token") from None
+        if not hmac.compare_digest(expected, signature):
+            raise RPCError(UNAUTHENTICATED, "Invalid session token")
+        try:
+            claims = json.loads(_b64decode(claims_b64))
+            session = Session(
+                client=str(claims["sub"]),
+                scopes=frozenset(str(scope) for scope in claims["scopes"]),
+                expires_at=int(claims["exp"]),
+            )
+        except (ValueError, KeyError, TypeError):
+            raise RPCError(UNAUTHENTICATED, "Malformed session token") from None
+        if session.expired():
+            raise RPCError(UNAUTHENTICATED, "Session expired")
+        return session
+
+
+def mint_token(key: bytes, client: str, scopes: Iterable[str], ttl: int) -> str:
+    """Mint a token in the format produced by the desktop shell."""
+
+    claims = {"sub": client, "scopes": sorted(set(scopes)), "exp": int(time.time()) + ttl}
+    claims_b64 = _b64encode(json.dumps(claims, separators=(",", ":")).encode("utf-8"))
+    signed = f"{TOKEN_VERSION}.{claims_b64}"
+    return f"{signed}.{_b64encode(_sign(key, signed))}"
+
+
+def transport_token_matches(expected: str, presented: Any) -> bool:
+    """Compare a presented TCP transport token in constant time."""
+
+    if not isinstance(presented, str):
+        return False
+    return hmac.compare_digest(expected.encode("utf-8"), presented.encode("utf-8"))
+
+
+def _sign(key: bytes, message: str) -> bytes:
+    return hmac.new(key, message.encode("ascii"), hashlib.sha256).digest()
+
+
+def _b64encode(data: bytes) -> str:
+    return base64.urlsafe_b64encode(data).rstrip(b"=").decode("ascii")
+
+
+def _b64decode(data: str) -> bytes:
+    padded = data + "=" * (-len(data) % 4)
+    try:
+        return base64.urlsafe_b64decode(padded.encode("ascii"))
+    except (UnicodeEncodeError, ValueError) as exc:
+        raise ValueError(str(exc)) from exc
+
+
+__all__ = [
+    "CAPABILITIES",
+    "CAPABILITY_DENIED",
+    "Session",
+    "SessionVerifier",
+    "TCP_TOKEN_ENV",
+    "UNAUTHENTICATED",
+    "mint_token",
+    "transport_token_matches",
+]
@@ -0,0 +1,83 @@
+"""Inherited listener sockets from systemd and launchd socket activation."""
+from __future__ import annotations
+
+import ctypes
+import ctypes.util
+import os
+import socket
+import sys
+from typing import MutableMapping
+
+SD_LISTEN_FDS_START = 3
+LAUNCHD_SOCKET_NAME = "Listeners"
+
+
+def systemd_listen_fds(
+    environ: MutableMapping[str, str] | None = None, *, unset_environment: bool = True
+) -> list[int]:
+    """Return descriptors passed by systemd, mirroring ``sd_listen_fds``."""
+
+    env = os.environ if environ is None else environ
+    try:
+        pid = int(env.get("LISTEN_PID", ""))
+        count = int(env.get("LISTEN_FDS", ""))
+    except ValueError:
+        return []
+    if pid != os.getpid() or count <= 0:
+        return []
+    if unset_environment:
+        for key in ("LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"):
+            env.pop(key, None)
+    return list(range(SD_LISTEN_FDS_START, SD_LISTEN_FDS_START + count))
+
+
+def launchd_listen_fds(name: str = LAUNCHD_SOCKET_NAME) -> list[int]:
+    """Return descriptors for ``name`` via ``launch_activate_socket`` on macOS."""
+
+    if sys.platform != "darwin":
+        return []
+    libc = ctypes.CDLL(ctypes.util.find_library("c") or "libc.dylib", use_errno=True)
+    activate = getattr(libc, "launch_activate_socket", None)
+    if activate is None:  # pragma: no cover - pre-10.10 macOS
+        return []
+    fds = ctypes.POINTER(ctypes.c_int)()
+    count = ctypes.c_size_t(0)
+    activate.argtypes = [
+        ctypes.c_char_p,
+        ctypes.POINTER(ctypes.POINTER(ctypes.c_int)),
+        ctypes.POINTER(ctypes.c_size_t),
+    ]
+    if activate(name.encode("utf-8"), ctypes.byref(fds), ctypes.byref(count)) != 0:
+        return []
+    try:
+        return [fds[index] for index in range(count.value)]
+    finally:
+        libc.free(fds)
+
+
+def inherited_listener(environ: MutableMapping[str, str] | None = None) -> socket.socket | None:
+    """Return the first activated listening socket, or ``None`` when not activated."""
+
+    if sys.platform == "win32":
+        return None
+    env = os.environ if environ is None else environ
+    fds = systemd_listen_fds(env)
+    if not fds:
+        fds = launchd_listen_fds(env.get("DG_LAUNCHD_SOCKET", LAUNCHD_SOCKET_NAME))
+    if not fds:
+        return None
+    listener = socket.socket(fileno=fds[0])
+    os.set_inheritable(listener.fileno(), False)
+    listener.setblocking(False)
+    for extra in fds[1:]:
+        os.close(extra)
+    return listener
+
+
+__all__ = [
+    "LAUNCHD_SOCKET_NAME",
+    "SD_LISTEN_FDS_START",
+    "inherited_listener",
+    "launchd_listen_fds",
+    "systemd_listen_fds",
+]
@@ -3,6 +3,7 @@ from __future__ import annotations
 
 import asyncio
 import os
+import socket
 import sys
 from abc import ABC, abstractmethod
 from asyncio import StreamReader, StreamWriter
@@ -14,14 +15,33 @@ from typing import Awaitable, Callable, Optional, Set
 from ..config import AppConfig
 from ..paths import runtime_config_dir
 from ..utils.validation import ensure_loopback_host, resolve_and_check_path
+from .activation import inherited_listener
 
 MessageHandler = Callable[["BaseConnection"], Awaitable[None]]
 
+DEFAULT_MAX_FRAME_BYTES = 512 * 1024
+
+_PIPE_PREFIX = "\\\\.\\pipe\\"
+_PIPE_BUFFER_BYTES = 64 * 1024
+_READ_CHUNK_BYTES = 4096
+_ACCEPT_POLL_MS = 200
+_ERROR_PIPE_CONNECTED = 535
+# ERROR_BROKEN_PIPE, ERROR_NO_DATA, ERROR_PIPE_NOT_CONNECTED, ERROR_OPERATION_ABORTED
+_CLOSED_PIPE_ERRORS = frozenset({109, 232, 233, 995})
+
 
 class ConnectionClosed(RuntimeError):
     """Raised when a connection is closed unexpectedly."""
 
 
+class FrameTooLarge(ConnectionClosed):
+    """Raised when a peer sends a frame larger than the transport accepts."""
+
+    def __init__(self, limit: int) -> None:
+        super().__init__(f"frame exceeds {limit} bytes")
+        self.limit = limit
+
+
 class BaseConnection(ABC):
     @abstractmethod
     async def receive(self) -> str:  # pragma: no cover - interface
@@ -40,11 +60,17 @@ class BaseConnection(ABC):
 class SocketConnection(BaseConnection):
     reader: StreamReader
     writer: StreamWriter
+    max_frame_bytes: int = DEFAULT_MAX_FRAME_BYTES
 
     async def receive(self) -> str:
-        data = await self.reader.readline()
+        try:
+            data = await self.reader.readline()
+        except ValueError as exc:
+            raise FrameTooLarge(self.max_frame_bytes) from exc
         if not data:
             raise ConnectionClosed("socket closed")
+        if len(data.rstrip(b"\r\n")) > self.max_frame_bytes:
+            raise FrameTooLarge(self.max_frame_bytes)
         return data.decode("utf-8").rstrip("\r\n")
 
     async def send(self, payload: str) -> None:
@@ -61,8 +87,9 @@ class SocketConnection(BaseConnection):
 
 
 class BaseTransport(ABC):
-    def __init__(self) -> None:
+    def __init__(self, *, max_frame_bytes: int = DEFAULT_MAX_FRAME_BYTES) -> None:
         self._clients: Set[BaseConnection] = set()
+        self.max_frame_bytes = max_frame_bytes
 
     @abstractmethod
     async def start(self, handler: MessageHandler) -> None:  # pragma: no cover - interface
@@ -77,13 +104,15 @@ class BaseTransport(ABC):
 
 
 class _SocketTransport(BaseTransport):
-    def __init__(self) -> None:
-        super().__init__()
+    def __init__(self, *, max_frame_bytes: int = DEFAULT_MAX_FRAME_BYTES) -> None:
+        super().__init__(max_frame_bytes=max_frame_bytes)
         self._server: asyncio.base_events.Server | None = None
 
     async def _serve(self, handler: MessageHandler, create_server: Callable[..., Awaitable[asyncio.base_events.Server]], *args, **kwargs) -> None:
         async def _client_connected(reader: StreamReader, writer: StreamWriter) -> None:
-            connection = SocketConnection(reader=reader, writer=writer)
+            connection = SocketConnection(
+                reader=reader, writer=writer, max_frame_bytes=self.max_frame_bytes
+            )
             self._clients.add(connection)
             try:
                 await handler(connection)
@@ -91,6 +120,7 @@ class _SocketTransport(BaseTransport):
                 self._clients.discard(connection)
                 await connection.close()
 
+        kwargs.setdefault("limit", self.max_frame_bytes + 2)
         self._server = await create_server(_client_connected, *args, **kwargs)
 
     async def close(self) -> None:
@@ -103,8 +133,8 @@ class _SocketTransport(BaseTransport):
 
 
 class UnixSocketTransport(_SocketTransport):
-    def __init__(self, path: Path) -> None:
-        super().__init__()
+    def __init__(self, path: Path, *, max_frame_bytes: int = DEFAULT_MAX_FRAME_BYTES) -> None:
+        super().__init__(max_frame_bytes=max_frame_bytes)
         self.path = path
 
     async def start(self, handler: MessageHandler) -> None:
@@ -123,8 +153,10 @@ class UnixSocketTransport(_SocketTransport):
 
 
 class TCPTransport(_SocketTransport):
-    def __init__(self, host: str, port: int) -> None:
-        super().__init__()
+    def __init__(
+        self, host: str, port: int, *, max_frame_bytes: int = DEFAULT_MAX_FRAME_BYTES
+    ) -> None:
+        super().__init__(max_frame_bytes=max_frame_bytes)
         self.host = ensure_loopback_host(host)
         self.port = port
 
@@ -132,76 +164,97 @@ class TCPTransport(_SocketTransport):
         await self._serve(handler, asyncio.start_server, host=self.host, port=self.port)
 
 
+class InheritedSocketTransport(_SocketTransport):
+    """Serve on a listener passed in by systemd or launchd socket activation."""
+
+    def __init__(
+        self, sock: socket.socket, *, max_frame_bytes: int = DEFAULT_MAX_FRAME_BYTES
+    ) -> None:
+        super().__init__(max_frame_bytes=max_frame_bytes)
+        self.sock = sock
+
+    @property
+    def address(self) -> str:
+        name = self.sock.getsockname()
+        if isinstance(name, tuple):
+            return f"{name[0]}:{name[1]}"
+        return name.decode("utf-8") if isinstance(name, bytes) else str(name)
+
+    async def start(self, handler: MessageHandler) -> None:
+        if self.sock.family == getattr(socket, "AF_UNIX", None):
+            await self._serve(handler, asyncio.start_unix_server, sock=self.sock)
+        else:
+            await self._serve(handler, asyncio.start_server, sock=self.sock)
+
+
 class NamedPipeTransport(BaseTransport):
-    """Windows named pipe transport using pywin32 in a background thread."""
+    """Windows named pipe transport using overlapped pywin32 I/O.
 
-    def __init__(self, pipe_name: str) -> None:
+    Each client gets its own pipe instance opened in byte mode with
+    ``FILE_FLAG_OVERLAPPED`` so a pending read never blocks a response or
+    notification written to the same handle from another thread.
+    """
+
+    def __init__(self, pipe_name: str, *, max_frame_bytes: int = DEFAULT_MAX_FRAME_BYTES) -> None:
         if sys.platform != "win32":  # pragma: no cover - platform guard
             raise RuntimeError("Named pipes only supported on Windows")
-        super().__init__()
+        super().__init__(max_frame_bytes=max_frame_bytes)
         self.pipe_name = pipe_name
         self._thread: Optional[Thread] = None
         self._stop_event = Event()
         self._loop: Optional[asyncio.AbstractEventLoop] = None
         self._tasks: Set[asyncio.Future] = set()
 
+    @property
+    def pipe_path(self) -> str:
+        if self.pipe_name.startswith(_PIPE_PREFIX):
+            return self.pipe_name
+        return f"{_PIPE_PREFIX}{self.pipe_name}"
+
     async def start(self, handler: MessageHandler) -> None:
         import win32file
         import win32pipe
 
         loop = asyncio.get_running_loop()
         self._loop = loop
-        pipe_path = self.pipe_name if self.pipe_name.startswith("\\\\.\\pipe\\") else f"\\\\.\\pipe\\{self.pipe_name}"
+        pipe_path = self.pipe_path
 
         def _run() -> None:
             while not self._stop_event.is_set():
                 handle = win32pipe.CreateNamedPipe(
                     pipe_path,
-                    win32pipe.PIPE_ACCESS_DUPLEX,
-                    win32pipe.PIPE_TYPE_MESSAGE | win32pipe.PIPE_READMODE_MESSAGE | win32pipe.PIPE_WAIT,
+                    win32pipe.PIPE_ACCESS_DUPLEX | win32file.FILE_FLAG_OVERLAPPED,
+                    win32pipe.PIPE_TYPE_BYTE | win32pipe.PIPE_READMODE_BYTE | win32pipe.PIPE_WAIT,
                     win32pipe.PIPE_UNLIMITED_INSTANCES,
-                    1024 * 64,
-                    1024 * 64,
+                    _PIPE_BUFFER_BYTES,
+                    _PIPE_BUFFER_BYTES,
                     0,
                     None,
                 )
-                try:
-                    win32pipe.ConnectNamedPipe(handle, None)
-                except OSError:
+                if not _wait_for_client(handle, self._stop_event):
                     win32file.CloseHandle(handle)
-                    if self._stop_event.is_set():
-                        break
                     continue
                 future = asyncio.run_coroutine_threadsafe(
                     self._handle_client(handle, handler), loop
                 )
                 self._tasks.add(future)
+                future.add_done_callback(self._tasks.discard)
 
         self._thread = Thread(target=_run, name="dg-core-pipe", daemon=True)
         self._thread.start()
 
     async def _handle_client(self, handle: int, handler: MessageHandler) -> None:
-        import win32file
-        import win32pipe
-
-        connection = PipeConnection(handle)
+        connection = PipeConnection(handle, self.max_frame_bytes)
         self._clients.add(connection)
         try:
             await handler(connection)
         finally:
             self._clients.discard(connection)
             await connection.close()
-            try:
-                win32pipe.DisconnectNamedPipe(handle)
-            except OSError:
-                pass
-            win32file.CloseHandle(handle)
 
     async def close(self) -> None:
         if self._thread:
             self._stop_event.set()
-            pipe_path = self.pipe_name if self.pipe_name.startswith("\\\\.\\pipe\\") else f"\\\\.\\pipe\\{self.pipe_name}"
-            await _touch_pipe(pipe_path)
             self._thread.join(timeout=1)
         for future in list(self._tasks):
             future.cancel()
@@ -211,76 +264,128 @@ class NamedPipeTransport(BaseTransport):
 
 
 class PipeConnection(BaseConnection):
-    """Async wrapper around a Windows named pipe handle."""
+    """Async wrapper around an overlapped Windows named pipe handle."""
 
-    def __init__(self, handle: int) -> None:
+    def __init__(self, handle: int, max_frame_bytes: int = DEFAULT_MAX_FRAME_BYTES) -> None:
+        import pywintypes
+        import win32event
         import win32file
+        import win32pipe
 
-        self._handle = handle
+        self._handle: Optional[int] = handle
+        self._max_frame_bytes = max_frame_bytes
         self._buffer = b""
         self._closed = False
+        self._send_lock = asyncio.Lock()
+        self._pywintypes = pywintypes
+        self._win32event = win32event
         self._win32file = win32file
+        self._win32pipe = win32pipe
 
     async def receive(self) -> str:
         loop = asyncio.get_running_loop()
         while b"\n" not in self._buffer:
+            if self._closed:
+                raise ConnectionClosed("pipe closed")
             chunk = await loop.run_in_executor(None, self._read_chunk)
             if not chunk:
                 self._closed = True
                 raise ConnectionClosed("pipe closed")
             self._buffer += chunk
+            if len(self._buffer) > self._max_frame_bytes and b"\n" not in self._buffer:
+                raise FrameTooLarge(self._max_frame_bytes)
         line, self._buffer = self._buffer.split(b"\n", 1)
         return line.decode("utf-8").rstrip("\r")
 
     async def send(self, payload: str) -> None:
         data = payload.encode("utf-8") + b"\n"
         loop = asyncio.get_running_loop()
-        await loop.run_in_executor(None, self._write_chunk, data)
+        async with self._send_lock:
+            if self._closed:
+                raise ConnectionClosed("pipe closed")
+            await loop.run_in_executor(None, self._write_chunk, data)
 
     async def close(self) -> None:
-        if self._closed:
-            return
         self._closed = True
+        handle, self._handle = self._handle, None
+        if handle is None:
+            return
+        try:
+            self._win32pipe.DisconnectNamedPipe(handle)
+        except self._pywintypes.error:
+            pass
         try:
-            self._win32file.CloseHandle(self._handle)
-        except Exception:  # pragma: no cover - best effort
+            self._win32file.CloseHandle(handle)
+        except self._pywintypes.error:  # pragma: no cover - best effort
             pass
 
+    def _overlapped(self) -> object:
+        overlapped = self._pywintypes.OVERLAPPED()
+        overlapped.hEvent = self._win32event.CreateEvent(None, True, False, None)
+        return overlapped
+
     def _read_chunk(self) -> bytes:
-        _, data = self._win32file.ReadFile(self._handle, 4096)
-        return data
+        handle = self._handle
+        if handle is None:
+            return b""
+        overlapped = self._overlapped()
+        buffer = self._win32file.AllocateReadBuffer(_READ_CHUNK_BYTES)
+        try:
+            self._win32file.ReadFile(handle, buffer, overlapped)
+            size = self._win32file.GetOverlappedResult(handle, overlapped, True)
+        except self._pywintypes.error as exc:
+            if exc.winerror in _CLOSED_PIPE_ERRORS:
+                return b""
+            raise
+        return bytes(buffer[:size])
 
     def _write_chunk(self, data: bytes) -> None:
-        self._win32file.WriteFile(self._handle, data)
+        handle = self._handle
+        if handle is None:
+            raise ConnectionClosed("pipe closed")
+        overlapped = self._overlapped()
+        try:
+            self._win32file.WriteFile(handle, data, overlapped)
+            self._win32file.GetOverlappedResult(handle, overlapped, True)
+        except self._pywintypes.error as exc:
+            if exc.winerror in _CLOSED_PIPE_ERRORS:
+                raise ConnectionClosed("pipe closed") from exc
+            raise
+
 
+def _wait_for_client(handle: int, stop_event: Event) -> bool:
+    """Wait for a client on an overlapped pipe instance, polling ``stop_event``."""
 
-async def _touch_pipe(path: str) -> None:
-    if sys.platform != "win32":  # pragma: no cover
-        return
+    import pywintypes
+    import win32event
     import win32file
     import win32pipe
 
-    loop = asyncio.get_running_loop()
-
-    def _connect() -> None:
+    overlapped = pywintypes.OVERLAPPED()
+    overlapped.hEvent = win32event.CreateEvent(None, True, False, None)
+    try:
+        result = win32pipe.ConnectNamedPipe(handle, overlapped)
+    except pywintypes.error:
+        return False
+    if result == _ERROR_PIPE_CONNECTED:
+        return True
+    while not stop_event.is_set():
+        signalled = win32event.WaitForSingleObject(overlapped.hEvent, _ACCEPT_POLL_MS)
+        if signalled != win32event.WAIT_OBJECT_0:
+            continue
         try:
-            handle = win32file.CreateFile(
-                path,
-                win32file.GENERIC_READ | win32file.GENERIC_WRITE,
-                0,
-                None,
-                win32file.OPEN_EXISTING,
-                0,
-                None,
-            )
-            win32file.CloseHandle(handle)
-        except Exception:
-            pass
-
-    await loop.run_in_executor(None, _connect)
+            win32file.GetOverlappedResult(handle, overlapped, False)
+        except pywintypes.error:
+            return False
+        return True
+    win32file.CancelIo(handle)
+    return False
 
 
 def create_transport(config: AppConfig) -> BaseTransport:
+    activated = inherited_listener()
+    if activated is not None:
+        return InheritedSocketTransport(activated)
     ipc_config = config.ipc
     transport = ipc_config.resolved_transport()
     if transport == "uds":
@@ -302,6 +407,7 @@ def create_transport(config: AppConfig) -> BaseTransport:
 
 __all__ = [
     "BaseTransport",
+    "InheritedSocketTransport",
     "SocketConnection",
     "NamedPipeTransport",
     "UnixSocketTransport",
@@ -309,4 +415,6 @@ __all__ = [
     "create_transport",
     "BaseConnection",
     "ConnectionClosed",
+    "DEFAULT_MAX_FRAME_BYTES",
+    "FrameTooLarge",
 ]
\ No newline at end of file
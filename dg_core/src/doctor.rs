@@ -0,0 +1,279 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::integrity::{check_data_dir, IntegrityComponent};
+use crate::keyring::Keyring;
+
+pub const LOW_DISK_BYTES: u64 = 512 * 1024 * 1024;
+pub const CRITICAL_DISK_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Ok,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Ok => "ok",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    pub check: String,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl Finding {
+    pub fn ok(check: &str, message: impl Into<String>) -> Self {
+        Self {
+            check: check.into(),
+            severity: Severity::Ok,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    pub fn warning(check: &str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            check: check.into(),
+            severity: Severity::Warning,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    pub fn error(check: &str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            check: check.into(),
+            severity: Severity::Error,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DoctorReport {
+    pub findings: Vec<Finding>,
+}
+
+impl DoctorReport {
+    pub fn push(&mut self, finding: Finding) {
+        self.findings.push(finding);
+    }
+
+    pub fn extend(&mut self, findings: impl IntoIterator<Item = Finding>) {
+        self.findings.extend(findings);
+    }
+
+    pub fn worst(&self) -> Severity {
+        self.findings
+            .iter()
+            .map(|finding| finding.severity)
+            .max()
+            .unwrap_or(Severity::Ok)
+    }
+
+    pub fn healthy(&self) -> bool {
+        self.worst() < Severity::Error
+    }
+
+    pub fn problems(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity > Severity::Ok)
+    }
+}
+
+pub async fn examine(data_dir: &Path) -> DoctorReport {
+    let mut report = DoctorReport::default();
+    match fs::metadata(data_dir).await {
+        Ok(metadata) if !metadata.is_dir() => {
+            report.push(Finding::error(
+                "data-dir",
+                format!("{} is not a directory", data_dir.display()),
+                "Point DG_DATA_DIR or --data-dir at a directory, or move the file out of the way",
+            ));
+            return report;
+        }
+        Ok(metadata) if metadata.permissions().readonly() => report.push(Finding::warning(
+            "data-dir",
+            format!("{} is read-only", data_dir.display()),
+            "Make the directory writable, or keep running in read-only mode for archive access",
+        )),
+        Ok(_) => report.push(Finding::ok(
+            "data-dir",
+            format!("{} exists", data_dir.display()),
+        )),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            report.push(Finding::warning(
+                "data-dir",
+                format!("{} does not exist yet", data_dir.display()),
+                "Start Data Guardian once to create it, or check DG_DATA_DIR",
+            ));
+            report.push(disk_space(data_dir));
+            return report;
+        }
+        Err(err) => {
+            report.push(Finding::error(
+                "data-dir",
+                format!("unable to inspect {}: {err}", data_dir.display()),
+                "Check that the current user owns the data directory",
+            ));
+            return report;
+        }
+    }
+
+    let integrity = check_data_dir(data_dir).await;
+    for component in [IntegrityComponent::Key, IntegrityComponent::Policy] {
+        let (check, healthy) = match component {
+            IntegrityComponent::Key => ("key-permissions", "master key is private to its owner"),
+            _ => ("policy", "policy is valid"),
+        };
+        let mut issues = integrity
+            .outstanding()
+            .filter(|issue| issue.component == component)
+            .peekable();
+        if issues.peek().is_none() {
+            report.push(Finding::ok(check, healthy));
+        }
+        for issue in issues {
+            let fix = match (component, issue.repairable) {
+                (IntegrityComponent::Key, true) => {
+                    "Restrict the key to its owner (chmod 600) or run the data directory repair"
+                }
+                (IntegrityComponent::Key, false) => "Restore keys/master.key from a backup",
+                _ => "Fix the policy file; `dg policy simulate` shows how each rule evaluates",
+            };
+            let finding = if issue.fatal {
+                Finding::error(check, issue.problem.clone(), fix)
+            } else {
+                Finding::warning(check, issue.problem.clone(), fix)
+            };
+            report.push(finding);
+        }
+    }
+    for issue in integrity.outstanding().filter(|issue| {
+        !matches!(
+            issue.component,
+            IntegrityComponent::Key | IntegrityComponent::Policy
+        )
+    }) {
+        report.push(Finding::warning(
+            "integrity",
+            issue.problem.clone(),
+            "Run the data directory repair (DG_REPAIR_ON_INIT=true or repair_data_dir)",
+        ));
+    }
+
+    match Keyring::load_read_only(data_dir).await {
+        Ok(keyring) => report.push(Finding::ok(
+            "keyring",
+            format!("{} keys loaded", keyring.identities().len()),
+        )),
+        Err(err) => report.push(Finding::error(
+            "keyring",
+            format!("keyring cannot be loaded: {err}"),
+            "Restore keyring.json from a backup or re-import the affected keys",
+        )),
+    }
+
+    report.push(disk_space(data_dir));
+    report
+}
+
+fn disk_space(data_dir: &Path) -> Finding {
+    let existing = data_dir
+        .ancestors()
+        .find(|dir| dir.exists())
+        .unwrap_or(data_dir);
+    let free = match available_space(existing) {
+        Ok(free) => free,
+        Err(err) => {
+            return Finding::warning(
+                "disk-space",
+                format!(
+                    "unable to read free space for {}: {err}",
+                    existing.display()
+                ),
+                "Check the volume holding the data directory manually",
+            )
+        }
+    };
+    let mib = free / (1024 * 1024);
+    if free < CRITICAL_DISK_BYTES {
+        Finding::error(
+            "disk-space",
+            format!("only {mib} MiB free on the data directory volume"),
+            "Free disk space; encryption writes a temporary copy of every file",
+        )
+    } else if free < LOW_DISK_BYTES {
+        Finding::warning(
+            "disk-space",
+            format!("{mib} MiB free on the data directory volume"),
+            "Free disk space before encrypting large files or directories",
+        )
+    } else {
+        Finding::ok("disk-space", format!("{mib} MiB free"))
+    }
+}
+
+#[cfg(unix)]
+pub fn available_space(path: &Path) -> std::io::Result<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())?;
+    let mut stats = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is NUL terminated and `stats` is only read after statvfs succeeds.
+    let stats = unsafe {
+        if libc::statvfs(path.as_ptr(), stats.as_mut_ptr()) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        stats.assume_init()
+    };
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(windows)]
+pub fn available_space(path: &Path) -> std::io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available = 0u64;
+    // SAFETY: `wide` is NUL terminated and the out pointers are valid for the call.
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(available)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn available_space(_path: &Path) -> std::io::Result<u64> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "free space is not available on this platform",
+    ))
+}
@@ -1,20 +1,53 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
 use aes_gcm::{Aes256Gcm, Nonce};
-use rand::rngs::OsRng;
-use rand::RngCore;
+use base64::{engine::general_purpose, Engine as _};
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 use tracing::{debug, info, instrument, warn};
+use zeroize::Zeroizing;
 
-use crate::api::{DGConfig, DGError, DGResult, DataGuardian, EncryptRequest, Envelope};
-use crate::policy::PolicyEngine;
+use crate::api::{
+    DGConfig, DGError, DGResult, DataGuardian, EncryptRequest, Envelope, LoadedProfile,
+};
+use crate::audit::{AuditEvent, AuditFilter, AuditLog};
+use crate::chunking::{self, ChunkTable, ChunkingParams, RangeVerification, CHUNKS_FIELD};
+use crate::device::DeviceKey;
+use crate::entropy::Entropy;
+use crate::grant::{self, AccessGrant, GrantAction, GrantRequest, SignedGrant};
+use crate::identity::Identity;
+use crate::index::{EnvelopeIndex, IndexEntry, Reconciliation};
+use crate::integrity::{self, IntegrityReport};
+use crate::journal::{self, JournalAppend, JOURNAL_FIELD};
+use crate::key_age::{KeyAgeState, KeyExpiryReport, KeyRotation, MaxKeyAge};
+use crate::keyring::{KeyAlgorithm, KeyMetadata, Keyring};
+use crate::management;
+use crate::metadata::{self, MetadataSchema, METADATA_FIELD};
+use crate::offline::{OfflineRequest, OfflineResponse};
+use crate::passphrase::{self, KeyProtection, WrappedKey};
+use crate::policy::{self, DecisionExplanation, DecisionSource, PolicyEngine, PolicyFormat};
+use crate::policy_cache::PolicyCacheStats;
+use crate::preview::{self, Preview, PREVIEW_FIELD};
+use crate::provenance::{self, ProvenanceEntry, RekeyContext, PROVENANCE_FIELD};
+use crate::resource_attrs::ResourceAttributes;
+use crate::secret::SecretKey;
+use crate::selftest::{self, SelfTestReport};
+use crate::signature::{self, SIGNATURE_EXTENSION};
+use crate::state::{self, StateStore};
+use crate::subkey::{KeyHierarchy, KeyPurpose};
+use crate::threshold::{self, KeyShare, ThresholdSpec};
+use crate::trust::{self, TrustRole, TrustStore};
+use crate::warmup::WarmupReport;
+use crate::wrap::{self, KeyWrap, WrapKind};
 
 const KEY_FILE: &str = "master.key";
-const POLICY_FILE: &str = "policy.json";
+const NONCE_LEN: usize = 12;
+const MASTER_KEY_ID: &str = "master";
+const SYSTEM_SUBJECT: &str = "system";
 
 #[derive(Clone)]
 pub struct DefaultDataGuardian {
@@ -24,8 +57,16 @@ pub struct DefaultDataGuardian {
 #[derive(Default)]
 struct InnerState {
     config: Option<DGConfig>,
-    key: Option<[u8; 32]>,
+    // Wiped and unlocked from memory when dropped, so locking the engine scrubs it.
+    key: Option<SecretKey>,
     policy: Option<PolicyEngine>,
+    keyring: Option<Keyring>,
+    audit: Option<AuditLog>,
+    state: Option<Arc<dyn StateStore>>,
+    index: Option<Arc<Mutex<EnvelopeIndex>>>,
+    integrity: Option<IntegrityReport>,
+    metadata_schema: Option<MetadataSchema>,
+    entropy: Entropy,
 }
 
 impl DefaultDataGuardian {
@@ -41,51 +82,270 @@ impl DataGuardian for DefaultDataGuardian {
     #[instrument(skip(self))]
     async fn init(&self, cfg: DGConfig) -> DGResult<()> {
         debug!(profile = %cfg.profile, data_dir = %cfg.data_dir.display(), "initializing Data Guardian");
-        fs::create_dir_all(&cfg.data_dir)
-            .await
-            .map_err(|err| DGError::Config(format!("failed to create data dir: {err}")))?;
+        if cfg.self_test_on_init {
+            let report = selftest::run();
+            if !report.passed {
+                let failed: Vec<&str> =
+                    report.failures().map(|check| check.name.as_str()).collect();
+                return Err(DGError::Crypto(format!(
+                    "self-test failed: {}",
+                    failed.join(", ")
+                )));
+            }
+            debug!(checks = report.checks.len(), "startup self-test passed");
+        }
+        let entropy = Entropy::from_seed(cfg.deterministic_seed)?;
+        if let Some(max_key_age) = &cfg.max_key_age {
+            max_key_age.validate().map_err(DGError::Config)?;
+        }
+        if cfg.read_only {
+            if cfg.repair_on_init {
+                return Err(DGError::Config(
+                    "repair_on_init cannot be used with a read-only data directory".into(),
+                ));
+            }
+            if !cfg.data_dir.is_dir() {
+                return Err(DGError::Config(format!(
+                    "read-only data directory {} does not exist",
+                    cfg.data_dir.display()
+                )));
+            }
+        } else {
+            fs::create_dir_all(&cfg.data_dir)
+                .await
+                .map_err(|err| DGError::Config(format!("failed to create data dir: {err}")))?;
+        }
+
+        if let Some(lock) = management::lock_state(&cfg.data_dir).await? {
+            return Err(DGError::PolicyDenied(format!(
+                "{} was locked by {} ({}); apply a signed unlock command to reopen it",
+                cfg.data_dir.display(),
+                lock.locked_by,
+                if lock.reason.is_empty() {
+                    "no reason given"
+                } else {
+                    lock.reason.as_str()
+                }
+            )));
+        }
+
+        let report = if cfg.repair_on_init {
+            integrity::repair_data_dir(&cfg.data_dir).await
+        } else {
+            integrity::check_data_dir(&cfg.data_dir).await
+        };
+        for issue in report.outstanding() {
+            warn!(component = ?issue.component, fatal = issue.fatal, repairable = issue.repairable, "{}", issue.problem);
+        }
+        let blocking: Vec<&str> = report
+            .blocking()
+            .map(|issue| issue.problem.as_str())
+            .collect();
+        if !blocking.is_empty() {
+            return Err(DGError::Config(format!(
+                "data directory integrity check failed: {}",
+                blocking.join("; ")
+            )));
+        }
 
-        let key = load_or_create_key(&cfg.data_dir).await?;
-        let policy = load_policy(&cfg.data_dir).await?;
+        let key = if passphrase::requires_unlock(&cfg.data_dir, cfg.key_protection).await {
+            if cfg.read_only && passphrase::load(&cfg.data_dir).await?.is_none() {
+                return Err(DGError::Config(
+                    "a passphrase cannot be set on a read-only data directory".into(),
+                ));
+            }
+            info!("master key is passphrase-protected; waiting for unlock");
+            None
+        } else {
+            let key = load_or_create_key(&cfg.data_dir, !cfg.read_only, &entropy).await?;
+            Some(SecretKey::new(&key))
+        };
+        let device = DeviceKey::load_or_create(&cfg.data_dir, !cfg.read_only, &entropy)
+            .await?
+            .map(|device| device.device_id().to_owned());
+        let (mut keyring, audit, state, index) = if cfg.read_only {
+            info!(data_dir = %cfg.data_dir.display(), "opening data directory read-only");
+            (
+                Keyring::load_read_only(&cfg.data_dir).await?,
+                AuditLog::open_read_only(&cfg.data_dir).with_device(device),
+                state::open_read_only(&cfg.data_dir, cfg.state_backend)?,
+                EnvelopeIndex::load_read_only(&cfg.data_dir).await?,
+            )
+        } else {
+            (
+                Keyring::load(&cfg.data_dir).await?,
+                AuditLog::open(&cfg.data_dir).await?.with_device(device),
+                state::open(&cfg.data_dir, cfg.state_backend)?,
+                EnvelopeIndex::load(&cfg.data_dir).await?,
+            )
+        };
+        if key.is_none() {
+            keyring.lock();
+        }
+        let policy = load_policy(&cfg.data_dir, &keyring).await?;
+        let metadata_schema = metadata::load_schema(&cfg.data_dir).await?;
 
         let mut guard = self.inner.write().await;
         guard.config = Some(cfg);
-        guard.key = Some(key);
+        guard.key = key;
         guard.policy = Some(policy);
+        guard.keyring = Some(keyring);
+        guard.audit = Some(audit);
+        guard.state = Some(state);
+        guard.index = Some(Arc::new(Mutex::new(index)));
+        guard.integrity = Some(report);
+        guard.metadata_schema = metadata_schema;
+        guard.entropy = entropy;
         info!("Data Guardian initialized");
         Ok(())
     }
 
     #[instrument(skip(self, req))]
-    async fn encrypt(&self, req: EncryptRequest) -> DGResult<Envelope> {
+    async fn encrypt(&self, mut req: EncryptRequest) -> DGResult<Envelope> {
+        let caller = Caller::current();
+        let plaintext = Zeroizing::new(std::mem::take(&mut req.plaintext));
         let guard = self.inner.read().await;
+        guard.writable("encryption")?;
         let (key, config, policy) = guard.parts()?;
 
-        if !policy
-            .evaluate("system", "encrypt", "data")
+        let allowed = policy
+            .evaluate_any(&caller.subjects, "encrypt", "data")
             .await
-            .map_err(DGError::Internal)?
-        {
+            .map_err(DGError::Internal)?;
+        guard
+            .record_decision(&caller.subject, "encrypt", "data", allowed)
+            .await?;
+        if !allowed {
             return Err(DGError::PolicyDenied("encryption denied by policy".into()));
         }
+        metadata::check_keys(&req.metadata)?;
+        if let Some(schema) = &guard.metadata_schema {
+            schema.validate(&req.metadata)?;
+        }
+        if req.journal
+            && (req.chunking.is_some() || req.previous.is_some() || req.threshold.is_some())
+        {
+            return Err(DGError::Config(
+                "append-mode envelopes cannot be chunked, reuse an earlier version or need custodian shares"
+                    .into(),
+            ));
+        }
+        guard.check_key_age(&req.recipients).await?;
+        let state = guard.state()?;
+        policy
+            .charge_quota(state, &caller.subjects, "encrypt", unix_now())
+            .await?;
 
-        let cipher = Aes256Gcm::new(key.into());
-        let mut nonce_bytes = [0u8; 12];
-        OsRng.fill_bytes(&mut nonce_bytes);
-        let nonce = Nonce::from_slice(&nonce_bytes);
-        let ciphertext = cipher
-            .encrypt(nonce, req.plaintext.as_ref())
-            .map_err(|err| DGError::Crypto(format!("failed to encrypt: {err}")))?;
+        let mut bound = serde_json::json!({});
+        if !req.metadata.is_empty() {
+            bound[METADATA_FIELD] = serde_json::json!(req.metadata);
+        }
+        let aad = provenance::associated_data(&bound)?;
+        let previous = req.previous.take();
+        let reused = match &previous {
+            Some(previous) if req.threshold.is_none() => {
+                guard.previous_version(key, previous, &req.recipients)?
+            }
+            Some(_) => {
+                return Err(DGError::Config(
+                    "threshold envelopes cannot reuse an earlier version".into(),
+                ))
+            }
+            None => None,
+        };
+        let file_key = match &reused {
+            Some(reused) => reused.file_key.clone(),
+            None => guard.entropy.key(),
+        };
+        let params = req
+            .chunking
+            .or(reused.as_ref().map(|reused| reused.table.params))
+            .or(previous.as_ref().map(|_| ChunkingParams::default()));
+        let (payload, chunks) = match params {
+            Some(params) => {
+                let prior = reused.as_ref().zip(previous.as_ref()).map(|(reused, env)| {
+                    (&reused.table, env.bytes.as_slice(), reused.aad.as_slice())
+                });
+                let (payload, table) =
+                    chunking::seal(&guard.entropy, &file_key, &plaintext, &aad, params, prior)?;
+                (payload, Some(table))
+            }
+            None if req.journal => (
+                journal::seal(&guard.entropy, &file_key, &plaintext, &aad)?,
+                None,
+            ),
+            None => (
+                seal_payload_with(&guard.entropy, &file_key, &plaintext, &aad)?,
+                None,
+            ),
+        };
+        let (mut wraps, threshold) = match req.threshold {
+            Some(required) => {
+                let (wraps, spec) = guard.share_file_key(&file_key, &req.recipients, required)?;
+                guard
+                    .audit()?
+                    .record(
+                        AuditEvent::new(&caller.subject, "threshold", "data", "allow").with_details(
+                            serde_json::json!({
+                                "required": spec.required,
+                                "custodians": wraps.iter().map(|wrap| &wrap.key_id).collect::<Vec<_>>(),
+                            }),
+                        ),
+                    )
+                    .await?;
+                (wraps, Some(spec))
+            }
+            None => {
+                let mut wraps = vec![wrap::wrap_symmetric(
+                    &guard.entropy,
+                    WrapKind::Master,
+                    key,
+                    &file_key,
+                )?];
+                wraps.extend(guard.recipient_wraps(&file_key, &req.recipients)?);
+                (wraps, None)
+            }
+        };
 
-        let mut payload = Vec::with_capacity(12 + ciphertext.len());
-        payload.extend_from_slice(&nonce_bytes);
-        payload.extend_from_slice(&ciphertext);
+        if let Some(escrow_wrap) = guard.escrow_wrap(policy, &file_key).await? {
+            wraps.push(escrow_wrap);
+        }
+        let escrowed = guard.subkey_escrow_wraps(&file_key, &wraps)?;
+        wraps.extend(escrowed);
+        guard.record_subkey_roots(&mut wraps)?;
+        for entry in &wraps {
+            state::record_key_use(state, wrap_key_id(entry))?;
+        }
 
-        let meta = serde_json::json!({
+        let mut meta = serde_json::json!({
             "labels": req.labels,
             "recipients": req.recipients,
             "profile": config.profile,
+            "key_wraps": wraps,
         });
+        if let Some(spec) = threshold {
+            meta["threshold"] = serde_json::json!(spec);
+        }
+        if let Some(table) = chunks {
+            meta[CHUNKS_FIELD] = serde_json::json!(table);
+        }
+        if req.journal {
+            meta[JOURNAL_FIELD] = journal::descriptor();
+        }
+        if let Some(custom) = bound.get(METADATA_FIELD) {
+            meta[METADATA_FIELD] = custom.clone();
+        }
+        if let Some(thumbnail) = req.preview.take() {
+            if policy
+                .evaluate_any(&caller.subjects, "preview", "data")
+                .await
+                .map_err(DGError::Internal)?
+            {
+                meta[PREVIEW_FIELD] = preview::seal(&guard.entropy, &file_key, &thumbnail)?;
+            } else {
+                debug!("preview dropped by policy");
+            }
+        }
 
         Ok(Envelope {
             bytes: payload,
@@ -94,45 +354,1366 @@ impl DataGuardian for DefaultDataGuardian {
     }
 
     #[instrument(skip(self, env))]
-    async fn decrypt(&self, env: Envelope) -> DGResult<Vec<u8>> {
+    async fn decrypt(&self, env: Envelope) -> DGResult<Zeroizing<Vec<u8>>> {
+        let caller = Caller::current();
         let guard = self.inner.read().await;
         let (key, _config, policy) = guard.parts()?;
 
-        if env.bytes.len() < 12 {
+        if env.bytes.len() < NONCE_LEN {
             return Err(DGError::Crypto("envelope missing nonce".into()));
         }
 
+        let allowed = policy
+            .evaluate_any(&caller.subjects, "decrypt", "data")
+            .await
+            .map_err(DGError::Internal)?;
+        guard
+            .record_decision(&caller.subject, "decrypt", "data", allowed)
+            .await?;
+        if !allowed {
+            return Err(DGError::PolicyDenied("decryption denied by policy".into()));
+        }
+        let state = guard.state()?;
+        let now = unix_now();
+        policy
+            .check_lockout(state, &caller.subject, "decrypt", now)
+            .await?;
+        policy
+            .charge_quota(state, &caller.subjects, "decrypt", now)
+            .await?;
+
+        match guard.open_envelope(key, &env) {
+            Ok((plaintext, key_id)) => {
+                // Wiped on drop, whether recording the decryption fails or the caller is done.
+                let plaintext = Zeroizing::new(plaintext);
+                policy
+                    .record_success(state, &caller.subject, "decrypt")
+                    .await?;
+                state::record_key_use(state, &key_id)?;
+                Ok(plaintext)
+            }
+            Err(err) => {
+                if matches!(err, DGError::Crypto(_)) {
+                    policy
+                        .record_failure(state, &caller.subject, "decrypt", now)
+                        .await?;
+                }
+                Err(err)
+            }
+        }
+    }
+
+    #[instrument(skip(self))]
+    async fn check_policy(&self, subject: &str, action: &str, resource: &str) -> DGResult<bool> {
+        let guard = self.inner.read().await;
+        let allowed = guard
+            .policy()?
+            .evaluate(subject, action, resource)
+            .await
+            .map_err(DGError::Internal)?;
+        guard
+            .record_decision(subject, action, resource, allowed)
+            .await?;
+        Ok(allowed)
+    }
+
+    #[instrument(skip(self))]
+    async fn check_identity(
+        &self,
+        identity: &Identity,
+        action: &str,
+        resource: &str,
+    ) -> DGResult<bool> {
+        Ok(self
+            .explain_decision(identity, action, resource)
+            .await?
+            .allowed())
+    }
+
+    #[instrument(skip(self))]
+    async fn explain_decision(
+        &self,
+        identity: &Identity,
+        action: &str,
+        resource: &str,
+    ) -> DGResult<DecisionExplanation> {
+        let guard = self.inner.read().await;
+        let policy = guard.policy()?;
+        // Attributes are only read for policies that match on them and for paths that exist.
+        let path = Path::new(resource);
+        let attributes = if policy.uses_resource_attributes().await && path.is_absolute() {
+            let path = path.to_path_buf();
+            tokio::task::spawn_blocking(move || ResourceAttributes::for_path(&path))
+                .await
+                .map_err(|err| DGError::Internal(format!("attribute lookup failed: {err}")))?
+        } else {
+            ResourceAttributes::default()
+        };
+        let explanation = policy
+            .explain_with(&identity.subjects(), action, resource, &attributes)
+            .await;
+        if matches!(
+            explanation.source,
+            DecisionSource::Fallback | DecisionSource::StaleCache
+        ) {
+            guard
+                .audit()?
+                .record(
+                    AuditEvent::new(
+                        &identity.subject(),
+                        "policy_fallback",
+                        resource,
+                        explanation.decision.as_str(),
+                    )
+                    .with_details(serde_json::json!({
+                        "action": action,
+                        "explanation": explanation,
+                    })),
+                )
+                .await?;
+        }
+        Ok(explanation)
+    }
+
+    #[instrument(skip(self, pem))]
+    async fn import_private_key(&self, pem: &str, label: &str) -> DGResult<KeyMetadata> {
+        let mut guard = self.inner.write().await;
+        guard.writable("importing keys")?;
+        guard.keyring_mut()?.import_private_key(pem, label).await
+    }
+
+    #[instrument(skip(self, pem))]
+    async fn import_public_key(&self, pem: &str, contact: &str) -> DGResult<KeyMetadata> {
+        let mut guard = self.inner.write().await;
+        guard.writable("importing keys")?;
+        guard.keyring_mut()?.import_public_key(pem, contact).await
+    }
+
+    #[instrument(skip(self))]
+    async fn revoke_key(&self, id: &str) -> DGResult<KeyMetadata> {
+        let caller = Caller::current();
+        let mut guard = self.inner.write().await;
+        guard.writable("revoking keys")?;
+        let meta = guard.keyring_mut()?.revoke(id).await?;
+        guard
+            .audit()?
+            .record(
+                AuditEvent::new(&caller.subject, "revoke", "key", "allow")
+                    .with_details(serde_json::json!({ "key_id": meta.id, "label": meta.label })),
+            )
+            .await?;
+        Ok(meta)
+    }
+
+    #[instrument(skip(self))]
+    async fn generate_identity(&self, label: &str) -> DGResult<KeyMetadata> {
+        let caller = Caller::current();
+        let mut guard = self.inner.write().await;
+        guard.writable("generating keys")?;
+        let InnerState {
+            keyring, entropy, ..
+        } = &mut *guard;
+        let meta = keyring
+            .as_mut()
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))?
+            .generate_x25519(entropy, label)
+            .await?;
+        guard
+            .audit()?
+            .record(
+                AuditEvent::new(&caller.subject, "generate_key", "key", "allow")
+                    .with_details(serde_json::json!({ "key_id": meta.id, "label": meta.label })),
+            )
+            .await?;
+        Ok(meta)
+    }
+
+    #[instrument(skip(self))]
+    async fn generate_key_hierarchy(&self, label: &str) -> DGResult<KeyHierarchy> {
+        let caller = Caller::current();
+        let mut guard = self.inner.write().await;
+        guard.writable("generating keys")?;
+        let InnerState {
+            keyring, entropy, ..
+        } = &mut *guard;
+        let hierarchy = keyring
+            .as_mut()
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))?
+            .generate_hierarchy(entropy, label)
+            .await?;
+        guard
+            .audit()?
+            .record(
+                AuditEvent::new(&caller.subject, "generate_key", "key", "allow").with_details(
+                    serde_json::json!({
+                        "key_id": hierarchy.root.id,
+                        "label": hierarchy.root.label,
+                        "subkeys": hierarchy.subkeys.iter().map(|meta| &meta.id).collect::<Vec<_>>(),
+                    }),
+                ),
+            )
+            .await?;
+        Ok(hierarchy)
+    }
+
+    async fn key_expiry(&self) -> DGResult<KeyExpiryReport> {
+        let guard = self.inner.read().await;
+        let Some(policy) = guard.max_key_age().await? else {
+            return Ok(KeyExpiryReport::default());
+        };
+        let now = unix_now();
+        let keys = guard
+            .keyring
+            .as_ref()
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))?
+            .active_keys()
+            .into_iter()
+            .map(|(meta, secret)| {
+                let rotatable = secret
+                    && (meta.algorithm == KeyAlgorithm::X25519 || meta.certificate.is_some());
+                policy.expiry(&meta, rotatable, now)
+            })
+            .collect();
+        Ok(KeyExpiryReport {
+            policy: Some(policy),
+            keys,
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn rotate_key(&self, id: &str) -> DGResult<KeyRotation> {
+        let caller = Caller::current();
+        let mut guard = self.inner.write().await;
+        guard.writable("rotating keys")?;
+        let InnerState {
+            keyring, entropy, ..
+        } = &mut *guard;
+        let (retired, replacement) = keyring
+            .as_mut()
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))?
+            .rotate(entropy, id)
+            .await?;
+        guard
+            .audit()?
+            .record(
+                AuditEvent::new(&caller.subject, "rotate_key", "key", "allow").with_details(
+                    serde_json::json!({
+                        "key_id": retired.id,
+                        "replacement": replacement.id,
+                        "label": retired.label,
+                    }),
+                ),
+            )
+            .await?;
+        Ok(KeyRotation {
+            retired,
+            replacement,
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn list_identities(&self) -> DGResult<Vec<KeyMetadata>> {
+        let guard = self.inner.read().await;
+        guard
+            .keyring
+            .as_ref()
+            .map(Keyring::identities)
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))
+    }
+
+    #[instrument(skip(self, env))]
+    async fn rekey(&self, env: Envelope, context: RekeyContext) -> DGResult<Envelope> {
+        let caller = Caller::current();
+        let guard = self.inner.read().await;
+        guard.writable("re-encryption")?;
+        let (key, _config, policy) = guard.parts()?;
+
         if !policy
-            .evaluate("system", "decrypt", "data")
+            .evaluate_any(&caller.subjects, "rekey", "data")
             .await
             .map_err(DGError::Internal)?
         {
-            return Err(DGError::PolicyDenied("decryption denied by policy".into()));
+            return Err(DGError::PolicyDenied(
+                "re-encryption denied by policy".into(),
+            ));
+        }
+        let state = guard.state()?;
+        policy
+            .charge_quota(state, &caller.subjects, "rekey", unix_now())
+            .await?;
+        if threshold_spec(&env)?.is_some() {
+            return Err(DGError::Config(
+                "threshold envelopes must be re-shared by their custodians".into(),
+            ));
+        }
+
+        let wraps = envelope_wraps(&env)?;
+        let aad = provenance::associated_data(&env.meta)?;
+        let previous_key = if env.meta.get("key_wraps").is_some() {
+            guard.unwrap_file_key(key, &wraps)?.0
+        } else {
+            Zeroizing::new(*key)
+        };
+        let plaintext = Zeroizing::new(open_body(&previous_key, &env, &aad)?);
+        let thumbnail = preview::open(&previous_key, &env.meta)?;
+
+        let mut chain = provenance::provenance(&env.meta)?;
+        let previous_key_ids = if wraps.is_empty() {
+            vec![MASTER_KEY_ID.to_owned()]
+        } else {
+            wraps
+                .iter()
+                .map(|entry| wrap_key_id(entry).to_owned())
+                .collect()
+        };
+        chain.push(ProvenanceEntry::new(context, previous_key_ids, unix_now()));
+        let chain = serde_json::json!(chain);
+        let mut bound = serde_json::json!({ PROVENANCE_FIELD: chain });
+        if let Some(custom) = env.meta.get(METADATA_FIELD) {
+            bound[METADATA_FIELD] = custom.clone();
+        }
+        let aad = provenance::associated_data(&bound)?;
+
+        let file_key = guard.entropy.key();
+        // Chunk ids are keyed by the file key, so a rekeyed envelope is chunked afresh.
+        let (payload, chunks) = match chunking::read(&env.meta)? {
+            Some(table) => {
+                let (payload, table) = chunking::seal(
+                    &guard.entropy,
+                    &file_key,
+                    &plaintext,
+                    &aad,
+                    table.params,
+                    None,
+                )?;
+                (payload, Some(table))
+            }
+            // The journal is compacted into a single commit under the new key.
+            None if journal::read(&env.meta)?.is_some() => (
+                journal::seal(&guard.entropy, &file_key, &plaintext, &aad)?,
+                None,
+            ),
+            None => (
+                seal_payload_with(&guard.entropy, &file_key, &plaintext, &aad)?,
+                None,
+            ),
+        };
+        let mut rewrapped = vec![wrap::wrap_symmetric(
+            &guard.entropy,
+            WrapKind::Master,
+            key,
+            &file_key,
+        )?];
+        if let Some(escrow_wrap) = guard.escrow_wrap(policy, &file_key).await? {
+            rewrapped.push(escrow_wrap);
+        }
+
+        let keyring = guard
+            .keyring
+            .as_ref()
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))?;
+        let mut removed = Vec::new();
+        for entry in wraps
+            .iter()
+            .filter(|entry| !matches!(entry.kind, WrapKind::Master | WrapKind::Escrow))
+        {
+            let Some(id) = entry.key_id.as_deref() else {
+                continue;
+            };
+            let recipient = keyring
+                .x25519_public(id)
+                .or_else(|| keyring.x25519_successor(id));
+            match recipient {
+                Some((id, _)) if rewrapped.iter().any(|wrap| wrap_key_id(wrap) == id) => {}
+                Some((_, public)) => rewrapped.push(wrap::wrap_x25519(
+                    &guard.entropy,
+                    entry.kind,
+                    &public,
+                    &file_key,
+                )?),
+                None => {
+                    if !keyring.is_revoked(id) {
+                        warn!(key_id = id, "dropping wrap for key missing from keyring");
+                    }
+                    removed.push(id.to_owned());
+                }
+            }
+        }
+
+        let escrowed = guard.subkey_escrow_wraps(&file_key, &rewrapped)?;
+        rewrapped.extend(escrowed);
+        guard.record_subkey_roots(&mut rewrapped)?;
+        for entry in &rewrapped {
+            state::record_key_use(state, wrap_key_id(entry))?;
+        }
+
+        let mut meta = env.meta.clone();
+        let Some(fields) = meta.as_object_mut() else {
+            return Err(DGError::Crypto(
+                "envelope metadata must be an object".into(),
+            ));
+        };
+        // The recipient list names who can open the envelope, so dropped wraps leave it too.
+        if let Some(recipients) = fields.get("recipients") {
+            let recipients: Vec<String> = serde_json::from_value(recipients.clone())
+                .map_err(|err| DGError::Crypto(format!("invalid recipients: {err}")))?;
+            let remaining: Vec<String> = recipients
+                .into_iter()
+                .filter(|name| {
+                    keyring
+                        .x25519_public(name)
+                        .or_else(|| keyring.x25519_successor(name))
+                        .is_some_and(|(id, _)| rewrapped.iter().any(|wrap| wrap_key_id(wrap) == id))
+                })
+                .collect();
+            fields.insert("recipients".into(), serde_json::json!(remaining));
+        }
+        fields.insert("key_wraps".into(), serde_json::json!(rewrapped));
+        fields.insert(PROVENANCE_FIELD.into(), chain);
+        if let Some(table) = chunks {
+            fields.insert(CHUNKS_FIELD.into(), serde_json::json!(table));
+        }
+        if let Some(thumbnail) = thumbnail {
+            fields.insert(
+                PREVIEW_FIELD.into(),
+                preview::seal(&guard.entropy, &file_key, &thumbnail)?,
+            );
+        }
+        guard
+            .audit()?
+            .record(
+                AuditEvent::new(&caller.subject, "rekey", "data", "allow")
+                    .with_details(serde_json::json!({ "removed": removed })),
+            )
+            .await?;
+        Ok(Envelope {
+            bytes: payload,
+            meta,
+        })
+    }
+
+    #[instrument(skip(self, data))]
+    async fn sign_detached(&self, signer: &str, data: &[u8]) -> DGResult<Vec<u8>> {
+        let caller = Caller::current();
+        let guard = self.inner.read().await;
+        guard.writable("signing")?;
+        let (key_id, secret) = guard.keyring()?.ed25519_secret(signer).ok_or_else(|| {
+            DGError::Config(format!("no usable ed25519 signing key named {signer}"))
+        })?;
+        guard
+            .audit()?
+            .record(AuditEvent::new(&caller.subject, "sign", &key_id, "allow"))
+            .await?;
+        Ok(signature::sign(&secret, data).to_vec())
+    }
+
+    #[instrument(skip(self, data, detached))]
+    async fn verify_detached(&self, signer: &str, data: &[u8], detached: &[u8]) -> DGResult<bool> {
+        if signer.trim_start().starts_with("-----BEGIN") {
+            return signature::verify_with_pem(signer, data, detached);
+        }
+        let guard = self.inner.read().await;
+        let (meta, public) = guard
+            .keyring()?
+            .ed25519_public(signer)
+            .ok_or_else(|| DGError::Config(format!("no ed25519 key named {signer}")))?;
+        if meta.revoked_at.is_some() {
+            return Err(DGError::Crypto(format!(
+                "signer key {} is revoked",
+                meta.id
+            )));
         }
+        signature::verify(&public, data, detached)
+    }
 
-        let (nonce, cipher_bytes) = env.bytes.split_at(12);
-        let cipher = Aes256Gcm::new(key.into());
-        cipher
-            .decrypt(Nonce::from_slice(nonce), cipher_bytes)
-            .map_err(|err| DGError::Crypto(format!("failed to decrypt: {err}")))
+    #[instrument(skip(self))]
+    async fn self_test(&self) -> DGResult<SelfTestReport> {
+        let report = selftest::run();
+        info!(
+            passed = report.passed,
+            checks = report.checks.len(),
+            "self-test complete"
+        );
+        Ok(report)
     }
 
     #[instrument(skip(self))]
-    async fn check_policy(&self, subject: &str, action: &str, resource: &str) -> DGResult<bool> {
+    async fn warm_up(&self) -> DGResult<WarmupReport> {
+        let guard = self.inner.read().await;
+        let (key, _, policy) = guard.parts()?;
+        let mut report = WarmupReport::default();
+
+        let started = Instant::now();
+        let matchers = policy.warm_up().await;
+        report.push("policy", started, Some(format!("{matchers} matchers")));
+
+        let started = Instant::now();
+        open_payload(key, &seal_payload(&Entropy::Os, key, b"warm-up")?)?;
+        report.push("cipher", started, None);
+
+        let started = Instant::now();
+        let tracked = state::key_usage_all(guard.state()?)?;
+        report.push(
+            "state",
+            started,
+            Some(format!("{} keys tracked", tracked.len())),
+        );
+
+        info!(
+            steps = report.steps.len(),
+            elapsed_us = report.elapsed_us(),
+            "engine warm-up complete"
+        );
+        Ok(report)
+    }
+
+    #[instrument(skip(self))]
+    async fn integrity(&self, repair: bool) -> DGResult<IntegrityReport> {
+        let mut guard = self.inner.write().await;
+        let data_dir = guard
+            .config
+            .as_ref()
+            .map(|config| config.data_dir.clone())
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))?;
+        if !repair {
+            return Ok(guard.integrity.clone().unwrap_or_default());
+        }
+        guard.writable("repair")?;
+        let report = integrity::repair_data_dir(&data_dir).await;
+        info!(
+            issues = report.issues.len(),
+            healthy = report.healthy(),
+            "data directory repair complete"
+        );
+        guard.integrity = Some(report.clone());
+        Ok(report)
+    }
+
+    #[instrument(skip(self, env))]
+    async fn collect_share(&self, env: &Envelope) -> DGResult<Vec<KeyShare>> {
+        let caller = Caller::current();
         let guard = self.inner.read().await;
         let (_, _, policy) = guard.parts()?;
+
+        if !policy
+            .evaluate_any(&caller.subjects, "collect_share", "data")
+            .await
+            .map_err(DGError::Internal)?
+        {
+            return Err(DGError::PolicyDenied(
+                "share collection denied by policy".into(),
+            ));
+        }
+        if threshold_spec(env)?.is_none() {
+            return Err(DGError::Config(
+                "envelope is not threshold protected".into(),
+            ));
+        }
+
+        let keyring = guard
+            .keyring
+            .as_ref()
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))?;
+        let mut shares = Vec::new();
+        for entry in envelope_wraps(env)?
+            .iter()
+            .filter(|entry| entry.kind == WrapKind::Share)
+        {
+            let (Some(id), Some(index)) = (entry.key_id.as_deref(), entry.share_index) else {
+                continue;
+            };
+            let Some(secret) = keyring.x25519_secret(id) else {
+                continue;
+            };
+            let share = wrap::unwrap_x25519(&secret, entry)?;
+            shares.push(KeyShare {
+                key_id: id.to_owned(),
+                index,
+                share: general_purpose::STANDARD.encode(share),
+            });
+        }
+        if shares.is_empty() {
+            return Err(DGError::Crypto(
+                "no custodian share in this envelope matches a keyring identity".into(),
+            ));
+        }
+
+        guard
+            .audit()?
+            .record(
+                AuditEvent::new(&caller.subject, "collect_share", "data", "allow").with_details(
+                    serde_json::json!({
+                        "key_ids": shares.iter().map(|share| &share.key_id).collect::<Vec<_>>(),
+                    }),
+                ),
+            )
+            .await?;
+        Ok(shares)
+    }
+
+    #[instrument(skip(self, env, shares))]
+    async fn combine_shares(
+        &self,
+        env: Envelope,
+        shares: Vec<KeyShare>,
+    ) -> DGResult<Zeroizing<Vec<u8>>> {
+        let caller = Caller::current();
+        let guard = self.inner.read().await;
+        let (_, _, policy) = guard.parts()?;
+
+        let allowed = policy
+            .evaluate_any(&caller.subjects, "decrypt", "data")
+            .await
+            .map_err(DGError::Internal)?;
+        guard
+            .record_decision(&caller.subject, "decrypt", "data", allowed)
+            .await?;
+        if !allowed {
+            return Err(DGError::PolicyDenied("decryption denied by policy".into()));
+        }
+        let state = guard.state()?;
+        let now = unix_now();
         policy
-            .evaluate(subject, action, resource)
+            .check_lockout(state, &caller.subject, "decrypt", now)
+            .await?;
+        policy
+            .charge_quota(state, &caller.subjects, "decrypt", now)
+            .await?;
+        let spec = threshold_spec(&env)?
+            .ok_or_else(|| DGError::Config("envelope is not threshold protected".into()))?;
+
+        let points = Zeroizing::new(
+            shares
+                .iter()
+                .map(|share| {
+                    let value: [u8; 32] = general_purpose::STANDARD
+                        .decode(&share.share)
+                        .map_err(|err| DGError::Crypto(format!("invalid share encoding: {err}")))?
+                        .try_into()
+                        .map_err(|_| DGError::Crypto("share must be 32 bytes".into()))?;
+                    Ok((share.index, value))
+                })
+                .collect::<DGResult<Vec<_>>>()?,
+        );
+        let file_key = Zeroizing::new(threshold::combine(&points, spec.required)?);
+        let aad = provenance::associated_data(&env.meta)?;
+        let plaintext = match open_body(&file_key, &env, &aad) {
+            Ok(plaintext) => Zeroizing::new(plaintext),
+            Err(_) => {
+                policy
+                    .record_failure(state, &caller.subject, "decrypt", now)
+                    .await?;
+                return Err(DGError::Crypto(
+                    "shares do not reconstruct this envelope's key".into(),
+                ));
+            }
+        };
+        policy
+            .record_success(state, &caller.subject, "decrypt")
+            .await?;
+        for share in &shares {
+            state::record_key_use(state, &share.key_id)?;
+        }
+
+        guard
+            .audit()?
+            .record(
+                AuditEvent::new(&caller.subject, "combine_shares", "data", "allow").with_details(
+                    serde_json::json!({
+                        "key_ids": shares.iter().map(|share| &share.key_id).collect::<Vec<_>>(),
+                    }),
+                ),
+            )
+            .await?;
+        Ok(plaintext)
+    }
+
+    #[instrument(skip(self, env))]
+    async fn issue_grant(&self, env: &Envelope, request: GrantRequest) -> DGResult<SignedGrant> {
+        let caller = Caller::current();
+        let guard = self.inner.read().await;
+        let (key, _, policy) = guard.parts()?;
+        guard.writable("issuing grants")?;
+
+        if !policy
+            .evaluate_any(&caller.subjects, "grant", "data")
             .await
-            .map_err(DGError::Internal)
+            .map_err(DGError::Internal)?
+        {
+            return Err(DGError::PolicyDenied(
+                "grant issuance denied by policy".into(),
+            ));
+        }
+        if request.actions.is_empty() {
+            return Err(DGError::Config(
+                "a grant must allow at least one action".into(),
+            ));
+        }
+        let now = unix_now();
+        if request.expires_at <= now {
+            return Err(DGError::Config("grant expiry must be in the future".into()));
+        }
+        // Legacy envelopes are sealed with the master key itself, which can never be handed out.
+        if env.meta.get("key_wraps").is_none() {
+            return Err(DGError::Config(
+                "envelope has no key wraps; re-encrypt it before issuing grants".into(),
+            ));
+        }
+
+        let keyring = guard.keyring()?;
+        let (recipient, public) = keyring.x25519_public(&request.recipient).ok_or_else(|| {
+            DGError::Config(format!(
+                "grant recipient '{}' is not an X25519 key in the keyring",
+                request.recipient
+            ))
+        })?;
+        let (signer, secret) = keyring.ed25519_secret(&request.signer).ok_or_else(|| {
+            DGError::Config(format!(
+                "no usable ed25519 signing key named {}",
+                request.signer
+            ))
+        })?;
+        let (file_key, _) = guard.unwrap_file_key(key, &envelope_wraps(env)?)?;
+        let mut id = [0u8; 16];
+        guard.entropy.fill(&mut id);
+        let grant = AccessGrant {
+            id: hex::encode(id),
+            envelope: grant::envelope_digest(env),
+            recipient,
+            signer,
+            issued_at: now,
+            expires_at: request.expires_at,
+            actions: request.actions,
+            wrap: wrap::wrap_x25519(&guard.entropy, WrapKind::Grant, &public, &file_key)?,
+        };
+        let payload = serde_json::to_vec(&grant)
+            .map_err(|err| DGError::Internal(format!("unable to encode grant: {err}")))?;
+        let signed = SignedGrant::new(&grant, &signature::sign(&secret, &payload))?;
+
+        guard
+            .audit()?
+            .record(
+                AuditEvent::new(&caller.subject, "grant", "data", "allow").with_details(
+                    serde_json::json!({
+                        "grant": grant.id,
+                        "recipient": grant.recipient,
+                        "signer": grant.signer,
+                        "actions": grant.actions,
+                        "expires_at": grant.expires_at,
+                    }),
+                ),
+            )
+            .await?;
+        Ok(signed)
+    }
+
+    #[instrument(skip(self, env, signed))]
+    async fn decrypt_with_grant(
+        &self,
+        env: Envelope,
+        signed: SignedGrant,
+        action: GrantAction,
+    ) -> DGResult<Zeroizing<Vec<u8>>> {
+        let caller = Caller::current();
+        let guard = self.inner.read().await;
+        let (_, _, policy) = guard.parts()?;
+
+        let allowed = policy
+            .evaluate_any(&caller.subjects, "decrypt", "data")
+            .await
+            .map_err(DGError::Internal)?;
+        guard
+            .record_decision(&caller.subject, "decrypt", "data", allowed)
+            .await?;
+        if !allowed {
+            return Err(DGError::PolicyDenied("decryption denied by policy".into()));
+        }
+        let state = guard.state()?;
+        let now = unix_now();
+        policy
+            .check_lockout(state, &caller.subject, "decrypt", now)
+            .await?;
+        policy
+            .charge_quota(state, &caller.subjects, "decrypt", now)
+            .await?;
+
+        let grant = signed.grant()?;
+        let keyring = guard.keyring()?;
+        let (issuer, public) = keyring.ed25519_public(&grant.signer).ok_or_else(|| {
+            DGError::Crypto(format!(
+                "grant {} is signed by unknown key {}",
+                grant.id, grant.signer
+            ))
+        })?;
+        if issuer.revoked_at.is_some() {
+            return Err(DGError::Crypto(format!(
+                "grant {} is signed by revoked key {}",
+                grant.id, issuer.id
+            )));
+        }
+        if !signature::verify(
+            &public,
+            &signed.payload_bytes()?,
+            &signed.signature_bytes()?,
+        )? {
+            return Err(DGError::Crypto(format!(
+                "grant {} signature does not verify",
+                grant.id
+            )));
+        }
+        grant.check(&env, action, now)?;
+        let secret = keyring.x25519_secret(&grant.recipient).ok_or_else(|| {
+            DGError::Crypto(format!(
+                "grant {} is addressed to key {}, which is not in this keyring",
+                grant.id, grant.recipient
+            ))
+        })?;
+
+        let file_key = wrap::unwrap_x25519(&secret, &grant.wrap)?;
+        let aad = provenance::associated_data(&env.meta)?;
+        let plaintext = match open_body(&file_key, &env, &aad) {
+            Ok(plaintext) => Zeroizing::new(plaintext),
+            Err(_) => {
+                policy
+                    .record_failure(state, &caller.subject, "decrypt", now)
+                    .await?;
+                return Err(DGError::Crypto(format!(
+                    "grant {} does not open this envelope",
+                    grant.id
+                )));
+            }
+        };
+        policy
+            .record_success(state, &caller.subject, "decrypt")
+            .await?;
+        state::record_key_use(state, &grant.recipient)?;
+
+        guard
+            .audit()?
+            .record(
+                AuditEvent::new(&caller.subject, "grant_use", "data", "allow").with_details(
+                    serde_json::json!({
+                        "grant": grant.id,
+                        "issuer": issuer.id,
+                        "recipient": grant.recipient,
+                        "action": action,
+                        "expires_at": grant.expires_at,
+                    }),
+                ),
+            )
+            .await?;
+        Ok(plaintext)
+    }
+
+    #[instrument(skip(self, env))]
+    async fn export_offline_request(&self, env: &Envelope) -> DGResult<OfflineRequest> {
+        let caller = Caller::current();
+        let guard = self.inner.read().await;
+        let (key, _, policy) = guard.parts()?;
+
+        if !policy
+            .evaluate_any(&caller.subjects, "offline_request", "data")
+            .await
+            .map_err(DGError::Internal)?
+        {
+            return Err(DGError::PolicyDenied(
+                "offline requests denied by policy".into(),
+            ));
+        }
+        if threshold_spec(env)?.is_some() {
+            return Err(DGError::Config(
+                "threshold envelopes are opened with custodian shares, not offline requests".into(),
+            ));
+        }
+        let labels = env
+            .meta
+            .get("labels")
+            .and_then(|labels| serde_json::from_value(labels.clone()).ok())
+            .unwrap_or_default();
+        let request = OfflineRequest::new(
+            &guard.entropy,
+            key,
+            env,
+            envelope_wraps(env)?,
+            labels,
+            unix_now(),
+        )?;
+
+        guard
+            .audit()?
+            .record(
+                AuditEvent::new(&caller.subject, "offline_request", "data", "allow").with_details(
+                    serde_json::json!({
+                        "request": request.id,
+                        "envelope": request.envelope,
+                        "expires_at": request.expires_at,
+                    }),
+                ),
+            )
+            .await?;
+        Ok(request)
+    }
+
+    // Runs on the air-gapped machine, which only needs its keyring; the envelope never leaves the
+    // online machine.
+    #[instrument(skip(self, request))]
+    async fn answer_offline_request(&self, request: OfflineRequest) -> DGResult<OfflineResponse> {
+        let caller = Caller::current();
+        let guard = self.inner.read().await;
+        let (_, _, policy) = guard.parts()?;
+
+        if !policy
+            .evaluate_any(&caller.subjects, "offline_answer", "data")
+            .await
+            .map_err(DGError::Internal)?
+        {
+            return Err(DGError::PolicyDenied(
+                "answering offline requests denied by policy".into(),
+            ));
+        }
+        let now = unix_now();
+        request.check(now)?;
+        let keyring = guard.keyring()?;
+        let mut opened = None;
+        for entry in &request.wraps {
+            let Some(secret) = entry
+                .key_id
+                .as_deref()
+                .and_then(|id| keyring.x25519_secret(id))
+            else {
+                continue;
+            };
+            if let Ok(file_key) = wrap::unwrap_x25519(&secret, entry) {
+                opened = Some((file_key, wrap_key_id(entry).to_owned()));
+                break;
+            }
+        }
+        let (file_key, key_id) = opened.ok_or_else(|| {
+            DGError::Crypto(format!(
+                "no key wrap in offline request {} matches a keyring identity",
+                request.id
+            ))
+        })?;
+        let response = request.answer(&guard.entropy, &key_id, &file_key, now)?;
+        state::record_key_use(guard.state()?, &key_id)?;
+
+        guard
+            .audit()?
+            .record(
+                AuditEvent::new(&caller.subject, "offline_answer", "data", "allow").with_details(
+                    serde_json::json!({
+                        "request": request.id,
+                        "envelope": request.envelope,
+                        "key_id": key_id,
+                    }),
+                ),
+            )
+            .await?;
+        Ok(response)
+    }
+
+    #[instrument(skip(self, env, response))]
+    async fn complete_offline_request(
+        &self,
+        env: Envelope,
+        response: OfflineResponse,
+    ) -> DGResult<Zeroizing<Vec<u8>>> {
+        let caller = Caller::current();
+        let guard = self.inner.read().await;
+        let (key, _, policy) = guard.parts()?;
+
+        let allowed = policy
+            .evaluate_any(&caller.subjects, "decrypt", "data")
+            .await
+            .map_err(DGError::Internal)?;
+        guard
+            .record_decision(&caller.subject, "decrypt", "data", allowed)
+            .await?;
+        if !allowed {
+            return Err(DGError::PolicyDenied("decryption denied by policy".into()));
+        }
+        let state = guard.state()?;
+        let now = unix_now();
+        policy
+            .check_lockout(state, &caller.subject, "decrypt", now)
+            .await?;
+        policy
+            .charge_quota(state, &caller.subjects, "decrypt", now)
+            .await?;
+
+        let file_key = response.file_key(key, &env, now)?;
+        let aad = provenance::associated_data(&env.meta)?;
+        let plaintext = match open_body(&file_key, &env, &aad) {
+            Ok(plaintext) => Zeroizing::new(plaintext),
+            Err(_) => {
+                policy
+                    .record_failure(state, &caller.subject, "decrypt", now)
+                    .await?;
+                return Err(DGError::Crypto(format!(
+                    "offline response {} does not open this envelope",
+                    response.request
+                )));
+            }
+        };
+        policy
+            .record_success(state, &caller.subject, "decrypt")
+            .await?;
+        state::record_key_use(state, &response.key_id)?;
+
+        guard
+            .audit()?
+            .record(
+                AuditEvent::new(&caller.subject, "offline_complete", "data", "allow").with_details(
+                    serde_json::json!({
+                        "request": response.request,
+                        "key_id": response.key_id,
+                        "answered_at": response.answered_at,
+                    }),
+                ),
+            )
+            .await?;
+        Ok(plaintext)
+    }
+
+    // Previews are not charged against decrypt quotas, but a locked-out subject cannot view them.
+    #[instrument(skip(self, env))]
+    async fn preview(&self, env: &Envelope) -> DGResult<Option<Preview>> {
+        let caller = Caller::current();
+        if !preview::has_preview(&env.meta) {
+            return Ok(None);
+        }
+        let guard = self.inner.read().await;
+        let (key, _config, policy) = guard.parts()?;
+        if !policy
+            .evaluate_any(&caller.subjects, "preview", "data")
+            .await
+            .map_err(DGError::Internal)?
+        {
+            return Err(DGError::PolicyDenied("preview denied by policy".into()));
+        }
+        let state = guard.state()?;
+        policy
+            .check_lockout(state, &caller.subject, "decrypt", unix_now())
+            .await?;
+        let file_key = if env.meta.get("key_wraps").is_some() {
+            guard.unwrap_file_key(key, &envelope_wraps(env)?)?.0
+        } else {
+            Zeroizing::new(*key)
+        };
+        preview::open(&file_key, &env.meta)
+    }
+
+    // Checking a range releases no plaintext, so it is not charged against decrypt quotas.
+    #[instrument(skip(self, env))]
+    async fn verify_range(
+        &self,
+        env: &Envelope,
+        offset: u64,
+        len: u64,
+    ) -> DGResult<RangeVerification> {
+        let caller = Caller::current();
+        let Some(table) = chunking::read(&env.meta)? else {
+            return Err(DGError::Config(
+                "envelope was sealed without chunking; verify it by decrypting it whole".into(),
+            ));
+        };
+        let guard = self.inner.read().await;
+        let (key, _config, policy) = guard.parts()?;
+        if !policy
+            .evaluate_any(&caller.subjects, "decrypt", "data")
+            .await
+            .map_err(DGError::Internal)?
+        {
+            return Err(DGError::PolicyDenied("decryption denied by policy".into()));
+        }
+        let state = guard.state()?;
+        policy
+            .check_lockout(state, &caller.subject, "decrypt", unix_now())
+            .await?;
+        let (plaintext, verification, key_id) =
+            guard.open_envelope_range(key, env, &table, offset, len)?;
+        drop(Zeroizing::new(plaintext));
+        state::record_key_use(state, &key_id)?;
+        Ok(verification)
+    }
+
+    #[instrument(skip(self, env))]
+    async fn decrypt_range(
+        &self,
+        env: &Envelope,
+        offset: u64,
+        len: u64,
+    ) -> DGResult<Zeroizing<Vec<u8>>> {
+        let caller = Caller::current();
+        let Some(table) = chunking::read(&env.meta)? else {
+            return Err(DGError::Config(
+                "envelope was sealed without chunking; decrypt it whole instead".into(),
+            ));
+        };
+        let guard = self.inner.read().await;
+        let (key, _config, policy) = guard.parts()?;
+        let allowed = policy
+            .evaluate_any(&caller.subjects, "decrypt", "data")
+            .await
+            .map_err(DGError::Internal)?;
+        guard
+            .record_decision(&caller.subject, "decrypt", "data", allowed)
+            .await?;
+        if !allowed {
+            return Err(DGError::PolicyDenied("decryption denied by policy".into()));
+        }
+        let state = guard.state()?;
+        let now = unix_now();
+        policy
+            .check_lockout(state, &caller.subject, "decrypt", now)
+            .await?;
+        policy
+            .charge_quota(state, &caller.subjects, "decrypt", now)
+            .await?;
+
+        match guard.open_envelope_range(key, env, &table, offset, len) {
+            Ok((plaintext, _verification, key_id)) => {
+                let plaintext = Zeroizing::new(plaintext);
+                policy
+                    .record_success(state, &caller.subject, "decrypt")
+                    .await?;
+                state::record_key_use(state, &key_id)?;
+                Ok(plaintext)
+            }
+            Err(err) => {
+                if matches!(err, DGError::Crypto(_)) {
+                    policy
+                        .record_failure(state, &caller.subject, "decrypt", now)
+                        .await?;
+                }
+                Err(err)
+            }
+        }
+    }
+
+    #[instrument(skip(self, env, data))]
+    async fn append(&self, env: &Envelope, data: Vec<u8>) -> DGResult<JournalAppend> {
+        let caller = Caller::current();
+        let data = Zeroizing::new(data);
+        if journal::read(&env.meta)?.is_none() {
+            return Err(DGError::Config(
+                "envelope was not sealed in append mode; encrypt it with `journal` first".into(),
+            ));
+        }
+        let guard = self.inner.read().await;
+        guard.writable("encryption")?;
+        let (key, _config, policy) = guard.parts()?;
+        let allowed = policy
+            .evaluate_any(&caller.subjects, "encrypt", "data")
+            .await
+            .map_err(DGError::Internal)?;
+        guard
+            .record_decision(&caller.subject, "encrypt", "data", allowed)
+            .await?;
+        if !allowed {
+            return Err(DGError::PolicyDenied("encryption denied by policy".into()));
+        }
+        let state = guard.state()?;
+        policy
+            .charge_quota(state, &caller.subjects, "encrypt", unix_now())
+            .await?;
+        let (file_key, key_id) = guard.unwrap_file_key(key, &envelope_wraps(env)?)?;
+        let aad = provenance::associated_data(&env.meta)?;
+        let appended = journal::append(&guard.entropy, &file_key, &env.bytes, &aad, &data)?;
+        state::record_key_use(state, &key_id)?;
+        Ok(appended)
+    }
+
+    async fn index_entries(&self) -> DGResult<Vec<IndexEntry>> {
+        let index = self.inner.read().await.index()?;
+        let mut index = index.lock().await;
+        index.refresh().await?;
+        Ok(index.entries().to_vec())
+    }
+
+    #[instrument(skip(self, entry), fields(path = %entry.path.display()))]
+    async fn index_record(&self, entry: IndexEntry) -> DGResult<()> {
+        let index = self.inner.read().await.writable_index()?;
+        let mut index = index.lock().await;
+        index.record_entry(entry).await
+    }
+
+    #[instrument(skip(self))]
+    async fn index_remove(&self, path: &Path) -> DGResult<()> {
+        let index = self.inner.read().await.writable_index()?;
+        let mut index = index.lock().await;
+        index.remove(path).await
+    }
+
+    #[instrument(skip(self, observed))]
+    async fn index_reconcile(
+        &self,
+        roots: Vec<PathBuf>,
+        observed: Vec<IndexEntry>,
+    ) -> DGResult<Reconciliation> {
+        let index = self.inner.read().await.writable_index()?;
+        let mut index = index.lock().await;
+        index.reconcile(&roots, observed).await
+    }
+
+    #[instrument(skip(self))]
+    async fn reload_policy(&self) -> DGResult<PolicyCacheStats> {
+        let caller = Caller::current();
+        let guard = self.inner.read().await;
+        let (config, policy) = (guard.config()?, guard.policy()?);
+        let fresh = load_policy(&config.data_dir, guard.keyring()?).await?;
+        let previous = policy.cache_stats();
+        policy.reload(fresh).await;
+        guard
+            .audit()?
+            .record(AuditEvent::new(
+                &caller.subject,
+                "reload_policy",
+                "policy",
+                "allow",
+            ))
+            .await?;
+        info!(
+            hit_rate = previous.hit_rate(),
+            dropped = previous.entries,
+            "policy reloaded"
+        );
+        Ok(policy.cache_stats())
+    }
+
+    async fn policy_cache_stats(&self) -> DGResult<PolicyCacheStats> {
+        let guard = self.inner.read().await;
+        Ok(guard.policy()?.cache_stats())
+    }
+
+    async fn query_audit(&self, filter: AuditFilter) -> DGResult<Vec<AuditEvent>> {
+        let guard = self.inner.read().await;
+        guard.audit()?.query(&filter).await
+    }
+
+    async fn loaded_profile(&self) -> DGResult<Option<LoadedProfile>> {
+        let guard = self.inner.read().await;
+        Ok(guard.config.as_ref().map(|config| LoadedProfile {
+            profile: config.profile.clone(),
+            data_dir: config.data_dir.clone(),
+            read_only: config.read_only,
+            locked: guard.key.is_none(),
+        }))
+    }
+
+    #[instrument(skip(self))]
+    async fn lock(&self) -> DGResult<()> {
+        let caller = Caller::current();
+        let mut guard = self.inner.write().await;
+        let data_dir = guard.config()?.data_dir.clone();
+        if passphrase::load(&data_dir).await?.is_none() {
+            return Err(DGError::Config(
+                "the master key has no passphrase and cannot be locked".into(),
+            ));
+        }
+        guard.forget_key();
+        guard
+            .audit()?
+            .record(AuditEvent::new(
+                &caller.subject,
+                "lock",
+                "master_key",
+                "allow",
+            ))
+            .await?;
+        info!("master key locked");
+        Ok(())
+    }
+
+    #[instrument(skip(self, passphrase))]
+    async fn unlock(&self, passphrase: &str) -> DGResult<()> {
+        let caller = Caller::current();
+        let mut guard = self.inner.write().await;
+        let config = guard.config()?.clone();
+        if guard.key.is_some() {
+            return Ok(());
+        }
+        let policy = guard.policy()?.clone();
+        let state = guard.state()?;
+        let now = unix_now();
+        policy
+            .check_lockout(state, &caller.subject, "unlock", now)
+            .await?;
+
+        let key = match passphrase::load(&config.data_dir).await? {
+            Some(wrapped) => {
+                let secret = Zeroizing::new(passphrase.to_owned());
+                let opened = tokio::task::spawn_blocking(move || wrapped.open(&secret))
+                    .await
+                    .map_err(|err| DGError::Internal(format!("key derivation failed: {err}")))?;
+                match opened {
+                    Ok(key) => key,
+                    Err(err) => {
+                        if matches!(err, DGError::Crypto(_)) {
+                            policy
+                                .record_failure(state, &caller.subject, "unlock", now)
+                                .await?;
+                            guard
+                                .audit()?
+                                .record(AuditEvent::new(
+                                    &caller.subject,
+                                    "unlock",
+                                    "master_key",
+                                    "deny",
+                                ))
+                                .await?;
+                        }
+                        return Err(err);
+                    }
+                }
+            }
+            None => {
+                let KeyProtection::Passphrase(params) = config.key_protection else {
+                    return Err(DGError::Config(
+                        "the master key is not passphrase-protected".into(),
+                    ));
+                };
+                guard.writable("setting a passphrase")?;
+                guard
+                    .protect_key(&config.data_dir, passphrase, params)
+                    .await?
+            }
+        };
+        policy
+            .record_success(state, &caller.subject, "unlock")
+            .await?;
+        guard
+            .audit()?
+            .record(AuditEvent::new(
+                &caller.subject,
+                "unlock",
+                "master_key",
+                "allow",
+            ))
+            .await?;
+        let InnerState {
+            keyring, entropy, ..
+        } = &mut *guard;
+        keyring
+            .as_mut()
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))?
+            .unseal(entropy, &key)
+            .await?;
+        guard.key = Some(SecretKey::new(&key));
+        info!("master key unlocked");
+        Ok(())
     }
 
     #[instrument(skip(self))]
     async fn shutdown(&self) -> DGResult<()> {
         let mut guard = self.inner.write().await;
         guard.config = None;
-        guard.key = None;
+        guard.forget_key();
         guard.policy = None;
+        guard.keyring = None;
+        guard.audit = None;
+        guard.state = None;
+        guard.index = None;
         info!("Data Guardian shutdown complete");
         Ok(())
     }
@@ -140,44 +1721,570 @@ impl DataGuardian for DefaultDataGuardian {
 
 impl InnerState {
     fn parts(&self) -> DGResult<(&[u8; 32], &DGConfig, &PolicyEngine)> {
-        let key = self
-            .key
+        let config = self.config()?;
+        let key = self.key.as_deref().ok_or_else(|| {
+            DGError::Locked("unlock the master key with its passphrase first".into())
+        })?;
+        Ok((key, config, self.policy()?))
+    }
+
+    fn config(&self) -> DGResult<&DGConfig> {
+        self.config
             .as_ref()
-            .ok_or_else(|| DGError::Internal("engine not initialized".into()))?;
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))
+    }
+
+    // Policy checks do not need the master key, so they keep working while it is locked.
+    fn policy(&self) -> DGResult<&PolicyEngine> {
+        self.policy
+            .as_ref()
+            .ok_or_else(|| DGError::Internal("policy not loaded".into()))
+    }
+
+    fn forget_key(&mut self) {
+        self.key = None;
+        if let Some(keyring) = &mut self.keyring {
+            keyring.lock();
+        }
+    }
+
+    // Wraps the current (or a new) master key under the passphrase, then scrubs the plain key file.
+    async fn protect_key(
+        &self,
+        data_dir: &Path,
+        passphrase: &str,
+        params: passphrase::KdfParams,
+    ) -> DGResult<Zeroizing<[u8; 32]>> {
+        passphrase::check_passphrase(passphrase)?;
+        let key = load_or_create_key(data_dir, true, &self.entropy).await?;
+        let wrapped = WrappedKey::seal(&self.entropy, &key, passphrase, params)?;
+        passphrase::store(data_dir, &wrapped).await?;
+        scrub_key_file(&data_dir.join("keys").join(KEY_FILE)).await?;
+        info!("master key is now passphrase-protected");
+        Ok(key)
+    }
+
+    fn audit(&self) -> DGResult<&AuditLog> {
+        self.audit
+            .as_ref()
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))
+    }
+
+    async fn record_decision(
+        &self,
+        subject: &str,
+        action: &str,
+        resource: &str,
+        allowed: bool,
+    ) -> DGResult<()> {
+        let effect = if allowed { "allow" } else { "deny" };
+        self.audit()?
+            .record(AuditEvent::new(subject, action, resource, effect))
+            .await
+    }
+
+    fn writable(&self, operation: &str) -> DGResult<()> {
         let config = self
             .config
             .as_ref()
-            .ok_or_else(|| DGError::Internal("config missing".into()))?;
-        let policy = self
-            .policy
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))?;
+        if config.read_only {
+            return Err(DGError::ReadOnly(format!(
+                "{operation} is not allowed on {}",
+                config.data_dir.display()
+            )));
+        }
+        Ok(())
+    }
+
+    fn index(&self) -> DGResult<Arc<Mutex<EnvelopeIndex>>> {
+        self.index
+            .clone()
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))
+    }
+
+    fn writable_index(&self) -> DGResult<Arc<Mutex<EnvelopeIndex>>> {
+        self.writable("index updates")?;
+        self.index()
+    }
+
+    fn state(&self) -> DGResult<&dyn StateStore> {
+        self.state
+            .as_deref()
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))
+    }
+
+    fn open_envelope(&self, key: &[u8; 32], env: &Envelope) -> DGResult<(Vec<u8>, String)> {
+        let aad = provenance::associated_data(&env.meta)?;
+        if env.meta.get("key_wraps").is_none() {
+            return Ok((open_body(key, env, &aad)?, MASTER_KEY_ID.to_owned()));
+        }
+        let wraps = envelope_wraps(env)?;
+        let (file_key, key_id) = match self.unwrap_file_key(key, &wraps) {
+            Ok(unwrapped) => unwrapped,
+            Err(err) => match threshold_spec(env)? {
+                Some(spec) => {
+                    return Err(DGError::Crypto(format!(
+                        "envelope requires {} of {} custodian shares",
+                        spec.required, spec.total
+                    )))
+                }
+                None => return Err(err),
+            },
+        };
+        Ok((open_body(&file_key, env, &aad)?, key_id))
+    }
+
+    // Opens plaintext bytes `offset..offset + len` from the chunks of `table` that hold them,
+    // returning the key id that unwrapped the file key.
+    fn open_envelope_range(
+        &self,
+        key: &[u8; 32],
+        env: &Envelope,
+        table: &ChunkTable,
+        offset: u64,
+        len: u64,
+    ) -> DGResult<(Vec<u8>, RangeVerification, String)> {
+        let (file_key, key_id) = self.unwrap_file_key(key, &envelope_wraps(env)?)?;
+        let aad = provenance::associated_data(&env.meta)?;
+        let (plaintext, verification) =
+            chunking::open_range(&file_key, &env.bytes, &aad, table, offset, len)?;
+        Ok((plaintext, verification, key_id))
+    }
+
+    // Recovers the file key and chunk table of an earlier version so its unchanged chunks can be
+    // carried over. Versions sealed without chunking have nothing to reuse.
+    fn previous_version(
+        &self,
+        master: &[u8; 32],
+        previous: &Envelope,
+        recipients: &[String],
+    ) -> DGResult<Option<PreviousVersion>> {
+        let Some(table) = chunking::read(&previous.meta)? else {
+            return Ok(None);
+        };
+        if threshold_spec(previous)?.is_some() {
+            return Err(DGError::Config(
+                "threshold envelopes cannot be reused as an earlier version".into(),
+            ));
+        }
+        // Anyone who could open the earlier version holds its file key.
+        let earlier: Vec<String> = previous
+            .meta
+            .get("recipients")
+            .map(|field| serde_json::from_value(field.clone()))
+            .transpose()
+            .map_err(|err| DGError::Crypto(format!("invalid recipients: {err}")))?
+            .unwrap_or_default();
+        if let Some(dropped) = earlier.iter().find(|id| !recipients.contains(id)) {
+            return Err(DGError::Config(format!(
+                "recipient {dropped} can open the earlier version; encrypt without it to revoke them"
+            )));
+        }
+        let (file_key, key_id) = self.unwrap_file_key(master, &envelope_wraps(previous)?)?;
+        state::record_key_use(self.state()?, &key_id)?;
+        let aad = provenance::associated_data(&previous.meta)?;
+        Ok(Some(PreviousVersion {
+            file_key,
+            table,
+            aad,
+        }))
+    }
+
+    fn unwrap_file_key(
+        &self,
+        master: &[u8; 32],
+        wraps: &[KeyWrap],
+    ) -> DGResult<(Zeroizing<[u8; 32]>, String)> {
+        for entry in wraps.iter().filter(|entry| entry.kind == WrapKind::Master) {
+            if let Ok(file_key) = wrap::unwrap_symmetric(master, entry) {
+                return Ok((file_key, wrap_key_id(entry).to_owned()));
+            }
+        }
+        if let Some(keyring) = &self.keyring {
+            for entry in wraps
+                .iter()
+                .filter(|entry| !matches!(entry.kind, WrapKind::Master | WrapKind::Share))
+            {
+                let secret = entry
+                    .key_id
+                    .as_deref()
+                    .and_then(|id| keyring.x25519_secret(id));
+                if let Some(secret) = secret {
+                    if let Ok(file_key) = wrap::unwrap_x25519(&secret, entry) {
+                        debug!(key_id = ?entry.key_id, kind = ?entry.kind, "file key unwrapped via keyring identity");
+                        return Ok((file_key, wrap_key_id(entry).to_owned()));
+                    }
+                }
+            }
+        }
+        Err(DGError::Crypto(
+            "no key wrap in this envelope can be opened with the available keys".into(),
+        ))
+    }
+
+    async fn escrow_wrap(
+        &self,
+        policy: &PolicyEngine,
+        file_key: &[u8; 32],
+    ) -> DGResult<Option<KeyWrap>> {
+        let caller = Caller::current();
+        let Some(escrow) = policy.escrow().await else {
+            return Ok(None);
+        };
+        let escrow_wrap = wrap::wrap_x25519(
+            &self.entropy,
+            WrapKind::Escrow,
+            &escrow.public_key,
+            file_key,
+        )?;
+        self.audit()?
+            .record(
+                AuditEvent::new(&caller.subject, "escrow", "data", "allow").with_details(
+                    serde_json::json!({
+                        "key_id": escrow_wrap.key_id,
+                        "label": escrow.label,
+                    }),
+                ),
+            )
+            .await?;
+        debug!(key_id = ?escrow_wrap.key_id, "file key wrapped to escrow");
+        Ok(Some(escrow_wrap))
+    }
+
+    // Envelopes wrapped to an encryption subkey are also wrapped to the escrow subkey of the same
+    // root, so the identity can still recover them if the encryption subkey is lost.
+    fn subkey_escrow_wraps(
+        &self,
+        file_key: &[u8; 32],
+        wraps: &[KeyWrap],
+    ) -> DGResult<Vec<KeyWrap>> {
+        let keyring = self.keyring()?;
+        let mut escrowed: Vec<KeyWrap> = Vec::new();
+        for id in wraps
+            .iter()
+            .filter(|entry| entry.kind == WrapKind::Recipient)
+            .filter_map(|entry| entry.key_id.as_deref())
+        {
+            let Some((escrow_id, public)) = keyring
+                .root_of(id)
+                .and_then(|root| keyring.subkey(root, KeyPurpose::Escrow))
+                .and_then(|escrow| keyring.x25519_public(&escrow.id))
+            else {
+                continue;
+            };
+            if wraps
+                .iter()
+                .chain(&escrowed)
+                .any(|wrap| wrap_key_id(wrap) == escrow_id)
+            {
+                continue;
+            }
+            escrowed.push(wrap::wrap_x25519(
+                &self.entropy,
+                WrapKind::Escrow,
+                &public,
+                file_key,
+            )?);
+        }
+        Ok(escrowed)
+    }
+
+    // Records in each wrap the root identity that certified the subkey it names.
+    fn record_subkey_roots(&self, wraps: &mut [KeyWrap]) -> DGResult<()> {
+        let keyring = self.keyring()?;
+        for entry in wraps {
+            entry.root = entry
+                .key_id
+                .as_deref()
+                .and_then(|id| keyring.root_of(id))
+                .map(str::to_owned);
+        }
+        Ok(())
+    }
+
+    async fn max_key_age(&self) -> DGResult<Option<MaxKeyAge>> {
+        let policy = self.policy()?.max_key_age().await;
+        Ok(policy.or(self.config()?.max_key_age))
+    }
+
+    // Expired recipient keys are refused; keys close to expiry are only logged.
+    async fn check_key_age(&self, recipients: &[String]) -> DGResult<()> {
+        let Some(max_key_age) = self.max_key_age().await? else {
+            return Ok(());
+        };
+        let keyring = self
+            .keyring
+            .as_ref()
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))?;
+        let now = unix_now();
+        for name in recipients {
+            let Some(meta) = keyring
+                .x25519_public(name)
+                .and_then(|(id, _)| keyring.lookup(&id))
+            else {
+                continue;
+            };
+            max_key_age.check_recipient(meta, now)?;
+            if max_key_age.state(meta, now) == KeyAgeState::Expiring {
+                warn!(
+                    key_id = %meta.id,
+                    expires_at = max_key_age.expires_at(meta),
+                    "encrypting to a key that is close to its maximum age"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    // Every recipient must resolve to an unrevoked X25519 key, so nobody listed on an envelope is
+    // silently left unable to open it.
+    fn recipient_wraps(
+        &self,
+        file_key: &[u8; 32],
+        recipients: &[String],
+    ) -> DGResult<Vec<KeyWrap>> {
+        if recipients.is_empty() {
+            return Ok(Vec::new());
+        }
+        let keyring = self
+            .keyring
+            .as_ref()
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))?;
+        let mut wraps: Vec<KeyWrap> = Vec::with_capacity(recipients.len());
+        for name in recipients {
+            let (id, public) = keyring.x25519_public(name).ok_or_else(|| {
+                DGError::Config(format!(
+                    "recipient '{name}' is not an X25519 key in the keyring"
+                ))
+            })?;
+            if wraps
+                .iter()
+                .any(|wrap| wrap.key_id.as_deref() == Some(id.as_str()))
+            {
+                continue;
+            }
+            wraps.push(wrap::wrap_x25519(
+                &self.entropy,
+                WrapKind::Recipient,
+                &public,
+                file_key,
+            )?);
+        }
+        debug!(count = wraps.len(), "file key wrapped to recipients");
+        Ok(wraps)
+    }
+
+    fn share_file_key(
+        &self,
+        file_key: &[u8; 32],
+        custodians: &[String],
+        required: u8,
+    ) -> DGResult<(Vec<KeyWrap>, ThresholdSpec)> {
+        let total = u8::try_from(custodians.len())
+            .map_err(|_| DGError::Config("at most 255 custodians are supported".into()))?;
+        let keyring = self
+            .keyring
+            .as_ref()
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))?;
+        let publics = custodians
+            .iter()
+            .map(|name| {
+                keyring.x25519_public(name).ok_or_else(|| {
+                    DGError::Config(format!(
+                        "custodian '{name}' is not an X25519 key in the keyring"
+                    ))
+                })
+            })
+            .collect::<DGResult<Vec<_>>>()?;
+
+        let shares = Zeroizing::new(threshold::split(&self.entropy, file_key, required, total)?);
+        let wraps = publics
+            .iter()
+            .zip(shares.iter())
+            .map(|((_, public), (index, share))| {
+                wrap::wrap_share(&self.entropy, public, *index, share)
+            })
+            .collect::<DGResult<Vec<_>>>()?;
+        debug!(required, total, "file key split across custodians");
+        Ok((wraps, ThresholdSpec { required, total }))
+    }
+
+    fn keyring(&self) -> DGResult<&Keyring> {
+        self.keyring
             .as_ref()
-            .ok_or_else(|| DGError::Internal("policy not loaded".into()))?;
-        Ok((key, config, policy))
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))
+    }
+
+    fn keyring_mut(&mut self) -> DGResult<&mut Keyring> {
+        self.keyring
+            .as_mut()
+            .ok_or_else(|| DGError::Internal("engine not initialized".into()))
     }
 }
 
-async fn load_or_create_key(data_dir: &Path) -> DGResult<[u8; 32]> {
+fn wrap_key_id(entry: &KeyWrap) -> &str {
+    entry.key_id.as_deref().unwrap_or(MASTER_KEY_ID)
+}
+
+fn envelope_wraps(env: &Envelope) -> DGResult<Vec<KeyWrap>> {
+    match env.meta.get("key_wraps") {
+        Some(wraps) => serde_json::from_value(wraps.clone())
+            .map_err(|err| DGError::Crypto(format!("invalid key wraps: {err}"))),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn threshold_spec(env: &Envelope) -> DGResult<Option<ThresholdSpec>> {
+    env.meta
+        .get("threshold")
+        .map(|spec| {
+            serde_json::from_value(spec.clone())
+                .map_err(|err| DGError::Crypto(format!("invalid threshold header: {err}")))
+        })
+        .transpose()
+}
+
+// Who a call's policy checks and audit events are for: the identity a daemon is acting for, or
+// the built-in `system` subject for in-process callers.
+struct Caller {
+    subject: String,
+    subjects: Vec<String>,
+}
+
+impl Caller {
+    fn current() -> Self {
+        match Identity::current() {
+            Some(identity) => Self {
+                subject: identity.actor(),
+                subjects: identity.subjects(),
+            },
+            None => Self {
+                subject: SYSTEM_SUBJECT.to_owned(),
+                subjects: vec![SYSTEM_SUBJECT.to_owned()],
+            },
+        }
+    }
+}
+
+struct PreviousVersion {
+    file_key: Zeroizing<[u8; 32]>,
+    table: ChunkTable,
+    aad: Vec<u8>,
+}
+
+fn open_body(file_key: &[u8; 32], env: &Envelope, aad: &[u8]) -> DGResult<Vec<u8>> {
+    if journal::read(&env.meta)?.is_some() {
+        return journal::open(file_key, &env.bytes, aad);
+    }
+    match chunking::read(&env.meta)? {
+        Some(table) => chunking::open(file_key, &env.bytes, aad, &table),
+        None => open_payload_with(file_key, &env.bytes, aad),
+    }
+}
+
+pub(crate) fn seal_payload(
+    entropy: &Entropy,
+    key: &[u8; 32],
+    plaintext: &[u8],
+) -> DGResult<Vec<u8>> {
+    seal_payload_with(entropy, key, plaintext, &[])
+}
+
+pub(crate) fn seal_payload_with(
+    entropy: &Entropy,
+    key: &[u8; 32],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> DGResult<Vec<u8>> {
+    let cipher = Aes256Gcm::new(key.into());
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    entropy.fill(&mut nonce_bytes);
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce_bytes),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|err| DGError::Crypto(format!("failed to encrypt: {err}")))?;
+
+    let mut payload = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    payload.extend_from_slice(&nonce_bytes);
+    payload.extend_from_slice(&ciphertext);
+    Ok(payload)
+}
+
+pub(crate) fn open_payload(key: &[u8; 32], payload: &[u8]) -> DGResult<Vec<u8>> {
+    open_payload_with(key, payload, &[])
+}
+
+pub(crate) fn open_payload_with(key: &[u8; 32], payload: &[u8], aad: &[u8]) -> DGResult<Vec<u8>> {
+    if payload.len() < NONCE_LEN {
+        return Err(DGError::Crypto("envelope missing nonce".into()));
+    }
+    let (nonce, cipher_bytes) = payload.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(key.into());
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: cipher_bytes,
+                aad,
+            },
+        )
+        .map_err(|err| DGError::Crypto(format!("failed to decrypt: {err}")))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+async fn load_or_create_key(
+    data_dir: &Path,
+    create: bool,
+    entropy: &Entropy,
+) -> DGResult<Zeroizing<[u8; 32]>> {
     let key_dir = data_dir.join("keys");
     let key_path = key_dir.join(KEY_FILE);
     if let Ok(bytes) = fs::read(&key_path).await {
+        let bytes = Zeroizing::new(bytes);
         if bytes.len() == 32 {
-            let mut key = [0u8; 32];
+            let mut key = Zeroizing::new([0u8; 32]);
             key.copy_from_slice(&bytes);
             return Ok(key);
         }
-        warn!(path = %key_path.display(), "existing key has unexpected length; regenerating");
+        return Err(DGError::Config(format!(
+            "{} has unexpected length {}; refusing to replace it",
+            key_path.display(),
+            bytes.len()
+        )));
+    }
+    if !create {
+        return Err(DGError::Config(format!(
+            "{} is missing and cannot be created on a read-only data directory",
+            key_path.display()
+        )));
     }
 
     fs::create_dir_all(&key_dir)
         .await
         .map_err(|err| DGError::Config(format!("unable to create key directory: {err}")))?;
 
-    let mut key = [0u8; 32];
-    OsRng.fill_bytes(&mut key);
-    let mut file = fs::File::create(&key_path)
+    let key = entropy.key();
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options
+        .open(&key_path)
         .await
         .map_err(|err| DGError::Config(format!("unable to create key file: {err}")))?;
-    file.write_all(&key)
+    file.write_all(&*key)
         .await
         .map_err(|err| DGError::Config(format!("unable to write key file: {err}")))?;
     file.sync_all()
@@ -187,15 +2294,58 @@ async fn load_or_create_key(data_dir: &Path) -> DGResult<[u8; 32]> {
     Ok(key)
 }
 
-async fn load_policy(data_dir: &Path) -> DGResult<PolicyEngine> {
-    let path = data_dir.join(POLICY_FILE);
-    if let Ok(bytes) = fs::read(&path).await {
-        return PolicyEngine::from_bytes(bytes)
-            .await
-            .map_err(|err| DGError::Config(format!("failed to load policy: {err}")));
+async fn scrub_key_file(path: &Path) -> DGResult<()> {
+    let io = |err: std::io::Error| {
+        DGError::Config(format!("unable to remove {}: {err}", path.display()))
+    };
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .await
+        .map_err(io)?;
+    file.write_all(&[0u8; 32]).await.map_err(io)?;
+    file.sync_all().await.map_err(io)?;
+    drop(file);
+    fs::remove_file(path).await.map_err(io)
+}
+
+async fn load_policy(data_dir: &Path, keyring: &Keyring) -> DGResult<PolicyEngine> {
+    if let Some(path) = policy::find_policy(data_dir).await {
+        if let Ok(bytes) = fs::read(&path).await {
+            let trust = trust::load_verified(data_dir, keyring).await?;
+            if trust.requires(TrustRole::Policy) {
+                verify_policy_signature(&trust, &path, &bytes).await?;
+            }
+            return PolicyEngine::parse_as(&bytes, PolicyFormat::from_path(&path)).map_err(|err| {
+                DGError::Config(format!("failed to load {}: {err}", path.display()))
+            });
+        }
     }
 
     PolicyEngine::default()
         .await
         .map_err(|err| DGError::Config(format!("failed to build default policy: {err}")))
 }
+
+// Once the trust store names a policy signer, an unsigned or foreign-signed policy refuses to load.
+async fn verify_policy_signature(trust: &TrustStore, path: &Path, bytes: &[u8]) -> DGResult<()> {
+    let mut signature_path = path.as_os_str().to_owned();
+    signature_path.push(".");
+    signature_path.push(SIGNATURE_EXTENSION);
+    let detached = fs::read(&signature_path).await.map_err(|err| {
+        DGError::PolicyDenied(format!(
+            "{} must be signed by a trusted policy signer: {err}",
+            path.display()
+        ))
+    })?;
+    match trust.verify(TrustRole::Policy, bytes, &detached)? {
+        Some(signer) => {
+            info!(signer = %signer.id, policy = %path.display(), "policy signature verified");
+            Ok(())
+        }
+        None => Err(DGError::PolicyDenied(format!(
+            "{} is not signed by a trusted policy signer",
+            path.display()
+        ))),
+    }
+}
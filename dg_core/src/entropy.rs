@@ -0,0 +1,68 @@
+use rand::rngs::OsRng;
+use rand::RngCore;
+#[cfg(feature = "deterministic")]
+use rand::SeedableRng;
+#[cfg(feature = "deterministic")]
+use rand_chacha::ChaCha20Rng;
+#[cfg(feature = "deterministic")]
+use std::sync::Mutex;
+
+use zeroize::Zeroizing;
+
+use crate::api::{DGError, DGResult};
+
+#[derive(Default)]
+pub(crate) enum Entropy {
+    #[default]
+    Os,
+    #[cfg(feature = "deterministic")]
+    Seeded(Box<Mutex<ChaCha20Rng>>),
+}
+
+impl Entropy {
+    pub(crate) fn from_seed(seed: Option<u64>) -> DGResult<Self> {
+        match seed {
+            None => Ok(Entropy::Os),
+            Some(seed) => seeded(seed),
+        }
+    }
+
+    pub(crate) fn fill(&self, bytes: &mut [u8]) {
+        match self {
+            Entropy::Os => OsRng.fill_bytes(bytes),
+            #[cfg(feature = "deterministic")]
+            Entropy::Seeded(rng) => rng
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .fill_bytes(bytes),
+        }
+    }
+
+    pub(crate) fn key(&self) -> Zeroizing<[u8; 32]> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        self.fill(&mut *key);
+        key
+    }
+}
+
+#[cfg(feature = "deterministic")]
+fn seeded(seed: u64) -> DGResult<Entropy> {
+    if !cfg!(debug_assertions) {
+        return Err(DGError::Config(
+            "deterministic entropy is refused in release builds".into(),
+        ));
+    }
+    tracing::warn!(
+        seed,
+        "using deterministic entropy; envelopes are reproducible and not secret"
+    );
+    let rng = ChaCha20Rng::seed_from_u64(seed);
+    Ok(Entropy::Seeded(Box::new(Mutex::new(rng))))
+}
+
+#[cfg(not(feature = "deterministic"))]
+fn seeded(_seed: u64) -> DGResult<Entropy> {
+    Err(DGError::Config(
+        "deterministic_seed needs dg_core built with the `deterministic` feature".into(),
+    ))
+}
@@ -0,0 +1,212 @@
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+
+use crate::api::{DGError, DGResult, Envelope};
+use crate::wrap::KeyWrap;
+
+pub const ENVELOPE_MAGIC: &[u8; 4] = b"DGEV";
+// Versions 0 and 2 were JSON documents; 3 is the first binary layout.
+pub const ENVELOPE_VERSION: u16 = 3;
+const MASTER_KEY_ID: &str = "master";
+
+// magic | version u16 | algorithm u8 | key id (u8 length) | nonce (u8 length) | aad (u32 length)
+// | ciphertext with tag. Integers are big-endian and the aad block is the JSON metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CipherAlgorithm {
+    Aes256Gcm,
+}
+
+impl CipherAlgorithm {
+    pub fn id(self) -> u8 {
+        match self {
+            CipherAlgorithm::Aes256Gcm => 1,
+        }
+    }
+
+    pub fn from_id(id: u8) -> DGResult<Self> {
+        match id {
+            1 => Ok(CipherAlgorithm::Aes256Gcm),
+            other => Err(malformed(format!("unknown cipher algorithm {other}"))),
+        }
+    }
+
+    pub fn nonce_len(self) -> usize {
+        match self {
+            CipherAlgorithm::Aes256Gcm => 12,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnvelopeHeader {
+    pub version: u16,
+    pub algorithm: CipherAlgorithm,
+    pub key_id: String,
+    pub nonce: Vec<u8>,
+    pub aad_len: usize,
+    pub ciphertext_len: usize,
+}
+
+impl Envelope {
+    pub fn is_binary(data: &[u8]) -> bool {
+        data.starts_with(ENVELOPE_MAGIC)
+    }
+
+    // The key id names the first key the file key is wrapped to, or `master` for envelopes that
+    // predate key wraps. Decryption still goes by the wraps in the metadata.
+    pub fn key_id(&self) -> DGResult<String> {
+        let first = match self.meta.get("key_wraps") {
+            Some(wraps) => serde_json::from_value::<Vec<KeyWrap>>(wraps.clone())
+                .map_err(|err| malformed(format!("invalid key wraps: {err}")))?
+                .into_iter()
+                .next()
+                .and_then(|wrap| wrap.key_id),
+            None => None,
+        };
+        Ok(first.unwrap_or_else(|| MASTER_KEY_ID.to_owned()))
+    }
+
+    pub fn serialize(&self) -> DGResult<Vec<u8>> {
+        let algorithm = CipherAlgorithm::Aes256Gcm;
+        if !self.meta.is_object() {
+            return Err(malformed("envelope metadata must be a JSON object".into()));
+        }
+        if self.bytes.len() < algorithm.nonce_len() {
+            return Err(malformed("payload is shorter than its nonce".into()));
+        }
+        let (nonce, ciphertext) = self.bytes.split_at(algorithm.nonce_len());
+        let key_id = self.key_id()?;
+        let key_len = u8::try_from(key_id.len())
+            .map_err(|_| malformed("key id is longer than 255 bytes".into()))?;
+        let aad = serde_json::to_vec(&self.meta)
+            .map_err(|err| DGError::Internal(format!("unable to encode metadata: {err}")))?;
+        let aad_len = u32::try_from(aad.len())
+            .map_err(|_| malformed("metadata is larger than 4 GiB".into()))?;
+
+        let mut out = Vec::with_capacity(
+            ENVELOPE_MAGIC.len() + 9 + key_id.len() + nonce.len() + aad.len() + ciphertext.len(),
+        );
+        out.extend_from_slice(ENVELOPE_MAGIC);
+        out.extend_from_slice(&ENVELOPE_VERSION.to_be_bytes());
+        out.push(algorithm.id());
+        out.push(key_len);
+        out.extend_from_slice(key_id.as_bytes());
+        out.push(nonce.len() as u8);
+        out.extend_from_slice(nonce);
+        out.extend_from_slice(&aad_len.to_be_bytes());
+        out.extend_from_slice(&aad);
+        out.extend_from_slice(ciphertext);
+        Ok(out)
+    }
+
+    // Reads the binary layout, or a JSON document `{ "payload": <base64>, "meta": {...} }` as
+    // written before it existed.
+    pub fn parse(data: &[u8]) -> DGResult<Self> {
+        if !Self::is_binary(data) {
+            return parse_legacy(data);
+        }
+        let (header, aad, ciphertext) = split(data)?;
+        let meta: serde_json::Value = serde_json::from_slice(aad)
+            .map_err(|err| malformed(format!("invalid metadata block: {err}")))?;
+        if !meta.is_object() {
+            return Err(malformed("envelope metadata must be a JSON object".into()));
+        }
+        let mut bytes = Vec::with_capacity(header.nonce.len() + ciphertext.len());
+        bytes.extend_from_slice(&header.nonce);
+        bytes.extend_from_slice(ciphertext);
+        Ok(Self { bytes, meta })
+    }
+
+    pub fn parse_header(data: &[u8]) -> DGResult<EnvelopeHeader> {
+        if !Self::is_binary(data) {
+            return Err(malformed("missing envelope magic".into()));
+        }
+        Ok(split(data)?.0)
+    }
+}
+
+fn split(data: &[u8]) -> DGResult<(EnvelopeHeader, &[u8], &[u8])> {
+    let mut reader = Reader {
+        data,
+        offset: ENVELOPE_MAGIC.len(),
+    };
+    let version = u16::from_be_bytes(reader.array()?);
+    if version != ENVELOPE_VERSION {
+        return Err(malformed(format!("unsupported envelope version {version}")));
+    }
+    let algorithm = CipherAlgorithm::from_id(reader.array::<1>()?[0])?;
+    let key_len = reader.array::<1>()?[0] as usize;
+    let key_id = std::str::from_utf8(reader.take(key_len)?)
+        .map_err(|_| malformed("key id is not UTF-8".into()))?
+        .to_owned();
+    let nonce_len = reader.array::<1>()?[0] as usize;
+    if nonce_len != algorithm.nonce_len() {
+        return Err(malformed(format!(
+            "nonce is {nonce_len} bytes; {algorithm:?} needs {}",
+            algorithm.nonce_len()
+        )));
+    }
+    let nonce = reader.take(nonce_len)?.to_vec();
+    let aad_len = u32::from_be_bytes(reader.array()?) as usize;
+    let aad = reader.take(aad_len)?;
+    let ciphertext = &data[reader.offset..];
+    Ok((
+        EnvelopeHeader {
+            version,
+            algorithm,
+            key_id,
+            nonce,
+            aad_len,
+            ciphertext_len: ciphertext.len(),
+        },
+        aad,
+        ciphertext,
+    ))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> DGResult<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| malformed("header is truncated".into()))?;
+        let slice = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> DGResult<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("length checked"))
+    }
+}
+
+#[derive(Deserialize)]
+struct LegacyEnvelope {
+    payload: String,
+    meta: serde_json::Value,
+}
+
+fn parse_legacy(data: &[u8]) -> DGResult<Envelope> {
+    let legacy: LegacyEnvelope = serde_json::from_slice(data)
+        .map_err(|err| malformed(format!("not a Data Guardian envelope: {err}")))?;
+    if !legacy.meta.is_object() {
+        return Err(malformed("envelope metadata must be a JSON object".into()));
+    }
+    Ok(Envelope {
+        bytes: general_purpose::STANDARD
+            .decode(&legacy.payload)
+            .map_err(|err| malformed(format!("invalid envelope payload: {err}")))?,
+        meta: legacy.meta,
+    })
+}
+
+fn malformed(detail: String) -> DGError {
+    DGError::Crypto(format!("malformed envelope: {detail}"))
+}
@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+
+use crate::keyring::KeyAlgorithm;
+
+const CONTEXT: &str = "data-guardian key fingerprint v1";
+const HEX_BYTES: usize = 20;
+const HEX_GROUP: usize = 4;
+const WORD_COUNT: usize = 8;
+const EMOJI_COUNT: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub hex: String,
+    pub words: Vec<String>,
+    pub emoji: String,
+}
+
+impl Fingerprint {
+    pub fn compute(algorithm: KeyAlgorithm, public: &[u8]) -> Self {
+        let mut hasher = blake3::Hasher::new_derive_key(CONTEXT);
+        hasher.update(algorithm_tag(algorithm));
+        hasher.update(&[0]);
+        hasher.update(public);
+        let digest = hasher.finalize();
+        let digest = digest.as_bytes();
+
+        let hex = hex::encode(&digest[..HEX_BYTES]);
+        let hex = hex
+            .as_bytes()
+            .chunks(HEX_GROUP)
+            .map(|group| std::str::from_utf8(group).unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join(" ");
+        Self {
+            hex,
+            words: digest[..WORD_COUNT]
+                .iter()
+                .map(|byte| WORDS[usize::from(*byte)].to_owned())
+                .collect(),
+            emoji: digest[..EMOJI_COUNT]
+                .iter()
+                .map(|byte| EMOJI[usize::from(*byte >> 2)])
+                .collect(),
+        }
+    }
+
+    pub fn matches(&self, presented: &str) -> bool {
+        let presented = presented.trim().to_lowercase();
+        if presented.is_empty() {
+            return false;
+        }
+        let compact: String = presented
+            .chars()
+            .filter(|c| !c.is_whitespace() && !matches!(c, ':' | '-'))
+            .collect();
+        if compact == self.hex.replace(' ', "") {
+            return true;
+        }
+        let words: Vec<&str> = presented
+            .split(|c: char| c.is_whitespace() || c == '-' || c == ',')
+            .filter(|word| !word.is_empty())
+            .collect();
+        if words == self.words {
+            return true;
+        }
+        compact == self.emoji
+    }
+}
+
+fn algorithm_tag(algorithm: KeyAlgorithm) -> &'static [u8] {
+    match algorithm {
+        KeyAlgorithm::X25519 => b"x25519",
+        KeyAlgorithm::Ed25519 => b"ed25519",
+    }
+}
+
+const WORDS: [&str; 256] = [
+    "acid", "acorn", "actor", "adobe", "agent", "alarm", "album", "alley", "amber", "angle",
+    "ankle", "apple", "apron", "arena", "armor", "arrow", "aspen", "atlas", "attic", "audio",
+    "award", "bacon", "badge", "bagel", "baker", "bamboo", "banjo", "barn", "basil", "basin",
+    "beach", "beard", "bench", "berry", "bison", "blade", "blaze", "bloom", "board", "bonus",
+    "boost", "brass", "bread", "brick", "bride", "brook", "broom", "brush", "cabin", "cable",
+    "cactus", "camel", "canal", "candy", "canoe", "canyon", "cargo", "carpet", "castle", "cedar",
+    "chalk", "charm", "cherry", "chess", "chief", "chili", "cider", "cinema", "circus", "civic",
+    "cliff", "clock", "cloud", "clover", "cobra", "cocoa", "comet", "coral", "cotton", "couch",
+    "crane", "crater", "crown", "cube", "cycle", "daisy", "dance", "delta", "denim", "depot",
+    "desert", "diary", "dingo", "disco", "dock", "dolphin", "donut", "dragon", "drum", "dune",
+    "eagle", "easel", "echo", "elbow", "elder", "ember", "emu", "engine", "epoch", "falcon",
+    "fable", "fern", "ferry", "fiber", "fiddle", "field", "flame", "flask", "fleet", "flint",
+    "flute", "focus", "forest", "fossil", "fox", "frost", "fudge", "gable", "galaxy", "garden",
+    "garlic", "gecko", "geyser", "ginger", "glacier", "globe", "goose", "grain", "granite",
+    "grape", "gravel", "guitar", "gull", "habit", "hammer", "harbor", "harp", "hazel", "hedge",
+    "helmet", "hermit", "hippo", "honey", "hornet", "hotel", "husky", "igloo", "index", "inlet",
+    "iris", "island", "ivory", "jacket", "jaguar", "jasmine", "jelly", "jetty", "jewel", "jigsaw",
+    "jungle", "kayak", "kernel", "kettle", "kiosk", "kitten", "koala", "ladder", "lagoon",
+    "lantern", "laser", "lemon", "lentil", "lever", "lilac", "limbo", "linen", "lizard", "llama",
+    "lobster", "locket", "lotus", "lunar", "magnet", "mango", "maple", "marble", "meadow", "melon",
+    "mesa", "meteor", "mint", "mirror", "mocha", "molar", "monk", "moose", "mosaic", "moth",
+    "muffin", "nectar", "needle", "nickel", "noodle", "nugget", "oasis", "ocean", "olive", "onion",
+    "opal", "orbit", "orchid", "otter", "oven", "oyster", "paddle", "palace", "panda", "paper",
+    "parrot", "pasta", "peach", "pebble", "pepper", "piano", "pigeon", "pillow", "pilot", "pine",
+    "planet", "plum", "polar", "pond", "poppy", "quartz", "quill", "rabbit", "radar", "radish",
+    "raft", "raven", "reef", "relic", "ribbon", "river", "robin", "rocket",
+];
+
+const EMOJI: [&str; 64] = [
+    "🐶", "🐱", "🦊", "🐻", "🐼", "🐨", "🐯", "🦁", "🐮", "🐷", "🐸", "🐵", "🐔", "🐧", "🐦", "🦆",
+    "🦉", "🐺", "🐴", "🦄", "🐝", "🐛", "🦋", "🐌", "🐞", "🐢", "🐍", "🐙", "🦀", "🐬", "🐳", "🦈",
+    "🌵", "🌲", "🌴", "🍀", "🍁", "🌻", "🌹", "🍄", "🌙", "⭐", "🌈", "🔥", "💧", "🌊", "🍎", "🍌",
+    "🍇", "🍓", "🍒", "🍍", "🥕", "🌽", "🍞", "🧀", "🍕", "🎈", "🎁", "🔑", "🔔", "⚓", "🚲", "🚀",
+];
@@ -0,0 +1,107 @@
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::api::{DGError, DGResult, Envelope};
+use crate::wrap::KeyWrap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GrantAction {
+    Decrypt,
+    View,
+}
+
+impl GrantAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GrantAction::Decrypt => "decrypt",
+            GrantAction::View => "view",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrantRequest {
+    pub recipient: String,
+    pub signer: String,
+    pub actions: Vec<GrantAction>,
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessGrant {
+    pub id: String,
+    pub envelope: String,
+    pub recipient: String,
+    pub signer: String,
+    pub issued_at: u64,
+    pub expires_at: u64,
+    pub actions: Vec<GrantAction>,
+    pub wrap: KeyWrap,
+}
+
+impl AccessGrant {
+    // Everything but the signature: the grant must name this envelope, be live and allow the action.
+    pub fn check(&self, env: &Envelope, action: GrantAction, now: u64) -> DGResult<()> {
+        if self.envelope != envelope_digest(env) {
+            return Err(DGError::Crypto(format!(
+                "grant {} was issued for a different envelope",
+                self.id
+            )));
+        }
+        if self.expires_at <= now {
+            return Err(DGError::PolicyDenied(format!(
+                "grant {} has expired",
+                self.id
+            )));
+        }
+        if !self.actions.contains(&action) {
+            return Err(DGError::PolicyDenied(format!(
+                "grant {} does not allow {}",
+                self.id,
+                action.as_str()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedGrant {
+    pub payload: String,
+    pub signature: String,
+}
+
+impl SignedGrant {
+    pub fn new(grant: &AccessGrant, signature: &[u8]) -> DGResult<Self> {
+        let payload = serde_json::to_vec(grant)
+            .map_err(|err| DGError::Internal(format!("unable to encode grant: {err}")))?;
+        Ok(Self {
+            payload: general_purpose::STANDARD.encode(payload),
+            signature: general_purpose::STANDARD.encode(signature),
+        })
+    }
+
+    pub fn grant(&self) -> DGResult<AccessGrant> {
+        serde_json::from_slice(&self.payload_bytes()?)
+            .map_err(|err| DGError::Config(format!("unreadable access grant: {err}")))
+    }
+
+    pub(crate) fn payload_bytes(&self) -> DGResult<Vec<u8>> {
+        general_purpose::STANDARD
+            .decode(self.payload.trim())
+            .map_err(|err| DGError::Config(format!("grant payload is not base64: {err}")))
+    }
+
+    pub(crate) fn signature_bytes(&self) -> DGResult<Vec<u8>> {
+        general_purpose::STANDARD
+            .decode(self.signature.trim())
+            .map_err(|err| DGError::Config(format!("grant signature is not base64: {err}")))
+    }
+}
+
+// Grants are bound to the ciphertext, so re-encrypting an envelope invalidates every grant for it.
+pub fn envelope_digest(env: &Envelope) -> String {
+    hex::encode(Sha256::digest(&env.bytes))
+}
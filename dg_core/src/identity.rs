@@ -0,0 +1,156 @@
+use std::env;
+use std::future::Future;
+
+use serde::{Deserialize, Serialize};
+
+pub const LOCAL_SUBJECT: &str = "local-user";
+const USER_PREFIX: &str = "user:";
+const ORG_PREFIX: &str = "org:";
+const GROUP_PREFIX: &str = "group:";
+const SESSION_PREFIX: &str = "session:";
+const SCOPE_PREFIX: &str = "scope:";
+const UNKNOWN_USER: &str = "unknown";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Identity {
+    pub user: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+    // The client an IPC session token was minted for, and the scopes it carries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<String>,
+}
+
+tokio::task_local! {
+    static ACTING: Identity;
+}
+
+impl Identity {
+    pub fn new(user: impl Into<String>) -> Self {
+        Self {
+            user: user.into(),
+            organization: None,
+            groups: Vec::new(),
+            session: None,
+            scopes: Vec::new(),
+        }
+    }
+
+    pub fn from_os() -> Self {
+        Self::new(os_user().unwrap_or_else(|| UNKNOWN_USER.to_owned()))
+    }
+
+    pub fn with_organization(mut self, organization: Option<String>) -> Self {
+        self.organization = organization.filter(|org| !org.trim().is_empty());
+        self
+    }
+
+    pub fn with_groups(mut self, groups: impl IntoIterator<Item = String>) -> Self {
+        for group in groups {
+            if !group.trim().is_empty() && !self.groups.contains(&group) {
+                self.groups.push(group);
+            }
+        }
+        self
+    }
+
+    pub fn with_session<S: Into<String>>(
+        mut self,
+        client: &str,
+        scopes: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.session = Some(client.to_owned());
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn subject(&self) -> String {
+        format!("{USER_PREFIX}{}", self.user)
+    }
+
+    // The subject audit events name: the session when there is one, otherwise the user.
+    pub fn actor(&self) -> String {
+        match &self.session {
+            Some(client) => format!("{SESSION_PREFIX}{client}"),
+            None => self.subject(),
+        }
+    }
+
+    pub fn subjects(&self) -> Vec<String> {
+        let mut subjects = vec![self.subject()];
+        if let Some(org) = &self.organization {
+            subjects.push(format!("{ORG_PREFIX}{org}"));
+        }
+        subjects.extend(
+            self.groups
+                .iter()
+                .map(|group| format!("{GROUP_PREFIX}{group}")),
+        );
+        if let Some(client) = &self.session {
+            subjects.push(format!("{SESSION_PREFIX}{client}"));
+            subjects.extend(
+                self.scopes
+                    .iter()
+                    .map(|scope| format!("{SCOPE_PREFIX}{scope}")),
+            );
+        }
+        subjects.push(LOCAL_SUBJECT.to_owned());
+        subjects
+    }
+
+    // Rebuilds an identity from the list `subjects` produced, e.g. on the far side of an RPC.
+    pub fn from_subjects(subjects: &[String]) -> Option<Self> {
+        let user = subjects
+            .iter()
+            .find_map(|subject| subject.strip_prefix(USER_PREFIX))?;
+        Some(Self {
+            user: user.to_owned(),
+            organization: subjects
+                .iter()
+                .find_map(|subject| subject.strip_prefix(ORG_PREFIX))
+                .map(str::to_owned),
+            groups: subjects
+                .iter()
+                .filter_map(|subject| subject.strip_prefix(GROUP_PREFIX))
+                .map(str::to_owned)
+                .collect(),
+            session: subjects
+                .iter()
+                .find_map(|subject| subject.strip_prefix(SESSION_PREFIX))
+                .map(str::to_owned),
+            scopes: subjects
+                .iter()
+                .filter_map(|subject| subject.strip_prefix(SCOPE_PREFIX))
+                .map(str::to_owned)
+                .collect(),
+        })
+    }
+
+    // Runs `call` with this identity as the caller the engine checks policy for and audits, in
+    // place of its built-in `system` subject. Daemons wrap each authenticated request in it.
+    pub async fn acting<F: Future>(self, call: F) -> F::Output {
+        ACTING.scope(self, call).await
+    }
+
+    pub(crate) fn current() -> Option<Self> {
+        ACTING.try_with(Clone::clone).ok()
+    }
+}
+
+impl Default for Identity {
+    fn default() -> Self {
+        Self::from_os()
+    }
+}
+
+fn os_user() -> Option<String> {
+    ["USER", "USERNAME", "LOGNAME"]
+        .into_iter()
+        .filter_map(|key| env::var(key).ok())
+        .map(|value| value.trim().to_owned())
+        .find(|value| !value.is_empty())
+}
@@ -0,0 +1,545 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+
+use crate::api::{DGError, DGResult, Envelope};
+use crate::metadata::{self, CustomMetadata, MetadataFilter};
+use crate::wrap::KeyWrap;
+
+pub const INDEX_DIR: &str = "index";
+pub const INDEX_FILE: &str = "envelopes.json";
+pub const LOG_FILE: &str = "envelopes.wal";
+const LOCK_FILE: &str = "envelopes.lock";
+// Changes are folded into the snapshot once the log holds this many.
+const COMPACT_AFTER: usize = 256;
+const CHECK_LEN: usize = 16;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    #[serde(with = "crate::path_encoding")]
+    pub path: PathBuf,
+    #[serde(default)]
+    pub key_ids: Vec<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    pub updated_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    #[serde(default, skip_serializing_if = "CustomMetadata::is_empty")]
+    pub metadata: CustomMetadata,
+}
+
+impl IndexEntry {
+    pub async fn observe(path: &Path, envelope: &Envelope) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            key_ids: wrap_key_ids(envelope),
+            labels: envelope_labels(envelope),
+            updated_at: unix_now(),
+            file_id: file_id(path).await,
+            digest: Some(hex::encode(Sha256::digest(&envelope.bytes))),
+            metadata: metadata::custom_metadata(&envelope.meta),
+        }
+    }
+
+    // A moved envelope keeps its payload. When several copies match, the one with the same file id
+    // is the original; a file id alone is not enough, since deleted files' ids get reused.
+    fn move_score(&self, other: &IndexEntry) -> Option<u8> {
+        let same_payload = matches!((&self.digest, &other.digest), (Some(a), Some(b)) if a == b);
+        let same_file = matches!((&self.file_id, &other.file_id), (Some(a), Some(b)) if a == b);
+        same_payload.then_some(if same_file { 2 } else { 1 })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MovedEnvelope {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Reconciliation {
+    pub roots: Vec<PathBuf>,
+    pub scanned: usize,
+    pub unchanged: usize,
+    pub added: Vec<PathBuf>,
+    pub moved: Vec<MovedEnvelope>,
+    pub removed: Vec<PathBuf>,
+}
+
+impl Reconciliation {
+    pub fn changed(&self) -> bool {
+        !(self.added.is_empty() && self.moved.is_empty() && self.removed.is_empty())
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "index reconciled: {} scanned, {} new, {} moved, {} removed",
+            self.scanned,
+            self.added.len(),
+            self.moved.len(),
+            self.removed.len()
+        )
+    }
+}
+
+// How long a writer waits for another process to finish its write, and how many times a reader
+// starts over when a compaction swaps the files under it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusyRetry {
+    pub attempts: u32,
+    pub delay: Duration,
+}
+
+impl Default for BusyRetry {
+    fn default() -> Self {
+        Self {
+            attempts: 100,
+            delay: Duration::from_millis(20),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndexFile {
+    #[serde(default)]
+    generation: u64,
+    #[serde(default)]
+    envelopes: Vec<IndexEntry>,
+}
+
+// The first line of the log names the snapshot generation it continues.
+#[derive(Serialize, Deserialize)]
+struct LogHeader {
+    generation: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Change {
+    Record {
+        entry: IndexEntry,
+    },
+    Remove {
+        #[serde(with = "crate::path_encoding")]
+        path: PathBuf,
+    },
+}
+
+impl Change {
+    fn apply(self, envelopes: &mut Vec<IndexEntry>) {
+        match self {
+            Change::Record { entry } => {
+                match envelopes
+                    .iter_mut()
+                    .find(|existing| existing.path == entry.path)
+                {
+                    Some(existing) => *existing = entry,
+                    None => envelopes.push(entry),
+                }
+            }
+            Change::Remove { path } => envelopes.retain(|entry| entry.path != path),
+        }
+    }
+}
+
+struct Replay {
+    state: IndexFile,
+    // Whether the log exists and continues the snapshot, so changes can be appended to it.
+    appendable: bool,
+    changes: usize,
+    discarded: usize,
+}
+
+// The index is a snapshot plus a write-ahead log of changes since, both under `index/`. Writers
+// hold an exclusive lock on `envelopes.lock` while they re-read the files, append one change and
+// fsync it; compaction writes a new snapshot and an empty log, each by rename, bumping the
+// generation. Readers take no lock: a log newer than the snapshot means a compaction ran in
+// between, and the read starts over. A change cut short by a crash fails its checksum and is
+// dropped.
+pub struct EnvelopeIndex {
+    dir: PathBuf,
+    state: IndexFile,
+    writable: bool,
+    retry: BusyRetry,
+}
+
+impl EnvelopeIndex {
+    pub async fn load(data_dir: &Path) -> DGResult<Self> {
+        Self::open(data_dir, true).await
+    }
+
+    // For processes that only read, such as tools run while no daemon owns the index.
+    pub async fn load_read_only(data_dir: &Path) -> DGResult<Self> {
+        Self::open(data_dir, false).await
+    }
+
+    async fn open(data_dir: &Path, writable: bool) -> DGResult<Self> {
+        let mut index = Self {
+            dir: data_dir.join(INDEX_DIR),
+            state: IndexFile::default(),
+            writable,
+            retry: BusyRetry::default(),
+        };
+        index.refresh().await?;
+        Ok(index)
+    }
+
+    pub fn with_retry(mut self, retry: BusyRetry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.writable
+    }
+
+    // Picks up changes other processes made since this handle last read the index.
+    pub async fn refresh(&mut self) -> DGResult<()> {
+        self.state = self.read().await?.state;
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.state.envelopes
+    }
+
+    pub fn referencing(&self, key_id: &str) -> Vec<PathBuf> {
+        self.state
+            .envelopes
+            .iter()
+            .filter(|entry| entry.key_ids.iter().any(|id| id == key_id))
+            .map(|entry| entry.path.clone())
+            .collect()
+    }
+
+    // Every filter has to match.
+    pub fn search(&self, filters: &[MetadataFilter]) -> Vec<IndexEntry> {
+        self.state
+            .envelopes
+            .iter()
+            .filter(|entry| filters.iter().all(|filter| filter.matches(&entry.metadata)))
+            .cloned()
+            .collect()
+    }
+
+    pub async fn record(&mut self, path: &Path, envelope: &Envelope) -> DGResult<()> {
+        let entry = IndexEntry::observe(path, envelope).await;
+        self.record_entry(entry).await
+    }
+
+    pub async fn record_entry(&mut self, entry: IndexEntry) -> DGResult<()> {
+        self.change(Change::Record { entry }).await
+    }
+
+    pub async fn remove(&mut self, path: &Path) -> DGResult<()> {
+        self.change(Change::Remove {
+            path: path.to_path_buf(),
+        })
+        .await
+    }
+
+    // `observed` is every envelope found under `roots`. Entries elsewhere are only dropped once
+    // their file is gone and nothing observed matches them.
+    pub async fn reconcile(
+        &mut self,
+        roots: &[PathBuf],
+        observed: Vec<IndexEntry>,
+    ) -> DGResult<Reconciliation> {
+        let _lock = self.lock().await?;
+        self.state = self.read().await?.state;
+        let mut report = Reconciliation {
+            roots: roots.to_vec(),
+            scanned: observed.len(),
+            ..Default::default()
+        };
+        let mut unclaimed = observed;
+        let mut kept = Vec::with_capacity(self.state.envelopes.len());
+        let mut lost = Vec::new();
+        let mut refreshed = false;
+        for entry in std::mem::take(&mut self.state.envelopes) {
+            if let Some(position) = unclaimed.iter().position(|seen| seen.path == entry.path) {
+                let seen = unclaimed.swap_remove(position);
+                report.unchanged += 1;
+                refreshed |= seen.file_id != entry.file_id
+                    || seen.digest != entry.digest
+                    || seen.metadata != entry.metadata;
+                kept.push(IndexEntry {
+                    updated_at: entry.updated_at,
+                    ..seen
+                });
+            } else if !roots.iter().any(|root| entry.path.starts_with(root))
+                && fs::try_exists(&entry.path).await.unwrap_or(true)
+            {
+                kept.push(entry);
+            } else {
+                lost.push(entry);
+            }
+        }
+        for entry in lost {
+            let best = unclaimed
+                .iter()
+                .enumerate()
+                .filter_map(|(position, seen)| Some((entry.move_score(seen)?, position)))
+                .max()
+                .map(|(_, position)| position);
+            match best {
+                Some(position) => {
+                    let seen = unclaimed.swap_remove(position);
+                    report.moved.push(MovedEnvelope {
+                        from: entry.path,
+                        to: seen.path.clone(),
+                    });
+                    kept.push(seen);
+                }
+                None => report.removed.push(entry.path),
+            }
+        }
+        for seen in unclaimed {
+            report.added.push(seen.path.clone());
+            kept.push(seen);
+        }
+        report.added.sort();
+        report.removed.sort();
+        kept.sort_by(|a, b| a.path.cmp(&b.path));
+        self.state.envelopes = kept;
+        if report.changed() || refreshed {
+            self.compact().await?;
+        }
+        Ok(report)
+    }
+
+    async fn change(&mut self, change: Change) -> DGResult<()> {
+        let _lock = self.lock().await?;
+        let replay = self.read().await?;
+        self.state = replay.state;
+        let line = encode_change(&change)?;
+        change.apply(&mut self.state.envelopes);
+        if !replay.appendable || replay.discarded > 0 || replay.changes + 1 >= COMPACT_AFTER {
+            if replay.discarded > 0 {
+                warn!(
+                    discarded = replay.discarded,
+                    "dropped torn envelope index changes"
+                );
+            }
+            return self.compact().await;
+        }
+        let mut log = fs::OpenOptions::new()
+            .append(true)
+            .open(self.dir.join(LOG_FILE))
+            .await
+            .map_err(|err| DGError::Config(format!("unable to open envelope index log: {err}")))?;
+        log.write_all(&line)
+            .await
+            .map_err(|err| DGError::Config(format!("unable to write envelope index log: {err}")))?;
+        log.sync_data()
+            .await
+            .map_err(|err| DGError::Config(format!("unable to flush envelope index log: {err}")))
+    }
+
+    // Only called with the writer lock held.
+    async fn compact(&mut self) -> DGResult<()> {
+        self.state.generation += 1;
+        let snapshot = serde_json::to_vec_pretty(&self.state)
+            .map_err(|err| DGError::Internal(format!("unable to encode envelope index: {err}")))?;
+        let mut header = serde_json::to_vec(&LogHeader {
+            generation: self.state.generation,
+        })
+        .map_err(|err| DGError::Internal(format!("unable to encode envelope index log: {err}")))?;
+        header.push(b'\n');
+        write_replacing(&self.dir.join(INDEX_FILE), &snapshot).await?;
+        write_replacing(&self.dir.join(LOG_FILE), &header).await?;
+        debug!(
+            generation = self.state.generation,
+            envelopes = self.state.envelopes.len(),
+            "envelope index compacted"
+        );
+        Ok(())
+    }
+
+    async fn read(&self) -> DGResult<Replay> {
+        for attempt in 0..self.retry.attempts.max(1) {
+            let snapshot = read_optional(&self.dir.join(INDEX_FILE)).await?;
+            let log = read_optional(&self.dir.join(LOG_FILE)).await?;
+            let state = match snapshot {
+                Some(bytes) => serde_json::from_slice(&bytes)
+                    .map_err(|err| DGError::Config(format!("invalid envelope index: {err}")))?,
+                None => IndexFile::default(),
+            };
+            if let Some(replay) = replay(state, log.as_deref())? {
+                return Ok(replay);
+            }
+            debug!(attempt, "envelope index compacted while reading; retrying");
+            tokio::time::sleep(self.retry.delay).await;
+        }
+        Err(DGError::Internal(
+            "envelope index kept changing while it was read".into(),
+        ))
+    }
+
+    async fn lock(&self) -> DGResult<File> {
+        if !self.writable {
+            return Err(DGError::ReadOnly(
+                "the envelope index was opened read-only".into(),
+            ));
+        }
+        fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|err| DGError::Config(format!("unable to create index directory: {err}")))?;
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.dir.join(LOCK_FILE))
+            .map_err(|err| DGError::Config(format!("unable to open envelope index lock: {err}")))?;
+        for _ in 0..self.retry.attempts.max(1) {
+            match file.try_lock() {
+                Ok(()) => return Ok(file),
+                Err(TryLockError::WouldBlock) => tokio::time::sleep(self.retry.delay).await,
+                Err(TryLockError::Error(err)) => {
+                    return Err(DGError::Config(format!(
+                        "unable to lock envelope index: {err}"
+                    )))
+                }
+            }
+        }
+        Err(DGError::Internal(
+            "envelope index is busy: another process is still writing it".into(),
+        ))
+    }
+}
+
+// `None` when the log is newer than the snapshot, which means a compaction replaced both files
+// between the two reads. An older log was already folded into the snapshot by a compaction that
+// stopped before replacing it.
+fn replay(mut state: IndexFile, log: Option<&[u8]>) -> DGResult<Option<Replay>> {
+    let stale = Replay {
+        state,
+        appendable: false,
+        changes: 0,
+        discarded: 0,
+    };
+    let Some(log) = log else {
+        return Ok(Some(stale));
+    };
+    let mut lines = log.split(|byte| *byte == b'\n');
+    let header: LogHeader = serde_json::from_slice(lines.next().unwrap_or_default())
+        .map_err(|err| DGError::Config(format!("invalid envelope index log: {err}")))?;
+    if header.generation > stale.state.generation {
+        return Ok(None);
+    }
+    if header.generation < stale.state.generation {
+        return Ok(Some(stale));
+    }
+    state = stale.state;
+    let mut changes = 0;
+    let mut discarded = 0;
+    for line in lines.filter(|line| !line.is_empty()) {
+        match decode_change(line) {
+            Some(change) => {
+                changes += 1;
+                change.apply(&mut state.envelopes);
+            }
+            None => discarded += 1,
+        }
+    }
+    Ok(Some(Replay {
+        state,
+        appendable: true,
+        changes,
+        discarded,
+    }))
+}
+
+// A log line is a checksum of the change, a space, then the change as JSON.
+fn encode_change(change: &Change) -> DGResult<Vec<u8>> {
+    let json = serde_json::to_vec(change)
+        .map_err(|err| DGError::Internal(format!("unable to encode index change: {err}")))?;
+    let mut line = checksum(&json).into_bytes();
+    line.push(b' ');
+    line.extend_from_slice(&json);
+    line.push(b'\n');
+    Ok(line)
+}
+
+fn decode_change(line: &[u8]) -> Option<Change> {
+    let (check, json) = line.split_at_checked(CHECK_LEN)?;
+    let json = json.strip_prefix(b" ")?;
+    (check == checksum(json).as_bytes())
+        .then(|| serde_json::from_slice(json).ok())
+        .flatten()
+}
+
+fn checksum(json: &[u8]) -> String {
+    hex::encode(&Sha256::digest(json)[..CHECK_LEN / 2])
+}
+
+async fn read_optional(path: &Path) -> DGResult<Option<Vec<u8>>> {
+    match fs::read(path).await {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(DGError::Config(format!(
+            "unable to read {}: {err}",
+            path.display()
+        ))),
+    }
+}
+
+async fn write_replacing(path: &Path, contents: &[u8]) -> DGResult<()> {
+    let mut staging = path.as_os_str().to_owned();
+    staging.push(".tmp");
+    let staging = PathBuf::from(staging);
+    let io =
+        |err: std::io::Error| DGError::Config(format!("unable to write {}: {err}", path.display()));
+    let mut file = fs::File::create(&staging).await.map_err(io)?;
+    file.write_all(contents).await.map_err(io)?;
+    file.sync_all().await.map_err(io)?;
+    fs::rename(&staging, path).await.map_err(io)
+}
+
+#[cfg(unix)]
+async fn file_id(path: &Path) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = fs::metadata(path).await.ok()?;
+    Some(format!("{}:{}", metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+async fn file_id(_path: &Path) -> Option<String> {
+    None
+}
+
+fn wrap_key_ids(envelope: &Envelope) -> Vec<String> {
+    envelope
+        .meta
+        .get("key_wraps")
+        .and_then(|wraps| serde_json::from_value::<Vec<KeyWrap>>(wraps.clone()).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|wrap| wrap.key_id)
+        .collect()
+}
+
+fn envelope_labels(envelope: &Envelope) -> Vec<String> {
+    envelope
+        .meta
+        .get("labels")
+        .and_then(|labels| serde_json::from_value(labels.clone()).ok())
+        .unwrap_or_default()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
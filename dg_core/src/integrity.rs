@@ -0,0 +1,290 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::api::{DGError, DGResult};
+use crate::audit::AuditEvent;
+use crate::index::{EnvelopeIndex, INDEX_DIR, INDEX_FILE, LOG_FILE};
+use crate::policy::{self, PolicyEngine, PolicyFormat};
+
+const KEY_DIR: &str = "keys";
+const KEY_FILE: &str = "master.key";
+const AUDIT_LOG: [&str; 2] = ["audit", "audit.log"];
+const KEY_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IntegrityComponent {
+    Key,
+    Policy,
+    Audit,
+    Index,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityIssue {
+    pub component: IntegrityComponent,
+    pub problem: String,
+    pub fatal: bool,
+    pub repairable: bool,
+    #[serde(default)]
+    pub repaired: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub checked_at: u64,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    pub fn healthy(&self) -> bool {
+        self.outstanding().next().is_none()
+    }
+
+    pub fn outstanding(&self) -> impl Iterator<Item = &IntegrityIssue> {
+        self.issues.iter().filter(|issue| !issue.repaired)
+    }
+
+    pub fn blocking(&self) -> impl Iterator<Item = &IntegrityIssue> {
+        self.outstanding().filter(|issue| issue.fatal)
+    }
+
+    fn push(&mut self, component: IntegrityComponent, problem: String, fatal: bool) {
+        self.issues.push(IntegrityIssue {
+            component,
+            problem,
+            fatal,
+            repairable: false,
+            repaired: false,
+        });
+    }
+
+    fn push_repairable(
+        &mut self,
+        component: IntegrityComponent,
+        problem: String,
+        fatal: bool,
+        repaired: bool,
+    ) {
+        self.issues.push(IntegrityIssue {
+            component,
+            problem,
+            fatal,
+            repairable: true,
+            repaired,
+        });
+    }
+}
+
+pub async fn check_data_dir(data_dir: &Path) -> IntegrityReport {
+    inspect(data_dir, false).await
+}
+
+pub async fn repair_data_dir(data_dir: &Path) -> IntegrityReport {
+    inspect(data_dir, true).await
+}
+
+async fn inspect(data_dir: &Path, repair: bool) -> IntegrityReport {
+    let mut report = IntegrityReport {
+        checked_at: unix_now(),
+        issues: Vec::new(),
+    };
+    check_key(data_dir, repair, &mut report).await;
+    check_policy(data_dir, &mut report).await;
+    check_audit(data_dir, repair, &mut report).await;
+    check_index(data_dir, repair, &mut report).await;
+    report
+}
+
+async fn check_key(data_dir: &Path, repair: bool, report: &mut IntegrityReport) {
+    let path = data_dir.join(KEY_DIR).join(KEY_FILE);
+    let metadata = match fs::metadata(&path).await {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return,
+        Err(err) => {
+            report.push(
+                IntegrityComponent::Key,
+                format!("unable to inspect {}: {err}", path.display()),
+                true,
+            );
+            return;
+        }
+    };
+    if metadata.len() != KEY_LEN as u64 {
+        report.push(
+            IntegrityComponent::Key,
+            format!(
+                "{} is {} bytes instead of {KEY_LEN}; restore it from a backup",
+                path.display(),
+                metadata.len()
+            ),
+            true,
+        );
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = metadata.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            let repaired = repair
+                && fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+                    .await
+                    .is_ok();
+            report.push_repairable(
+                IntegrityComponent::Key,
+                format!(
+                    "{} is readable by other users (mode {mode:o})",
+                    path.display()
+                ),
+                false,
+                repaired,
+            );
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = repair;
+}
+
+async fn check_policy(data_dir: &Path, report: &mut IntegrityReport) {
+    let Some(path) = policy::find_policy(data_dir).await else {
+        return;
+    };
+    let Ok(bytes) = fs::read(&path).await else {
+        return;
+    };
+    if let Err(err) = PolicyEngine::parse_as(&bytes, PolicyFormat::from_path(&path)) {
+        report.push(
+            IntegrityComponent::Policy,
+            format!("{} cannot be parsed: {err}", path.display()),
+            true,
+        );
+    }
+}
+
+async fn check_audit(data_dir: &Path, repair: bool, report: &mut IntegrityReport) {
+    let path = data_dir.join(AUDIT_LOG[0]).join(AUDIT_LOG[1]);
+    let Ok(content) = fs::read(&path).await else {
+        return;
+    };
+    let complete = content
+        .iter()
+        .rposition(|byte| *byte == b'\n')
+        .map(|position| position + 1)
+        .unwrap_or(0);
+    let valid = |line: &[u8]| serde_json::from_slice::<AuditEvent>(line).is_ok();
+    let lines = content[..complete]
+        .split(|byte| *byte == b'\n')
+        .filter(|line| !line.iter().all(u8::is_ascii_whitespace));
+    let head_ok = lines.clone().next_back().is_none_or(valid);
+    let corrupt = lines.filter(|line| !valid(line)).count();
+    if corrupt > usize::from(!head_ok) {
+        report.push(
+            IntegrityComponent::Audit,
+            format!(
+                "{} has {} unreadable entries before its last entry",
+                path.display(),
+                corrupt - usize::from(!head_ok)
+            ),
+            false,
+        );
+    }
+
+    let torn = complete < content.len();
+    if !torn && head_ok {
+        return;
+    }
+    let keep = if head_ok {
+        complete
+    } else {
+        content[..complete.saturating_sub(1)]
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map(|position| position + 1)
+            .unwrap_or(0)
+    };
+    let repaired = repair
+        && set_aside(&path, &content[keep..], "torn").await.is_ok()
+        && fs::write(&path, &content[..keep]).await.is_ok();
+    report.push_repairable(
+        IntegrityComponent::Audit,
+        format!(
+            "{} ends with a partially written entry ({} bytes)",
+            path.display(),
+            content.len() - keep
+        ),
+        false,
+        repaired,
+    );
+}
+
+async fn check_index(data_dir: &Path, repair: bool, report: &mut IntegrityReport) {
+    let Err(err) = EnvelopeIndex::load_read_only(data_dir).await else {
+        return;
+    };
+    let dir = data_dir.join(INDEX_DIR);
+    let snapshot = dir.join(INDEX_FILE);
+    let log = dir.join(LOG_FILE);
+    let snapshot_ok = match fs::read(&snapshot).await {
+        Ok(content) => serde_json::from_slice::<serde_json::Value>(&content)
+            .is_ok_and(|value| value.is_object()),
+        Err(err) => err.kind() == std::io::ErrorKind::NotFound,
+    };
+    // A damaged log only loses the changes since the last compaction; a damaged snapshot takes
+    // the log with it, since the log continues it.
+    let (path, damaged) = if snapshot_ok {
+        (log.clone(), vec![log])
+    } else {
+        (snapshot.clone(), vec![snapshot, log])
+    };
+    let Ok(content) = fs::read(&path).await else {
+        report.push(IntegrityComponent::Index, err.to_string(), true);
+        return;
+    };
+    let mut repaired = repair && set_aside(&path, &content, "corrupt").await.is_ok();
+    if repaired {
+        for file in damaged {
+            repaired &= match fs::remove_file(&file).await {
+                Ok(()) => true,
+                Err(err) => err.kind() == std::io::ErrorKind::NotFound,
+            };
+        }
+    }
+    report.push_repairable(
+        IntegrityComponent::Index,
+        if snapshot_ok {
+            format!(
+                "{} is damaged ({err}); repairing drops the changes since the last compaction",
+                path.display()
+            )
+        } else {
+            format!(
+                "{} is damaged ({err}); repairing starts an empty index",
+                path.display()
+            )
+        },
+        true,
+        repaired,
+    );
+}
+
+async fn set_aside(path: &Path, content: &[u8], reason: &str) -> DGResult<PathBuf> {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{reason}-{}", unix_now()));
+    let target = PathBuf::from(name);
+    fs::write(&target, content)
+        .await
+        .map_err(|err| DGError::Internal(format!("unable to save {}: {err}", target.display())))?;
+    Ok(target)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,269 @@
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::api::{DGError, DGResult};
+use crate::engine::{open_payload_with, seal_payload_with};
+use crate::entropy::Entropy;
+
+pub const JOURNAL_FIELD: &str = "journal";
+pub const JOURNAL_VERSION: u32 = 1;
+// Appended data is split into records of at most this many plaintext bytes.
+pub const RECORD_LIMIT: usize = 1024 * 1024;
+const ID_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const SEALED_OVERHEAD: usize = ID_LEN + TAG_LEN;
+const RECORD: u8 = 1;
+const COMMIT: u8 = 2;
+const RECORD_HEADER_LEN: usize = 1 + 4;
+const COMMIT_LEN: usize = 1 + 8 + 32;
+const COMMIT_CONTEXT: &str = "data-guardian journal 2024 commit";
+
+// An append-mode payload is a random journal id followed by frames:
+//   record: 0x01 | sealed length u32 | nonce | ciphertext with tag
+//   commit: 0x02 | records so far u64 | chain tag (32 bytes)
+// Each record is sealed under the file key with the journal id and its index as associated data,
+// so records cannot be moved. A commit's tag is a keyed hash over the previous tag and the records
+// since, starting from a hash of the id and the bound metadata. Only committed records are read;
+// frames after the last commit are a torn append and are dropped by the next one. Dropping whole
+// commits from the end cannot be told apart from an older copy of the file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalInfo {
+    pub version: u32,
+}
+
+// Frames for the caller to write after the first `committed_len` payload bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalAppend {
+    pub committed_len: u64,
+    // Bytes of a torn append found after the last commit, which the write replaces.
+    pub discarded_bytes: u64,
+    pub frames: Vec<u8>,
+    pub records: u64,
+    pub plaintext_len: u64,
+}
+
+pub fn read(meta: &serde_json::Value) -> DGResult<Option<JournalInfo>> {
+    let Some(field) = meta.get(JOURNAL_FIELD) else {
+        return Ok(None);
+    };
+    let info: JournalInfo = serde_json::from_value(field.clone())
+        .map_err(|err| DGError::Crypto(format!("invalid journal descriptor: {err}")))?;
+    if info.version != JOURNAL_VERSION {
+        return Err(DGError::Crypto(format!(
+            "unsupported journal version {}",
+            info.version
+        )));
+    }
+    Ok(Some(info))
+}
+
+pub fn descriptor() -> serde_json::Value {
+    serde_json::json!(JournalInfo {
+        version: JOURNAL_VERSION,
+    })
+}
+
+pub(crate) fn seal(
+    entropy: &Entropy,
+    file_key: &[u8; 32],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> DGResult<Vec<u8>> {
+    let mut id = [0u8; ID_LEN];
+    entropy.fill(&mut id);
+    let commit_key = commit_key(file_key);
+    let mut payload = id.to_vec();
+    payload.extend(frames(
+        entropy,
+        file_key,
+        &commit_key,
+        &id,
+        genesis(&commit_key, &id, aad),
+        0,
+        plaintext,
+    )?);
+    Ok(payload)
+}
+
+pub(crate) fn open(file_key: &[u8; 32], payload: &[u8], aad: &[u8]) -> DGResult<Vec<u8>> {
+    let walk = walk(file_key, payload, aad)?;
+    let mut plaintext = Vec::with_capacity(walk.plaintext_len() as usize);
+    for (index, record) in walk.records.iter().enumerate() {
+        plaintext.extend(open_payload_with(
+            file_key,
+            record,
+            &record_aad(walk.id, index as u64),
+        )?);
+    }
+    Ok(plaintext)
+}
+
+// Checks the commit chain without opening any record, then seals `data` after the last commit.
+pub(crate) fn append(
+    entropy: &Entropy,
+    file_key: &[u8; 32],
+    payload: &[u8],
+    aad: &[u8],
+    data: &[u8],
+) -> DGResult<JournalAppend> {
+    if data.is_empty() {
+        return Err(DGError::Config("nothing to append".into()));
+    }
+    let walk = walk(file_key, payload, aad)?;
+    let first = walk.records.len() as u64;
+    let frames = frames(
+        entropy,
+        file_key,
+        &commit_key(file_key),
+        walk.id,
+        walk.chain,
+        first,
+        data,
+    )?;
+    Ok(JournalAppend {
+        committed_len: walk.committed_len as u64,
+        discarded_bytes: (payload.len() - walk.committed_len) as u64,
+        frames,
+        records: first + data.chunks(RECORD_LIMIT).len() as u64,
+        plaintext_len: walk.plaintext_len() + data.len() as u64,
+    })
+}
+
+struct Walk<'a> {
+    id: &'a [u8],
+    chain: blake3::Hash,
+    records: Vec<&'a [u8]>,
+    committed_len: usize,
+}
+
+impl Walk<'_> {
+    fn plaintext_len(&self) -> u64 {
+        self.records
+            .iter()
+            .map(|record| (record.len() - SEALED_OVERHEAD) as u64)
+            .sum()
+    }
+}
+
+fn walk<'a>(file_key: &[u8; 32], payload: &'a [u8], aad: &[u8]) -> DGResult<Walk<'a>> {
+    if payload.len() < ID_LEN {
+        return Err(DGError::Crypto("journal is missing its id".into()));
+    }
+    let id = &payload[..ID_LEN];
+    let commit_key = commit_key(file_key);
+    let mut walk = Walk {
+        id,
+        chain: genesis(&commit_key, id, aad),
+        records: Vec::new(),
+        committed_len: ID_LEN,
+    };
+    let mut pending = Vec::new();
+    let mut offset = ID_LEN;
+    // A frame that runs past the end of the payload was cut short by a crash.
+    while let Some(&kind) = payload.get(offset) {
+        match kind {
+            RECORD => {
+                let Some(len) = payload.get(offset + 1..offset + RECORD_HEADER_LEN) else {
+                    break;
+                };
+                let len = u32::from_be_bytes(len.try_into().expect("four bytes")) as usize;
+                let start = offset + RECORD_HEADER_LEN;
+                let Some(record) = payload.get(start..start + len) else {
+                    break;
+                };
+                if len < SEALED_OVERHEAD {
+                    return Err(DGError::Crypto(format!(
+                        "journal record at byte {offset} is too short"
+                    )));
+                }
+                pending.push(record);
+                offset = start + len;
+            }
+            COMMIT => {
+                let Some(frame) = payload.get(offset..offset + COMMIT_LEN) else {
+                    break;
+                };
+                let count = u64::from_be_bytes(frame[1..9].try_into().expect("eight bytes"));
+                let recorded: [u8; 32] = frame[9..].try_into().expect("32 bytes");
+                let total = (walk.records.len() + pending.len()) as u64;
+                let expected = advance(&commit_key, &walk.chain, total, &pending);
+                // `blake3::Hash` compares in constant time.
+                if count != total || expected != blake3::Hash::from(recorded) {
+                    return Err(DGError::Crypto(format!(
+                        "journal commit at byte {offset} does not match its records"
+                    )));
+                }
+                walk.chain = expected;
+                walk.records.append(&mut pending);
+                offset += COMMIT_LEN;
+                walk.committed_len = offset;
+            }
+            other => {
+                return Err(DGError::Crypto(format!(
+                    "unknown journal frame {other} at byte {offset}"
+                )))
+            }
+        }
+    }
+    Ok(walk)
+}
+
+fn frames(
+    entropy: &Entropy,
+    file_key: &[u8; 32],
+    commit_key: &[u8; 32],
+    id: &[u8],
+    chain: blake3::Hash,
+    first: u64,
+    data: &[u8],
+) -> DGResult<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / RECORD_LIMIT * 64 + 128);
+    let mut sealed = Vec::new();
+    for (index, piece) in (first..).zip(data.chunks(RECORD_LIMIT)) {
+        let record = seal_payload_with(entropy, file_key, piece, &record_aad(id, index))?;
+        out.push(RECORD);
+        out.extend_from_slice(&(record.len() as u32).to_be_bytes());
+        out.extend_from_slice(&record);
+        sealed.push(record);
+    }
+    let total = first + sealed.len() as u64;
+    let records: Vec<&[u8]> = sealed.iter().map(Vec::as_slice).collect();
+    out.push(COMMIT);
+    out.extend_from_slice(&total.to_be_bytes());
+    out.extend_from_slice(advance(commit_key, &chain, total, &records).as_bytes());
+    Ok(out)
+}
+
+fn commit_key(file_key: &[u8; 32]) -> Zeroizing<[u8; 32]> {
+    Zeroizing::new(blake3::derive_key(COMMIT_CONTEXT, file_key))
+}
+
+fn genesis(commit_key: &[u8; 32], id: &[u8], aad: &[u8]) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new_keyed(commit_key);
+    hasher.update(id);
+    hasher.update(&(aad.len() as u64).to_be_bytes());
+    hasher.update(aad);
+    hasher.finalize()
+}
+
+fn advance(
+    commit_key: &[u8; 32],
+    chain: &blake3::Hash,
+    total: u64,
+    records: &[&[u8]],
+) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new_keyed(commit_key);
+    hasher.update(chain.as_bytes());
+    hasher.update(&total.to_be_bytes());
+    for record in records {
+        hasher.update(&(record.len() as u64).to_be_bytes());
+        hasher.update(record);
+    }
+    hasher.finalize()
+}
+
+fn record_aad(id: &[u8], index: u64) -> Vec<u8> {
+    let mut aad = id.to_vec();
+    aad.extend_from_slice(&index.to_be_bytes());
+    aad
+}
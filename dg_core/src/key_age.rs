@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::{DGError, DGResult};
+use crate::keyring::{KeyAlgorithm, KeyMetadata};
+
+pub const DAY: u64 = 24 * 60 * 60;
+
+// Set in `DGConfig` or the policy document; the policy wins when both are present. A key's age
+// counts from when it was generated or imported into the keyring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaxKeyAge {
+    pub age_secs: u64,
+    #[serde(default = "default_warn_before")]
+    pub warn_before_secs: u64,
+    // Replace expired identities and re-encrypt their envelopes without waiting for an operator.
+    #[serde(default)]
+    pub auto_rotate: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyAgeState {
+    Current,
+    Expiring,
+    Expired,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyExpiry {
+    pub key_id: String,
+    pub label: String,
+    pub algorithm: KeyAlgorithm,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub state: KeyAgeState,
+    // Only identities whose secret is in the keyring can be replaced locally.
+    pub rotatable: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyExpiryReport {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<MaxKeyAge>,
+    pub keys: Vec<KeyExpiry>,
+}
+
+impl KeyExpiryReport {
+    pub fn auto_rotate(&self) -> bool {
+        self.policy.is_some_and(|policy| policy.auto_rotate)
+    }
+
+    pub fn in_state(&self, state: KeyAgeState) -> impl Iterator<Item = &KeyExpiry> {
+        self.keys.iter().filter(move |key| key.state == state)
+    }
+}
+
+// The key an identity was rotated to, and the one it retired.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRotation {
+    pub retired: KeyMetadata,
+    pub replacement: KeyMetadata,
+}
+
+fn default_warn_before() -> u64 {
+    14 * DAY
+}
+
+impl MaxKeyAge {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.age_secs == 0 {
+            return Err("max_key_age.age_secs must be greater than zero".into());
+        }
+        Ok(())
+    }
+
+    pub fn expires_at(&self, meta: &KeyMetadata) -> u64 {
+        meta.provenance.recorded_at.saturating_add(self.age_secs)
+    }
+
+    pub fn state(&self, meta: &KeyMetadata, now: u64) -> KeyAgeState {
+        let expires_at = self.expires_at(meta);
+        if now >= expires_at {
+            KeyAgeState::Expired
+        } else if now.saturating_add(self.warn_before_secs) >= expires_at {
+            KeyAgeState::Expiring
+        } else {
+            KeyAgeState::Current
+        }
+    }
+
+    pub fn expiry(&self, meta: &KeyMetadata, rotatable: bool, now: u64) -> KeyExpiry {
+        KeyExpiry {
+            key_id: meta.id.clone(),
+            label: meta.label.clone(),
+            algorithm: meta.algorithm,
+            created_at: meta.provenance.recorded_at,
+            expires_at: self.expires_at(meta),
+            state: self.state(meta, now),
+            rotatable,
+        }
+    }
+
+    // Refuses new encryptions to an expired key; opening existing envelopes is unaffected.
+    pub fn check_recipient(&self, meta: &KeyMetadata, now: u64) -> DGResult<()> {
+        if self.state(meta, now) == KeyAgeState::Expired {
+            return Err(DGError::PolicyDenied(format!(
+                "key {} ({}) passed its maximum age; rotate it before encrypting to it",
+                meta.label, meta.id
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,867 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::{engine::general_purpose, Engine as _};
+use ed25519_dalek::SigningKey;
+use hkdf::Hkdf;
+use pkcs8::der::asn1::OctetStringRef;
+use pkcs8::der::Decode;
+use pkcs8::{Document, ObjectIdentifier, PrivateKeyInfo, SecretDocument, SubjectPublicKeyInfoRef};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+use crate::api::{DGError, DGResult};
+use crate::engine::{open_payload_with, seal_payload_with};
+use crate::entropy::Entropy;
+use crate::fingerprint::Fingerprint;
+use crate::passphrase;
+use crate::secret::SecretKey;
+use crate::subkey::{self, KeyHierarchy, KeyPurpose, SubkeyCertificate, SUBKEY_PURPOSES};
+
+const KEYRING_FILE: &str = "keyring.json";
+const PRIVATE_KEY_LABEL: &str = "PRIVATE KEY";
+const PUBLIC_KEY_LABEL: &str = "PUBLIC KEY";
+const OID_X25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.110");
+const OID_ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+const SEALED_PREFIX: &str = "sealed:";
+const SEALING_INFO: &[u8] = b"data-guardian keyring secrets";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyAlgorithm {
+    X25519,
+    Ed25519,
+}
+
+pub const ALLOWED_ALGORITHMS: &[KeyAlgorithm] = &[KeyAlgorithm::X25519, KeyAlgorithm::Ed25519];
+
+impl KeyAlgorithm {
+    fn from_oid(oid: ObjectIdentifier) -> DGResult<Self> {
+        let algorithm = if oid == OID_X25519 {
+            KeyAlgorithm::X25519
+        } else if oid == OID_ED25519 {
+            KeyAlgorithm::Ed25519
+        } else {
+            return Err(DGError::Crypto(format!(
+                "unsupported key algorithm {oid}; allowed: x25519, ed25519"
+            )));
+        };
+        if !ALLOWED_ALGORITHMS.contains(&algorithm) {
+            return Err(DGError::Crypto(format!(
+                "key algorithm {algorithm:?} is not allowed"
+            )));
+        }
+        Ok(algorithm)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeySource {
+    Generated,
+    Imported,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyProvenance {
+    pub source: KeySource,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    pub recorded_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyMetadata {
+    pub id: String,
+    pub label: String,
+    pub algorithm: KeyAlgorithm,
+    pub public_key: String,
+    pub provenance: KeyProvenance,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<Fingerprint>,
+    // The key this one was rotated from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaces: Option<String>,
+    // Set on root identities and the subkeys they certify; flat keys have neither.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<KeyPurpose>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<SubkeyCertificate>,
+}
+
+impl KeyMetadata {
+    pub fn compute_fingerprint(&self) -> DGResult<Fingerprint> {
+        let public = general_purpose::STANDARD
+            .decode(&self.public_key)
+            .map_err(|err| DGError::Crypto(format!("invalid public key encoding: {err}")))?;
+        Ok(Fingerprint::compute(self.algorithm, &public))
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.purpose == Some(KeyPurpose::Root)
+    }
+
+    fn named(&self, name: &str) -> bool {
+        self.id == name || self.label == name
+    }
+}
+
+// `secret` is the base64 key, or `sealed:` and the base64 AES-GCM payload once the master key has a
+// passphrase.
+#[derive(Clone, Serialize, Deserialize)]
+struct StoredIdentity {
+    #[serde(flatten)]
+    meta: KeyMetadata,
+    secret: String,
+}
+
+impl fmt::Debug for StoredIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoredIdentity")
+            .field("meta", &self.meta)
+            .finish_non_exhaustive()
+    }
+}
+
+// Behind a passphrase, private keys are sealed under a key derived from the master key, which the
+// keyring only holds while the master key is unlocked.
+enum Sealing {
+    Plain,
+    Locked,
+    Unlocked(SecretKey),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct KeyringFile {
+    #[serde(default)]
+    identities: Vec<StoredIdentity>,
+    #[serde(default)]
+    contacts: Vec<KeyMetadata>,
+}
+
+pub struct Keyring {
+    path: PathBuf,
+    state: KeyringFile,
+    read_only: bool,
+    sealing: Sealing,
+}
+
+impl Keyring {
+    pub async fn load(data_dir: &Path) -> DGResult<Self> {
+        Self::open(data_dir, false).await
+    }
+
+    pub async fn load_read_only(data_dir: &Path) -> DGResult<Self> {
+        Self::open(data_dir, true).await
+    }
+
+    async fn open(data_dir: &Path, read_only: bool) -> DGResult<Self> {
+        let path = data_dir.join("keys").join(KEYRING_FILE);
+        let state = match fs::read(&path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|err| DGError::Config(format!("invalid keyring file: {err}")))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => KeyringFile::default(),
+            Err(err) => {
+                return Err(DGError::Config(format!("unable to read keyring: {err}")));
+            }
+        };
+        let sealing = if fs::try_exists(passphrase::wrapped_key_path(data_dir))
+            .await
+            .unwrap_or(false)
+        {
+            Sealing::Locked
+        } else {
+            Sealing::Plain
+        };
+        let mut keyring = Self {
+            path,
+            state,
+            read_only,
+            sealing,
+        };
+        if keyring.backfill_fingerprints() && !read_only {
+            if let Err(err) = keyring.persist().await {
+                warn!("unable to store key fingerprints: {err}");
+            }
+        }
+        Ok(keyring)
+    }
+
+    fn backfill_fingerprints(&mut self) -> bool {
+        let mut changed = false;
+        for meta in self
+            .state
+            .identities
+            .iter_mut()
+            .map(|identity| &mut identity.meta)
+            .chain(self.state.contacts.iter_mut())
+            .filter(|meta| meta.fingerprint.is_none())
+        {
+            if let Ok(fingerprint) = meta.compute_fingerprint() {
+                meta.fingerprint = Some(fingerprint);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    // Takes the sealing key while the master key is unlocked, and seals any secrets still stored in
+    // the clear, such as those written before the passphrase was set.
+    pub(crate) async fn unseal(
+        &mut self,
+        entropy: &Entropy,
+        master_key: &[u8; 32],
+    ) -> DGResult<()> {
+        let key = sealing_key(master_key)?;
+        let mut changed = false;
+        for identity in &mut self.state.identities {
+            if identity.secret.starts_with(SEALED_PREFIX) {
+                continue;
+            }
+            let secret = decode_secret(&identity.secret).ok_or_else(|| {
+                DGError::Crypto(format!("invalid secret for key {}", identity.meta.id))
+            })?;
+            identity.secret = seal_secret(entropy, &key, &identity.meta.id, &secret)?;
+            changed = true;
+        }
+        self.sealing = Sealing::Unlocked(key);
+        if changed && !self.read_only {
+            self.persist().await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn lock(&mut self) {
+        self.sealing = Sealing::Locked;
+    }
+
+    fn check_unsealed(&self) -> DGResult<()> {
+        match self.sealing {
+            Sealing::Locked => Err(DGError::Locked(
+                "unlock the master key with its passphrase first".into(),
+            )),
+            Sealing::Plain | Sealing::Unlocked(_) => Ok(()),
+        }
+    }
+
+    fn stored(
+        &self,
+        entropy: &Entropy,
+        meta: KeyMetadata,
+        secret: &[u8; 32],
+    ) -> DGResult<StoredIdentity> {
+        self.check_unsealed()?;
+        let secret = match &self.sealing {
+            Sealing::Unlocked(key) => seal_secret(entropy, key, &meta.id, secret)?,
+            Sealing::Plain | Sealing::Locked => general_purpose::STANDARD.encode(secret),
+        };
+        Ok(StoredIdentity { meta, secret })
+    }
+
+    fn secret_of(&self, identity: &StoredIdentity) -> Option<Zeroizing<[u8; 32]>> {
+        let Some(sealed) = identity.secret.strip_prefix(SEALED_PREFIX) else {
+            return decode_secret(&identity.secret);
+        };
+        let Sealing::Unlocked(key) = &self.sealing else {
+            return None;
+        };
+        let payload = general_purpose::STANDARD.decode(sealed).ok()?;
+        let opened =
+            Zeroizing::new(open_payload_with(key, &payload, identity.meta.id.as_bytes()).ok()?);
+        let secret: [u8; 32] = opened.as_slice().try_into().ok()?;
+        Some(Zeroizing::new(secret))
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&KeyMetadata> {
+        self.state
+            .identities
+            .iter()
+            .map(|identity| &identity.meta)
+            .chain(self.state.contacts.iter())
+            .find(|meta| meta.id == name || meta.label == name)
+    }
+
+    pub fn verify_fingerprint(&self, name: &str, presented: &str) -> DGResult<bool> {
+        let meta = self
+            .lookup(name)
+            .ok_or_else(|| DGError::Config(format!("key {name} not found in keyring")))?;
+        Ok(meta.compute_fingerprint()?.matches(presented))
+    }
+
+    pub fn identities(&self) -> Vec<KeyMetadata> {
+        self.state
+            .identities
+            .iter()
+            .map(|identity| identity.meta.clone())
+            .collect()
+    }
+
+    pub fn contacts(&self) -> &[KeyMetadata] {
+        &self.state.contacts
+    }
+
+    pub async fn import_private_key(&mut self, pem: &str, label: &str) -> DGResult<KeyMetadata> {
+        if self.lookup(label).is_some() {
+            return Err(DGError::Config(format!(
+                "a key labelled '{label}' is already in the keyring"
+            )));
+        }
+        let (pem_label, document) = SecretDocument::from_pem(pem)
+            .map_err(|err| DGError::Crypto(format!("invalid PEM private key: {err}")))?;
+        if pem_label != PRIVATE_KEY_LABEL {
+            return Err(DGError::Crypto(format!(
+                "expected an unencrypted PKCS#8 '{PRIVATE_KEY_LABEL}' block, found '{pem_label}'"
+            )));
+        }
+        let info = PrivateKeyInfo::try_from(document.as_bytes())
+            .map_err(|err| DGError::Crypto(format!("invalid PKCS#8 structure: {err}")))?;
+        let algorithm = KeyAlgorithm::from_oid(info.algorithm.oid)?;
+        let secret = OctetStringRef::from_der(info.private_key)
+            .map_err(|err| DGError::Crypto(format!("invalid private key encoding: {err}")))?;
+        let secret: Zeroizing<[u8; 32]> = Zeroizing::new(
+            secret
+                .as_bytes()
+                .try_into()
+                .map_err(|_| DGError::Crypto("private key must be 32 bytes".into()))?,
+        );
+
+        let public = public_key(algorithm, &secret);
+        if let Some(embedded) = info.public_key {
+            if embedded != public {
+                return Err(DGError::Crypto(
+                    "embedded public key does not match private key".into(),
+                ));
+            }
+        }
+
+        let meta = new_metadata(
+            label,
+            algorithm,
+            &public,
+            KeySource::Imported,
+            Some("pkcs8-pem"),
+        );
+        if self.find(&meta.id).is_some() {
+            return Err(DGError::Config(format!("key {} already present", meta.id)));
+        }
+        let stored = self.stored(&Entropy::Os, meta.clone(), &secret)?;
+        self.state.identities.push(stored);
+        self.persist().await?;
+        info!(key_id = %meta.id, algorithm = ?algorithm, "imported private key");
+        Ok(meta)
+    }
+
+    pub async fn import_public_key(&mut self, pem: &str, contact: &str) -> DGResult<KeyMetadata> {
+        let (algorithm, public) = parse_public_pem(pem)?;
+
+        let meta = new_metadata(
+            contact,
+            algorithm,
+            &public,
+            KeySource::Imported,
+            Some("spki-pem"),
+        );
+        if self.find(&meta.id).is_some() {
+            return Err(DGError::Config(format!("key {} already present", meta.id)));
+        }
+        self.state.contacts.push(meta.clone());
+        self.persist().await?;
+        info!(key_id = %meta.id, contact, "imported public key");
+        Ok(meta)
+    }
+
+    pub(crate) async fn generate_x25519(
+        &mut self,
+        entropy: &Entropy,
+        label: &str,
+    ) -> DGResult<KeyMetadata> {
+        if label.trim().is_empty() {
+            return Err(DGError::Config("an identity needs a label".into()));
+        }
+        if self.lookup(label).is_some() {
+            return Err(DGError::Config(format!(
+                "a key labelled '{label}' is already in the keyring"
+            )));
+        }
+        let secret = entropy.key();
+        let public = X25519PublicKey::from(&StaticSecret::from(*secret)).to_bytes();
+        let meta = new_metadata(
+            label,
+            KeyAlgorithm::X25519,
+            &public,
+            KeySource::Generated,
+            None,
+        );
+        let stored = self.stored(entropy, meta.clone(), &secret)?;
+        self.state.identities.push(stored);
+        self.persist().await?;
+        info!(key_id = %meta.id, label, "generated identity");
+        Ok(meta)
+    }
+
+    // Generates an Ed25519 root identity and one subkey per purpose, each labelled
+    // `<label>/<purpose>` and certified by the root. The root's secret signs certificates only.
+    pub(crate) async fn generate_hierarchy(
+        &mut self,
+        entropy: &Entropy,
+        label: &str,
+    ) -> DGResult<KeyHierarchy> {
+        if label.trim().is_empty() {
+            return Err(DGError::Config("an identity needs a label".into()));
+        }
+        let labels = SUBKEY_PURPOSES
+            .iter()
+            .map(|purpose| subkey_label(label, *purpose));
+        if let Some(taken) = std::iter::once(label.to_owned())
+            .chain(labels)
+            .find(|name| self.lookup(name).is_some())
+        {
+            return Err(DGError::Config(format!(
+                "a key labelled '{taken}' is already in the keyring"
+            )));
+        }
+        let root_secret = entropy.key();
+        let public = public_key(KeyAlgorithm::Ed25519, &root_secret);
+        let mut root = new_metadata(
+            label,
+            KeyAlgorithm::Ed25519,
+            &public,
+            KeySource::Generated,
+            None,
+        );
+        root.purpose = Some(KeyPurpose::Root);
+        let subkeys = SUBKEY_PURPOSES
+            .iter()
+            .map(|purpose| {
+                let (meta, secret) = new_subkey(
+                    entropy,
+                    &root_secret,
+                    &root.id,
+                    &subkey_label(label, *purpose),
+                    *purpose,
+                );
+                self.stored(entropy, meta, &secret)
+            })
+            .collect::<DGResult<Vec<_>>>()?;
+        let stored_root = self.stored(entropy, root.clone(), &root_secret)?;
+        self.state.identities.push(stored_root);
+        let subkey_meta = subkeys.iter().map(|stored| stored.meta.clone()).collect();
+        self.state.identities.extend(subkeys);
+        self.persist().await?;
+        info!(key_id = %root.id, label, "generated root identity and subkeys");
+        Ok(KeyHierarchy {
+            root,
+            subkeys: subkey_meta,
+        })
+    }
+
+    // The active subkey of `purpose` that the root identity `name` certified. Subkeys whose
+    // certificate does not verify against the root are ignored.
+    pub fn subkey(&self, name: &str, purpose: KeyPurpose) -> Option<&KeyMetadata> {
+        let root = self
+            .lookup(name)
+            .filter(|meta| meta.is_root() && meta.revoked_at.is_none())?;
+        let root_public: [u8; 32] = general_purpose::STANDARD
+            .decode(&root.public_key)
+            .ok()?
+            .try_into()
+            .ok()?;
+        self.keys().find(|meta| {
+            meta.purpose == Some(purpose)
+                && meta.revoked_at.is_none()
+                && meta
+                    .certificate
+                    .as_ref()
+                    .is_some_and(|certificate| certificate.root == root.id)
+                && subkey::is_certified(&root_public, meta)
+        })
+    }
+
+    // The root identity that certified key `id`, if it is a subkey.
+    pub fn root_of(&self, id: &str) -> Option<&str> {
+        self.find(id)?
+            .certificate
+            .as_ref()
+            .map(|certificate| certificate.root.as_str())
+    }
+
+    // A root identity's name stands for its subkey of `purpose`; any other name is kept as is.
+    fn resolve<'a>(&'a self, name: &'a str, purpose: KeyPurpose) -> &'a str {
+        self.subkey(name, purpose)
+            .map(|meta| meta.id.as_str())
+            .unwrap_or(name)
+    }
+
+    // Replaces an X25519 identity or a subkey with a fresh key under the same label and revokes the
+    // old one, so recipients named by label resolve to the new key. A root identity's name rotates
+    // its encryption subkey; replacement subkeys are certified by the same root.
+    pub(crate) async fn rotate(
+        &mut self,
+        entropy: &Entropy,
+        name: &str,
+    ) -> DGResult<(KeyMetadata, KeyMetadata)> {
+        let name = self.resolve(name, KeyPurpose::Encryption).to_owned();
+        let index = self
+            .state
+            .identities
+            .iter()
+            .position(|identity| {
+                (identity.meta.algorithm == KeyAlgorithm::X25519
+                    || identity.meta.certificate.is_some())
+                    && identity.meta.revoked_at.is_none()
+                    && identity.meta.named(&name)
+            })
+            .ok_or_else(|| {
+                DGError::Config(format!(
+                    "no active X25519 identity or subkey named {name} to rotate"
+                ))
+            })?;
+        self.check_unsealed()?;
+        let current = &self.state.identities[index].meta;
+        let (meta, secret) = match (current.purpose, &current.certificate) {
+            (Some(purpose), Some(certificate)) => {
+                let root_secret = self.root_secret(&certificate.root).ok_or_else(|| {
+                    DGError::Config(format!(
+                        "the root identity {} that certifies {name} is revoked or not held here",
+                        certificate.root
+                    ))
+                })?;
+                new_subkey(
+                    entropy,
+                    &root_secret,
+                    &certificate.root,
+                    &current.label,
+                    purpose,
+                )
+            }
+            _ => {
+                let secret = entropy.key();
+                let public = public_key(KeyAlgorithm::X25519, &secret);
+                let meta = new_metadata(
+                    &current.label,
+                    KeyAlgorithm::X25519,
+                    &public,
+                    KeySource::Generated,
+                    None,
+                );
+                (meta, secret)
+            }
+        };
+        let replacement = self.stored(entropy, meta, &secret)?;
+        let retired = &mut self.state.identities[index].meta;
+        retired.revoked_at = Some(unix_now());
+        let retired = retired.clone();
+        let mut replacement = replacement;
+        replacement.meta.replaces = Some(retired.id.clone());
+        let replacement_meta = replacement.meta.clone();
+        self.state.identities.push(replacement);
+        self.persist().await?;
+        info!(retired = %retired.id, replacement = %replacement_meta.id, "rotated identity");
+        Ok((retired, replacement_meta))
+    }
+
+    // Follows rotations from a retired key to the active X25519 key that replaced it.
+    pub(crate) fn x25519_successor(&self, id: &str) -> Option<(String, [u8; 32])> {
+        let mut current = id.to_owned();
+        // Each hop moves to a newer key, so the chain cannot be longer than the keyring.
+        for _ in 0..self.state.identities.len() {
+            let next = self
+                .state
+                .identities
+                .iter()
+                .map(|identity| &identity.meta)
+                .find(|meta| meta.replaces.as_deref() == Some(current.as_str()))?;
+            if next.revoked_at.is_none() {
+                return self.x25519_public(&next.id);
+            }
+            current = next.id.clone();
+        }
+        None
+    }
+
+    // Keys that are not revoked, and whether each one's secret is held here.
+    pub fn active_keys(&self) -> Vec<(KeyMetadata, bool)> {
+        self.state
+            .identities
+            .iter()
+            .map(|identity| (identity.meta.clone(), true))
+            .chain(self.state.contacts.iter().map(|meta| (meta.clone(), false)))
+            .filter(|(meta, _)| meta.revoked_at.is_none())
+            .collect()
+    }
+
+    pub(crate) fn x25519_secret(&self, id: &str) -> Option<Zeroizing<[u8; 32]>> {
+        self.state
+            .identities
+            .iter()
+            .filter(|identity| identity.meta.algorithm == KeyAlgorithm::X25519)
+            .find(|identity| identity.meta.id == id)
+            .and_then(|identity| self.secret_of(identity))
+    }
+
+    // A root identity signs with its signing subkey; the root's own secret never signs data.
+    pub(crate) fn ed25519_secret(&self, name: &str) -> Option<(String, Zeroizing<[u8; 32]>)> {
+        let name = self.resolve(name, KeyPurpose::Signing);
+        self.state
+            .identities
+            .iter()
+            .filter(|identity| {
+                identity.meta.algorithm == KeyAlgorithm::Ed25519
+                    && identity.meta.revoked_at.is_none()
+                    && !identity.meta.is_root()
+            })
+            .find(|identity| identity.meta.named(name))
+            .and_then(|identity| Some((identity.meta.id.clone(), self.secret_of(identity)?)))
+    }
+
+    pub(crate) fn ed25519_public(&self, name: &str) -> Option<(KeyMetadata, [u8; 32])> {
+        let name = self.resolve(name, KeyPurpose::Signing);
+        self.state
+            .identities
+            .iter()
+            .map(|identity| &identity.meta)
+            .chain(self.state.contacts.iter())
+            .filter(|meta| meta.algorithm == KeyAlgorithm::Ed25519)
+            .find(|meta| meta.id == name || meta.label == name)
+            .and_then(|meta| {
+                let public = general_purpose::STANDARD.decode(&meta.public_key).ok()?;
+                Some((meta.clone(), public.try_into().ok()?))
+            })
+    }
+
+    pub async fn revoke(&mut self, id: &str) -> DGResult<KeyMetadata> {
+        let meta = self
+            .state
+            .identities
+            .iter_mut()
+            .map(|identity| &mut identity.meta)
+            .chain(self.state.contacts.iter_mut())
+            .find(|meta| meta.id == id || meta.label == id)
+            .ok_or_else(|| DGError::Config(format!("key {id} not found in keyring")))?;
+        let now = unix_now();
+        if meta.revoked_at.is_none() {
+            meta.revoked_at = Some(now);
+        }
+        let meta = meta.clone();
+        // A revoked root takes every subkey it certified with it.
+        if meta.is_root() {
+            for subkey in self
+                .state
+                .identities
+                .iter_mut()
+                .map(|identity| &mut identity.meta)
+                .filter(|subkey| {
+                    subkey.revoked_at.is_none()
+                        && subkey
+                            .certificate
+                            .as_ref()
+                            .is_some_and(|certificate| certificate.root == meta.id)
+                })
+            {
+                subkey.revoked_at = Some(now);
+            }
+        }
+        self.persist().await?;
+        info!(key_id = %meta.id, "revoked key");
+        Ok(meta)
+    }
+
+    pub fn is_revoked(&self, id: &str) -> bool {
+        self.find(id)
+            .map(|meta| meta.revoked_at.is_some())
+            .unwrap_or(false)
+    }
+
+    pub(crate) fn x25519_public(&self, name: &str) -> Option<(String, [u8; 32])> {
+        let name = self.resolve(name, KeyPurpose::Encryption);
+        self.state
+            .identities
+            .iter()
+            .map(|identity| &identity.meta)
+            .chain(self.state.contacts.iter())
+            .filter(|meta| meta.algorithm == KeyAlgorithm::X25519 && meta.revoked_at.is_none())
+            .find(|meta| meta.id == name || meta.label == name)
+            .and_then(|meta| {
+                let public = general_purpose::STANDARD.decode(&meta.public_key).ok()?;
+                Some((meta.id.clone(), public.try_into().ok()?))
+            })
+    }
+
+    fn find(&self, id: &str) -> Option<&KeyMetadata> {
+        self.keys().find(|meta| meta.id == id)
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &KeyMetadata> {
+        self.state
+            .identities
+            .iter()
+            .map(|identity| &identity.meta)
+            .chain(self.state.contacts.iter())
+    }
+
+    fn root_secret(&self, id: &str) -> Option<Zeroizing<[u8; 32]>> {
+        self.state
+            .identities
+            .iter()
+            .find(|identity| {
+                identity.meta.id == id
+                    && identity.meta.is_root()
+                    && identity.meta.revoked_at.is_none()
+            })
+            .and_then(|identity| self.secret_of(identity))
+    }
+
+    async fn persist(&self) -> DGResult<()> {
+        if self.read_only {
+            return Err(DGError::ReadOnly(
+                "the keyring cannot be changed on a read-only data directory".into(),
+            ));
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|err| DGError::Config(format!("unable to create key directory: {err}")))?;
+        }
+        // The keyring holds private keys: written owner-only through a staging file, so a crash
+        // mid-write leaves the previous keyring in place.
+        let io = |err: std::io::Error| DGError::Config(format!("unable to write keyring: {err}"));
+        let json = Zeroizing::new(
+            serde_json::to_vec_pretty(&self.state)
+                .map_err(|err| DGError::Internal(format!("unable to encode keyring: {err}")))?,
+        );
+        let staging = self.path.with_extension("json.tmp");
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&staging).await.map_err(io)?;
+        file.write_all(&json).await.map_err(io)?;
+        file.sync_all().await.map_err(io)?;
+        drop(file);
+        fs::rename(&staging, &self.path).await.map_err(io)
+    }
+}
+
+// The decoded bytes are wiped along with the key copied out of them.
+fn decode_secret(encoded: &str) -> Option<Zeroizing<[u8; 32]>> {
+    let decoded = Zeroizing::new(general_purpose::STANDARD.decode(encoded).ok()?);
+    let secret: [u8; 32] = decoded.as_slice().try_into().ok()?;
+    Some(Zeroizing::new(secret))
+}
+
+fn sealing_key(master_key: &[u8; 32]) -> DGResult<SecretKey> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(None, master_key)
+        .expand(SEALING_INFO, &mut *key)
+        .map_err(|err| DGError::Crypto(format!("key derivation failed: {err}")))?;
+    Ok(SecretKey::new(&key))
+}
+
+// The key id is bound as associated data, so a sealed secret cannot be moved to another entry.
+fn seal_secret(entropy: &Entropy, key: &[u8; 32], id: &str, secret: &[u8; 32]) -> DGResult<String> {
+    let payload = seal_payload_with(entropy, key, secret, id.as_bytes())?;
+    Ok(format!(
+        "{SEALED_PREFIX}{}",
+        general_purpose::STANDARD.encode(payload)
+    ))
+}
+
+fn new_metadata(
+    label: &str,
+    algorithm: KeyAlgorithm,
+    public: &[u8],
+    source: KeySource,
+    format: Option<&str>,
+) -> KeyMetadata {
+    KeyMetadata {
+        id: key_id(public),
+        label: label.to_owned(),
+        algorithm,
+        public_key: general_purpose::STANDARD.encode(public),
+        provenance: KeyProvenance {
+            source,
+            format: format.map(str::to_owned),
+            recorded_at: unix_now(),
+        },
+        revoked_at: None,
+        fingerprint: Some(Fingerprint::compute(algorithm, public)),
+        replaces: None,
+        purpose: None,
+        certificate: None,
+    }
+}
+
+fn new_subkey(
+    entropy: &Entropy,
+    root_secret: &[u8; 32],
+    root_id: &str,
+    label: &str,
+    purpose: KeyPurpose,
+) -> (KeyMetadata, Zeroizing<[u8; 32]>) {
+    let secret = entropy.key();
+    let algorithm = purpose.algorithm();
+    let public = public_key(algorithm, &secret);
+    let mut meta = new_metadata(label, algorithm, &public, KeySource::Generated, None);
+    meta.purpose = Some(purpose);
+    meta.certificate = Some(subkey::certify(
+        root_secret,
+        root_id,
+        purpose,
+        &public,
+        meta.provenance.recorded_at,
+    ));
+    (meta, secret)
+}
+
+fn subkey_label(root: &str, purpose: KeyPurpose) -> String {
+    format!("{root}/{purpose}")
+}
+
+fn public_key(algorithm: KeyAlgorithm, secret: &[u8; 32]) -> [u8; 32] {
+    match algorithm {
+        KeyAlgorithm::X25519 => X25519PublicKey::from(&StaticSecret::from(*secret)).to_bytes(),
+        KeyAlgorithm::Ed25519 => SigningKey::from_bytes(secret).verifying_key().to_bytes(),
+    }
+}
+
+pub fn parse_public_pem(pem: &str) -> DGResult<(KeyAlgorithm, [u8; 32])> {
+    let (pem_label, document) = Document::from_pem(pem)
+        .map_err(|err| DGError::Crypto(format!("invalid PEM public key: {err}")))?;
+    if pem_label != PUBLIC_KEY_LABEL {
+        return Err(DGError::Crypto(format!(
+            "expected a '{PUBLIC_KEY_LABEL}' block, found '{pem_label}'"
+        )));
+    }
+    let info = SubjectPublicKeyInfoRef::try_from(document.as_bytes())
+        .map_err(|err| DGError::Crypto(format!("invalid SubjectPublicKeyInfo: {err}")))?;
+    let algorithm = KeyAlgorithm::from_oid(info.algorithm.oid)?;
+    let public: [u8; 32] = info
+        .subject_public_key
+        .raw_bytes()
+        .try_into()
+        .map_err(|_| DGError::Crypto("public key must be 32 bytes".into()))?;
+    if algorithm == KeyAlgorithm::Ed25519 {
+        ed25519_dalek::VerifyingKey::from_bytes(&public)
+            .map_err(|err| DGError::Crypto(format!("invalid ed25519 public key: {err}")))?;
+    }
+    Ok((algorithm, public))
+}
+
+pub(crate) fn key_id(public: &[u8]) -> String {
+    hex::encode(&Sha256::digest(public)[..8])
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
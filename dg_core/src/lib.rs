@@ -1,5 +1,80 @@
 pub mod api;
+pub mod audit;
+pub mod chunking;
+pub mod device;
+pub mod doctor;
 mod engine;
-mod policy;
+mod entropy;
+pub mod envelope;
+pub mod fingerprint;
+pub mod grant;
+pub mod identity;
+pub mod index;
+pub mod integrity;
+pub mod journal;
+pub mod key_age;
+pub mod keyring;
+pub mod license;
+pub mod management;
+pub mod metadata;
+pub mod offline;
+pub mod pairing;
+pub mod passphrase;
+pub mod path_encoding;
+pub mod policy;
+pub mod policy_cache;
+pub mod policy_hook;
+pub mod preview;
+pub mod provenance;
+pub mod resource_attrs;
+pub mod rpc_error;
+pub mod secret;
+pub mod selftest;
+pub mod signature;
+pub mod state;
+pub mod subkey;
+pub mod threshold;
+pub mod trust;
+pub mod warmup;
+pub mod wrap;
 
-pub use api::{new_default, DGConfig, DGError, DGResult, DataGuardian, EncryptRequest, Envelope};
+pub use api::{
+    new_default, DGConfig, DGError, DGResult, DataGuardian, EncryptRequest, Envelope, LoadedProfile,
+};
+pub use audit::{AuditEvent, AuditFilter};
+pub use chunking::{
+    ChunkSpan, ChunkTable, ChunkingParams, DedupStats, MerkleProof, RangeVerification,
+};
+pub use device::{DeviceIdentity, DeviceKey, Enrollment};
+pub use doctor::{DoctorReport, Finding, Severity};
+pub use envelope::{CipherAlgorithm, EnvelopeHeader};
+pub use fingerprint::Fingerprint;
+pub use grant::{AccessGrant, GrantAction, GrantRequest, SignedGrant};
+pub use identity::Identity;
+pub use integrity::{IntegrityComponent, IntegrityIssue, IntegrityReport};
+pub use journal::{JournalAppend, JournalInfo};
+pub use key_age::{KeyAgeState, KeyExpiry, KeyExpiryReport, KeyRotation, MaxKeyAge};
+pub use keyring::{KeyAlgorithm, KeyMetadata};
+pub use license::{License, LicenseState, LicenseStatus, LicenseVerifier};
+pub use management::{LockState, ManagementAction, ManagementCommand, SignedCommand};
+pub use metadata::{CustomMetadata, MetadataFilter};
+pub use offline::{OfflineRequest, OfflineResponse};
+pub use pairing::{PairedDevice, PairingOffer, PairingResponse, UnlockApproval, UnlockChallenge};
+pub use passphrase::{KdfParams, KeyProtection};
+pub use policy::{
+    AccessRequest, Decision, DecisionExplanation, DecisionSource, PolicyEngine, PolicyFormat,
+    SimulationReport,
+};
+pub use policy_cache::{CacheSettings, PolicyCacheStats};
+pub use policy_hook::{FailMode, HookOutcome, HookResult, PolicyHook};
+pub use preview::Preview;
+pub use provenance::{ProvenanceEntry, RekeyContext};
+pub use resource_attrs::{DriveType, ResourceAttributes, ResourceCondition, SecurityZone};
+pub use rpc_error::RpcError;
+pub use secret::SecretKey;
+pub use selftest::SelfTestReport;
+pub use state::{StateBackend, StateStore};
+pub use subkey::{KeyHierarchy, KeyPurpose, SubkeyCertificate};
+pub use threshold::{KeyShare, ThresholdSpec};
+pub use trust::{NewSigner, TrustRole, TrustStore, TrustedSigner};
+pub use warmup::{WarmupReport, WarmupStep};
@@ -0,0 +1,223 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::{engine::general_purpose, Engine as _};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::api::{DGError, DGResult};
+
+const LICENSE_DIR: &str = "license";
+const LICENSE_FILE: &str = "license.json";
+const DAY: u64 = 24 * 60 * 60;
+const VENDOR_KEY: &str = match option_env!("DG_LICENSE_PUBLIC_KEY") {
+    Some(key) => key,
+    None => "zLEt/csJNQrtCe4+GIktSaBhjl9+3wSF0C/HO59Qy6o=",
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedLicense {
+    pub payload: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct License {
+    pub id: String,
+    pub licensee: String,
+    pub tier: String,
+    #[serde(default)]
+    pub features: Vec<String>,
+    pub issued_at: u64,
+    pub expires_at: u64,
+    #[serde(default)]
+    pub grace_days: u64,
+}
+
+impl License {
+    pub fn grace_until(&self) -> u64 {
+        self.expires_at
+            .saturating_add(self.grace_days.saturating_mul(DAY))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LicenseState {
+    Unlicensed,
+    Active,
+    Grace,
+    Expired,
+    Invalid,
+}
+
+impl LicenseState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LicenseState::Unlicensed => "unlicensed",
+            LicenseState::Active => "active",
+            LicenseState::Grace => "grace",
+            LicenseState::Expired => "expired",
+            LicenseState::Invalid => "invalid",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LicenseStatus {
+    pub state: LicenseState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<License>,
+    pub features: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl LicenseStatus {
+    pub fn unlicensed() -> Self {
+        Self {
+            state: LicenseState::Unlicensed,
+            license: None,
+            features: Vec::new(),
+            detail: None,
+        }
+    }
+
+    fn invalid(detail: String) -> Self {
+        Self {
+            state: LicenseState::Invalid,
+            license: None,
+            features: Vec::new(),
+            detail: Some(detail),
+        }
+    }
+
+    pub fn allows(&self, feature: &str) -> bool {
+        self.features.iter().any(|enabled| enabled == feature)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LicenseVerifier {
+    keys: Vec<VerifyingKey>,
+}
+
+impl LicenseVerifier {
+    pub fn vendor() -> Self {
+        Self::from_base64(&[VENDOR_KEY]).expect("embedded license key is valid")
+    }
+
+    pub fn new(keys: Vec<VerifyingKey>) -> Self {
+        Self { keys }
+    }
+
+    pub fn from_base64(keys: &[&str]) -> DGResult<Self> {
+        let keys = keys
+            .iter()
+            .map(|encoded| {
+                let bytes: [u8; 32] = general_purpose::STANDARD
+                    .decode(encoded.trim())
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| {
+                        DGError::Config("license keys must be 32-byte base64 values".into())
+                    })?;
+                VerifyingKey::from_bytes(&bytes)
+                    .map_err(|err| DGError::Config(format!("invalid license key: {err}")))
+            })
+            .collect::<DGResult<Vec<_>>>()?;
+        Ok(Self { keys })
+    }
+
+    pub fn verify(&self, raw: &[u8]) -> DGResult<License> {
+        let signed: SignedLicense = serde_json::from_slice(raw)
+            .map_err(|err| DGError::Config(format!("invalid license file: {err}")))?;
+        let payload = general_purpose::STANDARD
+            .decode(signed.payload.trim())
+            .map_err(|err| DGError::Config(format!("invalid license payload: {err}")))?;
+        let signature: [u8; 64] = general_purpose::STANDARD
+            .decode(signed.signature.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| DGError::Crypto("license signature must be 64 bytes".into()))?;
+        let signature = Signature::from_bytes(&signature);
+        if !self
+            .keys
+            .iter()
+            .any(|key| key.verify(&payload, &signature).is_ok())
+        {
+            return Err(DGError::Crypto(
+                "license signature does not match a trusted key".into(),
+            ));
+        }
+        serde_json::from_slice(&payload)
+            .map_err(|err| DGError::Config(format!("invalid license contents: {err}")))
+    }
+
+    pub fn evaluate(&self, raw: &[u8], now: u64) -> LicenseStatus {
+        let license = match self.verify(raw) {
+            Ok(license) => license,
+            Err(err) => return LicenseStatus::invalid(err.to_string()),
+        };
+        let (state, features) = if now < license.issued_at {
+            (LicenseState::Invalid, Vec::new())
+        } else if now < license.expires_at {
+            (LicenseState::Active, license.features.clone())
+        } else if now < license.grace_until() {
+            (LicenseState::Grace, license.features.clone())
+        } else {
+            (LicenseState::Expired, Vec::new())
+        };
+        LicenseStatus {
+            state,
+            detail: (state == LicenseState::Invalid)
+                .then(|| "license is not yet valid".to_string()),
+            license: Some(license),
+            features,
+        }
+    }
+}
+
+pub fn license_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(LICENSE_DIR).join(LICENSE_FILE)
+}
+
+pub async fn install_license(
+    data_dir: &Path,
+    raw: &[u8],
+    verifier: &LicenseVerifier,
+) -> DGResult<LicenseStatus> {
+    let status = verifier.evaluate(raw, unix_now());
+    if matches!(status.state, LicenseState::Invalid | LicenseState::Expired) {
+        return Err(DGError::Config(format!(
+            "license rejected: {}",
+            status.detail.as_deref().unwrap_or("license has expired")
+        )));
+    }
+    let path = license_path(data_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|err| DGError::Config(format!("unable to create license directory: {err}")))?;
+    }
+    fs::write(&path, raw)
+        .await
+        .map_err(|err| DGError::Internal(format!("unable to store license: {err}")))?;
+    Ok(status)
+}
+
+pub async fn load_license_status(data_dir: &Path, verifier: &LicenseVerifier) -> LicenseStatus {
+    match fs::read(license_path(data_dir)).await {
+        Ok(raw) => verifier.evaluate(&raw, unix_now()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => LicenseStatus::unlicensed(),
+        Err(err) => LicenseStatus::invalid(format!("unable to read license: {err}")),
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
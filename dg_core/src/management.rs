@@ -0,0 +1,255 @@
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::api::{DGError, DGResult};
+use crate::audit::{AuditEvent, AuditLog};
+use crate::device;
+use crate::keyring::Keyring;
+use crate::trust::{self, TrustRole, TrustStore, TrustedSigner};
+
+pub const MANAGEMENT_DIR: &str = "management";
+const LOCK_FILE: &str = "lock.json";
+const APPLIED_FILE: &str = "applied.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ManagementAction {
+    Lock,
+    Unlock,
+    WipeKeys,
+    ForceRotation { key_id: String },
+}
+
+impl ManagementAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ManagementAction::Lock => "lock",
+            ManagementAction::Unlock => "unlock",
+            ManagementAction::WipeKeys => "wipe_keys",
+            ManagementAction::ForceRotation { .. } => "force_rotation",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManagementCommand {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    #[serde(flatten)]
+    pub action: ManagementAction,
+    pub issued_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedCommand {
+    pub payload: String,
+    pub signature: String,
+}
+
+impl SignedCommand {
+    pub fn new(command: &ManagementCommand, signature: &[u8]) -> DGResult<Self> {
+        let payload = serde_json::to_vec(command)
+            .map_err(|err| DGError::Internal(format!("unable to encode command: {err}")))?;
+        Ok(Self {
+            payload: general_purpose::STANDARD.encode(payload),
+            signature: general_purpose::STANDARD.encode(signature),
+        })
+    }
+
+    pub fn command(&self) -> DGResult<ManagementCommand> {
+        serde_json::from_slice(&self.payload_bytes()?)
+            .map_err(|err| DGError::Config(format!("unreadable management command: {err}")))
+    }
+
+    // Commands must come from a `management` signer and be addressed to this device or to every device.
+    pub fn verify(
+        &self,
+        store: &TrustStore,
+        device_id: Option<&str>,
+        now: u64,
+    ) -> DGResult<(ManagementCommand, TrustedSigner)> {
+        let payload = self.payload_bytes()?;
+        let signature = general_purpose::STANDARD
+            .decode(self.signature.trim())
+            .map_err(|err| DGError::Config(format!("command signature is not base64: {err}")))?;
+        let signer = store
+            .verify(TrustRole::Management, &payload, &signature)?
+            .cloned()
+            .ok_or_else(|| {
+                DGError::Crypto(
+                    "management command is not signed by a trusted management signer".into(),
+                )
+            })?;
+        let command = self.command()?;
+        if command.id.trim().is_empty() {
+            return Err(DGError::Config("management command has no id".into()));
+        }
+        if let Some(target) = &command.device {
+            if device_id != Some(target.as_str()) {
+                return Err(DGError::PolicyDenied(format!(
+                    "management command {} is addressed to device {target}",
+                    command.id
+                )));
+            }
+        }
+        if command
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= now)
+        {
+            return Err(DGError::PolicyDenied(format!(
+                "management command {} has expired",
+                command.id
+            )));
+        }
+        Ok((command, signer))
+    }
+
+    fn payload_bytes(&self) -> DGResult<Vec<u8>> {
+        general_purpose::STANDARD
+            .decode(self.payload.trim())
+            .map_err(|err| DGError::Config(format!("command payload is not base64: {err}")))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    pub locked_by: String,
+    #[serde(default)]
+    pub reason: String,
+    pub locked_at: u64,
+    #[serde(default)]
+    pub keys_wiped: bool,
+}
+
+pub async fn lock_state(data_dir: &Path) -> DGResult<Option<LockState>> {
+    let path = management_dir(data_dir).join(LOCK_FILE);
+    let raw = match fs::read(&path).await {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(DGError::Config(format!(
+                "unable to read {}: {err}",
+                path.display()
+            )))
+        }
+    };
+    // An unreadable lock file still locks the data directory.
+    Ok(Some(serde_json::from_slice(&raw).unwrap_or(LockState {
+        command: None,
+        locked_by: "unknown".into(),
+        reason: format!("{} is unreadable", path.display()),
+        locked_at: 0,
+        keys_wiped: false,
+    })))
+}
+
+pub async fn write_lock(data_dir: &Path, state: &LockState) -> DGResult<()> {
+    let encoded = serde_json::to_vec_pretty(state)
+        .map_err(|err| DGError::Internal(format!("unable to encode lock state: {err}")))?;
+    write_atomic(&management_dir(data_dir).join(LOCK_FILE), &encoded).await
+}
+
+// Returns false when the command id was applied before, so replayed commands are ignored.
+pub async fn mark_applied(data_dir: &Path, id: &str) -> DGResult<bool> {
+    let path = management_dir(data_dir).join(APPLIED_FILE);
+    let mut applied: Vec<String> = match fs::read(&path).await {
+        Ok(raw) => serde_json::from_slice(&raw)
+            .map_err(|err| DGError::Config(format!("unreadable {}: {err}", path.display())))?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => {
+            return Err(DGError::Config(format!(
+                "unable to read {}: {err}",
+                path.display()
+            )))
+        }
+    };
+    if applied.iter().any(|existing| existing == id) {
+        return Ok(false);
+    }
+    applied.push(id.to_owned());
+    let encoded = serde_json::to_vec_pretty(&applied)
+        .map_err(|err| DGError::Internal(format!("unable to encode applied commands: {err}")))?;
+    write_atomic(&path, &encoded).await?;
+    Ok(true)
+}
+
+// Unlocking runs before the engine starts, since a locked data directory refuses to initialize.
+pub async fn unlock(
+    data_dir: &Path,
+    signed: &SignedCommand,
+    now: u64,
+) -> DGResult<(ManagementCommand, TrustedSigner)> {
+    let Some(lock) = lock_state(data_dir).await? else {
+        return Err(DGError::Config(format!(
+            "{} is not locked",
+            data_dir.display()
+        )));
+    };
+    if lock.keys_wiped {
+        return Err(DGError::PolicyDenied(
+            "keys were wiped on this data directory; restore it from a backup instead".into(),
+        ));
+    }
+    let keyring = Keyring::load_read_only(data_dir).await?;
+    let store = trust::load_verified(data_dir, &keyring).await?;
+    let device_id = device::load_identity(data_dir)
+        .await?
+        .map(|identity| identity.device_id);
+    let (command, signer) = signed.verify(&store, device_id.as_deref(), now)?;
+    if command.action != ManagementAction::Unlock {
+        return Err(DGError::Config(format!(
+            "management command {} is a {} command, not unlock",
+            command.id,
+            command.action.as_str()
+        )));
+    }
+    if !mark_applied(data_dir, &command.id).await? {
+        return Err(DGError::PolicyDenied(format!(
+            "management command {} was already applied",
+            command.id
+        )));
+    }
+    AuditLog::open(data_dir)
+        .await?
+        .with_device(device_id.clone())
+        .record(
+            AuditEvent::new(
+                &format!("management:{}", signer.id),
+                "management.unlock",
+                device_id.as_deref().unwrap_or("*"),
+                "allow",
+            )
+            .with_details(serde_json::json!({ "command": command.id, "reason": command.reason })),
+        )
+        .await?;
+    let path = management_dir(data_dir).join(LOCK_FILE);
+    fs::remove_file(&path)
+        .await
+        .map_err(|err| DGError::Config(format!("unable to remove {}: {err}", path.display())))?;
+    Ok((command, signer))
+}
+
+fn management_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(MANAGEMENT_DIR)
+}
+
+async fn write_atomic(path: &Path, contents: &[u8]) -> DGResult<()> {
+    let io =
+        |err: std::io::Error| DGError::Config(format!("unable to write {}: {err}", path.display()));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await.map_err(io)?;
+    }
+    let staging = path.with_extension("json.tmp");
+    fs::write(&staging, contents).await.map_err(io)?;
+    fs::rename(&staging, path).await.map_err(io)
+}
@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use jsonschema::JSONSchema;
+use tokio::fs;
+
+use crate::api::{DGError, DGResult};
+
+pub const METADATA_FIELD: &str = "metadata";
+pub const SCHEMA_FILE: &str = "metadata.schema.json";
+const MAX_REPORTED_ERRORS: usize = 3;
+
+pub type CustomMetadata = BTreeMap<String, serde_json::Value>;
+
+pub struct MetadataSchema {
+    compiled: JSONSchema,
+}
+
+impl MetadataSchema {
+    pub fn compile(schema: &serde_json::Value) -> DGResult<Self> {
+        let compiled = JSONSchema::compile(schema)
+            .map_err(|err| DGError::Config(format!("invalid metadata schema: {err}")))?;
+        Ok(Self { compiled })
+    }
+
+    // The schema describes the whole map, so `required` and `additionalProperties` apply to
+    // envelopes encrypted without any metadata as well.
+    pub fn validate(&self, metadata: &CustomMetadata) -> DGResult<()> {
+        let instance = serde_json::json!(metadata);
+        if let Err(errors) = self.compiled.validate(&instance) {
+            let errors: Vec<String> = errors
+                .take(MAX_REPORTED_ERRORS)
+                .map(|err| match err.instance_path.to_string() {
+                    path if path.is_empty() => err.to_string(),
+                    path => format!("{path}: {err}"),
+                })
+                .collect();
+            return Err(DGError::Config(format!(
+                "envelope metadata does not match the schema: {}",
+                errors.join("; ")
+            )));
+        }
+        Ok(())
+    }
+}
+
+// The schema is optional; without one any well-formed map is accepted.
+pub async fn load_schema(data_dir: &Path) -> DGResult<Option<MetadataSchema>> {
+    let path = data_dir.join(SCHEMA_FILE);
+    let bytes = match fs::read(&path).await {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(DGError::Config(format!(
+                "unable to read {}: {err}",
+                path.display()
+            )))
+        }
+    };
+    let schema: serde_json::Value = serde_json::from_slice(&bytes)
+        .map_err(|err| DGError::Config(format!("invalid metadata schema: {err}")))?;
+    MetadataSchema::compile(&schema).map(Some)
+}
+
+pub fn check_keys(metadata: &CustomMetadata) -> DGResult<()> {
+    match metadata
+        .keys()
+        .find(|key| key.is_empty() || key.trim() != key.as_str() || key.contains('='))
+    {
+        Some(key) => Err(DGError::Config(format!(
+            "metadata field '{key}' must be non-empty, without surrounding spaces or '='"
+        ))),
+        None => Ok(()),
+    }
+}
+
+pub fn custom_metadata(meta: &serde_json::Value) -> CustomMetadata {
+    meta.get(METADATA_FIELD)
+        .and_then(|fields| serde_json::from_value(fields.clone()).ok())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataFilter {
+    pub field: String,
+    pub value: Option<String>,
+}
+
+impl MetadataFilter {
+    // `field=value` matches an exact value; a bare `field` matches any envelope that sets it.
+    pub fn parse(filter: &str) -> DGResult<Self> {
+        let (field, value) = match filter.split_once('=') {
+            Some((field, value)) => (field.trim(), Some(value.trim().to_owned())),
+            None => (filter.trim(), None),
+        };
+        if field.is_empty() {
+            return Err(DGError::Config(format!(
+                "metadata filter '{filter}' needs a field name"
+            )));
+        }
+        Ok(Self {
+            field: field.to_owned(),
+            value,
+        })
+    }
+
+    pub fn matches(&self, metadata: &CustomMetadata) -> bool {
+        match (metadata.get(&self.field), &self.value) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(serde_json::Value::Array(items)), Some(wanted)) => {
+                items.iter().any(|item| value_matches(item, wanted))
+            }
+            (Some(found), Some(wanted)) => value_matches(found, wanted),
+        }
+    }
+}
+
+fn value_matches(value: &serde_json::Value, wanted: &str) -> bool {
+    match value {
+        serde_json::Value::String(text) => text == wanted,
+        other => {
+            serde_json::from_str::<serde_json::Value>(wanted).is_ok_and(|parsed| parsed == *other)
+        }
+    }
+}
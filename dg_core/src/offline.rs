@@ -0,0 +1,200 @@
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+use crate::api::{DGError, DGResult, Envelope};
+use crate::engine::{open_payload_with, seal_payload_with};
+use crate::entropy::Entropy;
+use crate::grant::envelope_digest;
+use crate::key_age::DAY;
+use crate::wrap::{self, KeyWrap, WrapKind};
+
+pub const OFFLINE_VERSION: u8 = 1;
+pub const REQUEST_TTL_SECS: u64 = 7 * DAY;
+pub const REQUEST_EXTENSION: &str = "dgreq";
+pub const RESPONSE_EXTENSION: &str = "dgresp";
+const REPLY_CONTEXT: &str = "data-guardian offline reply v1";
+
+// What the online machine carries to the air-gapped one: the envelope's key wraps and a one-time
+// reply key. The reply key's secret travels along sealed under the online master key, so only the
+// machine that wrote the request can use the response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineRequest {
+    pub version: u8,
+    pub id: String,
+    pub envelope: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    pub wraps: Vec<KeyWrap>,
+    pub reply_key: String,
+    pub challenge: String,
+    pub reply_secret: String,
+    pub issued_at: u64,
+    pub expires_at: u64,
+}
+
+// The air-gapped machine's answer: the file key wrapped to the request's reply key, with the
+// request's identifiers and sealed reply secret echoed back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineResponse {
+    pub version: u8,
+    pub request: String,
+    pub envelope: String,
+    pub challenge: String,
+    pub expires_at: u64,
+    pub key_id: String,
+    pub reply_secret: String,
+    pub wrap: KeyWrap,
+    pub answered_at: u64,
+}
+
+impl OfflineRequest {
+    // The wraps the air-gapped machine may hold a key for: never the online master wrap, and not
+    // custodian shares, which need several keys.
+    pub(crate) fn new(
+        entropy: &Entropy,
+        master: &[u8; 32],
+        env: &Envelope,
+        wraps: Vec<KeyWrap>,
+        labels: Vec<String>,
+        now: u64,
+    ) -> DGResult<Self> {
+        let wraps: Vec<KeyWrap> = wraps
+            .into_iter()
+            .filter(|entry| !matches!(entry.kind, WrapKind::Master | WrapKind::Share))
+            .collect();
+        if wraps.is_empty() {
+            return Err(DGError::Config(
+                "envelope has no key wraps another machine could open".into(),
+            ));
+        }
+        let secret = entropy.key();
+        let mut id = [0u8; 16];
+        entropy.fill(&mut id);
+        let mut challenge = [0u8; 16];
+        entropy.fill(&mut challenge);
+        let mut request = Self {
+            version: OFFLINE_VERSION,
+            id: hex::encode(id),
+            envelope: envelope_digest(env),
+            file_name: None,
+            labels,
+            wraps,
+            reply_key: general_purpose::STANDARD
+                .encode(X25519PublicKey::from(&StaticSecret::from(*secret)).as_bytes()),
+            challenge: hex::encode(challenge),
+            reply_secret: String::new(),
+            issued_at: now,
+            expires_at: now.saturating_add(REQUEST_TTL_SECS),
+        };
+        let aad = reply_aad(
+            &request.id,
+            &request.envelope,
+            &request.challenge,
+            request.expires_at,
+        );
+        request.reply_secret =
+            general_purpose::STANDARD.encode(seal_payload_with(entropy, master, &*secret, &aad)?);
+        Ok(request)
+    }
+
+    pub(crate) fn check(&self, now: u64) -> DGResult<()> {
+        if self.version != OFFLINE_VERSION {
+            return Err(DGError::Config(format!(
+                "unsupported offline request version {}",
+                self.version
+            )));
+        }
+        if self.expires_at <= now {
+            return Err(DGError::PolicyDenied(format!(
+                "offline request {} has expired",
+                self.id
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn answer(
+        &self,
+        entropy: &Entropy,
+        key_id: &str,
+        file_key: &[u8; 32],
+        now: u64,
+    ) -> DGResult<OfflineResponse> {
+        let reply_key: [u8; 32] = general_purpose::STANDARD
+            .decode(&self.reply_key)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| DGError::Crypto("reply key must be 32 base64 bytes".into()))?;
+        Ok(OfflineResponse {
+            version: OFFLINE_VERSION,
+            request: self.id.clone(),
+            envelope: self.envelope.clone(),
+            challenge: self.challenge.clone(),
+            expires_at: self.expires_at,
+            key_id: key_id.to_owned(),
+            reply_secret: self.reply_secret.clone(),
+            wrap: wrap::wrap_x25519(entropy, WrapKind::Offline, &reply_key, file_key)?,
+            answered_at: now,
+        })
+    }
+}
+
+impl OfflineResponse {
+    // Recovers the file key on the machine that wrote the request. A response edited in transit,
+    // answered for another envelope or past its request's expiry is refused.
+    pub(crate) fn file_key(
+        &self,
+        master: &[u8; 32],
+        env: &Envelope,
+        now: u64,
+    ) -> DGResult<Zeroizing<[u8; 32]>> {
+        if self.version != OFFLINE_VERSION {
+            return Err(DGError::Config(format!(
+                "unsupported offline response version {}",
+                self.version
+            )));
+        }
+        if self.envelope != envelope_digest(env) {
+            return Err(DGError::Crypto(format!(
+                "offline response {} was answered for a different envelope",
+                self.request
+            )));
+        }
+        if self.expires_at <= now {
+            return Err(DGError::PolicyDenied(format!(
+                "offline request {} has expired",
+                self.request
+            )));
+        }
+        let sealed = general_purpose::STANDARD
+            .decode(&self.reply_secret)
+            .map_err(|err| DGError::Crypto(format!("reply secret is not base64: {err}")))?;
+        let aad = reply_aad(
+            &self.request,
+            &self.envelope,
+            &self.challenge,
+            self.expires_at,
+        );
+        let opened = Zeroizing::new(open_payload_with(master, &sealed, &aad).map_err(|_| {
+            DGError::Crypto(format!(
+                "offline response {} does not belong to a request from this machine",
+                self.request
+            ))
+        })?);
+        let secret: Zeroizing<[u8; 32]> = Zeroizing::new(
+            opened
+                .as_slice()
+                .try_into()
+                .map_err(|_| DGError::Crypto("reply secret must be 32 bytes".into()))?,
+        );
+        wrap::unwrap_x25519(&secret, &self.wrap)
+    }
+}
+
+fn reply_aad(id: &str, envelope: &str, challenge: &str, expires_at: u64) -> Vec<u8> {
+    format!("{REPLY_CONTEXT}\n{id}\n{envelope}\n{challenge}\n{expires_at}").into_bytes()
+}
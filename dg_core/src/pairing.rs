@@ -0,0 +1,457 @@
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose, Engine as _};
+use ed25519_dalek::SigningKey;
+use hkdf::Hkdf;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+use crate::api::{DGError, DGResult, Envelope};
+use crate::device::DEVICE_DIR;
+use crate::engine::{open_payload_with, seal_payload_with};
+use crate::entropy::Entropy;
+use crate::grant::envelope_digest;
+use crate::keyring::key_id;
+use crate::signature;
+
+pub const PAIRING_SCHEME: &str = "dg-pair";
+const PAIRING_VERSION: &str = "v1";
+const PAIRED_FILE: &str = "paired.json";
+const PAIRING_CONTEXT: &str = "data-guardian device pairing v1";
+const UNLOCK_CONTEXT: &str = "data-guardian unlock approval v1";
+const CHANNEL_INFO: &[u8] = b"data-guardian/pairing-channel/v1";
+const CODE_BYTES: usize = 16;
+
+// What the desktop shows as a QR code. The code only travels through the QR code, never over the
+// channel, so a relay that sees the channel cannot pair itself in the user's place.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PairingOffer {
+    pub device_id: String,
+    pub channel_key: String,
+    pub code: String,
+    pub expires_at: u64,
+}
+
+impl PairingOffer {
+    pub fn to_uri(&self) -> String {
+        format!(
+            "{PAIRING_SCHEME}:{PAIRING_VERSION}?device={}&key={}&code={}&expires={}",
+            self.device_id, self.channel_key, self.code, self.expires_at
+        )
+    }
+
+    pub fn parse_uri(uri: &str) -> DGResult<Self> {
+        let query = uri
+            .trim()
+            .strip_prefix(&format!("{PAIRING_SCHEME}:{PAIRING_VERSION}?"))
+            .ok_or_else(|| DGError::Config(format!("not a {PAIRING_SCHEME} link: {uri}")))?;
+        let field = |name: &str| {
+            query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+                .map(str::to_owned)
+                .ok_or_else(|| DGError::Config(format!("pairing link is missing {name}")))
+        };
+        Ok(Self {
+            device_id: field("device")?,
+            channel_key: field("key")?,
+            code: field("code")?,
+            expires_at: field("expires")?
+                .parse()
+                .map_err(|err| DGError::Config(format!("invalid pairing expiry: {err}")))?,
+        })
+    }
+
+    // Where the paired device posts its response; derived from the code without revealing it.
+    pub fn pairing_id(&self) -> String {
+        hex::encode(&Sha256::digest(self.code.as_bytes())[..8])
+    }
+}
+
+// An offer together with the channel secret that stays on the desktop.
+pub struct PendingPairing {
+    pub offer: PairingOffer,
+    secret: Zeroizing<[u8; 32]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PairingResponse {
+    pub name: String,
+    pub signing_key: String,
+    pub channel_key: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PairedDevice {
+    pub id: String,
+    pub name: String,
+    pub signing_key: String,
+    pub paired_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredPairing {
+    #[serde(flatten)]
+    device: PairedDevice,
+    channel: String,
+}
+
+// What travels over the channel: the request id in the clear and the message sealed under the
+// pairing's channel key, bound to that id.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedMessage {
+    pub request_id: String,
+    pub sealed: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnlockChallenge {
+    pub request_id: String,
+    pub device_id: String,
+    pub envelope: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    pub subject: String,
+    pub nonce: String,
+    pub issued_at: u64,
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnlockApproval {
+    pub request_id: String,
+    pub approved: bool,
+    pub signature: String,
+}
+
+pub fn offer(device_id: &str, ttl_secs: u64, now: u64) -> PendingPairing {
+    let entropy = Entropy::default();
+    let secret = entropy.key();
+    let mut code = [0u8; CODE_BYTES];
+    entropy.fill(&mut code);
+    PendingPairing {
+        offer: PairingOffer {
+            device_id: device_id.to_owned(),
+            channel_key: hex::encode(
+                X25519PublicKey::from(&StaticSecret::from(*secret)).as_bytes(),
+            ),
+            code: hex::encode(code),
+            expires_at: now.saturating_add(ttl_secs),
+        },
+        secret,
+    }
+}
+
+// The paired device's side: proves it read the QR code and derives the shared channel key.
+pub fn respond(
+    offer: &PairingOffer,
+    name: &str,
+    signing_secret: &[u8; 32],
+    channel_secret: &[u8; 32],
+) -> DGResult<(PairingResponse, Zeroizing<[u8; 32]>)> {
+    let signing_key = hex::encode(
+        SigningKey::from_bytes(signing_secret)
+            .verifying_key()
+            .to_bytes(),
+    );
+    let channel_key =
+        hex::encode(X25519PublicKey::from(&StaticSecret::from(*channel_secret)).as_bytes());
+    let transcript = pairing_transcript(offer, name, &signing_key, &channel_key);
+    let response = PairingResponse {
+        name: name.to_owned(),
+        signature: general_purpose::STANDARD.encode(signature::sign(signing_secret, &transcript)),
+        signing_key,
+        channel_key,
+    };
+    let channel = channel_key_for(channel_secret, &offer.channel_key, &offer.code)?;
+    Ok((response, channel))
+}
+
+impl PendingPairing {
+    pub fn accept(
+        &self,
+        response: &PairingResponse,
+        now: u64,
+    ) -> DGResult<(PairedDevice, Zeroizing<[u8; 32]>)> {
+        if now >= self.offer.expires_at {
+            return Err(DGError::PolicyDenied(
+                "the pairing code has expired; show a new one".into(),
+            ));
+        }
+        if response.name.trim().is_empty() {
+            return Err(DGError::Config("a paired device needs a name".into()));
+        }
+        let public = decode_key(&response.signing_key, "signing key")?;
+        let transcript = pairing_transcript(
+            &self.offer,
+            &response.name,
+            &response.signing_key,
+            &response.channel_key,
+        );
+        if !signature::verify(
+            &public,
+            &transcript,
+            &decode_signature(&response.signature)?,
+        )? {
+            return Err(DGError::Crypto(
+                "the pairing response was not signed for this pairing code".into(),
+            ));
+        }
+        let channel = channel_key_for(&self.secret, &response.channel_key, &self.offer.code)?;
+        Ok((
+            PairedDevice {
+                id: key_id(&public),
+                name: response.name.trim().to_owned(),
+                signing_key: response.signing_key.clone(),
+                paired_at: now,
+            },
+            channel,
+        ))
+    }
+}
+
+impl UnlockChallenge {
+    pub fn new(
+        device_id: &str,
+        envelope: &Envelope,
+        file_name: Option<String>,
+        subject: String,
+        now: u64,
+        timeout_secs: u64,
+    ) -> Self {
+        let entropy = Entropy::default();
+        let mut nonce = [0u8; CODE_BYTES];
+        entropy.fill(&mut nonce);
+        let mut request = [0u8; 8];
+        entropy.fill(&mut request);
+        Self {
+            request_id: hex::encode(request),
+            device_id: device_id.to_owned(),
+            envelope: envelope_digest(envelope),
+            file_name,
+            subject,
+            nonce: hex::encode(nonce),
+            issued_at: now,
+            expires_at: now.saturating_add(timeout_secs),
+        }
+    }
+}
+
+impl UnlockApproval {
+    // The paired device's answer; a refusal is signed too, so it cannot be forged either.
+    pub fn sign(
+        challenge: &UnlockChallenge,
+        approved: bool,
+        signing_secret: &[u8; 32],
+    ) -> DGResult<Self> {
+        let message = approval_message(challenge, approved)?;
+        Ok(Self {
+            request_id: challenge.request_id.clone(),
+            approved,
+            signature: general_purpose::STANDARD.encode(signature::sign(signing_secret, &message)),
+        })
+    }
+
+    pub fn verify(&self, challenge: &UnlockChallenge, device: &PairedDevice) -> DGResult<bool> {
+        if self.request_id != challenge.request_id {
+            return Ok(false);
+        }
+        let public = decode_key(&device.signing_key, "signing key")?;
+        signature::verify(
+            &public,
+            &approval_message(challenge, self.approved)?,
+            &decode_signature(&self.signature)?,
+        )
+    }
+}
+
+pub fn seal(
+    channel: &[u8; 32],
+    request_id: &str,
+    message: &impl Serialize,
+) -> DGResult<SealedMessage> {
+    let plaintext = Zeroizing::new(
+        serde_json::to_vec(message)
+            .map_err(|err| DGError::Internal(format!("unable to encode message: {err}")))?,
+    );
+    let sealed = seal_payload_with(
+        &Entropy::default(),
+        channel,
+        &plaintext,
+        request_id.as_bytes(),
+    )?;
+    Ok(SealedMessage {
+        request_id: request_id.to_owned(),
+        sealed: general_purpose::STANDARD.encode(sealed),
+    })
+}
+
+pub fn open<T: DeserializeOwned>(channel: &[u8; 32], message: &SealedMessage) -> DGResult<T> {
+    let sealed = general_purpose::STANDARD
+        .decode(&message.sealed)
+        .map_err(|err| DGError::Crypto(format!("sealed message is not base64: {err}")))?;
+    let plaintext = Zeroizing::new(open_payload_with(
+        channel,
+        &sealed,
+        message.request_id.as_bytes(),
+    )?);
+    serde_json::from_slice(&plaintext)
+        .map_err(|err| DGError::Crypto(format!("unreadable sealed message: {err}")))
+}
+
+pub async fn load_paired(data_dir: &Path) -> DGResult<Vec<PairedDevice>> {
+    Ok(read_paired(data_dir)
+        .await?
+        .into_iter()
+        .map(|stored| stored.device)
+        .collect())
+}
+
+// Paired devices with their channel keys, for publishing challenges and opening answers.
+pub async fn paired_channels(
+    data_dir: &Path,
+) -> DGResult<Vec<(PairedDevice, Zeroizing<[u8; 32]>)>> {
+    read_paired(data_dir)
+        .await?
+        .into_iter()
+        .map(|stored| {
+            let channel = general_purpose::STANDARD
+                .decode(&stored.channel)
+                .ok()
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                .ok_or_else(|| {
+                    DGError::Config(format!(
+                        "the channel key of paired device {} is unreadable",
+                        stored.device.id
+                    ))
+                })?;
+            Ok((stored.device, Zeroizing::new(channel)))
+        })
+        .collect()
+}
+
+// Pairing the same device again replaces its earlier channel key.
+pub async fn record_pairing(
+    data_dir: &Path,
+    device: PairedDevice,
+    channel: &[u8; 32],
+) -> DGResult<()> {
+    let mut paired = read_paired(data_dir).await?;
+    paired.retain(|stored| stored.device.id != device.id);
+    paired.push(StoredPairing {
+        device,
+        channel: general_purpose::STANDARD.encode(channel),
+    });
+    write_paired(data_dir, &paired).await
+}
+
+pub async fn remove_pairing(data_dir: &Path, id: &str) -> DGResult<PairedDevice> {
+    let mut paired = read_paired(data_dir).await?;
+    let index = paired
+        .iter()
+        .position(|stored| stored.device.id == id || stored.device.name == id)
+        .ok_or_else(|| DGError::Config(format!("no paired device named {id}")))?;
+    let removed = paired.remove(index).device;
+    write_paired(data_dir, &paired).await?;
+    Ok(removed)
+}
+
+fn pairing_transcript(
+    offer: &PairingOffer,
+    name: &str,
+    signing_key: &str,
+    channel_key: &str,
+) -> Vec<u8> {
+    format!(
+        "{PAIRING_CONTEXT}\n{}\n{}\n{}\n{name}\n{signing_key}\n{channel_key}",
+        offer.device_id, offer.channel_key, offer.code
+    )
+    .into_bytes()
+}
+
+fn approval_message(challenge: &UnlockChallenge, approved: bool) -> DGResult<Vec<u8>> {
+    let mut message = format!("{UNLOCK_CONTEXT}\n{approved}\n").into_bytes();
+    message.extend(
+        serde_json::to_vec(challenge)
+            .map_err(|err| DGError::Internal(format!("unable to encode challenge: {err}")))?,
+    );
+    Ok(message)
+}
+
+fn channel_key_for(secret: &[u8; 32], peer: &str, code: &str) -> DGResult<Zeroizing<[u8; 32]>> {
+    let peer = X25519PublicKey::from(decode_key(peer, "channel key")?);
+    let shared = StaticSecret::from(*secret).diffie_hellman(&peer);
+    let mut channel = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(Some(code.as_bytes()), shared.as_bytes())
+        .expand(CHANNEL_INFO, channel.as_mut())
+        .map_err(|err| DGError::Crypto(format!("channel key derivation failed: {err}")))?;
+    Ok(channel)
+}
+
+fn decode_key(encoded: &str, what: &str) -> DGResult<[u8; 32]> {
+    hex::decode(encoded)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| DGError::Crypto(format!("{what} must be 32 hex-encoded bytes")))
+}
+
+fn decode_signature(encoded: &str) -> DGResult<Vec<u8>> {
+    general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|err| DGError::Crypto(format!("signature is not base64: {err}")))
+}
+
+async fn read_paired(data_dir: &Path) -> DGResult<Vec<StoredPairing>> {
+    let path = paired_path(data_dir);
+    match fs::read(&path).await {
+        Ok(raw) => serde_json::from_slice(&raw)
+            .map_err(|err| DGError::Config(format!("unreadable {}: {err}", path.display()))),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(DGError::Config(format!(
+            "unable to read {}: {err}",
+            path.display()
+        ))),
+    }
+}
+
+// The file holds channel keys, so it is written owner-only like the device key.
+async fn write_paired(data_dir: &Path, paired: &[StoredPairing]) -> DGResult<()> {
+    let path = paired_path(data_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|err| DGError::Config(format!("unable to create device directory: {err}")))?;
+    }
+    let staging = path.with_extension("json.tmp");
+    let encoded = Zeroizing::new(
+        serde_json::to_vec_pretty(paired)
+            .map_err(|err| DGError::Internal(format!("unable to encode paired devices: {err}")))?,
+    );
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options
+        .open(&staging)
+        .await
+        .map_err(|err| DGError::Config(format!("unable to write {}: {err}", staging.display())))?;
+    file.write_all(&encoded)
+        .await
+        .map_err(|err| DGError::Config(format!("unable to write {}: {err}", staging.display())))?;
+    file.sync_all()
+        .await
+        .map_err(|err| DGError::Config(format!("unable to flush {}: {err}", staging.display())))?;
+    fs::rename(&staging, &path)
+        .await
+        .map_err(|err| DGError::Config(format!("unable to write {}: {err}", path.display())))
+}
+
+fn paired_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(DEVICE_DIR).join(PAIRED_FILE)
+}
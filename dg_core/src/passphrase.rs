@@ -0,0 +1,171 @@
+use std::path::{Path, PathBuf};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use zeroize::Zeroizing;
+
+use crate::api::{DGError, DGResult};
+use crate::engine::{open_payload_with, seal_payload_with};
+use crate::entropy::Entropy;
+
+pub const WRAPPED_KEY_FILE: &str = "master.key.wrapped";
+pub const MIN_PASSPHRASE_CHARS: usize = 8;
+const KEY_DIR: &str = "keys";
+const KDF: &str = "argon2id";
+const SALT_LEN: usize = 16;
+const WRAP_AAD: &[u8] = b"data-guardian/master-key/v1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum KeyProtection {
+    #[default]
+    None,
+    Passphrase(KdfParams),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrappedKey {
+    pub kdf: String,
+    pub params: KdfParams,
+    pub salt: String,
+    pub wrapped: String,
+}
+
+impl WrappedKey {
+    pub(crate) fn seal(
+        entropy: &Entropy,
+        master: &[u8; 32],
+        passphrase: &str,
+        params: KdfParams,
+    ) -> DGResult<Self> {
+        check_passphrase(passphrase)?;
+        let mut salt = [0u8; SALT_LEN];
+        entropy.fill(&mut salt);
+        let kek = derive_kek(passphrase, &salt, params)?;
+        Ok(Self {
+            kdf: KDF.to_owned(),
+            params,
+            salt: general_purpose::STANDARD.encode(salt),
+            wrapped: general_purpose::STANDARD
+                .encode(seal_payload_with(entropy, &kek, master, WRAP_AAD)?),
+        })
+    }
+
+    pub(crate) fn open(&self, passphrase: &str) -> DGResult<Zeroizing<[u8; 32]>> {
+        if self.kdf != KDF {
+            return Err(DGError::Config(format!(
+                "unsupported key derivation '{}'",
+                self.kdf
+            )));
+        }
+        let decode = |field: &str| {
+            general_purpose::STANDARD
+                .decode(field)
+                .map_err(|err| DGError::Config(format!("wrapped master key is corrupt: {err}")))
+        };
+        let kek = derive_kek(passphrase, &decode(&self.salt)?, self.params)?;
+        // AES-GCM cannot tell a wrong passphrase from a damaged file; the former is far likelier.
+        let master = Zeroizing::new(
+            open_payload_with(&kek, &decode(&self.wrapped)?, WRAP_AAD)
+                .map_err(|_| DGError::Crypto("incorrect passphrase".into()))?,
+        );
+        let key: [u8; 32] = master
+            .as_slice()
+            .try_into()
+            .map_err(|_| DGError::Config("wrapped master key has the wrong length".into()))?;
+        Ok(Zeroizing::new(key))
+    }
+}
+
+pub(crate) fn check_passphrase(passphrase: &str) -> DGResult<()> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+        return Err(DGError::Config(format!(
+            "the passphrase must be at least {MIN_PASSPHRASE_CHARS} characters"
+        )));
+    }
+    Ok(())
+}
+
+fn derive_kek(passphrase: &str, salt: &[u8], params: KdfParams) -> DGResult<Zeroizing<[u8; 32]>> {
+    let params = Params::new(
+        params.memory_kib,
+        params.iterations,
+        params.parallelism,
+        Some(32),
+    )
+    .map_err(|err| DGError::Config(format!("invalid key derivation parameters: {err}")))?;
+    let mut kek = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, kek.as_mut())
+        .map_err(|err| DGError::Crypto(format!("key derivation failed: {err}")))?;
+    Ok(kek)
+}
+
+pub fn wrapped_key_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(KEY_DIR).join(WRAPPED_KEY_FILE)
+}
+
+// A passphrase-protected directory opens locked. With protection configured but no wrapped key
+// yet, the first unlock sets the passphrase.
+pub async fn requires_unlock(data_dir: &Path, protection: KeyProtection) -> bool {
+    matches!(protection, KeyProtection::Passphrase(_))
+        || fs::try_exists(wrapped_key_path(data_dir))
+            .await
+            .unwrap_or(false)
+}
+
+pub async fn load(data_dir: &Path) -> DGResult<Option<WrappedKey>> {
+    let path = wrapped_key_path(data_dir);
+    let bytes = match fs::read(&path).await {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(DGError::Config(format!(
+                "unable to read {}: {err}",
+                path.display()
+            )))
+        }
+    };
+    serde_json::from_slice(&bytes)
+        .map(Some)
+        .map_err(|err| DGError::Config(format!("{} is corrupt: {err}", path.display())))
+}
+
+pub(crate) async fn store(data_dir: &Path, wrapped: &WrappedKey) -> DGResult<()> {
+    let path = wrapped_key_path(data_dir);
+    let io =
+        |err: std::io::Error| DGError::Config(format!("unable to write {}: {err}", path.display()));
+    let json = serde_json::to_vec_pretty(wrapped)
+        .map_err(|err| DGError::Internal(format!("unable to encode wrapped key: {err}")))?;
+    let staging = path.with_extension("wrapped.tmp");
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(&staging).await.map_err(io)?;
+    file.write_all(&json).await.map_err(io)?;
+    file.sync_all().await.map_err(io)?;
+    drop(file);
+    fs::rename(&staging, &path).await.map_err(io)
+}
@@ -1,20 +1,188 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use base64::{engine::general_purpose, Engine as _};
 use globset::{Glob, GlobMatcher};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tokio::sync::RwLock;
+use tracing::warn;
+
+use crate::api::{DGError, DGResult};
+use crate::key_age::MaxKeyAge;
+use crate::policy_cache::{self, CacheSettings, Cached, DecisionCache, PolicyCacheStats};
+use crate::policy_hook::{
+    self, CommandHook, FailMode, HookOutcome, HookRequest, PolicyHook, DEFAULT_HOOK_TIMEOUT_MS,
+};
+use crate::resource_attrs::{CompiledCondition, ResourceAttributes, ResourceCondition};
+use crate::state::StateStore;
+
+const MAX_POLICY_BYTES: usize = 1 << 20;
+const MAX_POLICY_ENTRIES: usize = 4096;
+const MAX_PATTERN_LEN: usize = 1024;
+const QUOTA_PREFIX: &str = "quota/";
+const LOCKOUT_PREFIX: &str = "lockout/";
+
+pub const POLICY_FILES: [&str; 3] = ["policy.json", "policy.yaml", "policy.yml"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PolicyFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+impl PolicyFormat {
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("yaml" | "yml") => PolicyFormat::Yaml,
+            _ => PolicyFormat::Json,
+        }
+    }
+}
+
+pub async fn find_policy(data_dir: &Path) -> Option<PathBuf> {
+    for name in POLICY_FILES {
+        let path = data_dir.join(name);
+        if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            return Some(path);
+        }
+    }
+    None
+}
 
 #[derive(Clone)]
 pub struct PolicyEngine {
     inner: ArcPolicy,
+    cache: Arc<DecisionCache>,
+    hooks: Arc<std::sync::RwLock<HookRegistry>>,
 }
 
 type ArcPolicy = Arc<RwLock<CompiledPolicy>>;
+type HookRegistry = HashMap<String, Arc<dyn PolicyHook>>;
 
 #[derive(Default)]
 struct CompiledPolicy {
     rules: Vec<CompiledRule>,
     default_allow: bool,
+    escrow: Option<EscrowKey>,
+    quotas: Vec<CompiledQuota>,
+    lockout: Option<LockoutPolicy>,
+    max_key_age: Option<MaxKeyAge>,
+    cache: CacheSettings,
+    hooks: HookRegistry,
+}
+
+#[derive(Debug, Clone)]
+pub struct EscrowKey {
+    pub public_key: [u8; 32],
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessRequest {
+    pub subject: String,
+    pub action: String,
+    pub resource: String,
+    #[serde(default, skip_serializing_if = "ResourceAttributes::is_empty")]
+    pub attributes: ResourceAttributes,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect: Option<Decision>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    Allow,
+    Deny,
+}
+
+impl Decision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Deny => "deny",
+        }
+    }
+}
+
+impl From<bool> for Decision {
+    fn from(allowed: bool) -> Self {
+        if allowed {
+            Self::Allow
+        } else {
+            Self::Deny
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchedRule {
+    pub index: usize,
+    pub subject: String,
+    pub action: String,
+    pub resource: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<ResourceCondition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecisionSource {
+    Policy,
+    Cache,
+    StaleCache,
+    Fallback,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecisionExplanation {
+    pub decision: Decision,
+    pub source: DecisionSource,
+    // The rule that decided; absent for the default and for cached decisions.
+    pub rule: Option<MatchedRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hooks: Vec<HookOutcome>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fail_mode: Option<FailMode>,
+}
+
+impl DecisionExplanation {
+    pub fn allowed(&self) -> bool {
+        self.decision == Decision::Allow
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SimulationResult {
+    pub request: AccessRequest,
+    pub decision: Decision,
+    pub rule: Option<MatchedRule>,
+    pub as_expected: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SimulationReport {
+    pub default_allow: bool,
+    pub allowed: usize,
+    pub denied: usize,
+    pub unexpected: usize,
+    pub results: Vec<SimulationResult>,
+}
+
+impl SimulationReport {
+    pub fn passed(&self) -> bool {
+        self.unexpected == 0
+    }
 }
 
 #[derive(Clone)]
@@ -22,7 +190,25 @@ struct CompiledRule {
     subject: GlobMatcher,
     action: GlobMatcher,
     resource: GlobMatcher,
+    when: Option<CompiledCondition>,
     effect: PolicyEffect,
+    hook: Option<RuleHook>,
+}
+
+#[derive(Clone)]
+struct RuleHook {
+    name: String,
+    timeout: Duration,
+    on_failure: FailMode,
+}
+
+#[derive(Clone)]
+struct CompiledQuota {
+    subject: GlobMatcher,
+    action: GlobMatcher,
+    key: String,
+    limit: u64,
+    window_secs: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +217,41 @@ struct PolicyDocument {
     default_allow: bool,
     #[serde(default)]
     rules: Vec<PolicyRule>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    escrow: Option<EscrowPolicy>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    quotas: Vec<QuotaRule>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lockout: Option<LockoutPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_key_age: Option<MaxKeyAge>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cache: Option<CacheSettings>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    hooks: BTreeMap<String, CommandHook>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QuotaRule {
+    subject: String,
+    action: String,
+    limit: u64,
+    #[serde(default = "default_quota_window")]
+    window_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockoutPolicy {
+    pub max_failures: u64,
+    #[serde(default = "default_cooldown")]
+    pub cooldown_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EscrowPolicy {
+    public_key: String,
+    #[serde(default)]
+    label: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,8 +259,16 @@ struct PolicyRule {
     subject: String,
     action: String,
     resource: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    when: Option<ResourceCondition>,
     #[serde(default)]
     effect: PolicyEffect,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hook: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timeout_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    on_failure: Option<FailMode>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Copy, PartialEq, Eq, Default)]
@@ -54,66 +283,694 @@ fn default_allow_true() -> bool {
     true
 }
 
+fn default_quota_window() -> u64 {
+    86_400
+}
+
+fn default_cooldown() -> u64 {
+    300
+}
+
 impl PolicyEngine {
     pub async fn from_bytes(bytes: Vec<u8>) -> Result<Self, String> {
-        let document: PolicyDocument = serde_json::from_slice(&bytes)
-            .map_err(|err| format!("invalid policy format: {err}"))?;
-        Self::from_document(document).await
+        Self::parse(&bytes)
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        Self::parse_as(bytes, PolicyFormat::Json)
+    }
+
+    pub fn parse_as(bytes: &[u8], format: PolicyFormat) -> Result<Self, String> {
+        Self::from_document(read_document(bytes, format)?)
+    }
+
+    pub fn convert(
+        document: &[u8],
+        from: PolicyFormat,
+        to: PolicyFormat,
+        comments_from: Option<&str>,
+    ) -> Result<Vec<u8>, String> {
+        let doc = read_document(document, from)?;
+        CompiledPolicy::compile(doc.clone())?;
+        match to {
+            PolicyFormat::Json => {
+                let mut bytes = serde_json::to_vec_pretty(&doc)
+                    .map_err(|err| format!("unable to encode policy: {err}"))?;
+                bytes.push(b'\n');
+                Ok(bytes)
+            }
+            PolicyFormat::Yaml => {
+                let rendered = serde_yaml::to_string(&doc)
+                    .map_err(|err| format!("unable to encode policy: {err}"))?;
+                Ok(match comments_from {
+                    Some(existing) => restore_comments(existing, &rendered),
+                    None => rendered,
+                }
+                .into_bytes())
+            }
+        }
     }
 
     pub async fn default() -> Result<Self, String> {
         Self::from_document(PolicyDocument {
             default_allow: true,
             rules: vec![],
+            escrow: None,
+            quotas: vec![],
+            lockout: None,
+            max_key_age: None,
+            cache: None,
+            hooks: BTreeMap::new(),
         })
-        .await
     }
 
-    async fn from_document(doc: PolicyDocument) -> Result<Self, String> {
+    pub fn simulate(
+        document: &[u8],
+        requests: Vec<AccessRequest>,
+    ) -> Result<SimulationReport, String> {
+        Self::simulate_as(document, PolicyFormat::Json, requests)
+    }
+
+    pub fn simulate_as(
+        document: &[u8],
+        format: PolicyFormat,
+        requests: Vec<AccessRequest>,
+    ) -> Result<SimulationReport, String> {
+        let compiled = CompiledPolicy::compile(read_document(document, format)?)?;
+        let mut report = SimulationReport {
+            default_allow: compiled.default_allow,
+            ..Default::default()
+        };
+        for request in requests {
+            let fired = compiled.first_match(
+                &[&request.subject],
+                &request.action,
+                &request.resource,
+                &request.attributes,
+            );
+            let decision = match fired.map(|(_, rule)| rule.effect) {
+                Some(PolicyEffect::Allow) => Decision::Allow,
+                Some(PolicyEffect::Deny) => Decision::Deny,
+                None if compiled.default_allow => Decision::Allow,
+                None => Decision::Deny,
+            };
+            match decision {
+                Decision::Allow => report.allowed += 1,
+                Decision::Deny => report.denied += 1,
+            }
+            let as_expected = request.expect.map(|expected| expected == decision);
+            if as_expected == Some(false) {
+                report.unexpected += 1;
+            }
+            report.results.push(SimulationResult {
+                rule: fired.map(|(index, rule)| rule.describe(index)),
+                request,
+                decision,
+                as_expected,
+            });
+        }
+        Ok(report)
+    }
+
+    fn from_document(doc: PolicyDocument) -> Result<Self, String> {
+        Ok(Self {
+            inner: Arc::new(RwLock::new(CompiledPolicy::compile(doc)?)),
+            cache: Arc::default(),
+            hooks: Arc::default(),
+        })
+    }
+
+    // Registered hooks take precedence over command hooks of the same name and survive reloads.
+    pub fn register_hook(&self, name: impl Into<String>, hook: Arc<dyn PolicyHook>) {
+        self.hooks
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(name.into(), hook);
+    }
+
+    pub async fn evaluate(
+        &self,
+        subject: &str,
+        action: &str,
+        resource: &str,
+    ) -> Result<bool, String> {
+        self.evaluate_any(&[subject], action, resource).await
+    }
+
+    pub async fn evaluate_any<S: AsRef<str>>(
+        &self,
+        subjects: &[S],
+        action: &str,
+        resource: &str,
+    ) -> Result<bool, String> {
+        Ok(self.explain_any(subjects, action, resource).await.allowed())
+    }
+
+    pub async fn explain_any<S: AsRef<str>>(
+        &self,
+        subjects: &[S],
+        action: &str,
+        resource: &str,
+    ) -> DecisionExplanation {
+        self.explain_with(subjects, action, resource, &ResourceAttributes::default())
+            .await
+    }
+
+    // Rules with a `when` block only match when the attributes satisfy it; without attributes
+    // they never do. A hook failure is answered from a stale cache entry when one exists,
+    // otherwise by the rule's fail mode. Neither kind of fallback is cached.
+    pub async fn explain_with<S: AsRef<str>>(
+        &self,
+        subjects: &[S],
+        action: &str,
+        resource: &str,
+        attributes: &ResourceAttributes,
+    ) -> DecisionExplanation {
+        let guard = self.inner.read().await;
+        let key = policy_cache::key(subjects, action, resource, attributes);
+        let now = Instant::now();
+        let stale = match self.cache.lookup(&key, now) {
+            Cached::Fresh(allowed) => {
+                return DecisionExplanation {
+                    decision: allowed.into(),
+                    source: DecisionSource::Cache,
+                    rule: None,
+                    hooks: Vec::new(),
+                    fail_mode: None,
+                }
+            }
+            Cached::Stale(allowed) => Some(allowed),
+            Cached::Missing => None,
+        };
+        let explanation = guard
+            .explain(&self.hooks, subjects, action, resource, attributes)
+            .await;
+        match (explanation.source, stale) {
+            (DecisionSource::Fallback, Some(allowed)) => {
+                warn!(
+                    action,
+                    resource, "policy hook failed; serving a stale policy decision"
+                );
+                self.cache.record_stale_hit();
+                DecisionExplanation {
+                    decision: allowed.into(),
+                    source: DecisionSource::StaleCache,
+                    fail_mode: None,
+                    ..explanation
+                }
+            }
+            (DecisionSource::Fallback, None) => {
+                warn!(
+                    action,
+                    resource,
+                    decision = explanation.decision.as_str(),
+                    "policy hook failed; applying the rule's fail mode"
+                );
+                self.cache.record_miss();
+                explanation
+            }
+            _ => {
+                self.cache.record_miss();
+                self.cache
+                    .insert(guard.cache, key, explanation.allowed(), now);
+                explanation
+            }
+        }
+    }
+
+    // Swaps in a freshly loaded policy for every clone of this engine and drops cached decisions.
+    pub async fn reload(&self, fresh: PolicyEngine) {
+        let compiled = std::mem::take(&mut *fresh.inner.write().await);
+        *self.inner.write().await = compiled;
+        self.cache.invalidate();
+    }
+
+    pub fn cache_stats(&self) -> PolicyCacheStats {
+        self.cache.stats()
+    }
+
+    // Whether any rule has a `when` block, so callers can skip reading attributes otherwise.
+    pub async fn uses_resource_attributes(&self) -> bool {
+        self.inner
+            .read()
+            .await
+            .rules
+            .iter()
+            .any(|rule| rule.when.is_some())
+    }
+
+    pub async fn warm_up(&self) -> usize {
+        let guard = self.inner.read().await;
+        let matchers = guard
+            .rules
+            .iter()
+            .flat_map(|rule| [&rule.subject, &rule.action, &rule.resource])
+            .chain(
+                guard
+                    .quotas
+                    .iter()
+                    .flat_map(|quota| [&quota.subject, &quota.action]),
+            );
+        let mut primed = 0;
+        for matcher in matchers {
+            std::hint::black_box(matcher.is_match("system/warm-up"));
+            primed += 1;
+        }
+        primed
+    }
+
+    pub async fn escrow(&self) -> Option<EscrowKey> {
+        self.inner.read().await.escrow.clone()
+    }
+
+    pub async fn max_key_age(&self) -> Option<MaxKeyAge> {
+        self.inner.read().await.max_key_age
+    }
+
+    pub async fn charge_quota<S: AsRef<str>>(
+        &self,
+        store: &dyn StateStore,
+        subjects: &[S],
+        action: &str,
+        now: u64,
+    ) -> DGResult<()> {
+        let guard = self.inner.read().await;
+        for quota in &guard.quotas {
+            if !(subjects
+                .iter()
+                .any(|subject| quota.subject.is_match(subject.as_ref()))
+                && quota.action.is_match(action))
+            {
+                continue;
+            }
+            let prefix = format!("{QUOTA_PREFIX}{}/", quota.key);
+            let current = format!("{prefix}{}", now - now % quota.window_secs);
+            let used = store.add(&current, 1)?;
+            if used == 1 {
+                for (stale, _) in store.scan(&prefix)? {
+                    if stale != current {
+                        store.set(&stale, 0)?;
+                    }
+                }
+            }
+            if used > quota.limit {
+                return Err(DGError::PolicyDenied(format!(
+                    "quota of {} {action} operations per {}s exhausted",
+                    quota.limit, quota.window_secs
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn check_lockout(
+        &self,
+        store: &dyn StateStore,
+        subject: &str,
+        action: &str,
+        now: u64,
+    ) -> DGResult<()> {
+        if self.inner.read().await.lockout.is_none() {
+            return Ok(());
+        }
+        let until = store.get(&lockout_key(subject, action, "until"))?;
+        if until > now {
+            return Err(DGError::PolicyDenied(format!(
+                "{action} is locked for {}s after repeated failures",
+                until - now
+            )));
+        }
+        Ok(())
+    }
+
+    pub async fn record_failure(
+        &self,
+        store: &dyn StateStore,
+        subject: &str,
+        action: &str,
+        now: u64,
+    ) -> DGResult<()> {
+        let Some(lockout) = self.inner.read().await.lockout else {
+            return Ok(());
+        };
+        let failures_key = lockout_key(subject, action, "failures");
+        let failures = store.add(&failures_key, 1)?;
+        if failures >= lockout.max_failures {
+            warn!(
+                subject,
+                action, failures, "locking out after repeated failures"
+            );
+            store.set(
+                &lockout_key(subject, action, "until"),
+                now.saturating_add(lockout.cooldown_secs),
+            )?;
+            store.set(&failures_key, 0)?;
+        }
+        Ok(())
+    }
+
+    pub async fn record_success(
+        &self,
+        store: &dyn StateStore,
+        subject: &str,
+        action: &str,
+    ) -> DGResult<()> {
+        if self.inner.read().await.lockout.is_none() {
+            return Ok(());
+        }
+        store.set(&lockout_key(subject, action, "failures"), 0)
+    }
+}
+
+fn compile_quota(rule: QuotaRule) -> Result<CompiledQuota, String> {
+    if rule.window_secs == 0 {
+        return Err("quota window_secs must be positive".to_string());
+    }
+    let digest = Sha256::new()
+        .chain_update(rule.subject.as_bytes())
+        .chain_update([0])
+        .chain_update(rule.action.as_bytes())
+        .finalize();
+    Ok(CompiledQuota {
+        subject: compile_glob("quota subject", &rule.subject)?,
+        action: compile_glob("quota action", &rule.action)?,
+        key: hex::encode(&digest[..8]),
+        limit: rule.limit,
+        window_secs: rule.window_secs,
+    })
+}
+
+impl CompiledPolicy {
+    fn compile(doc: PolicyDocument) -> Result<Self, String> {
+        if doc.rules.len() + doc.quotas.len() > MAX_POLICY_ENTRIES {
+            return Err(format!(
+                "policy has more than {MAX_POLICY_ENTRIES} rules and quotas"
+            ));
+        }
+        let escrow = doc.escrow.map(compile_escrow).transpose()?;
+        if let Some(lockout) = &doc.lockout {
+            if lockout.max_failures == 0 || lockout.cooldown_secs == 0 {
+                return Err("lockout needs positive max_failures and cooldown_secs".to_string());
+            }
+        }
+        if let Some(max_key_age) = &doc.max_key_age {
+            max_key_age.validate()?;
+        }
+        let quotas = doc
+            .quotas
+            .into_iter()
+            .map(compile_quota)
+            .collect::<Result<Vec<_>, _>>()?;
         let mut compiled = CompiledPolicy {
             rules: Vec::new(),
             default_allow: doc.default_allow,
+            escrow,
+            quotas,
+            lockout: doc.lockout,
+            max_key_age: doc.max_key_age,
+            cache: doc.cache.unwrap_or_default(),
+            hooks: HookRegistry::new(),
         };
+        for (name, hook) in doc.hooks {
+            if hook.command.is_empty() {
+                return Err(format!("hook '{name}' needs a command"));
+            }
+            compiled.hooks.insert(name, Arc::new(hook));
+        }
 
         for rule in doc.rules {
-            let subject = Glob::new(&rule.subject)
-                .map_err(|err| format!("invalid subject glob: {err}"))?
-                .compile_matcher();
-            let action = Glob::new(&rule.action)
-                .map_err(|err| format!("invalid action glob: {err}"))?
-                .compile_matcher();
-            let resource = Glob::new(&rule.resource)
-                .map_err(|err| format!("invalid resource glob: {err}"))?
-                .compile_matcher();
+            let subject = compile_glob("subject", &rule.subject)?;
+            let action = compile_glob("action", &rule.action)?;
+            let resource = compile_glob("resource", &rule.resource)?;
+            let when = rule.when.map(compile_condition).transpose()?;
+            let hook = match rule.hook {
+                Some(name) => Some(RuleHook {
+                    name,
+                    timeout: match rule.timeout_ms.unwrap_or(DEFAULT_HOOK_TIMEOUT_MS) {
+                        0 => return Err("a hook timeout_ms must be positive".to_string()),
+                        millis => Duration::from_millis(millis),
+                    },
+                    on_failure: rule.on_failure.unwrap_or_default(),
+                }),
+                None if rule.timeout_ms.is_some() || rule.on_failure.is_some() => {
+                    return Err("timeout_ms and on_failure only apply to rules with a hook".into())
+                }
+                None => None,
+            };
             compiled.rules.push(CompiledRule {
                 subject,
                 action,
                 resource,
+                when,
                 effect: rule.effect,
+                hook,
             });
         }
 
-        Ok(Self {
-            inner: std::sync::Arc::new(RwLock::new(compiled)),
-        })
+        Ok(compiled)
     }
 
-    pub async fn evaluate(
+    // A rule with a hook only applies when the hook approves; a declined hook moves on to the
+    // next rule. If the hook fails or times out the rule's fail mode decides.
+    async fn explain<S: AsRef<str>>(
         &self,
-        subject: &str,
+        registered: &std::sync::RwLock<HookRegistry>,
+        subjects: &[S],
         action: &str,
         resource: &str,
-    ) -> Result<bool, String> {
-        let guard = self.inner.read().await;
-        for rule in &guard.rules {
-            if rule.subject.is_match(subject)
-                && rule.action.is_match(action)
-                && rule.resource.is_match(resource)
-            {
-                return Ok(rule.effect == PolicyEffect::Allow);
+        attributes: &ResourceAttributes,
+    ) -> DecisionExplanation {
+        let mut hooks = Vec::new();
+        let mut request = None;
+        for (index, rule) in self.rules.iter().enumerate() {
+            if !rule.matches(subjects, action, resource, attributes) {
+                continue;
             }
+            if let Some(hook) = &rule.hook {
+                let request = request.get_or_insert_with(|| HookRequest {
+                    subjects: subjects.iter().map(|s| s.as_ref().to_owned()).collect(),
+                    action: action.to_owned(),
+                    resource: resource.to_owned(),
+                    attributes: attributes.clone(),
+                });
+                let handler = registered
+                    .read()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .get(&hook.name)
+                    .or_else(|| self.hooks.get(&hook.name))
+                    .cloned();
+                let outcome =
+                    policy_hook::run(&hook.name, handler, request, hook.timeout, index).await;
+                let (failed, approved) = (
+                    outcome.result.failed(),
+                    outcome.result == policy_hook::HookResult::Approved,
+                );
+                hooks.push(outcome);
+                if failed {
+                    return DecisionExplanation {
+                        decision: hook.on_failure.decision(),
+                        source: DecisionSource::Fallback,
+                        rule: Some(rule.describe(index)),
+                        hooks,
+                        fail_mode: Some(hook.on_failure),
+                    };
+                }
+                if !approved {
+                    continue;
+                }
+            }
+            return DecisionExplanation {
+                decision: (rule.effect == PolicyEffect::Allow).into(),
+                source: DecisionSource::Policy,
+                rule: Some(rule.describe(index)),
+                hooks,
+                fail_mode: None,
+            };
+        }
+        DecisionExplanation {
+            decision: self.default_allow.into(),
+            source: DecisionSource::Policy,
+            rule: None,
+            hooks,
+            fail_mode: None,
+        }
+    }
+
+    // Hooks are not consulted here; simulations assume every hook approves.
+    fn first_match<S: AsRef<str>>(
+        &self,
+        subjects: &[S],
+        action: &str,
+        resource: &str,
+        attributes: &ResourceAttributes,
+    ) -> Option<(usize, &CompiledRule)> {
+        self.rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.matches(subjects, action, resource, attributes))
+    }
+}
+
+impl CompiledRule {
+    fn matches<S: AsRef<str>>(
+        &self,
+        subjects: &[S],
+        action: &str,
+        resource: &str,
+        attributes: &ResourceAttributes,
+    ) -> bool {
+        subjects
+            .iter()
+            .any(|subject| self.subject.is_match(subject.as_ref()))
+            && self.action.is_match(action)
+            && self.resource.is_match(resource)
+            && self
+                .when
+                .as_ref()
+                .is_none_or(|when| when.matches(attributes))
+    }
+
+    fn describe(&self, index: usize) -> MatchedRule {
+        MatchedRule {
+            index,
+            subject: self.subject.glob().glob().to_string(),
+            action: self.action.glob().glob().to_string(),
+            resource: self.resource.glob().glob().to_string(),
+            when: self.when.as_ref().map(|when| when.source.clone()),
+            hook: self.hook.as_ref().map(|hook| hook.name.clone()),
         }
+    }
+}
 
-        Ok(guard.default_allow)
+fn read_document(bytes: &[u8], format: PolicyFormat) -> Result<PolicyDocument, String> {
+    if bytes.len() > MAX_POLICY_BYTES {
+        return Err(format!(
+            "policy is {} bytes; the limit is {MAX_POLICY_BYTES}",
+            bytes.len()
+        ));
     }
+    match format {
+        PolicyFormat::Json => {
+            serde_json::from_slice(bytes).map_err(|err| format!("invalid policy format: {err}"))
+        }
+        PolicyFormat::Yaml => {
+            serde_yaml::from_slice(bytes).map_err(|err| format!("invalid policy format: {err}"))
+        }
+    }
+}
+
+// Comments are re-attached to the first rendered line whose content matches the line they
+// preceded (or trailed) in `existing`; quoting differences are ignored. Comments whose line
+// no longer exists are kept at the end of the document rather than dropped.
+fn restore_comments(existing: &str, rendered: &str) -> String {
+    let anchor = |line: &str| line.trim().replace(['\'', '"'], "");
+    let mut blocks: Vec<(String, Vec<String>, Option<String>)> = Vec::new();
+    let mut pending = Vec::new();
+    for line in existing.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') {
+            pending.push(trimmed.to_owned());
+            continue;
+        }
+        if trimmed.is_empty() {
+            continue;
+        }
+        let (content, inline) = split_inline_comment(line);
+        if !pending.is_empty() || inline.is_some() {
+            blocks.push((
+                anchor(content),
+                std::mem::take(&mut pending),
+                inline.map(str::to_owned),
+            ));
+        }
+    }
+
+    let mut output = String::with_capacity(rendered.len() + existing.len());
+    for line in rendered.lines() {
+        let key = anchor(line);
+        let position = blocks
+            .iter()
+            .position(|(candidate, _, _)| *candidate == key);
+        let Some((_, comments, inline)) = position.map(|index| blocks.remove(index)) else {
+            output.push_str(line);
+            output.push('\n');
+            continue;
+        };
+        let indent = &line[..line.len() - line.trim_start().len()];
+        for comment in comments {
+            output.push_str(indent);
+            output.push_str(&comment);
+            output.push('\n');
+        }
+        output.push_str(line);
+        if let Some(inline) = inline {
+            output.push(' ');
+            output.push_str(&inline);
+        }
+        output.push('\n');
+    }
+    for (_, comments, inline) in blocks {
+        for comment in comments.into_iter().chain(inline) {
+            output.push_str(&comment);
+            output.push('\n');
+        }
+    }
+    output.extend(pending.into_iter().map(|comment| comment + "\n"));
+    output
+}
+
+fn split_inline_comment(line: &str) -> (&str, Option<&str>) {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (index, ch) in line.char_indices() {
+        match (quote, ch) {
+            (None, '\'' | '"') => quote = Some(ch),
+            (Some(open), _) if ch == open => quote = None,
+            (None, '#') if previous.is_whitespace() => {
+                return (line[..index].trim_end(), Some(&line[index..]));
+            }
+            _ => {}
+        }
+        previous = ch;
+    }
+    (line, None)
+}
+
+fn compile_glob(field: &str, pattern: &str) -> Result<GlobMatcher, String> {
+    if pattern.len() > MAX_PATTERN_LEN {
+        return Err(format!(
+            "{field} glob is longer than {MAX_PATTERN_LEN} bytes"
+        ));
+    }
+    Ok(Glob::new(pattern)
+        .map_err(|err| format!("invalid {field} glob: {err}"))?
+        .compile_matcher())
+}
+
+fn compile_condition(condition: ResourceCondition) -> Result<CompiledCondition, String> {
+    if condition == ResourceCondition::default() {
+        return Err("a rule's when block needs at least one attribute".to_string());
+    }
+    let owner_sid = condition
+        .owner_sid
+        .as_deref()
+        .map(|pattern| compile_glob("owner_sid", &pattern.to_ascii_uppercase()))
+        .transpose()?;
+    Ok(CompiledCondition::new(condition, owner_sid))
+}
+
+fn lockout_key(subject: &str, action: &str, field: &str) -> String {
+    format!("{LOCKOUT_PREFIX}{subject}/{action}/{field}")
+}
+
+fn compile_escrow(policy: EscrowPolicy) -> Result<EscrowKey, String> {
+    let public_key = general_purpose::STANDARD
+        .decode(policy.public_key.trim())
+        .map_err(|err| format!("invalid escrow public key: {err}"))?
+        .try_into()
+        .map_err(|_| "escrow public key must be a 32-byte X25519 key".to_string())?;
+    Ok(EscrowKey {
+        public_key,
+        label: policy.label,
+    })
 }
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::resource_attrs::ResourceAttributes;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheSettings {
+    // Zero turns the cache off.
+    #[serde(default = "default_ttl")]
+    pub ttl_secs: u64,
+    // How long past its TTL a decision may still be served when evaluation fails.
+    #[serde(default)]
+    pub stale_secs: u64,
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            ttl_secs: default_ttl(),
+            stale_secs: 0,
+            max_entries: default_max_entries(),
+        }
+    }
+}
+
+fn default_ttl() -> u64 {
+    60
+}
+
+fn default_max_entries() -> usize {
+    4096
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PolicyCacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub stale_hits: u64,
+    pub evictions: u64,
+    pub invalidations: u64,
+}
+
+impl PolicyCacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.stale_hits + self.misses;
+        if lookups == 0 {
+            return 0.0;
+        }
+        (self.hits + self.stale_hits) as f64 / lookups as f64
+    }
+}
+
+pub(crate) type CacheKey = [u8; 32];
+
+pub(crate) enum Cached {
+    Fresh(bool),
+    Stale(bool),
+    Missing,
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    allowed: bool,
+    fresh_until: Instant,
+    stale_until: Instant,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, Entry>,
+    stats: PolicyCacheStats,
+}
+
+#[derive(Default)]
+pub(crate) struct DecisionCache {
+    state: Mutex<CacheState>,
+}
+
+// Fields are length-prefixed so `("ab", "c")` and `("a", "bc")` hash differently. Subject order
+// matters as given, which is stable for a given identity.
+pub(crate) fn key<S: AsRef<str>>(
+    subjects: &[S],
+    action: &str,
+    resource: &str,
+    attributes: &ResourceAttributes,
+) -> CacheKey {
+    let mut hasher = Sha256::new();
+    let attributes = serde_json::to_string(attributes).unwrap_or_default();
+    let fields = subjects
+        .iter()
+        .map(AsRef::as_ref)
+        .chain([action, resource, attributes.as_str()]);
+    hasher.update((subjects.len() as u64).to_be_bytes());
+    for field in fields {
+        hasher.update((field.len() as u64).to_be_bytes());
+        hasher.update(field.as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+impl DecisionCache {
+    pub fn lookup(&self, key: &CacheKey, now: Instant) -> Cached {
+        let mut state = self.lock();
+        let found = match state.entries.get(key) {
+            Some(entry) if now < entry.fresh_until => Cached::Fresh(entry.allowed),
+            Some(entry) if now < entry.stale_until => Cached::Stale(entry.allowed),
+            _ => Cached::Missing,
+        };
+        if let Cached::Fresh(_) = found {
+            state.stats.hits += 1;
+        }
+        found
+    }
+
+    pub fn record_miss(&self) {
+        self.lock().stats.misses += 1;
+    }
+
+    pub fn record_stale_hit(&self) {
+        self.lock().stats.stale_hits += 1;
+    }
+
+    pub fn insert(&self, settings: CacheSettings, key: CacheKey, allowed: bool, now: Instant) {
+        if settings.ttl_secs == 0 || settings.max_entries == 0 {
+            return;
+        }
+        let mut state = self.lock();
+        if !state.entries.contains_key(&key) && state.entries.len() >= settings.max_entries {
+            let before = state.entries.len();
+            state.entries.retain(|_, entry| now < entry.stale_until);
+            if state.entries.len() >= settings.max_entries {
+                if let Some(oldest) = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.fresh_until)
+                    .map(|(key, _)| *key)
+                {
+                    state.entries.remove(&oldest);
+                }
+            }
+            state.stats.evictions += (before - state.entries.len()) as u64;
+        }
+        let fresh_until = now + Duration::from_secs(settings.ttl_secs);
+        state.entries.insert(
+            key,
+            Entry {
+                allowed,
+                fresh_until,
+                stale_until: fresh_until + Duration::from_secs(settings.stale_secs),
+            },
+        );
+    }
+
+    pub fn invalidate(&self) {
+        let mut state = self.lock();
+        state.entries.clear();
+        state.stats.invalidations += 1;
+    }
+
+    pub fn stats(&self) -> PolicyCacheStats {
+        let state = self.lock();
+        PolicyCacheStats {
+            entries: state.entries.len(),
+            ..state.stats
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
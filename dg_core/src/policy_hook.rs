@@ -0,0 +1,152 @@
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+
+use crate::policy::Decision;
+use crate::resource_attrs::ResourceAttributes;
+
+pub const DEFAULT_HOOK_TIMEOUT_MS: u64 = 2_000;
+const MAX_REASON_CHARS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HookRequest {
+    pub subjects: Vec<String>,
+    pub action: String,
+    pub resource: String,
+    #[serde(skip_serializing_if = "ResourceAttributes::is_empty")]
+    pub attributes: ResourceAttributes,
+}
+
+// `Ok(true)` lets the rule apply, `Ok(false)` skips it. Errors and timeouts fall back to the rule's
+// fail mode.
+#[async_trait::async_trait]
+pub trait PolicyHook: Send + Sync {
+    async fn evaluate(&self, request: &HookRequest) -> Result<bool, String>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailMode {
+    Open,
+    #[default]
+    Closed,
+}
+
+impl FailMode {
+    pub fn decision(self) -> Decision {
+        match self {
+            FailMode::Open => Decision::Allow,
+            FailMode::Closed => Decision::Deny,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum HookResult {
+    Approved,
+    Declined,
+    TimedOut,
+    Failed { reason: String },
+}
+
+impl HookResult {
+    pub fn failed(&self) -> bool {
+        matches!(self, HookResult::TimedOut | HookResult::Failed { .. })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HookOutcome {
+    pub rule: usize,
+    pub hook: String,
+    #[serde(flatten)]
+    pub result: HookResult,
+    pub elapsed_ms: u64,
+}
+
+// Runs an external program with the request as JSON on stdin. Exit status 0 approves, 1 declines
+// and anything else is a failure.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandHook {
+    pub command: Vec<String>,
+}
+
+#[async_trait::async_trait]
+impl PolicyHook for CommandHook {
+    async fn evaluate(&self, request: &HookRequest) -> Result<bool, String> {
+        let (program, args) = self
+            .command
+            .split_first()
+            .ok_or_else(|| "hook command is empty".to_string())?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| format!("unable to start {program}: {err}"))?;
+        let input = serde_json::to_vec(request).map_err(|err| err.to_string())?;
+        // A program that decides without reading its input closes the pipe early; that is fine.
+        if let Some(mut stdin) = child.stdin.take() {
+            match stdin.write_all(&input).await {
+                Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => {
+                    return Err(format!("unable to send the request to {program}: {err}"));
+                }
+                _ => {}
+            }
+        }
+        let mut stderr = String::new();
+        if let Some(mut pipe) = child.stderr.take() {
+            let _ = pipe.read_to_string(&mut stderr).await;
+        }
+        let status = child
+            .wait()
+            .await
+            .map_err(|err| format!("{program} did not finish: {err}"))?;
+        match status.code() {
+            Some(0) => Ok(true),
+            Some(1) => Ok(false),
+            _ => {
+                let reason: String = stderr.trim().chars().take(MAX_REASON_CHARS).collect();
+                Err(if reason.is_empty() {
+                    format!("{program} exited with {status}")
+                } else {
+                    format!("{program} exited with {status}: {reason}")
+                })
+            }
+        }
+    }
+}
+
+pub(crate) async fn run(
+    name: &str,
+    hook: Option<Arc<dyn PolicyHook>>,
+    request: &HookRequest,
+    timeout: Duration,
+    rule: usize,
+) -> HookOutcome {
+    let started = Instant::now();
+    let result = match hook {
+        None => HookResult::Failed {
+            reason: format!("no hook named '{name}' is registered"),
+        },
+        Some(hook) => match tokio::time::timeout(timeout, hook.evaluate(request)).await {
+            Ok(Ok(true)) => HookResult::Approved,
+            Ok(Ok(false)) => HookResult::Declined,
+            Ok(Err(reason)) => HookResult::Failed { reason },
+            Err(_) => HookResult::TimedOut,
+        },
+    };
+    HookOutcome {
+        rule,
+        hook: name.to_owned(),
+        result,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    }
+}
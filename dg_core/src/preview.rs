@@ -0,0 +1,81 @@
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+
+use crate::api::{DGError, DGResult};
+use crate::engine::{open_payload_with, seal_payload_with};
+use crate::entropy::Entropy;
+
+pub const PREVIEW_FIELD: &str = "preview";
+pub const MAX_PREVIEW_BYTES: usize = 64 * 1024;
+pub const PREVIEW_MEDIA_TYPES: [&str; 3] = ["image/jpeg", "image/png", "image/webp"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Preview {
+    pub media_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Preview {
+    pub fn validate(&self) -> DGResult<()> {
+        if !PREVIEW_MEDIA_TYPES.contains(&self.media_type.as_str()) {
+            return Err(DGError::Config(format!(
+                "unsupported preview type '{}'; expected one of {}",
+                self.media_type,
+                PREVIEW_MEDIA_TYPES.join(", ")
+            )));
+        }
+        if self.data.is_empty() || self.data.len() > MAX_PREVIEW_BYTES {
+            return Err(DGError::Config(format!(
+                "previews must be between 1 and {MAX_PREVIEW_BYTES} bytes, got {}",
+                self.data.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SealedPreview {
+    media_type: String,
+    sealed: String,
+}
+
+pub fn has_preview(meta: &serde_json::Value) -> bool {
+    meta.get(PREVIEW_FIELD).is_some()
+}
+
+// Sealed under the envelope's file key with the media type as associated data, so only someone who
+// can open the envelope can see the thumbnail.
+pub(crate) fn seal(
+    entropy: &Entropy,
+    file_key: &[u8; 32],
+    preview: &Preview,
+) -> DGResult<serde_json::Value> {
+    preview.validate()?;
+    let sealed = seal_payload_with(
+        entropy,
+        file_key,
+        &preview.data,
+        preview.media_type.as_bytes(),
+    )?;
+    Ok(serde_json::json!(SealedPreview {
+        media_type: preview.media_type.clone(),
+        sealed: general_purpose::STANDARD.encode(sealed),
+    }))
+}
+
+pub(crate) fn open(file_key: &[u8; 32], meta: &serde_json::Value) -> DGResult<Option<Preview>> {
+    let Some(field) = meta.get(PREVIEW_FIELD) else {
+        return Ok(None);
+    };
+    let sealed: SealedPreview = serde_json::from_value(field.clone())
+        .map_err(|err| DGError::Crypto(format!("invalid envelope preview: {err}")))?;
+    let payload = general_purpose::STANDARD
+        .decode(sealed.sealed.trim())
+        .map_err(|err| DGError::Crypto(format!("envelope preview is not base64: {err}")))?;
+    let data = open_payload_with(file_key, &payload, sealed.media_type.as_bytes())?;
+    Ok(Some(Preview {
+        media_type: sealed.media_type,
+        data,
+    }))
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::{DGError, DGResult};
+use crate::metadata::METADATA_FIELD;
+
+pub const PROVENANCE_FIELD: &str = "provenance";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RekeyContext {
+    pub action: String,
+    pub subject: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_format: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_format: Option<u32>,
+}
+
+impl Default for RekeyContext {
+    fn default() -> Self {
+        Self {
+            action: "rekey".into(),
+            subject: "system".into(),
+            from_format: None,
+            to_format: None,
+        }
+    }
+}
+
+impl RekeyContext {
+    pub fn rekey(subject: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn migrate(subject: impl Into<String>, from_format: u32, to_format: u32) -> Self {
+        Self {
+            action: "migrate".into(),
+            subject: subject.into(),
+            from_format: Some(from_format),
+            to_format: Some(to_format),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvenanceEntry {
+    pub action: String,
+    pub subject: String,
+    pub timestamp: u64,
+    #[serde(default)]
+    pub previous_key_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_format: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_format: Option<u32>,
+}
+
+impl ProvenanceEntry {
+    pub fn new(context: RekeyContext, previous_key_ids: Vec<String>, timestamp: u64) -> Self {
+        Self {
+            action: context.action,
+            subject: context.subject,
+            timestamp,
+            previous_key_ids,
+            from_format: context.from_format,
+            to_format: context.to_format,
+        }
+    }
+}
+
+pub fn provenance(meta: &serde_json::Value) -> DGResult<Vec<ProvenanceEntry>> {
+    match meta.get(PROVENANCE_FIELD) {
+        None => Ok(Vec::new()),
+        Some(chain) => serde_json::from_value(chain.clone())
+            .map_err(|err| DGError::Crypto(format!("invalid envelope provenance: {err}"))),
+    }
+}
+
+// The chain and any custom metadata are bound to the payload as AES-GCM associated data, so editing
+// either breaks decryption. Envelopes with neither use empty associated data and stay readable,
+// and envelopes with only a chain keep the chain-only encoding they were sealed with.
+pub(crate) fn associated_data(meta: &serde_json::Value) -> DGResult<Vec<u8>> {
+    let bound = match (meta.get(PROVENANCE_FIELD), meta.get(METADATA_FIELD)) {
+        (None, None) => return Ok(Vec::new()),
+        (Some(chain), None) => chain.clone(),
+        (chain, Some(custom)) => {
+            let mut bound = serde_json::Map::new();
+            bound.insert(METADATA_FIELD.into(), custom.clone());
+            bound.insert(
+                PROVENANCE_FIELD.into(),
+                chain.cloned().unwrap_or(serde_json::Value::Null),
+            );
+            serde_json::Value::Object(bound)
+        }
+    };
+    serde_json::to_vec(&bound)
+        .map_err(|err| DGError::Internal(format!("unable to encode associated data: {err}")))
+}
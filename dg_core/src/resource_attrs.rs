@@ -0,0 +1,202 @@
+use std::path::Path;
+
+use globset::GlobMatcher;
+use serde::{Deserialize, Serialize};
+
+// URL security zones as written to the `Zone.Identifier` stream (mark of the web).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityZone {
+    LocalMachine,
+    Intranet,
+    Trusted,
+    Internet,
+    Restricted,
+}
+
+impl SecurityZone {
+    pub fn from_id(id: u32) -> Option<Self> {
+        match id {
+            0 => Some(SecurityZone::LocalMachine),
+            1 => Some(SecurityZone::Intranet),
+            2 => Some(SecurityZone::Trusted),
+            3 => Some(SecurityZone::Internet),
+            4 => Some(SecurityZone::Restricted),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriveType {
+    Removable,
+    Fixed,
+    Remote,
+    Cdrom,
+    Ramdisk,
+}
+
+// Facts about a file that policy rules can match on besides its path. Every field is optional;
+// off Windows nothing is extracted and rules with conditions never match.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ResourceAttributes {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_sid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<SecurityZone>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drive_type: Option<DriveType>,
+}
+
+impl ResourceAttributes {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    // Reads whatever the platform exposes; attributes that cannot be read are left unset.
+    pub fn for_path(path: &Path) -> Self {
+        extract(path)
+    }
+}
+
+// Reads the `ZoneId=` line of a `Zone.Identifier` stream. Unknown zones are ignored.
+pub fn parse_zone_identifier(stream: &str) -> Option<SecurityZone> {
+    stream
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("ZoneId="))
+        .find_map(|id| id.trim().parse().ok())
+        .and_then(SecurityZone::from_id)
+}
+
+// A rule's `when` block. Every listed attribute has to match; the owner SID is a glob.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceCondition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_sid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<SecurityZone>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drive_type: Option<DriveType>,
+}
+
+#[derive(Clone)]
+pub(crate) struct CompiledCondition {
+    pub(crate) source: ResourceCondition,
+    owner_sid: Option<GlobMatcher>,
+}
+
+impl CompiledCondition {
+    pub(crate) fn new(source: ResourceCondition, owner_sid: Option<GlobMatcher>) -> Self {
+        Self { source, owner_sid }
+    }
+
+    pub(crate) fn matches(&self, attributes: &ResourceAttributes) -> bool {
+        let owner = match (&self.owner_sid, &attributes.owner_sid) {
+            (None, _) => true,
+            (Some(glob), Some(sid)) => glob.is_match(sid.to_ascii_uppercase()),
+            (Some(_), None) => false,
+        };
+        owner
+            && self
+                .source
+                .zone
+                .is_none_or(|zone| attributes.zone == Some(zone))
+            && self
+                .source
+                .drive_type
+                .is_none_or(|drive| attributes.drive_type == Some(drive))
+    }
+}
+
+#[cfg(windows)]
+fn extract(path: &Path) -> ResourceAttributes {
+    let mut stream = path.as_os_str().to_owned();
+    stream.push(":Zone.Identifier");
+    ResourceAttributes {
+        owner_sid: windows::owner_sid(path),
+        zone: std::fs::read_to_string(stream)
+            .ok()
+            .and_then(|contents| parse_zone_identifier(&contents)),
+        drive_type: windows::drive_type(path),
+    }
+}
+
+#[cfg(not(windows))]
+fn extract(_path: &Path) -> ResourceAttributes {
+    ResourceAttributes::default()
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+
+    use windows_sys::Win32::Foundation::{LocalFree, ERROR_SUCCESS};
+    use windows_sys::Win32::Security::Authorization::{
+        ConvertSidToStringSidW, GetNamedSecurityInfoW, SE_FILE_OBJECT,
+    };
+    use windows_sys::Win32::Security::OWNER_SECURITY_INFORMATION;
+    use windows_sys::Win32::Storage::FileSystem::{GetDriveTypeW, GetVolumePathNameW};
+
+    use super::DriveType;
+
+    fn wide(path: &Path) -> Vec<u16> {
+        path.as_os_str().encode_wide().chain(Some(0)).collect()
+    }
+
+    pub(super) fn owner_sid(path: &Path) -> Option<String> {
+        let name = wide(path);
+        let mut owner = std::ptr::null_mut();
+        let mut descriptor = std::ptr::null_mut();
+        // SAFETY: `name` is NUL terminated and the out pointers are valid for the call. The
+        // owner SID points into `descriptor`, which is freed once the SID has been copied out.
+        unsafe {
+            let status = GetNamedSecurityInfoW(
+                name.as_ptr(),
+                SE_FILE_OBJECT,
+                OWNER_SECURITY_INFORMATION,
+                &mut owner,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                &mut descriptor,
+            );
+            if status != ERROR_SUCCESS {
+                return None;
+            }
+            let mut text = std::ptr::null_mut();
+            let converted = ConvertSidToStringSidW(owner, &mut text) != 0;
+            let sid = converted.then(|| {
+                let len = (0..).take_while(|&i| *text.add(i) != 0).count();
+                String::from_utf16_lossy(std::slice::from_raw_parts(text, len))
+            });
+            if converted {
+                LocalFree(text as _);
+            }
+            LocalFree(descriptor as _);
+            sid
+        }
+    }
+
+    pub(super) fn drive_type(path: &Path) -> Option<DriveType> {
+        let name = wide(path);
+        let mut root = [0u16; 261];
+        // SAFETY: `name` is NUL terminated and `root` is large enough for MAX_PATH plus the NUL.
+        let kind = unsafe {
+            if GetVolumePathNameW(name.as_ptr(), root.as_mut_ptr(), root.len() as u32) == 0 {
+                return None;
+            }
+            GetDriveTypeW(root.as_ptr())
+        };
+        match kind {
+            2 => Some(DriveType::Removable),
+            3 => Some(DriveType::Fixed),
+            4 => Some(DriveType::Remote),
+            5 => Some(DriveType::Cdrom),
+            6 => Some(DriveType::Ramdisk),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::api::DGError;
+
+pub const REQUEST_TIMED_OUT: i64 = -32000;
+pub const APPLICATION_ERROR: i64 = -32001;
+pub const POLICY_ONLY: i64 = -32010;
+pub const UNAUTHENTICATED: i64 = -32011;
+pub const CAPABILITY_DENIED: i64 = -32012;
+pub const POLICY_DENIED: i64 = -32013;
+pub const RATE_LIMITED: i64 = -32014;
+pub const CRYPTO_FAILED: i64 = -32015;
+pub const CONFIG_INVALID: i64 = -32016;
+pub const READ_ONLY: i64 = -32017;
+pub const LOCKED: i64 = -32018;
+pub const INDEX_UNAVAILABLE: i64 = -32020;
+pub const ENVELOPE_NOT_FOUND: i64 = -32021;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const REQUEST_TOO_LARGE: i64 = INVALID_REQUEST;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+pub const PARSE_ERROR: i64 = -32700;
+
+pub const ERROR_CODES: &[(i64, &str)] = &[
+    (PARSE_ERROR, "rpc.parse_error"),
+    (INVALID_REQUEST, "rpc.invalid_request"),
+    (METHOD_NOT_FOUND, "rpc.method_not_found"),
+    (INVALID_PARAMS, "rpc.invalid_params"),
+    (INTERNAL_ERROR, "engine.internal"),
+    (REQUEST_TIMED_OUT, "rpc.timed_out"),
+    (APPLICATION_ERROR, "application.error"),
+    (POLICY_ONLY, "daemon.policy_only"),
+    (UNAUTHENTICATED, "session.unauthenticated"),
+    (CAPABILITY_DENIED, "session.capability_denied"),
+    (POLICY_DENIED, "engine.policy_denied"),
+    (RATE_LIMITED, "rpc.rate_limited"),
+    (CRYPTO_FAILED, "engine.crypto"),
+    (CONFIG_INVALID, "engine.config"),
+    (READ_ONLY, "engine.read_only"),
+    (LOCKED, "engine.locked"),
+    (INDEX_UNAVAILABLE, "index.unavailable"),
+    (ENVELOPE_NOT_FOUND, "index.envelope_not_found"),
+];
+
+pub fn kind_for(code: i64) -> Option<&'static str> {
+    ERROR_CODES
+        .iter()
+        .find(|(candidate, _)| *candidate == code)
+        .map(|(_, kind)| *kind)
+}
+
+pub fn code_for(kind: &str) -> Option<i64> {
+    ERROR_CODES
+        .iter()
+        .find(|(_, candidate)| *candidate == kind)
+        .map(|(code, _)| *code)
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[error("{message} (code {code})")]
+pub struct RpcError {
+    pub code: i64,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    pub fn kind(&self) -> &str {
+        self.data
+            .as_ref()
+            .and_then(|data| data.get("kind"))
+            .and_then(Value::as_str)
+            .or_else(|| kind_for(self.code))
+            .unwrap_or("application.error")
+    }
+
+    pub fn detail(&self) -> &str {
+        self.data
+            .as_ref()
+            .and_then(|data| data.get("detail"))
+            .and_then(Value::as_str)
+            .unwrap_or(&self.message)
+    }
+
+    pub fn is_limit_violation(&self) -> bool {
+        matches!(self.code, RATE_LIMITED | REQUEST_TOO_LARGE)
+    }
+
+    pub fn to_dg_error(&self, detail: impl Into<String>) -> DGError {
+        let detail = detail.into();
+        match self.code {
+            UNAUTHENTICATED | CAPABILITY_DENIED | POLICY_DENIED => DGError::PolicyDenied(detail),
+            CRYPTO_FAILED => DGError::Crypto(detail),
+            CONFIG_INVALID => DGError::Config(detail),
+            READ_ONLY => DGError::ReadOnly(detail),
+            LOCKED => DGError::Locked(detail),
+            _ => DGError::Internal(detail),
+        }
+    }
+}
+
+impl From<&DGError> for RpcError {
+    fn from(err: &DGError) -> Self {
+        RpcError::new(
+            code_for(err.code()).unwrap_or(INTERNAL_ERROR),
+            err.to_string(),
+        )
+        .with_data(json!({ "kind": err.code(), "detail": err.detail() }))
+    }
+}
+
+impl From<DGError> for RpcError {
+    fn from(err: DGError) -> Self {
+        RpcError::from(&err)
+    }
+}
+
+impl From<RpcError> for DGError {
+    fn from(err: RpcError) -> Self {
+        err.to_dg_error(err.detail())
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt;
+use std::ops::Deref;
+
+use tracing::debug;
+use zeroize::Zeroize;
+
+// Large enough for the 16 KiB pages of Apple silicon, so unlocking one key's page never unlocks
+// another's.
+#[repr(C, align(16384))]
+struct KeyPage([u8; 32]);
+
+// A 32-byte key that is wiped when dropped and, where the platform allows, locked into memory so
+// it is never written to swap. Locking is best effort: a process over its memlock limit still
+// gets a working key.
+pub struct SecretKey {
+    page: Box<KeyPage>,
+    locked: bool,
+}
+
+impl SecretKey {
+    pub fn new(key: &[u8; 32]) -> Self {
+        let mut page = Box::new(KeyPage([0; 32]));
+        let locked = lock(&page.0);
+        if !locked {
+            debug!(
+                error = %std::io::Error::last_os_error(),
+                "key memory could not be locked; it may be swapped out"
+            );
+        }
+        page.0.copy_from_slice(key);
+        Self { page, locked }
+    }
+
+    pub fn is_memory_locked(&self) -> bool {
+        self.locked
+    }
+}
+
+impl Deref for SecretKey {
+    type Target = [u8; 32];
+
+    fn deref(&self) -> &[u8; 32] {
+        &self.page.0
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.page.0.zeroize();
+        if self.locked {
+            unlock(&self.page.0);
+        }
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretKey")
+            .field("locked", &self.locked)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(unix)]
+fn lock(bytes: &[u8; 32]) -> bool {
+    // SAFETY: the range is a live allocation owned by the caller for the duration of the call.
+    unsafe { libc::mlock(bytes.as_ptr().cast(), bytes.len()) == 0 }
+}
+
+#[cfg(unix)]
+fn unlock(bytes: &[u8; 32]) {
+    // SAFETY: as in `lock`; the range was locked by it.
+    unsafe {
+        libc::munlock(bytes.as_ptr().cast(), bytes.len());
+    }
+}
+
+#[cfg(windows)]
+fn lock(bytes: &[u8; 32]) -> bool {
+    use windows_sys::Win32::System::Memory::VirtualLock;
+
+    // SAFETY: the range is a live allocation owned by the caller for the duration of the call.
+    unsafe { VirtualLock(bytes.as_ptr().cast(), bytes.len()) != 0 }
+}
+
+#[cfg(windows)]
+fn unlock(bytes: &[u8; 32]) {
+    use windows_sys::Win32::System::Memory::VirtualUnlock;
+
+    // SAFETY: as in `lock`; the range was locked by it.
+    unsafe {
+        VirtualUnlock(bytes.as_ptr().cast(), bytes.len());
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn lock(_bytes: &[u8; 32]) -> bool {
+    false
+}
+
+#[cfg(not(any(unix, windows)))]
+fn unlock(_bytes: &[u8; 32]) {}
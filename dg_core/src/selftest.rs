@@ -0,0 +1,213 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use ed25519_dalek::{Signer, SigningKey, Verifier};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+use crate::engine::{open_payload, seal_payload};
+use crate::entropy::Entropy;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTestCheck {
+    pub name: String,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub checks: Vec<SelfTestCheck>,
+}
+
+impl SelfTestReport {
+    pub fn failures(&self) -> impl Iterator<Item = &SelfTestCheck> {
+        self.checks.iter().filter(|check| !check.passed)
+    }
+}
+
+struct GcmVector {
+    name: &'static str,
+    key: &'static str,
+    nonce: &'static str,
+    aad: &'static str,
+    plaintext: &'static str,
+    expected: &'static str,
+}
+
+// McGrew & Viega, "The Galois/Counter Mode of Operation", test cases 13, 14 and 16.
+const GCM_VECTORS: &[GcmVector] = &[
+    GcmVector {
+        name: "aes-256-gcm/nist-tc13",
+        key: "0000000000000000000000000000000000000000000000000000000000000000",
+        nonce: "000000000000000000000000",
+        aad: "",
+        plaintext: "",
+        expected: "530f8afbc74536b9a963b4f1c4cb738b",
+    },
+    GcmVector {
+        name: "aes-256-gcm/nist-tc14",
+        key: "0000000000000000000000000000000000000000000000000000000000000000",
+        nonce: "000000000000000000000000",
+        aad: "",
+        plaintext: "00000000000000000000000000000000",
+        expected: "cea7403d4d606b6e074ec5d3baf39d18d0d1c8a799996bf0265b98b5d48ab919",
+    },
+    GcmVector {
+        name: "aes-256-gcm/nist-tc16",
+        key: "feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308",
+        nonce: "cafebabefacedbaddecaf888",
+        aad: "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+        plaintext: "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+        expected: "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f66276fc6ece0f4e1768cddf8853bb2d551b",
+    },
+];
+
+// The envelope checks seal under a throwaway key so the diagnostic never touches the master key.
+pub(crate) fn run() -> SelfTestReport {
+    let envelope_key = Entropy::Os.key();
+    let mut checks: Vec<SelfTestCheck> = GCM_VECTORS
+        .iter()
+        .map(|vector| check(vector.name, || gcm_known_answer(vector)))
+        .collect();
+    checks.push(check("sha-256/nist-abc", sha256_known_answer));
+    checks.push(check("x25519/rfc7748", x25519_known_answer));
+    checks.push(check("ed25519/rfc8032-test1", ed25519_known_answer));
+    checks.push(check("envelope/round-trip", || {
+        envelope_round_trip(&envelope_key)
+    }));
+    checks.push(check("envelope/tamper-detect", || {
+        envelope_tamper_detect(&envelope_key)
+    }));
+
+    SelfTestReport {
+        passed: checks.iter().all(|check| check.passed),
+        checks,
+    }
+}
+
+fn check(name: &str, test: impl FnOnce() -> Result<(), String>) -> SelfTestCheck {
+    match test() {
+        Ok(()) => SelfTestCheck {
+            name: name.to_owned(),
+            passed: true,
+            detail: None,
+        },
+        Err(detail) => SelfTestCheck {
+            name: name.to_owned(),
+            passed: false,
+            detail: Some(detail),
+        },
+    }
+}
+
+fn gcm_known_answer(vector: &GcmVector) -> Result<(), String> {
+    let key = decode(vector.key)?;
+    let nonce = decode(vector.nonce)?;
+    let aad = decode(vector.aad)?;
+    let plaintext = decode(vector.plaintext)?;
+    let expected = decode(vector.expected)?;
+
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|err| err.to_string())?;
+    let nonce = Nonce::from_slice(&nonce);
+    let sealed = cipher
+        .encrypt(
+            nonce,
+            Payload {
+                msg: &plaintext,
+                aad: &aad,
+            },
+        )
+        .map_err(|err| format!("encrypt failed: {err}"))?;
+    if sealed != expected {
+        return Err("ciphertext mismatch".into());
+    }
+    let opened = cipher
+        .decrypt(
+            nonce,
+            Payload {
+                msg: &sealed,
+                aad: &aad,
+            },
+        )
+        .map_err(|err| format!("decrypt failed: {err}"))?;
+    if opened != plaintext {
+        return Err("plaintext mismatch".into());
+    }
+    Ok(())
+}
+
+fn sha256_known_answer() -> Result<(), String> {
+    let expected = decode("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")?;
+    if Sha256::digest(b"abc").as_slice() != expected.as_slice() {
+        return Err("digest mismatch".into());
+    }
+    Ok(())
+}
+
+fn x25519_known_answer() -> Result<(), String> {
+    let alice = fixed::<32>("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a")?;
+    let bob_public =
+        fixed::<32>("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f")?;
+    let expected = fixed::<32>("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742")?;
+
+    let shared = StaticSecret::from(alice).diffie_hellman(&X25519PublicKey::from(bob_public));
+    if shared.as_bytes() != &expected {
+        return Err("shared secret mismatch".into());
+    }
+    Ok(())
+}
+
+fn ed25519_known_answer() -> Result<(), String> {
+    let secret = fixed::<32>("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")?;
+    let public = fixed::<32>("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")?;
+    let expected = fixed::<64>(
+        "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+    )?;
+
+    let signing = SigningKey::from_bytes(&secret);
+    if signing.verifying_key().to_bytes() != public {
+        return Err("public key mismatch".into());
+    }
+    let signature = signing.sign(b"");
+    if signature.to_bytes() != expected {
+        return Err("signature mismatch".into());
+    }
+    signing
+        .verifying_key()
+        .verify(b"", &signature)
+        .map_err(|err| format!("verification failed: {err}"))
+}
+
+fn envelope_round_trip(key: &[u8; 32]) -> Result<(), String> {
+    let plaintext = b"data guardian self-test";
+    let sealed = seal_payload(&Entropy::Os, key, plaintext).map_err(|err| err.to_string())?;
+    let opened = open_payload(key, &sealed).map_err(|err| err.to_string())?;
+    if opened != plaintext {
+        return Err("round-trip plaintext mismatch".into());
+    }
+    Ok(())
+}
+
+fn envelope_tamper_detect(key: &[u8; 32]) -> Result<(), String> {
+    let mut sealed = seal_payload(&Entropy::Os, key, b"tamper").map_err(|err| err.to_string())?;
+    if let Some(last) = sealed.last_mut() {
+        *last ^= 0x01;
+    }
+    match open_payload(key, &sealed) {
+        Ok(_) => Err("tampered envelope was accepted".into()),
+        Err(_) => Ok(()),
+    }
+}
+
+fn decode(vector: &str) -> Result<Vec<u8>, String> {
+    hex::decode(vector).map_err(|err| format!("invalid test vector: {err}"))
+}
+
+fn fixed<const N: usize>(vector: &str) -> Result<[u8; N], String> {
+    decode(vector)?
+        .try_into()
+        .map_err(|_| format!("vector must be {N} bytes"))
+}
@@ -0,0 +1,32 @@
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+use crate::api::{DGError, DGResult};
+use crate::keyring::{parse_public_pem, KeyAlgorithm};
+
+pub const SIGNATURE_EXTENSION: &str = "sig";
+pub const SIGNATURE_LEN: usize = 64;
+
+pub fn sign(secret: &[u8; 32], data: &[u8]) -> [u8; SIGNATURE_LEN] {
+    SigningKey::from_bytes(secret).sign(data).to_bytes()
+}
+
+pub fn verify(public: &[u8; 32], data: &[u8], signature: &[u8]) -> DGResult<bool> {
+    let signature: [u8; SIGNATURE_LEN] = signature.try_into().map_err(|_| {
+        DGError::Crypto(format!(
+            "detached signature must be {SIGNATURE_LEN} raw bytes, found {}",
+            signature.len()
+        ))
+    })?;
+    let key = VerifyingKey::from_bytes(public)
+        .map_err(|err| DGError::Crypto(format!("invalid ed25519 public key: {err}")))?;
+    Ok(key.verify(data, &Signature::from_bytes(&signature)).is_ok())
+}
+
+pub fn verify_with_pem(pem: &str, data: &[u8], signature: &[u8]) -> DGResult<bool> {
+    match parse_public_pem(pem)? {
+        (KeyAlgorithm::Ed25519, public) => verify(&public, data, signature),
+        (algorithm, _) => Err(DGError::Crypto(format!(
+            "{algorithm:?} keys cannot verify signatures"
+        ))),
+    }
+}
@@ -0,0 +1,456 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use crate::api::{DGError, DGResult};
+
+const STATE_DIR: &str = "state";
+const JOURNAL_FILE: &str = "counters.log";
+#[cfg(feature = "sled-state")]
+const SLED_DIR: &str = "counters.sled";
+const COMPACT_MIN_RECORDS: usize = 1024;
+const COMPACT_RATIO: usize = 4;
+const CHECK_LEN: usize = 16;
+const KEY_USAGE_PREFIX: &str = "key_usage/";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateBackend {
+    #[default]
+    Journal,
+    Sled,
+}
+
+pub trait StateStore: Send + Sync {
+    fn get(&self, key: &str) -> DGResult<u64>;
+    fn add(&self, key: &str, delta: u64) -> DGResult<u64>;
+    fn set(&self, key: &str, value: u64) -> DGResult<()>;
+    fn scan(&self, prefix: &str) -> DGResult<Vec<(String, u64)>>;
+    fn compact(&self) -> DGResult<()>;
+}
+
+pub fn open(data_dir: &Path, backend: StateBackend) -> DGResult<Arc<dyn StateStore>> {
+    let dir = data_dir.join(STATE_DIR);
+    fs::create_dir_all(&dir)
+        .map_err(|err| DGError::Config(format!("unable to create state directory: {err}")))?;
+    match backend {
+        StateBackend::Journal => Ok(Arc::new(JournalStore::open(&dir.join(JOURNAL_FILE))?)),
+        #[cfg(feature = "sled-state")]
+        StateBackend::Sled => Ok(Arc::new(SledStore::open(&dir.join(SLED_DIR))?)),
+        #[cfg(not(feature = "sled-state"))]
+        StateBackend::Sled => Err(DGError::Config(
+            "the sled state backend is not compiled into this build".into(),
+        )),
+    }
+}
+
+pub fn open_read_only(data_dir: &Path, backend: StateBackend) -> DGResult<Arc<dyn StateStore>> {
+    let counters = match backend {
+        StateBackend::Journal => match fs::read(data_dir.join(STATE_DIR).join(JOURNAL_FILE)) {
+            Ok(bytes) => replay(&bytes).counters,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => {
+                return Err(DGError::Config(format!(
+                    "unable to read state journal: {err}"
+                )))
+            }
+        },
+        StateBackend::Sled => BTreeMap::new(),
+    };
+    Ok(Arc::new(MemoryStore::from_counters(counters)))
+}
+
+pub fn key_usage(store: &dyn StateStore, key_id: &str) -> DGResult<u64> {
+    store.get(&format!("{KEY_USAGE_PREFIX}{key_id}"))
+}
+
+pub fn key_usage_all(store: &dyn StateStore) -> DGResult<BTreeMap<String, u64>> {
+    Ok(store
+        .scan(KEY_USAGE_PREFIX)?
+        .into_iter()
+        .map(|(key, count)| (key[KEY_USAGE_PREFIX.len()..].to_owned(), count))
+        .collect())
+}
+
+pub(crate) fn record_key_use(store: &dyn StateStore, key_id: &str) -> DGResult<u64> {
+    store.add(&format!("{KEY_USAGE_PREFIX}{key_id}"), 1)
+}
+
+#[derive(Serialize, Deserialize)]
+struct Record {
+    key: String,
+    value: u64,
+    check: String,
+}
+
+impl Record {
+    fn new(key: &str, value: u64) -> Self {
+        Self {
+            key: key.to_owned(),
+            value,
+            check: checksum(key, value),
+        }
+    }
+
+    fn parse(line: &[u8]) -> Option<Self> {
+        let record: Self = serde_json::from_slice(line).ok()?;
+        (record.check == checksum(&record.key, record.value)).then_some(record)
+    }
+}
+
+struct Replay {
+    counters: BTreeMap<String, u64>,
+    records: usize,
+    discarded: usize,
+}
+
+fn replay(bytes: &[u8]) -> Replay {
+    let mut replay = Replay {
+        counters: BTreeMap::new(),
+        records: 0,
+        discarded: 0,
+    };
+    for line in bytes.split(|byte| *byte == b'\n') {
+        if line.is_empty() {
+            continue;
+        }
+        match Record::parse(line) {
+            Some(record) => {
+                replay.records += 1;
+                apply(&mut replay.counters, record.key, record.value);
+            }
+            None => replay.discarded += 1,
+        }
+    }
+    replay
+}
+
+struct Journal {
+    counters: BTreeMap<String, u64>,
+    file: File,
+    records: usize,
+}
+
+pub struct JournalStore {
+    path: PathBuf,
+    journal: Mutex<Journal>,
+    discarded: usize,
+}
+
+impl JournalStore {
+    pub fn open(path: &Path) -> DGResult<Self> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                return Err(DGError::Config(format!(
+                    "unable to read state journal: {err}"
+                )))
+            }
+        };
+
+        let Replay {
+            counters,
+            records,
+            discarded,
+        } = replay(&bytes);
+        let file = open_append(path)?;
+        let store = Self {
+            path: path.to_path_buf(),
+            journal: Mutex::new(Journal {
+                counters,
+                file,
+                records,
+            }),
+            discarded,
+        };
+        if discarded > 0 {
+            warn!(path = %path.display(), discarded, "dropped corrupt state journal records");
+            store.compact()?;
+        }
+        Ok(store)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn discarded(&self) -> usize {
+        self.discarded
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Journal> {
+        self.journal
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn append(&self, journal: &mut Journal, key: &str, value: u64) -> DGResult<()> {
+        let mut line = serde_json::to_vec(&Record::new(key, value))
+            .map_err(|err| DGError::Internal(format!("unable to encode state record: {err}")))?;
+        line.push(b'\n');
+        journal
+            .file
+            .write_all(&line)
+            .and_then(|_| journal.file.sync_data())
+            .map_err(|err| DGError::Internal(format!("unable to write state journal: {err}")))?;
+        journal.records += 1;
+        apply(&mut journal.counters, key.to_owned(), value);
+        if journal.records > COMPACT_MIN_RECORDS.max(journal.counters.len() * COMPACT_RATIO) {
+            self.rewrite(journal)?;
+        }
+        Ok(())
+    }
+
+    fn rewrite(&self, journal: &mut Journal) -> DGResult<()> {
+        let mut staging = self.path.as_os_str().to_owned();
+        staging.push(".tmp");
+        let staging = PathBuf::from(staging);
+
+        let mut snapshot = Vec::new();
+        for (key, value) in &journal.counters {
+            serde_json::to_writer(&mut snapshot, &Record::new(key, *value)).map_err(|err| {
+                DGError::Internal(format!("unable to encode state record: {err}"))
+            })?;
+            snapshot.push(b'\n');
+        }
+        File::create(&staging)
+            .and_then(|mut file| {
+                file.write_all(&snapshot)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&staging, &self.path))
+            .map_err(|err| DGError::Internal(format!("unable to compact state journal: {err}")))?;
+
+        journal.file = open_append(&self.path)?;
+        debug!(
+            before = journal.records,
+            after = journal.counters.len(),
+            "state journal compacted"
+        );
+        journal.records = journal.counters.len();
+        Ok(())
+    }
+}
+
+impl StateStore for JournalStore {
+    fn get(&self, key: &str) -> DGResult<u64> {
+        Ok(self.lock().counters.get(key).copied().unwrap_or_default())
+    }
+
+    fn add(&self, key: &str, delta: u64) -> DGResult<u64> {
+        let mut journal = self.lock();
+        let value = journal
+            .counters
+            .get(key)
+            .copied()
+            .unwrap_or_default()
+            .saturating_add(delta);
+        self.append(&mut journal, key, value)?;
+        Ok(value)
+    }
+
+    fn set(&self, key: &str, value: u64) -> DGResult<()> {
+        let mut journal = self.lock();
+        if journal.counters.get(key).copied().unwrap_or_default() == value {
+            return Ok(());
+        }
+        self.append(&mut journal, key, value)
+    }
+
+    fn scan(&self, prefix: &str) -> DGResult<Vec<(String, u64)>> {
+        Ok(self
+            .lock()
+            .counters
+            .range(prefix.to_owned()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| (key.clone(), *value))
+            .collect())
+    }
+
+    fn compact(&self) -> DGResult<()> {
+        let mut journal = self.lock();
+        self.rewrite(&mut journal)
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryStore {
+    counters: Mutex<BTreeMap<String, u64>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn from_counters(counters: BTreeMap<String, u64>) -> Self {
+        Self {
+            counters: Mutex::new(counters),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, u64>> {
+        self.counters
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl StateStore for MemoryStore {
+    fn get(&self, key: &str) -> DGResult<u64> {
+        Ok(self.lock().get(key).copied().unwrap_or_default())
+    }
+
+    fn add(&self, key: &str, delta: u64) -> DGResult<u64> {
+        let mut counters = self.lock();
+        let value = counters
+            .get(key)
+            .copied()
+            .unwrap_or_default()
+            .saturating_add(delta);
+        apply(&mut counters, key.to_owned(), value);
+        Ok(value)
+    }
+
+    fn set(&self, key: &str, value: u64) -> DGResult<()> {
+        apply(&mut self.lock(), key.to_owned(), value);
+        Ok(())
+    }
+
+    fn scan(&self, prefix: &str) -> DGResult<Vec<(String, u64)>> {
+        Ok(self
+            .lock()
+            .range(prefix.to_owned()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| (key.clone(), *value))
+            .collect())
+    }
+
+    fn compact(&self) -> DGResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sled-state")]
+pub struct SledStore {
+    db: sled::Db,
+}
+
+#[cfg(feature = "sled-state")]
+impl SledStore {
+    pub fn open(path: &Path) -> DGResult<Self> {
+        let db = match sled::open(path) {
+            Ok(db) => db,
+            Err(err) => {
+                let mut quarantine = path.as_os_str().to_owned();
+                quarantine.push(".corrupt");
+                let quarantine = PathBuf::from(quarantine);
+                warn!(path = %path.display(), error = %err, "moving unreadable state database aside");
+                let _ = fs::remove_dir_all(&quarantine);
+                fs::rename(path, &quarantine).map_err(|err| {
+                    DGError::Config(format!("unable to quarantine state database: {err}"))
+                })?;
+                sled::open(path).map_err(sled_error)?
+            }
+        };
+        Ok(Self { db })
+    }
+}
+
+#[cfg(feature = "sled-state")]
+impl StateStore for SledStore {
+    fn get(&self, key: &str) -> DGResult<u64> {
+        Ok(decode(self.db.get(key).map_err(sled_error)?.as_deref()))
+    }
+
+    fn add(&self, key: &str, delta: u64) -> DGResult<u64> {
+        let updated = self
+            .db
+            .update_and_fetch(key, |old| {
+                Some(decode(old).saturating_add(delta).to_be_bytes().to_vec())
+            })
+            .map_err(sled_error)?;
+        self.db.flush().map_err(sled_error)?;
+        Ok(decode(updated.as_deref()))
+    }
+
+    fn set(&self, key: &str, value: u64) -> DGResult<()> {
+        if value == 0 {
+            self.db.remove(key).map_err(sled_error)?;
+        } else {
+            self.db
+                .insert(key, &value.to_be_bytes())
+                .map_err(sled_error)?;
+        }
+        self.db.flush().map_err(sled_error)?;
+        Ok(())
+    }
+
+    fn scan(&self, prefix: &str) -> DGResult<Vec<(String, u64)>> {
+        self.db
+            .scan_prefix(prefix)
+            .map(|entry| {
+                let (key, value) = entry.map_err(sled_error)?;
+                Ok((
+                    String::from_utf8_lossy(&key).into_owned(),
+                    decode(Some(&value)),
+                ))
+            })
+            .collect()
+    }
+
+    fn compact(&self) -> DGResult<()> {
+        for entry in self.db.iter() {
+            let (key, value) = entry.map_err(sled_error)?;
+            if decode(Some(&value)) == 0 {
+                self.db.remove(key).map_err(sled_error)?;
+            }
+        }
+        self.db.flush().map_err(sled_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sled-state")]
+fn decode(bytes: Option<&[u8]>) -> u64 {
+    bytes
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_be_bytes)
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "sled-state")]
+fn sled_error(err: sled::Error) -> DGError {
+    DGError::Internal(format!("state database error: {err}"))
+}
+
+fn apply(counters: &mut BTreeMap<String, u64>, key: String, value: u64) {
+    if value == 0 {
+        counters.remove(&key);
+    } else {
+        counters.insert(key, value);
+    }
+}
+
+fn checksum(key: &str, value: u64) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    hasher.update([0]);
+    hasher.update(value.to_be_bytes());
+    hex::encode(&hasher.finalize()[..CHECK_LEN / 2])
+}
+
+fn open_append(path: &Path) -> DGResult<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| DGError::Config(format!("unable to open state journal: {err}")))
+}
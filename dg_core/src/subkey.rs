@@ -0,0 +1,118 @@
+use std::fmt;
+
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+
+use crate::keyring::{KeyAlgorithm, KeyMetadata};
+use crate::signature;
+
+const CERTIFICATE_CONTEXT: &[u8] = b"data-guardian/subkey-certificate/v1";
+
+// A root identity only certifies subkeys; each subkey does one job and is rotated on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyPurpose {
+    Root,
+    Encryption,
+    Signing,
+    Escrow,
+}
+
+pub const SUBKEY_PURPOSES: &[KeyPurpose] = &[
+    KeyPurpose::Encryption,
+    KeyPurpose::Signing,
+    KeyPurpose::Escrow,
+];
+
+impl KeyPurpose {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KeyPurpose::Root => "root",
+            KeyPurpose::Encryption => "encryption",
+            KeyPurpose::Signing => "signing",
+            KeyPurpose::Escrow => "escrow",
+        }
+    }
+
+    pub fn algorithm(self) -> KeyAlgorithm {
+        match self {
+            KeyPurpose::Root | KeyPurpose::Signing => KeyAlgorithm::Ed25519,
+            KeyPurpose::Encryption | KeyPurpose::Escrow => KeyAlgorithm::X25519,
+        }
+    }
+}
+
+impl fmt::Display for KeyPurpose {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// The root's Ed25519 signature over the subkey's purpose, public key and issue time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubkeyCertificate {
+    pub root: String,
+    pub issued_at: u64,
+    pub signature: String,
+}
+
+// A root identity and the subkeys it certified when it was generated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyHierarchy {
+    pub root: KeyMetadata,
+    pub subkeys: Vec<KeyMetadata>,
+}
+
+impl KeyHierarchy {
+    pub fn subkey(&self, purpose: KeyPurpose) -> Option<&KeyMetadata> {
+        self.subkeys
+            .iter()
+            .find(|meta| meta.purpose == Some(purpose))
+    }
+}
+
+pub(crate) fn certify(
+    root_secret: &[u8; 32],
+    root_id: &str,
+    purpose: KeyPurpose,
+    public: &[u8],
+    issued_at: u64,
+) -> SubkeyCertificate {
+    let signature = signature::sign(root_secret, &message(purpose, public, issued_at));
+    SubkeyCertificate {
+        root: root_id.to_owned(),
+        issued_at,
+        signature: general_purpose::STANDARD.encode(signature),
+    }
+}
+
+// Whether `meta` carries a certificate that `root_public` signed for its purpose and key.
+pub fn is_certified(root_public: &[u8; 32], meta: &KeyMetadata) -> bool {
+    let (Some(purpose), Some(certificate)) = (meta.purpose, &meta.certificate) else {
+        return false;
+    };
+    let (Ok(public), Ok(signature)) = (
+        general_purpose::STANDARD.decode(&meta.public_key),
+        general_purpose::STANDARD.decode(&certificate.signature),
+    ) else {
+        return false;
+    };
+    purpose != KeyPurpose::Root
+        && meta.algorithm == purpose.algorithm()
+        && signature::verify(
+            root_public,
+            &message(purpose, &public, certificate.issued_at),
+            &signature,
+        )
+        .unwrap_or(false)
+}
+
+fn message(purpose: KeyPurpose, public: &[u8], issued_at: u64) -> Vec<u8> {
+    let mut message = CERTIFICATE_CONTEXT.to_vec();
+    message.push(0);
+    message.extend_from_slice(purpose.as_str().as_bytes());
+    message.push(0);
+    message.extend_from_slice(public);
+    message.extend_from_slice(&issued_at.to_be_bytes());
+    message
+}
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::api::{DGError, DGResult};
+use crate::entropy::Entropy;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThresholdSpec {
+    pub required: u8,
+    pub total: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyShare {
+    pub key_id: String,
+    pub index: u8,
+    pub share: String,
+}
+
+pub(crate) fn split(
+    entropy: &Entropy,
+    secret: &[u8; 32],
+    required: u8,
+    total: u8,
+) -> DGResult<Vec<(u8, [u8; 32])>> {
+    if required == 0 || required > total {
+        return Err(DGError::Config(format!(
+            "threshold must be between 1 and the number of custodians ({total}), got {required}"
+        )));
+    }
+
+    let mut coefficients = Zeroizing::new(vec![0u8; 32 * (usize::from(required) - 1)]);
+    entropy.fill(&mut coefficients);
+
+    Ok((1..=total)
+        .map(|x| {
+            let mut value = [0u8; 32];
+            for (pos, byte) in value.iter_mut().enumerate() {
+                let mut acc = 0u8;
+                for coefficient in coefficients.chunks_exact(32).rev() {
+                    acc = gf_mul(acc, x) ^ coefficient[pos];
+                }
+                *byte = gf_mul(acc, x) ^ secret[pos];
+            }
+            (x, value)
+        })
+        .collect())
+}
+
+pub(crate) fn combine(shares: &[(u8, [u8; 32])], required: u8) -> DGResult<[u8; 32]> {
+    let distinct: BTreeMap<u8, [u8; 32]> = shares.iter().copied().collect();
+    if distinct.contains_key(&0) {
+        return Err(DGError::Crypto("share index 0 is invalid".into()));
+    }
+    if distinct.len() < usize::from(required) {
+        return Err(DGError::Crypto(format!(
+            "{} of {required} required shares provided",
+            distinct.len()
+        )));
+    }
+
+    let points: Vec<(u8, [u8; 32])> = distinct.into_iter().take(required.into()).collect();
+    let mut secret = [0u8; 32];
+    for (i, (xi, yi)) in points.iter().enumerate() {
+        let mut basis = 1u8;
+        for (j, (xj, _)) in points.iter().enumerate() {
+            if i != j {
+                basis = gf_mul(basis, gf_div(*xj, xj ^ xi));
+            }
+        }
+        for (byte, share) in secret.iter_mut().zip(yi.iter()) {
+            *byte ^= gf_mul(basis, *share);
+        }
+    }
+    Ok(secret)
+}
+
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+fn gf_div(a: u8, b: u8) -> u8 {
+    let mut inverse = 1u8;
+    for _ in 0..254 {
+        inverse = gf_mul(inverse, b);
+    }
+    gf_mul(a, inverse)
+}
@@ -0,0 +1,361 @@
+use std::path::{Component, Path, PathBuf};
+
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+
+use crate::api::{DGError, DGResult};
+use crate::fingerprint::Fingerprint;
+use crate::keyring::{parse_public_pem, KeyAlgorithm, Keyring};
+use crate::signature::{self, SIGNATURE_EXTENSION};
+
+pub const TRUST_DIR: &str = "trust";
+pub const TRUST_FILE: &str = "signers.json";
+pub const BUNDLE_MANIFEST: &str = "SHA256SUMS";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustRole {
+    Policy,
+    Envelope,
+    Runtime,
+    Management,
+}
+
+impl TrustRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TrustRole::Policy => "policy",
+            TrustRole::Envelope => "envelope",
+            TrustRole::Runtime => "runtime",
+            TrustRole::Management => "management",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedSigner {
+    pub id: String,
+    #[serde(default)]
+    pub label: String,
+    pub public_key: String,
+    pub fingerprint: Fingerprint,
+    pub roles: Vec<TrustRole>,
+    #[serde(default)]
+    pub pinned: bool,
+    pub added_at: u64,
+}
+
+impl TrustedSigner {
+    pub fn has_role(&self, role: TrustRole) -> bool {
+        self.roles.contains(&role)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewSigner {
+    pub id: String,
+    #[serde(default)]
+    pub label: String,
+    pub public_key: String,
+    pub roles: Vec<TrustRole>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrustStore {
+    pub signers: Vec<TrustedSigner>,
+}
+
+impl TrustStore {
+    pub fn get(&self, id: &str) -> Option<&TrustedSigner> {
+        self.signers.iter().find(|signer| signer.id == id)
+    }
+
+    pub fn signers_for(&self, role: TrustRole) -> impl Iterator<Item = &TrustedSigner> {
+        self.signers
+            .iter()
+            .filter(move |signer| signer.has_role(role))
+    }
+
+    pub fn requires(&self, role: TrustRole) -> bool {
+        self.signers_for(role).next().is_some()
+    }
+
+    // Re-importing an id replaces its key unless the signer is pinned to the key it has.
+    pub fn import(&mut self, signer: NewSigner, now: u64) -> DGResult<TrustedSigner> {
+        let id = signer.id.trim();
+        if id.is_empty() {
+            return Err(DGError::Config(
+                "trusted signer id must not be empty".into(),
+            ));
+        }
+        if signer.roles.is_empty() {
+            return Err(DGError::Config(format!(
+                "trusted signer {id} needs at least one role"
+            )));
+        }
+        let public = match parse_public_pem(&signer.public_key)? {
+            (KeyAlgorithm::Ed25519, public) => public,
+            (algorithm, _) => {
+                return Err(DGError::Crypto(format!(
+                    "{algorithm:?} keys cannot sign; trusted signers must be Ed25519"
+                )))
+            }
+        };
+        let mut roles = signer.roles;
+        roles.sort_by_key(|role| role.as_str());
+        roles.dedup();
+        let imported = TrustedSigner {
+            id: id.to_owned(),
+            label: signer.label.trim().to_owned(),
+            public_key: signer.public_key.trim().to_owned(),
+            fingerprint: Fingerprint::compute(KeyAlgorithm::Ed25519, &public),
+            roles,
+            pinned: false,
+            added_at: now,
+        };
+        match self.signers.iter_mut().find(|existing| existing.id == id) {
+            Some(existing) if existing.pinned && existing.fingerprint != imported.fingerprint => {
+                Err(DGError::PolicyDenied(format!(
+                    "trusted signer {id} is pinned to another key; unpin it before replacing the key"
+                )))
+            }
+            Some(existing) => {
+                *existing = TrustedSigner {
+                    pinned: existing.pinned,
+                    ..imported
+                };
+                Ok(existing.clone())
+            }
+            None => {
+                self.signers.push(imported.clone());
+                Ok(imported)
+            }
+        }
+    }
+
+    pub fn remove(&mut self, id: &str) -> DGResult<TrustedSigner> {
+        let position = self
+            .signers
+            .iter()
+            .position(|signer| signer.id == id)
+            .ok_or_else(|| DGError::Config(format!("no trusted signer named {id}")))?;
+        if self.signers[position].pinned {
+            return Err(DGError::PolicyDenied(format!(
+                "trusted signer {id} is pinned; unpin it before removing it"
+            )));
+        }
+        Ok(self.signers.remove(position))
+    }
+
+    pub fn pin(&mut self, id: &str, pinned: bool) -> DGResult<TrustedSigner> {
+        let signer = self
+            .signers
+            .iter_mut()
+            .find(|signer| signer.id == id)
+            .ok_or_else(|| DGError::Config(format!("no trusted signer named {id}")))?;
+        signer.pinned = pinned;
+        Ok(signer.clone())
+    }
+
+    pub fn verify(
+        &self,
+        role: TrustRole,
+        data: &[u8],
+        detached: &[u8],
+    ) -> DGResult<Option<&TrustedSigner>> {
+        for signer in self.signers_for(role) {
+            if signature::verify_with_pem(&signer.public_key, data, detached)? {
+                return Ok(Some(signer));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn to_payload(&self) -> DGResult<Vec<u8>> {
+        serde_json::to_vec(self)
+            .map_err(|err| DGError::Config(format!("unable to encode trust store: {err}")))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedTrustStore {
+    pub payload: String,
+    pub signed_by: String,
+    pub signature: String,
+}
+
+impl SignedTrustStore {
+    pub fn new(payload: &[u8], signed_by: &str, signature: &[u8]) -> Self {
+        Self {
+            payload: general_purpose::STANDARD.encode(payload),
+            signed_by: signed_by.to_owned(),
+            signature: general_purpose::STANDARD.encode(signature),
+        }
+    }
+
+    pub fn payload(&self) -> DGResult<Vec<u8>> {
+        general_purpose::STANDARD
+            .decode(self.payload.trim())
+            .map_err(|err| DGError::Config(format!("trust store payload is not base64: {err}")))
+    }
+
+    pub fn signature(&self) -> DGResult<Vec<u8>> {
+        general_purpose::STANDARD
+            .decode(self.signature.trim())
+            .map_err(|err| DGError::Config(format!("trust store signature is not base64: {err}")))
+    }
+
+    pub fn store(&self) -> DGResult<TrustStore> {
+        serde_json::from_slice(&self.payload()?)
+            .map_err(|err| DGError::Config(format!("unreadable trust store: {err}")))
+    }
+
+    pub fn verify_with(&self, keyring: &Keyring) -> DGResult<TrustStore> {
+        let (meta, public) = keyring.ed25519_public(&self.signed_by).ok_or_else(|| {
+            DGError::Crypto(format!(
+                "trust store is signed by {}, which is not an Ed25519 key in the keyring",
+                self.signed_by
+            ))
+        })?;
+        if meta.revoked_at.is_some() {
+            return Err(DGError::Crypto(format!(
+                "trust store signer {} is revoked",
+                meta.id
+            )));
+        }
+        if !signature::verify(&public, &self.payload()?, &self.signature()?)? {
+            return Err(DGError::Crypto(
+                "trust store signature does not verify; it was changed outside Data Guardian"
+                    .into(),
+            ));
+        }
+        self.store()
+    }
+
+    pub async fn read(data_dir: &Path) -> DGResult<Option<Self>> {
+        let path = store_path(data_dir);
+        let raw = match fs::read(&path).await {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(DGError::Config(format!(
+                    "unable to read {}: {err}",
+                    path.display()
+                )))
+            }
+        };
+        serde_json::from_slice(&raw)
+            .map(Some)
+            .map_err(|err| DGError::Config(format!("unreadable {}: {err}", path.display())))
+    }
+
+    pub async fn write(&self, data_dir: &Path) -> DGResult<()> {
+        let path = store_path(data_dir);
+        let io = |err: std::io::Error| {
+            DGError::Config(format!("unable to write {}: {err}", path.display()))
+        };
+        fs::create_dir_all(data_dir.join(TRUST_DIR))
+            .await
+            .map_err(io)?;
+        let staging = path.with_extension("json.tmp");
+        let encoded = serde_json::to_vec_pretty(self)
+            .map_err(|err| DGError::Config(format!("unable to encode trust store: {err}")))?;
+        fs::write(&staging, encoded).await.map_err(io)?;
+        fs::rename(&staging, &path).await.map_err(io)
+    }
+}
+
+pub fn store_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(TRUST_DIR).join(TRUST_FILE)
+}
+
+pub async fn load_verified(data_dir: &Path, keyring: &Keyring) -> DGResult<TrustStore> {
+    match SignedTrustStore::read(data_dir).await? {
+        Some(signed) => signed.verify_with(keyring),
+        None => Ok(TrustStore::default()),
+    }
+}
+
+// A bundle carries a `SHA256SUMS` manifest signed by a runtime signer; every file must be listed with its digest.
+pub async fn verify_bundle(store: &TrustStore, dir: &Path) -> DGResult<TrustedSigner> {
+    let manifest_path = dir.join(BUNDLE_MANIFEST);
+    let signature_path = dir.join(format!("{BUNDLE_MANIFEST}.{SIGNATURE_EXTENSION}"));
+    let unreadable = |path: &Path, err: std::io::Error| {
+        DGError::Config(format!("unable to read {}: {err}", path.display()))
+    };
+    let manifest = fs::read(&manifest_path)
+        .await
+        .map_err(|err| unreadable(&manifest_path, err))?;
+    let detached = fs::read(&signature_path)
+        .await
+        .map_err(|err| unreadable(&signature_path, err))?;
+    let signer = store
+        .verify(TrustRole::Runtime, &manifest, &detached)?
+        .cloned()
+        .ok_or_else(|| {
+            DGError::Crypto(format!(
+                "{} is not signed by a trusted runtime signer",
+                manifest_path.display()
+            ))
+        })?;
+
+    let manifest = std::str::from_utf8(&manifest)
+        .map_err(|_| DGError::Config(format!("{} is not UTF-8", manifest_path.display())))?;
+    let mut listed = Vec::new();
+    for line in manifest.lines().filter(|line| !line.trim().is_empty()) {
+        let (digest, name) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| DGError::Config(format!("malformed manifest line '{line}'")))?;
+        let name = name.trim_start().trim_start_matches('*');
+        let relative = Path::new(name);
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(DGError::Config(format!(
+                "manifest entry {name} leaves the bundle"
+            )));
+        }
+        let path = dir.join(relative);
+        let contents = fs::read(&path)
+            .await
+            .map_err(|err| unreadable(&path, err))?;
+        if hex::encode(Sha256::digest(&contents)) != digest.to_ascii_lowercase() {
+            return Err(DGError::Crypto(format!(
+                "{} does not match the signed manifest",
+                path.display()
+            )));
+        }
+        listed.push(path);
+    }
+
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let mut entries = fs::read_dir(&current)
+            .await
+            .map_err(|err| unreadable(&current, err))?;
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|err| unreadable(&current, err))?
+        {
+            let path = entry.path();
+            let kind = entry
+                .file_type()
+                .await
+                .map_err(|err| unreadable(&path, err))?;
+            if kind.is_dir() {
+                pending.push(path);
+            } else if path != manifest_path && path != signature_path && !listed.contains(&path) {
+                return Err(DGError::Crypto(format!(
+                    "{} is not listed in the signed manifest",
+                    path.display()
+                )));
+            }
+        }
+    }
+    Ok(signer)
+}
@@ -0,0 +1,30 @@
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmupStep {
+    pub name: String,
+    pub elapsed_us: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WarmupReport {
+    pub steps: Vec<WarmupStep>,
+}
+
+impl WarmupReport {
+    pub fn elapsed_us(&self) -> u64 {
+        self.steps.iter().map(|step| step.elapsed_us).sum()
+    }
+
+    pub fn push(&mut self, name: &str, started: Instant, detail: Option<String>) {
+        self.steps.push(WarmupStep {
+            name: name.to_owned(),
+            elapsed_us: u64::try_from(started.elapsed().as_micros()).unwrap_or(u64::MAX),
+            detail,
+        });
+    }
+}
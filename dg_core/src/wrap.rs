@@ -0,0 +1,138 @@
+use base64::{engine::general_purpose, Engine as _};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+use crate::api::{DGError, DGResult};
+use crate::engine::{open_payload, seal_payload};
+use crate::entropy::Entropy;
+use crate::keyring::key_id;
+
+const WRAP_INFO: &[u8] = b"data-guardian/x25519-key-wrap/v1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WrapKind {
+    Master,
+    Escrow,
+    Share,
+    Grant,
+    Recipient,
+    // Only in offline responses: the file key wrapped to a request's one-time reply key.
+    Offline,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyWrap {
+    pub kind: WrapKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ephemeral_public: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub share_index: Option<u8>,
+    // The root identity that certified `key_id` when the file key was wrapped to a subkey.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    pub wrapped: String,
+}
+
+pub(crate) fn wrap_symmetric(
+    entropy: &Entropy,
+    kind: WrapKind,
+    kek: &[u8; 32],
+    file_key: &[u8; 32],
+) -> DGResult<KeyWrap> {
+    Ok(KeyWrap {
+        kind,
+        key_id: None,
+        ephemeral_public: None,
+        share_index: None,
+        root: None,
+        wrapped: general_purpose::STANDARD.encode(seal_payload(entropy, kek, file_key)?),
+    })
+}
+
+pub(crate) fn wrap_x25519(
+    entropy: &Entropy,
+    kind: WrapKind,
+    recipient: &[u8; 32],
+    file_key: &[u8; 32],
+) -> DGResult<KeyWrap> {
+    let recipient = X25519PublicKey::from(*recipient);
+    let ephemeral = StaticSecret::from(*entropy.key());
+    let ephemeral_public = X25519PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(&recipient);
+    let kek = derive_kek(shared.as_bytes(), &ephemeral_public, &recipient)?;
+
+    Ok(KeyWrap {
+        kind,
+        key_id: Some(key_id(recipient.as_bytes())),
+        ephemeral_public: Some(general_purpose::STANDARD.encode(ephemeral_public.as_bytes())),
+        share_index: None,
+        root: None,
+        wrapped: general_purpose::STANDARD.encode(seal_payload(entropy, &kek, file_key)?),
+    })
+}
+
+pub(crate) fn wrap_share(
+    entropy: &Entropy,
+    recipient: &[u8; 32],
+    index: u8,
+    share: &[u8; 32],
+) -> DGResult<KeyWrap> {
+    let mut wrap = wrap_x25519(entropy, WrapKind::Share, recipient, share)?;
+    wrap.share_index = Some(index);
+    Ok(wrap)
+}
+
+pub(crate) fn unwrap_symmetric(kek: &[u8; 32], wrap: &KeyWrap) -> DGResult<Zeroizing<[u8; 32]>> {
+    open_file_key(kek, wrap)
+}
+
+pub(crate) fn unwrap_x25519(secret: &[u8; 32], wrap: &KeyWrap) -> DGResult<Zeroizing<[u8; 32]>> {
+    let ephemeral = wrap
+        .ephemeral_public
+        .as_deref()
+        .ok_or_else(|| DGError::Crypto("key wrap missing ephemeral public key".into()))?;
+    let ephemeral: [u8; 32] = general_purpose::STANDARD
+        .decode(ephemeral)
+        .map_err(|err| DGError::Crypto(format!("invalid ephemeral public key: {err}")))?
+        .try_into()
+        .map_err(|_| DGError::Crypto("ephemeral public key must be 32 bytes".into()))?;
+    let ephemeral = X25519PublicKey::from(ephemeral);
+    let secret = StaticSecret::from(*secret);
+    let recipient = X25519PublicKey::from(&secret);
+    let shared = secret.diffie_hellman(&ephemeral);
+    let kek = derive_kek(shared.as_bytes(), &ephemeral, &recipient)?;
+    open_file_key(&kek, wrap)
+}
+
+fn derive_kek(
+    shared: &[u8; 32],
+    ephemeral: &X25519PublicKey,
+    recipient: &X25519PublicKey,
+) -> DGResult<Zeroizing<[u8; 32]>> {
+    let mut salt = Vec::with_capacity(64);
+    salt.extend_from_slice(ephemeral.as_bytes());
+    salt.extend_from_slice(recipient.as_bytes());
+    let mut kek = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(Some(&salt), shared)
+        .expand(WRAP_INFO, &mut *kek)
+        .map_err(|err| DGError::Crypto(format!("key derivation failed: {err}")))?;
+    Ok(kek)
+}
+
+fn open_file_key(kek: &[u8; 32], wrap: &KeyWrap) -> DGResult<Zeroizing<[u8; 32]>> {
+    let sealed = general_purpose::STANDARD
+        .decode(&wrap.wrapped)
+        .map_err(|err| DGError::Crypto(format!("invalid wrapped key encoding: {err}")))?;
+    let opened = Zeroizing::new(open_payload(kek, &sealed)?);
+    let file_key: [u8; 32] = opened
+        .as_slice()
+        .try_into()
+        .map_err(|_| DGError::Crypto("unwrapped file key must be 32 bytes".into()))?;
+    Ok(Zeroizing::new(file_key))
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use common::engine;
+use dg_core::api::{DGError, EncryptRequest};
+use dg_core::AuditFilter;
+use serde_json::json;
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn policy_decisions_are_recorded_and_queryable() {
+    let temp = tempdir().expect("tempdir");
+    let policy = json!({
+        "default_allow": true,
+        "rules": [{ "subject": "user:mallory", "action": "*", "resource": "*", "effect": "deny" }],
+    });
+    std::fs::write(
+        temp.path().join("policy.json"),
+        serde_json::to_vec(&policy).expect("json"),
+    )
+    .expect("policy");
+    let engine = engine(temp.path()).await;
+
+    let envelope = engine
+        .encrypt(EncryptRequest {
+            plaintext: b"payroll".to_vec(),
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+    engine.decrypt(envelope).await.expect("decrypt");
+    assert!(engine
+        .check_policy("user:alice", "share", "/srv/payroll.csv")
+        .await
+        .expect("check"));
+    assert!(!engine
+        .check_policy("user:mallory", "share", "/srv/payroll.csv")
+        .await
+        .expect("check"));
+
+    let everything = engine
+        .query_audit(AuditFilter::default())
+        .await
+        .expect("query");
+    let actions: Vec<&str> = everything
+        .iter()
+        .map(|event| event.action.as_str())
+        .collect();
+    assert_eq!(actions, ["share", "share", "decrypt", "encrypt"]);
+    assert!(everything
+        .windows(2)
+        .all(|pair| pair[0].timestamp >= pair[1].timestamp));
+
+    let denied = engine
+        .query_audit(AuditFilter {
+            effect: Some("deny".into()),
+            ..Default::default()
+        })
+        .await
+        .expect("query");
+    assert_eq!(denied.len(), 1);
+    assert_eq!(denied[0].subject, "user:mallory");
+    assert_eq!(denied[0].resource, "/srv/payroll.csv");
+
+    let latest = engine
+        .query_audit(AuditFilter {
+            resource: Some("payroll".into()),
+            limit: Some(1),
+            ..Default::default()
+        })
+        .await
+        .expect("query");
+    assert_eq!(latest.len(), 1);
+    assert_eq!(latest[0].subject, "user:mallory");
+
+    let future = engine
+        .query_audit(AuditFilter {
+            since: Some(everything[0].timestamp + 1),
+            ..Default::default()
+        })
+        .await
+        .expect("query");
+    assert!(future.is_empty());
+}
+
+#[tokio::test]
+async fn denied_operations_are_recorded() {
+    let temp = tempdir().expect("tempdir");
+    std::fs::write(
+        temp.path().join("policy.json"),
+        br#"{ "default_allow": false, "rules": [] }"#,
+    )
+    .expect("policy");
+    let engine = engine(temp.path()).await;
+
+    let err = engine
+        .encrypt(EncryptRequest {
+            plaintext: b"payroll".to_vec(),
+            ..Default::default()
+        })
+        .await
+        .expect_err("denied");
+    assert!(matches!(err, DGError::PolicyDenied(_)));
+    let events = engine
+        .query_audit(AuditFilter {
+            action: Some("encrypt".into()),
+            ..Default::default()
+        })
+        .await
+        .expect("query");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].effect, "deny");
+}
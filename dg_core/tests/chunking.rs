@@ -0,0 +1,377 @@
+mod common;
+
+use common::engine;
+use dg_core::api::{DGError, EncryptRequest};
+use dg_core::chunking::{self, ChunkingParams, DedupStats};
+use dg_core::RekeyContext;
+use tempfile::tempdir;
+
+// Incompressible but reproducible content, so FastCDC finds its usual spread of boundaries.
+fn noise(len: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed | 1;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+fn small_chunks() -> ChunkingParams {
+    ChunkingParams {
+        min_size: 1024,
+        avg_size: 4096,
+        max_size: 16384,
+    }
+}
+
+#[tokio::test]
+async fn unchanged_chunks_are_reused_from_the_previous_version() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    let original = noise(512 * 1024, 7);
+    let first = engine
+        .encrypt(EncryptRequest {
+            plaintext: original.clone(),
+            chunking: Some(small_chunks()),
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+    let first_table = chunking::read(&first.meta)
+        .expect("table")
+        .expect("chunked");
+    assert!(first_table.chunks.len() > 20);
+    assert_eq!(first_table.plaintext_len(), original.len() as u64);
+    assert_eq!(
+        *engine.decrypt(first.clone()).await.expect("decrypt"),
+        original
+    );
+
+    let mut edited = original.clone();
+    edited.splice(200_000..200_010, b"1 KB edit".iter().copied());
+    let second = engine
+        .encrypt(EncryptRequest {
+            plaintext: edited.clone(),
+            previous: Some(first.clone()),
+            ..Default::default()
+        })
+        .await
+        .expect("re-encrypt");
+    let second_table = chunking::read(&second.meta)
+        .expect("table")
+        .expect("chunked");
+    assert_eq!(second_table.params, small_chunks());
+    let stats = DedupStats::between(&first_table, &second_table);
+    assert_eq!(stats.bytes, edited.len() as u64);
+    assert!(stats.chunks - stats.reused_chunks <= 3, "{stats:?}");
+    assert!(stats.reused_bytes > stats.bytes * 9 / 10, "{stats:?}");
+    // Reused chunks are copied verbatim, so the payloads share their leading segment.
+    assert_eq!(second.bytes[..64], first.bytes[..64]);
+    assert_eq!(*engine.decrypt(second).await.expect("decrypt"), edited);
+}
+
+#[tokio::test]
+async fn tampered_chunk_tables_are_rejected() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    let envelope = engine
+        .encrypt(EncryptRequest {
+            plaintext: noise(64 * 1024, 3),
+            chunking: Some(small_chunks()),
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+
+    let mut reordered = envelope.clone();
+    let chunks = reordered.meta["chunks"]["chunks"]
+        .as_array_mut()
+        .expect("chunk list");
+    assert!(chunks.len() > 2);
+    chunks.swap(0, 1);
+    assert!(matches!(
+        engine.decrypt(reordered).await,
+        Err(DGError::Crypto(_))
+    ));
+
+    let mut truncated = envelope.clone();
+    truncated.bytes.truncate(envelope.bytes.len() - 1);
+    assert!(matches!(
+        engine.decrypt(truncated).await,
+        Err(DGError::Crypto(_))
+    ));
+
+    let mut flattened = envelope;
+    flattened
+        .meta
+        .as_object_mut()
+        .expect("meta")
+        .remove("chunks");
+    assert!(engine.decrypt(flattened).await.is_err());
+}
+
+#[tokio::test]
+async fn rekeyed_and_empty_envelopes_stay_chunked() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    let plaintext = noise(96 * 1024, 11);
+    let envelope = engine
+        .encrypt(EncryptRequest {
+            plaintext: plaintext.clone(),
+            chunking: Some(small_chunks()),
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+    let rekeyed = engine
+        .rekey(envelope.clone(), RekeyContext::rekey("alice"))
+        .await
+        .expect("rekey");
+    let before = chunking::read(&envelope.meta)
+        .expect("table")
+        .expect("chunked");
+    let after = chunking::read(&rekeyed.meta)
+        .expect("table")
+        .expect("chunked");
+    assert_eq!(after.params, before.params);
+    assert_eq!(DedupStats::between(&before, &after).reused_chunks, 0);
+    assert_eq!(*engine.decrypt(rekeyed).await.expect("decrypt"), plaintext);
+
+    let empty = engine
+        .encrypt(EncryptRequest {
+            chunking: Some(ChunkingParams::default()),
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt empty");
+    assert_eq!(
+        chunking::read(&empty.meta)
+            .expect("table")
+            .expect("chunked")
+            .chunks
+            .len(),
+        1
+    );
+    assert!(engine.decrypt(empty).await.expect("decrypt").is_empty());
+}
+
+#[tokio::test]
+async fn invalid_chunk_sizes_are_refused() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    for params in [
+        ChunkingParams {
+            min_size: 8,
+            ..ChunkingParams::default()
+        },
+        ChunkingParams {
+            min_size: 128 * 1024,
+            avg_size: 64 * 1024,
+            max_size: 256 * 1024,
+        },
+    ] {
+        assert!(params.validate().is_err());
+        let result = engine
+            .encrypt(EncryptRequest {
+                plaintext: b"data".to_vec(),
+                chunking: Some(params),
+                ..Default::default()
+            })
+            .await;
+        assert!(matches!(result, Err(DGError::Config(_))));
+    }
+}
+
+#[tokio::test]
+async fn merkle_proofs_place_each_chunk_under_the_root() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    let envelope = engine
+        .encrypt(EncryptRequest {
+            plaintext: noise(128 * 1024, 5),
+            chunking: Some(small_chunks()),
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+    let table = chunking::read(&envelope.meta)
+        .expect("table")
+        .expect("chunked");
+    let root = table.root.clone().expect("merkle root");
+    assert_eq!(table.merkle_root().expect("root").to_hex().as_str(), root);
+
+    for (index, chunk) in table.chunks.iter().enumerate() {
+        let proof = table.proof(index).expect("proof");
+        assert!(proof.verify(&root, chunk), "chunk {index}");
+        let other = &table.chunks[(index + 1) % table.chunks.len()];
+        assert!(
+            !proof.verify(&root, other),
+            "chunk {index} against its neighbour"
+        );
+        let mut shifted = proof.clone();
+        shifted.offset += 1;
+        assert!(!shifted.verify(&root, chunk));
+    }
+    assert!(table.proof(table.chunks.len()).is_err());
+
+    let mut forged = envelope.clone();
+    forged.meta["chunks"]["root"] = "00".repeat(32).into();
+    assert!(matches!(
+        engine.decrypt(forged).await,
+        Err(DGError::Crypto(_))
+    ));
+}
+
+#[tokio::test]
+async fn verify_range_opens_only_the_chunks_it_covers() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    let plaintext = noise(256 * 1024, 13);
+    let envelope = engine
+        .encrypt(EncryptRequest {
+            plaintext: plaintext.clone(),
+            chunking: Some(small_chunks()),
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+    let table = chunking::read(&envelope.meta)
+        .expect("table")
+        .expect("chunked");
+
+    let checked = engine
+        .verify_range(&envelope, 100_000, 20_000)
+        .await
+        .expect("verify range");
+    let span = table.span(100_000, 20_000).expect("span");
+    assert_eq!(checked.first_chunk, span.chunks.start);
+    assert_eq!(checked.chunks, span.chunks.len());
+    assert!(checked.chunks < table.chunks.len());
+    assert_eq!(checked.root, table.root);
+
+    // Damage outside the range goes unnoticed until the whole envelope is opened.
+    let mut damaged_tail = envelope.clone();
+    *damaged_tail.bytes.last_mut().expect("payload") ^= 1;
+    engine
+        .verify_range(&damaged_tail, 100_000, 20_000)
+        .await
+        .expect("range before the damage");
+    assert!(engine.decrypt(damaged_tail).await.is_err());
+
+    let segment_start: usize = table.chunks[..span.chunks.start]
+        .iter()
+        .map(|chunk| 12 + chunk.len as usize + 16)
+        .sum();
+    let mut damaged_range = envelope.clone();
+    damaged_range.bytes[segment_start + 20] ^= 1;
+    assert!(matches!(
+        engine.verify_range(&damaged_range, 100_000, 20_000).await,
+        Err(DGError::Crypto(_))
+    ));
+
+    let len = plaintext.len() as u64;
+    for (offset, count) in [(len, 1), (len - 10, 11), (0, 0), (u64::MAX, 2)] {
+        assert!(matches!(
+            engine.verify_range(&envelope, offset, count).await,
+            Err(DGError::Config(_))
+        ));
+    }
+    let whole = engine
+        .verify_range(&envelope, 0, len)
+        .await
+        .expect("whole range");
+    assert_eq!(whole.chunks, table.chunks.len());
+
+    let unchunked = engine
+        .encrypt(EncryptRequest {
+            plaintext: b"not chunked".to_vec(),
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+    assert!(matches!(
+        engine.verify_range(&unchunked, 0, 4).await,
+        Err(DGError::Config(_))
+    ));
+}
+
+#[tokio::test]
+async fn decrypt_range_matches_the_whole_plaintext() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    let plaintext = noise(192 * 1024, 17);
+    let envelope = engine
+        .encrypt(EncryptRequest {
+            plaintext: plaintext.clone(),
+            chunking: Some(small_chunks()),
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+    let table = chunking::read(&envelope.meta)
+        .expect("table")
+        .expect("chunked");
+    let whole = engine
+        .decrypt(envelope.clone())
+        .await
+        .expect("decrypt whole");
+    assert_eq!(*whole, plaintext);
+
+    let boundary: u64 = table.chunks[..3].iter().map(|chunk| chunk.len).sum();
+    let len = plaintext.len() as u64;
+    for (offset, count) in [
+        (0, 1),
+        (boundary - 1, 2),
+        (boundary, table.chunks[3].len),
+        (77_777, 33_333),
+        (len - 1, 1),
+        (0, len),
+    ] {
+        let range = engine
+            .decrypt_range(&envelope, offset, count)
+            .await
+            .expect("decrypt range");
+        assert_eq!(
+            *range,
+            whole[offset as usize..(offset + count) as usize],
+            "{offset}+{count}"
+        );
+    }
+
+    for (offset, count) in [(len, 1), (len - 10, 11), (5, 0), (u64::MAX, 2)] {
+        assert!(matches!(
+            engine.decrypt_range(&envelope, offset, count).await,
+            Err(DGError::Config(_))
+        ));
+    }
+
+    // Only the chunks holding the range are opened.
+    let mut damaged_tail = envelope.clone();
+    *damaged_tail.bytes.last_mut().expect("payload") ^= 1;
+    assert_eq!(
+        *engine
+            .decrypt_range(&damaged_tail, 0, 100)
+            .await
+            .expect("range before the damage"),
+        whole[..100]
+    );
+    assert!(matches!(
+        engine.decrypt_range(&damaged_tail, len - 100, 100).await,
+        Err(DGError::Crypto(_))
+    ));
+
+    let unchunked = engine
+        .encrypt(EncryptRequest {
+            plaintext: b"not chunked".to_vec(),
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+    assert!(matches!(
+        engine.decrypt_range(&unchunked, 0, 4).await,
+        Err(DGError::Config(_))
+    ));
+}
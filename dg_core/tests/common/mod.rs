@@ -0,0 +1,33 @@
+// Fixtures shared by the engine integration tests; each test crate uses only some of them.
+#![allow(dead_code)]
+
+use std::path::Path;
+use std::sync::Arc;
+
+use dg_core::api::{new_default, DGConfig, DataGuardian};
+use dg_core::KdfParams;
+
+// Deliberately cheap parameters; the defaults take a noticeable fraction of a second per unlock.
+pub const FAST: KdfParams = KdfParams {
+    memory_kib: 1024,
+    iterations: 1,
+    parallelism: 1,
+};
+
+pub fn config(data_dir: &Path) -> DGConfig {
+    DGConfig {
+        profile: "dev".into(),
+        data_dir: data_dir.to_path_buf(),
+        ..Default::default()
+    }
+}
+
+pub async fn engine(data_dir: &Path) -> Arc<dyn DataGuardian + Send + Sync> {
+    engine_with(config(data_dir)).await
+}
+
+pub async fn engine_with(config: DGConfig) -> Arc<dyn DataGuardian + Send + Sync> {
+    let engine = new_default();
+    engine.init(config).await.expect("init");
+    engine
+}
@@ -0,0 +1,210 @@
+import asyncio
+import base64
+import hashlib
+import json
+import struct
+from pathlib import Path
+
+import pytest
+
+from dg_core.daemon.envelopes import EnvelopeIndex, IndexUnavailable, SearchFilters
+from dg_core.daemon.protocol import JSONRPCRequest, MethodContext, RPCError
+from dg_core.daemon.server import DaemonServer
+
+
+def _write_index(data_dir: Path, envelopes: list[dict]) -> None:
+    index = data_dir / "index" / "envelopes.json"
+    index.parent.mkdir(parents=True)
+    index.write_text(json.dumps({"envelopes": envelopes}), encoding="utf-8")
+
+
+def _entries(tmp_path: Path) -> list[dict]:
+    report = tmp_path / "report.pdf.dgenc"
+    report.write_text(
+        json.dumps(
+            {
+                "format": 2,
+                "payload": base64.b64encode(b"ciphertext").decode(),
+                "meta": {
+                    "labels": ["pii/ssn"],
+                    "content_type": "application/pdf",
+                    "read_receipts": True,
+                    "key_wraps": [{"key_id": "escrow", "wrapped": "secret"}],
+                    "provenance": [
+                        {
+                            "action": "migrate",
+                            "subject": "user:ana",
+                            "timestamp": 250,
+                            "previous_key_ids": ["master"],
+                            "from_format": 1,
+                            "to_format": 2,
+                        }
+                    ],
+                },
+                "original_path": "/home/ana/report.pdf",
+            }
+        ),
+        encoding="utf-8",
+    )
+    return [
+        {"path": str(report), "key_ids": ["escrow"], "labels": ["pii/ssn"], "updated_at": 300},
+        {
+            "path": "/archive/Payroll.csv.dgenc",
+            "key_ids": ["hr"],
+            "labels": ["pii"],
+            "updated_at": 200,
+        },
+        {"path": "/archive/notes.txt.dgenc", "key_ids": ["hr"], "updated_at": 100},
+    ]
+
+
+def test_search_filters_and_paginates(tmp_path: Path) -> None:
+    _write_index(tmp_path, _entries(tmp_path))
+    index = EnvelopeIndex(tmp_path)
+
+    first = index.search(SearchFilters(), limit=2)
+    assert first["total"] == 3
+    assert [entry["updated_at"] for entry in first["envelopes"]] == [300, 200]
+    assert first["next_offset"] == 2
+    second = index.search(SearchFilters(), offset=first["next_offset"], limit=2)
+    assert [entry["path"] for entry in second["envelopes"]] == ["/archive/notes.txt.dgenc"]
+    assert second["next_offset"] is None
+
+    assert index.search(SearchFilters(label="PII"))["total"] == 2
+    assert index.search(SearchFilters(label="pii/ssn"))["total"] == 1
+    assert index.search(SearchFilters(query="payroll"))["total"] == 1
+    assert index.search(SearchFilters(key_id="hr", updated_after=150))["total"] == 1
+    assert index.search(SearchFilters(updated_before=150))["total"] == 1
+    with pytest.raises(ValueError):
+        index.search(SearchFilters(), limit=0)
+    with pytest.raises(ValueError):
+        SearchFilters.from_params({"updated_after": "yesterday"})
+
+
+def test_inspect_hides_wrapped_keys(tmp_path: Path) -> None:
+    entries = _entries(tmp_path)
+    _write_index(tmp_path, entries)
+    fingerprint = {"hex": "ab12 cd34", "words": ["acid", "acorn"], "emoji": "🐶🐱"}
+    (tmp_path / "keys").mkdir()
+    (tmp_path / "keys" / "keyring.json").write_text(
+        json.dumps(
+            {
+                "identities": [{"id": "escrow", "secret": "c2VjcmV0", "fingerprint": fingerprint}],
+                "contacts": [{"id": "bob", "fingerprint": fingerprint}],
+            }
+        ),
+        encoding="utf-8",
+    )
+    index = EnvelopeIndex(tmp_path)
+
+    details = index.inspect(entries[0]["path"])
+    assert details is not None
+    assert details["present"] is True
+    assert details["format"] == 2
+    assert details["payload_bytes"] == len(b"ciphertext")
+    assert details["recipients"] == ["escrow"]
+    assert details["fingerprints"] == {"escrow": fingerprint}
+    assert details["original_path"] == "/home/ana/report.pdf"
+    assert "key_wraps" not in details["meta"]
+    assert "provenance" not in details["meta"]
+    assert [step["action"] for step in details["provenance"]] == ["migrate"]
+    assert details["provenance"][0]["previous_key_ids"] == ["master"]
+    assert details["read_receipts"] is True
+    assert "read_receipts" not in details["meta"]
+    assert "secret" not in json.dumps(details)
+
+    assert index.inspect("/archive/notes.txt.dgenc")["present"] is False
+    assert index.inspect(str(tmp_path / "unindexed.dgenc")) is None
+
+
+def test_inspect_reads_binary_envelopes(tmp_path: Path) -> None:
+    meta = json.dumps(
+        {"labels": ["hr"], "key_wraps": [{"key_id": "hr"}], "original_path": "/home/ana/pay.csv"}
+    ).encode()
+    nonce = bytes(12)
+    binary = tmp_path / "pay.csv.dgenc"
+    binary.write_bytes(
+        b"DGEV"
+        + struct.pack(">HBB", 3, 1, 2)
+        + b"hr"
+        + struct.pack(">B", len(nonce))
+        + nonce
+        + struct.pack(">I", len(meta))
+        + meta
+        + b"ciphertext"
+    )
+    _write_index(tmp_path, [{"path": str(binary), "key_ids": ["hr"], "updated_at": 1}])
+
+    details = EnvelopeIndex(tmp_path).inspect(str(binary))
+    assert details is not None
+    assert details["format"] == 3
+    assert details["payload_bytes"] == len(nonce) + len(b"ciphertext")
+    assert details["original_path"] == "/home/ana/pay.csv"
+    assert details["recipients"] == ["hr"]
+    assert details["meta"] == {"labels": ["hr"]}
+
+    binary.write_bytes(b"DGEV\x00\x03")
+    with pytest.raises(IndexUnavailable):
+        EnvelopeIndex(tmp_path).inspect(str(binary))
+
+
+def test_corrupt_index_is_reported(tmp_path: Path) -> None:
+    (tmp_path / "index").mkdir()
+    (tmp_path / "index" / "envelopes.json").write_text("{", encoding="utf-8")
+    with pytest.raises(IndexUnavailable):
+        EnvelopeIndex(tmp_path).entries()
+    assert EnvelopeIndex(tmp_path / "missing").entries() == []
+
+
+def _log_line(change: dict) -> bytes:
+    body = json.dumps(change).encode("utf-8")
+    return hashlib.sha256(body).hexdigest()[:16].encode("ascii") + b" " + body + b"\n"
+
+
+def test_entries_replay_the_change_log(tmp_path: Path) -> None:
+    entries = _entries(tmp_path)
+    _write_index(tmp_path, entries)
+    added = {"path": "/archive/new.txt.dgenc", "key_ids": ["hr"], "updated_at": 400}
+    (tmp_path / "index" / "envelopes.wal").write_bytes(
+        b'{"generation":0}\n'
+        + _log_line({"op": "remove", "path": "/archive/notes.txt.dgenc"})
+        + _log_line({"op": "record", "entry": added})
+        + b"0123456789abcdef {\"op\": \"rem"
+    )
+    paths = [entry.path for entry in EnvelopeIndex(tmp_path).entries()]
+    assert paths == [entries[0]["path"], "/archive/Payroll.csv.dgenc", added["path"]]
+
+    # A compaction that stopped before replacing the log already folded it into the snapshot.
+    snapshot = {"generation": 1, "envelopes": entries}
+    (tmp_path / "index" / "envelopes.json").write_text(json.dumps(snapshot), encoding="utf-8")
+    assert len(EnvelopeIndex(tmp_path).entries()) == 3
+
+
+def _call(server: DaemonServer, method: str, params: dict) -> dict:
+    request = JSONRPCRequest(method=method, params=params, id=1)
+    context = MethodContext(server=server, connection=None)
+    return asyncio.run(server._registry.dispatch(context, request)).result
+
+
+def test_rpc_methods_serve_the_index(tmp_path: Path) -> None:
+    entries = _entries(tmp_path)
+    _write_index(tmp_path, entries)
+    server = DaemonServer(socket_path=tmp_path / "dg.sock", data_dir=tmp_path)
+
+    page = _call(server, "core.search_envelopes", {"label": "pii", "limit": 1})
+    assert page["total"] == 2
+    assert page["next_offset"] == 1
+    details = _call(server, "core.inspect_envelope", {"path": entries[0]["path"]})
+    assert details["labels"] == ["pii/ssn"]
+
+    with pytest.raises(RPCError) as missing:
+        _call(server, "core.inspect_envelope", {"path": "/nowhere.dgenc"})
+    assert missing.value.error.code == -32021
+    with pytest.raises(RPCError) as invalid:
+        _call(server, "core.search_envelopes", {"limit": "ten"})
+    assert invalid.value.error.code == -32602
+
+    unconfigured = DaemonServer(socket_path=tmp_path / "other.sock")
+    with pytest.raises(RPCError) as unavailable:
+        _call(unconfigured, "core.search_envelopes", {})
+    assert unavailable.value.error.code == -32020
@@ -0,0 +1,113 @@
+import asyncio
+import json
+import sys
+from pathlib import Path
+
+import pytest
+
+from dg_core.daemon.limits import (
+    RATE_LIMITED,
+    REQUEST_TOO_LARGE,
+    ConnectionGuard,
+    ConnectionLimits,
+    LimitExceeded,
+    RateLimiter,
+)
+from dg_core.daemon.server import DaemonServer
+
+
+class _Clock:
+    def __init__(self) -> None:
+        self.now = 0.0
+
+    def __call__(self) -> float:
+        return self.now
+
+
+def test_rate_limiter_refills_up_to_burst() -> None:
+    clock = _Clock()
+    limiter = RateLimiter(rate=2.0, burst=3, clock=clock)
+    assert [limiter.allow() for _ in range(4)] == [True, True, True, False]
+    clock.now = 0.5
+    assert limiter.allow()
+    assert not limiter.allow()
+    clock.now = 100.0
+    assert [limiter.allow() for _ in range(4)] == [True, True, True, False]
+
+
+def test_guard_rejects_oversized_frames_and_in_flight_overflow() -> None:
+    guard = ConnectionGuard(ConnectionLimits(max_frame_bytes=16, burst=10, max_in_flight=2))
+    with pytest.raises(LimitExceeded) as excinfo:
+        guard.admit(17)
+    assert excinfo.value.error.code == REQUEST_TOO_LARGE
+    assert excinfo.value.reason == "frame"
+
+    guard.admit(8)
+    guard.admit(8)
+    with pytest.raises(LimitExceeded) as excinfo:
+        guard.admit(8)
+    assert excinfo.value.error.code == RATE_LIMITED
+    assert excinfo.value.reason == "in_flight"
+    guard.release()
+    guard.admit(8)
+
+
+def test_limits_reject_non_positive_values() -> None:
+    with pytest.raises(ValueError):
+        ConnectionLimits(max_in_flight=0)
+
+
+async def _exchange(socket_path: Path, lines: list[bytes]) -> list[dict]:
+    reader, writer = await asyncio.open_unix_connection(str(socket_path))
+    writer.writelines(lines)
+    await writer.drain()
+    responses = []
+    while True:
+        line = await asyncio.wait_for(reader.readline(), timeout=2)
+        if not line:
+            break
+        responses.append(json.loads(line))
+    writer.close()
+    return responses
+
+
+async def _serve(socket_path: Path, limits: ConnectionLimits, lines: list[bytes]) -> list[dict]:
+    server = DaemonServer(socket_path=socket_path, limits=limits)
+    task = asyncio.create_task(server.serve_forever())
+    for _ in range(50):
+        if socket_path.exists():
+            break
+        await asyncio.sleep(0.02)
+    try:
+        return await _exchange(socket_path, lines)
+    finally:
+        await server.stop()
+        await asyncio.wait_for(task, timeout=2)
+
+
+def _ping(request_id: int) -> bytes:
+    return json.dumps({"jsonrpc": "2.0", "id": request_id, "method": "core.ping"}).encode() + b"\n"
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="unix socket transport")
+def test_flooding_client_is_disconnected(tmp_path: Path) -> None:
+    limits = ConnectionLimits(requests_per_second=0.001, burst=2)
+    responses = asyncio.run(
+        _serve(tmp_path / "dg.sock", limits, [_ping(index) for index in range(10)])
+    )
+    errors = [response["error"] for response in responses if response.get("error")]
+    answered = {response["id"] for response in responses if response.get("result")}
+    assert len(errors) == 1
+    assert errors[0]["code"] == RATE_LIMITED
+    assert errors[0]["data"]["reason"] == "rate"
+    assert answered <= {0, 1}
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="unix socket transport")
+def test_oversized_frame_closes_connection(tmp_path: Path) -> None:
+    limits = ConnectionLimits(max_frame_bytes=64)
+    payload = b'{"jsonrpc": "2.0", "id": 1, "method": "core.ping", "pad": "' + b"x" * 256 + b'"}\n'
+    responses = asyncio.run(_serve(tmp_path / "dg.sock", limits, [payload, _ping(2)]))
+    assert len(responses) == 1
+    assert responses[0]["error"]["code"] == REQUEST_TOO_LARGE
+    assert responses[0]["error"]["data"]["limit"] == 64
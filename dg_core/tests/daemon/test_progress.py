@@ -0,0 +1,37 @@
+import asyncio
+
+from dg_core.daemon.log_stream import LogStream
+from dg_core.daemon.progress import ProgressReporter
+
+
+def test_progress_reporter_publishes_stages() -> None:
+    async def scenario() -> list[dict[str, object]]:
+        stream = LogStream(backlog=0)
+        stream.attach_loop(asyncio.get_running_loop())
+        subscription = stream.subscribe()
+        reporter = ProgressReporter(stream, "core.scan_path", op_id="op-1", total_bytes=200)
+        reporter.stage("read")
+        reporter.stage("scan", 50)
+        reporter.done()
+        await asyncio.sleep(0)
+        records = [await subscription.__anext__() for _ in range(3)]
+        await subscription.aclose()
+        return records
+
+    records = asyncio.run(scenario())
+    assert [record["stage"] for record in records] == ["read", "scan", "done"]
+    assert all(record["op_id"] == "op-1" for record in records)
+    assert records[1]["bytes"] == 50
+    assert records[1]["percent"] == 25.0
+    assert records[2]["percent"] == 100.0
+    assert records[2]["method"] == "core.scan_path"
+
+
+def test_progress_reporter_generates_op_ids() -> None:
+    stream = LogStream(backlog=1)
+    first = ProgressReporter(stream, "core.redact_file")
+    second = ProgressReporter(stream, "core.redact_file")
+    assert first.op_id != second.op_id
+    update = first.stage("read")
+    assert update.percent is None
+    assert update.total_bytes is None
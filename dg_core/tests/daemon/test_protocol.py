@@ -4,6 +4,7 @@ import json
 import pytest
 
 from dg_core.daemon.protocol import (
+    EMPTY_PARAMS,
     JSONRPCError,
     JSONRPCRequest,
     MethodContext,
@@ -92,3 +93,32 @@ def test_method_registry_duplicate_registration() -> None:
     registry.register("core.ping", handler)
     with pytest.raises(ValueError):
         registry.register("core.ping", handler)
+
+
+def test_method_registry_describe_reports_schemas_and_capabilities() -> None:
+    registry = MethodRegistry()
+    schema = {"type": "object", "properties": {"path": {"type": "string"}}, "required": ["path"]}
+
+    def handler(_context: MethodContext, _params: dict[str, object]) -> dict[str, object]:
+        return {"ok": True}
+
+    registry.register("core.scan_path", handler, scope="scan", params=schema)
+    registry.register("core.ping", handler)
+    registry.register(
+        "core.subscribe",
+        handler,
+        scope=lambda params: "progress",
+        capabilities=("progress", "read-logs"),
+    )
+
+    described = registry.describe()
+    assert [method["name"] for method in described] == [
+        "core.ping",
+        "core.scan_path",
+        "core.subscribe",
+    ]
+    assert described[0] == {"name": "core.ping", "params": EMPTY_PARAMS, "capabilities": []}
+    assert described[1]["params"] == schema
+    assert described[1]["capabilities"] == ["scan"]
+    assert described[2]["capabilities"] == ["progress", "read-logs"]
+
@@ -0,0 +1,104 @@
+import asyncio
+from dataclasses import replace
+
+import pytest
+
+from dg_core.daemon.protocol import (
+    JSONRPCRequest,
+    MethodContext,
+    MethodRegistry,
+    RPCError,
+)
+from dg_core.daemon.session import SessionVerifier, mint_token, transport_token_matches
+
+KEY = bytes(range(32))
+
+
+def _registry() -> MethodRegistry:
+    registry = MethodRegistry()
+    registry.register("core.ping", lambda _ctx, _params: {"ok": True})
+    registry.register("core.authenticate", lambda _ctx, _params: {"required": False})
+    registry.register("core.scan_path", lambda _ctx, _params: {"detections": []}, scope="scan")
+    registry.register(
+        "core.subscribe",
+        lambda _ctx, params: {"topic": params.get("topic")},
+        scope=lambda params: "read-logs" if params.get("topic") == "logs" else "progress",
+    )
+    return registry
+
+
+def _call(registry: MethodRegistry, context: MethodContext, method: str, **params: object):
+    request = JSONRPCRequest(id=1, method=method, params=params)
+    return asyncio.run(registry.dispatch(context, request)).result
+
+
+def test_verifier_round_trips_minted_tokens() -> None:
+    verifier = SessionVerifier(KEY)
+    session = verifier.verify(mint_token(KEY, "tray", ["scan", "progress"], ttl=60))
+    assert session.subject == "session:tray"
+    assert session.scopes == frozenset({"scan", "progress"})
+    assert session.describe()["scopes"] == ["progress", "scan"]
+    assert "device" not in session.describe()
+
+
+def test_sessions_carry_the_handshake_device() -> None:
+    session = SessionVerifier(KEY).verify(mint_token(KEY, "tray", ["status"], ttl=60))
+    tagged = replace(session, device="4f1c2a9be07d4c55a1d2e3f4a5b6c7d8")
+    assert tagged.describe()["device"] == "4f1c2a9be07d4c55a1d2e3f4a5b6c7d8"
+    assert tagged.subject == session.subject
+
+
+@pytest.mark.parametrize(
+    "token",
+    [
+        "garbage",
+        mint_token(bytes(32), "tray", ["scan"], ttl=60),
+        mint_token(KEY, "tray", ["scan"], ttl=-1),
+        mint_token(KEY, "tray", ["scan"], ttl=60).replace("v1.", "v2.", 1),
+    ],
+)
+def test_verifier_rejects_invalid_tokens(token: str) -> None:
+    with pytest.raises(RPCError) as excinfo:
+        SessionVerifier(KEY).verify(token)
+    assert excinfo.value.error.code == -32011
+
+
+def test_dispatch_enforces_method_scopes() -> None:
+    registry = _registry()
+    session = SessionVerifier(KEY).verify(mint_token(KEY, "tray", ["progress"], ttl=60))
+    anonymous = MethodContext(server=None, connection=None, enforce_scopes=True)
+    scoped = MethodContext(server=None, connection=None, session=session, enforce_scopes=True)
+
+    assert _call(registry, anonymous, "core.ping") == {"ok": True}
+    with pytest.raises(RPCError) as excinfo:
+        _call(registry, anonymous, "core.scan_path", path="x")
+    assert excinfo.value.error.code == -32011
+
+    assert _call(registry, scoped, "core.subscribe", topic="progress") == {"topic": "progress"}
+    with pytest.raises(RPCError) as excinfo:
+        _call(registry, scoped, "core.subscribe", topic="logs")
+    assert excinfo.value.error.code == -32012
+    assert excinfo.value.error.data["scope"] == "read-logs"
+    assert excinfo.value.error.data["subject"] == "session:tray"
+
+
+def test_dispatch_ignores_scopes_when_sessions_disabled() -> None:
+    context = MethodContext(server=None, connection=None)
+    assert _call(_registry(), context, "core.scan_path", path="x") == {"detections": []}
+
+
+def test_unverified_tcp_connections_may_only_authenticate() -> None:
+    registry = _registry()
+    unverified = MethodContext(server=None, connection=None, transport_verified=False)
+
+    assert _call(registry, unverified, "core.authenticate") == {"required": False}
+    with pytest.raises(RPCError) as excinfo:
+        _call(registry, unverified, "core.ping")
+    assert excinfo.value.error.code == -32011
+    assert excinfo.value.error.data == "core.ping"
+
+
+def test_transport_tokens_must_match_exactly() -> None:
+    assert transport_token_matches("s3cret", "s3cret")
+    assert not transport_token_matches("s3cret", "s3cre")
+    assert not transport_token_matches("s3cret", None)
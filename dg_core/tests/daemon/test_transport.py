@@ -0,0 +1,23 @@
+import argparse
+
+import pytest
+
+from dg_core.daemon.server import DaemonServer, _tcp_address
+from dg_core.ipc.transport import TCPTransport
+
+
+def test_tcp_address_flag_parses_host_and_port() -> None:
+    assert _tcp_address("127.0.0.1:7400") == ("127.0.0.1", 7400)
+    assert _tcp_address("[::1]:7400") == ("::1", 7400)
+    for value in ("7400", "localhost:", "localhost:http", "localhost:70000"):
+        with pytest.raises(argparse.ArgumentTypeError):
+            _tcp_address(value)
+
+
+def test_tcp_transport_is_selected_and_limited_to_loopback() -> None:
+    server = DaemonServer(tcp_address=("127.0.0.1", 7400))
+    assert isinstance(server._transport, TCPTransport)
+    assert server.endpoint == "127.0.0.1:7400"
+
+    with pytest.raises(ValueError):
+        DaemonServer(tcp_address=("0.0.0.0", 7400))
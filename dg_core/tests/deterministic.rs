@@ -0,0 +1,67 @@
+mod common;
+
+use std::path::Path;
+
+use base64::{engine::general_purpose, Engine as _};
+use common::config;
+use dg_core::api::{new_default, DGConfig, DGError, DGResult, EncryptRequest, Envelope};
+use serde_json::{json, Value};
+use tempfile::tempdir;
+
+const SEED: u64 = 0x6467_2d67_6f6c_6465;
+
+async fn seeded_envelope(data_dir: &Path) -> DGResult<Envelope> {
+    let engine = new_default();
+    engine
+        .init(DGConfig {
+            deterministic_seed: Some(SEED),
+            ..config(data_dir)
+        })
+        .await?;
+    let envelope = engine
+        .encrypt(EncryptRequest {
+            plaintext: b"golden envelope".to_vec(),
+            labels: vec!["qa".into()],
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(*engine.decrypt(envelope.clone()).await?, b"golden envelope");
+    engine.shutdown().await?;
+    Ok(envelope)
+}
+
+fn to_json(envelope: &Envelope) -> Value {
+    json!({
+        "payload": general_purpose::STANDARD.encode(&envelope.bytes),
+        "meta": envelope.meta,
+    })
+}
+
+#[tokio::test]
+async fn seeded_entropy_reproduces_envelope_bytes() {
+    let first_dir = tempdir().expect("tempdir");
+    let first = seeded_envelope(first_dir.path()).await;
+    if !cfg!(feature = "deterministic") || !cfg!(debug_assertions) {
+        let Err(err) = first else {
+            panic!("deterministic entropy should be refused in this build");
+        };
+        assert!(matches!(err, DGError::Config(_)), "{err}");
+        assert!(!first_dir.path().join("keys").exists());
+        return;
+    }
+
+    let first = first.expect("seeded envelope");
+    let second_dir = tempdir().expect("tempdir");
+    let second = seeded_envelope(second_dir.path())
+        .await
+        .expect("seeded envelope");
+    assert_eq!(first.bytes, second.bytes);
+    assert_eq!(
+        std::fs::read(first_dir.path().join("keys/master.key")).expect("first key"),
+        std::fs::read(second_dir.path().join("keys/master.key")).expect("second key"),
+    );
+
+    let golden: Value = serde_json::from_str(include_str!("golden/deterministic_envelope.json"))
+        .expect("golden envelope");
+    assert_eq!(to_json(&first), golden);
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use std::path::Path;
+
+use common::engine;
+use dg_core::doctor::{available_space, examine};
+use dg_core::{DoctorReport, Severity};
+use tempfile::tempdir;
+
+async fn initialize(data_dir: &Path) {
+    let engine = engine(data_dir).await;
+    engine.shutdown().await.expect("shutdown");
+}
+
+fn severity(report: &DoctorReport, check: &str) -> Severity {
+    report
+        .findings
+        .iter()
+        .find(|finding| finding.check == check)
+        .unwrap_or_else(|| panic!("no {check} finding in {:?}", report.findings))
+        .severity
+}
+
+#[tokio::test]
+async fn fresh_data_dir_has_no_problems() {
+    let temp = tempdir().expect("tempdir");
+    initialize(temp.path()).await;
+
+    let report = examine(temp.path()).await;
+    for check in ["data-dir", "key-permissions", "policy", "keyring"] {
+        assert_eq!(severity(&report, check), Severity::Ok, "{check}");
+    }
+    assert!(available_space(temp.path()).expect("free space") > 0);
+    assert!(report.healthy(), "{:?}", report.findings);
+    assert!(report
+        .problems()
+        .all(|finding| finding.check == "disk-space"));
+}
+
+#[tokio::test]
+async fn findings_carry_suggested_fixes() {
+    let temp = tempdir().expect("tempdir");
+    let missing = examine(&temp.path().join("missing")).await;
+    assert_eq!(severity(&missing, "data-dir"), Severity::Warning);
+    assert!(missing.healthy());
+
+    initialize(temp.path()).await;
+    std::fs::write(temp.path().join("policy.json"), b"{ not json").expect("policy");
+    std::fs::write(temp.path().join("keys/keyring.json"), b"{ broken").expect("keyring");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::set_permissions(
+            temp.path().join("keys/master.key"),
+            std::fs::Permissions::from_mode(0o644),
+        )
+        .expect("chmod");
+    }
+
+    let report = examine(temp.path()).await;
+    assert_eq!(severity(&report, "policy"), Severity::Error);
+    assert_eq!(severity(&report, "keyring"), Severity::Error);
+    if cfg!(unix) {
+        assert_eq!(severity(&report, "key-permissions"), Severity::Warning);
+    }
+    assert_eq!(report.worst(), Severity::Error);
+    assert!(!report.healthy());
+    assert!(report.problems().all(|finding| finding.fix.is_some()));
+
+    let json = serde_json::to_value(&report).expect("serialize");
+    assert!(json["findings"]
+        .as_array()
+        .expect("findings")
+        .iter()
+        .any(|finding| finding["check"] == "policy" && finding["severity"] == "error"));
+}
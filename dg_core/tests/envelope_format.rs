@@ -0,0 +1,105 @@
+mod common;
+
+use base64::{engine::general_purpose, Engine as _};
+use common::engine;
+use dg_core::api::{DGError, EncryptRequest, Envelope};
+use dg_core::envelope::{ENVELOPE_MAGIC, ENVELOPE_VERSION};
+use dg_core::CipherAlgorithm;
+use proptest::prelude::*;
+use serde_json::json;
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn binary_envelopes_round_trip_and_describe_themselves() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    let envelope = engine
+        .encrypt(EncryptRequest {
+            plaintext: b"quarterly numbers".to_vec(),
+            labels: vec!["finance".into()],
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+
+    let encoded = envelope.serialize().expect("serialize");
+    assert!(encoded.starts_with(ENVELOPE_MAGIC));
+    let header = Envelope::parse_header(&encoded).expect("header");
+    assert_eq!(header.version, ENVELOPE_VERSION);
+    assert_eq!(header.algorithm, CipherAlgorithm::Aes256Gcm);
+    assert_eq!(header.key_id, "master");
+    assert_eq!(header.nonce, envelope.bytes[..12]);
+    assert_eq!(header.ciphertext_len, envelope.bytes.len() - 12);
+
+    let parsed = Envelope::parse(&encoded).expect("parse");
+    assert_eq!(parsed.bytes, envelope.bytes);
+    assert_eq!(parsed.meta, envelope.meta);
+    assert_eq!(
+        *engine.decrypt(parsed).await.expect("decrypt"),
+        b"quarterly numbers"
+    );
+
+    let legacy = serde_json::to_vec(&json!({
+        "format": 2,
+        "payload": general_purpose::STANDARD.encode(&envelope.bytes),
+        "meta": envelope.meta,
+        "original_path": "/home/me/q3.csv",
+    }))
+    .expect("json");
+    let upgraded = Envelope::parse(&legacy).expect("legacy");
+    assert_eq!(upgraded.bytes, envelope.bytes);
+    assert_eq!(
+        *engine.decrypt(upgraded).await.expect("decrypt legacy"),
+        b"quarterly numbers"
+    );
+}
+
+#[test]
+fn malformed_headers_are_rejected() {
+    let envelope = Envelope {
+        bytes: vec![7; 40],
+        meta: json!({ "labels": [] }),
+    };
+    let encoded = envelope.serialize().expect("serialize");
+    let header = Envelope::parse_header(&encoded).expect("header");
+    // Cuts the metadata block short.
+    let truncated = &encoded[..encoded.len() - header.ciphertext_len - 1];
+
+    let mut future = encoded.clone();
+    future[4..6].copy_from_slice(&(ENVELOPE_VERSION + 1).to_be_bytes());
+    let mut unknown_cipher = encoded.clone();
+    unknown_cipher[6] = 9;
+    for data in [
+        truncated,
+        &encoded[..8],
+        future.as_slice(),
+        unknown_cipher.as_slice(),
+        b"DGEV".as_slice(),
+        b"{\"payload\": 1}".as_slice(),
+    ] {
+        assert!(matches!(Envelope::parse(data), Err(DGError::Crypto(_))));
+    }
+
+    for invalid in [
+        Envelope {
+            bytes: vec![1; 4],
+            meta: json!({}),
+        },
+        Envelope {
+            bytes: vec![1; 40],
+            meta: json!(["not", "an", "object"]),
+        },
+    ] {
+        assert!(invalid.serialize().is_err());
+    }
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_bytes_never_panic(tail in prop::collection::vec(any::<u8>(), 0..512)) {
+        let mut data = ENVELOPE_MAGIC.to_vec();
+        data.extend(tail);
+        let _ = Envelope::parse(&data);
+        let _ = Envelope::parse_header(&data);
+    }
+}
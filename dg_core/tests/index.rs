@@ -0,0 +1,124 @@
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use dg_core::api::DGError;
+use dg_core::index::{BusyRetry, EnvelopeIndex, IndexEntry};
+use tempfile::tempdir;
+
+fn entry(path: &str) -> IndexEntry {
+    IndexEntry {
+        path: PathBuf::from(path),
+        key_ids: vec!["master".into()],
+        labels: Vec::new(),
+        updated_at: 1,
+        file_id: None,
+        digest: None,
+        metadata: Default::default(),
+    }
+}
+
+fn paths(index: &EnvelopeIndex) -> Vec<String> {
+    index
+        .entries()
+        .iter()
+        .map(|entry| entry.path.display().to_string())
+        .collect()
+}
+
+fn hold_lock(data_dir: &Path) -> File {
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(data_dir.join("index").join("envelopes.lock"))
+        .expect("lock file");
+    lock.try_lock().expect("lock");
+    lock
+}
+
+#[tokio::test]
+async fn writers_in_separate_handles_do_not_lose_updates() {
+    let temp = tempdir().expect("tempdir");
+    // Each handle stands in for a process that loaded the index before the other wrote.
+    let mut cli = EnvelopeIndex::load(temp.path()).await.expect("index");
+    let mut desktop = EnvelopeIndex::load(temp.path()).await.expect("index");
+    cli.record_entry(entry("/a.dgenc")).await.expect("record");
+    desktop
+        .record_entry(entry("/b.dgenc"))
+        .await
+        .expect("record");
+    cli.remove(Path::new("/missing.dgenc"))
+        .await
+        .expect("remove");
+    assert_eq!(paths(&cli), ["/a.dgenc", "/b.dgenc"]);
+
+    let mut reader = EnvelopeIndex::load_read_only(temp.path())
+        .await
+        .expect("index");
+    assert_eq!(paths(&reader), ["/a.dgenc", "/b.dgenc"]);
+    desktop.remove(Path::new("/a.dgenc")).await.expect("remove");
+    reader.refresh().await.expect("refresh");
+    assert_eq!(paths(&reader), ["/b.dgenc"]);
+    assert!(matches!(
+        reader.record_entry(entry("/c.dgenc")).await,
+        Err(DGError::ReadOnly(_))
+    ));
+}
+
+#[tokio::test]
+async fn torn_changes_are_dropped_and_the_log_is_compacted() {
+    let temp = tempdir().expect("tempdir");
+    let mut index = EnvelopeIndex::load(temp.path()).await.expect("index");
+    for name in 0..300 {
+        index
+            .record_entry(entry(&format!("/{name}.dgenc")))
+            .await
+            .expect("record");
+    }
+    let log = temp.path().join("index").join("envelopes.wal");
+    assert!(std::fs::read(&log).expect("log").len() < 64 * 1024);
+
+    index.remove(Path::new("/0.dgenc")).await.expect("remove");
+    let mut content = std::fs::read(&log).expect("log");
+    content.extend_from_slice(b"0123456789abcdef {\"op\":\"rec");
+    std::fs::write(&log, &content).expect("tear log");
+    let mut reopened = EnvelopeIndex::load(temp.path()).await.expect("index");
+    assert_eq!(reopened.entries().len(), 299);
+    reopened
+        .record_entry(entry("/0.dgenc"))
+        .await
+        .expect("record after crash");
+    let reloaded = EnvelopeIndex::load(temp.path()).await.expect("index");
+    assert_eq!(reloaded.entries().len(), 300);
+}
+
+#[tokio::test]
+async fn a_busy_writer_is_retried_then_reported() {
+    let temp = tempdir().expect("tempdir");
+    let mut index = EnvelopeIndex::load(temp.path())
+        .await
+        .expect("index")
+        .with_retry(BusyRetry {
+            attempts: 3,
+            delay: Duration::from_millis(1),
+        });
+    index.record_entry(entry("/a.dgenc")).await.expect("record");
+
+    let lock = hold_lock(temp.path());
+    assert!(matches!(
+        index.record_entry(entry("/b.dgenc")).await,
+        Err(DGError::Internal(_))
+    ));
+    let release = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        drop(lock);
+    });
+    let mut patient = EnvelopeIndex::load(temp.path()).await.expect("index");
+    patient
+        .record_entry(entry("/b.dgenc"))
+        .await
+        .expect("record once released");
+    release.await.expect("release");
+    assert_eq!(paths(&patient), ["/a.dgenc", "/b.dgenc"]);
+}
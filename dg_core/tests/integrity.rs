@@ -0,0 +1,161 @@
+mod common;
+
+use std::path::Path;
+
+use dg_core::api::{new_default, DGConfig, DGError};
+use dg_core::integrity::{check_data_dir, repair_data_dir};
+use dg_core::IntegrityComponent;
+use tempfile::tempdir;
+
+fn config(data_dir: &Path, repair_on_init: bool) -> DGConfig {
+    DGConfig {
+        repair_on_init,
+        ..common::config(data_dir)
+    }
+}
+
+async fn initialize(data_dir: &Path) {
+    let engine = new_default();
+    engine
+        .init(config(data_dir, false))
+        .await
+        .expect("init fresh data dir");
+    engine.shutdown().await.expect("shutdown");
+}
+
+#[tokio::test]
+async fn fresh_data_dir_is_healthy() {
+    let temp = tempdir().expect("tempdir");
+    initialize(temp.path()).await;
+
+    let report = check_data_dir(temp.path()).await;
+    assert!(report.healthy(), "issues: {:?}", report.issues);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(temp.path().join("keys/master.key"))
+            .expect("key metadata")
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
+
+#[tokio::test]
+async fn truncated_key_blocks_init_and_is_not_replaced() {
+    let temp = tempdir().expect("tempdir");
+    initialize(temp.path()).await;
+    let key_path = temp.path().join("keys/master.key");
+    std::fs::write(&key_path, [7u8; 12]).expect("truncate key");
+
+    let engine = new_default();
+    let err = engine
+        .init(config(temp.path(), true))
+        .await
+        .expect_err("damaged key must block init");
+    assert!(matches!(err, DGError::Config(_)), "unexpected error: {err}");
+    assert_eq!(std::fs::read(&key_path).expect("key").len(), 12);
+}
+
+#[tokio::test]
+async fn corrupt_index_is_reported_then_repaired_on_init() {
+    let temp = tempdir().expect("tempdir");
+    initialize(temp.path()).await;
+    let index_dir = temp.path().join("index");
+    std::fs::create_dir_all(&index_dir).expect("index dir");
+    std::fs::write(index_dir.join("envelopes.json"), b"{\"envelopes\": [").expect("index");
+
+    let report = check_data_dir(temp.path()).await;
+    let issue = report
+        .blocking()
+        .find(|issue| issue.component == IntegrityComponent::Index)
+        .expect("index issue");
+    assert!(issue.repairable);
+
+    let engine = new_default();
+    assert!(engine.init(config(temp.path(), false)).await.is_err());
+    engine
+        .init(config(temp.path(), true))
+        .await
+        .expect("init with repair");
+
+    let report = engine.integrity(false).await.expect("integrity report");
+    assert!(report.healthy());
+    assert!(report
+        .issues
+        .iter()
+        .any(|issue| issue.component == IntegrityComponent::Index && issue.repaired));
+    assert!(!index_dir.join("envelopes.json").exists());
+    let set_aside = std::fs::read_dir(&index_dir)
+        .expect("read index dir")
+        .filter_map(Result::ok)
+        .any(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("envelopes.json.corrupt-")
+        });
+    assert!(set_aside);
+    engine.shutdown().await.expect("shutdown");
+}
+
+#[tokio::test]
+async fn torn_audit_tail_is_truncated() {
+    let temp = tempdir().expect("tempdir");
+    initialize(temp.path()).await;
+    let log = temp.path().join("audit/audit.log");
+    let entry = r#"{"timestamp":1,"subject":"system","action":"encrypt","resource":"data","effect":"allow"}"#;
+    std::fs::write(&log, format!("{entry}\n{entry}\n{{\"timestamp\":2,\"sub")).expect("audit log");
+
+    let report = check_data_dir(temp.path()).await;
+    assert!(!report.healthy());
+    assert_eq!(report.blocking().count(), 0);
+
+    let report = repair_data_dir(temp.path()).await;
+    assert!(report.healthy(), "issues: {:?}", report.issues);
+    assert_eq!(
+        std::fs::read_to_string(&log).expect("audit log"),
+        format!("{entry}\n{entry}\n")
+    );
+    assert!(check_data_dir(temp.path()).await.healthy());
+}
+
+#[tokio::test]
+async fn unparsable_policy_is_fatal() {
+    let temp = tempdir().expect("tempdir");
+    initialize(temp.path()).await;
+    std::fs::write(temp.path().join("policy.json"), b"{ not json").expect("policy");
+
+    let report = repair_data_dir(temp.path()).await;
+    let issue = report
+        .blocking()
+        .find(|issue| issue.component == IntegrityComponent::Policy)
+        .expect("policy issue");
+    assert!(!issue.repairable);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn loose_key_permissions_are_tightened() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempdir().expect("tempdir");
+    initialize(temp.path()).await;
+    let key_path = temp.path().join("keys/master.key");
+    std::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o644))
+        .expect("loosen key");
+
+    let report = check_data_dir(temp.path()).await;
+    assert_eq!(report.outstanding().count(), 1);
+    assert_eq!(report.blocking().count(), 0);
+
+    let report = repair_data_dir(temp.path()).await;
+    assert!(report.healthy());
+    let mode = std::fs::metadata(&key_path)
+        .expect("key metadata")
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o600);
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::engine;
+use dg_core::api::{DGError, DataGuardian, EncryptRequest, Envelope};
+use dg_core::journal::{self, JournalAppend, JournalInfo, JOURNAL_VERSION};
+use dg_core::{ChunkingParams, RekeyContext};
+use tempfile::tempdir;
+
+async fn start(engine: &Arc<dyn DataGuardian + Send + Sync>, first: &[u8]) -> Envelope {
+    engine
+        .encrypt(EncryptRequest {
+            plaintext: first.to_vec(),
+            journal: true,
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt")
+}
+
+// What the controller writes to disk: the committed prefix followed by the new frames.
+fn apply(envelope: &Envelope, appended: &JournalAppend) -> Envelope {
+    let mut bytes = envelope.bytes[..appended.committed_len as usize].to_vec();
+    bytes.extend_from_slice(&appended.frames);
+    Envelope {
+        bytes,
+        meta: envelope.meta.clone(),
+    }
+}
+
+#[tokio::test]
+async fn appended_records_decrypt_in_order() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    let mut envelope = start(&engine, b"boot\n").await;
+    assert_eq!(
+        journal::read(&envelope.meta).expect("descriptor"),
+        Some(JournalInfo {
+            version: JOURNAL_VERSION
+        })
+    );
+
+    let mut expected = b"boot\n".to_vec();
+    for line in ["login alice\n", "logout alice\n"] {
+        let appended = engine
+            .append(&envelope, line.as_bytes().to_vec())
+            .await
+            .expect("append");
+        assert_eq!(appended.committed_len, envelope.bytes.len() as u64);
+        assert_eq!(appended.discarded_bytes, 0);
+        envelope = apply(&envelope, &appended);
+        expected.extend_from_slice(line.as_bytes());
+        assert_eq!(appended.plaintext_len, expected.len() as u64);
+    }
+    assert_eq!(*engine.decrypt(envelope).await.expect("decrypt"), expected);
+}
+
+#[tokio::test]
+async fn a_torn_append_is_ignored_and_then_replaced() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    let envelope = start(&engine, b"first\n").await;
+    let appended = engine
+        .append(&envelope, b"second\n".to_vec())
+        .await
+        .expect("append");
+
+    // Cut the write off inside the commit frame, as a crash would.
+    let mut torn = apply(&envelope, &appended);
+    torn.bytes.truncate(torn.bytes.len() - 10);
+    assert_eq!(
+        *engine.decrypt(torn.clone()).await.expect("decrypt"),
+        b"first\n"
+    );
+
+    let recovered = engine
+        .append(&torn, b"third\n".to_vec())
+        .await
+        .expect("append after crash");
+    assert_eq!(recovered.committed_len, envelope.bytes.len() as u64);
+    assert_eq!(recovered.discarded_bytes, appended.frames.len() as u64 - 10);
+    assert_eq!(
+        *engine
+            .decrypt(apply(&torn, &recovered))
+            .await
+            .expect("decrypt"),
+        b"first\nthird\n"
+    );
+}
+
+#[tokio::test]
+async fn tampered_or_reordered_journals_are_rejected() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    let envelope = start(&engine, b"a").await;
+    let second = engine.append(&envelope, b"b".to_vec()).await.expect("b");
+    let envelope = apply(&envelope, &second);
+
+    let mut flipped = envelope.clone();
+    let last = flipped.bytes.len() - 1;
+    flipped.bytes[last] ^= 1;
+    assert!(matches!(
+        engine.decrypt(flipped.clone()).await,
+        Err(DGError::Crypto(_))
+    ));
+    assert!(matches!(
+        engine.append(&flipped, b"c".to_vec()).await,
+        Err(DGError::Crypto(_))
+    ));
+
+    // Swap the two committed segments: each is well formed, but the chain no longer matches.
+    let split = second.committed_len as usize;
+    let mut swapped = envelope.bytes[..12].to_vec();
+    swapped.extend_from_slice(&envelope.bytes[split..]);
+    swapped.extend_from_slice(&envelope.bytes[12..split]);
+    let swapped = Envelope {
+        bytes: swapped,
+        meta: envelope.meta.clone(),
+    };
+    assert!(matches!(
+        engine.decrypt(swapped).await,
+        Err(DGError::Crypto(_))
+    ));
+}
+
+#[tokio::test]
+async fn append_mode_rejects_other_layouts() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    let chunked = engine
+        .encrypt(EncryptRequest {
+            plaintext: b"log".to_vec(),
+            journal: true,
+            chunking: Some(ChunkingParams {
+                min_size: 1024,
+                avg_size: 4096,
+                max_size: 16384,
+            }),
+            ..Default::default()
+        })
+        .await;
+    assert!(matches!(chunked, Err(DGError::Config(_))));
+
+    let plain = engine
+        .encrypt(EncryptRequest {
+            plaintext: b"log".to_vec(),
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+    assert!(matches!(
+        engine.append(&plain, b"more".to_vec()).await,
+        Err(DGError::Config(_))
+    ));
+    let envelope = start(&engine, b"log").await;
+    assert!(matches!(
+        engine.append(&envelope, Vec::new()).await,
+        Err(DGError::Config(_))
+    ));
+}
+
+#[tokio::test]
+async fn rekey_compacts_the_journal() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    let envelope = start(&engine, b"one\n").await;
+    let appended = engine
+        .append(&envelope, b"two\n".to_vec())
+        .await
+        .expect("append");
+    let envelope = apply(&envelope, &appended);
+
+    let rekeyed = engine
+        .rekey(envelope, RekeyContext::rekey("alice"))
+        .await
+        .expect("rekey");
+    assert!(journal::read(&rekeyed.meta).expect("descriptor").is_some());
+    let appended = engine
+        .append(&rekeyed, b"three\n".to_vec())
+        .await
+        .expect("append after rekey");
+    assert_eq!(appended.records, 2);
+    assert_eq!(
+        *engine
+            .decrypt(apply(&rekeyed, &appended))
+            .await
+            .expect("decrypt"),
+        b"one\ntwo\nthree\n"
+    );
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use std::path::Path;
+use std::time::Duration;
+
+use dg_core::api::{new_default, DGConfig, DGError, EncryptRequest};
+use dg_core::audit::AuditLog;
+use dg_core::{KeyAgeState, MaxKeyAge, RekeyContext};
+use tempfile::tempdir;
+
+fn config(data_dir: &Path, max_key_age: Option<MaxKeyAge>) -> DGConfig {
+    DGConfig {
+        max_key_age,
+        ..common::config(data_dir)
+    }
+}
+
+fn request(recipient: &str) -> EncryptRequest {
+    EncryptRequest {
+        plaintext: b"payroll".to_vec(),
+        recipients: vec![recipient.into()],
+        ..Default::default()
+    }
+}
+
+fn recipient_ids(meta: &serde_json::Value) -> Vec<String> {
+    meta["key_wraps"]
+        .as_array()
+        .expect("key wraps")
+        .iter()
+        .filter(|wrap| wrap["kind"] == "recipient")
+        .filter_map(|wrap| wrap["key_id"].as_str().map(str::to_owned))
+        .collect()
+}
+
+#[tokio::test]
+async fn expired_keys_refuse_encryption_until_rotated() {
+    let temp = tempdir().expect("tempdir");
+    let engine = new_default();
+    let max_key_age = MaxKeyAge {
+        age_secs: 2,
+        warn_before_secs: 0,
+        auto_rotate: false,
+    };
+    engine
+        .init(config(temp.path(), Some(max_key_age)))
+        .await
+        .expect("init");
+    let alice = engine.generate_identity("alice").await.expect("identity");
+    let envelope = engine.encrypt(request("alice")).await.expect("encrypt");
+
+    tokio::time::sleep(Duration::from_millis(2100)).await;
+    let report = engine.key_expiry().await.expect("expiry");
+    assert_eq!(report.policy, Some(max_key_age));
+    let expired: Vec<_> = report.in_state(KeyAgeState::Expired).collect();
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].key_id, alice.id);
+    assert!(expired[0].rotatable);
+    assert!(matches!(
+        engine.encrypt(request("alice")).await,
+        Err(DGError::PolicyDenied(_))
+    ));
+    assert_eq!(
+        *engine.decrypt(envelope.clone()).await.expect("decrypt"),
+        b"payroll"
+    );
+
+    let rotation = engine.rotate_key("alice").await.expect("rotate");
+    assert_eq!(rotation.retired.id, alice.id);
+    assert!(rotation.retired.revoked_at.is_some());
+    assert_eq!(rotation.replacement.label, "alice");
+    assert_eq!(
+        rotation.replacement.replaces.as_deref(),
+        Some(alice.id.as_str())
+    );
+    let fresh = engine.encrypt(request("alice")).await.expect("encrypt");
+    assert_eq!(
+        recipient_ids(&fresh.meta),
+        [rotation.replacement.id.as_str()]
+    );
+
+    let rekeyed = engine
+        .rekey(envelope, RekeyContext::default())
+        .await
+        .expect("rekey");
+    assert_eq!(
+        recipient_ids(&rekeyed.meta),
+        [rotation.replacement.id.as_str()]
+    );
+    assert_eq!(*engine.decrypt(rekeyed).await.expect("decrypt"), b"payroll");
+
+    let audit = AuditLog::open(temp.path()).await.expect("audit");
+    assert!(audit
+        .read_all()
+        .await
+        .expect("read audit")
+        .iter()
+        .any(|event| event.action == "rotate_key"));
+    engine.shutdown().await.expect("shutdown");
+}
+
+#[tokio::test]
+async fn expiry_is_reported_only_when_configured() {
+    let temp = tempdir().expect("tempdir");
+    let engine = new_default();
+    engine.init(config(temp.path(), None)).await.expect("init");
+    engine.generate_identity("bob").await.expect("identity");
+    let report = engine.key_expiry().await.expect("expiry");
+    assert!(report.policy.is_none());
+    assert!(report.keys.is_empty());
+    engine.shutdown().await.expect("shutdown");
+
+    let engine = new_default();
+    engine
+        .init(config(
+            temp.path(),
+            Some(MaxKeyAge {
+                age_secs: 30 * 24 * 60 * 60,
+                warn_before_secs: 60 * 24 * 60 * 60,
+                auto_rotate: true,
+            }),
+        ))
+        .await
+        .expect("re-init");
+    let report = engine.key_expiry().await.expect("expiry");
+    assert!(report.auto_rotate());
+    assert_eq!(report.keys.len(), 1);
+    assert_eq!(report.keys[0].state, KeyAgeState::Expiring);
+    engine
+        .encrypt(request("bob"))
+        .await
+        .expect("still encrypts");
+    engine.shutdown().await.expect("shutdown");
+
+    let invalid = new_default()
+        .init(config(
+            temp.path(),
+            Some(MaxKeyAge {
+                age_secs: 0,
+                warn_before_secs: 0,
+                auto_rotate: false,
+            }),
+        ))
+        .await;
+    assert!(matches!(invalid, Err(DGError::Config(_))));
+}
@@ -0,0 +1,119 @@
+use base64::{engine::general_purpose, Engine as _};
+use dg_core::license::{install_license, load_license_status};
+use dg_core::{LicenseState, LicenseVerifier};
+use ed25519_dalek::{Signer, SigningKey};
+use serde_json::json;
+use tempfile::tempdir;
+
+const DAY: u64 = 24 * 60 * 60;
+const ISSUED_AT: u64 = 1_700_000_000;
+const EXPIRES_AT: u64 = ISSUED_AT + 365 * DAY;
+
+fn signing_key(seed: u8) -> SigningKey {
+    SigningKey::from_bytes(&[seed; 32])
+}
+
+fn sign(key: &SigningKey, expires_at: u64) -> Vec<u8> {
+    let payload = serde_json::to_vec(&json!({
+        "id": "lic-0001",
+        "licensee": "Acme Corp",
+        "tier": "enterprise",
+        "features": ["threshold", "escrow"],
+        "issued_at": ISSUED_AT,
+        "expires_at": expires_at,
+        "grace_days": 14,
+    }))
+    .expect("payload");
+    serde_json::to_vec(&json!({
+        "payload": general_purpose::STANDARD.encode(&payload),
+        "signature": general_purpose::STANDARD.encode(key.sign(&payload).to_bytes()),
+    }))
+    .expect("license")
+}
+
+fn verifier(key: &SigningKey) -> LicenseVerifier {
+    LicenseVerifier::new(vec![key.verifying_key()])
+}
+
+#[test]
+fn license_state_follows_expiry_and_grace_period() {
+    let key = signing_key(7);
+    let raw = sign(&key, EXPIRES_AT);
+    let verifier = verifier(&key);
+
+    let active = verifier.evaluate(&raw, ISSUED_AT + DAY);
+    assert_eq!(active.state, LicenseState::Active);
+    assert!(active.allows("threshold"));
+    assert!(!active.allows("remote-wipe"));
+    assert_eq!(active.license.expect("license").licensee, "Acme Corp");
+
+    let grace = verifier.evaluate(&raw, EXPIRES_AT + DAY);
+    assert_eq!(grace.state, LicenseState::Grace);
+    assert!(grace.allows("escrow"));
+
+    let expired = verifier.evaluate(&raw, EXPIRES_AT + 15 * DAY);
+    assert_eq!(expired.state, LicenseState::Expired);
+    assert!(expired.features.is_empty());
+
+    assert_eq!(
+        verifier.evaluate(&raw, ISSUED_AT - 1).state,
+        LicenseState::Invalid
+    );
+}
+
+#[test]
+fn tampered_or_foreign_licenses_are_invalid() {
+    let key = signing_key(7);
+    let raw = sign(&key, EXPIRES_AT);
+
+    let foreign = verifier(&signing_key(9)).evaluate(&raw, ISSUED_AT + DAY);
+    assert_eq!(foreign.state, LicenseState::Invalid);
+    assert!(foreign.features.is_empty());
+
+    let mut tampered: serde_json::Value = serde_json::from_slice(&raw).expect("json");
+    let forged = serde_json::to_vec(&json!({
+        "id": "lic-0001",
+        "licensee": "Acme Corp",
+        "tier": "enterprise",
+        "features": ["threshold", "escrow", "remote-wipe"],
+        "issued_at": ISSUED_AT,
+        "expires_at": EXPIRES_AT,
+    }))
+    .expect("payload");
+    tampered["payload"] = general_purpose::STANDARD.encode(forged).into();
+    let tampered = serde_json::to_vec(&tampered).expect("json");
+    assert_eq!(
+        verifier(&key).evaluate(&tampered, ISSUED_AT + DAY).state,
+        LicenseState::Invalid
+    );
+    assert!(LicenseVerifier::from_base64(&["not-a-key"]).is_err());
+}
+
+#[tokio::test]
+async fn install_rejects_invalid_licenses_and_persists_valid_ones() {
+    let temp = tempdir().expect("tempdir");
+    let key = signing_key(7);
+    let verifier = verifier(&key);
+
+    assert_eq!(
+        load_license_status(temp.path(), &verifier).await.state,
+        LicenseState::Unlicensed
+    );
+    assert!(
+        install_license(temp.path(), &sign(&key, ISSUED_AT + DAY), &verifier)
+            .await
+            .is_err()
+    );
+    assert!(
+        install_license(temp.path(), &sign(&signing_key(9), u64::MAX / 2), &verifier)
+            .await
+            .is_err()
+    );
+
+    let installed = install_license(temp.path(), &sign(&key, u64::MAX / 2), &verifier)
+        .await
+        .expect("install");
+    assert_eq!(installed.state, LicenseState::Active);
+    let loaded = load_license_status(temp.path(), &verifier).await;
+    assert_eq!(loaded, installed);
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use common::engine;
+use dg_core::api::{new_default, DGError, EncryptRequest};
+use dg_core::index::EnvelopeIndex;
+use dg_core::{CustomMetadata, MetadataFilter, RekeyContext};
+use serde_json::json;
+use tempfile::tempdir;
+
+fn request(metadata: serde_json::Value) -> EncryptRequest {
+    EncryptRequest {
+        plaintext: b"witness statement".to_vec(),
+        metadata: serde_json::from_value(metadata).expect("metadata"),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn metadata_is_authenticated_and_survives_rekey() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    let envelope = engine
+        .encrypt(request(json!({ "case": "2024-117", "retention": "7y" })))
+        .await
+        .expect("encrypt");
+    assert_eq!(envelope.meta["metadata"]["case"], "2024-117");
+
+    let rekeyed = engine
+        .rekey(envelope.clone(), RekeyContext::rekey("alice"))
+        .await
+        .expect("rekey");
+    assert_eq!(rekeyed.meta["metadata"], envelope.meta["metadata"]);
+    assert_eq!(
+        *engine.decrypt(rekeyed.clone()).await.expect("decrypt"),
+        b"witness statement"
+    );
+
+    for original in [envelope, rekeyed] {
+        let mut tampered = original.clone();
+        tampered.meta["metadata"]["case"] = json!("2024-118");
+        assert!(matches!(
+            engine.decrypt(tampered).await,
+            Err(DGError::Crypto(_))
+        ));
+        let mut stripped = original;
+        stripped
+            .meta
+            .as_object_mut()
+            .expect("meta")
+            .remove("metadata");
+        assert!(engine.decrypt(stripped).await.is_err());
+    }
+
+    assert!(matches!(
+        engine.encrypt(request(json!({ " case": 1 }))).await,
+        Err(DGError::Config(_))
+    ));
+}
+
+#[tokio::test]
+async fn a_schema_in_the_data_dir_is_enforced() {
+    let temp = tempdir().expect("tempdir");
+    std::fs::write(
+        temp.path().join("metadata.schema.json"),
+        r#"{
+            "type": "object",
+            "required": ["case"],
+            "properties": {
+                "case": { "type": "string", "pattern": "^[0-9]{4}-[0-9]+$" },
+                "retention": { "enum": ["1y", "7y", "forever"] }
+            },
+            "additionalProperties": false
+        }"#,
+    )
+    .expect("schema");
+    let engine = engine(temp.path()).await;
+    engine
+        .encrypt(request(json!({ "case": "2024-117", "retention": "7y" })))
+        .await
+        .expect("valid metadata");
+
+    for invalid in [
+        json!({}),
+        json!({ "case": "unfiled" }),
+        json!({ "case": "2024-1", "retention": "10y" }),
+        json!({ "case": "2024-1", "owner": "legal" }),
+    ] {
+        match engine.encrypt(request(invalid.clone())).await {
+            Err(DGError::Config(message)) => assert!(message.contains("schema"), "{message}"),
+            other => panic!("{invalid} was accepted: {other:?}"),
+        }
+    }
+
+    let broken = tempdir().expect("tempdir");
+    std::fs::write(
+        broken.path().join("metadata.schema.json"),
+        r#"{ "type": 12 }"#,
+    )
+    .expect("schema");
+    let result = new_default().init(common::config(broken.path())).await;
+    assert!(matches!(result, Err(DGError::Config(_))));
+}
+
+#[tokio::test]
+async fn indexed_metadata_is_searchable() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    let mut index = EnvelopeIndex::load(temp.path()).await.expect("index");
+    let cases = [
+        (
+            "a.dgenc",
+            json!({ "case": "2024-117", "tags": ["urgent", "legal"] }),
+        ),
+        ("b.dgenc", json!({ "case": "2024-118", "priority": 2 })),
+        ("c.dgenc", json!({})),
+    ];
+    for (name, metadata) in cases {
+        let envelope = engine.encrypt(request(metadata)).await.expect("encrypt");
+        index
+            .record(&temp.path().join(name), &envelope)
+            .await
+            .expect("record");
+    }
+
+    let found = |filters: &[&str]| -> Vec<String> {
+        let filters: Vec<MetadataFilter> = filters
+            .iter()
+            .map(|filter| MetadataFilter::parse(filter).expect("filter"))
+            .collect();
+        index
+            .search(&filters)
+            .into_iter()
+            .map(|entry| {
+                entry
+                    .path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    };
+    assert_eq!(found(&["case=2024-117"]), ["a.dgenc"]);
+    assert_eq!(found(&["case"]), ["a.dgenc", "b.dgenc"]);
+    assert_eq!(found(&["tags=legal"]), ["a.dgenc"]);
+    assert_eq!(found(&["priority=2", "case=2024-118"]), ["b.dgenc"]);
+    assert!(found(&["case=2024-119"]).is_empty());
+    assert_eq!(found(&[]).len(), 3);
+    assert!(MetadataFilter::parse("=value").is_err());
+
+    let reloaded = EnvelopeIndex::load(temp.path()).await.expect("index");
+    let expected: CustomMetadata =
+        serde_json::from_value(json!({ "case": "2024-118", "priority": 2 })).expect("metadata");
+    assert_eq!(reloaded.entries()[1].metadata, expected);
+}
@@ -0,0 +1,92 @@
+use dg_core::api::{DGError, Envelope};
+use dg_core::pairing::{self, SealedMessage};
+use dg_core::{PairingOffer, UnlockApproval, UnlockChallenge};
+use serde_json::json;
+use tempfile::tempdir;
+
+const PHONE_SIGNING: [u8; 32] = [7; 32];
+const PHONE_CHANNEL: [u8; 32] = [9; 32];
+
+fn envelope() -> Envelope {
+    Envelope {
+        bytes: b"sealed".to_vec(),
+        meta: json!({}),
+    }
+}
+
+#[tokio::test]
+async fn pairing_derives_the_same_channel_on_both_sides() {
+    let pending = pairing::offer("desktop-1", 300, 1_000);
+    let scanned = PairingOffer::parse_uri(&pending.offer.to_uri()).expect("parse");
+    assert_eq!(scanned, pending.offer);
+
+    let (response, phone_channel) =
+        pairing::respond(&scanned, "Pixel", &PHONE_SIGNING, &PHONE_CHANNEL).expect("respond");
+    let (device, desktop_channel) = pending.accept(&response, 1_010).expect("accept");
+    assert_eq!(*phone_channel, *desktop_channel);
+    assert_eq!(device.name, "Pixel");
+
+    let mut guessed = scanned.clone();
+    guessed.code = "00".repeat(16);
+    let (forged, _) =
+        pairing::respond(&guessed, "Relay", &PHONE_SIGNING, &PHONE_CHANNEL).expect("respond");
+    assert!(matches!(
+        pending.accept(&forged, 1_010),
+        Err(DGError::Crypto(_))
+    ));
+    assert!(matches!(
+        pending.accept(&response, 1_300),
+        Err(DGError::PolicyDenied(_))
+    ));
+
+    let temp = tempdir().expect("tempdir");
+    pairing::record_pairing(temp.path(), device.clone(), &desktop_channel)
+        .await
+        .expect("record");
+    let channels = pairing::paired_channels(temp.path()).await.expect("load");
+    assert_eq!(channels.len(), 1);
+    assert_eq!(*channels[0].1, *desktop_channel);
+    pairing::remove_pairing(temp.path(), "Pixel")
+        .await
+        .expect("remove");
+    assert!(pairing::load_paired(temp.path())
+        .await
+        .expect("load")
+        .is_empty());
+}
+
+#[tokio::test]
+async fn approvals_are_sealed_and_bound_to_their_challenge() {
+    let pending = pairing::offer("desktop-1", 300, 1_000);
+    let (response, channel) =
+        pairing::respond(&pending.offer, "Pixel", &PHONE_SIGNING, &PHONE_CHANNEL).expect("respond");
+    let (device, _) = pending.accept(&response, 1_000).expect("accept");
+
+    let challenge = UnlockChallenge::new(
+        "desktop-1",
+        &envelope(),
+        Some("payroll.csv.dgenc".into()),
+        "alice".into(),
+        1_000,
+        60,
+    );
+    let sealed = pairing::seal(&channel, &challenge.request_id, &challenge).expect("seal");
+    assert!(!sealed.sealed.contains("payroll"));
+    let opened: UnlockChallenge = pairing::open(&channel, &sealed).expect("open");
+    assert_eq!(opened, challenge);
+    let moved = SealedMessage {
+        request_id: "another".into(),
+        ..sealed
+    };
+    assert!(pairing::open::<UnlockChallenge>(&channel, &moved).is_err());
+
+    let approval = UnlockApproval::sign(&opened, true, &PHONE_SIGNING).expect("sign");
+    assert!(approval.verify(&challenge, &device).expect("verify"));
+    let flipped = UnlockApproval {
+        approved: false,
+        ..approval.clone()
+    };
+    assert!(!flipped.verify(&challenge, &device).expect("verify"));
+    let other = UnlockChallenge::new("desktop-1", &envelope(), None, "alice".into(), 1_000, 60);
+    assert!(!approval.verify(&other, &device).expect("verify"));
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use common::{config, engine_with, FAST};
+use dg_core::api::{DGConfig, DGError, DataGuardian, EncryptRequest};
+use dg_core::KeyProtection;
+use tempfile::tempdir;
+
+async fn engine(data_dir: &Path, protection: KeyProtection) -> Arc<dyn DataGuardian + Send + Sync> {
+    engine_with(DGConfig {
+        key_protection: protection,
+        ..config(data_dir)
+    })
+    .await
+}
+
+fn request() -> EncryptRequest {
+    EncryptRequest {
+        plaintext: b"payroll 2024".to_vec(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn the_first_unlock_sets_the_passphrase() {
+    let temp = tempdir().expect("tempdir");
+    let keys = temp.path().join("keys");
+    let engine = engine(temp.path(), KeyProtection::Passphrase(FAST)).await;
+
+    let err = engine.encrypt(request()).await.expect_err("locked");
+    assert!(matches!(err, DGError::Locked(_)), "{err}");
+    assert_eq!(err.code(), "engine.locked");
+    assert!(engine
+        .check_policy("system", "encrypt", "data")
+        .await
+        .expect("policy checks work while locked"));
+    assert!(matches!(
+        engine.unlock("short").await,
+        Err(DGError::Config(_))
+    ));
+    assert!(!keys.join("master.key").exists());
+
+    engine
+        .unlock("correct horse battery")
+        .await
+        .expect("unlock");
+    assert!(keys.join("master.key.wrapped").exists());
+    assert!(!keys.join("master.key").exists());
+    let envelope = engine.encrypt(request()).await.expect("encrypt");
+
+    engine.lock().await.expect("lock");
+    assert!(matches!(
+        engine.decrypt(envelope.clone()).await,
+        Err(DGError::Locked(_))
+    ));
+    assert!(matches!(
+        engine.unlock("wrong horse battery").await,
+        Err(DGError::Crypto(_))
+    ));
+
+    // The wrapped key alone decides; a later start without the option still opens locked.
+    let reopened = engine_without_protection(temp.path()).await;
+    assert!(reopened.decrypt(envelope.clone()).await.is_err());
+    reopened
+        .unlock("correct horse battery")
+        .await
+        .expect("unlock");
+    assert_eq!(
+        *reopened.decrypt(envelope).await.expect("decrypt"),
+        b"payroll 2024"
+    );
+
+    let log = std::fs::read_to_string(temp.path().join("audit").join("audit.log")).expect("audit");
+    assert!(log.contains("\"unlock\"") && log.contains("\"lock\""));
+    assert!(log.contains("\"deny\""));
+}
+
+#[tokio::test]
+async fn an_existing_key_is_wrapped_in_place() {
+    let temp = tempdir().expect("tempdir");
+    let plain = engine_without_protection(temp.path()).await;
+    let envelope = plain.encrypt(request()).await.expect("encrypt");
+    assert!(matches!(plain.lock().await, Err(DGError::Config(_))));
+    plain.shutdown().await.expect("shutdown");
+
+    let protected = engine(temp.path(), KeyProtection::Passphrase(FAST)).await;
+    protected
+        .unlock("a longer passphrase")
+        .await
+        .expect("unlock");
+    assert!(!temp.path().join("keys").join("master.key").exists());
+    assert_eq!(
+        *protected.decrypt(envelope).await.expect("decrypt"),
+        b"payroll 2024"
+    );
+}
+
+async fn engine_without_protection(data_dir: &Path) -> Arc<dyn DataGuardian + Send + Sync> {
+    engine(data_dir, KeyProtection::None).await
+}
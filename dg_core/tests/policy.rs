@@ -1,30 +1,27 @@
-use dg_core::api::{new_default, DGConfig, EncryptRequest};
+mod common;
+
+use common::engine;
+use dg_core::api::EncryptRequest;
 use tempfile::tempdir;
 
 #[tokio::test]
 async fn policy_default_allows_encryption() {
     let temp = tempdir().expect("tempdir");
     let data_dir = temp.path().to_path_buf();
-    let engine = new_default();
-    engine
-        .init(DGConfig {
-            profile: "dev".into(),
-            data_dir: data_dir.clone(),
-            telemetry: false,
-        })
-        .await
-        .expect("init");
+    let engine = engine(&data_dir).await;
+    engine.generate_identity("user").await.expect("identity");
 
     let envelope = engine
         .encrypt(EncryptRequest {
             plaintext: b"hello".to_vec(),
             labels: vec!["test".into()],
             recipients: vec!["user".into()],
+            ..Default::default()
         })
         .await
         .expect("encrypt");
     let decrypted = engine.decrypt(envelope).await.expect("decrypt");
-    assert_eq!(decrypted, b"hello");
+    assert_eq!(*decrypted, b"hello");
 
     engine.shutdown().await.expect("shutdown");
 }
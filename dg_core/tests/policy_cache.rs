@@ -0,0 +1,96 @@
+mod common;
+
+use std::path::Path;
+
+use common::engine;
+use dg_core::{Identity, PolicyEngine};
+use serde_json::json;
+use tempfile::tempdir;
+
+fn write_policy(data_dir: &Path, effect: &str) {
+    let doc = json!({
+        "rules": [{ "subject": "user:mallory", "action": "decrypt", "resource": "*", "effect": effect }],
+    });
+    std::fs::write(
+        data_dir.join("policy.json"),
+        serde_json::to_vec(&doc).expect("json"),
+    )
+    .expect("policy");
+}
+
+#[tokio::test]
+async fn repeated_decisions_are_served_from_the_cache() {
+    let policy = PolicyEngine::parse(br#"{ "default_allow": false }"#).expect("policy");
+    for _ in 0..4 {
+        assert!(!policy
+            .evaluate("user:alice", "encrypt", "/srv/a.txt")
+            .await
+            .expect("evaluate"));
+    }
+    policy
+        .evaluate("user:alice", "encrypt", "/srv/b.txt")
+        .await
+        .expect("evaluate");
+    let stats = policy.cache_stats();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (3, 2, 2));
+    assert!((stats.hit_rate() - 0.6).abs() < f64::EPSILON);
+
+    let uncached = PolicyEngine::parse(br#"{ "cache": { "ttl_secs": 0 } }"#).expect("policy");
+    for _ in 0..3 {
+        uncached
+            .evaluate("user:alice", "encrypt", "/srv/a.txt")
+            .await
+            .expect("evaluate");
+    }
+    let stats = uncached.cache_stats();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (0, 3, 0));
+
+    let bounded = PolicyEngine::parse(br#"{ "cache": { "max_entries": 2 } }"#).expect("policy");
+    for resource in ["a", "b", "c"] {
+        bounded
+            .evaluate("user:alice", "encrypt", resource)
+            .await
+            .expect("evaluate");
+    }
+    let stats = bounded.cache_stats();
+    assert_eq!((stats.entries, stats.evictions), (2, 1));
+}
+
+#[tokio::test]
+async fn reloading_the_policy_invalidates_cached_decisions() {
+    let temp = tempdir().expect("tempdir");
+    write_policy(temp.path(), "allow");
+    let engine = engine(temp.path()).await;
+    let mallory = Identity::new("mallory");
+
+    for _ in 0..2 {
+        assert!(engine
+            .check_identity(&mallory, "decrypt", "/srv/ledger.xlsx.dgenc")
+            .await
+            .expect("check"));
+    }
+    let before = engine.policy_cache_stats().await.expect("stats");
+    assert!(before.hits >= 1);
+
+    write_policy(temp.path(), "deny");
+    // Until the policy is reloaded the cached decision stands.
+    assert!(engine
+        .check_identity(&mallory, "decrypt", "/srv/ledger.xlsx.dgenc")
+        .await
+        .expect("check"));
+
+    let after = engine.reload_policy().await.expect("reload");
+    assert_eq!(after.entries, 0);
+    assert_eq!(after.invalidations, before.invalidations + 1);
+    assert!(!engine
+        .check_identity(&mallory, "decrypt", "/srv/ledger.xlsx.dgenc")
+        .await
+        .expect("check"));
+
+    std::fs::write(temp.path().join("policy.json"), b"{ not json").expect("policy");
+    assert!(engine.reload_policy().await.is_err());
+    assert!(!engine
+        .check_identity(&mallory, "decrypt", "/srv/ledger.xlsx.dgenc")
+        .await
+        .expect("check"));
+}
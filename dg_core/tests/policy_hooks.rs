@@ -0,0 +1,210 @@
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::engine;
+use dg_core::policy_hook::HookRequest;
+use dg_core::{Decision, DecisionSource, FailMode, HookResult, PolicyEngine, PolicyHook};
+use serde_json::json;
+
+struct Toggle(AtomicBool);
+
+#[async_trait::async_trait]
+impl PolicyHook for Toggle {
+    async fn evaluate(&self, _request: &HookRequest) -> Result<bool, String> {
+        Ok(self.0.load(Ordering::SeqCst))
+    }
+}
+
+struct Hang;
+
+#[async_trait::async_trait]
+impl PolicyHook for Hang {
+    async fn evaluate(&self, _request: &HookRequest) -> Result<bool, String> {
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        Ok(true)
+    }
+}
+
+struct Unreachable(AtomicBool);
+
+#[async_trait::async_trait]
+impl PolicyHook for Unreachable {
+    async fn evaluate(&self, _request: &HookRequest) -> Result<bool, String> {
+        if self.0.load(Ordering::SeqCst) {
+            Err("vault is unreachable".into())
+        } else {
+            Ok(true)
+        }
+    }
+}
+
+fn policy(rules: serde_json::Value, cache: serde_json::Value) -> PolicyEngine {
+    let doc = json!({ "default_allow": false, "rules": rules, "cache": cache });
+    PolicyEngine::parse(&serde_json::to_vec(&doc).expect("json")).expect("policy")
+}
+
+#[tokio::test]
+async fn hooks_gate_rules_and_failures_use_the_fail_mode() {
+    let policy = policy(
+        json!([
+            { "subject": "*", "action": "decrypt", "resource": "/vault/*", "hook": "vault", "timeout_ms": 50 },
+            { "subject": "*", "action": "read", "resource": "/public/*", "hook": "directory", "timeout_ms": 50, "on_failure": "open" },
+            { "subject": "*", "action": "read", "resource": "/missing/*", "hook": "nobody" },
+        ]),
+        json!({ "ttl_secs": 0 }),
+    );
+    let vault = Arc::new(Toggle(AtomicBool::new(true)));
+    policy.register_hook("vault", vault.clone());
+    policy.register_hook("directory", Arc::new(Hang));
+
+    let approved = policy
+        .explain_any(&["user:alice"], "decrypt", "/vault/ledger")
+        .await;
+    assert_eq!(approved.decision, Decision::Allow);
+    assert_eq!(approved.source, DecisionSource::Policy);
+    assert_eq!(approved.rule.expect("rule").hook.as_deref(), Some("vault"));
+    assert_eq!(approved.hooks[0].result, HookResult::Approved);
+
+    vault.0.store(false, Ordering::SeqCst);
+    let declined = policy
+        .explain_any(&["user:alice"], "decrypt", "/vault/ledger")
+        .await;
+    assert_eq!(declined.decision, Decision::Deny);
+    assert!(declined.rule.is_none(), "fell through to the default");
+    assert_eq!(declined.hooks[0].result, HookResult::Declined);
+
+    let timed_out = policy
+        .explain_any(&["user:alice"], "read", "/public/handbook")
+        .await;
+    assert_eq!(timed_out.decision, Decision::Allow);
+    assert_eq!(timed_out.source, DecisionSource::Fallback);
+    assert_eq!(timed_out.fail_mode, Some(FailMode::Open));
+    assert_eq!(timed_out.hooks[0].result, HookResult::TimedOut);
+    assert!(timed_out.hooks[0].elapsed_ms < 5_000);
+
+    let missing = policy
+        .explain_any(&["user:alice"], "read", "/missing/file")
+        .await;
+    assert_eq!(missing.decision, Decision::Deny);
+    assert_eq!(missing.fail_mode, Some(FailMode::Closed));
+    assert!(matches!(
+        &missing.hooks[0].result,
+        HookResult::Failed { reason } if reason.contains("nobody")
+    ));
+
+    let encoded = serde_json::to_value(&timed_out).expect("encode");
+    assert_eq!(encoded["hooks"][0]["result"], "timed_out");
+    assert_eq!(encoded["source"], "fallback");
+}
+
+#[tokio::test]
+async fn a_failing_hook_serves_a_stale_decision_before_falling_back() {
+    let policy = policy(
+        json!([{ "subject": "*", "action": "decrypt", "resource": "*", "hook": "vault" }]),
+        json!({ "ttl_secs": 1, "stale_secs": 300 }),
+    );
+    let vault = Arc::new(Unreachable(AtomicBool::new(false)));
+    policy.register_hook("vault", vault.clone());
+
+    assert!(policy
+        .evaluate("user:alice", "decrypt", "/srv/a")
+        .await
+        .expect("evaluate"));
+    vault.0.store(true, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(1_100)).await;
+
+    let stale = policy
+        .explain_any(&["user:alice"], "decrypt", "/srv/a")
+        .await;
+    assert_eq!(stale.source, DecisionSource::StaleCache);
+    assert_eq!(stale.decision, Decision::Allow);
+    assert!(stale.hooks[0].result.failed());
+
+    let fresh = policy.explain_any(&["user:bob"], "decrypt", "/srv/a").await;
+    assert_eq!(fresh.source, DecisionSource::Fallback);
+    assert_eq!(fresh.decision, Decision::Deny);
+    assert_eq!(policy.cache_stats().stale_hits, 1);
+}
+
+#[test]
+fn hook_settings_are_validated() {
+    for (rules, hooks) in [
+        (
+            json!([{ "subject": "*", "action": "*", "resource": "*", "timeout_ms": 10 }]),
+            json!({}),
+        ),
+        (
+            json!([{ "subject": "*", "action": "*", "resource": "*", "on_failure": "open" }]),
+            json!({}),
+        ),
+        (
+            json!([{ "subject": "*", "action": "*", "resource": "*", "hook": "x", "timeout_ms": 0 }]),
+            json!({}),
+        ),
+        (json!([]), json!({ "x": { "command": [] } })),
+    ] {
+        let doc = json!({ "rules": rules, "hooks": hooks });
+        assert!(
+            PolicyEngine::parse(&serde_json::to_vec(&doc).expect("json")).is_err(),
+            "{doc}"
+        );
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn command_hooks_are_explained_and_audited() {
+    use dg_core::Identity;
+
+    let temp = tempfile::tempdir().expect("tempdir");
+    let doc = json!({
+        "hooks": {
+            "approve": { "command": ["sh", "-c", "cat >/dev/null; exit 0"] },
+            "broken": { "command": ["sh", "-c", "echo 'directory offline' >&2; exit 3"] },
+            "stuck": { "command": ["sleep", "30"] },
+        },
+        "rules": [
+            { "subject": "user:alice", "action": "decrypt", "resource": "*", "hook": "approve", "effect": "allow" },
+            { "subject": "user:bob", "action": "decrypt", "resource": "*", "hook": "broken" },
+            { "subject": "user:carol", "action": "decrypt", "resource": "*", "hook": "stuck", "timeout_ms": 200, "on_failure": "open" },
+        ],
+        "cache": { "ttl_secs": 0 },
+    });
+    std::fs::write(
+        temp.path().join("policy.json"),
+        serde_json::to_vec(&doc).expect("json"),
+    )
+    .expect("policy");
+    let engine = engine(temp.path()).await;
+
+    let explain = |user: &'static str| {
+        let engine = engine.clone();
+        async move {
+            engine
+                .explain_decision(&Identity::new(user), "decrypt", "/srv/a.dgenc")
+                .await
+                .expect("explain")
+        }
+    };
+    let alice = explain("alice").await;
+    assert_eq!(
+        (alice.decision, alice.source),
+        (Decision::Allow, DecisionSource::Policy)
+    );
+    let bob = explain("bob").await;
+    assert_eq!(bob.decision, Decision::Deny);
+    assert!(matches!(
+        &bob.hooks[0].result,
+        HookResult::Failed { reason } if reason.contains("directory offline")
+    ));
+    let carol = explain("carol").await;
+    assert_eq!(carol.decision, Decision::Allow);
+    assert_eq!(carol.hooks[0].result, HookResult::TimedOut);
+    assert!(carol.hooks[0].elapsed_ms < 5_000);
+
+    let log = std::fs::read_to_string(temp.path().join("audit").join("audit.log")).expect("audit");
+    assert_eq!(log.matches("\"policy_fallback\"").count(), 2);
+}
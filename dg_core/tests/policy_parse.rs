@@ -0,0 +1,147 @@
+use dg_core::state::MemoryStore;
+use dg_core::PolicyEngine;
+use proptest::prelude::*;
+use serde_json::{json, Value};
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("runtime")
+        .block_on(future)
+}
+
+fn pattern() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just("*".to_string()),
+        "[a-z*?/]{0,12}",
+        "[\\[\\]{}!*?,\\\\a-z]{0,16}",
+        any::<String>(),
+    ]
+}
+
+fn rule() -> impl Strategy<Value = Value> {
+    (pattern(), pattern(), pattern(), prop::bool::ANY).prop_map(
+        |(subject, action, resource, allow)| {
+            json!({
+                "subject": subject,
+                "action": action,
+                "resource": resource,
+                "effect": if allow { "allow" } else { "deny" },
+            })
+        },
+    )
+}
+
+fn quota() -> impl Strategy<Value = Value> {
+    (pattern(), pattern(), any::<u64>(), any::<u64>()).prop_map(
+        |(subject, action, limit, window_secs)| {
+            json!({
+                "subject": subject,
+                "action": action,
+                "limit": limit,
+                "window_secs": window_secs,
+            })
+        },
+    )
+}
+
+fn document() -> impl Strategy<Value = Value> {
+    (
+        any::<bool>(),
+        prop::collection::vec(rule(), 0..8),
+        prop::collection::vec(quota(), 0..4),
+        prop::option::of((any::<u64>(), any::<u64>())),
+    )
+        .prop_map(|(default_allow, rules, quotas, lockout)| {
+            let mut doc =
+                json!({ "default_allow": default_allow, "rules": rules, "quotas": quotas });
+            if let Some((max_failures, cooldown_secs)) = lockout {
+                doc["lockout"] =
+                    json!({ "max_failures": max_failures, "cooldown_secs": cooldown_secs });
+            }
+            doc
+        })
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_bytes_never_panic(bytes in prop::collection::vec(any::<u8>(), 0..4096)) {
+        let _ = PolicyEngine::parse(&bytes);
+    }
+
+    #[test]
+    fn parsed_policies_evaluate_without_panicking(
+        doc in document(),
+        subject in any::<String>(),
+        action in any::<String>(),
+        now in any::<u64>(),
+    ) {
+        let bytes = serde_json::to_vec(&doc).expect("policy json");
+        if let Ok(policy) = PolicyEngine::parse(&bytes) {
+            block_on(async {
+                let store = MemoryStore::new();
+                let _ = policy.evaluate(&subject, &action, "data").await;
+                policy.warm_up().await;
+                let _ = policy.charge_quota(&store, &[&subject], &action, now).await;
+                let _ = policy.record_failure(&store, &subject, &action, now).await;
+                let _ = policy.check_lockout(&store, &subject, &action, now).await;
+            });
+        }
+    }
+
+    #[test]
+    fn rule_order_decides_between_allow_and_deny(first in any::<bool>(), resource in "[a-z]{1,8}") {
+        let (matched, fallback) = if first { ("allow", "deny") } else { ("deny", "allow") };
+        let doc = json!({
+            "default_allow": !first,
+            "rules": [
+                { "subject": "*", "action": "*", "resource": resource, "effect": matched },
+                { "subject": "*", "action": "*", "resource": "*", "effect": fallback },
+            ],
+        });
+        let bytes = serde_json::to_vec(&doc).expect("json");
+        let policy = PolicyEngine::parse(&bytes).expect("valid policy");
+        let allowed = block_on(policy.evaluate("anyone", "read", &resource)).expect("evaluate");
+        prop_assert_eq!(allowed, first);
+    }
+}
+
+#[test]
+fn oversized_and_pathological_policies_are_rejected() {
+    let padding = " ".repeat(2 << 20);
+    let oversized = format!("{{\"rules\": []{padding}}}");
+    let err = PolicyEngine::parse(oversized.as_bytes())
+        .err()
+        .expect("oversized policy");
+    assert!(err.contains("limit"), "{err}");
+
+    let long =
+        json!({ "rules": [{ "subject": "a".repeat(4096), "action": "*", "resource": "*" }] });
+    let err = PolicyEngine::parse(&serde_json::to_vec(&long).expect("json"))
+        .err()
+        .expect("long glob");
+    assert!(err.contains("longer than"), "{err}");
+
+    let rules: Vec<Value> = (0..5000)
+        .map(|_| json!({ "subject": "*", "action": "*", "resource": "*" }))
+        .collect();
+    let many = json!({ "rules": rules });
+    assert!(PolicyEngine::parse(&serde_json::to_vec(&many).expect("json")).is_err());
+}
+
+#[test]
+fn maximal_lockout_cooldown_saturates() {
+    let doc = json!({ "lockout": { "max_failures": 1, "cooldown_secs": u64::MAX } });
+    let policy = PolicyEngine::parse(&serde_json::to_vec(&doc).expect("json")).expect("policy");
+    let store = MemoryStore::new();
+    block_on(async {
+        policy
+            .record_failure(&store, "system", "decrypt", 10)
+            .await
+            .expect("record failure");
+        assert!(policy
+            .check_lockout(&store, "system", "decrypt", u64::MAX - 1)
+            .await
+            .is_err());
+    });
+}
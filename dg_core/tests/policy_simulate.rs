@@ -0,0 +1,100 @@
+use dg_core::policy::MatchedRule;
+use dg_core::{AccessRequest, Decision, PolicyEngine};
+use serde_json::json;
+
+fn request(subject: &str, action: &str, resource: &str, expect: Option<Decision>) -> AccessRequest {
+    AccessRequest {
+        subject: subject.into(),
+        action: action.into(),
+        resource: resource.into(),
+        attributes: Default::default(),
+        expect,
+    }
+}
+
+#[test]
+fn simulation_reports_the_rule_that_fired_for_each_request() {
+    let document = serde_json::to_vec(&json!({
+        "default_allow": false,
+        "rules": [
+            {
+                "subject": "contractor/*",
+                "action": "decrypt",
+                "resource": "vault/secret/**",
+                "effect": "deny"
+            },
+            { "subject": "*", "action": "decrypt", "resource": "vault/**" }
+        ]
+    }))
+    .expect("policy json");
+
+    let report = PolicyEngine::simulate(
+        &document,
+        vec![
+            request("alice", "decrypt", "vault/q3.pdf", Some(Decision::Allow)),
+            request(
+                "contractor/bob",
+                "decrypt",
+                "vault/secret/keys",
+                Some(Decision::Allow),
+            ),
+            request("alice", "encrypt", "vault/q3.pdf", None),
+        ],
+    )
+    .expect("simulate");
+
+    assert!(!report.default_allow);
+    assert_eq!(
+        (report.allowed, report.denied, report.unexpected),
+        (1, 2, 1)
+    );
+    assert!(!report.passed());
+
+    let decisions: Vec<_> = report
+        .results
+        .iter()
+        .map(|result| result.decision)
+        .collect();
+    assert_eq!(decisions, [Decision::Allow, Decision::Deny, Decision::Deny]);
+    assert_eq!(
+        report.results[1].rule,
+        Some(MatchedRule {
+            index: 0,
+            subject: "contractor/*".into(),
+            action: "decrypt".into(),
+            resource: "vault/secret/**".into(),
+            when: None,
+            hook: None,
+        })
+    );
+    assert_eq!(
+        report.results[0].rule.as_ref().map(|rule| rule.index),
+        Some(1)
+    );
+    assert_eq!(report.results[2].rule, None, "falls through to the default");
+    let expectations: Vec<_> = report
+        .results
+        .iter()
+        .map(|result| result.as_expected)
+        .collect();
+    assert_eq!(expectations, [Some(true), Some(false), None]);
+}
+
+#[test]
+fn simulation_accepts_request_corpora_and_rejects_invalid_policies() {
+    let requests: Vec<AccessRequest> = serde_json::from_value(json!([
+        { "subject": "system", "action": "encrypt", "resource": "notes.txt", "expect": "allow" }
+    ]))
+    .expect("requests json");
+    let report = PolicyEngine::simulate(b"{}", requests).expect("empty policy");
+    assert!(report.passed());
+    assert_eq!(report.allowed, 1);
+
+    let err = PolicyEngine::simulate(
+        br#"{"rules": [{"subject": "[", "action": "*", "resource": "*"}]}"#,
+        vec![],
+    )
+    .expect_err("invalid glob");
+    assert!(err.contains("subject"), "{err}");
+    PolicyEngine::simulate(b"not json", vec![]).expect_err("invalid document");
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use common::engine;
+use dg_core::policy::{find_policy, PolicyFormat};
+use dg_core::PolicyEngine;
+use tempfile::tempdir;
+
+const POLICY_YAML: &str = "\
+# Vault access policy, owned by the security team
+default_allow: false
+rules:
+# Contractors never see secrets
+- subject: contractor/*
+  action: decrypt
+  resource: vault/secret/**
+  effect: deny
+- subject: '*' # everyone else
+  action: decrypt
+  resource: vault/**
+  effect: allow
+";
+
+#[tokio::test]
+async fn yaml_policies_evaluate_like_json() {
+    let engine =
+        PolicyEngine::parse_as(POLICY_YAML.as_bytes(), PolicyFormat::Yaml).expect("yaml policy");
+    assert!(engine
+        .evaluate("alice", "decrypt", "vault/q3.pdf")
+        .await
+        .expect("evaluate"));
+    assert!(!engine
+        .evaluate("contractor/bob", "decrypt", "vault/secret/keys")
+        .await
+        .expect("evaluate"));
+    assert!(!engine
+        .evaluate("alice", "encrypt", "vault/q3.pdf")
+        .await
+        .expect("evaluate"));
+
+    let json = PolicyEngine::convert(
+        POLICY_YAML.as_bytes(),
+        PolicyFormat::Yaml,
+        PolicyFormat::Json,
+        None,
+    )
+    .expect("to json");
+    let document: serde_json::Value = serde_json::from_slice(&json).expect("json");
+    assert_eq!(document["default_allow"], false);
+    assert_eq!(document["rules"][1]["subject"], "*");
+    assert!(document.get("escrow").is_none());
+
+    let err = PolicyEngine::parse_as(b"rules: [", PolicyFormat::Yaml)
+        .err()
+        .expect("bad yaml");
+    assert!(err.contains("invalid policy format"), "{err}");
+    assert_eq!(
+        PolicyFormat::from_path("team/Policy.YML".as_ref()),
+        PolicyFormat::Yaml
+    );
+    assert_eq!(
+        PolicyFormat::from_path("policy.json".as_ref()),
+        PolicyFormat::Json
+    );
+}
+
+#[test]
+fn yaml_round_trip_keeps_comments() {
+    let saved = PolicyEngine::convert(
+        POLICY_YAML.as_bytes(),
+        PolicyFormat::Yaml,
+        PolicyFormat::Yaml,
+        Some(POLICY_YAML),
+    )
+    .expect("round trip");
+    let saved = String::from_utf8(saved).expect("utf8");
+    assert!(
+        saved.starts_with(
+            "# Vault access policy, owned by the security team\ndefault_allow: false\n"
+        ),
+        "{saved}"
+    );
+    assert!(
+        saved.contains("# Contractors never see secrets\n- subject: contractor/*\n"),
+        "{saved}"
+    );
+    assert!(
+        saved.contains("- subject: '*' # everyone else\n"),
+        "{saved}"
+    );
+    PolicyEngine::parse_as(saved.as_bytes(), PolicyFormat::Yaml).expect("still valid");
+
+    let edited = POLICY_YAML.replace("contractor/*", "vendor/*");
+    let saved = PolicyEngine::convert(
+        edited.as_bytes(),
+        PolicyFormat::Yaml,
+        PolicyFormat::Yaml,
+        Some(POLICY_YAML),
+    )
+    .expect("edited");
+    let saved = String::from_utf8(saved).expect("utf8");
+    assert!(saved.contains("- subject: vendor/*\n"), "{saved}");
+    assert!(saved.contains("# Contractors never see secrets"), "{saved}");
+}
+
+#[tokio::test]
+async fn engine_loads_policy_yaml_from_the_data_dir() {
+    let temp = tempdir().expect("tempdir");
+    std::fs::write(temp.path().join("policy.yaml"), POLICY_YAML).expect("policy");
+    assert_eq!(
+        find_policy(temp.path()).await,
+        Some(temp.path().join("policy.yaml"))
+    );
+
+    let engine = engine(temp.path()).await;
+    assert!(engine
+        .check_policy("alice", "decrypt", "vault/q3.pdf")
+        .await
+        .expect("allowed"));
+    assert!(!engine
+        .check_policy("alice", "encrypt", "vault/q3.pdf")
+        .await
+        .expect("denied"));
+    engine.shutdown().await.expect("shutdown");
+}
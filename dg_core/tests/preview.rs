@@ -0,0 +1,86 @@
+mod common;
+
+use common::engine;
+use dg_core::api::{DGError, EncryptRequest};
+use dg_core::{Preview, RekeyContext};
+use tempfile::tempdir;
+
+fn thumbnail() -> Preview {
+    Preview {
+        media_type: "image/jpeg".into(),
+        data: vec![0xff, 0xd8, 0xff, 0xe0, 1, 2, 3],
+    }
+}
+
+fn request(preview: Option<Preview>) -> EncryptRequest {
+    EncryptRequest {
+        plaintext: b"holiday photo".to_vec(),
+        preview,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn previews_are_sealed_in_the_metadata_and_survive_rekey() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    let envelope = engine
+        .encrypt(request(Some(thumbnail())))
+        .await
+        .expect("encrypt");
+    let stored = envelope.meta["preview"].to_string();
+    assert!(!stored.contains("[255,216"), "{stored}");
+    assert_eq!(
+        engine.preview(&envelope).await.expect("preview"),
+        Some(thumbnail())
+    );
+
+    let rekeyed = engine
+        .rekey(envelope.clone(), RekeyContext::rekey("alice"))
+        .await
+        .expect("rekey");
+    assert_ne!(rekeyed.meta["preview"], envelope.meta["preview"]);
+    assert_eq!(
+        engine.preview(&rekeyed).await.expect("preview"),
+        Some(thumbnail())
+    );
+
+    let plain = engine.encrypt(request(None)).await.expect("encrypt");
+    assert_eq!(engine.preview(&plain).await.expect("preview"), None);
+    assert!(engine
+        .encrypt(request(Some(Preview {
+            media_type: "text/html".into(),
+            data: b"<script>".to_vec(),
+        })))
+        .await
+        .is_err());
+
+    let other = tempdir().expect("tempdir");
+    let stranger = common::engine(other.path()).await;
+    assert!(matches!(
+        stranger.preview(&envelope).await,
+        Err(DGError::Crypto(_))
+    ));
+}
+
+#[tokio::test]
+async fn policy_can_withhold_previews() {
+    let temp = tempdir().expect("tempdir");
+    std::fs::write(
+        temp.path().join("policy.json"),
+        r#"{ "default_allow": true, "rules": [
+            { "subject": "*", "action": "preview", "resource": "*", "effect": "deny" }
+        ] }"#,
+    )
+    .expect("policy");
+    let engine = engine(temp.path()).await;
+    let envelope = engine
+        .encrypt(request(Some(thumbnail())))
+        .await
+        .expect("encrypt");
+    assert!(envelope.meta.get("preview").is_none());
+    assert_eq!(
+        *engine.decrypt(envelope).await.expect("decrypt"),
+        b"holiday photo"
+    );
+}
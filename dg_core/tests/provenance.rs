@@ -0,0 +1,61 @@
+mod common;
+
+use common::engine;
+use dg_core::api::{DGError, EncryptRequest};
+use dg_core::provenance::provenance;
+use dg_core::RekeyContext;
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn rekeys_append_an_authenticated_provenance_chain() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+
+    let envelope = engine
+        .encrypt(EncryptRequest {
+            plaintext: b"ledger".to_vec(),
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+    assert!(provenance(&envelope.meta).expect("chain").is_empty());
+
+    let envelope = engine
+        .rekey(envelope, RekeyContext::migrate("user:alice", 1, 2))
+        .await
+        .expect("migrate");
+    let envelope = engine
+        .rekey(envelope, RekeyContext::rekey("user:bob"))
+        .await
+        .expect("rekey");
+    let chain = provenance(&envelope.meta).expect("chain");
+    assert_eq!(chain.len(), 2);
+    assert_eq!(chain[0].action, "migrate");
+    assert_eq!(chain[0].subject, "user:alice");
+    assert_eq!(
+        (chain[0].from_format, chain[0].to_format),
+        (Some(1), Some(2))
+    );
+    assert_eq!(chain[1].action, "rekey");
+    assert_eq!(chain[1].subject, "user:bob");
+    assert_eq!(chain[1].previous_key_ids, vec!["master".to_owned()]);
+    assert!(chain[1].timestamp >= chain[0].timestamp && chain[0].timestamp > 0);
+    assert_eq!(
+        *engine.decrypt(envelope.clone()).await.expect("decrypt"),
+        b"ledger"
+    );
+
+    let mut forged = envelope.clone();
+    forged.meta["provenance"][0]["subject"] = "user:mallory".into();
+    let err = engine.decrypt(forged).await.expect_err("edited chain");
+    assert!(matches!(err, DGError::Crypto(_)), "{err}");
+
+    let mut stripped = envelope;
+    stripped
+        .meta
+        .as_object_mut()
+        .expect("object")
+        .remove("provenance");
+    assert!(engine.decrypt(stripped).await.is_err());
+    engine.shutdown().await.expect("shutdown");
+}
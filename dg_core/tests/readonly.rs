@@ -0,0 +1,113 @@
+mod common;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use dg_core::api::{new_default, DGConfig, DGError, EncryptRequest};
+use dg_core::RekeyContext;
+use tempfile::tempdir;
+
+fn config(data_dir: &Path, read_only: bool) -> DGConfig {
+    DGConfig {
+        read_only,
+        ..common::config(data_dir)
+    }
+}
+
+fn snapshot(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(next) = pending.pop() {
+        for entry in std::fs::read_dir(&next).expect("read dir") {
+            let path = entry.expect("entry").path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                let bytes = std::fs::read(&path).expect("read file");
+                files.insert(path, bytes);
+            }
+        }
+    }
+    files
+}
+
+fn assert_read_only(result: Result<impl std::fmt::Debug, DGError>) {
+    let err = result.expect_err("write should be rejected");
+    assert!(matches!(err, DGError::ReadOnly(_)), "{err}");
+    assert_eq!(err.code(), "engine.read_only");
+}
+
+#[tokio::test]
+async fn read_only_mode_decrypts_without_touching_the_data_dir() {
+    let temp = tempdir().expect("tempdir");
+    let engine = new_default();
+    engine.init(config(temp.path(), false)).await.expect("init");
+    let envelope = engine
+        .encrypt(EncryptRequest {
+            plaintext: b"archived".to_vec(),
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+    engine.shutdown().await.expect("shutdown");
+    let before = snapshot(temp.path());
+
+    let archive = new_default();
+    archive
+        .init(config(temp.path(), true))
+        .await
+        .expect("read-only init");
+    assert_eq!(
+        *archive.decrypt(envelope.clone()).await.expect("decrypt"),
+        b"archived"
+    );
+    archive
+        .check_policy("auditor", "decrypt", "archive")
+        .await
+        .expect("policy check");
+    archive.integrity(false).await.expect("integrity report");
+
+    assert_read_only(
+        archive
+            .encrypt(EncryptRequest {
+                plaintext: b"new".to_vec(),
+                ..Default::default()
+            })
+            .await,
+    );
+    assert_read_only(archive.import_public_key("not a key", "bob").await);
+    assert_read_only(archive.revoke_key("master").await);
+    assert_read_only(archive.rekey(envelope, RekeyContext::default()).await);
+    assert_read_only(archive.sign_detached("master", b"data").await);
+    assert_read_only(archive.integrity(true).await);
+    archive.shutdown().await.expect("shutdown");
+
+    assert_eq!(snapshot(temp.path()), before);
+}
+
+#[tokio::test]
+async fn read_only_mode_requires_an_existing_data_dir() {
+    let temp = tempdir().expect("tempdir");
+    let missing = temp.path().join("missing");
+    let err = new_default()
+        .init(config(&missing, true))
+        .await
+        .expect_err("missing dir");
+    assert!(matches!(err, DGError::Config(_)), "{err}");
+    assert!(!missing.exists());
+
+    let err = new_default()
+        .init(config(temp.path(), true))
+        .await
+        .expect_err("missing master key");
+    assert!(matches!(err, DGError::Config(_)), "{err}");
+
+    let err = new_default()
+        .init(DGConfig {
+            repair_on_init: true,
+            ..config(temp.path(), true)
+        })
+        .await
+        .expect_err("repair conflicts with read-only");
+    assert!(matches!(err, DGError::Config(_)), "{err}");
+}
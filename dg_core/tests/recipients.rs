@@ -0,0 +1,109 @@
+mod common;
+
+use base64::{engine::general_purpose, Engine as _};
+use common::engine;
+use dg_core::api::{DGError, EncryptRequest};
+use dg_core::keyring::{KeyMetadata, KeySource};
+use tempfile::tempdir;
+
+// SubjectPublicKeyInfo prefix for a raw X25519 key (OID 1.3.101.110).
+const X25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x6e, 0x03, 0x21, 0x00,
+];
+
+fn public_pem(meta: &KeyMetadata) -> String {
+    let mut der = X25519_SPKI_PREFIX.to_vec();
+    der.extend(
+        general_purpose::STANDARD
+            .decode(&meta.public_key)
+            .expect("public key"),
+    );
+    format!(
+        "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+        general_purpose::STANDARD.encode(der)
+    )
+}
+
+fn request(recipients: &[&str]) -> EncryptRequest {
+    EncryptRequest {
+        plaintext: b"board minutes".to_vec(),
+        recipients: recipients.iter().map(|name| name.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn any_listed_recipient_can_decrypt_with_their_own_key() {
+    let (alice_dir, bob_dir, carol_dir) = (
+        tempdir().expect("tempdir"),
+        tempdir().expect("tempdir"),
+        tempdir().expect("tempdir"),
+    );
+    let (alice, bob, carol) = (
+        engine(alice_dir.path()).await,
+        engine(bob_dir.path()).await,
+        engine(carol_dir.path()).await,
+    );
+
+    let bob_key = bob.generate_identity("bob").await.expect("generate");
+    assert_eq!(bob_key.provenance.source, KeySource::Generated);
+    assert!(bob.generate_identity("bob").await.is_err());
+    let listed = bob.list_identities().await.expect("list");
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].id, bob_key.id);
+    let carol_key = carol.generate_identity("carol").await.expect("generate");
+
+    for (meta, contact) in [(&bob_key, "bob"), (&carol_key, "carol")] {
+        alice
+            .import_public_key(&public_pem(meta), contact)
+            .await
+            .expect("import contact");
+    }
+    let envelope = alice
+        .encrypt(request(&["bob", "carol", &bob_key.id]))
+        .await
+        .expect("encrypt");
+    let wraps = envelope.meta["key_wraps"].as_array().expect("wraps");
+    let recipients: Vec<&str> = wraps
+        .iter()
+        .filter(|wrap| wrap["kind"] == "recipient")
+        .map(|wrap| wrap["key_id"].as_str().expect("key id"))
+        .collect();
+    assert_eq!(recipients, [bob_key.id.as_str(), carol_key.id.as_str()]);
+
+    for reader in [&alice, &bob, &carol] {
+        assert_eq!(
+            *reader.decrypt(envelope.clone()).await.expect("decrypt"),
+            b"board minutes"
+        );
+    }
+
+    let outsider_dir = tempdir().expect("tempdir");
+    let outsider = engine(outsider_dir.path()).await;
+    assert!(matches!(
+        outsider.decrypt(envelope).await,
+        Err(DGError::Crypto(_))
+    ));
+}
+
+#[tokio::test]
+async fn unknown_or_revoked_recipients_are_refused() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path()).await;
+    let err = engine
+        .encrypt(request(&["nobody"]))
+        .await
+        .expect_err("unknown recipient");
+    assert!(matches!(err, DGError::Config(_)), "{err}");
+
+    engine.generate_identity("dana").await.expect("generate");
+    engine
+        .encrypt(request(&["dana"]))
+        .await
+        .expect("encrypt to own identity");
+    engine.revoke_key("dana").await.expect("revoke");
+    assert!(engine.encrypt(request(&["dana"])).await.is_err());
+
+    let log = std::fs::read_to_string(temp.path().join("audit").join("audit.log")).expect("audit");
+    assert!(log.contains("\"generate_key\""));
+}
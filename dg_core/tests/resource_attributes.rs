@@ -0,0 +1,139 @@
+use dg_core::resource_attrs::parse_zone_identifier;
+use dg_core::{
+    AccessRequest, Decision, DriveType, PolicyEngine, ResourceAttributes, ResourceCondition,
+    SecurityZone,
+};
+use serde_json::json;
+
+fn policy(rules: serde_json::Value) -> PolicyEngine {
+    let doc = json!({ "default_allow": true, "rules": rules });
+    PolicyEngine::parse(&serde_json::to_vec(&doc).expect("json")).expect("policy")
+}
+
+fn downloaded() -> ResourceAttributes {
+    ResourceAttributes {
+        owner_sid: Some("S-1-5-21-1004336348-1177238915-682003330-1001".into()),
+        zone: Some(SecurityZone::Internet),
+        drive_type: Some(DriveType::Fixed),
+    }
+}
+
+#[test]
+fn zone_identifier_streams_are_parsed() {
+    let stream = "[ZoneTransfer]\r\nZoneId=3\r\nReferrerUrl=https://example.com/\r\n";
+    assert_eq!(parse_zone_identifier(stream), Some(SecurityZone::Internet));
+    assert_eq!(
+        parse_zone_identifier("[ZoneTransfer]\nZoneId=0\n"),
+        Some(SecurityZone::LocalMachine)
+    );
+    assert_eq!(parse_zone_identifier("[ZoneTransfer]\nZoneId=9\n"), None);
+    assert_eq!(parse_zone_identifier("not a zone stream"), None);
+}
+
+#[tokio::test]
+async fn rules_can_match_on_resource_attributes() {
+    let policy = policy(json!([
+        { "subject": "*", "action": "decrypt", "resource": "*", "when": { "zone": "internet" }, "effect": "deny" },
+        { "subject": "*", "action": "export", "resource": "*", "when": { "drive_type": "removable" }, "effect": "deny" },
+        { "subject": "*", "action": "share", "resource": "*", "when": { "owner_sid": "s-1-5-21-*-1001" }, "effect": "deny" },
+    ]));
+    assert!(policy.uses_resource_attributes().await);
+
+    let denied = policy
+        .explain_with(
+            &["user:alice"],
+            "decrypt",
+            "C:\\Downloads\\q3.xlsx",
+            &downloaded(),
+        )
+        .await;
+    assert_eq!(denied.decision, Decision::Deny);
+    assert_eq!(
+        denied.rule.expect("rule").when,
+        Some(ResourceCondition {
+            zone: Some(SecurityZone::Internet),
+            ..Default::default()
+        })
+    );
+    // Cached decisions are kept apart per attribute set.
+    let local = ResourceAttributes {
+        zone: Some(SecurityZone::LocalMachine),
+        ..downloaded()
+    };
+    for attributes in [&local, &ResourceAttributes::default()] {
+        let allowed = policy
+            .explain_with(
+                &["user:alice"],
+                "decrypt",
+                "C:\\Downloads\\q3.xlsx",
+                attributes,
+            )
+            .await;
+        assert_eq!(allowed.decision, Decision::Allow);
+    }
+    assert_eq!(
+        policy
+            .explain_any(&["user:alice"], "decrypt", "C:\\Downloads\\q3.xlsx")
+            .await
+            .decision,
+        Decision::Allow
+    );
+
+    let usb = ResourceAttributes {
+        drive_type: Some(DriveType::Removable),
+        ..Default::default()
+    };
+    let export = policy
+        .explain_with(&["user:alice"], "export", "E:\\q3.xlsx", &usb)
+        .await;
+    assert_eq!(export.decision, Decision::Deny);
+    let share = policy
+        .explain_with(&["user:alice"], "share", "C:\\q3.xlsx", &downloaded())
+        .await;
+    assert_eq!(
+        share.decision,
+        Decision::Deny,
+        "owner SIDs match case-insensitively"
+    );
+}
+
+#[test]
+fn simulations_take_attributes_and_conditions_are_validated() {
+    let document = serde_json::to_vec(&json!({
+        "default_allow": true,
+        "rules": [
+            { "subject": "*", "action": "decrypt", "resource": "*", "when": { "zone": "internet" }, "effect": "deny" },
+        ],
+    }))
+    .expect("json");
+    let requests: Vec<AccessRequest> = serde_json::from_value(json!([
+        { "subject": "user:alice", "action": "decrypt", "resource": "a", "attributes": { "zone": "internet" } },
+        { "subject": "user:alice", "action": "decrypt", "resource": "a" },
+    ]))
+    .expect("requests");
+    let report = PolicyEngine::simulate(&document, requests).expect("simulate");
+    assert_eq!((report.denied, report.allowed), (1, 1));
+
+    for when in [
+        json!({}),
+        json!({ "zone": "moon" }),
+        json!({ "owner": "S-1-5-18" }),
+        json!({ "owner_sid": "[" }),
+    ] {
+        let doc =
+            json!({ "rules": [{ "subject": "*", "action": "*", "resource": "*", "when": when }] });
+        assert!(
+            PolicyEngine::parse(&serde_json::to_vec(&doc).expect("json")).is_err(),
+            "{doc}"
+        );
+    }
+}
+
+#[cfg(not(windows))]
+#[tokio::test]
+async fn other_platforms_report_no_attributes() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let file = temp.path().join("report.csv");
+    std::fs::write(&file, b"q3").expect("write");
+    assert!(ResourceAttributes::for_path(&file).is_empty());
+}
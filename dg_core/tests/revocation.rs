@@ -0,0 +1,160 @@
+mod common;
+
+use std::path::Path;
+
+use common::config;
+use dg_core::api::{new_default, EncryptRequest};
+use dg_core::audit::AuditLog;
+use dg_core::index::EnvelopeIndex;
+use dg_core::RekeyContext;
+use serde_json::json;
+use tempfile::tempdir;
+use tokio::fs;
+
+const RETIRED_ESCROW: &str = "3p7bfXt9wbTTW2HC7OQ1Nz+DQ8hbeGdNrfx+FG+IK08=";
+const RETIRED_ESCROW_PEM: &str = "-----BEGIN PUBLIC KEY-----
+MCowBQYDK2VuAyEA3p7bfXt9wbTTW2HC7OQ1Nz+DQ8hbeGdNrfx+FG+IK08=
+-----END PUBLIC KEY-----
+";
+const CURRENT_ESCROW: &str = "Ecq99UZ7Een7EkvwhYkEAH0PpvOC4g9P8HJ9gWOh2Hg=";
+
+async fn write_policy(data_dir: &Path, escrow: &str) {
+    let policy = json!({ "default_allow": true, "escrow": { "public_key": escrow } });
+    fs::write(
+        data_dir.join("policy.json"),
+        serde_json::to_vec(&policy).expect("policy json"),
+    )
+    .await
+    .expect("write policy");
+}
+
+fn wrap_ids(meta: &serde_json::Value) -> Vec<String> {
+    meta["key_wraps"]
+        .as_array()
+        .expect("key wraps")
+        .iter()
+        .filter_map(|wrap| wrap["key_id"].as_str().map(str::to_owned))
+        .collect()
+}
+
+#[tokio::test]
+async fn revoked_escrow_wrap_is_replaced_on_rekey() {
+    let temp = tempdir().expect("tempdir");
+    write_policy(temp.path(), RETIRED_ESCROW).await;
+    let engine = new_default();
+    engine.init(config(temp.path())).await.expect("init");
+    let envelope = engine
+        .encrypt(EncryptRequest {
+            plaintext: b"ledger".to_vec(),
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+    let retired_id = wrap_ids(&envelope.meta).remove(0);
+
+    let mut index = EnvelopeIndex::load(temp.path()).await.expect("index");
+    let envelope_path = temp.path().join("ledger.dgenc");
+    index
+        .record(&envelope_path, &envelope)
+        .await
+        .expect("record");
+    engine.shutdown().await.expect("shutdown");
+
+    write_policy(temp.path(), CURRENT_ESCROW).await;
+    engine.init(config(temp.path())).await.expect("re-init");
+    engine
+        .import_public_key(RETIRED_ESCROW_PEM, "retired-escrow")
+        .await
+        .expect("import");
+    let revoked = engine.revoke_key("retired-escrow").await.expect("revoke");
+    assert_eq!(revoked.id, retired_id);
+    assert!(revoked.revoked_at.is_some());
+
+    let index = EnvelopeIndex::load(temp.path())
+        .await
+        .expect("reload index");
+    assert_eq!(index.referencing(&retired_id), vec![envelope_path]);
+
+    let rekeyed = engine
+        .rekey(envelope.clone(), RekeyContext::default())
+        .await
+        .expect("rekey");
+    let ids = wrap_ids(&rekeyed.meta);
+    assert_eq!(ids.len(), 1);
+    assert!(!ids.contains(&retired_id));
+    assert_ne!(rekeyed.bytes, envelope.bytes);
+    assert_eq!(*engine.decrypt(rekeyed).await.expect("decrypt"), b"ledger");
+
+    let audit = AuditLog::open(temp.path()).await.expect("audit");
+    let actions: Vec<String> = audit
+        .read_all()
+        .await
+        .expect("read audit")
+        .into_iter()
+        .map(|event| event.action)
+        .collect();
+    assert!(actions.contains(&"revoke".to_owned()));
+    assert!(actions.contains(&"rekey".to_owned()));
+
+    assert!(engine.revoke_key("nobody").await.is_err());
+    engine.shutdown().await.expect("shutdown");
+}
+
+#[tokio::test]
+async fn revoked_contacts_cannot_receive_new_shares() {
+    let temp = tempdir().expect("tempdir");
+    let engine = new_default();
+    engine.init(config(temp.path())).await.expect("init");
+    engine
+        .import_public_key(RETIRED_ESCROW_PEM, "former-custodian")
+        .await
+        .expect("import");
+    let request = EncryptRequest {
+        plaintext: b"x".to_vec(),
+        recipients: vec!["former-custodian".into()],
+        threshold: Some(1),
+        ..Default::default()
+    };
+    let envelope = engine.encrypt(request.clone()).await.expect("encrypt");
+    assert!(engine
+        .rekey(envelope, RekeyContext::default())
+        .await
+        .is_err());
+
+    engine.revoke_key("former-custodian").await.expect("revoke");
+    assert!(engine.encrypt(request).await.is_err());
+}
+
+#[tokio::test]
+async fn rekey_drops_revoked_recipients_from_the_recipient_list() {
+    let temp = tempdir().expect("tempdir");
+    let engine = new_default();
+    engine.init(config(temp.path())).await.expect("init");
+    engine
+        .import_public_key(RETIRED_ESCROW_PEM, "carol")
+        .await
+        .expect("import carol");
+    let dave = format!(
+        "-----BEGIN PUBLIC KEY-----\nMCowBQYDK2VuAyEA{CURRENT_ESCROW}\n-----END PUBLIC KEY-----\n"
+    );
+    engine
+        .import_public_key(&dave, "dave")
+        .await
+        .expect("import dave");
+    let envelope = engine
+        .encrypt(EncryptRequest {
+            plaintext: b"ledger".to_vec(),
+            recipients: vec!["carol".into(), "dave".into()],
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+
+    engine.revoke_key("carol").await.expect("revoke");
+    let rekeyed = engine
+        .rekey(envelope, RekeyContext::default())
+        .await
+        .expect("rekey");
+    assert_eq!(rekeyed.meta["recipients"], json!(["dave"]));
+    assert_eq!(wrap_ids(&rekeyed.meta).len(), 1);
+}
@@ -0,0 +1,156 @@
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use common::{config, engine_with, FAST};
+use dg_core::api::{DGConfig, DGError, DataGuardian, EncryptRequest};
+use dg_core::{KeyProtection, SecretKey};
+use tempfile::tempdir;
+use zeroize::Zeroizing;
+
+async fn engine(data_dir: &Path, protection: KeyProtection) -> Arc<dyn DataGuardian + Send + Sync> {
+    engine_with(DGConfig {
+        key_protection: protection,
+        ..config(data_dir)
+    })
+    .await
+}
+
+#[test]
+fn secret_keys_read_back_and_never_print_their_bytes() {
+    let key = SecretKey::new(&[0xA5; 32]);
+    assert_eq!(*key, [0xA5; 32]);
+    let printed = format!("{key:?}");
+    assert!(printed.starts_with("SecretKey"));
+    assert!(!printed.contains("165"));
+    assert!(!printed.to_lowercase().contains("a5"));
+
+    let other = SecretKey::new(&[1; 32]);
+    drop(key);
+    assert_eq!(*other, [1; 32]);
+}
+
+#[test]
+fn every_secret_key_gets_a_page_of_its_own() {
+    let keys: Vec<SecretKey> = (0..8u8).map(|byte| SecretKey::new(&[byte; 32])).collect();
+    let mut pages: Vec<usize> = keys.iter().map(|key| key.as_ptr() as usize).collect();
+    assert!(pages.iter().all(|page| page % 16384 == 0), "{pages:x?}");
+    pages.sort_unstable();
+    pages.dedup();
+    assert_eq!(pages.len(), keys.len());
+
+    // Dropping (and unlocking) every other key leaves the rest readable and intact.
+    let survivors: Vec<SecretKey> = keys
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, key)| (index % 2 == 1).then_some(key))
+        .collect();
+    for (index, key) in survivors.iter().enumerate() {
+        let byte = (index * 2 + 1) as u8;
+        assert_eq!(**key, [byte; 32]);
+    }
+}
+
+#[tokio::test]
+async fn decrypted_plaintext_carries_a_wipe_guard() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path(), KeyProtection::None).await;
+    let envelope = engine
+        .encrypt(EncryptRequest {
+            plaintext: b"quarterly payroll".to_vec(),
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+
+    let plaintext: Zeroizing<Vec<u8>> = engine.decrypt(envelope).await.expect("decrypt");
+    assert_eq!(plaintext.as_slice(), b"quarterly payroll");
+}
+
+#[tokio::test]
+async fn the_master_key_survives_a_lock_and_unlock() {
+    let temp = tempdir().expect("tempdir");
+    let engine = engine(temp.path(), KeyProtection::Passphrase(FAST)).await;
+    engine
+        .unlock("correct horse battery")
+        .await
+        .expect("first unlock");
+    let envelope = engine
+        .encrypt(EncryptRequest {
+            plaintext: b"board minutes".to_vec(),
+            ..Default::default()
+        })
+        .await
+        .expect("encrypt");
+
+    engine.lock().await.expect("lock");
+    assert!(engine.decrypt(envelope.clone()).await.is_err());
+
+    engine
+        .unlock("correct horse battery")
+        .await
+        .expect("second unlock");
+    let plaintext = engine.decrypt(envelope).await.expect("decrypt");
+    assert_eq!(plaintext.as_slice(), b"board minutes");
+}
+
+fn stored_secrets(data_dir: &Path) -> Vec<String> {
+    let raw: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(data_dir.join("keys").join("keyring.json")).expect("read keyring"),
+    )
+    .expect("keyring json");
+    raw["identities"]
+        .as_array()
+        .expect("identities")
+        .iter()
+        .map(|identity| identity["secret"].as_str().expect("secret").to_owned())
+        .collect()
+}
+
+#[tokio::test]
+async fn keyring_secrets_are_sealed_behind_the_passphrase() {
+    let temp = tempdir().expect("tempdir");
+    let plain = engine(temp.path(), KeyProtection::None).await;
+    plain
+        .generate_identity("before")
+        .await
+        .expect("plain identity");
+    plain.shutdown().await.expect("shutdown");
+
+    let engine = engine(temp.path(), KeyProtection::Passphrase(FAST)).await;
+    assert!(matches!(
+        engine.generate_key_hierarchy("signer").await,
+        Err(DGError::Locked(_))
+    ));
+    engine
+        .unlock("correct horse battery")
+        .await
+        .expect("first unlock");
+    engine
+        .generate_key_hierarchy("signer")
+        .await
+        .expect("hierarchy");
+    // The identity stored before the passphrase was set is sealed on the first unlock.
+    let secrets = stored_secrets(temp.path());
+    assert_eq!(secrets.len(), 5);
+    assert!(secrets.iter().all(|secret| secret.starts_with("sealed:")));
+
+    engine
+        .sign_detached("signer", b"release notes")
+        .await
+        .expect("sign while unlocked");
+    engine.lock().await.expect("lock");
+    assert!(engine
+        .sign_detached("signer", b"release notes")
+        .await
+        .is_err());
+    engine
+        .unlock("correct horse battery")
+        .await
+        .expect("second unlock");
+    engine
+        .sign_detached("signer", b"release notes")
+        .await
+        .expect("sign after unlock");
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use common::config;
+use dg_core::api::{new_default, DGConfig};
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn self_test_passes_before_and_after_init() {
+    let engine = new_default();
+    let report = engine.self_test().await.expect("self-test");
+    assert!(
+        report.passed,
+        "failures: {:?}",
+        report.failures().collect::<Vec<_>>()
+    );
+    assert!(report
+        .checks
+        .iter()
+        .any(|check| check.name == "envelope/round-trip"));
+
+    let temp = tempdir().expect("tempdir");
+    engine
+        .init(DGConfig {
+            self_test_on_init: true,
+            ..config(temp.path())
+        })
+        .await
+        .expect("init with startup self-test");
+
+    let report = engine.self_test().await.expect("self-test");
+    assert!(report.passed);
+    assert_eq!(report.failures().count(), 0);
+
+    engine.shutdown().await.expect("shutdown");
+}
@@ -0,0 +1,192 @@
+mod common;
+
+use std::sync::Arc;
+use std::thread;
+
+use common::config;
+use dg_core::api::{new_default, DGError, EncryptRequest};
+use dg_core::state::{self, JournalStore, StateBackend, StateStore};
+use serde_json::json;
+use tempfile::tempdir;
+use tokio::fs;
+
+fn request(plaintext: &[u8]) -> EncryptRequest {
+    EncryptRequest {
+        plaintext: plaintext.to_vec(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn journal_counters_survive_reopen_and_concurrent_writers() {
+    let temp = tempdir().expect("tempdir");
+    let path = temp.path().join("counters.log");
+    let store = Arc::new(JournalStore::open(&path).expect("open"));
+
+    let writers: Vec<_> = (0..4)
+        .map(|_| {
+            let store = Arc::clone(&store);
+            thread::spawn(move || {
+                for _ in 0..50 {
+                    store.add("hits", 1).expect("add");
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().expect("writer");
+    }
+    store.set("other", 7).expect("set");
+    store.set("gone", 3).expect("set");
+    store.set("gone", 0).expect("clear");
+    drop(store);
+
+    let reopened = JournalStore::open(&path).expect("reopen");
+    assert_eq!(reopened.get("hits").expect("get"), 200);
+    assert_eq!(reopened.get("other").expect("get"), 7);
+    assert_eq!(reopened.get("gone").expect("get"), 0);
+    assert_eq!(reopened.discarded(), 0);
+}
+
+#[test]
+fn journal_drops_corrupt_records_and_compacts() {
+    let temp = tempdir().expect("tempdir");
+    let path = temp.path().join("counters.log");
+    let store = JournalStore::open(&path).expect("open");
+    for _ in 0..10 {
+        store.add("key_usage/a", 1).expect("add");
+    }
+    store.add("key_usage/b", 2).expect("add");
+    drop(store);
+
+    let mut bytes = std::fs::read(&path).expect("read");
+    bytes.extend_from_slice(
+        b"{\"key\":\"key_usage/a\",\"value\":99,\"check\":\"0000\"}\n{\"key\":\"tor",
+    );
+    std::fs::write(&path, &bytes).expect("corrupt");
+
+    let recovered = JournalStore::open(&path).expect("recover");
+    assert_eq!(recovered.discarded(), 2);
+    assert_eq!(recovered.get("key_usage/a").expect("get"), 10);
+    assert_eq!(
+        std::fs::read_to_string(&path)
+            .expect("read")
+            .lines()
+            .count(),
+        2
+    );
+    let usage = state::key_usage_all(&recovered).expect("usage");
+    assert_eq!(usage.get("a"), Some(&10));
+    assert_eq!(usage.get("b"), Some(&2));
+
+    recovered.add("key_usage/b", 1).expect("add after recovery");
+    drop(recovered);
+    let reopened = JournalStore::open(&path).expect("reopen");
+    assert_eq!(reopened.discarded(), 0);
+    assert_eq!(reopened.get("key_usage/b").expect("get"), 3);
+}
+
+#[tokio::test]
+async fn policy_quota_limits_encryptions_and_tracks_key_usage() {
+    let temp = tempdir().expect("tempdir");
+    let policy = json!({
+        "default_allow": true,
+        "quotas": [{ "subject": "system", "action": "encrypt", "limit": 2, "window_secs": 3600 }]
+    });
+    fs::write(
+        temp.path().join("policy.json"),
+        serde_json::to_vec(&policy).expect("policy json"),
+    )
+    .await
+    .expect("write policy");
+
+    let engine = new_default();
+    engine.init(config(temp.path())).await.expect("init");
+    let envelope = engine.encrypt(request(b"one")).await.expect("first");
+    engine.encrypt(request(b"two")).await.expect("second");
+    let err = engine
+        .encrypt(request(b"three"))
+        .await
+        .expect_err("quota exhausted");
+    assert!(matches!(err, DGError::PolicyDenied(_)), "{err}");
+    engine
+        .decrypt(envelope)
+        .await
+        .expect("decrypt is not limited");
+    engine.shutdown().await.expect("shutdown");
+
+    let store = state::open(temp.path(), StateBackend::Journal).expect("state");
+    assert_eq!(
+        state::key_usage(store.as_ref(), "master").expect("usage"),
+        3
+    );
+
+    let engine = new_default();
+    engine.init(config(temp.path())).await.expect("reinit");
+    let err = engine
+        .encrypt(request(b"four"))
+        .await
+        .expect_err("quota persists across restarts");
+    assert!(matches!(err, DGError::PolicyDenied(_)), "{err}");
+    engine.shutdown().await.expect("shutdown");
+}
+
+#[tokio::test]
+async fn repeated_decrypt_failures_trigger_lockout() {
+    let foreign_dir = tempdir().expect("tempdir");
+    let foreign = new_default();
+    foreign
+        .init(config(foreign_dir.path()))
+        .await
+        .expect("init foreign");
+    let unreadable = foreign
+        .encrypt(request(b"elsewhere"))
+        .await
+        .expect("encrypt");
+    foreign.shutdown().await.expect("shutdown");
+
+    let temp = tempdir().expect("tempdir");
+    let policy = json!({
+        "default_allow": true,
+        "lockout": { "max_failures": 2, "cooldown_secs": 600 }
+    });
+    fs::write(
+        temp.path().join("policy.json"),
+        serde_json::to_vec(&policy).expect("policy json"),
+    )
+    .await
+    .expect("write policy");
+
+    let engine = new_default();
+    engine.init(config(temp.path())).await.expect("init");
+    let readable = engine.encrypt(request(b"local")).await.expect("encrypt");
+    for _ in 0..2 {
+        let err = engine
+            .decrypt(unreadable.clone())
+            .await
+            .expect_err("foreign envelope");
+        assert!(matches!(err, DGError::Crypto(_)), "{err}");
+    }
+    let err = engine
+        .decrypt(readable)
+        .await
+        .expect_err("locked out after two failures");
+    assert!(matches!(err, DGError::PolicyDenied(_)), "{err}");
+    engine.shutdown().await.expect("shutdown");
+}
+
+#[test]
+fn sled_backend_follows_the_build_feature() {
+    let temp = tempdir().expect("tempdir");
+    let result = state::open(temp.path(), StateBackend::Sled);
+    if cfg!(feature = "sled-state") {
+        let store = result.expect("sled store");
+        assert_eq!(store.add("hits", 2).expect("add"), 2);
+        assert_eq!(store.scan("hi").expect("scan"), vec![("hits".into(), 2)]);
+    } else {
+        let Err(err) = result else {
+            panic!("sled backend should be unavailable");
+        };
+        assert!(matches!(err, DGError::Config(_)), "{err}");
+    }
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use base64::{engine::general_purpose, Engine as _};
+use common::config;
+use dg_core::api::{new_default, DGError, EncryptRequest};
+use dg_core::subkey::is_certified;
+use dg_core::{KeyAlgorithm, KeyMetadata, KeyPurpose, RekeyContext};
+use tempfile::tempdir;
+
+fn request(recipient: &str) -> EncryptRequest {
+    EncryptRequest {
+        plaintext: b"merger terms".to_vec(),
+        recipients: vec![recipient.into()],
+        ..Default::default()
+    }
+}
+
+fn root_public(root: &KeyMetadata) -> [u8; 32] {
+    general_purpose::STANDARD
+        .decode(&root.public_key)
+        .expect("root public key")
+        .try_into()
+        .expect("32 bytes")
+}
+
+fn wrap<'a>(meta: &'a serde_json::Value, kind: &str) -> &'a serde_json::Value {
+    meta["key_wraps"]
+        .as_array()
+        .expect("key wraps")
+        .iter()
+        .find(|wrap| wrap["kind"] == kind)
+        .expect("wrap of kind")
+}
+
+#[tokio::test]
+async fn the_root_certifies_one_subkey_per_purpose() {
+    let temp = tempdir().expect("tempdir");
+    let engine = new_default();
+    engine.init(config(temp.path())).await.expect("init");
+    let hierarchy = engine
+        .generate_key_hierarchy("alice")
+        .await
+        .expect("hierarchy");
+
+    assert_eq!(hierarchy.root.purpose, Some(KeyPurpose::Root));
+    assert_eq!(hierarchy.root.algorithm, KeyAlgorithm::Ed25519);
+    let public = root_public(&hierarchy.root);
+    for (purpose, algorithm) in [
+        (KeyPurpose::Encryption, KeyAlgorithm::X25519),
+        (KeyPurpose::Signing, KeyAlgorithm::Ed25519),
+        (KeyPurpose::Escrow, KeyAlgorithm::X25519),
+    ] {
+        let subkey = hierarchy.subkey(purpose).expect("subkey");
+        assert_eq!(subkey.algorithm, algorithm);
+        assert_eq!(subkey.label, format!("alice/{purpose}"));
+        assert!(is_certified(&public, subkey));
+        let mut forged = subkey.clone();
+        forged.certificate.as_mut().expect("certificate").issued_at += 1;
+        assert!(!is_certified(&public, &forged));
+    }
+    assert_eq!(engine.list_identities().await.expect("list").len(), 4);
+    assert!(matches!(
+        engine.generate_key_hierarchy("alice").await,
+        Err(DGError::Config(_))
+    ));
+
+    let signature = engine
+        .sign_detached("alice", b"minutes")
+        .await
+        .expect("sign");
+    let signing = hierarchy.subkey(KeyPurpose::Signing).expect("signing");
+    assert!(engine
+        .verify_detached(&signing.id, b"minutes", &signature)
+        .await
+        .expect("verify"));
+    assert!(engine
+        .verify_detached("alice", b"minutes", &signature)
+        .await
+        .expect("verify by root"));
+    engine.shutdown().await.expect("shutdown");
+}
+
+#[tokio::test]
+async fn envelopes_record_the_subkeys_they_are_wrapped_to() {
+    let temp = tempdir().expect("tempdir");
+    let engine = new_default();
+    engine.init(config(temp.path())).await.expect("init");
+    let hierarchy = engine
+        .generate_key_hierarchy("alice")
+        .await
+        .expect("hierarchy");
+    let encryption = hierarchy
+        .subkey(KeyPurpose::Encryption)
+        .expect("encryption");
+    let escrow = hierarchy.subkey(KeyPurpose::Escrow).expect("escrow");
+
+    let envelope = engine.encrypt(request("alice")).await.expect("encrypt");
+    let recipient = wrap(&envelope.meta, "recipient");
+    assert_eq!(recipient["key_id"], encryption.id.as_str());
+    assert_eq!(recipient["root"], hierarchy.root.id.as_str());
+    let escrowed = wrap(&envelope.meta, "escrow");
+    assert_eq!(escrowed["key_id"], escrow.id.as_str());
+    assert_eq!(escrowed["root"], hierarchy.root.id.as_str());
+    assert!(wrap(&envelope.meta, "master").get("root").is_none());
+
+    let rotation = engine.rotate_key("alice").await.expect("rotate");
+    assert_eq!(rotation.retired.id, encryption.id);
+    assert_eq!(rotation.replacement.label, "alice/encryption");
+    assert!(is_certified(
+        &root_public(&hierarchy.root),
+        &rotation.replacement
+    ));
+    let signing = engine.rotate_key("alice/signing").await.expect("rotate");
+    assert_eq!(signing.replacement.purpose, Some(KeyPurpose::Signing));
+
+    let rekeyed = engine
+        .rekey(envelope, RekeyContext::default())
+        .await
+        .expect("rekey");
+    assert_eq!(
+        wrap(&rekeyed.meta, "recipient")["key_id"],
+        rotation.replacement.id.as_str()
+    );
+    assert_eq!(wrap(&rekeyed.meta, "escrow")["key_id"], escrow.id.as_str());
+    assert_eq!(
+        *engine.decrypt(rekeyed).await.expect("decrypt"),
+        b"merger terms"
+    );
+
+    engine.revoke_key("alice").await.expect("revoke root");
+    let identities = engine.list_identities().await.expect("list");
+    assert!(identities
+        .iter()
+        .filter(|meta| meta.is_root() || meta.certificate.is_some())
+        .all(|meta| meta.revoked_at.is_some()));
+    assert!(engine.encrypt(request("alice")).await.is_err());
+    assert!(engine.sign_detached("alice", b"minutes").await.is_err());
+    engine.shutdown().await.expect("shutdown");
+}
@@ -0,0 +1,46 @@
+import asyncio
+import os
+import socket
+import sys
+
+import pytest
+
+from dg_core.ipc.activation import SD_LISTEN_FDS_START, systemd_listen_fds
+from dg_core.ipc.transport import InheritedSocketTransport
+
+
+def test_systemd_listen_fds_requires_matching_pid() -> None:
+    env = {"LISTEN_PID": str(os.getpid()), "LISTEN_FDS": "2", "LISTEN_FDNAMES": "dg:extra"}
+    assert systemd_listen_fds(env) == [SD_LISTEN_FDS_START, SD_LISTEN_FDS_START + 1]
+    assert env == {}
+
+    assert systemd_listen_fds({"LISTEN_PID": "1", "LISTEN_FDS": "1"}) == []
+    assert systemd_listen_fds({"LISTEN_PID": str(os.getpid()), "LISTEN_FDS": "0"}) == []
+    assert systemd_listen_fds({}) == []
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="unix sockets only")
+def test_inherited_unix_listener_is_served_without_rebinding(tmp_path) -> None:
+    path = tmp_path / "dg-core.sock"
+    listener = socket.socket(socket.AF_UNIX, socket.SOCK_STREAM)
+    listener.bind(str(path))
+    listener.listen()
+    listener.setblocking(False)
+
+    async def scenario() -> bytes:
+        transport = InheritedSocketTransport(listener)
+
+        async def echo(connection) -> None:
+            await connection.send(await connection.receive())
+
+        await transport.start(echo)
+        reader, writer = await asyncio.open_unix_connection(str(path))
+        writer.write(b"ping\n")
+        await writer.drain()
+        line = await reader.readline()
+        writer.close()
+        await transport.close()
+        return line
+
+    assert asyncio.run(scenario()) == b"ping\n"
+    assert path.exists()
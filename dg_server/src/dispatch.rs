@@ -0,0 +1,432 @@
+use std::path::PathBuf;
+
+use dg_bridge::engine::*;
+use dg_core::api::{DGConfig, DGError, DataGuardian, EncryptRequest, Envelope};
+use dg_core::index::IndexEntry;
+use dg_core::path_encoding;
+use dg_core::rpc_error::{INVALID_PARAMS, METHOD_NOT_FOUND};
+use dg_core::{
+    AuditFilter, GrantAction, GrantRequest, Identity, KeyShare, OfflineRequest, OfflineResponse,
+    RekeyContext, RpcError, SignedGrant,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use zeroize::Zeroizing;
+
+#[derive(Deserialize)]
+struct InitParams {
+    config: DGConfig,
+}
+
+#[derive(Deserialize)]
+struct EnvelopeParams {
+    envelope: WireEnvelope,
+}
+
+#[derive(Deserialize)]
+struct PolicyParams {
+    subject: String,
+    #[serde(default)]
+    subjects: Option<Vec<String>>,
+    action: String,
+    resource: String,
+}
+
+impl PolicyParams {
+    fn identity(&self) -> Identity {
+        self.subjects
+            .as_deref()
+            .and_then(Identity::from_subjects)
+            .unwrap_or_else(|| {
+                Identity::new(self.subject.strip_prefix("user:").unwrap_or(&self.subject))
+            })
+    }
+}
+
+#[derive(Deserialize)]
+struct PrivateKeyParams {
+    pem: String,
+    label: String,
+}
+
+#[derive(Deserialize)]
+struct PublicKeyParams {
+    pem: String,
+    contact: String,
+}
+
+#[derive(Deserialize)]
+struct IdParams {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct LabelParams {
+    label: String,
+}
+
+#[derive(Deserialize)]
+struct RekeyParams {
+    envelope: WireEnvelope,
+    context: RekeyContext,
+}
+
+#[derive(Deserialize)]
+struct SignParams {
+    signer: String,
+    data: String,
+}
+
+#[derive(Deserialize)]
+struct VerifyParams {
+    signer: String,
+    data: String,
+    signature: String,
+}
+
+#[derive(Deserialize)]
+struct IntegrityParams {
+    #[serde(default)]
+    repair: bool,
+}
+
+#[derive(Deserialize)]
+struct CombineParams {
+    envelope: WireEnvelope,
+    shares: Vec<KeyShare>,
+}
+
+#[derive(Deserialize)]
+struct IssueGrantParams {
+    envelope: WireEnvelope,
+    request: GrantRequest,
+}
+
+#[derive(Deserialize)]
+struct GrantDecryptParams {
+    envelope: WireEnvelope,
+    grant: SignedGrant,
+    action: GrantAction,
+}
+
+#[derive(Deserialize)]
+struct AnswerOfflineParams {
+    request: OfflineRequest,
+}
+
+#[derive(Deserialize)]
+struct CompleteOfflineParams {
+    envelope: WireEnvelope,
+    response: OfflineResponse,
+}
+
+#[derive(Deserialize)]
+struct RangeParams {
+    envelope: WireEnvelope,
+    offset: u64,
+    len: u64,
+}
+
+#[derive(Deserialize)]
+struct AppendParams {
+    envelope: WireEnvelope,
+    data: String,
+}
+
+#[derive(Deserialize)]
+struct IndexRecordParams {
+    entry: IndexEntry,
+}
+
+#[derive(Deserialize)]
+struct IndexRemoveParams {
+    #[serde(with = "dg_core::path_encoding")]
+    path: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct IndexReconcileParams {
+    roots: Vec<String>,
+    #[serde(default)]
+    observed: Vec<IndexEntry>,
+}
+
+#[derive(Deserialize)]
+struct AuditParams {
+    #[serde(default)]
+    filter: AuditFilter,
+}
+
+#[derive(Deserialize)]
+struct UnlockParams {
+    passphrase: String,
+}
+
+// Runs one `engine.*` method against `engine`, decoding params and encoding the result in the
+// shapes `dg_controller`'s remote backend expects.
+pub async fn engine_call(
+    engine: &(dyn DataGuardian + Send + Sync),
+    method: &str,
+    params: Value,
+) -> Result<Value, RpcError> {
+    let result = match method {
+        ENGINE_INIT => {
+            let InitParams { config } = decode(method, params)?;
+            engine.init(config).await.map(|()| json!({ "ok": true }))
+        }
+        ENGINE_ENCRYPT => {
+            let request: WireEncryptRequest = decode(method, params)?;
+            let request = EncryptRequest::try_from(request).map_err(invalid(method))?;
+            engine
+                .encrypt(request)
+                .await
+                .map(|envelope| json!(WireEnvelope::from(&envelope)))
+        }
+        ENGINE_DECRYPT => {
+            let envelope = envelope(method, params)?;
+            engine.decrypt(envelope).await.map(plaintext)
+        }
+        ENGINE_CHECK_POLICY => {
+            let params: PolicyParams = decode(method, params)?;
+            let allowed = match params.subjects {
+                Some(_) => {
+                    engine
+                        .check_identity(&params.identity(), &params.action, &params.resource)
+                        .await
+                }
+                None => {
+                    engine
+                        .check_policy(&params.subject, &params.action, &params.resource)
+                        .await
+                }
+            };
+            allowed.map(|allowed| json!(AllowedResult { allowed }))
+        }
+        ENGINE_EXPLAIN => {
+            let params: PolicyParams = decode(method, params)?;
+            engine
+                .explain_decision(&params.identity(), &params.action, &params.resource)
+                .await
+                .map(to_value)
+        }
+        ENGINE_IMPORT_PRIVATE_KEY => {
+            let PrivateKeyParams { pem, label } = decode(method, params)?;
+            engine.import_private_key(&pem, &label).await.map(to_value)
+        }
+        ENGINE_IMPORT_PUBLIC_KEY => {
+            let PublicKeyParams { pem, contact } = decode(method, params)?;
+            engine.import_public_key(&pem, &contact).await.map(to_value)
+        }
+        ENGINE_REVOKE_KEY => {
+            let IdParams { id } = decode(method, params)?;
+            engine.revoke_key(&id).await.map(to_value)
+        }
+        ENGINE_GENERATE_IDENTITY => {
+            let LabelParams { label } = decode(method, params)?;
+            engine.generate_identity(&label).await.map(to_value)
+        }
+        ENGINE_GENERATE_KEY_HIERARCHY => {
+            let LabelParams { label } = decode(method, params)?;
+            engine.generate_key_hierarchy(&label).await.map(to_value)
+        }
+        ENGINE_LIST_IDENTITIES => engine.list_identities().await.map(to_value),
+        ENGINE_KEY_EXPIRY => engine.key_expiry().await.map(to_value),
+        ENGINE_ROTATE_KEY => {
+            let IdParams { id } = decode(method, params)?;
+            engine.rotate_key(&id).await.map(to_value)
+        }
+        ENGINE_REKEY => {
+            let RekeyParams { envelope, context } = decode(method, params)?;
+            let envelope = Envelope::try_from(envelope).map_err(invalid(method))?;
+            engine
+                .rekey(envelope, context)
+                .await
+                .map(|envelope| json!(WireEnvelope::from(&envelope)))
+        }
+        ENGINE_SIGN => {
+            let SignParams { signer, data } = decode(method, params)?;
+            let data = decode_bytes(&data).map_err(invalid(method))?;
+            engine.sign_detached(&signer, &data).await.map(|signature| {
+                json!(SignatureResult {
+                    signature: encode_bytes(&signature),
+                })
+            })
+        }
+        ENGINE_VERIFY_SIGNATURE => {
+            let VerifyParams {
+                signer,
+                data,
+                signature,
+            } = decode(method, params)?;
+            let data = decode_bytes(&data).map_err(invalid(method))?;
+            let signature = decode_bytes(&signature).map_err(invalid(method))?;
+            engine
+                .verify_detached(&signer, &data, &signature)
+                .await
+                .map(|valid| json!(ValidResult { valid }))
+        }
+        ENGINE_SELF_TEST => engine.self_test().await.map(to_value),
+        ENGINE_WARM_UP => engine.warm_up().await.map(to_value),
+        ENGINE_INTEGRITY => {
+            let IntegrityParams { repair } = decode(method, params)?;
+            engine.integrity(repair).await.map(to_value)
+        }
+        ENGINE_COLLECT_SHARE => {
+            let envelope = envelope(method, params)?;
+            engine
+                .collect_share(&envelope)
+                .await
+                .map(|shares| json!(SharesResult { shares }))
+        }
+        ENGINE_COMBINE_SHARES => {
+            let CombineParams { envelope, shares } = decode(method, params)?;
+            let envelope = Envelope::try_from(envelope).map_err(invalid(method))?;
+            engine.combine_shares(envelope, shares).await.map(plaintext)
+        }
+        ENGINE_ISSUE_GRANT => {
+            let IssueGrantParams { envelope, request } = decode(method, params)?;
+            let envelope = Envelope::try_from(envelope).map_err(invalid(method))?;
+            engine.issue_grant(&envelope, request).await.map(to_value)
+        }
+        ENGINE_DECRYPT_WITH_GRANT => {
+            let GrantDecryptParams {
+                envelope,
+                grant,
+                action,
+            } = decode(method, params)?;
+            let envelope = Envelope::try_from(envelope).map_err(invalid(method))?;
+            engine
+                .decrypt_with_grant(envelope, grant, action)
+                .await
+                .map(plaintext)
+        }
+        ENGINE_EXPORT_OFFLINE_REQUEST => {
+            let envelope = envelope(method, params)?;
+            engine.export_offline_request(&envelope).await.map(to_value)
+        }
+        ENGINE_ANSWER_OFFLINE_REQUEST => {
+            let AnswerOfflineParams { request } = decode(method, params)?;
+            engine.answer_offline_request(request).await.map(to_value)
+        }
+        ENGINE_COMPLETE_OFFLINE_REQUEST => {
+            let CompleteOfflineParams { envelope, response } = decode(method, params)?;
+            let envelope = Envelope::try_from(envelope).map_err(invalid(method))?;
+            engine
+                .complete_offline_request(envelope, response)
+                .await
+                .map(plaintext)
+        }
+        ENGINE_PREVIEW => {
+            let envelope = envelope(method, params)?;
+            engine.preview(&envelope).await.map(|preview| {
+                json!(PreviewResult {
+                    preview: preview.as_ref().map(WirePreview::from),
+                })
+            })
+        }
+        ENGINE_VERIFY_RANGE => {
+            let RangeParams {
+                envelope,
+                offset,
+                len,
+            } = decode(method, params)?;
+            let envelope = Envelope::try_from(envelope).map_err(invalid(method))?;
+            engine
+                .verify_range(&envelope, offset, len)
+                .await
+                .map(to_value)
+        }
+        ENGINE_DECRYPT_RANGE => {
+            let RangeParams {
+                envelope,
+                offset,
+                len,
+            } = decode(method, params)?;
+            let envelope = Envelope::try_from(envelope).map_err(invalid(method))?;
+            engine
+                .decrypt_range(&envelope, offset, len)
+                .await
+                .map(plaintext)
+        }
+        ENGINE_APPEND => {
+            let AppendParams { envelope, data } = decode(method, params)?;
+            let envelope = Envelope::try_from(envelope).map_err(invalid(method))?;
+            let data = decode_bytes(&data).map_err(invalid(method))?;
+            engine
+                .append(&envelope, data)
+                .await
+                .map(|appended| json!(WireJournalAppend::from(&appended)))
+        }
+        ENGINE_INDEX_ENTRIES => engine.index_entries().await.map(to_value),
+        ENGINE_INDEX_RECORD => {
+            let IndexRecordParams { entry } = decode(method, params)?;
+            engine
+                .index_record(entry)
+                .await
+                .map(|()| json!({ "ok": true }))
+        }
+        ENGINE_INDEX_REMOVE => {
+            let IndexRemoveParams { path } = decode(method, params)?;
+            engine
+                .index_remove(&path)
+                .await
+                .map(|()| json!({ "ok": true }))
+        }
+        ENGINE_INDEX_RECONCILE => {
+            let IndexReconcileParams { roots, observed } = decode(method, params)?;
+            let roots = roots
+                .iter()
+                .map(|root| path_encoding::decode(root))
+                .collect();
+            engine.index_reconcile(roots, observed).await.map(to_value)
+        }
+        ENGINE_RELOAD_POLICY => engine.reload_policy().await.map(to_value),
+        ENGINE_POLICY_CACHE => engine.policy_cache_stats().await.map(to_value),
+        ENGINE_QUERY_AUDIT => {
+            let AuditParams { filter } = decode(method, params)?;
+            engine.query_audit(filter).await.map(to_value)
+        }
+        ENGINE_LOCK => engine.lock().await.map(|()| json!({ "ok": true })),
+        ENGINE_UNLOCK => {
+            let UnlockParams { passphrase } = decode(method, params)?;
+            engine
+                .unlock(&passphrase)
+                .await
+                .map(|()| json!({ "ok": true }))
+        }
+        ENGINE_SHUTDOWN => engine.shutdown().await.map(|()| json!({ "ok": true })),
+        _ => {
+            return Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method {method}"),
+            ))
+        }
+    };
+    result.map_err(RpcError::from)
+}
+
+fn decode<T: DeserializeOwned>(method: &str, params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params)
+        .map_err(|err| RpcError::new(INVALID_PARAMS, format!("invalid {method} params: {err}")))
+}
+
+fn envelope(method: &str, params: Value) -> Result<Envelope, RpcError> {
+    let EnvelopeParams { envelope } = decode(method, params)?;
+    Envelope::try_from(envelope).map_err(invalid(method))
+}
+
+fn invalid(method: &str) -> impl Fn(DGError) -> RpcError + '_ {
+    move |err| RpcError::new(INVALID_PARAMS, format!("invalid {method} params: {err}"))
+}
+
+fn plaintext(bytes: Zeroizing<Vec<u8>>) -> Value {
+    json!(PlaintextResult {
+        plaintext: encode_bytes(&bytes),
+    })
+}
+
+fn to_value<T: Serialize>(value: T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
@@ -0,0 +1,11 @@
+pub mod dispatch;
+pub mod logs;
+pub mod methods;
+pub mod server;
+
+pub use logs::{LogFeed, LogLayer};
+pub use methods::{
+    MethodSpec, AUTHENTICATE_METHOD, METHODS, PING_METHOD, STATUS_METHOD, SUBSCRIBE_METHOD,
+    TAIL_LOGS_METHOD,
+};
+pub use server::{Listener, Server, ServerConfig};
@@ -0,0 +1,109 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Map, Value};
+use tokio::sync::broadcast;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+// Log entries buffered per subscriber; a subscriber that falls further behind loses the oldest.
+pub const DEFAULT_LOG_BUFFER: usize = 256;
+
+// Fans `info` and more severe tracing events out to `core.tail_logs` subscribers.
+#[derive(Debug, Clone)]
+pub struct LogFeed {
+    sender: broadcast::Sender<Value>,
+}
+
+impl Default for LogFeed {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOG_BUFFER)
+    }
+}
+
+impl LogFeed {
+    pub fn new(buffer: usize) -> Self {
+        let (sender, _) = broadcast::channel(buffer.max(1));
+        Self { sender }
+    }
+
+    // The tracing layer that feeds this log stream. Install it next to any other subscriber layers.
+    pub fn layer(&self) -> LogLayer {
+        LogLayer {
+            sender: self.sender.clone(),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Value> {
+        self.sender.subscribe()
+    }
+
+    pub fn subscribers(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+pub struct LogLayer {
+    sender: broadcast::Sender<Value>,
+}
+
+impl<S: Subscriber> Layer<S> for LogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if *metadata.level() > Level::INFO || self.sender.receiver_count() == 0 {
+            return;
+        }
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let mut entry = json!({
+            "ts": SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs_f64())
+                .unwrap_or_default(),
+            "level": metadata.level().as_str().to_ascii_lowercase(),
+            "msg": fields.message,
+            "component": metadata.target(),
+        });
+        if !fields.extra.is_empty() {
+            entry["fields"] = Value::Object(fields.extra);
+        }
+        let _ = self.sender.send(entry);
+    }
+}
+
+#[derive(Default)]
+struct Fields {
+    message: String,
+    extra: Map<String, Value>,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_owned();
+        } else {
+            self.extra
+                .insert(field.name().to_owned(), Value::from(value));
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.extra
+            .insert(field.name().to_owned(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.extra
+            .insert(field.name().to_owned(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.extra
+            .insert(field.name().to_owned(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record_str(field, &format!("{value:?}"));
+    }
+}
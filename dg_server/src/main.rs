@@ -0,0 +1,161 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use dg_bridge::{Endpoint, SessionAuthority, TransportKind, TransportToken, TCP_TOKEN_ENV};
+use dg_core::api::{new_default, DGConfig};
+use dg_server::server::{DEFAULT_MAX_FRAME_BYTES, DEFAULT_MAX_IN_FLIGHT};
+use dg_server::{Listener, LogFeed, Server, ServerConfig};
+use tokio::sync::watch;
+use tracing::warn;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer};
+
+#[derive(Debug, Parser)]
+#[command(author, version, about = "Native Data Guardian core daemon", long_about = None)]
+struct Cli {
+    /// Accepted for compatibility with the desktop process manager, which runs `serve`
+    #[arg(value_parser = ["serve"])]
+    command: Option<String>,
+
+    /// Stay attached to the terminal. The daemon never forks, so this is a no-op
+    #[arg(long)]
+    foreground: bool,
+
+    /// Unix socket path. On Windows the value is taken as a named pipe
+    #[arg(long, value_name = "PATH")]
+    socket: Option<String>,
+
+    /// Named pipe, with or without the `\\.\pipe\` prefix
+    #[arg(long, value_name = "NAME")]
+    pipe: Option<String>,
+
+    /// Loopback TCP address such as 127.0.0.1:7878. Set DG_CORE_TCP_TOKEN to require a shared
+    /// token in `core.authenticate` before any other method
+    #[arg(long, value_name = "ADDR")]
+    tcp: Option<String>,
+
+    /// Require `core.authenticate` with tokens signed by this key, creating it when missing
+    #[arg(long, value_name = "PATH")]
+    session_key_file: Option<PathBuf>,
+
+    /// Accepted for compatibility with the Python daemon. The native engine makes no network calls
+    #[arg(long)]
+    allow_network: bool,
+
+    #[arg(long, default_value_t = DEFAULT_MAX_FRAME_BYTES)]
+    max_frame_bytes: usize,
+
+    #[arg(long, default_value_t = DEFAULT_MAX_IN_FLIGHT)]
+    max_in_flight: usize,
+
+    /// Initialise the engine at startup instead of waiting for `engine.init`
+    #[arg(long, value_name = "PATH")]
+    data_dir: Option<PathBuf>,
+
+    /// Profile used with --data-dir
+    #[arg(long, default_value = "dev")]
+    profile: String,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let logs = LogFeed::default();
+    tracing_subscriber::registry()
+        .with(
+            fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_filter(EnvFilter::from_default_env()),
+        )
+        .with(logs.layer())
+        .init();
+    match run(Cli::parse(), logs).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli, logs: LogFeed) -> Result<()> {
+    let endpoints = endpoints(&cli)?;
+    let mut config = ServerConfig::default()
+        .with_log_feed(logs)
+        .with_max_frame_bytes(cli.max_frame_bytes)
+        .with_max_in_flight(cli.max_in_flight);
+    if let Some(path) = &cli.session_key_file {
+        config = config.with_session_authority(SessionAuthority::load_or_create(path).await?);
+    }
+    // The token comes from the environment so it does not show up in process listings.
+    if let Some(token) = TransportToken::from_env()? {
+        config = config.with_transport_token(token);
+    } else if cli.tcp.is_some() {
+        warn!("{TCP_TOKEN_ENV} is not set; any local process can use the TCP endpoint");
+    }
+
+    let engine = new_default();
+    if let Some(data_dir) = cli.data_dir {
+        engine
+            .init(DGConfig {
+                profile: cli.profile,
+                data_dir,
+                ..Default::default()
+            })
+            .await?;
+    }
+    let server = Server::new(engine.clone(), config);
+
+    let mut listeners = Vec::with_capacity(endpoints.len());
+    for endpoint in &endpoints {
+        listeners.push(Listener::bind(endpoint).await?);
+    }
+    let (stop, stopped) = watch::channel(false);
+    let mut tasks = tokio::task::JoinSet::new();
+    for listener in listeners {
+        let server = server.clone();
+        let mut stopped = stopped.clone();
+        tasks.spawn(async move {
+            server
+                .serve(listener, async move {
+                    let _ = stopped.changed().await;
+                })
+                .await
+        });
+    }
+
+    let result = tokio::select! {
+        _ = tokio::signal::ctrl_c() => Ok(()),
+        Some(joined) = tasks.join_next() => joined.map_err(|err| anyhow!(err)).and_then(|served| served),
+    };
+    let _ = stop.send(true);
+    while tasks.join_next().await.is_some() {}
+    let _ = engine.shutdown().await;
+    result
+}
+
+fn endpoints(cli: &Cli) -> Result<Vec<Endpoint>> {
+    let mut endpoints = Vec::new();
+    if let Some(socket) = &cli.socket {
+        let kind = if cfg!(target_os = "windows") {
+            TransportKind::NamedPipe
+        } else {
+            TransportKind::Unix
+        };
+        endpoints.push(Endpoint::from_user_input(kind, socket)?);
+    }
+    if let Some(pipe) = &cli.pipe {
+        endpoints.push(Endpoint::from_user_input(TransportKind::NamedPipe, pipe)?);
+    }
+    if let Some(tcp) = &cli.tcp {
+        endpoints.push(Endpoint::from_user_input(TransportKind::Tcp, tcp)?);
+    }
+    if endpoints.is_empty() {
+        return Err(anyhow!(
+            "pass --socket, --pipe or --tcp to choose where to listen"
+        ));
+    }
+    Ok(endpoints)
+}
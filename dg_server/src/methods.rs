@@ -0,0 +1,272 @@
+use dg_bridge::engine::*;
+use dg_bridge::{Capability, MethodDescriptor, DESCRIBE_METHOD};
+pub use dg_bridge::{HEALTH_METHOD, SUBSCRIBE_METHOD, TAIL_LOGS_METHOD};
+use serde_json::{json, Map, Value};
+
+pub const PING_METHOD: &str = "core.ping";
+pub const AUTHENTICATE_METHOD: &str = "core.authenticate";
+pub const STATUS_METHOD: &str = "core.get_status";
+pub const LOGS_TOPIC: &str = "logs";
+pub const PROGRESS_TOPIC: &str = "progress";
+
+const ANY_ENGINE: &[Capability] = &[Capability::Encrypt, Capability::Decrypt];
+const ENCRYPT: &[Capability] = &[Capability::Encrypt];
+const DECRYPT: &[Capability] = &[Capability::Decrypt];
+const POLICY: &[Capability] = &[Capability::Policy];
+const STATUS: &[Capability] = &[Capability::Status];
+const INSPECT: &[Capability] = &[Capability::Encrypt, Capability::Decrypt, Capability::Status];
+const AUDIT: &[Capability] = &[Capability::ReadLogs, Capability::Status];
+const LOGS: &[Capability] = &[Capability::ReadLogs];
+const PROGRESS: &[Capability] = &[Capability::Progress];
+const TOPICS: &[Capability] = &[Capability::ReadLogs, Capability::Progress];
+const KEYS: &[Capability] = &[Capability::Keys];
+const ADMIN: &[Capability] = &[Capability::Admin];
+const INTEGRITY: &[Capability] = &[
+    Capability::Encrypt,
+    Capability::Decrypt,
+    Capability::Status,
+    Capability::Admin,
+];
+
+// One registered method: its params as (name, JSON type, required) and the session scopes, any of
+// which authorizes it. Methods without scopes never need a session.
+pub struct MethodSpec {
+    pub name: &'static str,
+    pub params: &'static [(&'static str, &'static str, bool)],
+    pub scopes: &'static [Capability],
+}
+
+impl MethodSpec {
+    pub fn allows(&self, scopes: &[Capability]) -> bool {
+        self.scopes.is_empty() || self.scopes.iter().any(|scope| scopes.contains(scope))
+    }
+
+    pub fn descriptor(&self) -> MethodDescriptor {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for (name, kind, needed) in self.params {
+            properties.insert((*name).to_owned(), json!({ "type": kind }));
+            if *needed {
+                required.push(Value::from(*name));
+            }
+        }
+        let mut params = json!({ "type": "object", "properties": properties });
+        if !required.is_empty() {
+            params["required"] = Value::Array(required);
+        }
+        MethodDescriptor {
+            name: self.name.to_owned(),
+            params,
+            capabilities: self
+                .scopes
+                .iter()
+                .map(|scope| scope.as_str().to_owned())
+                .collect(),
+        }
+    }
+}
+
+const fn spec(
+    name: &'static str,
+    params: &'static [(&'static str, &'static str, bool)],
+    scopes: &'static [Capability],
+) -> MethodSpec {
+    MethodSpec {
+        name,
+        params,
+        scopes,
+    }
+}
+
+const ENVELOPE: (&str, &str, bool) = ("envelope", "object", true);
+
+pub const METHODS: &[MethodSpec] = &[
+    spec(PING_METHOD, &[], &[]),
+    spec(DESCRIBE_METHOD, &[], &[]),
+    spec(
+        AUTHENTICATE_METHOD,
+        &[
+            ("token", "string", false),
+            ("transport_token", "string", false),
+            ("device", "string", false),
+        ],
+        &[],
+    ),
+    spec(STATUS_METHOD, &[], STATUS),
+    spec(HEALTH_METHOD, &[], &[]),
+    spec(TAIL_LOGS_METHOD, &[], LOGS),
+    spec(SUBSCRIBE_METHOD, &[("topic", "string", false)], TOPICS),
+    spec(ENGINE_INIT, &[("config", "object", true)], ADMIN),
+    spec(
+        ENGINE_ENCRYPT,
+        &[
+            ("plaintext", "string", true),
+            ("labels", "array", false),
+            ("recipients", "array", false),
+            ("threshold", "integer", false),
+            ("preview", "object", false),
+            ("metadata", "object", false),
+            ("chunking", "object", false),
+            ("previous", "object", false),
+            ("journal", "boolean", false),
+        ],
+        ENCRYPT,
+    ),
+    spec(ENGINE_DECRYPT, &[ENVELOPE], DECRYPT),
+    spec(
+        ENGINE_CHECK_POLICY,
+        &[
+            ("subject", "string", true),
+            ("subjects", "array", false),
+            ("action", "string", true),
+            ("resource", "string", true),
+        ],
+        POLICY,
+    ),
+    spec(
+        ENGINE_EXPLAIN,
+        &[
+            ("subject", "string", true),
+            ("subjects", "array", false),
+            ("action", "string", true),
+            ("resource", "string", true),
+        ],
+        POLICY,
+    ),
+    spec(
+        ENGINE_IMPORT_PRIVATE_KEY,
+        &[("pem", "string", true), ("label", "string", true)],
+        KEYS,
+    ),
+    spec(
+        ENGINE_IMPORT_PUBLIC_KEY,
+        &[("pem", "string", true), ("contact", "string", true)],
+        KEYS,
+    ),
+    spec(ENGINE_REVOKE_KEY, &[("id", "string", true)], KEYS),
+    spec(ENGINE_GENERATE_IDENTITY, &[("label", "string", true)], KEYS),
+    spec(
+        ENGINE_GENERATE_KEY_HIERARCHY,
+        &[("label", "string", true)],
+        KEYS,
+    ),
+    spec(ENGINE_LIST_IDENTITIES, &[], INSPECT),
+    spec(ENGINE_KEY_EXPIRY, &[], INSPECT),
+    spec(ENGINE_ROTATE_KEY, &[("id", "string", true)], KEYS),
+    spec(
+        ENGINE_REKEY,
+        &[ENVELOPE, ("context", "object", true)],
+        ENCRYPT,
+    ),
+    spec(
+        ENGINE_SIGN,
+        &[("signer", "string", true), ("data", "string", true)],
+        KEYS,
+    ),
+    spec(
+        ENGINE_VERIFY_SIGNATURE,
+        &[
+            ("signer", "string", true),
+            ("data", "string", true),
+            ("signature", "string", true),
+        ],
+        INSPECT,
+    ),
+    spec(ENGINE_SELF_TEST, &[], INSPECT),
+    spec(ENGINE_WARM_UP, &[], ANY_ENGINE),
+    spec(ENGINE_INTEGRITY, &[("repair", "boolean", false)], INTEGRITY),
+    spec(ENGINE_COLLECT_SHARE, &[ENVELOPE], DECRYPT),
+    spec(
+        ENGINE_COMBINE_SHARES,
+        &[ENVELOPE, ("shares", "array", true)],
+        DECRYPT,
+    ),
+    spec(
+        ENGINE_ISSUE_GRANT,
+        &[ENVELOPE, ("request", "object", true)],
+        DECRYPT,
+    ),
+    spec(
+        ENGINE_DECRYPT_WITH_GRANT,
+        &[
+            ENVELOPE,
+            ("grant", "object", true),
+            ("action", "string", true),
+        ],
+        DECRYPT,
+    ),
+    spec(ENGINE_EXPORT_OFFLINE_REQUEST, &[ENVELOPE], DECRYPT),
+    spec(
+        ENGINE_ANSWER_OFFLINE_REQUEST,
+        &[("request", "object", true)],
+        DECRYPT,
+    ),
+    spec(
+        ENGINE_COMPLETE_OFFLINE_REQUEST,
+        &[ENVELOPE, ("response", "object", true)],
+        DECRYPT,
+    ),
+    spec(ENGINE_PREVIEW, &[ENVELOPE], DECRYPT),
+    spec(
+        ENGINE_VERIFY_RANGE,
+        &[
+            ENVELOPE,
+            ("offset", "integer", true),
+            ("len", "integer", true),
+        ],
+        DECRYPT,
+    ),
+    spec(
+        ENGINE_APPEND,
+        &[ENVELOPE, ("data", "string", true)],
+        ENCRYPT,
+    ),
+    spec(
+        ENGINE_DECRYPT_RANGE,
+        &[
+            ENVELOPE,
+            ("offset", "integer", true),
+            ("len", "integer", true),
+        ],
+        DECRYPT,
+    ),
+    spec(ENGINE_INDEX_ENTRIES, &[], INSPECT),
+    spec(ENGINE_INDEX_RECORD, &[("entry", "object", true)], ADMIN),
+    spec(ENGINE_INDEX_REMOVE, &[("path", "string", true)], ADMIN),
+    spec(
+        ENGINE_INDEX_RECONCILE,
+        &[("roots", "array", true), ("observed", "array", false)],
+        ADMIN,
+    ),
+    spec(ENGINE_RELOAD_POLICY, &[], POLICY),
+    spec(ENGINE_POLICY_CACHE, &[], POLICY),
+    spec(ENGINE_QUERY_AUDIT, &[("filter", "object", false)], AUDIT),
+    spec(ENGINE_LOCK, &[], ADMIN),
+    spec(ENGINE_UNLOCK, &[("passphrase", "string", true)], ADMIN),
+    spec(ENGINE_SHUTDOWN, &[], ADMIN),
+];
+
+pub fn find(name: &str) -> Option<&'static MethodSpec> {
+    METHODS.iter().find(|spec| spec.name == name)
+}
+
+// Read-only integrity checks stay with `INSPECT`; `repair: true` rewrites the store and needs
+// `admin`. `core.describe` lists the union of both.
+pub fn param_scopes(method: &str, params: &Value) -> Option<&'static [Capability]> {
+    match method {
+        ENGINE_INTEGRITY if params.get("repair").and_then(Value::as_bool) == Some(true) => {
+            Some(ADMIN)
+        }
+        ENGINE_INTEGRITY => Some(INSPECT),
+        _ => None,
+    }
+}
+
+// The scope a `core.subscribe` topic needs; `core.describe` lists the union of both.
+pub fn topic_scopes(topic: &str) -> Option<&'static [Capability]> {
+    match topic {
+        LOGS_TOPIC => Some(LOGS),
+        PROGRESS_TOPIC => Some(PROGRESS),
+        _ => None,
+    }
+}
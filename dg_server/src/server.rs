@@ -0,0 +1,764 @@
+use std::future::Future;
+use std::io;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use anyhow::{anyhow, Context, Result};
+use dg_bridge::{
+    Capability, CoreHealth, Endpoint, SessionAuthority, SessionClaims, TransportToken,
+    DESCRIBE_METHOD, LOG_METHOD,
+};
+use dg_core::api::DataGuardian;
+use dg_core::identity::Identity;
+use dg_core::rpc_error::{
+    CAPABILITY_DENIED, INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND, PARSE_ERROR,
+    RATE_LIMITED, REQUEST_TOO_LARGE, UNAUTHENTICATED,
+};
+use dg_core::RpcError;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+};
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc, Semaphore};
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{debug, info, warn};
+
+#[cfg(target_os = "windows")]
+use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+#[cfg(target_family = "unix")]
+use tokio::net::UnixListener;
+
+use crate::dispatch;
+use crate::logs::LogFeed;
+use crate::methods::{
+    self, MethodSpec, AUTHENTICATE_METHOD, HEALTH_METHOD, LOGS_TOPIC, PING_METHOD, PROGRESS_TOPIC,
+    STATUS_METHOD, SUBSCRIBE_METHOD, TAIL_LOGS_METHOD,
+};
+
+pub const DEFAULT_MAX_FRAME_BYTES: usize = 512 * 1024;
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 8;
+pub const DEFAULT_REQUESTS_PER_SECOND: u32 = 50;
+pub const DEFAULT_BURST: u32 = 100;
+// Responses waiting for the connection's writer.
+const OUTBOX: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub max_frame_bytes: usize,
+    pub max_in_flight: usize,
+    pub requests_per_second: u32,
+    pub burst: u32,
+    sessions: Option<SessionAuthority>,
+    transport_token: Option<TransportToken>,
+    logs: LogFeed,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            max_frame_bytes: DEFAULT_MAX_FRAME_BYTES,
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            requests_per_second: DEFAULT_REQUESTS_PER_SECOND,
+            burst: DEFAULT_BURST,
+            sessions: None,
+            transport_token: None,
+            logs: LogFeed::default(),
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn with_max_frame_bytes(mut self, max_frame_bytes: usize) -> Self {
+        self.max_frame_bytes = max_frame_bytes;
+        self
+    }
+
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+
+    pub fn with_rate_limit(mut self, requests_per_second: u32, burst: u32) -> Self {
+        self.requests_per_second = requests_per_second.max(1);
+        self.burst = burst.max(1);
+        self
+    }
+
+    // Every method with scopes then needs a `core.authenticate`d session whose token this
+    // authority signed.
+    pub fn with_session_authority(mut self, authority: SessionAuthority) -> Self {
+        self.sessions = Some(authority);
+        self
+    }
+
+    // TCP connections must then present this token in `core.authenticate` before any other
+    // method is accepted.
+    pub fn with_transport_token(mut self, token: TransportToken) -> Self {
+        self.transport_token = Some(token);
+        self
+    }
+
+    // Log subscribers receive what this feed's layer sees, so install `logs.layer()` globally.
+    pub fn with_log_feed(mut self, logs: LogFeed) -> Self {
+        self.logs = logs;
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    requests: AtomicU64,
+    connections: AtomicUsize,
+    sessions: AtomicUsize,
+    progress_subscribers: AtomicUsize,
+}
+
+// Serves a `DataGuardian` engine over the newline-delimited JSON-RPC protocol of the core daemon.
+#[derive(Clone)]
+pub struct Server {
+    engine: Arc<dyn DataGuardian + Send + Sync>,
+    config: ServerConfig,
+    stats: Arc<Stats>,
+    started: Instant,
+}
+
+impl Server {
+    pub fn new(engine: Arc<dyn DataGuardian + Send + Sync>, config: ServerConfig) -> Self {
+        Self {
+            engine,
+            config,
+            stats: Arc::new(Stats::default()),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn engine(&self) -> &Arc<dyn DataGuardian + Send + Sync> {
+        &self.engine
+    }
+
+    // Accepts connections until `shutdown` resolves. Connections already open keep running.
+    pub async fn serve(
+        &self,
+        mut listener: Listener,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<()> {
+        tokio::pin!(shutdown);
+        info!(endpoint = %listener.endpoint(), "serving DG Core");
+        let tcp = matches!(listener.endpoint(), Endpoint::Tcp(_));
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => return Ok(()),
+                accepted = listener.accept() => {
+                    let stream = accepted.with_context(|| format!("failed to accept on {}", listener.endpoint()))?;
+                    let server = self.clone();
+                    tokio::spawn(async move { server.serve_stream(stream, tcp).await });
+                }
+            }
+        }
+    }
+
+    pub async fn serve_connection<S>(&self, stream: S)
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        self.serve_stream(stream, false).await
+    }
+
+    async fn serve_stream<S>(&self, stream: S, tcp: bool)
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        self.stats.connections.fetch_add(1, Ordering::Relaxed);
+        info!("client connected");
+        let (read, write) = tokio::io::split(stream);
+        let (outbox, responses) = mpsc::channel(OUTBOX);
+        let writer = tokio::spawn(write_responses(write, responses));
+        let mut connection = Connection {
+            server: self.clone(),
+            outbox,
+            session: None,
+            transport_verified: !tcp || self.config.transport_token.is_none(),
+            in_flight: Arc::new(Semaphore::new(self.config.max_in_flight)),
+            bucket: TokenBucket::new(self.config.requests_per_second, self.config.burst),
+            tasks: JoinSet::new(),
+            logs: None,
+            progress: false,
+        };
+        let violated = connection.run(tokio::io::BufReader::new(read)).await;
+        if violated {
+            connection.tasks.abort_all();
+        }
+        while connection.tasks.join_next().await.is_some() {}
+        if connection.session.is_some() {
+            self.stats.sessions.fetch_sub(1, Ordering::Relaxed);
+        }
+        if connection.progress {
+            self.stats
+                .progress_subscribers
+                .fetch_sub(1, Ordering::Relaxed);
+        }
+        if let Some(forwarder) = connection.logs.take() {
+            forwarder.abort();
+        }
+        drop(connection);
+        let _ = writer.await;
+        self.stats.connections.fetch_sub(1, Ordering::Relaxed);
+        info!("client disconnected");
+    }
+
+    // Answered without a session so readiness checks work before the shell authenticates.
+    async fn health(&self) -> Result<Value, RpcError> {
+        Ok(json!(CoreHealth {
+            version: env!("CARGO_PKG_VERSION").into(),
+            uptime: self.started.elapsed().as_secs_f64(),
+            profile: self.engine.loaded_profile().await?,
+        }))
+    }
+
+    fn status(&self) -> Value {
+        json!({
+            "ok": true,
+            "uptime": self.started.elapsed().as_secs_f64(),
+            "requests": self.stats.requests.load(Ordering::Relaxed),
+            "connections": self.stats.connections.load(Ordering::Relaxed),
+            "log_subscribers": self.config.logs.subscribers(),
+            "progress_subscribers": self.stats.progress_subscribers.load(Ordering::Relaxed),
+            "sessions": self.stats.sessions.load(Ordering::Relaxed),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct AuthenticateParams {
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default)]
+    transport_token: Option<String>,
+    #[serde(default)]
+    device: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SubscribeParams {
+    #[serde(default = "default_topic")]
+    topic: String,
+}
+
+fn default_topic() -> String {
+    PROGRESS_TOPIC.to_owned()
+}
+
+enum Frame {
+    Line(Vec<u8>),
+    TooLarge,
+    Closed,
+}
+
+struct Connection {
+    server: Server,
+    outbox: mpsc::Sender<Value>,
+    session: Option<SessionClaims>,
+    // False on a token-guarded TCP connection until `core.authenticate` presents the token.
+    transport_verified: bool,
+    in_flight: Arc<Semaphore>,
+    bucket: TokenBucket,
+    tasks: JoinSet<()>,
+    // Forwards the log feed to this connection once it subscribes.
+    logs: Option<JoinHandle<()>>,
+    progress: bool,
+}
+
+impl Connection {
+    // Returns whether the connection was closed for breaking a limit.
+    async fn run<R: AsyncBufRead + Unpin>(&mut self, mut reader: R) -> bool {
+        let limit = self.server.config.max_frame_bytes;
+        let mut buffer = Vec::new();
+        loop {
+            let line = match read_frame(&mut reader, limit, &mut buffer).await {
+                Ok(Frame::Line(line)) => line,
+                Ok(Frame::TooLarge) => {
+                    self.limit_exceeded(REQUEST_TOO_LARGE, "frame", limit, "request too large")
+                        .await;
+                    return true;
+                }
+                Ok(Frame::Closed) | Err(_) => return false,
+            };
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            if !self.bucket.take() {
+                let limit = self.server.config.requests_per_second as usize;
+                self.limit_exceeded(RATE_LIMITED, "rate", limit, "request rate exceeded")
+                    .await;
+                return true;
+            }
+            self.server.stats.requests.fetch_add(1, Ordering::Relaxed);
+            let request = match serde_json::from_slice::<Value>(&line) {
+                Ok(value) => value,
+                Err(err) => {
+                    let error = RpcError::new(PARSE_ERROR, format!("parse error: {err}"));
+                    self.reply(Some(Value::Null), Err(error)).await;
+                    continue;
+                }
+            };
+            let id = request.get("id").cloned();
+            let request: Request = match serde_json::from_value(request) {
+                Ok(request) => request,
+                Err(err) => {
+                    let error = RpcError::new(INVALID_REQUEST, format!("invalid request: {err}"));
+                    self.reply(Some(id.unwrap_or(Value::Null)), Err(error))
+                        .await;
+                    continue;
+                }
+            };
+            if !self.dispatch(request).await {
+                return true;
+            }
+        }
+    }
+
+    // Returns false when the request broke the in-flight cap.
+    async fn dispatch(&mut self, request: Request) -> bool {
+        let Request { id, method, params } = request;
+        let Some(spec) = methods::find(&method) else {
+            let error = RpcError::new(METHOD_NOT_FOUND, format!("unknown method {method}"));
+            self.reply(id, Err(error)).await;
+            return true;
+        };
+        if !self.transport_verified && spec.name != AUTHENTICATE_METHOD {
+            let error = RpcError::new(
+                UNAUTHENTICATED,
+                format!("{method} requires core.authenticate with the transport token first"),
+            );
+            self.reply(id, Err(error)).await;
+            return true;
+        }
+        let topic = match spec.name {
+            SUBSCRIBE_METHOD => match subscribe_topic(params.clone()) {
+                Ok(topic) => Some(topic),
+                Err(error) => {
+                    self.reply(id, Err(error)).await;
+                    return true;
+                }
+            },
+            TAIL_LOGS_METHOD => Some(LOGS_TOPIC),
+            _ => None,
+        };
+        let scopes = topic
+            .and_then(methods::topic_scopes)
+            .or_else(|| methods::param_scopes(spec.name, &params))
+            .unwrap_or(spec.scopes);
+        if let Err(error) = self.authorize(spec.name, scopes) {
+            self.reply(id, Err(error)).await;
+            return true;
+        }
+        let result = match spec.name {
+            PING_METHOD => Ok(json!({ "ok": true, "version": env!("CARGO_PKG_VERSION") })),
+            DESCRIBE_METHOD => Ok(json!({
+                "version": env!("CARGO_PKG_VERSION"),
+                "methods": methods::METHODS.iter().map(MethodSpec::descriptor).collect::<Vec<_>>(),
+            })),
+            AUTHENTICATE_METHOD => self.authenticate(params),
+            STATUS_METHOD => Ok(self.server.status()),
+            HEALTH_METHOD => self.server.health().await,
+            TAIL_LOGS_METHOD => {
+                self.subscribe(LOGS_TOPIC);
+                Ok(json!({ "subscribed": true }))
+            }
+            SUBSCRIBE_METHOD => {
+                let topic = topic.unwrap_or(PROGRESS_TOPIC);
+                self.subscribe(topic);
+                Ok(json!({ "subscribed": true, "topic": topic }))
+            }
+            _ => {
+                let Ok(permit) = self.in_flight.clone().try_acquire_owned() else {
+                    let limit = self.server.config.max_in_flight;
+                    self.limit_exceeded(
+                        RATE_LIMITED,
+                        "in_flight",
+                        limit,
+                        "too many requests in flight",
+                    )
+                    .await;
+                    return false;
+                };
+                let engine = self.server.engine.clone();
+                let outbox = self.outbox.clone();
+                let caller = self.session.as_ref().map(|claims| {
+                    Identity::from_os()
+                        .with_session(&claims.sub, claims.scopes.iter().map(Capability::as_str))
+                });
+                self.tasks.spawn(async move {
+                    let call = dispatch::engine_call(engine.as_ref(), &method, params);
+                    let result = match caller {
+                        Some(identity) => identity.acting(call).await,
+                        None => call.await,
+                    };
+                    drop(permit);
+                    if let Some(response) = response(id, result) {
+                        let _ = outbox.send(response).await;
+                    }
+                });
+                return true;
+            }
+        };
+        self.reply(id, result).await;
+        true
+    }
+
+    fn authorize(&self, method: &str, scopes: &[Capability]) -> Result<(), RpcError> {
+        if self.server.config.sessions.is_none() || scopes.is_empty() {
+            return Ok(());
+        }
+        let claims = match &self.session {
+            Some(claims) if !claims.is_expired() => claims,
+            Some(claims) => {
+                return Err(RpcError::new(
+                    UNAUTHENTICATED,
+                    format!("session for {} has expired", claims.sub),
+                ))
+            }
+            None => {
+                return Err(RpcError::new(
+                    UNAUTHENTICATED,
+                    format!("{method} requires an authenticated session"),
+                ))
+            }
+        };
+        if scopes.iter().any(|scope| claims.scopes.contains(scope)) {
+            return Ok(());
+        }
+        let scope = scopes.first().map(Capability::as_str).unwrap_or_default();
+        Err(RpcError::new(
+            CAPABILITY_DENIED,
+            format!("session lacks the {scope} scope required by {method}"),
+        )
+        .with_data(json!({
+            "method": method,
+            "scope": scope,
+            "subject": claims.policy_subject(),
+        })))
+    }
+
+    fn authenticate(&mut self, params: Value) -> Result<Value, RpcError> {
+        let params: AuthenticateParams = serde_json::from_value(params).map_err(|err| {
+            RpcError::new(
+                UNAUTHENTICATED,
+                format!("invalid authenticate params: {err}"),
+            )
+        })?;
+        if !self.transport_verified {
+            let expected = self.server.config.transport_token.as_ref();
+            let presented = params.transport_token.as_deref().unwrap_or_default();
+            if !expected.is_some_and(|token| token.matches(presented)) {
+                warn!("rejected a tcp connection with a missing or wrong transport token");
+                return Err(RpcError::new(UNAUTHENTICATED, "invalid transport token"));
+            }
+            self.transport_verified = true;
+        }
+        // Without a session key nothing needs a session, so tokens are accepted unchecked.
+        let Some(authority) = &self.server.config.sessions else {
+            return Ok(json!({ "required": false }));
+        };
+        let Some(token) = &params.token else {
+            return Err(RpcError::new(UNAUTHENTICATED, "missing session token"));
+        };
+        let claims = authority
+            .verify(token)
+            .map_err(|err| RpcError::new(UNAUTHENTICATED, err.to_string()))?;
+        debug!(subject = %claims.policy_subject(), device = ?params.device, "session authenticated");
+        let result = json!({
+            "subject": claims.policy_subject(),
+            "scopes": claims.scopes,
+            "expires_at": claims.exp,
+            "device": params.device,
+        });
+        if self.session.replace(claims).is_none() {
+            self.server.stats.sessions.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(result)
+    }
+
+    // Progress is accepted for protocol parity; engine methods report none yet.
+    fn subscribe(&mut self, topic: &str) {
+        if topic == PROGRESS_TOPIC {
+            if !self.progress {
+                self.progress = true;
+                self.server
+                    .stats
+                    .progress_subscribers
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+            return;
+        }
+        if self.logs.is_some() {
+            return;
+        }
+        let mut entries = self.server.config.logs.subscribe();
+        let outbox = self.outbox.clone();
+        self.logs = Some(tokio::spawn(async move {
+            loop {
+                let entry = match entries.recv().await {
+                    Ok(entry) => entry,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return,
+                };
+                let notification =
+                    json!({ "jsonrpc": "2.0", "method": LOG_METHOD, "params": entry });
+                if outbox.send(notification).await.is_err() {
+                    return;
+                }
+            }
+        }));
+    }
+
+    async fn reply(&self, id: Option<Value>, result: Result<Value, RpcError>) {
+        if let Some(response) = response(id, result) {
+            let _ = self.outbox.send(response).await;
+        }
+    }
+
+    async fn limit_exceeded(&self, code: i64, reason: &str, limit: usize, message: &str) {
+        warn!(reason, limit, "daemon.connection.limit_exceeded");
+        let error =
+            RpcError::new(code, message).with_data(json!({ "reason": reason, "limit": limit }));
+        self.reply(Some(Value::Null), Err(error)).await;
+    }
+}
+
+fn subscribe_topic(params: Value) -> Result<&'static str, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    let SubscribeParams { topic } = serde_json::from_value(params).map_err(|err| {
+        RpcError::new(
+            INVALID_PARAMS,
+            format!("invalid {SUBSCRIBE_METHOD} params: {err}"),
+        )
+    })?;
+    match topic.as_str() {
+        LOGS_TOPIC => Ok(LOGS_TOPIC),
+        PROGRESS_TOPIC => Ok(PROGRESS_TOPIC),
+        _ => Err(RpcError::new(
+            INVALID_PARAMS,
+            format!("unknown topic {topic}; expected {LOGS_TOPIC} or {PROGRESS_TOPIC}"),
+        )),
+    }
+}
+
+// Requests without an id are notifications and get no response.
+fn response(id: Option<Value>, result: Result<Value, RpcError>) -> Option<Value> {
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+    })
+}
+
+async fn read_frame<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    limit: usize,
+    buffer: &mut Vec<u8>,
+) -> io::Result<Frame> {
+    buffer.clear();
+    let read = reader
+        .take(limit as u64 + 2)
+        .read_until(b'\n', buffer)
+        .await?;
+    if read == 0 {
+        return Ok(Frame::Closed);
+    }
+    let complete = buffer.last() == Some(&b'\n');
+    if complete {
+        buffer.pop();
+        if buffer.last() == Some(&b'\r') {
+            buffer.pop();
+        }
+    }
+    if buffer.len() > limit || (!complete && read as u64 > limit as u64) {
+        return Ok(Frame::TooLarge);
+    }
+    Ok(Frame::Line(std::mem::take(buffer)))
+}
+
+async fn write_responses<W: AsyncWrite>(writer: W, mut responses: mpsc::Receiver<Value>) {
+    tokio::pin!(writer);
+    while let Some(response) = responses.recv().await {
+        let Ok(mut line) = serde_json::to_vec(&response) else {
+            continue;
+        };
+        line.push(b'\n');
+        if writer.write_all(&line).await.is_err() || writer.flush().await.is_err() {
+            break;
+        }
+    }
+    let _ = writer.shutdown().await;
+}
+
+struct TokenBucket {
+    tokens: f64,
+    rate: f64,
+    burst: f64,
+    refilled: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u32, burst: u32) -> Self {
+        Self {
+            tokens: burst as f64,
+            rate: rate as f64,
+            burst: burst as f64,
+            refilled: Instant::now(),
+        }
+    }
+
+    fn take(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.refilled = now;
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+trait ServerStream: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<S> ServerStream for S where S: AsyncRead + AsyncWrite + Send + Unpin {}
+
+// A bound endpoint. Unix sockets are created with mode 0600 and removed again on drop.
+pub struct Listener {
+    endpoint: Endpoint,
+    inner: ListenerInner,
+}
+
+enum ListenerInner {
+    Tcp(TcpListener),
+    #[cfg(target_family = "unix")]
+    Unix(UnixListener),
+    #[cfg(target_os = "windows")]
+    Pipe(NamedPipeServer),
+}
+
+impl Listener {
+    pub async fn bind(endpoint: &Endpoint) -> Result<Self> {
+        let inner = match endpoint {
+            Endpoint::Tcp(addr) => {
+                if !addr.ip().is_loopback() {
+                    return Err(anyhow!("refusing to listen on non-loopback address {addr}"));
+                }
+                let listener = TcpListener::bind(addr)
+                    .await
+                    .with_context(|| format!("failed to listen on {addr}"))?;
+                let endpoint = Endpoint::Tcp(listener.local_addr()?);
+                return Ok(Self {
+                    endpoint,
+                    inner: ListenerInner::Tcp(listener),
+                });
+            }
+            Endpoint::Unix(path) => {
+                #[cfg(target_family = "unix")]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    if let Some(parent) = path.parent() {
+                        tokio::fs::create_dir_all(parent).await?;
+                    }
+                    if tokio::fs::symlink_metadata(path).await.is_ok() {
+                        tokio::fs::remove_file(path).await.with_context(|| {
+                            format!("failed to remove stale socket {}", path.display())
+                        })?;
+                    }
+                    let listener = UnixListener::bind(path)
+                        .with_context(|| format!("failed to listen on {}", path.display()))?;
+                    tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+                        .await?;
+                    ListenerInner::Unix(listener)
+                }
+                #[cfg(not(target_family = "unix"))]
+                {
+                    let _ = path;
+                    return Err(anyhow!("unix sockets not supported on this platform"));
+                }
+            }
+            Endpoint::NamedPipe(name) => {
+                #[cfg(target_os = "windows")]
+                {
+                    let server = ServerOptions::new()
+                        .first_pipe_instance(true)
+                        .create(pipe_path(name))
+                        .with_context(|| format!("failed to create named pipe {name}"))?;
+                    ListenerInner::Pipe(server)
+                }
+                #[cfg(not(target_os = "windows"))]
+                {
+                    let _ = name;
+                    return Err(anyhow!("named pipes are only supported on windows"));
+                }
+            }
+        };
+        Ok(Self {
+            endpoint: endpoint.clone(),
+            inner,
+        })
+    }
+
+    // The bound endpoint, with the port filled in for `127.0.0.1:0`.
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
+    }
+
+    async fn accept(&mut self) -> io::Result<Box<dyn ServerStream>> {
+        match &mut self.inner {
+            ListenerInner::Tcp(listener) => {
+                let (stream, _) = listener.accept().await?;
+                Ok(Box::new(stream))
+            }
+            #[cfg(target_family = "unix")]
+            ListenerInner::Unix(listener) => {
+                let (stream, _) = listener.accept().await?;
+                Ok(Box::new(stream))
+            }
+            // One instance listens at a time; the next is created before handing this one off.
+            #[cfg(target_os = "windows")]
+            ListenerInner::Pipe(server) => {
+                server.connect().await?;
+                let Endpoint::NamedPipe(name) = &self.endpoint else {
+                    unreachable!("pipe listeners are bound to pipe endpoints");
+                };
+                let next = ServerOptions::new().create(pipe_path(name))?;
+                Ok(Box::new(std::mem::replace(server, next)))
+            }
+        }
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        #[cfg(target_family = "unix")]
+        if let (ListenerInner::Unix(_), Endpoint::Unix(path)) = (&self.inner, &self.endpoint) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn pipe_path(name: &str) -> String {
+    if name.starts_with(r"\\.\pipe\") {
+        name.to_owned()
+    } else {
+        format!(r"\\.\pipe\{name}")
+    }
+}
@@ -0,0 +1,377 @@
+#![cfg(unix)]
+
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Result;
+use dg_bridge::{
+    BridgeClient, BridgeConfig, Capability, Endpoint, RpcRequest, SessionAuthority, TransportToken,
+    LOG_METHOD, REQUEST_TOO_LARGE,
+};
+use dg_controller::RemoteDaemon;
+use dg_core::api::{new_default, DGConfig, DataGuardian, EncryptRequest};
+use dg_core::rpc_error::{CAPABILITY_DENIED, METHOD_NOT_FOUND, PARSE_ERROR, UNAUTHENTICATED};
+use dg_core::{AuditFilter, ChunkingParams};
+use dg_server::{Listener, LogFeed, Server, ServerConfig};
+use serde_json::{json, Value};
+use tempfile::tempdir;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpStream, UnixStream};
+use tokio::time::timeout;
+use tracing_subscriber::layer::SubscriberExt;
+
+async fn spawn_server(endpoint: Endpoint, config: ServerConfig) -> Result<Endpoint> {
+    let listener = Listener::bind(&endpoint).await?;
+    let bound = listener.endpoint().clone();
+    let server = Server::new(new_default(), config);
+    tokio::spawn(async move { server.serve(listener, std::future::pending()).await });
+    Ok(bound)
+}
+
+fn request(id: &str, method: &str, params: Value) -> RpcRequest {
+    RpcRequest {
+        id: id.into(),
+        method: method.into(),
+        params: Some(params),
+    }
+}
+
+fn config(data_dir: &Path) -> DGConfig {
+    DGConfig {
+        profile: "dev".into(),
+        data_dir: data_dir.to_path_buf(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn remote_backend_round_trips_through_the_native_server() -> Result<()> {
+    let temp = tempdir()?;
+    let socket = temp.path().join("dg-core.sock");
+    let endpoint = spawn_server(Endpoint::Unix(socket), ServerConfig::default()).await?;
+    let remote = RemoteDaemon::new(BridgeClient::new(BridgeConfig::new(vec![endpoint]))?);
+
+    remote.init(config(&temp.path().join("data"))).await?;
+    let plaintext: Vec<u8> = (0..64 * 1024u32).map(|i| (i % 251) as u8).collect();
+    let envelope = remote
+        .encrypt(EncryptRequest {
+            plaintext: plaintext.clone(),
+            labels: vec!["finance".into()],
+            chunking: Some(ChunkingParams {
+                min_size: 1024,
+                avg_size: 4096,
+                max_size: 16384,
+            }),
+            ..Default::default()
+        })
+        .await?;
+    assert!(envelope.meta.get("chunks").is_some());
+    assert_eq!(*remote.decrypt(envelope).await?, plaintext);
+    assert!(
+        remote
+            .check_policy("user:alice", "decrypt", "label:finance")
+            .await?
+    );
+    remote.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn health_reports_the_loaded_profile_without_a_session() -> Result<()> {
+    let temp = tempdir()?;
+    let endpoint = spawn_server(
+        Endpoint::Unix(temp.path().join("dg-core.sock")),
+        ServerConfig::default().with_session_authority(SessionAuthority::generate()),
+    )
+    .await?;
+    let anonymous = BridgeClient::new(BridgeConfig::new(vec![endpoint.clone()]))?;
+    let health = anonymous.health().await?;
+    assert_eq!(health.version, env!("CARGO_PKG_VERSION"));
+    assert!(health.profile.is_none());
+
+    let endpoint = spawn_server(
+        Endpoint::Unix(temp.path().join("open.sock")),
+        ServerConfig::default(),
+    )
+    .await?;
+    let client = BridgeClient::new(BridgeConfig::new(vec![endpoint]))?;
+    let remote = RemoteDaemon::new(client.clone());
+    let data_dir = temp.path().join("data");
+    remote.init(config(&data_dir)).await?;
+    let profile = client.health().await?.profile.expect("loaded profile");
+    assert_eq!(profile.profile, "dev");
+    assert_eq!(profile.data_dir, data_dir);
+    assert!(!profile.read_only && !profile.locked);
+    assert_eq!(remote.loaded_profile().await?, Some(profile));
+
+    remote.shutdown().await?;
+    assert!(client.health().await?.profile.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn sessions_gate_engine_methods_by_scope() -> Result<()> {
+    let temp = tempdir()?;
+    let authority = SessionAuthority::generate();
+    let endpoint = spawn_server(
+        Endpoint::Unix(temp.path().join("dg-core.sock")),
+        ServerConfig::default().with_session_authority(authority.clone()),
+    )
+    .await?;
+
+    let anonymous = BridgeClient::new(BridgeConfig::new(vec![endpoint.clone()]))?;
+    let pong = anonymous
+        .send_request(request("ping", "core.ping", json!({})))
+        .await?;
+    assert_eq!(pong.result.expect("ping result")["ok"], true);
+    let methods = anonymous.describe().await?;
+    assert!(methods.iter().any(|method| method.name == "engine.encrypt"));
+    let denied = anonymous
+        .send_request(request("status", "core.get_status", json!({})))
+        .await?;
+    assert_eq!(denied.error.expect("unauthenticated").code, UNAUTHENTICATED);
+
+    let status_only = BridgeClient::new(
+        BridgeConfig::new(vec![endpoint.clone()]).with_session_authority(
+            authority.clone(),
+            "monitor",
+            &[Capability::Status],
+            Duration::from_secs(60),
+        ),
+    )?;
+    let status = status_only
+        .send_request(request("status", "core.get_status", json!({})))
+        .await?;
+    assert_eq!(status.result.expect("status result")["sessions"], 1);
+    let encrypt = status_only
+        .send_request(request(
+            "encrypt",
+            "engine.encrypt",
+            json!({ "plaintext": "" }),
+        ))
+        .await?;
+    let error = encrypt.error.expect("capability denied");
+    assert_eq!(error.code, CAPABILITY_DENIED);
+    assert_eq!(error.data.expect("denial data")["scope"], "encrypt");
+
+    // Envelope scopes reach neither key management nor the engine lifecycle, and only the
+    // read-only integrity check.
+    let writer = BridgeClient::new(BridgeConfig::new(vec![endpoint]).with_session_authority(
+        authority,
+        "writer",
+        &[Capability::Encrypt, Capability::Decrypt, Capability::Status],
+        Duration::from_secs(60),
+    ))?;
+    for (method, params, scope) in [
+        ("engine.init", json!({ "config": {} }), "admin"),
+        ("engine.lock", json!({}), "admin"),
+        ("engine.integrity", json!({ "repair": true }), "admin"),
+        ("engine.index_remove", json!({ "path": "/tmp/a" }), "admin"),
+        ("engine.revoke_key", json!({ "id": "laptop" }), "keys"),
+        (
+            "engine.sign",
+            json!({ "signer": "laptop", "data": "" }),
+            "keys",
+        ),
+    ] {
+        let error = writer
+            .send_request(request(method, method, params))
+            .await?
+            .error
+            .expect("capability denied");
+        assert_eq!(error.code, CAPABILITY_DENIED, "{method}");
+        assert_eq!(error.data.expect("denial data")["scope"], scope, "{method}");
+    }
+    let check = writer
+        .send_request(request(
+            "check",
+            "engine.integrity",
+            json!({ "repair": false }),
+        ))
+        .await?;
+    assert_ne!(check.error.map(|error| error.code), Some(CAPABILITY_DENIED));
+    Ok(())
+}
+
+#[tokio::test]
+async fn engine_calls_are_checked_and_audited_as_the_session() -> Result<()> {
+    let temp = tempdir()?;
+    let data_dir = temp.path().join("data");
+    std::fs::create_dir_all(&data_dir)?;
+    let policy = json!({
+        "default_allow": true,
+        "rules": [{ "subject": "session:ci-runner", "action": "encrypt", "resource": "*", "effect": "deny" }],
+    });
+    std::fs::write(data_dir.join("policy.json"), serde_json::to_vec(&policy)?)?;
+    let engine = new_default();
+    engine.init(config(&data_dir)).await?;
+    let authority = SessionAuthority::generate();
+    let listener = Listener::bind(&Endpoint::Unix(temp.path().join("dg-core.sock"))).await?;
+    let endpoint = listener.endpoint().clone();
+    let server = Server::new(
+        engine.clone(),
+        ServerConfig::default().with_session_authority(authority.clone()),
+    );
+    tokio::spawn(async move { server.serve(listener, std::future::pending()).await });
+
+    let runner = BridgeClient::new(BridgeConfig::new(vec![endpoint]).with_session_authority(
+        authority,
+        "ci-runner",
+        &[Capability::Encrypt],
+        Duration::from_secs(60),
+    ))?;
+    let encrypt = runner
+        .send_request(request(
+            "encrypt",
+            "engine.encrypt",
+            json!({ "plaintext": "" }),
+        ))
+        .await?;
+    assert!(encrypt.error.is_some());
+
+    // In-process callers still act as the built-in system subject.
+    engine
+        .encrypt(EncryptRequest {
+            plaintext: b"payroll".to_vec(),
+            ..Default::default()
+        })
+        .await?;
+    let events = engine.query_audit(AuditFilter::default()).await?;
+    let subjects: Vec<(&str, &str)> = events
+        .iter()
+        .map(|event| (event.subject.as_str(), event.effect.as_str()))
+        .collect();
+    assert_eq!(
+        subjects,
+        [("system", "allow"), ("session:ci-runner", "deny")]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn tcp_connections_must_present_the_transport_token() -> Result<()> {
+    let temp = tempdir()?;
+    let token = TransportToken::generate();
+    let guarded = ServerConfig::default().with_transport_token(token.clone());
+    let tcp = spawn_server(Endpoint::Tcp("127.0.0.1:0".parse()?), guarded.clone()).await?;
+
+    let anonymous = BridgeClient::new(BridgeConfig::new(vec![tcp.clone()]))?;
+    let denied = anonymous
+        .send_request(request("ping", "core.ping", json!({})))
+        .await?;
+    assert_eq!(denied.error.expect("unauthenticated").code, UNAUTHENTICATED);
+
+    let forged = BridgeClient::new(
+        BridgeConfig::new(vec![tcp.clone()])
+            .with_transport_token(TransportToken::new("not-the-token")?),
+    )?;
+    assert!(forged
+        .send_request(request("ping", "core.ping", json!({})))
+        .await
+        .is_err());
+
+    let trusted =
+        BridgeClient::new(BridgeConfig::new(vec![tcp]).with_transport_token(token.clone()))?;
+    let pong = trusted
+        .send_request(request("ping", "core.ping", json!({})))
+        .await?;
+    assert_eq!(pong.result.expect("ping result")["ok"], true);
+
+    // The token only guards TCP; the socket is already private to its owner.
+    let socket = spawn_server(Endpoint::Unix(temp.path().join("dg-core.sock")), guarded).await?;
+    let local = BridgeClient::new(BridgeConfig::new(vec![socket]))?;
+    let pong = local
+        .send_request(request("ping", "core.ping", json!({})))
+        .await?;
+    assert_eq!(pong.result.expect("ping result")["ok"], true);
+    Ok(())
+}
+
+#[tokio::test]
+async fn malformed_and_oversized_frames_are_rejected() -> Result<()> {
+    let endpoint = spawn_server(
+        Endpoint::Tcp("127.0.0.1:0".parse()?),
+        ServerConfig::default().with_max_frame_bytes(1024),
+    )
+    .await?;
+    let Endpoint::Tcp(addr) = endpoint else {
+        unreachable!("bound a tcp endpoint");
+    };
+    let (read, mut write) = TcpStream::connect(addr).await?.into_split();
+    let mut lines = BufReader::new(read).lines();
+
+    write.write_all(b"{not json\n").await?;
+    let parse: Value = serde_json::from_str(&lines.next_line().await?.expect("response"))?;
+    assert_eq!(parse["error"]["code"], PARSE_ERROR);
+    assert_eq!(parse["id"], Value::Null);
+
+    write
+        .write_all(b"{\"jsonrpc\":\"2.0\",\"id\":7,\"method\":\"engine.fly\"}\n")
+        .await?;
+    let unknown: Value = serde_json::from_str(&lines.next_line().await?.expect("response"))?;
+    assert_eq!(unknown["id"], 7);
+    assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+
+    let mut oversized = vec![b'x'; 2048];
+    oversized.push(b'\n');
+    write.write_all(&oversized).await?;
+    let rejected: Value = serde_json::from_str(&lines.next_line().await?.expect("response"))?;
+    assert_eq!(rejected["error"]["code"], REQUEST_TOO_LARGE);
+    assert_eq!(rejected["error"]["data"]["reason"], "frame");
+    assert!(lines.next_line().await?.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn tcp_listeners_refuse_non_loopback_addresses() {
+    let endpoint = Endpoint::Tcp("0.0.0.0:0".parse().expect("addr"));
+    assert!(Listener::bind(&endpoint).await.is_err());
+}
+
+#[tokio::test]
+async fn unix_sockets_are_private_and_replace_stale_files() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempdir()?;
+    let socket = temp.path().join("dg-core.sock");
+    std::fs::write(&socket, b"stale")?;
+    let endpoint = spawn_server(Endpoint::Unix(socket.clone()), ServerConfig::default()).await?;
+    assert_eq!(
+        std::fs::metadata(&socket)?.permissions().mode() & 0o777,
+        0o600
+    );
+    let Endpoint::Unix(path) = endpoint else {
+        unreachable!("bound a unix endpoint");
+    };
+    UnixStream::connect(path).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn log_subscribers_receive_server_events() -> Result<()> {
+    let logs = LogFeed::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(logs.layer()));
+    let temp = tempdir()?;
+    let endpoint = spawn_server(
+        Endpoint::Unix(temp.path().join("dg-core.sock")),
+        ServerConfig::default().with_log_feed(logs.clone()),
+    )
+    .await?;
+
+    let subscriber = BridgeClient::new(BridgeConfig::new(vec![endpoint.clone()]))?;
+    let mut entries = subscriber.subscribe_topic("logs").await?;
+    let client = BridgeClient::new(BridgeConfig::new(vec![endpoint]))?;
+    let status = client
+        .send_request(request("status", "core.get_status", json!({})))
+        .await?;
+    assert_eq!(status.result.expect("status result")["log_subscribers"], 1);
+
+    let entry = timeout(Duration::from_secs(5), entries.recv())
+        .await?
+        .expect("log stream open");
+    assert_eq!(entry.method, LOG_METHOD);
+    assert_eq!(entry.params["msg"], "client connected");
+    assert_eq!(entry.params["level"], "info");
+    Ok(())
+}
@@ -3,18 +3,15 @@ use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Context, Result};
 use clap::{Parser, Subcommand};
+use dg_bridge::{
+    BridgeClient, BridgeConfig, Endpoint, RpcNotification, RpcRequest, TransportKind, LOG_METHOD,
+    PROGRESS_METHOD,
+};
 use serde_json::{json, Value};
-use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
-use tokio::net::TcpStream;
-#[cfg(target_family = "unix")]
-use tokio::net::UnixStream;
+use tokio::sync::mpsc;
 use tokio::time::timeout;
 
-#[cfg(target_os = "windows")]
-use tokio::time::sleep;
-
-#[cfg(target_os = "windows")]
-use tokio_named_pipes::{ClientOptions, NamedPipeClient};
+mod repl;
 
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
 
@@ -33,6 +30,10 @@ struct Cli {
     #[arg(long, value_name = "ADDR")]
     tcp: Option<String>,
 
+    /// Session token minted by the desktop shell for daemons that require one
+    #[arg(long, value_name = "TOKEN")]
+    session_token: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -47,6 +48,8 @@ enum Commands {
         #[arg(long)]
         params: Option<String>,
     },
+    /// Interactive shell with method and parameter completion from core.describe
+    Repl,
     /// Subscribe to core.tail_logs and stream notifications
     TailLogs {
         /// Stop after collecting this many log notifications
@@ -56,68 +59,51 @@ enum Commands {
         #[arg(long, value_name = "MS", default_value_t = 3000)]
         duration_ms: u64,
     },
+    /// Subscribe to a notification topic via core.subscribe and stream notifications
+    Subscribe {
+        /// Topic to subscribe to (progress or logs)
+        #[arg(long, default_value = "progress")]
+        topic: String,
+        /// Stop after collecting this many notifications
+        #[arg(long, value_name = "N")]
+        max_events: Option<usize>,
+        /// Exit after this many milliseconds even if the stream is still active
+        #[arg(long, value_name = "MS", default_value_t = 3000)]
+        duration_ms: u64,
+    },
 }
 
-#[derive(Debug, Clone)]
-enum Endpoint {
-    #[cfg(target_family = "unix")]
-    Unix(PathBuf),
-    Tcp(String),
-    #[cfg(target_os = "windows")]
-    Pipe(String),
-}
-
-impl Endpoint {
-    fn from_cli(
-        socket: Option<PathBuf>,
-        tcp: Option<String>,
-        pipe: Option<String>,
-    ) -> Result<Self> {
-        #[allow(unused_mut)]
-        let mut selected: Option<Self> = None;
-
-        if let Some(path) = socket {
-            if selected.is_some() {
-                return Err(anyhow!("specify only one transport"));
-            }
-            #[cfg(target_family = "unix")]
-            {
-                selected = Some(Endpoint::Unix(path));
-            }
-            #[cfg(not(target_family = "unix"))]
-            {
-                return Err(anyhow!("unix sockets are not supported on this platform"));
-            }
-        }
-
-        if let Some(addr) = tcp {
-            if selected.is_some() {
-                return Err(anyhow!("specify only one transport"));
-            }
-            selected = Some(Endpoint::Tcp(addr));
-        }
-
-        #[cfg(target_os = "windows")]
-        if let Some(pipe_name) = pipe {
-            if selected.is_some() {
-                return Err(anyhow!("specify only one transport"));
-            }
-            selected = Some(Endpoint::Pipe(pipe_name));
-        }
-
-        #[cfg(not(target_os = "windows"))]
-        if pipe.is_some() {
-            return Err(anyhow!("named pipes are only supported on windows"));
-        }
-
-        selected.ok_or_else(|| anyhow!("an endpoint must be provided"))
+fn endpoint_from_cli(
+    socket: Option<PathBuf>,
+    tcp: Option<String>,
+    pipe: Option<String>,
+) -> Result<Endpoint> {
+    let mut selected = Vec::new();
+    if let Some(path) = socket {
+        selected.push(Endpoint::Unix(path));
+    }
+    if let Some(addr) = tcp {
+        selected.push(Endpoint::from_user_input(TransportKind::Tcp, &addr)?);
+    }
+    if let Some(name) = pipe {
+        selected.push(Endpoint::NamedPipe(name));
+    }
+    match selected.len() {
+        0 => Err(anyhow!("an endpoint must be provided")),
+        1 => Ok(selected.remove(0)),
+        _ => Err(anyhow!("specify only one transport")),
     }
 }
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
-    let endpoint = Endpoint::from_cli(cli.socket, cli.tcp, cli.pipe)?;
+    let endpoint = endpoint_from_cli(cli.socket, cli.tcp, cli.pipe)?;
+    let mut config = BridgeConfig::new(vec![endpoint]).with_timeout(DEFAULT_TIMEOUT);
+    if let Some(token) = cli.session_token {
+        config = config.with_session_token(token);
+    }
+    let client = BridgeClient::new(config)?;
 
     match cli.command {
         Commands::Call { method, params } => {
@@ -126,181 +112,95 @@ async fn main() -> Result<()> {
                 .transpose()
                 .context("failed to parse params JSON")?
                 .unwrap_or_else(|| Value::Object(Default::default()));
-            let response = call_method(&endpoint, &method, value).await?;
-            println!("{}", response);
+            println!("{}", call(&client, method, value).await?);
         }
+        Commands::Repl => repl::run(&client).await?,
         Commands::TailLogs {
             max_events,
             duration_ms,
         } => {
-            tail_logs(&endpoint, max_events, Duration::from_millis(duration_ms)).await?;
-        }
-    }
-
-    Ok(())
-}
-
-async fn call_method(endpoint: &Endpoint, method: &str, params: Value) -> Result<String> {
-    match endpoint {
-        #[cfg(target_family = "unix")]
-        Endpoint::Unix(path) => {
-            let stream = timeout(DEFAULT_TIMEOUT, UnixStream::connect(path))
-                .await
-                .context("unix socket connection timed out")??;
-            call_with_stream(stream, method, params).await
-        }
-        Endpoint::Tcp(addr) => {
-            let stream = timeout(DEFAULT_TIMEOUT, TcpStream::connect(addr))
-                .await
-                .with_context(|| format!("tcp connect to {addr} timed out"))??;
-            call_with_stream(stream, method, params).await
+            let notifications = client.tail_logs().await?;
+            print_notifications(
+                notifications,
+                LOG_METHOD,
+                max_events,
+                Duration::from_millis(duration_ms),
+            )
+            .await;
         }
-        #[cfg(target_os = "windows")]
-        Endpoint::Pipe(name) => {
-            let stream = connect_named_pipe(name, DEFAULT_TIMEOUT).await?;
-            call_with_stream(stream, method, params).await
+        Commands::Subscribe {
+            topic,
+            max_events,
+            duration_ms,
+        } => {
+            let notification = match topic.as_str() {
+                "progress" => PROGRESS_METHOD,
+                "logs" => LOG_METHOD,
+                other => {
+                    return Err(anyhow!(
+                        "unknown topic '{other}'; expected progress or logs"
+                    ))
+                }
+            };
+            let notifications = client.subscribe_topic(&topic).await?;
+            print_notifications(
+                notifications,
+                notification,
+                max_events,
+                Duration::from_millis(duration_ms),
+            )
+            .await;
         }
     }
-}
 
-async fn tail_logs(
-    endpoint: &Endpoint,
-    max_events: Option<usize>,
-    duration: Duration,
-) -> Result<()> {
-    match endpoint {
-        #[cfg(target_family = "unix")]
-        Endpoint::Unix(path) => {
-            let stream = timeout(DEFAULT_TIMEOUT, UnixStream::connect(path))
-                .await
-                .context("unix socket connection timed out")??;
-            tail_with_stream(stream, max_events, duration).await
-        }
-        Endpoint::Tcp(addr) => {
-            let stream = timeout(DEFAULT_TIMEOUT, TcpStream::connect(addr))
-                .await
-                .with_context(|| format!("tcp connect to {addr} timed out"))??;
-            tail_with_stream(stream, max_events, duration).await
-        }
-        #[cfg(target_os = "windows")]
-        Endpoint::Pipe(name) => {
-            let stream = connect_named_pipe(name, DEFAULT_TIMEOUT).await?;
-            tail_with_stream(stream, max_events, duration).await
-        }
-    }
+    Ok(())
 }
 
-async fn call_with_stream<S>(mut stream: S, method: &str, params: Value) -> Result<String>
-where
-    S: AsyncRead + AsyncWrite + Unpin,
-{
-    let payload = json!({
-        "jsonrpc": "2.0",
-        "id": "dg-e2e",
-        "method": method,
-        "params": params,
-    });
-    let mut message = serde_json::to_vec(&payload)?;
-    if !message.ends_with(b"\n") {
-        message.push(b'\n');
+async fn call(client: &BridgeClient, method: String, params: Value) -> Result<Value> {
+    let response = client
+        .send_request(RpcRequest {
+            id: "dg-e2e".into(),
+            method,
+            params: Some(params),
+        })
+        .await?;
+    let mut line = json!({ "jsonrpc": "2.0", "id": response.id });
+    match response.error {
+        Some(error) => line["error"] = serde_json::to_value(error)?,
+        None => line["result"] = response.result.unwrap_or(Value::Null),
     }
-
-    stream.write_all(&message).await?;
-    stream.flush().await?;
-
-    let mut reader = BufReader::new(stream);
-    let mut line = String::new();
-    let read = reader.read_line(&mut line).await?;
-    if read == 0 {
-        return Err(anyhow!("connection closed before response"));
-    }
-
-    Ok(line.trim().to_string())
+    Ok(line)
 }
 
-async fn tail_with_stream<S>(
-    mut stream: S,
+async fn print_notifications(
+    mut notifications: mpsc::Receiver<RpcNotification>,
+    method: &str,
     max_events: Option<usize>,
     duration: Duration,
-) -> Result<()>
-where
-    S: AsyncRead + AsyncWrite + Unpin,
-{
-    let payload = json!({
-        "jsonrpc": "2.0",
-        "id": "dg-e2e-tail",
-        "method": "core.tail_logs",
-        "params": {},
-    });
-    let mut message = serde_json::to_vec(&payload)?;
-    if !message.ends_with(b"\n") {
-        message.push(b'\n');
-    }
-
-    stream.write_all(&message).await?;
-    stream.flush().await?;
-
-    let mut reader = BufReader::new(stream);
-    let mut line = String::new();
-    let mut seen = 0usize;
+) {
     let deadline = Instant::now() + duration;
+    let mut seen = 0usize;
 
     loop {
-        line.clear();
-        let now = Instant::now();
-        if now >= deadline {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
             break;
         }
-        let remaining = deadline - now;
-        match timeout(remaining, reader.read_line(&mut line)).await {
-            Ok(Ok(0)) => break,
-            Ok(Ok(_)) => {
-                let trimmed = line.trim_end();
-                if trimmed.is_empty() {
-                    continue;
-                }
-                println!("{}", trimmed);
-                if let Ok(value) = serde_json::from_str::<Value>(trimmed) {
-                    if value
-                        .get("method")
-                        .and_then(Value::as_str)
-                        .map(|method| method == "core.log")
-                        .unwrap_or(false)
-                    {
-                        seen += 1;
-                        if let Some(limit) = max_events {
-                            if seen >= limit {
-                                break;
-                            }
-                        }
-                    }
-                }
-            }
-            Ok(Err(err)) => return Err(err.into()),
-            Err(_) => break,
-        }
-    }
-
-    Ok(())
-}
-
-#[cfg(target_os = "windows")]
-async fn connect_named_pipe(name: &str, timeout_duration: Duration) -> Result<NamedPipeClient> {
-    let deadline = Instant::now() + timeout_duration;
-    let pipe_name = if name.starts_with(r"\\.\pipe\") {
-        name.to_string()
-    } else {
-        format!(r"\\.\pipe\{}", name)
-    };
-
-    loop {
-        match ClientOptions::new().open(&pipe_name) {
-            Ok(client) => return Ok(client),
-            Err(err) => {
-                if Instant::now() >= deadline {
-                    return Err(anyhow!("failed to open named pipe {pipe_name}: {err}"));
-                }
-                sleep(Duration::from_millis(100)).await;
+        let Ok(Some(notification)) = timeout(remaining, notifications.recv()).await else {
+            break;
+        };
+        println!(
+            "{}",
+            json!({
+                "jsonrpc": "2.0",
+                "method": notification.method,
+                "params": notification.params,
+            })
+        );
+        if notification.method == method {
+            seen += 1;
+            if max_events.is_some_and(|limit| seen >= limit) {
+                break;
             }
         }
     }
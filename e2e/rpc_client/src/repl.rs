@@ -0,0 +1,181 @@
+use anyhow::{Context, Result};
+use dg_bridge::{BridgeClient, MethodDescriptor};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Editor, Helper};
+use serde_json::{json, Value};
+
+const PROMPT: &str = "dg> ";
+const COMMANDS: [&str; 3] = [".help", ".methods", ".quit"];
+
+pub struct MethodCompleter {
+    methods: Vec<MethodDescriptor>,
+}
+
+impl MethodCompleter {
+    pub fn new(methods: Vec<MethodDescriptor>) -> Self {
+        Self { methods }
+    }
+
+    fn find(&self, name: &str) -> Option<&MethodDescriptor> {
+        self.methods.iter().find(|method| method.name == name)
+    }
+
+    pub fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let line = &line[..pos];
+        let Some((method, rest)) = line.split_once(char::is_whitespace) else {
+            let names = self.methods.iter().map(|method| method.name.as_str());
+            let matches = names
+                .chain(COMMANDS)
+                .filter(|name| name.starts_with(line))
+                .map(str::to_owned)
+                .collect();
+            return (0, matches);
+        };
+        let Some(method) = self.find(method) else {
+            return (pos, Vec::new());
+        };
+        let start = line.len() - rest.len()
+            + rest
+                .rfind(|c: char| c == '{' || c == ',' || c.is_whitespace())
+                .map(|index| index + 1)
+                .unwrap_or(0);
+        let word = line[start..].trim_start_matches('"');
+        let matches = method
+            .parameter_names()
+            .into_iter()
+            .filter(|name| name.starts_with(word) && !rest.contains(&format!("\"{name}\"")))
+            .map(|name| format!("\"{name}\": "))
+            .collect();
+        (start, matches)
+    }
+}
+
+impl Completer for MethodCompleter {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let (start, matches) = self.candidates(line, pos);
+        let pairs = matches
+            .into_iter()
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: candidate,
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for MethodCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for MethodCompleter {}
+
+impl Validator for MethodCompleter {}
+
+impl Helper for MethodCompleter {}
+
+pub async fn run(client: &BridgeClient) -> Result<()> {
+    let methods = client
+        .describe()
+        .await
+        .context("unable to load the method registry")?;
+    let mut editor: Editor<MethodCompleter, _> = Editor::new()?;
+    editor.set_helper(Some(MethodCompleter::new(methods)));
+
+    loop {
+        let line = match tokio::task::block_in_place(|| editor.readline(PROMPT)) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => break,
+            Err(err) => return Err(err.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line)?;
+        let (command, payload) = line
+            .split_once(char::is_whitespace)
+            .map(|(command, payload)| (command, payload.trim()))
+            .unwrap_or((line, ""));
+        let helper = editor.helper().expect("completer installed");
+        match command {
+            ".quit" => break,
+            ".help" => {
+                println!(
+                    "<method> [params JSON]  invoke a method (Tab completes names and parameters)"
+                );
+                println!(
+                    ".methods [prefix]       list methods with their parameters and capabilities"
+                );
+                println!(".quit                   leave the REPL");
+            }
+            ".methods" => {
+                for method in helper
+                    .methods
+                    .iter()
+                    .filter(|method| method.name.starts_with(payload))
+                {
+                    print_method(method);
+                }
+            }
+            method => {
+                if helper.find(method).is_none() {
+                    eprintln!("unknown method {method}; .methods lists the available ones");
+                    continue;
+                }
+                let params = if payload.is_empty() {
+                    json!({})
+                } else {
+                    match serde_json::from_str::<Value>(payload) {
+                        Ok(params) => params,
+                        Err(err) => {
+                            eprintln!("invalid params JSON: {err}");
+                            continue;
+                        }
+                    }
+                };
+                match crate::call(client, method.to_owned(), params).await {
+                    Ok(line) => println!("{line}"),
+                    Err(err) => eprintln!("{err:#}"),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_method(method: &MethodDescriptor) {
+    let required = method.required_parameters();
+    let params: Vec<String> = method
+        .parameter_names()
+        .into_iter()
+        .map(|name| {
+            if required.contains(&name) {
+                name.to_owned()
+            } else {
+                format!("{name}?")
+            }
+        })
+        .collect();
+    let capabilities = if method.capabilities.is_empty() {
+        "none".to_owned()
+    } else {
+        method.capabilities.join(", ")
+    };
+    println!(
+        "{}({})  [capabilities: {capabilities}]",
+        method.name,
+        params.join(", ")
+    );
+}
@@ -20,6 +20,8 @@ async fn happy_path_encrypt_decrypt_shutdown() -> Result<()> {
             .await?
     );
 
+    controller.generate_identity("user:a").await?;
+
     let original = temp.path().join("note.txt");
     fs::write(&original, b"temporary secret").await?;
     let env_path = controller
@@ -27,9 +29,10 @@ async fn happy_path_encrypt_decrypt_shutdown() -> Result<()> {
             &original,
             vec!["user:a".into()],
             vec!["confidential".into()],
+            None,
         )
         .await?;
-    let decrypted = controller.decrypt_file(&env_path).await?;
+    let decrypted = controller.decrypt_file(&env_path, None).await?;
     let decrypted_bytes = fs::read(&decrypted).await?;
     assert_eq!(decrypted_bytes, b"temporary secret");
 
@@ -60,7 +63,7 @@ async fn policy_denies_flow() -> Result<()> {
     let file = temp.path().join("classified.bin");
     fs::write(&file, b"payload").await?;
     let result = controller
-        .encrypt_file(&file, vec!["user:b".into()], vec!["secret".into()])
+        .encrypt_file(&file, vec!["user:b".into()], vec!["secret".into()], None)
         .await;
     assert!(result.is_err(), "encryption should be denied");
 
@@ -76,17 +79,25 @@ async fn corrupt_envelope_fails_to_decrypt() -> Result<()> {
     let controller = Controller::new(new_default());
     controller.boot("dev", data_dir.clone(), false).await?;
 
+    controller.generate_identity("user:c").await?;
     let original = temp.path().join("text.txt");
     fs::write(&original, b"original").await?;
     let env_path = controller
-        .encrypt_file(&original, vec!["user:c".into()], vec!["internal".into()])
+        .encrypt_file(
+            &original,
+            vec!["user:c".into()],
+            vec!["internal".into()],
+            None,
+        )
         .await?;
 
-    let mut envelope = serde_json::from_slice::<serde_json::Value>(&fs::read(&env_path).await?)?;
-    envelope["payload"] = serde_json::Value::String("!!not-base64!!".into());
-    fs::write(&env_path, serde_json::to_vec(&envelope)?).await?;
+    let mut envelope = fs::read(&env_path).await?;
+    if let Some(last) = envelope.last_mut() {
+        *last ^= 0xff;
+    }
+    fs::write(&env_path, envelope).await?;
 
-    let result = controller.decrypt_file(&env_path).await;
+    let result = controller.decrypt_file(&env_path, None).await;
     assert!(result.is_err(), "corrupt envelope should fail");
 
     controller.shutdown().await?;
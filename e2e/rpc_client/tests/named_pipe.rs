@@ -0,0 +1,159 @@
+#![cfg(windows)]
+
+use std::process::{Command, Output};
+
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+use tokio::task::{JoinHandle, JoinSet};
+
+fn pipe_name(test: &str) -> String {
+    format!("dg_e2e_cli_{test}_{}", std::process::id())
+}
+
+async fn answer(server: NamedPipeServer) {
+    let (read, mut write) = tokio::io::split(server);
+    let mut lines = BufReader::new(read).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let request: Value = serde_json::from_str(&line).expect("request json");
+        let mut messages = vec![json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "result": { "method": request["method"], "params": request["params"] }
+        })];
+        if request["method"] == "core.subscribe" {
+            for index in 0..5 {
+                messages.push(json!({
+                    "jsonrpc": "2.0",
+                    "method": "core.log",
+                    "params": { "msg": format!("event {index}") }
+                }));
+            }
+        }
+        let mut payload = Vec::new();
+        for message in messages {
+            payload.extend(serde_json::to_vec(&message).expect("encode"));
+            payload.push(b'\n');
+        }
+        if write.write_all(&payload).await.is_err() {
+            break;
+        }
+    }
+}
+
+fn listen(name: &str) -> Result<JoinHandle<()>> {
+    let path = format!(r"\\.\pipe\{name}");
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(&path)?;
+    Ok(tokio::spawn(async move {
+        let mut connections = JoinSet::new();
+        while server.connect().await.is_ok() {
+            let connected = server;
+            server = ServerOptions::new()
+                .create(&path)
+                .expect("next pipe instance");
+            connections.spawn(answer(connected));
+        }
+    }))
+}
+
+async fn cli(args: Vec<String>) -> Result<Output> {
+    let output = tokio::task::spawn_blocking(move || {
+        Command::new(env!("CARGO_BIN_EXE_dg_e2e_cli"))
+            .args(args)
+            .output()
+    })
+    .await??;
+    if !output.status.success() {
+        bail!(
+            "dg_e2e_cli failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(output)
+}
+
+fn lines(output: &Output) -> Vec<Value> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("json line"))
+        .collect()
+}
+
+fn args(name: &str, command: &[&str]) -> Vec<String> {
+    ["--pipe", name]
+        .iter()
+        .chain(command)
+        .map(|arg| arg.to_string())
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn call_round_trips_over_a_named_pipe() -> Result<()> {
+    let name = pipe_name("call");
+    let _server = listen(&name)?;
+
+    let output = cli(args(
+        &name,
+        &["call", "core.get_status", "--params", r#"{"verbose":true}"#],
+    ))
+    .await?;
+    assert_eq!(
+        lines(&output),
+        vec![json!({
+            "jsonrpc": "2.0",
+            "id": "dg-e2e",
+            "result": { "method": "core.get_status", "params": { "verbose": true } }
+        })]
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn subscribe_streams_notifications_over_a_named_pipe() -> Result<()> {
+    let name = pipe_name("subscribe");
+    let _server = listen(&name)?;
+
+    let output = cli(args(
+        &name,
+        &["subscribe", "--topic", "logs", "--max-events", "3"],
+    ))
+    .await?;
+    let events = lines(&output);
+    assert_eq!(events.len(), 3, "{events:?}");
+    for (index, event) in events.iter().enumerate() {
+        assert_eq!(event["method"], "core.log");
+        assert_eq!(event["params"]["msg"], format!("event {index}"));
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn concurrent_cli_processes_share_one_pipe_server() -> Result<()> {
+    let name = pipe_name("concurrent");
+    let _server = listen(&name)?;
+
+    let mut clients = JoinSet::new();
+    for index in 0..6 {
+        let params = json!({ "client": index }).to_string();
+        clients.spawn(cli(args(
+            &name,
+            &["call", "core.ping", "--params", &params],
+        )));
+    }
+    let mut seen = Vec::new();
+    while let Some(joined) = clients.join_next().await {
+        let output = joined??;
+        let reply = lines(&output).remove(0);
+        seen.push(
+            reply["result"]["params"]["client"]
+                .as_u64()
+                .expect("client"),
+        );
+    }
+    seen.sort_unstable();
+    assert_eq!(seen, (0..6).collect::<Vec<_>>());
+    Ok(())
+}
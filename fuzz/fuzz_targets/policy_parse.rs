@@ -0,0 +1,16 @@
+#![no_main]
+
+use dg_core::PolicyEngine;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(policy) = PolicyEngine::parse(data) {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("runtime");
+        runtime.block_on(async {
+            let _ = policy.evaluate("system", "encrypt", "data").await;
+            policy.warm_up().await;
+        });
+    }
+});
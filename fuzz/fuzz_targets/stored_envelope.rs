@@ -0,0 +1,13 @@
+#![no_main]
+
+use dg_controller::StoredEnvelope;
+use dg_core::api::Envelope;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Envelope::parse_header(data);
+    if let Ok(stored) = StoredEnvelope::parse(data) {
+        let _ = stored.detect_format();
+        let _ = stored.into_envelope();
+    }
+});
@@ -0,0 +1,105 @@
+#!/usr/bin/env python3
+"""Compare criterion results against recorded baselines and fail on regressions."""
+from __future__ import annotations
+
+import argparse
+import json
+import sys
+from pathlib import Path
+
+REPO_ROOT = Path(__file__).resolve().parents[1]
+CRITERION_DIR = REPO_ROOT / "target" / "criterion"
+BASELINES_PATH = REPO_ROOT / "scripts" / "bench_baselines.json"
+DEFAULT_THRESHOLD_PCT = 20.0
+
+
+def parse_args() -> argparse.Namespace:
+    parser = argparse.ArgumentParser(description="Check criterion benchmarks against baselines")
+    parser.add_argument(
+        "--criterion-dir",
+        type=Path,
+        default=CRITERION_DIR,
+        help="Directory holding criterion output (default: target/criterion)",
+    )
+    parser.add_argument(
+        "--baselines",
+        type=Path,
+        default=BASELINES_PATH,
+        help="Recorded baseline file (default: scripts/bench_baselines.json)",
+    )
+    parser.add_argument(
+        "--threshold",
+        type=float,
+        default=None,
+        help="Allowed slowdown in percent before a benchmark fails "
+        f"(default: value from the baseline file or {DEFAULT_THRESHOLD_PCT})",
+    )
+    parser.add_argument(
+        "--record",
+        action="store_true",
+        help="Overwrite the baseline file with the current results instead of comparing",
+    )
+    return parser.parse_args()
+
+
+def collect(criterion_dir: Path) -> dict[str, float]:
+    results: dict[str, float] = {}
+    for estimates in sorted(criterion_dir.glob("**/new/estimates.json")):
+        bench_dir = estimates.parent.parent
+        benchmark = json.loads((estimates.parent / "benchmark.json").read_text(encoding="utf-8"))
+        name = benchmark.get("full_id") or bench_dir.relative_to(criterion_dir).as_posix()
+        mean = json.loads(estimates.read_text(encoding="utf-8"))["mean"]["point_estimate"]
+        results[name] = float(mean)
+    return results
+
+
+def record(path: Path, results: dict[str, float], threshold: float | None) -> None:
+    document = {
+        "threshold_pct": threshold if threshold is not None else DEFAULT_THRESHOLD_PCT,
+        "mean_ns": {name: round(value, 1) for name, value in sorted(results.items())},
+    }
+    path.write_text(json.dumps(document, indent=2) + "\n", encoding="utf-8")
+    print(f"Recorded {len(results)} baselines to {path}")
+
+
+def compare(baselines: dict, results: dict[str, float], threshold: float) -> list[str]:
+    failures = []
+    for name, baseline in sorted(baselines["mean_ns"].items()):
+        current = results.get(name)
+        if current is None:
+            failures.append(f"{name}: no result (run the benchmark before checking)")
+            continue
+        change = (current - baseline) / baseline * 100.0
+        status = "FAIL" if change > threshold else "ok"
+        print(f"{status:>4}  {name:<32} {baseline:>14.1f} ns -> {current:>14.1f} ns  ({change:+.1f}%)")
+        if change > threshold:
+            failures.append(f"{name}: {change:+.1f}% exceeds the {threshold:.1f}% budget")
+    for name in sorted(set(results) - set(baselines["mean_ns"])):
+        print(f" new  {name:<32} {results[name]:>14.1f} ns (no baseline)")
+    return failures
+
+
+def main() -> None:
+    args = parse_args()
+    results = collect(args.criterion_dir)
+    if not results:
+        raise SystemExit(f"No criterion results under {args.criterion_dir}")
+    if args.record:
+        record(args.baselines, results, args.threshold)
+        return
+    if not args.baselines.is_file():
+        raise SystemExit(f"Baseline file not found: {args.baselines} (run with --record)")
+    baselines = json.loads(args.baselines.read_text(encoding="utf-8"))
+    threshold = args.threshold
+    if threshold is None:
+        threshold = float(baselines.get("threshold_pct", DEFAULT_THRESHOLD_PCT))
+    failures = compare(baselines, results, threshold)
+    if failures:
+        for failure in failures:
+            print(f"regression: {failure}", file=sys.stderr)
+        sys.exit(1)
+    print(f"All benchmarks within the {threshold:.1f}% budget")
+
+
+if __name__ == "__main__":
+    main()
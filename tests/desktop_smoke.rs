@@ -20,11 +20,16 @@ async fn desktop_controller_smoke() {
     fs::write(&file, b"hello world").await.expect("write file");
 
     let encrypted = controller
-        .encrypt_file(&file, vec!["user:smoke".into()], vec!["public".into()])
+        .encrypt_file(
+            &file,
+            vec!["user:smoke".into()],
+            vec!["public".into()],
+            None,
+        )
         .await
         .expect("encrypt file");
     let decrypted = controller
-        .decrypt_file(&encrypted)
+        .decrypt_file(&encrypted, None)
         .await
         .expect("decrypt file");
     let decrypted_bytes = fs::read(&decrypted).await.expect("read decrypted");